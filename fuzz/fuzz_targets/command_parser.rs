@@ -0,0 +1,62 @@
+#![no_main]
+
+//! Feeds raw protocol lines through the same path as `handle_client`:
+//! `parse_command_line` followed by `Server::handle_command`, against an in-memory database.
+//! The literal `TOKEN` in an input is replaced with a valid session token, so the
+//! corpus can reach the handlers behind session validation.
+
+use libfuzzer_sys::fuzz_target;
+use ruggine_modulare::server::{
+    config::ServerConfig,
+    connection::{parse_command_line, Server},
+    database::Database,
+    presence::PresenceRegistry,
+};
+use std::sync::{Arc, OnceLock};
+use tokio::runtime::Runtime;
+
+/// Password of the harness users, also used by the `login` and `session_flow` corpus entries;
+/// `/register` requires two character classes
+const PASSWORD: &str = "passw0rd";
+
+struct Harness {
+    rt: Runtime,
+    server: Server,
+    token: String,
+}
+
+fn harness() -> &'static Harness {
+    static HARNESS: OnceLock<Harness> = OnceLock::new();
+    HARNESS.get_or_init(|| {
+        let rt = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
+        let (server, token) = rt.block_on(async {
+            let db = Arc::new(Database::connect("sqlite::memory:").await.unwrap());
+            db.migrate().await.unwrap();
+            let mut config = ServerConfig::from_env();
+            config.enable_audit_log = true;
+            config.admin_users = vec!["fuzz".to_string()];
+            let server = Server { db, config, presence: PresenceRegistry::new(), ws_manager: None, peer: None };
+            server.handle_command("/register", &["friend", PASSWORD]).await;
+            let resp = server.handle_command("/register", &["fuzz", PASSWORD]).await;
+            let token = resp
+                .split("SESSION:")
+                .nth(1)
+                .and_then(|t| t.split_whitespace().next())
+                .expect("registration of the fuzz user failed")
+                .to_string();
+            (server, token)
+        });
+        Harness { rt, server, token }
+    })
+}
+
+fuzz_target!(|data: &[u8]| {
+    // read_line rejects invalid UTF-8 and the connection is closed before parsing
+    let Ok(input) = std::str::from_utf8(data) else { return };
+    let h = harness();
+    let input = input.replace("TOKEN", &h.token);
+    for line in input.split_inclusive('\n') {
+        let Some((cmd, args)) = parse_command_line(line) else { continue };
+        h.rt.block_on(h.server.handle_command(cmd, &args));
+    }
+});
@@ -0,0 +1,131 @@
+// Load test client: registra N utenti, ognuno invia M messaggi privati a se stesso
+// e stampa throughput e latenze.
+//
+// Uso: bench --bench <host> <users> <messages_each> [--admin <username> <password>]
+//
+// Con --admin gli utenti di test vengono eliminati alla fine via /admin_delete_user
+// (l'utente deve essere elencato in ADMIN_USERS sul server). Tenere <users> sotto
+// MAX_CONNECTION_ATTEMPTS_PER_MINUTE, altrimenti il server blocca l'IP.
+use hdrhistogram::Histogram;
+use ruggine_modulare::client::services::chat_service::ChatService;
+use std::time::{Duration, Instant};
+
+const BENCH_PASSWORD: &str = "bench-password";
+
+struct Args {
+    host: String,
+    users: usize,
+    messages_each: usize,
+    admin: Option<(String, String)>,
+}
+
+impl Args {
+    fn parse() -> anyhow::Result<Self> {
+        let args: Vec<String> = std::env::args().skip(1).collect();
+        let usage = "usage: bench --bench <host> <users> <messages_each> [--admin <username> <password>]";
+        let pos = args.iter().position(|a| a == "--bench").ok_or_else(|| anyhow::anyhow!(usage))?;
+        let rest = &args[pos + 1..];
+        if rest.len() < 3 {
+            return Err(anyhow::anyhow!(usage));
+        }
+        let admin = args.iter().position(|a| a == "--admin").and_then(|i| {
+            Some((args.get(i + 1)?.clone(), args.get(i + 2)?.clone()))
+        });
+        Ok(Self {
+            host: rest[0].clone(),
+            users: rest[1].parse()?,
+            messages_each: rest[2].parse()?,
+            admin,
+        })
+    }
+}
+
+fn extract_session(resp: &str) -> Option<String> {
+    resp.split("SESSION:").nth(1).and_then(|s| s.split_whitespace().next()).map(str::to_string)
+}
+
+/// Register one test user and send `messages` private messages to itself.
+/// Returns the per-message round-trip latencies.
+async fn run_user(host: String, username: String, messages: usize) -> anyhow::Result<Vec<Duration>> {
+    let mut svc = ChatService::new();
+    let resp = svc.send_command(&host, format!("/register {} {}", username, BENCH_PASSWORD)).await?;
+    let token = match extract_session(&resp) {
+        Some(token) => token,
+        None => {
+            // Already registered from a previous run: log in instead
+            let resp = svc.send_command(&host, format!("/login {} {}", username, BENCH_PASSWORD)).await?;
+            extract_session(&resp).ok_or_else(|| anyhow::anyhow!("login failed for {}: {}", username, resp))?
+        }
+    };
+
+    let mut latencies = Vec::with_capacity(messages);
+    for i in 0..messages {
+        let started = Instant::now();
+        let resp = svc.send_command(&host, format!("/send_private_message {} {} bench message {}", token, username, i)).await?;
+        latencies.push(started.elapsed());
+        if !resp.starts_with("OK:") {
+            eprintln!("[BENCH] {} message {} failed: {}", username, i, resp);
+        }
+    }
+    let _ = svc.send_command(&host, format!("/logout {}", token)).await;
+    Ok(latencies)
+}
+
+async fn cleanup(host: &str, admin: &(String, String), usernames: &[String]) -> anyhow::Result<()> {
+    let mut svc = ChatService::new();
+    let resp = svc.send_command(host, format!("/login {} {}", admin.0, admin.1)).await?;
+    let token = extract_session(&resp).ok_or_else(|| anyhow::anyhow!("admin login failed: {}", resp))?;
+    for username in usernames {
+        let resp = svc.send_command(host, format!("/admin_delete_user {} {}", token, username)).await?;
+        if !resp.starts_with("OK:") {
+            eprintln!("[BENCH] could not delete {}: {}", username, resp);
+        }
+    }
+    let _ = svc.send_command(host, format!("/logout {}", token)).await;
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args = Args::parse()?;
+    let run_id = &uuid::Uuid::new_v4().to_string()[..8];
+    let usernames: Vec<String> = (0..args.users).map(|i| format!("bench_{}_{}", run_id, i)).collect();
+    println!("Benchmarking {} with {} users x {} messages", args.host, args.users, args.messages_each);
+
+    let started = Instant::now();
+    let handles: Vec<_> = usernames
+        .iter()
+        .cloned()
+        .map(|username| tokio::spawn(run_user(args.host.clone(), username, args.messages_each)))
+        .collect();
+
+    let mut histogram = Histogram::<u64>::new_with_bounds(1, 60_000_000, 3)?;
+    let mut sent = 0u64;
+    for handle in handles {
+        match handle.await? {
+            Ok(latencies) => {
+                for latency in latencies {
+                    histogram.saturating_record(latency.as_micros() as u64);
+                    sent += 1;
+                }
+            }
+            Err(e) => eprintln!("[BENCH] user task failed: {}", e),
+        }
+    }
+    let elapsed = started.elapsed();
+
+    let throughput = sent as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+    println!(
+        "Throughput: {:.1} msg/s, P50: {:.2}ms, P99: {:.2}ms",
+        throughput,
+        histogram.value_at_quantile(0.50) as f64 / 1000.0,
+        histogram.value_at_quantile(0.99) as f64 / 1000.0,
+    );
+    println!("Sent {} messages in {:.2}s", sent, elapsed.as_secs_f64());
+
+    match &args.admin {
+        Some(admin) => cleanup(&args.host, admin, &usernames).await?,
+        None => println!("No --admin given: test users bench_{}_* were left in the database", run_id),
+    }
+    Ok(())
+}
@@ -17,7 +17,7 @@ async fn main() -> anyhow::Result<()> {
         let resp = guard.send_command(&host, "/login ciao ciaone".to_string()).await?;
         println!("LOGIN1 -> {}", resp);
         // extract token
-        let token = resp.lines().find_map(|l| l.split("SESSION:").nth(1).map(|s| s.trim().to_string()));
+        let token = resp.lines().find_map(|l| l.split("SESSION:").nth(1).and_then(|s| s.split_whitespace().next()).map(str::to_string));
         if token.is_none() {
             println!("No session token in login response");
             return Ok(());
@@ -151,8 +151,8 @@ async fn main() -> anyhow::Result<()> {
         // Estrai session_token dopo login
         if command == "/login" && raw_response.contains("SESSION:") {
             if let Some(line) = raw_response.lines().find(|l| l.contains("SESSION:")) {
-                if let Some(token) = line.split("SESSION:").nth(1) {
-                    session_token = Some(token.trim().to_string());
+                if let Some(token) = line.split("SESSION:").nth(1).and_then(|s| s.split_whitespace().next()) {
+                    session_token = Some(token.to_string());
                     println!("[CLIENT] Login effettuato! Sessione attiva.");
                 }
             }
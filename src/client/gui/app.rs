@@ -1,12 +1,44 @@
-use iced::{Application, Command, Element, Theme};
-use crate::client::models::app_state::{AppState, ChatAppState};
-use crate::client::models::messages::Message;
+use iced::{Application, Command, Element, Subscription, Theme};
+use iced::widget::scrollable;
+use crate::client::gui::views::logger::logger_scroll_id;
+use crate::client::models::app_state::{merge_messages, resolve_host, AppState, ChatAppState, ChatType};
+use crate::client::gui::views::view_friends::format_elapsed;
+use crate::client::models::messages::{LastLogin, Message};
 use crate::client::services::chat_service::ChatService;
-use crate::client::services::message_parser::format_timestamp;
+use crate::client::services::friend_service::FriendService;
+use crate::client::services::websocket_client::WebSocketMessage;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{mpsc, Mutex};
 use crate::client::utils::session_store;
 
+/// How often an idle subscription checks whether a WebSocket has been connected
+/// (or replaced) in the `ChatService`.
+const WEBSOCKET_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_millis(200);
+
+/// Label our sessions get on the server, e.g. "linux-workstation": the OS plus the machine
+/// name when the environment has one, limited to the characters `/login` accepts
+fn device_label() -> String {
+    let machine = std::env::var("COMPUTERNAME").or_else(|_| std::env::var("HOSTNAME")).unwrap_or_default();
+    let label: String = format!("{}-{}", std::env::consts::OS, machine)
+        .chars()
+        .filter(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        .take(32)
+        .collect();
+    label.trim_end_matches('-').to_string()
+}
+
+/// Keystrokes within this window share one typing indicator
+const TYPING_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// How often stale typing indicators are looked for while some are shown
+const TYPING_TICK_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// How often an admin broadcast banner is checked for expiry while shown
+const BROADCAST_TICK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+
+/// Longest the startup `/validate_session` may take before the app goes offline
+const SESSION_CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
 pub struct ChatApp {
     pub state: ChatAppState,
     pub chat_service: Arc<Mutex<ChatService>>,
@@ -26,32 +58,38 @@ impl Application for ChatApp {
             chat_service: chat_service.clone(),
         };
         // Perform async startup check: if a token is saved, try validate it against the default host.
+        let host = resolve_host(&app.state);
         let cmd = Command::perform(
             async move {
                 // Load token from secure store (do not log token contents)
                 if let Some(token) = session_store::load_session_token() {
                     println!("[APP_START] Found saved session token (redacted)");
-                    // try to connect to default host from env
-                    let cfg = crate::server::config::ClientConfig::from_env();
-                    let host = format!("{}:{}", cfg.default_host, cfg.default_port);
                 // Use the app-level ChatService (persistent) to validate the saved session.
                 let svc = chat_service.clone();
                 let mut guard = svc.lock().await;
-                match guard.send_command(&host, format!("/validate_session {}", token)).await {
-                    Ok(response) => {
+                match tokio::time::timeout(SESSION_CHECK_TIMEOUT, guard.send_command(&host, format!("/validate_session {}", token))).await {
+                    Ok(Ok(response)) => {
                         if response.starts_with("OK:") {
                             // Extract username from response for auto-login display
                             let username = response.trim_start_matches("OK:").trim();
                             Message::AuthResult { 
                                 success: true, 
                                 message: username.to_string(), 
-                                token: Some(token) 
+                                token: Some(token),
+                                last_login: None,
                             }
                         } else {
                             Message::SessionMissing
                         }
                     }
-                    Err(_) => Message::SessionMissing,
+                    Ok(Err(e)) => {
+                        println!("[APP_START] Session check failed: {}", e);
+                        Message::ServerUnreachable
+                    }
+                    Err(_) => {
+                        println!("[APP_START] Session check timed out after {:?}", SESSION_CHECK_TIMEOUT);
+                        Message::ServerUnreachable
+                    }
                 }
         } else { Message::SessionMissing }
             },
@@ -66,18 +104,105 @@ impl Application for ChatApp {
     }
 
     fn update(&mut self, message: Message) -> Command<Message> {
+        let log_count = self.state.logger.len();
+        let command = self.dispatch(message);
+        // Newest log entries are shown first: bring them into view as they arrive
+        if self.state.logger.len() > log_count {
+            Command::batch([command, scrollable::snap_to(logger_scroll_id(), scrollable::RelativeOffset::START)])
+        } else {
+            command
+        }
+    }
+
+    fn subscription(&self) -> Subscription<Message> {
+        // Incoming WebSocket traffic only matters while a session is open
+        if self.state.session_token.is_none() {
+            return Subscription::none();
+        }
+        let typing_tick = if self.state.typing_users.is_empty() {
+            Subscription::none()
+        } else {
+            iced::time::every(TYPING_TICK_INTERVAL).map(|_| Message::TypingTick)
+        };
+        let broadcast_tick = if self.state.broadcast_banner.is_none() {
+            Subscription::none()
+        } else {
+            iced::time::every(BROADCAST_TICK_INTERVAL).map(|_| Message::BroadcastTick)
+        };
+        Subscription::batch([websocket_subscription(self.chat_service.clone()), typing_tick, broadcast_tick])
+    }
+
+    fn view(&self) -> Element<'_, Message> {
+        let content = self.screen();
+        match &self.state.broadcast_banner {
+            Some(banner) => iced::widget::Column::new()
+                .push(crate::client::gui::widgets::alert::broadcast_banner(&banner.message))
+                .push(content)
+                .into(),
+            None => content,
+        }
+    }
+}
+
+impl ChatApp {
+    /// The view of the current `AppState`
+    fn screen(&self) -> Element<'_, Message> {
+        match &self.state.app_state {
+            AppState::CheckingSession => iced::widget::Text::new("Controllo sessione...").into(),
+            AppState::Registration => crate::client::gui::views::registration::view(&self.state),
+            AppState::MainActions => crate::client::gui::views::main_actions::view(&self.state),
+            AppState::PrivateChat(username) => crate::client::gui::views::private_chat::view(&self.state, username),
+            AppState::GroupChat(group_id, group_name) => crate::client::gui::views::group_chat::view(&self.state, group_id, group_name),
+            AppState::UsersList(kind) => crate::client::gui::views::users_list::view(&self.state, kind),
+            AppState::FriendRequests => crate::client::gui::views::friend_requests::view(&self.state),
+            AppState::Chat => crate::client::gui::views::main_actions::view(&self.state),
+            AppState::CreateGroup => crate::client::gui::views::create_group::view(&self.state),
+            AppState::MyGroups => crate::client::gui::views::my_groups::view(&self.state),
+            AppState::InviteToGroup { group_id, group_name } => crate::client::gui::views::invite_to_group::view(&self.state, group_id, group_name),
+            AppState::MyGroupInvites => crate::client::gui::views::my_group_invites::view(&self.state),
+            AppState::SendFriendRequest => crate::client::gui::views::send_friend_request::view(&self.state),
+            AppState::ViewFriends => crate::client::gui::views::view_friends::view(&self.state),
+            AppState::BlockedUsers => crate::client::gui::views::blocked_users::view(&self.state),
+            AppState::SearchGroups => crate::client::gui::views::search_groups::view(&self.state),
+            AppState::DiscoverGroups => crate::client::gui::views::discover_groups::view(&self.state),
+            AppState::SearchResults => crate::client::gui::views::search_results::view(&self.state),
+            AppState::PinnedMessages(group_id) => crate::client::gui::views::pinned_messages::view(&self.state, group_id),
+            AppState::ArchivedChats => crate::client::gui::views::archived_chats::view(&self.state),
+            AppState::ChangePassword => crate::client::gui::views::change_password::view(&self.state),
+            AppState::DeleteAccount => crate::client::gui::views::delete_account::view(&self.state),
+            AppState::Sessions => crate::client::gui::views::sessions::view(&self.state),
+            AppState::ConfirmDialog { prompt, confirm_message, cancel_message } => crate::client::gui::views::confirm_dialog::view(&self.state, prompt, (**confirm_message).clone(), (**cancel_message).clone()),
+        }
+    }
+
+    /// Send a typing indicator for the open chat, if any
+    fn typing_indicator_command(&self, is_typing: bool) -> Command<Message> {
+        let (chat_type, target) = match &self.state.app_state {
+            AppState::PrivateChat(username) => (ChatType::Private, username.clone()),
+            AppState::GroupChat(group_id, _) => (ChatType::Group, group_id.clone()),
+            _ => return Command::none(),
+        };
+        let svc = self.chat_service.clone();
+        Command::perform(
+            async move {
+                if let Err(e) = svc.lock().await.send_typing_indicator(chat_type, &target, is_typing).await {
+                    println!("[APP] Typing indicator not sent: {}", e);
+                }
+                Message::NoOp
+            },
+            |msg| msg,
+        )
+    }
+
+    fn dispatch(&mut self, message: Message) -> Command<Message> {
     use crate::client::models::messages::Message as Msg;
-    match message.clone() {
+    self.state.update_polling_flags(&message);
+    // Arms handled here return early; everything else is moved on to the state
+    let message = match message {
             Msg::SubmitLoginOrRegister => {
                 let username = self.state.username.clone();
                 let password = self.state.password.clone();
-                // Resolve host selection: use ClientConfig from env for defaults
-                let cfg = crate::server::config::ClientConfig::from_env();
-                let host = match self.state.selected_host {
-                    crate::client::gui::views::registration::HostType::Localhost => format!("{}:{}", cfg.default_host, cfg.default_port),
-                    crate::client::gui::views::registration::HostType::Remote => format!("{}:{}", cfg.public_host, cfg.default_port),
-                    crate::client::gui::views::registration::HostType::Manual => self.state.manual_host.clone(),
-                };
+                let host = resolve_host(&self.state);
                 let is_login = self.state.is_login;
                 self.state.loading = true;
                 self.state.error_message = None;
@@ -94,7 +219,7 @@ impl Application for ChatApp {
                         // Use the persistent ChatService stored in the app
                         let mut guard = svc_outer.lock().await;
                         let cmd = if is_login {
-                            format!("/login {} {}", username, password)
+                            format!("/login {} {} {}", username, password, device_label())
                         } else {
                             format!("/register {} {}", username, password)
                         };
@@ -102,22 +227,59 @@ impl Application for ChatApp {
                             Ok(response) => {
                                 let token = response.lines().find_map(|l| {
                                     if l.contains("SESSION:") {
-                                        Some(l.split("SESSION:").nth(1).map(|s| s.trim().to_string()).unwrap_or_default())
+                                        Some(l.split("SESSION:").nth(1).and_then(|s| s.split_whitespace().next()).unwrap_or_default().to_string())
                                     } else { None }
                                 });
                                 let cleaned = response.split("SESSION:").next().map(|s| s.trim().to_string()).unwrap_or_default();
                                 if response.contains("OK: Registered") || response.contains("OK: Logged in") {
-                                    Msg::AuthResult { success: true, message: cleaned, token }
+                                    // A login without LAST_LOGIN means the account had no earlier session
+                                    let last_login = is_login.then(|| {
+                                        response
+                                            .split("LAST_LOGIN:")
+                                            .nth(1)
+                                            .and_then(|s| s.trim().parse().ok())
+                                            .map_or(LastLogin::First, LastLogin::At)
+                                    });
+                                    Msg::AuthResult { success: true, message: cleaned, token, last_login }
                                 } else {
-                                    Msg::AuthResult { success: false, message: cleaned, token: None }
+                                    Msg::AuthResult { success: false, message: cleaned, token: None, last_login: None }
                                 }
                             }
-                            Err(e) => Msg::AuthResult { success: false, message: format!("Connessione fallita: {}", e), token: None },
+                            Err(e) => Msg::AuthResult { success: false, message: format!("Connessione fallita: {}", e), token: None, last_login: None },
                         }
                     },
                     |msg| msg,
                 );
             }
+            Msg::MessageInputChanged(input) => {
+                let was_empty = self.state.current_message_input.trim().is_empty();
+                let is_empty = input.trim().is_empty();
+                let command = self.state.update(Msg::MessageInputChanged(input), &self.chat_service);
+                if !is_empty && !self.state.typing_indicator_pending {
+                    self.state.typing_indicator_pending = true;
+                    return Command::batch([command, Command::perform(tokio::time::sleep(TYPING_DEBOUNCE), |_| Msg::SendTypingIndicator)]);
+                }
+                // Clearing the input takes the indicator down right away
+                if is_empty && !was_empty {
+                    return Command::batch([command, self.typing_indicator_command(false)]);
+                }
+                return command;
+            }
+            Msg::SendTypingIndicator => {
+                self.state.typing_indicator_pending = false;
+                if self.state.current_message_input.trim().is_empty() {
+                    return Command::none();
+                }
+                return self.typing_indicator_command(true);
+            }
+            Msg::ServerUnreachable => {
+                use crate::client::gui::views::logger::{LogMessage, LogLevel};
+                self.state.logger.push(LogMessage {
+                    level: LogLevel::Error,
+                    message: "Server unreachable, working offline".to_string(),
+                });
+                return self.dispatch(Msg::SessionMissing);
+            }
             Msg::SessionMissing => {
                 // Token non valido o assente, vai alla schermata di registrazione
                 println!("[APP] Sessione non valida, vai alla registrazione");
@@ -125,10 +287,18 @@ impl Application for ChatApp {
                 self.state.loading = false;
                 return Command::none();
             }
-            Msg::AuthResult { success, message, token } => {
+            Msg::AuthResult { success, message, token, last_login } => {
                 self.state.loading = false;
                 
                 if success {
+                    use crate::client::gui::views::logger::{LogMessage, LogLevel};
+                    if let Some(last_login) = last_login {
+                        let notice = match last_login {
+                            LastLogin::At(ts) => format!("Last login: {}", format_elapsed(chrono::Utc::now().timestamp() - ts)),
+                            LastLogin::First => "Welcome! This is your first login.".to_string(),
+                        };
+                        self.state.logger.push(LogMessage { level: LogLevel::Info, message: notice });
+                    }
                     // Login/registrazione riuscita
                     if let Some(token) = token {
                         self.state.session_token = Some(token.clone());
@@ -150,36 +320,13 @@ impl Application for ChatApp {
                         self.state.username = username.to_string();
                         
                         // Salva il token in modo sicuro
-                        crate::client::utils::session_store::save_session_token(&token);
-                        
-                        // Imposta l'utente corrente nel ChatService
-                        let svc = self.chat_service.clone();
-                        let username_clone = username.to_string();
-                        let token_clone = token.clone();
+                        if let Err(e) = crate::client::utils::session_store::save_session_token(&token) {
+                            println!("[SESSION] Failed to save session token: {}", e);
+                        }
                         
                         // Avvia connessione WebSocket e inizia il loop di controllo messaggi
-                        return Command::perform(
-                            async move {
-                                let mut guard = svc.lock().await;
-                                guard.set_current_user(username_clone);
-                                
-                                // Connetti il WebSocket
-                                let cfg = crate::server::config::ClientConfig::from_env();
-                                let ws_port = cfg.default_port + 1; // WebSocket su porta +1
-                                println!("[APP] Tentativo connessione WebSocket a {}:{}", cfg.default_host, ws_port);
-                                match guard.connect_websocket(&cfg.default_host, ws_port, &token_clone).await {
-                                    Ok(()) => {
-                                        println!("[APP] WebSocket connesso, avviando controllo messaggi");
-                                        Msg::WebSocketConnected
-                                    }
-                                    Err(e) => {
-                                        println!("[APP] Errore connessione WebSocket: {}", e);
-                                        Msg::WebSocketError { error: format!("WebSocket connection failed: {}", e) }
-                                    }
-                                }
-                            },
-                            |msg| msg,
-                        );
+                        let username = username.to_string();
+                        return Command::perform(async move { Msg::ConnectWebSocket { token, username } }, |msg| msg);
                     }
                 } else {
                     // Login/registrazione fallita - usa il logger per mostrare l'errore
@@ -214,16 +361,9 @@ impl Application for ChatApp {
                     |msg| msg,
                 );
                 
-                // Avvia il loop di controllo messaggi
-                let websocket_loop = Command::perform(
-                    async move {
-                        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-                        Msg::CheckWebSocketMessages
-                    },
-                    |msg| msg,
-                );
-                
-                return Command::batch(vec![cleanup_delay, websocket_loop]);
+                let load_recent = Command::perform(async { Msg::LoadRecentConversations }, |msg| msg);
+
+                return Command::batch(vec![cleanup_delay, load_recent]);
             }
             Msg::WebSocketError { error } => {
                 println!("[APP] Errore WebSocket: {}", error);
@@ -232,14 +372,15 @@ impl Application for ChatApp {
             }
             Msg::StartMessagePolling { with } => {
                 // Load initial messages for the private chat
+                self.state.loading_private_chats.insert(with.clone());
                 let svc = self.chat_service.clone();
                 let token = self.state.session_token.clone().unwrap_or_default();
-                let cfg = crate::server::config::ClientConfig::from_env();
+                let host = resolve_host(&self.state);
                 let username = with.clone();
                 
                 return Command::perform(
                     async move {
-                        match svc.lock().await.get_private_messages(&cfg.default_host, &token, &username).await {
+                        match svc.lock().await.get_private_messages(&host, &token, &username).await {
                             Ok(messages) => Msg::NewMessagesReceived { with: username, messages },
                             Err(e) => {
                                 println!("[APP] Error loading initial messages for {}: {}", username, e);
@@ -250,38 +391,58 @@ impl Application for ChatApp {
                     |msg| msg,
                 );
             }
-            Msg::StartGroupMessagePolling { group_id } => {
+            Msg::StartGroupMessagePolling { group_id: _ } => {
                 // Group messages now use WebSocket real-time updates only (no polling)
-                self.state.group_polling_active = false;
                 return Command::<Message>::none();
             }
             Msg::StopGroupMessagePolling => {
                 // Stop group polling and return to main actions view
-                self.state.group_polling_active = false;
-                self.state.app_state = AppState::MainActions;
-                return Command::<Message>::none();
+                self.state.leave_current_chat();
+                return Command::perform(async { Msg::LoadRecentConversations }, |msg| msg);
             }
             Msg::NewGroupMessagesReceived { group_id, messages } => {
                 // Update group chat messages from WebSocket (no more polling)
-                self.state.group_chats.insert(group_id.clone(), messages.to_vec());
+                let fetch_avatars = self.state.fetch_missing_avatars(&messages);
+                let merged = merge_messages(self.state.group_chats.get(&group_id).map(|v| v.as_slice()).unwrap_or(&[]), &messages);
+                self.state.group_chats.insert(group_id.clone(), merged);
                 // clear loading flag when messages arrive
                 self.state.loading_group_chats.remove(&group_id);
-                return Command::<Message>::none();
+                return fetch_avatars;
+            }
+            Msg::OlderGroupMessagesLoaded { group_id, messages, next_before } => {
+                self.state.loading_older_group_messages.remove(&group_id);
+                match next_before {
+                    Some(cursor) => self.state.group_next_before.insert(group_id.clone(), cursor),
+                    None => self.state.group_next_before.remove(&group_id),
+                };
+                // Older messages go before the visible ones; merge keeps both, sorted by time
+                let fetch_avatars = self.state.fetch_missing_avatars(&messages);
+                let merged = merge_messages(self.state.group_chats.get(&group_id).map(|v| v.as_slice()).unwrap_or(&[]), &messages);
+                self.state.group_chats.insert(group_id, merged);
+                return fetch_avatars;
             }
-            Msg::TriggerImmediateGroupRefresh { group_id } => {
+            Msg::TriggerImmediateGroupRefresh { group_id: _ } => {
                 // Group messages now use WebSocket real-time updates only (no manual refresh needed)
                 return Command::<Message>::none();
             }
             Msg::StopMessagePolling => {
                 // Stop polling and return to main actions view
-                self.state.polling_active = false;
-                self.state.app_state = AppState::MainActions;
-                return Command::<Message>::none();
+                self.state.leave_current_chat();
+                return Command::perform(async { Msg::LoadRecentConversations }, |msg| msg);
             }
             Msg::NewMessagesReceived { with, messages } => {
                 println!("[APP] NewMessagesReceived for {}: {} messages", with, messages.len());
+                let fetch_avatars = self.state.fetch_missing_avatars(&messages);
+                if let Some(latest) = messages.last().map(|m| m.timestamp) {
+                    let last_ts = self.state.last_poll_ts.entry(with.clone()).or_insert(latest);
+                    *last_ts = (*last_ts).max(latest);
+                }
                 if self.state.polling_active {
-                    self.state.private_chats.insert(with.clone(), messages.to_vec());
+                    let previous = self.state.private_chats.get(&with).map(|v| v.len()).unwrap_or(0);
+                    let merged = merge_messages(self.state.private_chats.get(&with).map(|v| v.as_slice()).unwrap_or(&[]), &messages);
+                    let grew = merged.len() > previous;
+                    self.state.private_chats.insert(with.clone(), merged);
+                    let scroll = if grew { self.state.scroll_private_chat_to_end(&with) } else { Command::none() };
                     // clear loading flag when messages arrive
                     self.state.loading_private_chats.remove(&with);
                     
@@ -290,15 +451,15 @@ impl Application for ChatApp {
                     // Continue polling
                     let svc = self.chat_service.clone();
                     let token = self.state.session_token.clone().unwrap_or_default();
-                    let cfg = crate::server::config::ClientConfig::from_env();
-                    let host = format!("{}:{}", cfg.default_host, cfg.default_port);
+                    let host = resolve_host(&self.state);
                     let username = with.clone();
+                    let since_ts = self.state.last_poll_ts.get(&with).copied().unwrap_or(0);
                     
-                    return Command::perform(
+                    let next_poll = Command::perform(
                         async move {
                             tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
                             let mut guard = svc.lock().await;
-                            match guard.get_private_messages(&host, &token, &username).await {
+                            match guard.poll_new_messages(&host, &token, &username, since_ts).await {
                                 Ok(messages) => {
                                     drop(guard);
                                     Msg::NewMessagesReceived { with: username.clone(), messages }
@@ -311,13 +472,13 @@ impl Application for ChatApp {
                         },
                         |msg| msg,
                     );
+                    return Command::batch([fetch_avatars, scroll, next_poll]);
                 } else {
-                    return Command::<Message>::none();
+                    return fetch_avatars;
                 }
             }
             Msg::TriggerImmediateRefresh { with } => {
-                let cfg = crate::server::config::ClientConfig::from_env();
-                let host = format!("{}:{}", cfg.default_host, cfg.default_port);
+                let host = resolve_host(&self.state);
                 let token = self.state.session_token.clone().unwrap_or_default();
                 let svc = self.chat_service.clone();
                     let with_cloned = with.clone();
@@ -329,89 +490,82 @@ impl Application for ChatApp {
                         move |messages| Msg::NewMessagesReceived { with: with.clone(), messages }
                     );
             }
-            Msg::CheckWebSocketMessages => {
-                // Controlla se ci sono messaggi WebSocket in arrivo
+            Msg::SendFriendRequestToUser { username, message } => {
+                let host = resolve_host(&self.state);
+                let token = self.state.session_token.clone().unwrap_or_default();
                 let svc = self.chat_service.clone();
+                
                 return Command::perform(
                     async move {
-                        let mut guard = svc.lock().await;
-                        
-                        // Only check for messages if WebSocket is connected
-                        if guard.is_websocket_connected().await {
-                            if let Some(ws_message) = guard.try_receive_websocket_message().await {
-                                return Msg::WebSocketMessageReceived(ws_message);
-                            }
+                        match FriendService::send_request(&svc, &host, &token, &username, &message).await {
+                            Ok(_) => Msg::FriendRequestResult { success: true, message: "Friend request sent successfully!".to_string() },
+                            Err(e) => Msg::FriendActionFailed { level: e.log_level(), message: format!("Error sending friend request: {}", e) },
                         }
-                        
-                        // Continue checking after a brief delay
-                        tokio::time::sleep(tokio::time::Duration::from_millis(50)).await;
-                        Msg::CheckWebSocketMessages
                     },
                     |msg| msg,
                 );
             }
-            Msg::WebSocketMessageReceived(ws_msg) => {
-                // Forward the WebSocket message to app state for proper processing and UI refresh
-                let state_update = self.state.update(Message::WebSocketMessageReceived(ws_msg), &self.chat_service);
-                
-                // Immediately restart the WebSocket message checking loop
-                let restart_loop = Command::perform(
-                    async move { Msg::CheckWebSocketMessages },
-                    |msg| msg,
-                );
-                
-                return Command::batch([state_update, restart_loop]);
-            }
-            _ => {}
-        }
-        
-        // Handle friend request sending
-        if let Msg::SendFriendRequestToUser { username, message } = &message {
-            let cfg = crate::server::config::ClientConfig::from_env();
-            let host = format!("{}:{}", cfg.default_host, cfg.default_port);
-            let token = self.state.session_token.clone().unwrap_or_default();
-            let svc = self.chat_service.clone();
-            let username_clone = username.clone();
-            let message_clone = message.clone();
-            
-            return Command::perform(
-                async move {
-                    let mut guard = svc.lock().await;
-                    let cmd = format!("/send_friend_request {} {} {}", token, username_clone, message_clone);
-                    match guard.send_command(&host, cmd).await {
-                        Ok(response) => {
-                            if response.starts_with("OK:") {
-                                Msg::FriendRequestResult { success: true, message: "Friend request sent successfully!".to_string() }
-                            } else {
-                                Msg::FriendRequestResult { success: false, message: response }
-                            }
-                        }
-                        Err(e) => Msg::FriendRequestResult { success: false, message: format!("Error: {}", e) }
-                    }
-                },
-                |msg| msg,
-            );
-        }
+            other => other,
+        };
         
         self.state.update(message, &self.chat_service)
     }
+}
 
-    fn view(&self) -> Element<Message> {
-        match &self.state.app_state {
-            AppState::CheckingSession => iced::widget::Text::new("Controllo sessione...").into(),
-            AppState::Registration => crate::client::gui::views::registration::view(&self.state),
-            AppState::MainActions => crate::client::gui::views::main_actions::view(&self.state),
-            AppState::PrivateChat(username) => crate::client::gui::views::private_chat::view(&self.state, username),
-            AppState::GroupChat(group_id, group_name) => crate::client::gui::views::group_chat::view(&self.state, group_id, group_name),
-            AppState::UsersList(kind) => crate::client::gui::views::users_list::view(&self.state, kind),
-            AppState::FriendRequests => crate::client::gui::views::friend_requests::view(&self.state),
-            AppState::Chat => crate::client::gui::views::main_actions::view(&self.state),
-            AppState::CreateGroup => crate::client::gui::views::create_group::view(&self.state),
-            AppState::MyGroups => crate::client::gui::views::my_groups::view(&self.state),
-            AppState::InviteToGroup { group_id, group_name } => crate::client::gui::views::invite_to_group::view(&self.state, group_id, group_name),
-            AppState::MyGroupInvites => crate::client::gui::views::my_group_invites::view(&self.state),
-            AppState::SendFriendRequest => crate::client::gui::views::send_friend_request::view(&self.state),
-            AppState::ViewFriends => crate::client::gui::views::view_friends::view(&self.state),
-        }
-    }
+/// State carried between steps of the WebSocket subscription.
+enum WebSocketFeed {
+    /// Waiting for `ChatService::connect_websocket` to provide a receiver
+    Idle(Arc<Mutex<ChatService>>),
+    /// Owning the receiver of the current connection
+    Connected(Arc<Mutex<ChatService>>, mpsc::UnboundedReceiver<WebSocketMessage>),
+}
+
+/// Stream incoming WebSocket messages into the update loop.
+///
+/// The receiver is taken out of the `ChatService` so that waiting for the next
+/// message never holds the service lock. A reconnect replaces the receiver in the
+/// service and a logout drops the connection; both are noticed on the next check.
+fn websocket_subscription(chat_service: Arc<Mutex<ChatService>>) -> Subscription<Message> {
+    struct WebSocketFeedId;
+
+    iced::subscription::unfold(
+        std::any::TypeId::of::<WebSocketFeedId>(),
+        WebSocketFeed::Idle(chat_service),
+        |feed| async move {
+            let (svc, mut receiver) = match feed {
+                WebSocketFeed::Connected(svc, receiver) => (svc, receiver),
+                WebSocketFeed::Idle(svc) => loop {
+                    let taken = svc.lock().await.websocket_receiver.take();
+                    match taken {
+                        Some(receiver) => break (svc, receiver),
+                        None => tokio::time::sleep(WEBSOCKET_CHECK_INTERVAL).await,
+                    }
+                },
+            };
+
+            loop {
+                tokio::select! {
+                    received = receiver.recv() => {
+                        return match received {
+                            Some(WebSocketMessage::NewMessage(mut chat_msg)) => {
+                                chat_msg.content = svc.lock().await.open_e2e(chat_msg.content);
+                                (Message::WebSocketMessageReceived(WebSocketMessage::NewMessage(chat_msg)), WebSocketFeed::Connected(svc, receiver))
+                            }
+                            Some(ws_message) => (Message::WebSocketMessageReceived(ws_message), WebSocketFeed::Connected(svc, receiver)),
+                            None => (Message::WebSocketDisconnected, WebSocketFeed::Idle(svc)),
+                        };
+                    }
+                    _ = tokio::time::sleep(WEBSOCKET_CHECK_INTERVAL) => {
+                        let mut guard = svc.lock().await;
+                        if let Some(replacement) = guard.websocket_receiver.take() {
+                            receiver = replacement;
+                        } else if guard.websocket.is_none() {
+                            drop(guard);
+                            return (Message::WebSocketDisconnected, WebSocketFeed::Idle(svc));
+                        }
+                    }
+                }
+            }
+        },
+    )
 }
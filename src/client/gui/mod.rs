@@ -1,3 +1,4 @@
 pub mod views;
 pub mod widgets;
 pub mod app;
+pub mod save_dialog;
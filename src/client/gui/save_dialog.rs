@@ -0,0 +1,19 @@
+// Finestra di dialogo nativa per salvare file (export delle chat)
+use std::path::PathBuf;
+
+/// Ask the user where to save `contents` as a JSON file and write it there.
+/// Returns `Ok(None)` when the dialog is cancelled.
+pub async fn save_json(default_name: &str, contents: &str) -> anyhow::Result<Option<PathBuf>> {
+    let Some(handle) = rfd::AsyncFileDialog::new()
+        .set_title("Export chat")
+        .set_file_name(default_name)
+        .add_filter("JSON", &["json"])
+        .save_file()
+        .await
+    else {
+        return Ok(None);
+    };
+    let path = handle.path().to_path_buf();
+    tokio::fs::write(&path, contents).await?;
+    Ok(Some(path))
+}
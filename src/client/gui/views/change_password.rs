@@ -0,0 +1,217 @@
+use iced::{Element, Length, Alignment, Color, Font};
+use iced::widget::{Column, Row, Text, TextInput, Button, Container, Space};
+use crate::client::models::messages::Message;
+use crate::client::models::app_state::ChatAppState;
+use crate::client::gui::views::logger::logger_view;
+use crate::common::protocol::password_error;
+
+// Modern color palette consistent with other views
+const BG_MAIN: Color = Color::from_rgb(0.06, 0.07, 0.18);
+const CARD_BG: Color = Color::from_rgb(0.18, 0.19, 0.36);
+const INPUT_BG: Color = Color::from_rgb(0.12, 0.13, 0.26);
+const ACCENT_COLOR: Color = Color::from_rgb(0.0, 0.7, 0.3);
+const ERROR_COLOR: Color = Color::from_rgb(0.9, 0.3, 0.3);
+const TEXT_PRIMARY: Color = Color::WHITE;
+const TEXT_SECONDARY: Color = Color::from_rgb(0.7, 0.7, 0.7);
+
+const EMOJI_FONT: Font = Font::with_name("Segoe UI Emoji");
+const BOLD_FONT: Font = Font {
+    family: iced::font::Family::SansSerif,
+    weight: iced::font::Weight::Bold,
+    ..Font::DEFAULT
+};
+
+// Custom container styles
+fn bg_main_appearance(_: &iced::Theme) -> iced::widget::container::Appearance {
+    iced::widget::container::Appearance {
+        background: Some(iced::Background::Color(BG_MAIN)),
+        text_color: Some(TEXT_PRIMARY),
+        ..Default::default()
+    }
+}
+
+fn header_appearance(_: &iced::Theme) -> iced::widget::container::Appearance {
+    iced::widget::container::Appearance {
+        background: Some(iced::Background::Color(INPUT_BG)),
+        text_color: Some(TEXT_PRIMARY),
+        shadow: iced::Shadow {
+            offset: iced::Vector::new(0.0, 2.0),
+            blur_radius: 8.0,
+            color: Color::from_rgba(0.0, 0.0, 0.0, 0.2),
+        },
+        ..Default::default()
+    }
+}
+
+fn card_appearance(_: &iced::Theme) -> iced::widget::container::Appearance {
+    iced::widget::container::Appearance {
+        background: Some(iced::Background::Color(CARD_BG)),
+        text_color: Some(TEXT_PRIMARY),
+        border: iced::Border {
+            width: 0.0,
+            color: Color::TRANSPARENT,
+            radius: 16.0.into(),
+        },
+        shadow: iced::Shadow {
+            offset: iced::Vector::new(0.0, 4.0),
+            blur_radius: 12.0,
+            color: Color::from_rgba(0.0, 0.0, 0.0, 0.3),
+        },
+    }
+}
+
+fn input_appearance(_: &iced::Theme) -> iced::widget::container::Appearance {
+    iced::widget::container::Appearance {
+        background: Some(iced::Background::Color(INPUT_BG)),
+        text_color: Some(TEXT_PRIMARY),
+        border: iced::Border {
+            width: 1.0,
+            color: Color::from_rgb(0.3, 0.3, 0.4),
+            radius: 12.0.into(),
+        },
+        ..Default::default()
+    }
+}
+
+/// Label plus secure input; Enter submits only while the form is valid
+fn password_field<'a>(label: &'a str, value: &'a str, on_input: fn(String) -> Message, submit: Option<Message>) -> Element<'a, Message> {
+    let mut input = TextInput::new(label, value)
+        .on_input(on_input)
+        .secure(true)
+        .width(Length::Fill)
+        .padding(12)
+        .size(14);
+    if let Some(submit) = submit {
+        input = input.on_submit(submit);
+    }
+    Column::new()
+        .spacing(8)
+        .push(Text::new(label).size(14).style(TEXT_SECONDARY))
+        .push(Container::new(input).style(iced::theme::Container::Custom(Box::new(input_appearance))))
+        .into()
+}
+
+pub fn view(state: &ChatAppState) -> Element<'_, Message> {
+    let old = &state.change_password_old;
+    let new = &state.change_password_new;
+    let confirm = &state.change_password_confirm;
+
+    // Validation: the same rules the server applies to new passwords
+    let new_error = password_error(new);
+    let new_valid = new_error.is_none();
+    let matches = new == confirm;
+    let submit = (!old.is_empty() && new_valid && matches && !state.loading)
+        .then(|| Message::SubmitChangePassword { old: old.clone(), new: new.clone() });
+
+    // Top logger bar
+    let logger_bar = if !state.logger.is_empty() {
+        Container::new(logger_view(&state.logger))
+            .width(Length::Fill)
+            .padding([8, 12, 0, 12])
+    } else {
+        Container::new(Space::new(Length::Fill, Length::Fixed(0.0)))
+            .width(Length::Fill)
+    };
+
+    // Header with back button and title
+    let back_button = Button::new(
+        Container::new(
+            Row::new()
+                .spacing(8)
+                .align_items(Alignment::Center)
+                .push(Text::new("←").font(EMOJI_FONT).size(18))
+                .push(Text::new("Back").font(BOLD_FONT).size(14))
+        )
+        .width(Length::Fill)
+        .center_x()
+    )
+    .style(iced::theme::Button::Secondary)
+    .on_press(Message::OpenMainActions)
+    .padding(12)
+    .width(Length::Fixed(100.0));
+
+    let title_section = Column::new()
+        .spacing(4)
+        .align_items(Alignment::Center)
+        .push(
+            Row::new()
+                .spacing(8)
+                .align_items(Alignment::Center)
+                .push(Text::new("🔑").font(EMOJI_FONT).size(24))
+                .push(Text::new("Change Password").font(BOLD_FONT).size(24).style(TEXT_PRIMARY))
+        )
+        .push(Text::new("Pick a new password for your account").size(14).style(TEXT_SECONDARY));
+
+    let header_row = Row::new()
+        .spacing(16)
+        .align_items(Alignment::Center)
+        .push(back_button)
+        .push(Container::new(title_section).width(Length::Fill).center_x())
+        .push(Space::new(Length::Fixed(100.0), Length::Fixed(0.0))); // Balance space
+
+    let header = Container::new(header_row)
+        .padding([20, 24])
+        .width(Length::Fill)
+        .style(iced::theme::Container::Custom(Box::new(header_appearance)));
+
+    // Hints under the new password fields: neutral while empty, red when invalid
+    let new_hint = Text::new(new_error.unwrap_or("Password looks good"))
+        .size(12)
+        .style(if new.is_empty() { TEXT_SECONDARY } else if new_valid { ACCENT_COLOR } else { ERROR_COLOR });
+    let confirm_hint: Element<Message> = if !confirm.is_empty() && !matches {
+        Text::new("Passwords do not match").size(12).style(ERROR_COLOR).into()
+    } else {
+        Space::new(Length::Fill, Length::Fixed(0.0)).into()
+    };
+
+    let submit_button = Button::new(
+        Container::new(
+            Text::new(if state.loading { "Saving..." } else { "Change Password" })
+                .font(BOLD_FONT)
+                .size(16)
+                .style(TEXT_PRIMARY)
+        )
+        .width(Length::Fill)
+        .center_x()
+    )
+    .style(iced::theme::Button::Primary)
+    .on_press_maybe(submit.clone())
+    .width(Length::Fill)
+    .padding(16);
+
+    let mut form = Column::new()
+        .spacing(16)
+        .padding(24)
+        .push(password_field("Current password", old, Message::ChangePasswordOldChanged, submit.clone()))
+        .push(password_field("New password", new, Message::ChangePasswordNewChanged, submit.clone()))
+        .push(new_hint)
+        .push(password_field("Confirm new password", confirm, Message::ChangePasswordConfirmChanged, submit))
+        .push(confirm_hint);
+    if let Some(error) = &state.change_password_error {
+        form = form.push(Text::new(error).size(14).style(ERROR_COLOR));
+    }
+    let form = form.push(submit_button);
+
+    let form_card = Container::new(form)
+        .width(Length::Fixed(420.0))
+        .style(iced::theme::Container::Custom(Box::new(card_appearance)));
+
+    let content = Column::new()
+        .push(header)
+        .push(Space::new(Length::Fill, Length::Fixed(24.0)))
+        .push(Container::new(form_card).width(Length::Fill).center_x())
+        .width(Length::Fill)
+        .height(Length::Fill);
+
+    let final_content = Column::new()
+        .push(logger_bar)
+        .push(content)
+        .width(Length::Fill)
+        .height(Length::Fill);
+
+    Container::new(final_content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(iced::theme::Container::Custom(Box::new(bg_main_appearance)))
+        .into()
+}
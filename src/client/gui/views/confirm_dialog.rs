@@ -0,0 +1,98 @@
+use iced::{Element, Length, Alignment, Color, Font};
+use iced::widget::{Column, Row, Text, Button, Container};
+use crate::client::models::messages::Message;
+use crate::client::models::app_state::ChatAppState;
+use crate::client::gui::views::logger::logger_view;
+
+// Modern color palette consistent with other views
+const BG_MAIN: Color = Color::from_rgb(0.06, 0.07, 0.18);
+const CARD_BG: Color = Color::from_rgb(0.18, 0.19, 0.36);
+const TEXT_PRIMARY: Color = Color::WHITE;
+
+const EMOJI_FONT: Font = Font::with_name("Segoe UI Emoji");
+const BOLD_FONT: Font = Font {
+    family: iced::font::Family::SansSerif,
+    weight: iced::font::Weight::Bold,
+    ..Font::DEFAULT
+};
+
+// Dimmed backdrop behind the dialog
+fn overlay_appearance(_: &iced::Theme) -> iced::widget::container::Appearance {
+    iced::widget::container::Appearance {
+        background: Some(iced::Background::Color(BG_MAIN)),
+        text_color: Some(TEXT_PRIMARY),
+        ..Default::default()
+    }
+}
+
+fn dialog_appearance(_: &iced::Theme) -> iced::widget::container::Appearance {
+    iced::widget::container::Appearance {
+        background: Some(iced::Background::Color(CARD_BG)),
+        text_color: Some(TEXT_PRIMARY),
+        border: iced::Border {
+            width: 1.0,
+            color: Color::from_rgb(0.2, 0.2, 0.3),
+            radius: 16.0.into(),
+        },
+        shadow: iced::Shadow {
+            offset: iced::Vector::new(0.0, 4.0),
+            blur_radius: 16.0,
+            color: Color::from_rgba(0.0, 0.0, 0.0, 0.4),
+        },
+    }
+}
+
+pub fn view<'a>(state: &'a ChatAppState, prompt: &'a str, confirm_message: Message, cancel_message: Message) -> Element<'a, Message> {
+    let buttons = Row::new()
+        .spacing(12)
+        .push(
+            Button::new(
+                Container::new(Text::new("Cancel").font(BOLD_FONT).size(14))
+                    .width(Length::Fill)
+                    .center_x()
+            )
+            .style(iced::theme::Button::Secondary)
+            .on_press(cancel_message)
+            .padding(12)
+            .width(Length::Fill)
+        )
+        .push(
+            Button::new(
+                Container::new(Text::new("Confirm").font(BOLD_FONT).size(14))
+                    .width(Length::Fill)
+                    .center_x()
+            )
+            .style(iced::theme::Button::Destructive)
+            .on_press(confirm_message)
+            .padding(12)
+            .width(Length::Fill)
+        );
+
+    let dialog = Container::new(
+        Column::new()
+            .spacing(20)
+            .align_items(Alignment::Center)
+            .push(Text::new("⚠️").font(EMOJI_FONT).size(32))
+            .push(Text::new(prompt).size(16).style(TEXT_PRIMARY))
+            .push(buttons)
+    )
+    .padding(24)
+    .width(Length::Fixed(420.0))
+    .style(iced::theme::Container::Custom(Box::new(dialog_appearance)));
+
+    let mut content = Column::new()
+        .spacing(16)
+        .align_items(Alignment::Center);
+    if !state.logger.is_empty() {
+        content = content.push(logger_view(&state.logger));
+    }
+    content = content.push(dialog);
+
+    Container::new(content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .center_x()
+        .center_y()
+        .style(iced::theme::Container::Custom(Box::new(overlay_appearance)))
+        .into()
+}
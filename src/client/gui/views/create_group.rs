@@ -11,6 +11,7 @@ const INPUT_BG: Color = Color::from_rgb(0.12, 0.13, 0.26);
 const ACCENT_COLOR: Color = Color::from_rgb(0.0, 0.7, 0.3);
 const TEXT_PRIMARY: Color = Color::WHITE;
 const TEXT_SECONDARY: Color = Color::from_rgb(0.7, 0.7, 0.7);
+const ERROR_COLOR: Color = Color::from_rgb(0.9, 0.3, 0.3);
 
 const EMOJI_FONT: Font = Font::with_name("Segoe UI Emoji");
 const BOLD_FONT: Font = Font {
@@ -105,7 +106,7 @@ fn user_item_appearance(_: &iced::Theme) -> iced::widget::container::Appearance
     }
 }
 
-pub fn view(state: &ChatAppState) -> Element<Message> {
+pub fn view(state: &ChatAppState) -> Element<'_, Message> {
     // Top logger bar
     let logger_bar = if !state.logger.is_empty() {
         Container::new(logger_view(&state.logger))
@@ -160,7 +161,10 @@ pub fn view(state: &ChatAppState) -> Element<Message> {
     // Group name input validation
     let group_name_valid = !state.create_group_name.trim().is_empty() && state.create_group_name.len() >= 3;
     let has_participants = !state.selected_participants.is_empty();
-    let submit_enabled = group_name_valid && has_participants && !state.loading;
+    // The server keeps descriptions up to 500 characters
+    let description_len = state.create_group_description.trim().chars().count();
+    let description_valid = description_len <= 500;
+    let submit_enabled = group_name_valid && has_participants && description_valid && !state.loading;
 
     // Main form card
     let group_name_field = Column::new()
@@ -183,6 +187,29 @@ pub fn view(state: &ChatAppState) -> Element<Message> {
             .style(iced::theme::Container::Custom(Box::new(input_appearance)))
         );
 
+    // Optional description
+    let description_field = Column::new()
+        .spacing(8)
+        .push(
+            Row::new()
+                .spacing(8)
+                .align_items(Alignment::Center)
+                .push(Text::new("📝").font(EMOJI_FONT).size(16).style(TEXT_SECONDARY))
+                .push(Text::new("Description (optional)").size(14).style(TEXT_SECONDARY))
+                .push(Space::new(Length::Fill, Length::Fixed(0.0)))
+                .push(Text::new(format!("{}/500", description_len)).size(12).style(if description_valid { TEXT_SECONDARY } else { ERROR_COLOR }))
+        )
+        .push(
+            Container::new(
+                TextInput::new("What is this group about?", &state.create_group_description)
+                    .on_input(Message::CreateGroupDescriptionChanged)
+                    .width(Length::Fill)
+                    .padding(12)
+                    .size(14)
+            )
+            .style(iced::theme::Container::Custom(Box::new(input_appearance)))
+        );
+
     // Search field for participants
     let search_field = Column::new()
         .spacing(8)
@@ -273,10 +300,12 @@ pub fn view(state: &ChatAppState) -> Element<Message> {
             )
     };
 
-    // Available users list
+    // Available users list, filtered inline by the search field
+    let query = state.users_search_query.trim().to_lowercase();
     let mut users_list = Column::new().spacing(8);
     for username in &state.users_search_results {
-        if username != &state.username && !state.selected_participants.contains(username) {
+        let matches_query = query.is_empty() || username.to_lowercase().contains(&query);
+        if matches_query && username != &state.username && !state.selected_participants.contains(username) {
             let user_item = Container::new(
                 Row::new()
                     .spacing(12)
@@ -426,6 +455,7 @@ pub fn view(state: &ChatAppState) -> Element<Message> {
         .spacing(20)
         .padding(32)
         .push(group_name_field)
+        .push(description_field)
         .push(search_field)
         .push(selected_section)
         .push(users_section)
@@ -0,0 +1,282 @@
+use iced::{Element, Length, Alignment, Color, Font};
+use iced::widget::{Column, Row, Text, Button, Container, Space, Scrollable, TextInput};
+use crate::client::models::messages::Message;
+use crate::client::models::app_state::ChatAppState;
+use crate::client::services::group_service::PublicGroup;
+use crate::client::gui::views::logger::logger_view;
+
+// Modern color palette consistent with other views
+const BG_MAIN: Color = Color::from_rgb(0.06, 0.07, 0.18);
+const CARD_BG: Color = Color::from_rgb(0.18, 0.19, 0.36);
+const INPUT_BG: Color = Color::from_rgb(0.12, 0.13, 0.26);
+const TEXT_PRIMARY: Color = Color::WHITE;
+const TEXT_SECONDARY: Color = Color::from_rgb(0.7, 0.7, 0.7);
+
+const EMOJI_FONT: Font = Font::with_name("Segoe UI Emoji");
+const BOLD_FONT: Font = Font {
+    family: iced::font::Family::SansSerif,
+    weight: iced::font::Weight::Bold,
+    ..Font::DEFAULT
+};
+
+// Custom container styles
+fn bg_main_appearance(_: &iced::Theme) -> iced::widget::container::Appearance {
+    iced::widget::container::Appearance {
+        background: Some(iced::Background::Color(BG_MAIN)),
+        text_color: Some(TEXT_PRIMARY),
+        ..Default::default()
+    }
+}
+
+fn header_appearance(_: &iced::Theme) -> iced::widget::container::Appearance {
+    iced::widget::container::Appearance {
+        background: Some(iced::Background::Color(INPUT_BG)),
+        text_color: Some(TEXT_PRIMARY),
+        shadow: iced::Shadow {
+            offset: iced::Vector::new(0.0, 2.0),
+            blur_radius: 8.0,
+            color: Color::from_rgba(0.0, 0.0, 0.0, 0.2),
+        },
+        ..Default::default()
+    }
+}
+
+fn group_item_appearance(_: &iced::Theme) -> iced::widget::container::Appearance {
+    iced::widget::container::Appearance {
+        background: Some(iced::Background::Color(CARD_BG)),
+        text_color: Some(TEXT_PRIMARY),
+        border: iced::Border {
+            width: 1.0,
+            color: Color::from_rgb(0.2, 0.2, 0.3),
+            radius: 12.0.into(),
+        },
+        ..Default::default()
+    }
+}
+
+fn input_appearance(_: &iced::Theme) -> iced::widget::container::Appearance {
+    iced::widget::container::Appearance {
+        background: Some(iced::Background::Color(INPUT_BG)),
+        text_color: Some(TEXT_PRIMARY),
+        border: iced::Border {
+            width: 1.0,
+            color: Color::from_rgb(0.3, 0.3, 0.4),
+            radius: 12.0.into(),
+        },
+        ..Default::default()
+    }
+}
+
+/// Text input with a button next to it, both sending `submit`
+fn input_row<'a>(placeholder: &str, value: &str, on_input: fn(String) -> Message, label: &'a str, submit: Message) -> Element<'a, Message> {
+    let input = Container::new(
+        TextInput::new(placeholder, value)
+            .on_input(on_input)
+            .on_submit(submit.clone())
+            .padding(12)
+            .size(14)
+            .width(Length::Fill)
+    )
+    .style(iced::theme::Container::Custom(Box::new(input_appearance)));
+
+    let button = Button::new(
+        Container::new(Text::new(label).font(BOLD_FONT).size(14))
+            .width(Length::Fill)
+            .center_x()
+    )
+    .style(iced::theme::Button::Primary)
+    .on_press(submit)
+    .padding(12)
+    .width(Length::Fixed(140.0));
+
+    Row::new()
+        .spacing(12)
+        .align_items(Alignment::Center)
+        .push(input)
+        .push(button)
+        .into()
+}
+
+/// One public group: name, description and member count, with Join or Open
+fn group_item<'a>(state: &ChatAppState, group: &PublicGroup) -> Element<'a, Message> {
+    // Groups joined since the page was loaded can be opened too
+    let already_member = group.joined || state.my_groups.iter().any(|g| g.id == group.id);
+    let (label, action) = if already_member {
+        ("Open", Message::OpenGroupChat(group.id.clone(), group.name.clone()))
+    } else {
+        ("Join", Message::JoinGroup { group_id: group.id.clone() })
+    };
+    let action_button = Button::new(
+        Container::new(Text::new(label).font(BOLD_FONT).size(12))
+            .width(Length::Fill)
+            .center_x()
+    )
+    .style(if already_member { iced::theme::Button::Secondary } else { iced::theme::Button::Primary })
+    .on_press(action)
+    .padding(10)
+    .width(Length::Fixed(100.0));
+
+    let members_label = if group.member_count == 1 { "1 member".to_string() } else { format!("{} members", group.member_count) };
+    let mut details = Column::new()
+        .spacing(4)
+        .push(Text::new(group.name.clone()).font(BOLD_FONT).size(16).style(TEXT_PRIMARY));
+    if !group.description.is_empty() {
+        details = details.push(Text::new(group.description.clone()).size(13).style(TEXT_PRIMARY));
+    }
+    let details = details.push(Text::new(members_label).size(12).style(TEXT_SECONDARY));
+
+    Container::new(
+        Row::new()
+            .spacing(16)
+            .align_items(Alignment::Center)
+            .push(Text::new("🌐").font(EMOJI_FONT).size(24))
+            .push(Container::new(details).width(Length::Fill))
+            .push(action_button)
+    )
+    .padding(16)
+    .width(Length::Fill)
+    .style(iced::theme::Container::Custom(Box::new(group_item_appearance)))
+    .into()
+}
+
+/// Previous / next buttons around "Page x of y"
+fn pager<'a>(state: &ChatAppState) -> Element<'a, Message> {
+    let page = state.public_groups.page;
+    let pages = state.public_groups.pages;
+    let query = state.discover_query.clone();
+    let page_button = |label: &'a str, target: Option<i64>| {
+        Button::new(Text::new(label).font(BOLD_FONT).size(14))
+            .style(iced::theme::Button::Secondary)
+            .on_press_maybe(target.map(|page| Message::LoadPublicGroups { query: query.clone(), page }))
+            .padding([8, 16])
+    };
+
+    Row::new()
+        .spacing(16)
+        .align_items(Alignment::Center)
+        .push(page_button("← Prev", (page > 1).then(|| page - 1)))
+        .push(Text::new(format!("Page {} of {}", page, pages)).size(14).style(TEXT_SECONDARY))
+        .push(page_button("Next →", (page < pages).then(|| page + 1)))
+        .into()
+}
+
+pub fn view(state: &ChatAppState) -> Element<'_, Message> {
+    // Top logger bar
+    let logger_bar = if !state.logger.is_empty() {
+        Container::new(logger_view(&state.logger))
+            .width(Length::Fill)
+            .padding([8, 12, 0, 12])
+    } else {
+        Container::new(Space::new(Length::Fill, Length::Fixed(0.0)))
+            .width(Length::Fill)
+    };
+
+    // Header with back button and title
+    let back_button = Button::new(
+        Container::new(
+            Row::new()
+                .spacing(8)
+                .align_items(Alignment::Center)
+                .push(Text::new("←").font(EMOJI_FONT).size(18))
+                .push(Text::new("Back").font(BOLD_FONT).size(14))
+        )
+        .width(Length::Fill)
+        .center_x()
+    )
+    .style(iced::theme::Button::Secondary)
+    .on_press(Message::OpenMyGroups)
+    .padding(12)
+    .width(Length::Fixed(100.0));
+
+    let title_section = Column::new()
+        .spacing(4)
+        .align_items(Alignment::Center)
+        .push(
+            Row::new()
+                .spacing(8)
+                .align_items(Alignment::Center)
+                .push(Text::new("🌐").font(EMOJI_FONT).size(24))
+                .push(Text::new("Discover Groups").font(BOLD_FONT).size(24).style(TEXT_PRIMARY))
+        )
+        .push(Text::new("Browse public groups or join one with an invite code").size(14).style(TEXT_SECONDARY));
+
+    let header_row = Row::new()
+        .spacing(16)
+        .align_items(Alignment::Center)
+        .push(back_button)
+        .push(Container::new(title_section).width(Length::Fill).center_x())
+        .push(Space::new(Length::Fixed(100.0), Length::Fixed(0.0))); // Balance space
+
+    let header = Container::new(header_row)
+        .padding([20, 24])
+        .width(Length::Fill)
+        .style(iced::theme::Container::Custom(Box::new(header_appearance)));
+
+    // Search and invite code inputs
+    let search_row = input_row(
+        "Search public groups...",
+        &state.discover_query,
+        Message::DiscoverQueryChanged,
+        "Search",
+        Message::LoadPublicGroups { query: state.discover_query.clone(), page: 1 },
+    );
+    let code_row = input_row(
+        "Invite code",
+        &state.join_code_input,
+        Message::JoinCodeChanged,
+        "Join with code",
+        Message::JoinByCode { code: state.join_code_input.clone() },
+    );
+    let inputs = Container::new(Column::new().spacing(12).push(search_row).push(code_row))
+        .padding([0, 24]);
+
+    // Public groups, one page at a time
+    let content: Element<Message> = if state.loading {
+        Container::new(Text::new("Loading public groups...").font(BOLD_FONT).size(16).style(TEXT_SECONDARY))
+            .width(Length::Fill)
+            .center_x()
+            .padding(40)
+            .into()
+    } else if state.public_groups.groups.is_empty() {
+        Container::new(Text::new("No public groups found").size(14).style(TEXT_SECONDARY))
+            .width(Length::Fill)
+            .center_x()
+            .padding(40)
+            .into()
+    } else {
+        let groups = state.public_groups.groups.iter()
+            .fold(Column::new().spacing(12), |column, group| column.push(group_item(state, group)));
+        Container::new(
+            Column::new()
+                .spacing(12)
+                .push(Scrollable::new(groups).width(Length::Fill).height(Length::Fill))
+                .push(Container::new(pager(state)).width(Length::Fill).center_x())
+        )
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .padding([0, 24])
+        .into()
+    };
+
+    let main_content = Column::new()
+        .push(header)
+        .push(Space::new(Length::Fill, Length::Fixed(16.0)))
+        .push(inputs)
+        .push(Space::new(Length::Fill, Length::Fixed(16.0)))
+        .push(content)
+        .push(Space::new(Length::Fill, Length::Fixed(24.0)))
+        .width(Length::Fill)
+        .height(Length::Fill);
+
+    let final_content = Column::new()
+        .push(logger_bar)
+        .push(main_content)
+        .width(Length::Fill)
+        .height(Length::Fill);
+
+    Container::new(final_content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(iced::theme::Container::Custom(Box::new(bg_main_appearance)))
+        .into()
+}
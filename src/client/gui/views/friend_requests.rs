@@ -17,6 +17,10 @@ const BOLD_FONT: Font = Font {
     weight: iced::font::Weight::Bold,
     ..Font::DEFAULT
 };
+const ITALIC_FONT: Font = Font {
+    style: iced::font::Style::Italic,
+    ..Font::DEFAULT
+};
 
 // Custom container styles
 fn bg_main_appearance(_: &iced::Theme) -> iced::widget::container::Appearance {
@@ -87,7 +91,7 @@ fn empty_state_appearance(_: &iced::Theme) -> iced::widget::container::Appearanc
     }
 }
 
-pub fn view(state: &ChatAppState) -> Element<Message> {
+pub fn view(state: &ChatAppState) -> Element<'_, Message> {
     // Top logger bar
     let logger_bar = if !state.logger.is_empty() {
         Container::new(logger_view(&state.logger))
@@ -178,7 +182,8 @@ pub fn view(state: &ChatAppState) -> Element<Message> {
         // Friend requests list
         let mut requests_column = Column::new().spacing(12);
         
-        for (username, message) in &state.friend_requests {
+        for request in &state.friend_requests {
+            let username = &request.from_username;
             let request_item = Container::new(
                 Row::new()
                     .spacing(16)
@@ -203,11 +208,11 @@ pub fn view(state: &ChatAppState) -> Element<Message> {
                         Column::new()
                             .spacing(4)
                             .push(Text::new(username).font(BOLD_FONT).size(16).style(TEXT_PRIMARY))
-                            .push(
-                                Text::new(if message.is_empty() { "Friend request" } else { message })
-                                    .size(12)
-                                    .style(TEXT_SECONDARY)
-                            )
+                            .push(if request.message.is_empty() {
+                                Text::new("No message provided").font(ITALIC_FONT).size(12).style(TEXT_SECONDARY)
+                            } else {
+                                Text::new(&request.message).size(12).style(TEXT_SECONDARY)
+                            })
                     )
                     .push(Space::new(Length::Fill, Length::Fixed(0.0)))
                     .push(
@@ -1,7 +1,10 @@
 use iced::{Element, Length, Alignment, Color, Font};
 use iced::widget::{Column, Row, Text, TextInput, Button, Container, Scrollable, Space, scrollable};
 use crate::client::models::messages::Message;
-use crate::client::models::app_state::ChatAppState;
+use crate::client::models::app_state::{ChatAppState, ChatType, INVITE_CODE_DEFAULT_EXPIRY_SECS};
+use crate::client::gui::widgets::message_list::{inject_date_separators, date_separator, ChatViewItem};
+use crate::client::gui::widgets::{avatar, forward, reactions, reply};
+use crate::client::services::chat_service::HISTORY_PAGE_SIZE;
 
 // Color palette per chat moderna (WhatsApp-like)
 const BG_MAIN: Color = Color::from_rgb(0.06, 0.07, 0.18); // Deep navy
@@ -20,6 +23,20 @@ const BOLD_FONT: Font = Font {
 
 const EMOJI_FONT: Font = Font::with_name("Segoe UI Emoji");
 
+const ONLINE_DOT: Color = Color::from_rgb(0.2, 0.8, 0.3);
+const OFFLINE_DOT: Color = Color::from_rgb(0.5, 0.5, 0.5);
+
+/// Characters of the description shown while it is collapsed
+const DESCRIPTION_PREVIEW_CHARS: usize = 100;
+/// Longest description the server accepts
+const MAX_DESCRIPTION_CHARS: usize = 500;
+/// Pinned messages listed by the expanded banner; "See all" shows the rest
+const PINNED_BANNER_MESSAGES: usize = 3;
+/// Characters of a pinned message shown by the banner
+const PINNED_PREVIEW_CHARS: usize = 80;
+/// Expiry choices of the invite code dialog
+const INVITE_CODE_EXPIRIES: [(&str, Option<i64>); 3] = [("1 day", Some(24 * 60 * 60)), ("7 days", Some(INVITE_CODE_DEFAULT_EXPIRY_SECS)), ("Never", None)];
+
 
 pub fn view<'a>(state: &'a ChatAppState, group_id: &'a str, group_name: &'a str) -> Element<'a, Message> {
     // Header con nome gruppo e pulsante back
@@ -28,8 +45,12 @@ pub fn view<'a>(state: &'a ChatAppState, group_id: &'a str, group_name: &'a str)
         .style(iced::theme::Button::Secondary)
         .padding(8);
 
+    let my_role = state.group_members.get(group_id)
+        .and_then(|members| members.iter().find(|m| m.username == state.username))
+        .map(|m| m.role.as_str());
+    let i_am_admin = matches!(my_role, Some("owner" | "admin"));
     let group_info = Column::new()
-        .push(Text::new(group_name).font(BOLD_FONT).size(20).style(TEXT_PRIMARY))
+        .push(build_group_title(state, group_id, group_name, i_am_admin))
         .push(Text::new("Group Chat").size(12).style(TEXT_SECONDARY))
         .spacing(2);
 
@@ -49,24 +70,72 @@ pub fn view<'a>(state: &'a ChatAppState, group_id: &'a str, group_name: &'a str)
 
     // Pulsante per lasciare il gruppo
     let leave_group_btn = Button::new(Text::new("🚪").font(EMOJI_FONT).size(16))
-        .on_press(Message::LeaveGroup { 
+        .on_press(Message::ConfirmLeaveGroup { 
             group_id: group_id.to_string(), 
             group_name: group_name.to_string() 
         })
         .style(iced::theme::Button::Destructive)
         .padding(8);
 
-    let header = Container::new(
-        Row::new()
-            .spacing(12)
-            .align_items(Alignment::Center)
-            .push(back_btn)
-            .push(group_info)
-            .push(Space::new(Length::Fill, Length::Fixed(0.0)))
-            .push(add_member_btn)
-            .push(leave_group_btn)
-            .push(discard_btn)
-    )
+    // Pulsante per archiviare la chat
+    let archive_btn = Button::new(Text::new("🗄️").font(EMOJI_FONT).size(16))
+        .on_press(Message::ArchiveChat { chat_type: ChatType::Group, chat_id: group_id.to_string() })
+        .style(iced::theme::Button::Secondary)
+        .padding(8);
+
+    // Pulsante per mostrare/nascondere la lista membri
+    let sidebar_open = state.show_members_sidebar.get(group_id).copied().unwrap_or(false);
+    let members_btn = Button::new(Text::new("👥").font(EMOJI_FONT).size(16))
+        .on_press(Message::ToggleMemberSidebar { group_id: group_id.to_string() })
+        .style(if sidebar_open { iced::theme::Button::Primary } else { iced::theme::Button::Secondary })
+        .padding(8);
+
+    // Pulsante per cercare nei messaggi
+    let search_btn = Button::new(Text::new("🔍").font(EMOJI_FONT).size(16))
+        .on_press(Message::OpenMessageSearch)
+        .style(iced::theme::Button::Secondary)
+        .padding(8);
+
+    // Pulsante per esportare la chat in JSON
+    let export_btn = Button::new(Text::new("💾").font(EMOJI_FONT).size(16))
+        .on_press(Message::ExportChat { chat_type: ChatType::Group, chat_id: group_id.to_string() })
+        .style(iced::theme::Button::Secondary)
+        .padding(8);
+
+    let mut header_row = Row::new()
+        .spacing(12)
+        .align_items(Alignment::Center)
+        .push(back_btn)
+        .push(group_info)
+        .push(Space::new(Length::Fill, Length::Fixed(0.0)))
+        .push(search_btn)
+        .push(members_btn)
+        .push(add_member_btn)
+        .push(export_btn)
+        .push(archive_btn)
+        .push(leave_group_btn)
+        .push(discard_btn);
+    // Pulsante per condividere un codice d'invito, solo per owner e admin
+    if i_am_admin {
+        let dialog_open = state.invite_code_dialog.as_ref().is_some_and(|d| d.group_id == group_id);
+        header_row = header_row.push(
+            Button::new(Text::new("🔗").font(EMOJI_FONT).size(16))
+                .on_press(if dialog_open { Message::CloseInviteCodeDialog } else { Message::OpenInviteCodeDialog { group_id: group_id.to_string() } })
+                .style(if dialog_open { iced::theme::Button::Primary } else { iced::theme::Button::Secondary })
+                .padding(8),
+        );
+    }
+    // Pulsante per eliminare il gruppo, solo per chi l'ha creato (l'owner)
+    if my_role == Some("owner") {
+        header_row = header_row.push(
+            Button::new(Text::new("❌").font(EMOJI_FONT).size(16))
+                .on_press(Message::ConfirmDeleteGroup { group_id: group_id.to_string(), group_name: group_name.to_string() })
+                .style(iced::theme::Button::Destructive)
+                .padding(8),
+        );
+    }
+
+    let header = Container::new(header_row)
     .padding([12, 16])
     .width(Length::Fill)
     .style(iced::theme::Container::Custom(Box::new(|_: &iced::Theme| {
@@ -76,16 +145,35 @@ pub fn view<'a>(state: &'a ChatAppState, group_id: &'a str, group_name: &'a str)
         }
     })));
 
-    // Area messaggi
+    // Area messaggi, con la lista membri a destra se aperta
     let messages_area = build_messages_area(state, group_id);
+    let body: Element<'a, Message> = if sidebar_open {
+        Row::new()
+            .push(messages_area)
+            .push(build_members_sidebar(state, group_id))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .into()
+    } else {
+        messages_area
+    };
 
     // Input area
     let input_area = build_input_area(state, group_id);
 
     // Layout principale
-    let content = Column::new()
-        .push(header)
-        .push(messages_area)
+    let mut content = Column::new().push(header);
+    if let Some(invite_code) = build_invite_code_section(state, group_id) {
+        content = content.push(invite_code);
+    }
+    if let Some(description) = build_description_section(state, group_id, i_am_admin) {
+        content = content.push(description);
+    }
+    if let Some(pinned) = build_pinned_banner(state, group_id) {
+        content = content.push(pinned);
+    }
+    let content = content
+        .push(body)
         .push(input_area)
         .width(Length::Fill)
         .height(Length::Fill);
@@ -107,6 +195,11 @@ fn build_messages_area<'a>(state: &'a ChatAppState, group_id: &'a str) -> Elemen
 
     // Check if messages are discarded for this group
 
+    // Roles are known once the member list has loaded
+    let can_moderate = state.group_members.get(group_id).is_some_and(|members| {
+        members.iter().any(|m| m.username == state.username && matches!(m.role.as_str(), "owner" | "admin"))
+    });
+
     // Show cached messages or appropriate placeholder
     if let Some(chat_messages) = state.group_chats.get(group_id) {
         if chat_messages.is_empty() {
@@ -121,10 +214,23 @@ fn build_messages_area<'a>(state: &'a ChatAppState, group_id: &'a str) -> Elemen
                 .padding(20)
             );
         } else {
-            for msg in chat_messages.iter() {
-                let is_my_message = msg.sender == state.username;
-                let message_bubble = create_message_bubble(msg, is_my_message);
-                messages_column = messages_column.push(message_bubble);
+            if state.loading_older_group_messages.contains(group_id) {
+                messages_column = messages_column.push(
+                    Container::new(Text::new("Loading older messages...").size(12).style(TEXT_SECONDARY))
+                        .width(Length::Fill)
+                        .center_x()
+                );
+            }
+            for item in inject_date_separators(chat_messages) {
+                match item {
+                    ChatViewItem::DateSeparator(label) => {
+                        messages_column = messages_column.push(date_separator(label));
+                    }
+                    ChatViewItem::Message(msg) => {
+                        let is_my_message = msg.sender == state.username;
+                        messages_column = messages_column.push(create_message_bubble(state, group_id, msg, is_my_message, can_moderate));
+                    }
+                }
             }
         }
     } else {
@@ -145,10 +251,23 @@ fn build_messages_area<'a>(state: &'a ChatAppState, group_id: &'a str) -> Elemen
     messages_column = messages_column.push(Space::new(Length::Fixed(0.0), Length::Fixed(20.0)));
 
     // Scrollable container per i messaggi
+    // Reaching the top pages in older messages while the server has more
+    let page_group_id = group_id.to_string();
+    let load_older_before = state.group_next_before.get(group_id)
+        .copied()
+        .filter(|_| !state.loading_older_group_messages.contains(group_id));
     let scrollable_messages = Scrollable::new(messages_column)
             .width(Length::Fill)
             .height(Length::Fill)
-            .id(scrollable::Id::new("group_messages_scroll"));
+            .id(scrollable::Id::new("group_messages_scroll"))
+            .on_scroll(move |viewport| match load_older_before {
+                Some(before_ts) if viewport.relative_offset().y <= 0.0 => Message::LoadGroupMessagesPage {
+                    group_id: page_group_id.clone(),
+                    before_ts,
+                    limit: HISTORY_PAGE_SIZE,
+                },
+                _ => Message::NoOp,
+            });
 
     Container::new(scrollable_messages)
     .width(Length::Fill)
@@ -162,7 +281,340 @@ fn build_messages_area<'a>(state: &'a ChatAppState, group_id: &'a str) -> Elemen
     .into()
 }
 
-fn create_message_bubble(msg: &crate::client::models::app_state::ChatMessage, is_my_message: bool) -> Element<'_, Message> {
+/// Group name, replaced by an input while an admin renames the group
+fn build_group_title<'a>(state: &'a ChatAppState, group_id: &'a str, group_name: &'a str, i_am_admin: bool) -> Element<'a, Message> {
+    let Some(draft) = &state.group_rename_draft else {
+        let mut title = Row::new()
+            .spacing(8)
+            .align_items(Alignment::Center)
+            .push(Text::new(group_name).font(BOLD_FONT).size(20).style(TEXT_PRIMARY));
+        if i_am_admin {
+            title = title.push(
+                Button::new(Text::new("✏️").font(EMOJI_FONT).size(12))
+                    .on_press(Message::StartRenameGroup { current_name: group_name.to_string() })
+                    .style(iced::theme::Button::Text)
+                    .padding(0),
+            );
+        }
+        return title.into();
+    };
+
+    // The server takes the name as a single argument of 3-64 characters
+    let valid = (3..=64).contains(&draft.chars().count()) && !draft.chars().any(char::is_whitespace);
+    let submit = valid.then(|| Message::RenameGroup { group_id: group_id.to_string(), new_name: draft.clone() });
+    let mut input = TextInput::new("New group name", draft)
+        .on_input(Message::RenameGroupDraftChanged)
+        .padding(6)
+        .size(16)
+        .width(Length::Fixed(220.0));
+    if let Some(submit) = submit.clone() {
+        input = input.on_submit(submit);
+    }
+    Row::new()
+        .spacing(6)
+        .align_items(Alignment::Center)
+        .push(input)
+        .push(Button::new(Text::new("Save").size(12)).on_press_maybe(submit).style(iced::theme::Button::Primary).padding([4, 8]))
+        .push(Button::new(Text::new("Cancel").size(12)).on_press(Message::CancelRenameGroup).style(iced::theme::Button::Secondary).padding([4, 8]))
+        .into()
+}
+
+/// `text` cut to `max_chars` characters, with an ellipsis when something was cut
+fn preview(text: &str, max_chars: usize) -> String {
+    if text.chars().count() > max_chars {
+        format!("{}…", text.chars().take(max_chars).collect::<String>())
+    } else {
+        text.to_string()
+    }
+}
+
+/// "📌 Pinned" bar below the header: the latest pin while collapsed, the first few pins
+/// and a "See all" button while expanded. `None` when nothing is pinned.
+fn build_pinned_banner<'a>(state: &'a ChatAppState, group_id: &'a str) -> Option<Element<'a, Message>> {
+    let pins = state.pinned_messages.get(group_id).filter(|pins| !pins.is_empty())?;
+    let expanded = state.expanded_pinned_banners.contains(group_id);
+
+    let mut title = Row::new()
+        .spacing(8)
+        .align_items(Alignment::Center)
+        .push(Text::new("📌").font(EMOJI_FONT).size(12))
+        .push(Text::new(format!("Pinned ({})", pins.len())).font(BOLD_FONT).size(13).style(TEXT_PRIMARY));
+    if !expanded {
+        let latest = &pins[0].message;
+        title = title.push(
+            Text::new(format!("{}: {}", latest.sender, preview(&latest.content, PINNED_PREVIEW_CHARS)))
+                .size(13)
+                .style(TEXT_SECONDARY),
+        );
+    }
+    let title = title
+        .push(Space::new(Length::Fill, Length::Fixed(0.0)))
+        .push(
+            Button::new(Text::new(if expanded { "Hide" } else { "Show" }).size(12))
+                .on_press(Message::TogglePinnedBanner { group_id: group_id.to_string() })
+                .style(iced::theme::Button::Text)
+                .padding(0),
+        );
+
+    let mut section = Column::new().spacing(4).push(title);
+    if expanded {
+        for pin in pins.iter().take(PINNED_BANNER_MESSAGES) {
+            section = section.push(
+                Text::new(format!("{}: {}", pin.message.sender, preview(&pin.message.content, PINNED_PREVIEW_CHARS)))
+                    .size(13)
+                    .style(TEXT_SECONDARY),
+            );
+        }
+        section = section.push(
+            Button::new(Text::new("See all").size(12))
+                .on_press(Message::OpenPinnedMessages { group_id: group_id.to_string() })
+                .style(iced::theme::Button::Secondary)
+                .padding([4, 8]),
+        );
+    }
+
+    Some(
+        Container::new(section)
+            .padding([6, 16])
+            .width(Length::Fill)
+            .style(iced::theme::Container::Custom(Box::new(|_: &iced::Theme| {
+                iced::widget::container::Appearance {
+                    background: Some(iced::Background::Color(INPUT_BG)),
+                    ..Default::default()
+                }
+            })))
+            .into(),
+    )
+}
+
+/// Invite code bar below the header: expiry and use limit of the next code, then the code
+/// generated with a copy button. `None` unless the dialog is open on this group.
+fn build_invite_code_section<'a>(state: &'a ChatAppState, group_id: &'a str) -> Option<Element<'a, Message>> {
+    let dialog = state.invite_code_dialog.as_ref().filter(|d| d.group_id == group_id)?;
+
+    let mut options = Row::new()
+        .spacing(6)
+        .align_items(Alignment::Center)
+        .push(Text::new("🔗").font(EMOJI_FONT).size(12))
+        .push(Text::new("Expires:").size(12).style(TEXT_SECONDARY));
+    for (label, expires_secs) in INVITE_CODE_EXPIRIES {
+        options = options.push(
+            Button::new(Text::new(label).size(12))
+                .on_press(Message::InviteCodeExpiryChanged(expires_secs))
+                .style(if dialog.expires_secs == expires_secs { iced::theme::Button::Primary } else { iced::theme::Button::Secondary })
+                .padding([4, 8]),
+        );
+    }
+    let options = options
+        .push(Text::new("Max uses:").size(12).style(TEXT_SECONDARY))
+        .push(
+            TextInput::new("Unlimited", &dialog.max_uses)
+                .on_input(Message::InviteCodeMaxUsesChanged)
+                .on_submit(Message::GenerateInviteCode)
+                .padding(4)
+                .size(12)
+                .width(Length::Fixed(80.0)),
+        )
+        .push(Button::new(Text::new("Generate").size(12)).on_press(Message::GenerateInviteCode).style(iced::theme::Button::Primary).padding([4, 8]))
+        .push(Space::new(Length::Fill, Length::Fixed(0.0)))
+        .push(Button::new(Text::new("Close").size(12)).on_press(Message::CloseInviteCodeDialog).style(iced::theme::Button::Secondary).padding([4, 8]));
+
+    let mut section = Column::new().spacing(6).push(options);
+    if let Some(code) = &dialog.code {
+        section = section.push(
+            Row::new()
+                .spacing(8)
+                .align_items(Alignment::Center)
+                .push(Text::new("Share this code:").size(13).style(TEXT_SECONDARY))
+                .push(Text::new(code.clone()).font(BOLD_FONT).size(16).style(TEXT_PRIMARY))
+                .push(Button::new(Text::new("Copy").size(12)).on_press(Message::CopyInviteCode { code: code.clone() }).style(iced::theme::Button::Secondary).padding([4, 8])),
+        );
+    }
+
+    Some(
+        Container::new(section)
+            .padding([6, 16])
+            .width(Length::Fill)
+            .style(iced::theme::Container::Custom(Box::new(|_: &iced::Theme| {
+                iced::widget::container::Appearance {
+                    background: Some(iced::Background::Color(INPUT_BG)),
+                    ..Default::default()
+                }
+            })))
+            .into(),
+    )
+}
+
+/// Description bar below the header: the first characters with a toggle to show the rest,
+/// or an input while an admin edits it. `None` when there is nothing to show.
+fn build_description_section<'a>(state: &'a ChatAppState, group_id: &'a str, i_am_admin: bool) -> Option<Element<'a, Message>> {
+    let description = state.group_descriptions.get(group_id).map(String::as_str).unwrap_or("");
+    let row: Element<'a, Message> = if let Some(draft) = &state.group_description_draft {
+        let valid = draft.trim().chars().count() <= MAX_DESCRIPTION_CHARS;
+        let submit = valid.then(|| Message::SaveGroupDescription { group_id: group_id.to_string(), description: draft.clone() });
+        let mut input = TextInput::new("Describe this group", draft)
+            .on_input(Message::GroupDescriptionDraftChanged)
+            .padding(6)
+            .size(13)
+            .width(Length::Fill);
+        if let Some(submit) = submit.clone() {
+            input = input.on_submit(submit);
+        }
+        Row::new()
+            .spacing(6)
+            .align_items(Alignment::Center)
+            .push(input)
+            .push(Text::new(format!("{}/{}", draft.trim().chars().count(), MAX_DESCRIPTION_CHARS)).size(11).style(TEXT_SECONDARY))
+            .push(Button::new(Text::new("Save").size(12)).on_press_maybe(submit).style(iced::theme::Button::Primary).padding([4, 8]))
+            .push(Button::new(Text::new("Cancel").size(12)).on_press(Message::CancelEditGroupDescription).style(iced::theme::Button::Secondary).padding([4, 8]))
+            .into()
+    } else if !description.is_empty() {
+        let long = description.chars().count() > DESCRIPTION_PREVIEW_CHARS;
+        let expanded = state.expanded_group_descriptions.contains(group_id);
+        let shown = if long && !expanded {
+            format!("{}…", description.chars().take(DESCRIPTION_PREVIEW_CHARS).collect::<String>())
+        } else {
+            description.to_string()
+        };
+        let mut row = Row::new()
+            .spacing(8)
+            .align_items(Alignment::Center)
+            .push(Text::new("ℹ️").font(EMOJI_FONT).size(12))
+            .push(Text::new(shown).size(13).style(TEXT_SECONDARY).width(Length::Fill));
+        if long {
+            row = row.push(
+                Button::new(Text::new(if expanded { "Less" } else { "More" }).size(12))
+                    .on_press(Message::ToggleGroupDescription { group_id: group_id.to_string() })
+                    .style(iced::theme::Button::Text)
+                    .padding(0),
+            );
+        }
+        if i_am_admin {
+            row = row.push(
+                Button::new(Text::new("✏️").font(EMOJI_FONT).size(12))
+                    .on_press(Message::StartEditGroupDescription { current: description.to_string() })
+                    .style(iced::theme::Button::Text)
+                    .padding(0),
+            );
+        }
+        row.into()
+    } else if i_am_admin {
+        Button::new(Text::new("Add a description").size(12))
+            .on_press(Message::StartEditGroupDescription { current: String::new() })
+            .style(iced::theme::Button::Text)
+            .padding(0)
+            .into()
+    } else {
+        return None;
+    };
+
+    Some(
+        Container::new(row)
+            .padding([6, 16])
+            .width(Length::Fill)
+            .style(iced::theme::Container::Custom(Box::new(|_: &iced::Theme| {
+                iced::widget::container::Appearance {
+                    background: Some(iced::Background::Color(INPUT_BG)),
+                    ..Default::default()
+                }
+            })))
+            .into(),
+    )
+}
+
+fn build_members_sidebar<'a>(state: &'a ChatAppState, group_id: &'a str) -> Element<'a, Message> {
+    let mut members_column = Column::new()
+        .spacing(8)
+        .padding(12)
+        .push(Text::new("Members").font(BOLD_FONT).size(16).style(TEXT_PRIMARY));
+
+    match state.group_members.get(group_id) {
+        Some(members) => {
+            let i_am_admin = members.iter().any(|m| m.username == state.username && matches!(m.role.as_str(), "owner" | "admin"));
+            for member in members {
+                let role_icon = match member.role.as_str() {
+                    "owner" => "👑",
+                    "admin" => "⭐",
+                    _ => "👤",
+                };
+                let dot_color = if member.is_online { ONLINE_DOT } else { OFFLINE_DOT };
+                let mut row = Row::new()
+                    .spacing(6)
+                    .align_items(Alignment::Center)
+                    .push(Text::new("●").size(12).style(dot_color))
+                    .push(Text::new(role_icon).font(EMOJI_FONT).size(14))
+                    .push(Text::new(&member.username).size(14).style(TEXT_PRIMARY));
+                if member.role == "admin" {
+                    row = row.push(Text::new("Admin").size(10).style(TEXT_SECONDARY));
+                }
+                // Admins manage the other members' roles; the owner's role is fixed
+                if i_am_admin && member.username != state.username && member.role != "owner" {
+                    let promote = member.role == "member";
+                    row = row
+                        .push(Space::new(Length::Fill, Length::Fixed(0.0)))
+                        .push(
+                            Button::new(Text::new(if promote { "⬆" } else { "⬇" }).font(EMOJI_FONT).size(12))
+                                .on_press(Message::SetGroupAdmin { group_id: group_id.to_string(), username: member.username.clone(), admin: promote })
+                                .style(iced::theme::Button::Text)
+                                .padding(0),
+                        );
+                    // Only plain members can be kicked; an admin has to be demoted first
+                    if promote {
+                        row = row.push(
+                            Button::new(Text::new("Kick").size(10))
+                                .on_press(Message::KickMember { group_id: group_id.to_string(), username: member.username.clone() })
+                                .style(iced::theme::Button::Destructive)
+                                .padding([2, 4]),
+                        );
+                    }
+                }
+                members_column = members_column.push(row);
+            }
+        }
+        None => {
+            members_column = members_column.push(Text::new("Caricamento membri...").size(12).style(TEXT_SECONDARY));
+        }
+    }
+
+    Container::new(Scrollable::new(members_column))
+        .width(Length::Fixed(200.0))
+        .height(Length::Fill)
+        .style(iced::theme::Container::Custom(Box::new(|_: &iced::Theme| {
+            iced::widget::container::Appearance {
+                background: Some(iced::Background::Color(INPUT_BG)),
+                ..Default::default()
+            }
+        })))
+        .into()
+}
+
+fn edit_message_button<'a>(message_id: i64, content: &str) -> Element<'a, Message> {
+    Button::new(Text::new("✏️").font(EMOJI_FONT).size(10))
+        .on_press(Message::StartEditMessage { message_id, content: content.to_string() })
+        .style(iced::theme::Button::Text)
+        .padding(0)
+        .into()
+}
+
+fn delete_message_button<'a>(message_id: i64) -> Element<'a, Message> {
+    Button::new(Text::new("🗑️").font(EMOJI_FONT).size(10))
+        .on_press(Message::DeleteMessage { message_id })
+        .style(iced::theme::Button::Text)
+        .padding(0)
+        .into()
+}
+
+/// 📌 button pinning `message_id`, or unpinning it when `pinned`
+fn pin_message_button<'a>(group_id: &str, message_id: i64, pinned: bool) -> Element<'a, Message> {
+    Button::new(Text::new("📌").font(EMOJI_FONT).size(10))
+        .on_press(Message::SetMessagePinned { group_id: group_id.to_string(), message_id, pin: !pinned })
+        .style(if pinned { iced::theme::Button::Primary } else { iced::theme::Button::Text })
+        .padding(0)
+        .into()
+}
+
+/// `can_moderate`: the owner and admins may delete other members' messages too, and pin messages
+fn create_message_bubble<'a>(state: &'a ChatAppState, group_id: &str, msg: &'a crate::client::models::app_state::ChatMessage, is_my_message: bool, can_moderate: bool) -> Element<'a, Message> {
     let bubble_color = if is_my_message { MY_MESSAGE_BG } else { OTHER_MESSAGE_BG };
 
     // For group messages, show sender name if it's not my message
@@ -184,10 +636,41 @@ fn create_message_bubble(msg: &crate::client::models::app_state::ChatMessage, is
         message_content = message_content.push(header);
     }
     
+    let mut footer = Row::new()
+        .spacing(8)
+        .align_items(Alignment::Center)
+        .push(Text::new(&msg.formatted_time).size(10).style(TEXT_SECONDARY));
+    if msg.edited {
+        footer = footer.push(Text::new("✎ edited").size(10).style(TEXT_SECONDARY));
+    }
+    if let Some(message_id) = msg.message_id {
+        footer = footer
+            .push(reactions::picker_button(message_id))
+            .push(reply::reply_button(message_id))
+            .push(forward::forward_button(message_id));
+    }
+    if let (true, Some(message_id)) = (is_my_message, msg.message_id) {
+        footer = footer.push(edit_message_button(message_id, &msg.content));
+    }
+    if let (true, Some(message_id)) = (can_moderate, msg.message_id) {
+        let pinned = state.pinned_messages.get(group_id)
+            .is_some_and(|pins| pins.iter().any(|pin| pin.message.message_id == Some(message_id)));
+        footer = footer.push(pin_message_button(group_id, message_id, pinned));
+    }
+    if let (true, Some(message_id)) = (is_my_message || can_moderate, msg.message_id) {
+        footer = footer.push(delete_message_button(message_id));
+    }
+
+    if msg.forwarded_from_id.is_some() {
+        message_content = message_content.push(forward::forwarded_label());
+    }
     message_content = message_content
         .push(Text::new(&msg.content).size(14).style(TEXT_PRIMARY))
         .push(Space::new(Length::Fixed(0.0), Length::Fixed(4.0)))
-        .push(Text::new(&msg.formatted_time).size(10).style(TEXT_SECONDARY));
+        .push(footer);
+    if msg.seen_count > 0 {
+        message_content = message_content.push(Text::new(format!("Seen by {}", msg.seen_count)).size(10).style(TEXT_SECONDARY));
+    }
 
     let bubble = Container::new(message_content)
         .padding([8, 12])
@@ -210,7 +693,27 @@ fn create_message_bubble(msg: &crate::client::models::app_state::ChatMessage, is
         iced::alignment::Horizontal::Left 
     };
 
-    Container::new(bubble)
+    let mut bubble_column = Column::new().spacing(4);
+    if let Some(reply_to_id) = msg.reply_to_id {
+        bubble_column = bubble_column.push(reply::quote(reply_to_id, state.message_by_id(reply_to_id)));
+    }
+    bubble_column = bubble_column.push(bubble);
+    if let Some(bar) = reactions::view(msg, &state.username, state.reaction_picker.is_some() && state.reaction_picker == msg.message_id) {
+        bubble_column = bubble_column.push(bar);
+    }
+
+    // Received messages show the sender's avatar next to the bubble
+    let row = if is_my_message {
+        Row::new().push(bubble_column)
+    } else {
+        Row::new()
+            .push(avatar::view(&msg.sender, state.avatar_url_for(msg), &state.avatar_cache))
+            .push(bubble_column)
+            .spacing(8)
+            .align_items(Alignment::End)
+    };
+
+    Container::new(row)
         .width(Length::Fill)
         .align_x(alignment)
         .into()
@@ -221,9 +724,14 @@ fn build_input_area<'a>(state: &'a ChatAppState, group_id: &'a str) -> Element<'
     // desired background, border and radius without implementing a
     // custom `text_input::StyleSheet` trait. This keeps the style while
     // avoiding trait mismatch issues across iced versions.
+    // While an own message is being edited, submitting saves it instead of sending a new one
+    let submit = match state.editing_message {
+        Some(message_id) => Message::SubmitEditMessage { message_id },
+        None => Message::SendGroupMessage { group_id: group_id.to_string() },
+    };
     let raw_input = TextInput::new("Scrivi un messaggio al gruppo...", &state.current_message_input)
         .on_input(Message::MessageInputChanged)
-        .on_submit(Message::SendGroupMessage { group_id: group_id.to_string() })
+        .on_submit(submit.clone())
         .padding(12)
         .size(14)
         .width(Length::Fill);
@@ -243,8 +751,8 @@ fn build_input_area<'a>(state: &'a ChatAppState, group_id: &'a str) -> Element<'
             }
         })));
 
-    let send_button = Button::new(Text::new("Invia").size(14))
-        .on_press(Message::SendGroupMessage { group_id: group_id.to_string() })
+    let send_button = Button::new(Text::new(if state.editing_message.is_some() { "Salva" } else { "Invia" }).size(14))
+        .on_press(submit)
         .style(iced::theme::Button::Primary)
         .padding([12, 16]);
 
@@ -254,7 +762,35 @@ fn build_input_area<'a>(state: &'a ChatAppState, group_id: &'a str) -> Element<'
         .push(message_input)
         .push(send_button);
 
-    Container::new(input_row)
+    let mut input_column = Column::new().spacing(6);
+    if let Some(label) = state.typing_label(group_id) {
+        input_column = input_column.push(Text::new(label).size(12).style(TEXT_SECONDARY));
+    }
+    if let Some(picker) = forward::picker(state, TEXT_SECONDARY) {
+        input_column = input_column.push(picker);
+    }
+    if let Some(reply_to_id) = state.replying_to {
+        input_column = input_column.push(reply::composer_banner(state.message_by_id(reply_to_id), TEXT_SECONDARY));
+    }
+    if state.editing_message.is_some() {
+        input_column = input_column.push(
+            Row::new()
+                .spacing(8)
+                .align_items(Alignment::Center)
+                .push(Text::new("✏️").font(EMOJI_FONT).size(12))
+                .push(Text::new("Editing message").size(12).style(TEXT_SECONDARY))
+                .push(Space::with_width(Length::Fill))
+                .push(
+                    Button::new(Text::new("Cancel").size(12))
+                        .on_press(Message::CancelEditMessage)
+                        .style(iced::theme::Button::Text)
+                        .padding([2, 6])
+                )
+        );
+    }
+    let input_column = input_column.push(input_row);
+
+    Container::new(input_column)
         .padding([12, 16])
         .width(Length::Fill)
         .style(iced::theme::Container::Custom(Box::new(|_: &iced::Theme| {
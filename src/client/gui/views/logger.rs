@@ -13,7 +13,7 @@ impl iced::widget::container::StyleSheet for MyLogStyle {
     }
 }
 use iced::{Element, Font, Length};
-use iced::widget::{Row, Text};
+use iced::widget::{scrollable, Column, Row, Scrollable, Text};
 use iced::widget::Container;
 use iced::widget::container::Appearance;
 
@@ -50,42 +50,57 @@ impl LogMessage {
     }
 }
 
+/// Scrollable holding the log entries; `ChatApp::update` snaps it to the top on new entries
+pub fn logger_scroll_id() -> scrollable::Id {
+    scrollable::Id::new("logger_scroll")
+}
+
+/// Height of the log area; older entries are reached by scrolling
+const LOGGER_HEIGHT: f32 = 100.0;
+
 pub fn logger_view(messages: &[LogMessage]) -> Element<'_, crate::client::models::messages::Message> {
-    // Show only the latest message as an alert bar (single message at a time)
-    if let Some(log) = messages.iter().next_back() {
-        let log_color = log.color();
-        let bg_color = log_color;
-        Container::new(
-            Row::new()
-                .spacing(12)
-                .push(
-                    Text::new(log.emoji())
-                        .font(Font::with_name("Segoe UI Emoji"))
-                        .size(20)
-                        .style(iced::Color::WHITE)
-                )
-                .push(Text::new(&log.message).size(18).style(iced::Color::WHITE))
-        )
-        .padding([12, 16])
-        .width(Length::Fill)
-        .style(iced::theme::Container::Custom(Box::new(move |_: &iced::Theme| {
-            iced::widget::container::Appearance {
-                background: Some(iced::Background::Color(bg_color)),
-                text_color: Some(iced::Color::WHITE),
-                border: iced::Border {
-                    radius: 8.0.into(),
-                    ..Default::default()
-                },
-                shadow: iced::Shadow {
-                    offset: iced::Vector::new(0.0, 4.0),
-                    blur_radius: 12.0,
-                    color: iced::Color::from_rgba(0.0, 0.0, 0.0, 0.3),
-                },
-            }
-        })))
-        .into()
-    } else {
+    if messages.is_empty() {
         // Empty placeholder
-        iced::widget::Space::new(iced::Length::Fill, iced::Length::Fixed(0.0)).into()
+        return iced::widget::Space::new(iced::Length::Fill, iced::Length::Fixed(0.0)).into();
     }
+    // Newest first, so the top of the scrollable is always the latest entry
+    let entries = messages.iter().rev().fold(Column::new().spacing(6), |column, log| column.push(log_entry(log)));
+    Scrollable::new(entries)
+        .id(logger_scroll_id())
+        .width(Length::Fill)
+        .height(Length::Fixed(LOGGER_HEIGHT))
+        .into()
+}
+
+fn log_entry(log: &LogMessage) -> Element<'_, crate::client::models::messages::Message> {
+    let bg_color = log.color();
+    Container::new(
+        Row::new()
+            .spacing(12)
+            .push(
+                Text::new(log.emoji())
+                    .font(Font::with_name("Segoe UI Emoji"))
+                    .size(20)
+                    .style(iced::Color::WHITE)
+            )
+            .push(Text::new(&log.message).size(18).style(iced::Color::WHITE))
+    )
+    .padding([12, 16])
+    .width(Length::Fill)
+    .style(iced::theme::Container::Custom(Box::new(move |_: &iced::Theme| {
+        iced::widget::container::Appearance {
+            background: Some(iced::Background::Color(bg_color)),
+            text_color: Some(iced::Color::WHITE),
+            border: iced::Border {
+                radius: 8.0.into(),
+                ..Default::default()
+            },
+            shadow: iced::Shadow {
+                offset: iced::Vector::new(0.0, 4.0),
+                blur_radius: 12.0,
+                color: iced::Color::from_rgba(0.0, 0.0, 0.0, 0.3),
+            },
+        }
+    })))
+    .into()
 }
@@ -1,14 +1,16 @@
 use iced::{Element, Length, Alignment, Color, Font};
-use iced::widget::{Column, Row, Text, Button, Container, Space};
+use iced::widget::{Column, Row, Text, Button, Container, Space, TextInput};
 use crate::client::models::messages::Message;
-use crate::client::models::app_state::ChatAppState;
+use crate::client::models::app_state::{ChatAppState, ChatType, ConversationSummary};
 use crate::client::gui::views::logger::logger_view;
+use crate::client::services::friend_service::FriendRequest;
 
 // Modern color palette consistent with registration.rs
 const BG_MAIN: Color = Color::from_rgb(0.06, 0.07, 0.18); // Deep navy
 const CARD_BG: Color = Color::from_rgb(0.18, 0.19, 0.36); // Muted indigo for card bodies
 const INPUT_BG: Color = Color::from_rgb(0.12, 0.13, 0.26); // Input background
 const ACCENT_COLOR: Color = Color::from_rgb(0.0, 0.7, 0.3); // Green accent
+const ERROR_COLOR: Color = Color::from_rgb(0.9, 0.3, 0.3);
 const TEXT_PRIMARY: Color = Color::WHITE;
 const TEXT_SECONDARY: Color = Color::from_rgb(0.7, 0.7, 0.7);
 
@@ -125,7 +127,186 @@ fn action_card<'a>(icon: &'a str, title: &'a str, detail: &'a str, btn_label: &'
         .into()
 }
 
-pub fn view(state: &ChatAppState) -> Element<Message> {
+// Longest request message shown in the main screen badge before truncating
+const REQUEST_PREVIEW_CHARS: usize = 80;
+
+/// Longest status message the server accepts
+const MAX_STATUS_CHARS: usize = 100;
+
+fn badge_appearance(_: &iced::Theme) -> iced::widget::container::Appearance {
+    iced::widget::container::Appearance {
+        background: Some(iced::Background::Color(INPUT_BG)),
+        text_color: Some(TEXT_PRIMARY),
+        border: iced::Border {
+            width: 1.0,
+            color: ACCENT_COLOR,
+            radius: 12.0.into(),
+        },
+        ..Default::default()
+    }
+}
+
+fn preview(message: &str) -> String {
+    if message.chars().count() > REQUEST_PREVIEW_CHARS {
+        let cut: String = message.chars().take(REQUEST_PREVIEW_CHARS).collect();
+        format!("{}…", cut.trim_end())
+    } else {
+        message.to_string()
+    }
+}
+
+// Notification badge for pending friend requests, previewing the most recent one
+fn friend_requests_badge(requests: &[FriendRequest]) -> Option<Element<'_, Message>> {
+    let latest = requests.first()?;
+    let count = if requests.len() == 1 {
+        "1 friend request".to_string()
+    } else {
+        format!("{} friend requests", requests.len())
+    };
+    let message = if latest.message.is_empty() {
+        "No message provided".to_string()
+    } else {
+        preview(&latest.message)
+    };
+
+    let content = Row::new()
+        .spacing(12)
+        .align_items(Alignment::Center)
+        .push(Text::new("🔔").font(EMOJI_FONT).size(18))
+        .push(
+            Column::new()
+                .spacing(2)
+                .push(Text::new(count).font(BOLD_FONT).size(14).style(ACCENT_COLOR))
+                .push(Text::new(format!("{}: {}", latest.from_username, message)).size(12).style(TEXT_SECONDARY))
+        );
+
+    Some(
+        Button::new(
+            Container::new(content)
+                .padding([10, 14])
+                .width(Length::Fill)
+                .style(iced::theme::Container::Custom(Box::new(badge_appearance)))
+        )
+        .style(iced::theme::Button::Text)
+        .on_press(Message::OpenFriendRequests)
+        .padding(0)
+        .width(Length::Fill)
+        .into()
+    )
+}
+
+// Build one row of the "Recent" section
+fn recent_row(conversation: &ConversationSummary) -> Element<'_, Message> {
+    let (icon, open_msg) = match conversation.chat_type {
+        ChatType::Private => ("👤", Message::OpenPrivateChat(conversation.chat_id.clone())),
+        ChatType::Group => ("👥", Message::OpenGroupChat(conversation.chat_id.clone(), conversation.display_name.clone())),
+    };
+    let preview = match &conversation.last_message {
+        Some(msg) if msg.chars().count() > 40 => format!("{}…", msg.chars().take(40).collect::<String>()),
+        Some(msg) => msg.clone(),
+        None => String::new(),
+    };
+    let time = conversation
+        .last_timestamp
+        .map(crate::client::services::message_parser::format_timestamp)
+        .unwrap_or_default();
+
+    let mut row = Row::new()
+        .spacing(12)
+        .align_items(Alignment::Center)
+        .push(Text::new(icon).font(EMOJI_FONT).size(18))
+        .push(
+            Column::new()
+                .spacing(2)
+                .width(Length::Fill)
+                .push(Text::new(&conversation.display_name).font(BOLD_FONT).size(15).style(TEXT_PRIMARY))
+                .push(Text::new(preview).size(12).style(TEXT_SECONDARY))
+        )
+        .push(Text::new(time).size(12).style(TEXT_SECONDARY));
+    if conversation.unread > 0 {
+        row = row.push(
+            Container::new(Text::new(conversation.unread.to_string()).font(BOLD_FONT).size(12).style(TEXT_PRIMARY))
+                .padding([2, 8])
+                .style(iced::theme::Container::Custom(Box::new(|_: &iced::Theme| {
+                    iced::widget::container::Appearance {
+                        background: Some(iced::Background::Color(ACCENT_COLOR)),
+                        border: iced::Border {
+                            radius: 10.0.into(),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    }
+                })))
+        );
+    }
+
+    Button::new(row)
+        .style(iced::theme::Button::Text)
+        .on_press(open_msg)
+        .padding([8, 12])
+        .width(Length::Fill)
+        .into()
+}
+
+/// Our status message under the header, or its inline editor while `status_draft` is set
+fn status_row(state: &ChatAppState) -> Element<'_, Message> {
+    let current = state.user_statuses.get(&state.username).cloned().unwrap_or_default();
+    let Some(draft) = &state.status_draft else {
+        let label = if current.is_empty() { "Set a status".to_string() } else { current.clone() };
+        return Container::new(
+            Button::new(
+                Row::new()
+                    .spacing(8)
+                    .align_items(Alignment::Center)
+                    .push(Text::new("💬").font(EMOJI_FONT).size(14))
+                    .push(Text::new(label).size(14).style(TEXT_SECONDARY))
+                    .push(Text::new("✏️").font(EMOJI_FONT).size(12))
+            )
+            .style(iced::theme::Button::Text)
+            .on_press(Message::SetStatus(current))
+            .padding([4, 8])
+        )
+        .width(Length::Fill)
+        .center_x()
+        .into();
+    };
+
+    let length = draft.trim().chars().count();
+    let valid = length <= MAX_STATUS_CHARS;
+    let save = valid.then(|| Message::SaveStatus { status: draft.clone() });
+    let mut input = TextInput::new("What's on your mind?", draft)
+        .on_input(Message::SetStatus)
+        .padding(8)
+        .size(14)
+        .width(Length::Fixed(360.0));
+    if let Some(save) = save.clone() {
+        input = input.on_submit(save);
+    }
+    Container::new(
+        Row::new()
+            .spacing(8)
+            .align_items(Alignment::Center)
+            .push(input)
+            .push(Text::new(format!("{}/{}", length, MAX_STATUS_CHARS)).size(12).style(if valid { TEXT_SECONDARY } else { ERROR_COLOR }))
+            .push(
+                Button::new(Text::new("Save").size(14))
+                    .style(iced::theme::Button::Primary)
+                    .on_press_maybe(save)
+                    .padding([6, 12])
+            )
+            .push(
+                Button::new(Text::new("Cancel").size(14))
+                    .style(iced::theme::Button::Secondary)
+                    .on_press(Message::CancelStatus)
+                    .padding([6, 12])
+            )
+    )
+    .width(Length::Fill)
+    .center_x()
+    .into()
+}
+
+pub fn view(state: &ChatAppState) -> Element<'_, Message> {
     // Modern header with title and logout button
     let logout_button = Button::new(
         Container::new(
@@ -149,14 +330,56 @@ pub fn view(state: &ChatAppState) -> Element<Message> {
         .push(Text::new("Ruggine").font(BOLD_FONT).size(32).style(TEXT_PRIMARY))
         .push(Text::new("Secure Chat Platform").size(14).style(TEXT_SECONDARY));
 
+    // Account dropdown, same width as the logout button to keep the title centered
+    let account_button = Button::new(
+        Container::new(
+            Row::new()
+                .spacing(8)
+                .align_items(Alignment::Center)
+                .push(Text::new("⚙️").font(EMOJI_FONT).size(16))
+                .push(Text::new(if state.account_menu_open { "Account ▴" } else { "Account ▾" }).font(BOLD_FONT).size(14))
+        )
+        .width(Length::Fill)
+        .center_x()
+    )
+            .style(iced::theme::Button::Secondary)
+            .on_press(Message::ToggleAccountMenu)
+            .padding(12)
+            .width(Length::Fixed(100.0));
+
     let header_row = Row::new()
         .spacing(16)
         .align_items(Alignment::Center)
-        .push(Space::new(Length::Fixed(100.0), Length::Fixed(0.0))) // Balance space
+        .push(account_button)
         .push(Container::new(title_section).width(Length::Fill).center_x())
         .push(logout_button);
 
-    let header = Container::new(header_row)
+    let mut header_column = Column::new().spacing(12).push(header_row).push(status_row(state));
+    if state.account_menu_open {
+        let menu_item = |icon, label, message| {
+            Button::new(
+                Row::new()
+                    .spacing(8)
+                    .align_items(Alignment::Center)
+                    .push(Text::new(icon).font(EMOJI_FONT).size(14))
+                    .push(Text::new(label).size(14).style(TEXT_PRIMARY))
+            )
+            .style(iced::theme::Button::Text)
+            .on_press(message)
+            .padding([8, 12])
+        };
+        let account_menu = Container::new(
+            Column::new()
+                .push(menu_item("🔑", "Change Password", Message::OpenChangePassword))
+                .push(menu_item("🖥️", "Sessions", Message::OpenSessions))
+                .push(menu_item("⚠️", "Delete Account", Message::OpenDeleteAccount))
+        )
+        .padding(4)
+        .style(iced::theme::Container::Custom(Box::new(card_appearance)));
+        header_column = header_column.push(account_menu);
+    }
+
+    let header = Container::new(header_column)
         .padding([20, 24])
         .width(Length::Fill)
         .style(iced::theme::Container::Custom(Box::new(header_appearance)));
@@ -169,6 +392,13 @@ pub fn view(state: &ChatAppState) -> Element<Message> {
             .push(Text::new("👤").font(EMOJI_FONT).size(16).style(TEXT_SECONDARY))
             .push(Text::new("Logged in as:").size(14).style(TEXT_SECONDARY))
             .push(Text::new(&state.username).font(BOLD_FONT).size(14).style(ACCENT_COLOR))
+            .push(Space::new(Length::Fixed(12.0), Length::Fixed(0.0)))
+            .push(
+                Button::new(Text::new("🗄️ Archived").font(EMOJI_FONT).size(14))
+                    .style(iced::theme::Button::Text)
+                    .on_press(Message::OpenArchivedChats)
+                    .padding([2, 6])
+            )
     )
     .width(Length::Fill)
     .center_x()
@@ -211,14 +441,53 @@ pub fn view(state: &ChatAppState) -> Element<Message> {
         Some(("Send Friend Request", Message::OpenSendFriendRequest))
     );
 
+    let settings_card = action_card(
+        "⚙️",
+        "Settings",
+        "Privacy and account settings",
+        "Blocked Users",
+        Message::OpenBlockedUsers,
+        None
+    );
+
+    // Recent conversations, newest first
+    let recent_section: Element<Message> = if state.recent_conversations.is_empty() {
+        Space::new(Length::Fill, Length::Fixed(0.0)).into()
+    } else {
+        let rows = state
+            .recent_conversations
+            .iter()
+            .fold(Column::new().spacing(4), |col, c| col.push(recent_row(c)));
+        Container::new(
+            Column::new()
+                .spacing(8)
+                .push(Text::new("Recent").font(BOLD_FONT).size(20).style(TEXT_PRIMARY))
+                .push(
+                    iced::widget::scrollable(rows)
+                        .width(Length::Fill)
+                        .height(Length::Fixed(220.0))
+                )
+        )
+        .padding(16)
+        .width(Length::Fill)
+        .style(iced::theme::Container::Custom(Box::new(card_appearance)))
+        .into()
+    };
+
     // Cards container with proper spacing
-    let cards_container = Column::new()
+    let mut cards_container = Column::new()
         .spacing(20)
-        .padding([0, 24])
+        .padding([0, 24]);
+    if let Some(badge) = friend_requests_badge(&state.friend_requests) {
+        cards_container = cards_container.push(badge);
+    }
+    let cards_container = cards_container
+        .push(recent_section)
         .push(users_card)
         .push(groups_card)
         .push(invites_card)
-        .push(friends_card);
+        .push(friends_card)
+        .push(settings_card);
 
     // Top logger bar
     let logger_bar: Element<Message> = if !state.logger.is_empty() {
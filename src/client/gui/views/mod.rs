@@ -10,4 +10,14 @@ pub mod my_groups;
 pub mod invite_to_group;
 pub mod my_group_invites;
 pub mod send_friend_request;
-pub mod view_friends;
\ No newline at end of file
+pub mod view_friends;
+pub mod blocked_users;
+pub mod search_groups;
+pub mod discover_groups;
+pub mod search_results;
+pub mod pinned_messages;
+pub mod archived_chats;
+pub mod change_password;
+pub mod delete_account;
+pub mod sessions;
+pub mod confirm_dialog;
\ No newline at end of file
@@ -2,6 +2,7 @@ use iced::{Element, Length, Alignment, Color, Font};
 use iced::widget::{Column, Row, Text, Button, Container, Space, Scrollable};
 use crate::client::models::messages::Message;
 use crate::client::models::app_state::ChatAppState;
+use crate::client::services::group_service::GroupInvite;
 use crate::client::gui::views::logger::logger_view;
 
 // Modern color palette consistent with other views
@@ -87,7 +88,19 @@ fn empty_state_appearance(_: &iced::Theme) -> iced::widget::container::Appearanc
     }
 }
 
-pub fn view(state: &ChatAppState) -> Element<Message> {
+/// Local date and time at which an invite expires
+fn format_expiry(timestamp: i64) -> String {
+    use chrono::{DateTime, Local, TimeZone, Utc};
+    match Utc.timestamp_opt(timestamp, 0).single() {
+        Some(dt) => {
+            let local: DateTime<Local> = dt.with_timezone(&Local);
+            local.format("%d/%m %H:%M").to_string()
+        }
+        None => "??".to_string(),
+    }
+}
+
+pub fn view(state: &ChatAppState) -> Element<'_, Message> {
     // Top logger bar
     let logger_bar = if !state.logger.is_empty() {
         Container::new(logger_view(&state.logger))
@@ -172,7 +185,7 @@ pub fn view(state: &ChatAppState) -> Element<Message> {
         // Invites list
         let mut invites_column = Column::new().spacing(12);
         
-        for (invite_id, group_name, invited_by) in &state.my_group_invites {
+        for GroupInvite { invite_id, group_name, invited_by, expires_at } in &state.my_group_invites {
             let invite_item = Container::new(
                 Row::new()
                     .spacing(16)
@@ -202,6 +215,11 @@ pub fn view(state: &ChatAppState) -> Element<Message> {
                                     .size(12)
                                     .style(TEXT_SECONDARY)
                             )
+                            .push_maybe(expires_at.map(|ts| {
+                                Text::new(format!("Expires {}", format_expiry(ts)))
+                                    .size(12)
+                                    .style(TEXT_SECONDARY)
+                            }))
                     )
                     .push(Space::new(Length::Fill, Length::Fixed(0.0)))
                     .push(
@@ -86,7 +86,7 @@ fn empty_state_appearance(_: &iced::Theme) -> iced::widget::container::Appearanc
     }
 }
 
-pub fn view(state: &ChatAppState) -> Element<Message> {
+pub fn view(state: &ChatAppState) -> Element<'_, Message> {
     // Modern header with back button and title
     let back_button = Button::new(
         Container::new(
@@ -132,11 +132,28 @@ pub fn view(state: &ChatAppState) -> Element<Message> {
     .padding(12)
     .width(Length::Fixed(100.0));
 
+    let find_button = Button::new(
+        Container::new(
+            Row::new()
+                .spacing(8)
+                .align_items(Alignment::Center)
+                .push(Text::new("🔍").font(EMOJI_FONT).size(16))
+                .push(Text::new("Find").font(BOLD_FONT).size(14))
+        )
+        .width(Length::Fill)
+        .center_x()
+    )
+    .style(iced::theme::Button::Secondary)
+    .on_press(Message::OpenDiscoverGroups)
+    .padding(12)
+    .width(Length::Fixed(100.0));
+
     let header_row = Row::new()
         .spacing(16)
         .align_items(Alignment::Center)
         .push(back_button)
         .push(Container::new(title_section).width(Length::Fill).center_x())
+        .push(find_button)
         .push(create_button);
 
     let header = Container::new(header_row)
@@ -195,7 +212,8 @@ pub fn view(state: &ChatAppState) -> Element<Message> {
         // Groups list
         let mut groups_column = Column::new().spacing(12);
         
-        for (group_id, group_name, _member_count) in &state.my_groups {
+        for group in &state.my_groups {
+            let (group_id, group_name) = (&group.id, &group.name);
             let group_item = Container::new(
                 Row::new()
                     .spacing(16)
@@ -234,7 +252,7 @@ pub fn view(state: &ChatAppState) -> Element<Message> {
                                     .center_x()
                                 )
                                 .style(iced::theme::Button::Destructive)
-                                .on_press(Message::LeaveGroup { group_id: group_id.clone(), group_name: group_name.clone() })
+                                .on_press(Message::ConfirmLeaveGroup { group_id: group_id.clone(), group_name: group_name.clone() })
                                 .padding(8)
                                 .width(Length::Fixed(40.0))
                             )
@@ -0,0 +1,208 @@
+use iced::{Element, Length, Alignment, Color, Font};
+use iced::widget::{Column, Row, Text, Button, Container, Space, Scrollable};
+use crate::client::models::messages::Message;
+use crate::client::models::app_state::ChatAppState;
+use crate::client::services::message_parser::{format_timestamp, relative_time, PinnedMessage};
+use crate::client::gui::views::logger::logger_view;
+
+// Modern color palette consistent with other views
+const BG_MAIN: Color = Color::from_rgb(0.06, 0.07, 0.18);
+const CARD_BG: Color = Color::from_rgb(0.18, 0.19, 0.36);
+const INPUT_BG: Color = Color::from_rgb(0.12, 0.13, 0.26);
+const TEXT_PRIMARY: Color = Color::WHITE;
+const TEXT_SECONDARY: Color = Color::from_rgb(0.7, 0.7, 0.7);
+
+const EMOJI_FONT: Font = Font::with_name("Segoe UI Emoji");
+const BOLD_FONT: Font = Font {
+    family: iced::font::Family::SansSerif,
+    weight: iced::font::Weight::Bold,
+    ..Font::DEFAULT
+};
+
+// Custom container styles
+fn bg_main_appearance(_: &iced::Theme) -> iced::widget::container::Appearance {
+    iced::widget::container::Appearance {
+        background: Some(iced::Background::Color(BG_MAIN)),
+        text_color: Some(TEXT_PRIMARY),
+        ..Default::default()
+    }
+}
+
+fn header_appearance(_: &iced::Theme) -> iced::widget::container::Appearance {
+    iced::widget::container::Appearance {
+        background: Some(iced::Background::Color(INPUT_BG)),
+        text_color: Some(TEXT_PRIMARY),
+        shadow: iced::Shadow {
+            offset: iced::Vector::new(0.0, 2.0),
+            blur_radius: 8.0,
+            color: Color::from_rgba(0.0, 0.0, 0.0, 0.2),
+        },
+        ..Default::default()
+    }
+}
+
+fn pin_item_appearance(_: &iced::Theme) -> iced::widget::container::Appearance {
+    iced::widget::container::Appearance {
+        background: Some(iced::Background::Color(CARD_BG)),
+        text_color: Some(TEXT_PRIMARY),
+        border: iced::Border {
+            width: 1.0,
+            color: Color::from_rgb(0.2, 0.2, 0.3),
+            radius: 12.0.into(),
+        },
+        ..Default::default()
+    }
+}
+
+/// One pin: sender and time, the message, then who pinned it, with Unpin for admins
+fn pin_item<'a>(group_id: &str, pin: &PinnedMessage, can_unpin: bool) -> Element<'a, Message> {
+    let mut item = Column::new()
+        .spacing(6)
+        .width(Length::Fill)
+        .push(
+            Row::new()
+                .spacing(8)
+                .align_items(Alignment::Center)
+                .push(Text::new(pin.message.sender.clone()).font(BOLD_FONT).size(14).style(TEXT_PRIMARY))
+                .push(Text::new(format_timestamp(pin.message.sent_at)).size(11).style(TEXT_SECONDARY))
+        )
+        .push(Text::new(pin.message.content.clone()).size(14).style(TEXT_PRIMARY))
+        .push(
+            Row::new()
+                .spacing(4)
+                .align_items(Alignment::Center)
+                .push(Text::new("📌").font(EMOJI_FONT).size(11))
+                .push(Text::new(format!("Pinned by {} · {}", pin.pinned_by, relative_time(pin.pinned_at))).size(11).style(TEXT_SECONDARY))
+        );
+    if pin.message.edited {
+        item = item.push(Text::new("✎ edited").size(10).style(TEXT_SECONDARY));
+    }
+
+    let mut row = Row::new()
+        .spacing(16)
+        .align_items(Alignment::Center)
+        .push(item);
+    if let (true, Some(message_id)) = (can_unpin, pin.message.message_id) {
+        row = row.push(
+            Button::new(
+                Container::new(Text::new("Unpin").font(BOLD_FONT).size(12))
+                    .width(Length::Fill)
+                    .center_x()
+            )
+            .style(iced::theme::Button::Destructive)
+            .on_press(Message::SetMessagePinned { group_id: group_id.to_string(), message_id, pin: false })
+            .padding(10)
+            .width(Length::Fixed(100.0)),
+        );
+    }
+
+    Container::new(row)
+        .padding(16)
+        .width(Length::Fill)
+        .style(iced::theme::Container::Custom(Box::new(pin_item_appearance)))
+        .into()
+}
+
+pub fn view<'a>(state: &'a ChatAppState, group_id: &'a str) -> Element<'a, Message> {
+    let group_name = state.my_groups.iter()
+        .find(|g| g.id == group_id)
+        .map(|g| g.name.clone())
+        .unwrap_or_else(|| "Group".to_string());
+    let can_unpin = state.group_members.get(group_id).is_some_and(|members| {
+        members.iter().any(|m| m.username == state.username && matches!(m.role.as_str(), "owner" | "admin"))
+    });
+
+    // Top logger bar
+    let logger_bar = if !state.logger.is_empty() {
+        Container::new(logger_view(&state.logger))
+            .width(Length::Fill)
+            .padding([8, 12, 0, 12])
+    } else {
+        Container::new(Space::new(Length::Fill, Length::Fixed(0.0)))
+            .width(Length::Fill)
+    };
+
+    // Header with back button and title
+    let back_button = Button::new(
+        Container::new(
+            Row::new()
+                .spacing(8)
+                .align_items(Alignment::Center)
+                .push(Text::new("←").font(EMOJI_FONT).size(18))
+                .push(Text::new("Back").font(BOLD_FONT).size(14))
+        )
+        .width(Length::Fill)
+        .center_x()
+    )
+    .style(iced::theme::Button::Secondary)
+    .on_press(Message::OpenGroupChat(group_id.to_string(), group_name.clone()))
+    .padding(12)
+    .width(Length::Fixed(100.0));
+
+    let title_section = Column::new()
+        .spacing(4)
+        .align_items(Alignment::Center)
+        .push(
+            Row::new()
+                .spacing(8)
+                .align_items(Alignment::Center)
+                .push(Text::new("📌").font(EMOJI_FONT).size(24))
+                .push(Text::new("Pinned Messages").font(BOLD_FONT).size(24).style(TEXT_PRIMARY))
+        )
+        .push(Text::new(group_name).size(14).style(TEXT_SECONDARY));
+
+    let header_row = Row::new()
+        .spacing(16)
+        .align_items(Alignment::Center)
+        .push(back_button)
+        .push(Container::new(title_section).width(Length::Fill).center_x())
+        .push(Space::new(Length::Fixed(100.0), Length::Fixed(0.0))); // Balance space
+
+    let header = Container::new(header_row)
+        .padding([20, 24])
+        .width(Length::Fill)
+        .style(iced::theme::Container::Custom(Box::new(header_appearance)));
+
+    // Pins, most recent first
+    let content: Element<Message> = match state.pinned_messages.get(group_id) {
+        None => Container::new(Text::new("Loading pinned messages...").font(BOLD_FONT).size(16).style(TEXT_SECONDARY))
+            .width(Length::Fill)
+            .center_x()
+            .padding(40)
+            .into(),
+        Some(pins) if pins.is_empty() => Container::new(Text::new("No pinned messages").size(14).style(TEXT_SECONDARY))
+            .width(Length::Fill)
+            .center_x()
+            .padding(40)
+            .into(),
+        Some(pins) => {
+            let pins = pins.iter()
+                .fold(Column::new().spacing(12), |column, pin| column.push(pin_item(group_id, pin, can_unpin)));
+            Container::new(Scrollable::new(pins).width(Length::Fill).height(Length::Fill))
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .padding([0, 24])
+                .into()
+        }
+    };
+
+    let main_content = Column::new()
+        .push(header)
+        .push(Space::new(Length::Fill, Length::Fixed(16.0)))
+        .push(content)
+        .push(Space::new(Length::Fill, Length::Fixed(24.0)))
+        .width(Length::Fill)
+        .height(Length::Fill);
+
+    let final_content = Column::new()
+        .push(logger_bar)
+        .push(main_content)
+        .width(Length::Fill)
+        .height(Length::Fill);
+
+    Container::new(final_content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(iced::theme::Container::Custom(Box::new(bg_main_appearance)))
+        .into()
+}
@@ -1,7 +1,9 @@
 use iced::{Element, Length, Alignment, Color, Font};
 use iced::widget::{Column, Row, Text, TextInput, Button, Container, Scrollable, Space, scrollable};
 use crate::client::models::messages::Message;
-use crate::client::models::app_state::{ChatAppState};
+use crate::client::models::app_state::{ChatAppState, ChatType};
+use crate::client::gui::widgets::message_list::{inject_date_separators, date_separator, ChatViewItem};
+use crate::client::gui::widgets::{avatar, forward, reactions, reply};
 
 // Color palette per chat moderna (WhatsApp-like)
 const BG_MAIN: Color = Color::from_rgb(0.06, 0.07, 0.18); // Deep navy
@@ -11,6 +13,12 @@ const OTHER_MESSAGE_BG: Color = Color::from_rgb(0.2, 0.4, 0.8); // Blue for rece
 const INPUT_BG: Color = Color::from_rgb(0.12, 0.13, 0.26); // Input background
 const TEXT_PRIMARY: Color = Color::WHITE;
 const TEXT_SECONDARY: Color = Color::from_rgb(0.7, 0.7, 0.7);
+const READ_CHECK_COLOR: Color = Color::from_rgb(0.55, 0.85, 1.0); // Light blue, readable on the green bubble
+
+// Bubbles grow with their text between these widths (padding included)
+const BUBBLE_MAX_WIDTH: f32 = 600.0;
+const BUBBLE_MIN_WIDTH: f32 = 80.0;
+const BUBBLE_PADDING_X: u16 = 12;
 
 const BOLD_FONT: Font = Font {
     family: iced::font::Family::SansSerif,
@@ -36,6 +44,18 @@ pub fn view<'a>(state: &'a ChatAppState, username: &'a str) -> Element<'a, Messa
         .on_press(Message::DiscardPrivateMessages { with: username.to_string() })
         .style(iced::theme::Button::Destructive)
         .padding(8);
+    let archive_btn = Button::new(Text::new("🗄️").font(EMOJI_FONT).size(16))
+        .on_press(Message::ArchiveChat { chat_type: ChatType::Private, chat_id: username.to_string() })
+        .style(iced::theme::Button::Secondary)
+        .padding(8);
+    let search_btn = Button::new(Text::new("🔍").font(EMOJI_FONT).size(16))
+        .on_press(Message::OpenMessageSearch)
+        .style(iced::theme::Button::Secondary)
+        .padding(8);
+    let export_btn = Button::new(Text::new("💾").font(EMOJI_FONT).size(16))
+        .on_press(Message::ExportChat { chat_type: ChatType::Private, chat_id: username.to_string() })
+        .style(iced::theme::Button::Secondary)
+        .padding(8);
     let header = Container::new(
         Row::new()
             .spacing(12)
@@ -43,6 +63,9 @@ pub fn view<'a>(state: &'a ChatAppState, username: &'a str) -> Element<'a, Messa
             .push(back_btn)
             .push(user_info)
             .push(Space::new(Length::Fill, Length::Fixed(0.0)))
+            .push(search_btn)
+            .push(export_btn)
+            .push(archive_btn)
             .push(discard_btn)
     )
     .padding([12, 16])
@@ -88,7 +111,7 @@ fn build_messages_area<'a>(state: &'a ChatAppState, username: &'a str) -> Elemen
     // Show cached messages or appropriate placeholder
     if let Some(chat_messages) = state.private_chats.get(username) {
         // Only print count, not individual messages to reduce spam
-        if chat_messages.len() > 0 {
+        if !chat_messages.is_empty() {
             // println!("[PRIVATE_CHAT_VIEW] Found {} cached messages for {}", chat_messages.len(), username);
         }
         if chat_messages.is_empty() {
@@ -103,11 +126,23 @@ fn build_messages_area<'a>(state: &'a ChatAppState, username: &'a str) -> Elemen
                 .padding(20)
             );
         } else {
-            for (i, msg) in chat_messages.iter().enumerate() {
-                // println!("[PRIVATE_CHAT_VIEW] Message {}: {} -> {}", i, msg.sender, msg.content);
-                let is_my_message = msg.sender == state.username;
-                let message_bubble = create_message_bubble(msg, is_my_message);
-                messages_column = messages_column.push(message_bubble);
+            if state.loading_older_messages.contains(username) {
+                messages_column = messages_column.push(
+                    Container::new(Text::new("Loading older messages...").size(12).style(TEXT_SECONDARY))
+                        .width(Length::Fill)
+                        .center_x()
+                );
+            }
+            for item in inject_date_separators(chat_messages) {
+                match item {
+                    ChatViewItem::DateSeparator(label) => {
+                        messages_column = messages_column.push(date_separator(label));
+                    }
+                    ChatViewItem::Message(msg) => {
+                        let is_my_message = msg.sender == state.username;
+                        messages_column = messages_column.push(create_message_bubble(state, msg, is_my_message, state.private_last_read.get(username).copied()));
+                    }
+                }
             }
         }
     } else if state.loading_private_chats.contains(username) {
@@ -142,12 +177,40 @@ fn build_messages_area<'a>(state: &'a ChatAppState, username: &'a str) -> Elemen
     messages_column = messages_column.push(Space::new(Length::Fixed(0.0), Length::Fixed(20.0)));
 
     // Scrollable container per i messaggi
+    // Reaching the top pages in older messages while the server has more
+    let chat_id = username.to_string();
+    let load_older_before = state.private_chats.get(username)
+        .and_then(|msgs| msgs.first())
+        .map(|oldest| oldest.timestamp)
+        .filter(|_| state.private_has_more.get(username).copied().unwrap_or(false) && !state.loading_older_messages.contains(username));
     let scrollable_messages = Scrollable::new(messages_column)
             .width(Length::Fill)
             .height(Length::Fill)
-            .id(scrollable::Id::new("messages_scroll"));
+            .id(scrollable::Id::new("messages_scroll"))
+            .on_scroll(move |viewport| {
+                let offset = viewport.relative_offset();
+                match load_older_before {
+                    Some(before_ts) if offset.y <= 0.0 => Message::LoadPrivateMessagesPage { with: chat_id.clone(), before_ts },
+                    _ => Message::MessagesScrolled { chat_id: chat_id.clone(), offset },
+                }
+            });
 
-    Container::new(scrollable_messages)
+    let mut messages_area = Column::new().push(scrollable_messages);
+    // Messages arrived while the user was reading older ones
+    if state.unseen_new_messages.contains(username) {
+        let new_messages_btn = Button::new(Text::new("↓ New messages").font(BOLD_FONT).size(13))
+            .on_press(Message::ScrollToLatest { chat_id: username.to_string() })
+            .style(iced::theme::Button::Primary)
+            .padding([6, 14]);
+        messages_area = messages_area.push(
+            Container::new(new_messages_btn)
+                .width(Length::Fill)
+                .center_x()
+                .padding([0, 0, 8, 0])
+        );
+    }
+
+    Container::new(messages_area)
     .width(Length::Fill)
     .height(Length::Fill)
     .style(iced::theme::Container::Custom(Box::new(|_: &iced::Theme| {
@@ -159,17 +222,68 @@ fn build_messages_area<'a>(state: &'a ChatAppState, username: &'a str) -> Elemen
     .into()
 }
 
-fn create_message_bubble(msg: &crate::client::models::app_state::ChatMessage, is_my_message: bool) -> Element<'_, Message> {
+fn edit_message_button<'a>(message_id: i64, content: &str) -> Element<'a, Message> {
+    Button::new(Text::new("✏️").font(EMOJI_FONT).size(10))
+        .on_press(Message::StartEditMessage { message_id, content: content.to_string() })
+        .style(iced::theme::Button::Text)
+        .padding(0)
+        .into()
+}
+
+fn delete_message_button<'a>(message_id: i64) -> Element<'a, Message> {
+    Button::new(Text::new("🗑️").font(EMOJI_FONT).size(10))
+        .on_press(Message::DeleteMessage { message_id })
+        .style(iced::theme::Button::Text)
+        .padding(0)
+        .into()
+}
+
+/// `recipient_last_read`: how far the other user has read the chat, for the ✓ / ✓✓ marks
+fn create_message_bubble<'a>(state: &'a ChatAppState, msg: &'a crate::client::models::app_state::ChatMessage, is_my_message: bool, recipient_last_read: Option<i64>) -> Element<'a, Message> {
     let bubble_color = if is_my_message { MY_MESSAGE_BG } else { OTHER_MESSAGE_BG };
 
-    let message_content = Column::new()
+    // Own messages the server has assigned an id to can be edited or deleted for everyone
+    let mut footer = Row::new()
+        .spacing(8)
+        .align_items(Alignment::Center)
+        .push(Text::new(&msg.formatted_time).size(10).style(TEXT_SECONDARY));
+    if msg.edited {
+        footer = footer.push(Text::new("✎ edited").size(10).style(TEXT_SECONDARY));
+    }
+    if is_my_message && !msg.is_pending {
+        let read = recipient_last_read.is_some_and(|last_read| msg.sent_at <= last_read);
+        footer = footer.push(if read {
+            Text::new("✓✓").size(10).style(READ_CHECK_COLOR)
+        } else {
+            Text::new("✓").size(10).style(TEXT_SECONDARY)
+        });
+    }
+    if let Some(message_id) = msg.message_id {
+        footer = footer
+            .push(reactions::picker_button(message_id))
+            .push(reply::reply_button(message_id))
+            .push(forward::forward_button(message_id));
+    }
+    if let (true, Some(message_id)) = (is_my_message, msg.message_id) {
+        footer = footer
+            .push(edit_message_button(message_id, &msg.content))
+            .push(delete_message_button(message_id));
+    }
+
+    let mut message_content = Column::new();
+    if msg.forwarded_from_id.is_some() {
+        message_content = message_content.push(forward::forwarded_label());
+    }
+    let message_content = message_content
         .push(Text::new(&msg.content).size(14).style(TEXT_PRIMARY))
         .push(Space::new(Length::Fixed(0.0), Length::Fixed(4.0)))
-        .push(Text::new(&msg.formatted_time).size(10).style(TEXT_SECONDARY))
+        .push(footer)
+        // Keeps very short messages (e.g. "ok") from collapsing
+        .push(Space::with_width(Length::Fixed(BUBBLE_MIN_WIDTH - 2.0 * BUBBLE_PADDING_X as f32)))
         .spacing(2);
 
     let bubble = Container::new(message_content)
-        .padding([8, 12])
+        .padding([8, BUBBLE_PADDING_X])
         .style(iced::theme::Container::Custom(Box::new(move |_: &iced::Theme| {
             iced::widget::container::Appearance {
                 background: Some(iced::Background::Color(bubble_color)),
@@ -180,19 +294,33 @@ fn create_message_bubble(msg: &crate::client::models::app_state::ChatMessage, is
                 ..Default::default()
             }
         })))
-        .width(Length::Fixed(280.0));
+        .max_width(BUBBLE_MAX_WIDTH);
+
+    let mut bubble_column = Column::new().spacing(4);
+    if let Some(reply_to_id) = msg.reply_to_id {
+        bubble_column = bubble_column.push(reply::quote(reply_to_id, state.message_by_id(reply_to_id)));
+    }
+    bubble_column = bubble_column.push(bubble);
+    if let Some(bar) = reactions::view(msg, &state.username, state.reaction_picker.is_some() && state.reaction_picker == msg.message_id) {
+        bubble_column = bubble_column.push(bar);
+    }
 
-    // Create alignment container
-    let alignment = if is_my_message { 
-        iced::alignment::Horizontal::Right 
-    } else { 
-        iced::alignment::Horizontal::Left 
+    // The spacer pushes sent bubbles to the right and received ones to the left;
+    // received messages also show the sender's avatar next to the bubble
+    let row = if is_my_message {
+        Row::new()
+            .push(Space::with_width(Length::Fill))
+            .push(bubble_column)
+    } else {
+        Row::new()
+            .push(avatar::view(&msg.sender, state.avatar_url_for(msg), &state.avatar_cache))
+            .push(bubble_column)
+            .push(Space::with_width(Length::Fill))
+            .spacing(8)
+            .align_items(Alignment::End)
     };
 
-    Container::new(bubble)
-        .width(Length::Fill)
-        .align_x(alignment)
-        .into()
+    row.width(Length::Fill).into()
 }
 
 fn build_input_area<'a>(state: &'a ChatAppState, username: &'a str) -> Element<'a, Message> {
@@ -200,9 +328,14 @@ fn build_input_area<'a>(state: &'a ChatAppState, username: &'a str) -> Element<'
     // desired background, border and radius without implementing a
     // custom `text_input::StyleSheet` trait. This keeps the style while
     // avoiding trait mismatch issues across iced versions.
+    // While an own message is being edited, submitting saves it instead of sending a new one
+    let submit = match state.editing_message {
+        Some(message_id) => Message::SubmitEditMessage { message_id },
+        None => Message::SendPrivateMessage { to: username.to_string() },
+    };
     let raw_input = TextInput::new("Scrivi un messaggio...", &state.current_message_input)
         .on_input(Message::MessageInputChanged)
-        .on_submit(Message::SendPrivateMessage { to: username.to_string() })
+        .on_submit(submit.clone())
         .padding(12)
         .size(14)
         .width(Length::Fill);
@@ -222,8 +355,8 @@ fn build_input_area<'a>(state: &'a ChatAppState, username: &'a str) -> Element<'
             }
         })));
 
-    let send_button = Button::new(Text::new("Invia").size(14))
-        .on_press(Message::SendPrivateMessage { to: username.to_string() })
+    let send_button = Button::new(Text::new(if state.editing_message.is_some() { "Salva" } else { "Invia" }).size(14))
+        .on_press(submit)
         .style(iced::theme::Button::Primary)
         .padding([12, 16]);
 
@@ -233,7 +366,35 @@ fn build_input_area<'a>(state: &'a ChatAppState, username: &'a str) -> Element<'
         .push(message_input)
         .push(send_button);
 
-    Container::new(input_row)
+    let mut input_column = Column::new().spacing(6);
+    if let Some(label) = state.typing_label(username) {
+        input_column = input_column.push(Text::new(label).size(12).style(TEXT_SECONDARY));
+    }
+    if let Some(picker) = forward::picker(state, TEXT_SECONDARY) {
+        input_column = input_column.push(picker);
+    }
+    if let Some(reply_to_id) = state.replying_to {
+        input_column = input_column.push(reply::composer_banner(state.message_by_id(reply_to_id), TEXT_SECONDARY));
+    }
+    if state.editing_message.is_some() {
+        input_column = input_column.push(
+            Row::new()
+                .spacing(8)
+                .align_items(Alignment::Center)
+                .push(Text::new("✏️").font(EMOJI_FONT).size(12))
+                .push(Text::new("Editing message").size(12).style(TEXT_SECONDARY))
+                .push(Space::with_width(Length::Fill))
+                .push(
+                    Button::new(Text::new("Cancel").size(12))
+                        .on_press(Message::CancelEditMessage)
+                        .style(iced::theme::Button::Text)
+                        .padding([2, 6])
+                )
+        );
+    }
+    let input_column = input_column.push(input_row);
+
+    Container::new(input_column)
         .padding([12, 16])
         .width(Length::Fill)
         .style(iced::theme::Container::Custom(Box::new(|_: &iced::Theme| {
@@ -3,6 +3,18 @@ use iced::widget::{Column, Row, Text, TextInput, Button, PickList, Container, Sp
 use crate::client::models::messages::Message;
 use crate::client::models::app_state::ChatAppState;
 use crate::client::gui::views::logger::logger_view;
+use crate::common::protocol::password_error;
+
+/// Submit on Enter only while the form is valid; a disabled input simply has no `on_submit`
+trait SubmitLoginIf {
+    fn submit_login_if(self, enabled: bool) -> Self;
+}
+
+impl SubmitLoginIf for TextInput<'_, Message> {
+    fn submit_login_if(self, enabled: bool) -> Self {
+        if enabled { self.on_submit(Message::SubmitLoginOrRegister) } else { self }
+    }
+}
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum HostType {
@@ -38,6 +50,8 @@ const INPUT_BG: Color = Color::from_rgb(0.12, 0.13, 0.26); // Input background
 const ACCENT_COLOR: Color = Color::from_rgb(0.0, 0.7, 0.3); // Green accent
 const TEXT_PRIMARY: Color = Color::WHITE;
 const TEXT_SECONDARY: Color = Color::from_rgb(0.7, 0.7, 0.7);
+const ERROR_COLOR: Color = Color::from_rgb(0.9, 0.3, 0.3);
+const WARNING_COLOR: Color = Color::from_rgb(1.0, 0.75, 0.0);
 
 const BOLD_FONT: Font = Font {
     family: iced::font::Family::SansSerif,
@@ -116,7 +130,38 @@ fn host_selector_appearance(_: &iced::Theme) -> iced::widget::container::Appeara
     }
 }
 
-pub fn view(state: &ChatAppState) -> Element<Message> {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PasswordStrength {
+    Weak,
+    Medium,
+    Strong,
+}
+
+/// Rough strength estimate from length and character classes (lowercase, uppercase, digits, symbols)
+fn password_strength(password: &str) -> PasswordStrength {
+    let classes = [
+        password.chars().any(|c| c.is_lowercase()),
+        password.chars().any(|c| c.is_uppercase()),
+        password.chars().any(|c| c.is_ascii_digit()),
+        password.chars().any(|c| !c.is_alphanumeric()),
+    ]
+    .iter()
+    .filter(|&&present| present)
+    .count();
+    match (password.chars().count(), classes) {
+        (len, classes) if len >= 12 && classes >= 3 => PasswordStrength::Strong,
+        (len, classes) if len >= 8 && classes >= 2 => PasswordStrength::Medium,
+        _ => PasswordStrength::Weak,
+    }
+}
+
+/// Hint shown under a field: neutral while empty, green when valid, red otherwise
+fn field_hint<'a>(text: impl ToString, is_empty: bool, valid: bool) -> Element<'a, Message> {
+    let color = if is_empty { TEXT_SECONDARY } else if valid { ACCENT_COLOR } else { ERROR_COLOR };
+    Text::new(text.to_string()).size(12).style(color).into()
+}
+
+pub fn view(state: &ChatAppState) -> Element<'_, Message> {
     let username = &state.username;
     let password = &state.password;
     let selected_host = state.selected_host;
@@ -127,7 +172,9 @@ pub fn view(state: &ChatAppState) -> Element<Message> {
 
     // Validation
     let username_valid = !username.is_empty() && username.len() >= 3 && username.chars().all(|c| c.is_alphanumeric());
-    let password_valid = !password.is_empty() && password.len() >= 6;
+    // New accounts follow the server's password rules; logins only need something to send
+    let password_rule_error = password_error(password);
+    let password_valid = if is_login { password.len() >= 6 } else { password_rule_error.is_none() };
     let host_valid = match selected_host {
         HostType::Manual => !manual_host.is_empty() && manual_host.contains(':'),
         _ => true,
@@ -185,7 +232,7 @@ pub fn view(state: &ChatAppState) -> Element<Message> {
                     Container::new(
                         TextInput::new("host:port (e.g., 127.0.0.1:5000)", manual_host)
                             .on_input(Message::ManualHostChanged)
-                            .on_submit(if submit_enabled { Message::SubmitLoginOrRegister } else { Message::None })
+                            .submit_login_if(submit_enabled)
                             .width(Length::Fill)
                             .padding(12)
                             .size(14)
@@ -296,13 +343,27 @@ pub fn view(state: &ChatAppState) -> Element<Message> {
             Container::new(
                 TextInput::new("Enter your username", username)
                     .on_input(Message::UsernameChanged)
-                    .on_submit(if submit_enabled { Message::SubmitLoginOrRegister } else { Message::None })
+                    .submit_login_if(submit_enabled)
                     .width(Length::Fill)
                     .padding(12)
                     .size(14)
             )
             .style(iced::theme::Container::Custom(Box::new(input_appearance)))
-        );
+        )
+        .push(field_hint("Minimum 3 characters, letters and numbers only", username.is_empty(), username_valid));
+
+    // Strength meter, updated on every keystroke
+    let password_hint = if !password_valid {
+        let rule = if is_login { "Minimum 6 characters" } else { password_rule_error.unwrap_or_default() };
+        field_hint(rule, password.is_empty(), false)
+    } else {
+        let (label, color) = match password_strength(password) {
+            PasswordStrength::Weak => ("Strength: weak", ERROR_COLOR),
+            PasswordStrength::Medium => ("Strength: medium", WARNING_COLOR),
+            PasswordStrength::Strong => ("Strength: strong", ACCENT_COLOR),
+        };
+        Text::new(label).size(12).style(color).into()
+    };
 
     let password_field = Column::new()
         .spacing(8)
@@ -320,7 +381,7 @@ pub fn view(state: &ChatAppState) -> Element<Message> {
                     .push(
                         TextInput::new("Enter your password", password)
                             .on_input(Message::PasswordChanged)
-                            .on_submit(if submit_enabled { Message::SubmitLoginOrRegister } else { Message::None })
+                            .submit_login_if(submit_enabled)
                             .secure(!show_password)
                             .width(Length::Fill)
                             .padding(12)
@@ -338,41 +399,8 @@ pub fn view(state: &ChatAppState) -> Element<Message> {
                     )
             )
             .style(iced::theme::Container::Custom(Box::new(input_appearance)))
-        );
-
-    // Validation indicators
-    let validation_indicators = Column::new()
-        .spacing(4)
-        .push(
-            Row::new()
-                .spacing(8)
-                .align_items(Alignment::Center)
-                .push(
-                    Text::new(if username_valid { "✅" } else { "❌" })
-                        .font(EMOJI_FONT)
-                        .size(12)
-                )
-                .push(
-                    Text::new("Username (3+ alphanumeric characters)")
-                        .size(12)
-                        .style(if username_valid { ACCENT_COLOR } else { TEXT_SECONDARY })
-                )
         )
-        .push(
-            Row::new()
-                .spacing(8)
-                .align_items(Alignment::Center)
-                .push(
-                    Text::new(if password_valid { "✅" } else { "❌" })
-                        .font(EMOJI_FONT)
-                        .size(12)
-                )
-                .push(
-                    Text::new("Password (6+ characters)")
-                        .size(12)
-                        .style(if password_valid { ACCENT_COLOR } else { TEXT_SECONDARY })
-                )
-        );
+        .push(password_hint);
 
     // Modern submit button
     let submit_button = if submit_enabled {
@@ -465,8 +493,6 @@ pub fn view(state: &ChatAppState) -> Element<Message> {
         .push(username_field)
         .push(password_field)
         .push(Space::new(Length::Fill, Length::Fixed(8.0)))
-        .push(validation_indicators)
-        .push(Space::new(Length::Fill, Length::Fixed(8.0)))
         .push(submit_button)
         .push(loading_element);
 
@@ -0,0 +1,246 @@
+use iced::{Element, Length, Alignment, Color, Font};
+use iced::widget::{Column, Row, Text, Button, Container, Space, Scrollable, TextInput};
+use crate::client::models::messages::Message;
+use crate::client::models::app_state::{AppState, ChatAppState, ChatType};
+use crate::client::services::message_parser::{format_timestamp, SearchResult};
+use crate::client::gui::views::logger::logger_view;
+
+// Modern color palette consistent with other views
+const BG_MAIN: Color = Color::from_rgb(0.06, 0.07, 0.18);
+const CARD_BG: Color = Color::from_rgb(0.18, 0.19, 0.36);
+const INPUT_BG: Color = Color::from_rgb(0.12, 0.13, 0.26);
+const TEXT_PRIMARY: Color = Color::WHITE;
+const TEXT_SECONDARY: Color = Color::from_rgb(0.7, 0.7, 0.7);
+
+const EMOJI_FONT: Font = Font::with_name("Segoe UI Emoji");
+const BOLD_FONT: Font = Font {
+    family: iced::font::Family::SansSerif,
+    weight: iced::font::Weight::Bold,
+    ..Font::DEFAULT
+};
+
+// Custom container styles
+fn bg_main_appearance(_: &iced::Theme) -> iced::widget::container::Appearance {
+    iced::widget::container::Appearance {
+        background: Some(iced::Background::Color(BG_MAIN)),
+        text_color: Some(TEXT_PRIMARY),
+        ..Default::default()
+    }
+}
+
+fn header_appearance(_: &iced::Theme) -> iced::widget::container::Appearance {
+    iced::widget::container::Appearance {
+        background: Some(iced::Background::Color(INPUT_BG)),
+        text_color: Some(TEXT_PRIMARY),
+        shadow: iced::Shadow {
+            offset: iced::Vector::new(0.0, 2.0),
+            blur_radius: 8.0,
+            color: Color::from_rgba(0.0, 0.0, 0.0, 0.2),
+        },
+        ..Default::default()
+    }
+}
+
+fn result_item_appearance(_: &iced::Theme) -> iced::widget::container::Appearance {
+    iced::widget::container::Appearance {
+        background: Some(iced::Background::Color(CARD_BG)),
+        text_color: Some(TEXT_PRIMARY),
+        border: iced::Border {
+            width: 1.0,
+            color: Color::from_rgb(0.2, 0.2, 0.3),
+            radius: 12.0.into(),
+        },
+        ..Default::default()
+    }
+}
+
+fn input_appearance(_: &iced::Theme) -> iced::widget::container::Appearance {
+    iced::widget::container::Appearance {
+        background: Some(iced::Background::Color(INPUT_BG)),
+        text_color: Some(TEXT_PRIMARY),
+        border: iced::Border {
+            width: 1.0,
+            color: Color::from_rgb(0.3, 0.3, 0.4),
+            radius: 12.0.into(),
+        },
+        ..Default::default()
+    }
+}
+
+/// Message reopening the view the search was started from
+fn back_message(state: &ChatAppState) -> Message {
+    match &state.message_search_origin {
+        Some(AppState::PrivateChat(username)) => Message::OpenPrivateChat(username.clone()),
+        Some(AppState::GroupChat(group_id, group_name)) => Message::OpenGroupChat(group_id.clone(), group_name.clone()),
+        _ => Message::OpenMainActions,
+    }
+}
+
+/// One hit: chat name, sender and time, then the message; clicking opens the chat
+fn result_item<'a>(state: &ChatAppState, result: &SearchResult) -> Element<'a, Message> {
+    let (icon, chat_name, open) = match result.chat_type {
+        ChatType::Private => ("👤", result.chat_id.clone(), Message::OpenPrivateChat(result.chat_id.clone())),
+        ChatType::Group => {
+            let name = state.my_groups.iter()
+                .find(|g| g.id == result.chat_id)
+                .map(|g| g.name.clone())
+                .unwrap_or_else(|| "Group".to_string());
+            ("👥", name.clone(), Message::OpenGroupChat(result.chat_id.clone(), name))
+        }
+    };
+
+    let item = Column::new()
+        .spacing(6)
+        .push(
+            Row::new()
+                .spacing(8)
+                .align_items(Alignment::Center)
+                .push(Text::new(icon).font(EMOJI_FONT).size(14))
+                .push(Text::new(chat_name).font(BOLD_FONT).size(14).style(TEXT_PRIMARY))
+                .push(Text::new(format!("· {}", result.sender)).size(12).style(TEXT_SECONDARY))
+                .push(Space::new(Length::Fill, Length::Fixed(0.0)))
+                .push(Text::new(format_timestamp(result.sent_at)).size(11).style(TEXT_SECONDARY))
+        )
+        .push(Text::new(result.content.clone()).size(14).style(TEXT_PRIMARY));
+
+    Button::new(
+        Container::new(item)
+            .padding(12)
+            .width(Length::Fill)
+            .style(iced::theme::Container::Custom(Box::new(result_item_appearance)))
+    )
+    .on_press(open)
+    .style(iced::theme::Button::Text)
+    .padding(0)
+    .width(Length::Fill)
+    .into()
+}
+
+pub fn view(state: &ChatAppState) -> Element<'_, Message> {
+    // Top logger bar
+    let logger_bar = if !state.logger.is_empty() {
+        Container::new(logger_view(&state.logger))
+            .width(Length::Fill)
+            .padding([8, 12, 0, 12])
+    } else {
+        Container::new(Space::new(Length::Fill, Length::Fixed(0.0)))
+            .width(Length::Fill)
+    };
+
+    // Header with back button and title
+    let back_button = Button::new(
+        Container::new(
+            Row::new()
+                .spacing(8)
+                .align_items(Alignment::Center)
+                .push(Text::new("←").font(EMOJI_FONT).size(18))
+                .push(Text::new("Back").font(BOLD_FONT).size(14))
+        )
+        .width(Length::Fill)
+        .center_x()
+    )
+    .style(iced::theme::Button::Secondary)
+    .on_press(back_message(state))
+    .padding(12)
+    .width(Length::Fixed(100.0));
+
+    let title_section = Column::new()
+        .spacing(4)
+        .align_items(Alignment::Center)
+        .push(
+            Row::new()
+                .spacing(8)
+                .align_items(Alignment::Center)
+                .push(Text::new("🔍").font(EMOJI_FONT).size(24))
+                .push(Text::new("Search Messages").font(BOLD_FONT).size(24).style(TEXT_PRIMARY))
+        )
+        .push(Text::new("Find words in all your chats").size(14).style(TEXT_SECONDARY));
+
+    let header_row = Row::new()
+        .spacing(16)
+        .align_items(Alignment::Center)
+        .push(back_button)
+        .push(Container::new(title_section).width(Length::Fill).center_x())
+        .push(Space::new(Length::Fixed(100.0), Length::Fixed(0.0))); // Balance space
+
+    let header = Container::new(header_row)
+        .padding([20, 24])
+        .width(Length::Fill)
+        .style(iced::theme::Container::Custom(Box::new(header_appearance)));
+
+    // Search section
+    let submit = Message::SearchMessages { query: state.message_search_query.clone() };
+    let search_input_field = Container::new(
+        TextInput::new("Search messages...", &state.message_search_query)
+            .on_input(Message::MessageSearchQueryChanged)
+            .on_submit(submit.clone())
+            .padding(12)
+            .size(14)
+            .width(Length::Fill)
+    )
+    .style(iced::theme::Container::Custom(Box::new(input_appearance)));
+
+    let search_button = Button::new(
+        Container::new(Text::new("Search").font(BOLD_FONT).size(14))
+            .width(Length::Fill)
+            .center_x()
+    )
+    .style(iced::theme::Button::Primary)
+    .on_press(submit)
+    .padding(12)
+    .width(Length::Fixed(120.0));
+
+    let search_row = Container::new(
+        Row::new()
+            .spacing(12)
+            .align_items(Alignment::Center)
+            .push(search_input_field)
+            .push(search_button)
+    )
+    .padding([0, 24]);
+
+    // Results
+    let content: Element<Message> = if state.loading {
+        Container::new(Text::new("Searching messages...").font(BOLD_FONT).size(16).style(TEXT_SECONDARY))
+            .width(Length::Fill)
+            .center_x()
+            .padding(40)
+            .into()
+    } else if state.message_search_results.is_empty() {
+        Container::new(Text::new("No messages found. Type some words and press Search.").size(14).style(TEXT_SECONDARY))
+            .width(Length::Fill)
+            .center_x()
+            .padding(40)
+            .into()
+    } else {
+        let results = state.message_search_results.iter()
+            .fold(Column::new().spacing(12), |column, result| column.push(result_item(state, result)));
+        Container::new(Scrollable::new(results).width(Length::Fill).height(Length::Fill))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .padding([0, 24])
+            .into()
+    };
+
+    let main_content = Column::new()
+        .push(header)
+        .push(Space::new(Length::Fill, Length::Fixed(16.0)))
+        .push(search_row)
+        .push(Space::new(Length::Fill, Length::Fixed(16.0)))
+        .push(content)
+        .push(Space::new(Length::Fill, Length::Fixed(24.0)))
+        .width(Length::Fill)
+        .height(Length::Fill);
+
+    let final_content = Column::new()
+        .push(logger_bar)
+        .push(main_content)
+        .width(Length::Fill)
+        .height(Length::Fill);
+
+    Container::new(final_content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(iced::theme::Container::Custom(Box::new(bg_main_appearance)))
+        .into()
+}
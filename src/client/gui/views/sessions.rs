@@ -0,0 +1,206 @@
+use iced::{Element, Length, Alignment, Color, Font};
+use iced::widget::{Column, Row, Text, Button, Container, Space, Scrollable};
+use crate::client::models::messages::Message;
+use crate::client::models::app_state::ChatAppState;
+use crate::client::services::message_parser::SessionInfo;
+use crate::client::gui::views::logger::logger_view;
+
+// Modern color palette consistent with other views
+const BG_MAIN: Color = Color::from_rgb(0.06, 0.07, 0.18);
+const CARD_BG: Color = Color::from_rgb(0.18, 0.19, 0.36);
+const INPUT_BG: Color = Color::from_rgb(0.12, 0.13, 0.26);
+const ACCENT_COLOR: Color = Color::from_rgb(0.0, 0.7, 0.3);
+const TEXT_PRIMARY: Color = Color::WHITE;
+const TEXT_SECONDARY: Color = Color::from_rgb(0.7, 0.7, 0.7);
+
+const EMOJI_FONT: Font = Font::with_name("Segoe UI Emoji");
+const BOLD_FONT: Font = Font {
+    family: iced::font::Family::SansSerif,
+    weight: iced::font::Weight::Bold,
+    ..Font::DEFAULT
+};
+
+// Custom container styles
+fn bg_main_appearance(_: &iced::Theme) -> iced::widget::container::Appearance {
+    iced::widget::container::Appearance {
+        background: Some(iced::Background::Color(BG_MAIN)),
+        text_color: Some(TEXT_PRIMARY),
+        ..Default::default()
+    }
+}
+
+fn header_appearance(_: &iced::Theme) -> iced::widget::container::Appearance {
+    iced::widget::container::Appearance {
+        background: Some(iced::Background::Color(INPUT_BG)),
+        text_color: Some(TEXT_PRIMARY),
+        shadow: iced::Shadow {
+            offset: iced::Vector::new(0.0, 2.0),
+            blur_radius: 8.0,
+            color: Color::from_rgba(0.0, 0.0, 0.0, 0.2),
+        },
+        ..Default::default()
+    }
+}
+
+fn session_item_appearance(_: &iced::Theme) -> iced::widget::container::Appearance {
+    iced::widget::container::Appearance {
+        background: Some(iced::Background::Color(CARD_BG)),
+        text_color: Some(TEXT_PRIMARY),
+        border: iced::Border {
+            width: 1.0,
+            color: Color::from_rgb(0.2, 0.2, 0.3),
+            radius: 12.0.into(),
+        },
+        ..Default::default()
+    }
+}
+
+/// Local date and time of `timestamp`, e.g. "14 Nov 2023 23:13"
+fn format_date(timestamp: i64) -> String {
+    use chrono::{DateTime, Utc, Local, TimeZone};
+
+    let dt = Utc.timestamp_opt(timestamp, 0).single().unwrap_or_else(Utc::now);
+    let local_dt: DateTime<Local> = dt.with_timezone(&Local);
+    local_dt.format("%d %b %Y %H:%M").to_string()
+}
+
+/// One session: device, sign-in date and token tail, with a Revoke button unless it is ours
+fn session_item<'a>(session: &SessionInfo, current: bool) -> Element<'a, Message> {
+    let label = if session.label.is_empty() { "Unknown device".to_string() } else { session.label.clone() };
+    let action: Element<Message> = if current {
+        Container::new(Text::new("This device").font(BOLD_FONT).size(12).style(ACCENT_COLOR))
+            .width(Length::Fixed(100.0))
+            .center_x()
+            .into()
+    } else {
+        Button::new(
+            Container::new(Text::new("Revoke").font(BOLD_FONT).size(12))
+                .width(Length::Fill)
+                .center_x()
+        )
+        .style(iced::theme::Button::Destructive)
+        .on_press(Message::RevokeSession { id: session.id.clone() })
+        .padding(10)
+        .width(Length::Fixed(100.0))
+        .into()
+    };
+
+    Container::new(
+        Row::new()
+            .spacing(16)
+            .align_items(Alignment::Center)
+            .push(Text::new("🖥️").font(EMOJI_FONT).size(24))
+            .push(
+                Column::new()
+                    .spacing(4)
+                    .push(Text::new(label).font(BOLD_FONT).size(16).style(TEXT_PRIMARY))
+                    .push(Text::new(format!("Signed in {} · expires {}", format_date(session.created_at), format_date(session.expires_at))).size(12).style(TEXT_SECONDARY))
+                    .push(Text::new(format!("Token …{}", session.id)).size(12).style(TEXT_SECONDARY))
+            )
+            .push(Space::new(Length::Fill, Length::Fixed(0.0)))
+            .push(action)
+    )
+    .padding(16)
+    .width(Length::Fill)
+    .style(iced::theme::Container::Custom(Box::new(session_item_appearance)))
+    .into()
+}
+
+pub fn view(state: &ChatAppState) -> Element<'_, Message> {
+    // Top logger bar
+    let logger_bar = if !state.logger.is_empty() {
+        Container::new(logger_view(&state.logger))
+            .width(Length::Fill)
+            .padding([8, 12, 0, 12])
+    } else {
+        Container::new(Space::new(Length::Fill, Length::Fixed(0.0)))
+            .width(Length::Fill)
+    };
+
+    // Header with back button and title
+    let back_button = Button::new(
+        Container::new(
+            Row::new()
+                .spacing(8)
+                .align_items(Alignment::Center)
+                .push(Text::new("←").font(EMOJI_FONT).size(18))
+                .push(Text::new("Back").font(BOLD_FONT).size(14))
+        )
+        .width(Length::Fill)
+        .center_x()
+    )
+    .style(iced::theme::Button::Secondary)
+    .on_press(Message::OpenMainActions)
+    .padding(12)
+    .width(Length::Fixed(100.0));
+
+    let title_section = Column::new()
+        .spacing(4)
+        .align_items(Alignment::Center)
+        .push(
+            Row::new()
+                .spacing(8)
+                .align_items(Alignment::Center)
+                .push(Text::new("🖥️").font(EMOJI_FONT).size(24))
+                .push(Text::new("Active Sessions").font(BOLD_FONT).size(24).style(TEXT_PRIMARY))
+        )
+        .push(Text::new("Devices signed in to your account").size(14).style(TEXT_SECONDARY));
+
+    let header_row = Row::new()
+        .spacing(16)
+        .align_items(Alignment::Center)
+        .push(back_button)
+        .push(Container::new(title_section).width(Length::Fill).center_x())
+        .push(Space::new(Length::Fixed(100.0), Length::Fixed(0.0))); // Balance space
+
+    let header = Container::new(header_row)
+        .padding([20, 24])
+        .width(Length::Fill)
+        .style(iced::theme::Container::Custom(Box::new(header_appearance)));
+
+    // Sessions list; ours is recognised by the tail of our token
+    let content: Element<Message> = if state.loading {
+        Container::new(Text::new("Loading sessions...").font(BOLD_FONT).size(16).style(TEXT_SECONDARY))
+            .width(Length::Fill)
+            .center_x()
+            .padding(40)
+            .into()
+    } else if state.sessions.is_empty() {
+        Container::new(Text::new("No active sessions").size(14).style(TEXT_SECONDARY))
+            .width(Length::Fill)
+            .center_x()
+            .padding(40)
+            .into()
+    } else {
+        let token = state.session_token.as_deref().unwrap_or_default();
+        let sessions = state.sessions.iter()
+            .fold(Column::new().spacing(12), |column, session| {
+                column.push(session_item(session, token.ends_with(&session.id)))
+            });
+        Container::new(Scrollable::new(sessions).width(Length::Fill).height(Length::Fill))
+            .width(Length::Fill)
+            .height(Length::Fill)
+            .padding([0, 24])
+            .into()
+    };
+
+    let main_content = Column::new()
+        .push(header)
+        .push(Space::new(Length::Fill, Length::Fixed(16.0)))
+        .push(content)
+        .push(Space::new(Length::Fill, Length::Fixed(24.0)))
+        .width(Length::Fill)
+        .height(Length::Fill);
+
+    let final_content = Column::new()
+        .push(logger_bar)
+        .push(main_content)
+        .width(Length::Fill)
+        .height(Length::Fill);
+
+    Container::new(final_content)
+        .width(Length::Fill)
+        .height(Length::Fill)
+        .style(iced::theme::Container::Custom(Box::new(bg_main_appearance)))
+        .into()
+}
@@ -2,6 +2,7 @@ use iced::{Element, Length, Alignment, Color};
 use iced::widget::{Column, Row, Text, Button, Container, TextInput, Scrollable, Space};
 use crate::client::models::messages::Message;
 use crate::client::models::app_state::ChatAppState;
+use crate::client::services::message_parser::relative_time;
 
 // Modern color palette consistent with registration.rs and main_actions.rs
 const BG_MAIN: Color = Color::from_rgb(0.06, 0.07, 0.18);
@@ -9,6 +10,7 @@ const CARD_BG: Color = Color::from_rgb(0.18, 0.19, 0.36);
 const INPUT_BG: Color = Color::from_rgb(0.12, 0.13, 0.26);
 const TEXT_PRIMARY: Color = Color::WHITE;
 const TEXT_SECONDARY: Color = Color::from_rgb(0.7, 0.7, 0.7);
+const ONLINE_DOT: Color = Color::from_rgb(0.2, 0.8, 0.3);
 
 use iced::Font;
 const EMOJI_FONT: Font = Font::with_name("Segoe UI Emoji");
@@ -213,6 +215,59 @@ pub fn view<'a>(state: &'a ChatAppState, kind: &'a str) -> Element<'a, Message>
         );
     } else {
         for username in state.users_search_results.iter() {
+            // Friends get a check, pending requests a greyed label, everyone else an "Add Friend" button
+            let friend_action: Element<Message> = if state.friends_list.iter().any(|f| &f.username == username) {
+                Container::new(
+                    Row::new()
+                        .spacing(6)
+                        .align_items(Alignment::Center)
+                        .push(Text::new("✅").font(EMOJI_FONT).size(14))
+                        .push(Text::new("Friend").font(BOLD_FONT).size(12).style(Color::from_rgb(0.0, 0.7, 0.3)))
+                )
+                .width(Length::Fixed(110.0))
+                .center_x()
+                .into()
+            } else if state.pending_sent_requests.contains(username) {
+                Container::new(Text::new("Pending").font(BOLD_FONT).size(12).style(TEXT_SECONDARY))
+                    .width(Length::Fixed(110.0))
+                    .center_x()
+                    .into()
+            } else {
+                Button::new(
+                    Container::new(
+                        Row::new()
+                            .spacing(6)
+                            .align_items(Alignment::Center)
+                            .push(Text::new("➕").font(EMOJI_FONT).size(14))
+                            .push(Text::new("Add Friend").font(BOLD_FONT).size(12))
+                    )
+                    .width(Length::Fill)
+                    .center_x()
+                )
+                .style(iced::theme::Button::Secondary)
+                .on_press(Message::SendFriendRequest { to: username.clone(), message: String::new() })
+                .padding(10)
+                .width(Length::Fixed(110.0))
+                .into()
+            };
+
+            // Everyone in the "Online" list is online even before presence has loaded
+            let presence = state.user_presence.get(username).copied().unwrap_or_default();
+            let presence_line: Element<Message> = if kind == "Online" || presence.is_online {
+                Row::new()
+                    .spacing(6)
+                    .align_items(Alignment::Center)
+                    .push(Text::new("●").size(12).style(ONLINE_DOT))
+                    .push(Text::new("Online").size(12).style(TEXT_SECONDARY))
+                    .into()
+            } else {
+                let label = match presence.last_seen {
+                    Some(ts) => format!("Last seen {}", relative_time(ts)),
+                    None => "Offline".to_string(),
+                };
+                Text::new(label).size(12).style(TEXT_SECONDARY).into()
+            };
+
             let user_item = Container::new(
                 Row::new()
                     .spacing(16)
@@ -237,7 +292,7 @@ pub fn view<'a>(state: &'a ChatAppState, kind: &'a str) -> Element<'a, Message>
                         Column::new()
                             .spacing(2)
                             .push(Text::new(username).font(BOLD_FONT).size(16).style(TEXT_PRIMARY))
-                            .push(Text::new("User").size(12).style(TEXT_SECONDARY))
+                            .push(presence_line)
                     )
                     .push(Space::new(Length::Fill, Length::Fixed(0.0)))
                     .push(
@@ -257,6 +312,7 @@ pub fn view<'a>(state: &'a ChatAppState, kind: &'a str) -> Element<'a, Message>
                         .padding(10)
                         .width(Length::Fixed(100.0))
                     )
+                    .push(friend_action)
             )
             .padding(16)
             .width(Length::Fill)
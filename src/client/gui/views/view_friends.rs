@@ -10,6 +10,9 @@ const CARD_BG: Color = Color::from_rgb(0.18, 0.19, 0.36);
 const INPUT_BG: Color = Color::from_rgb(0.12, 0.13, 0.26);
 const TEXT_PRIMARY: Color = Color::WHITE;
 const TEXT_SECONDARY: Color = Color::from_rgb(0.7, 0.7, 0.7);
+const ONLINE_DOT: Color = Color::from_rgb(0.2, 0.8, 0.3);
+const OFFLINE_DOT: Color = Color::from_rgb(0.5, 0.5, 0.5);
+const STATUS_MUTED: Color = Color::from_rgb(0.55, 0.56, 0.68);
 
 const EMOJI_FONT: Font = Font::with_name("Segoe UI Emoji");
 const BOLD_FONT: Font = Font {
@@ -18,6 +21,17 @@ const BOLD_FONT: Font = Font {
     ..Font::DEFAULT
 };
 
+/// "just now", "5 minutes ago", "3 hours ago", "2 days ago"
+pub fn format_elapsed(elapsed_secs: i64) -> String {
+    let plural = |n: i64, unit: &str| format!("{} {}{} ago", n, unit, if n == 1 { "" } else { "s" });
+    match elapsed_secs.max(0) {
+        s if s < 60 => "just now".to_string(),
+        s if s < 3600 => plural(s / 60, "minute"),
+        s if s < 86_400 => plural(s / 3600, "hour"),
+        s => plural(s / 86_400, "day"),
+    }
+}
+
 // Custom container styles
 fn bg_main_appearance(_: &iced::Theme) -> iced::widget::container::Appearance {
     iced::widget::container::Appearance {
@@ -87,7 +101,7 @@ fn empty_state_appearance(_: &iced::Theme) -> iced::widget::container::Appearanc
     }
 }
 
-pub fn view(state: &ChatAppState) -> Element<Message> {
+pub fn view(state: &ChatAppState) -> Element<'_, Message> {
     // Top logger bar
     let logger_bar = if !state.logger.is_empty() {
         Container::new(logger_view(&state.logger))
@@ -196,7 +210,19 @@ pub fn view(state: &ChatAppState) -> Element<Message> {
         // Friends list
         let mut friends_column = Column::new().spacing(12);
         
-        for friend_username in &state.friends_list {
+        let now = chrono::Utc::now().timestamp();
+        for friend in &state.friends_list {
+            let friend_username = &friend.username;
+            let is_online = state.online_users.contains(friend_username);
+            let status_line = if is_online {
+                "Online".to_string()
+            } else {
+                match friend.last_seen {
+                    Some(ts) => format!("Last seen {}", format_elapsed(now - ts)),
+                    None => "Offline".to_string(),
+                }
+            };
+            let dot_color = if is_online { ONLINE_DOT } else { OFFLINE_DOT };
             let friend_item = Container::new(
                 Row::new()
                     .spacing(16)
@@ -220,8 +246,19 @@ pub fn view(state: &ChatAppState) -> Element<Message> {
                     .push(
                         Column::new()
                             .spacing(4)
-                            .push(Text::new(friend_username).font(BOLD_FONT).size(16).style(TEXT_PRIMARY))
-                            .push(Text::new("Friend").size(12).style(TEXT_SECONDARY))
+                            .push(
+                                Row::new()
+                                    .spacing(6)
+                                    .align_items(Alignment::Center)
+                                    .push(Text::new("●").size(12).style(dot_color))
+                                    .push(Text::new(friend_username).font(BOLD_FONT).size(16).style(TEXT_PRIMARY))
+                            )
+                            .push_maybe(
+                                state.user_statuses.get(friend_username)
+                                    .filter(|status| !status.is_empty())
+                                    .map(|status| Text::new(status.clone()).size(13).style(STATUS_MUTED))
+                            )
+                            .push(Text::new(status_line).size(12).style(TEXT_SECONDARY))
                     )
                     .push(Space::new(Length::Fill, Length::Fixed(0.0)))
                     .push(
@@ -241,6 +278,23 @@ pub fn view(state: &ChatAppState) -> Element<Message> {
                         .padding(10)
                         .width(Length::Fixed(100.0))
                     )
+                    .push(
+                        Button::new(
+                            Container::new(
+                                Row::new()
+                                    .spacing(6)
+                                    .align_items(Alignment::Center)
+                                    .push(Text::new("🚫").font(EMOJI_FONT).size(14))
+                                    .push(Text::new("Block").font(BOLD_FONT).size(12))
+                            )
+                            .width(Length::Fill)
+                            .center_x()
+                        )
+                        .style(iced::theme::Button::Destructive)
+                        .on_press(Message::BlockUser { username: friend_username.clone() })
+                        .padding(10)
+                        .width(Length::Fixed(100.0))
+                    )
             )
             .padding(16)
             .width(Length::Fill)
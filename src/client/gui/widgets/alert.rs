@@ -1,7 +1,23 @@
 // Widget di alert per la GUI
-use iced::{Element, widget::text};
+use iced::{Color, Element, Length, widget::{text, Container, Text}};
 use crate::client::models::messages::Message;
 
+const BROADCAST_BG: Color = Color::from_rgb(0.85, 0.55, 0.1);
+
 pub fn view(msg: &str) -> Element<'_, Message> {
     text(format!("ALERT: {}", msg)).into()
 }
+
+/// Full-width strip with an admin announcement; it has no close button and goes away on expiry
+pub fn broadcast_banner(msg: &str) -> Element<'_, Message> {
+    Container::new(Text::new(format!("📢 {}", msg)).size(14).style(Color::WHITE))
+        .width(Length::Fill)
+        .padding([8, 16])
+        .style(iced::theme::Container::Custom(Box::new(|_: &iced::Theme| {
+            iced::widget::container::Appearance {
+                background: Some(iced::Background::Color(BROADCAST_BG)),
+                ..Default::default()
+            }
+        })))
+        .into()
+}
@@ -0,0 +1,67 @@
+// Widget avatar: immagine circolare dell'utente o iniziale su sfondo colorato
+use std::collections::HashMap;
+use iced::{Color, Element, Length, Point, Rectangle, Renderer, Theme, mouse};
+use iced::widget::{canvas, image, Canvas, Image};
+use crate::client::models::messages::Message;
+
+/// Side of the rendered avatar
+pub const AVATAR_SIZE: f32 = 32.0;
+
+/// Background colors for generated avatars; picked from the username so each user keeps theirs
+const PALETTE: [Color; 6] = [
+    Color::from_rgb(0.85, 0.33, 0.31),
+    Color::from_rgb(0.93, 0.60, 0.20),
+    Color::from_rgb(0.36, 0.72, 0.36),
+    Color::from_rgb(0.20, 0.60, 0.86),
+    Color::from_rgb(0.61, 0.35, 0.71),
+    Color::from_rgb(0.10, 0.74, 0.61),
+];
+
+/// Circle with the first letter of the username, drawn on a canvas
+struct InitialAvatar {
+    letter: String,
+    color: Color,
+}
+
+impl InitialAvatar {
+    fn new(username: &str) -> Self {
+        let letter = username.chars().next().map(|c| c.to_uppercase().to_string()).unwrap_or_else(|| "?".to_string());
+        let hash = username.bytes().fold(0usize, |acc, b| acc.wrapping_mul(31).wrapping_add(b as usize));
+        InitialAvatar { letter, color: PALETTE[hash % PALETTE.len()] }
+    }
+}
+
+impl canvas::Program<Message> for InitialAvatar {
+    type State = ();
+
+    fn draw(&self, _state: &(), renderer: &Renderer, _theme: &Theme, bounds: Rectangle, _cursor: mouse::Cursor) -> Vec<canvas::Geometry> {
+        let mut frame = canvas::Frame::new(renderer, bounds.size());
+        let center = frame.center();
+        frame.fill(&canvas::Path::circle(center, bounds.width.min(bounds.height) / 2.0), self.color);
+        frame.fill_text(canvas::Text {
+            content: self.letter.clone(),
+            position: Point::new(center.x, center.y),
+            color: Color::WHITE,
+            size: (AVATAR_SIZE * 0.5).into(),
+            horizontal_alignment: iced::alignment::Horizontal::Center,
+            vertical_alignment: iced::alignment::Vertical::Center,
+            ..Default::default()
+        });
+        vec![frame.into_geometry()]
+    }
+}
+
+/// Avatar of `username`: the downloaded image if `avatar_url` is already in `cache`,
+/// otherwise the generated initial-letter avatar.
+pub fn view<'a>(username: &str, avatar_url: Option<&str>, cache: &HashMap<String, image::Handle>) -> Element<'a, Message> {
+    match avatar_url.and_then(|url| cache.get(url)) {
+        Some(handle) => Image::new(handle.clone())
+            .width(Length::Fixed(AVATAR_SIZE))
+            .height(Length::Fixed(AVATAR_SIZE))
+            .into(),
+        None => Canvas::new(InitialAvatar::new(username))
+            .width(Length::Fixed(AVATAR_SIZE))
+            .height(Length::Fixed(AVATAR_SIZE))
+            .into(),
+    }
+}
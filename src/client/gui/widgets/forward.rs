@@ -0,0 +1,70 @@
+// Widget inoltro: pulsante, etichetta "Forwarded" e selettore della chat di destinazione
+use iced::{Alignment, Color, Element, Font, Length};
+use iced::widget::{Button, Column, Row, Scrollable, Space, Text};
+use crate::client::models::app_state::{ChatAppState, ChatType};
+use crate::client::models::messages::Message;
+
+const EMOJI_FONT: Font = Font::with_name("Segoe UI Emoji");
+const BOLD_FONT: Font = Font {
+    family: iced::font::Family::SansSerif,
+    weight: iced::font::Weight::Bold,
+    ..Font::DEFAULT
+};
+const LABEL_COLOR: Color = Color::from_rgb(0.85, 0.85, 0.85);
+
+/// Footer button that opens the chat picker to forward `message_id`
+pub fn forward_button<'a>(message_id: i64) -> Element<'a, Message> {
+    Button::new(Text::new("➡").font(EMOJI_FONT).size(10))
+        .on_press(Message::OpenForwardPicker { message_id })
+        .style(iced::theme::Button::Text)
+        .padding(0)
+        .into()
+}
+
+/// Label shown above the content of a forwarded copy
+pub fn forwarded_label<'a>() -> Element<'a, Message> {
+    Text::new("➡ Forwarded").font(EMOJI_FONT).size(11).style(LABEL_COLOR).into()
+}
+
+/// Recent conversations, then the other groups we belong to, as a list of targets for
+/// `forwarding_message`; `None` unless the picker is open.
+pub fn picker<'a>(state: &'a ChatAppState, text_color: Color) -> Option<Element<'a, Message>> {
+    let message_id = state.forwarding_message.filter(|_| state.forwarding_mode)?;
+
+    let target = |label: String, target_chat_id: String| -> Element<'a, Message> {
+        Button::new(Text::new(label).size(13))
+            .on_press(Message::ForwardMessage { message_id, target_chat_id })
+            .style(iced::theme::Button::Secondary)
+            .width(Length::Fill)
+            .padding([4, 8])
+            .into()
+    };
+    let mut targets = Column::new().spacing(4);
+    for conversation in &state.recent_conversations {
+        let icon = if conversation.chat_type == ChatType::Group { "👥" } else { "👤" };
+        targets = targets.push(target(format!("{} {}", icon, conversation.display_name), conversation.key()));
+    }
+    let listed = |group_id: &str| state.recent_conversations.iter().any(|c| c.chat_type == ChatType::Group && c.chat_id == group_id);
+    for group in state.my_groups.iter().filter(|g| !listed(&g.id)) {
+        targets = targets.push(target(format!("👥 {}", group.name), format!("group:{}", group.id)));
+    }
+
+    let header = Row::new()
+        .spacing(8)
+        .align_items(Alignment::Center)
+        .push(Text::new("Forward to").font(BOLD_FONT).size(13).style(text_color))
+        .push(Space::with_width(Length::Fill))
+        .push(
+            Button::new(Text::new("Cancel").size(12))
+                .on_press(Message::CloseForwardPicker)
+                .style(iced::theme::Button::Text)
+                .padding([2, 6])
+        );
+    Some(
+        Column::new()
+            .spacing(6)
+            .push(header)
+            .push(Scrollable::new(targets).height(Length::Fixed(160.0)))
+            .into(),
+    )
+}
@@ -6,6 +6,6 @@ pub fn view(input: &str) -> Element<'_, Message> {
     column![
         text("Scrivi un messaggio:"),
     text_input("Messaggio", input),
-        button("Invia").on_press(Message::None)
+        button("Invia")
     ].into()
 }
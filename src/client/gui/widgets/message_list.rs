@@ -1,8 +1,61 @@
 // Widget per la lista dei messaggi
-use iced::{Element, widget::column};
+use iced::{Element, Color, Length, widget::{column, Container, Text}};
+use chrono::{Local, NaiveDate, TimeZone};
+use crate::client::models::app_state::ChatMessage;
 use crate::client::models::messages::Message;
 
 pub fn view<'a>(messages: &[String]) -> Element<'a, Message> {
     let items = messages.iter().map(|msg| iced::widget::text(msg).into()).collect::<Vec<_>>();
     column(items).into()
 }
+
+/// Elemento della lista messaggi di una chat: un messaggio o un separatore di data
+#[derive(Debug, Clone)]
+pub enum ChatViewItem<'a> {
+    Message(&'a ChatMessage),
+    DateSeparator(String),
+}
+
+fn local_date(timestamp: i64) -> Option<NaiveDate> {
+    Local.timestamp_opt(timestamp, 0).single().map(|dt| dt.date_naive())
+}
+
+/// Insert a `DateSeparator` before the first message and whenever the (local) day changes
+/// between two adjacent messages. Messages are expected in chronological order.
+pub fn inject_date_separators(messages: &[ChatMessage]) -> Vec<ChatViewItem<'_>> {
+    let mut items = Vec::with_capacity(messages.len() + 1);
+    let mut current: Option<NaiveDate> = None;
+    for msg in messages {
+        let date = local_date(msg.timestamp);
+        if date.is_some() && date != current {
+            if let Some(d) = date {
+                items.push(ChatViewItem::DateSeparator(d.format("%A, %-d %B %Y").to_string()));
+            }
+            current = date;
+        }
+        items.push(ChatViewItem::Message(msg));
+    }
+    items
+}
+
+/// Full-width centered label used between messages of different days
+pub fn date_separator<'a>(label: String) -> Element<'a, Message> {
+    Container::new(
+        Container::new(Text::new(label).size(12).style(Color::from_rgb(0.8, 0.8, 0.85)))
+            .padding([4, 12])
+            .style(iced::theme::Container::Custom(Box::new(|_: &iced::Theme| {
+                iced::widget::container::Appearance {
+                    background: Some(iced::Background::Color(Color::from_rgba(1.0, 1.0, 1.0, 0.08))),
+                    border: iced::Border {
+                        radius: 10.0.into(),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                }
+            })))
+    )
+    .width(Length::Fill)
+    .center_x()
+    .padding([8, 0])
+    .into()
+}
@@ -1,3 +1,7 @@
 pub mod alert;
 pub mod message_list;
 pub mod input_section;
+pub mod avatar;
+pub mod reactions;
+pub mod reply;
+pub mod forward;
@@ -0,0 +1,51 @@
+// Widget reazioni: pulsanti emoji sotto i messaggi
+use iced::{Alignment, Element, Font};
+use iced::widget::{Button, Row, Text};
+use crate::client::models::app_state::ChatMessage;
+use crate::client::models::messages::Message;
+
+const EMOJI_FONT: Font = Font::with_name("Segoe UI Emoji");
+
+/// Emoji offered by the picker
+pub const QUICK_REACTIONS: [&str; 6] = ["👍", "❤️", "😂", "😮", "😢", "🎉"];
+
+/// Footer button that opens the quick reaction picker of message `message_id`
+pub fn picker_button<'a>(message_id: i64) -> Element<'a, Message> {
+    Button::new(Text::new("🙂").font(EMOJI_FONT).size(10))
+        .on_press(Message::ToggleReactionPicker { message_id })
+        .style(iced::theme::Button::Text)
+        .padding(0)
+        .into()
+}
+
+fn reaction_button<'a>(message_id: i64, emoji: &str, label: String, mine: bool) -> Element<'a, Message> {
+    Button::new(Text::new(label).font(EMOJI_FONT).size(12))
+        .on_press(Message::ToggleReaction { message_id, emoji: emoji.to_string() })
+        // The current user's reactions stand out so they know a click takes them back
+        .style(if mine { iced::theme::Button::Primary } else { iced::theme::Button::Secondary })
+        .padding([2, 6])
+        .into()
+}
+
+/// `<emoji> <count>` buttons for the reactions to `msg`, followed by the quick picker when
+/// `picker_open`; `None` when there is nothing to show.
+pub fn view<'a>(msg: &ChatMessage, username: &str, picker_open: bool) -> Option<Element<'a, Message>> {
+    let message_id = msg.message_id?;
+    if msg.reactions.is_empty() && !picker_open {
+        return None;
+    }
+
+    let mut emojis: Vec<&String> = msg.reactions.keys().collect();
+    emojis.sort();
+    let mut row = Row::new().spacing(4).align_items(Alignment::Center);
+    for emoji in emojis {
+        let count = msg.reactions[emoji].len();
+        row = row.push(reaction_button(message_id, emoji, format!("{} {}", emoji, count), msg.has_reaction(emoji, username)));
+    }
+    if picker_open {
+        for emoji in QUICK_REACTIONS {
+            row = row.push(reaction_button(message_id, emoji, emoji.to_string(), msg.has_reaction(emoji, username)));
+        }
+    }
+    Some(row.into())
+}
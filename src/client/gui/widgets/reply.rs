@@ -0,0 +1,83 @@
+// Widget risposte: messaggio citato sopra la bolla e nella barra di input
+use iced::{Alignment, Color, Element, Font, Length};
+use iced::widget::{Button, Column, Container, Row, Space, Text};
+use crate::client::models::app_state::ChatMessage;
+use crate::client::models::messages::Message;
+
+const EMOJI_FONT: Font = Font::with_name("Segoe UI Emoji");
+const BOLD_FONT: Font = Font {
+    family: iced::font::Family::SansSerif,
+    weight: iced::font::Weight::Bold,
+    ..Font::DEFAULT
+};
+const QUOTE_BG: Color = Color::from_rgba(0.0, 0.0, 0.0, 0.25);
+const QUOTE_TEXT: Color = Color::from_rgb(0.85, 0.85, 0.85);
+
+/// Characters of the original message shown in a quote
+const SNIPPET_CHARS: usize = 80;
+
+fn snippet(content: &str) -> String {
+    match content.char_indices().nth(SNIPPET_CHARS) {
+        Some((end, _)) => format!("{}…", &content[..end]),
+        None => content.to_string(),
+    }
+}
+
+/// Sender and start of `parent`, or a placeholder when it is not loaded (or was deleted)
+fn quote_body<'a>(parent: Option<&ChatMessage>) -> Column<'a, Message> {
+    match parent {
+        Some(parent) => Column::new()
+            .spacing(2)
+            .push(Text::new(parent.sender.clone()).font(BOLD_FONT).size(11).style(QUOTE_TEXT))
+            .push(Text::new(snippet(&parent.content)).size(11).style(QUOTE_TEXT)),
+        None => Column::new().push(Text::new("Original message unavailable").size(11).style(QUOTE_TEXT)),
+    }
+}
+
+/// Footer button that makes the next message sent a reply to `message_id`
+pub fn reply_button<'a>(message_id: i64) -> Element<'a, Message> {
+    Button::new(Text::new("↩").font(EMOJI_FONT).size(10))
+        .on_press(Message::StartReply { message_id })
+        .style(iced::theme::Button::Text)
+        .padding(0)
+        .into()
+}
+
+/// Rounded block quoting `parent` above a reply; clicking it scrolls to the original
+pub fn quote<'a>(reply_to_id: i64, parent: Option<&ChatMessage>) -> Element<'a, Message> {
+    let block = Container::new(quote_body(parent))
+        .padding([4, 8])
+        .style(iced::theme::Container::Custom(Box::new(|_: &iced::Theme| {
+            iced::widget::container::Appearance {
+                background: Some(iced::Background::Color(QUOTE_BG)),
+                border: iced::Border {
+                    radius: 8.0.into(),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+        })));
+    Button::new(block)
+        .on_press(Message::ScrollToMessage { message_id: reply_to_id })
+        .style(iced::theme::Button::Text)
+        .padding(0)
+        .into()
+}
+
+/// "Replying to" bar shown above the input box while a reply is being written
+pub fn composer_banner<'a>(parent: Option<&ChatMessage>, text_color: Color) -> Element<'a, Message> {
+    Row::new()
+        .spacing(8)
+        .align_items(Alignment::Center)
+        .push(Text::new("↩").font(EMOJI_FONT).size(12))
+        .push(Text::new("Replying to").size(12).style(text_color))
+        .push(quote_body(parent))
+        .push(Space::with_width(Length::Fill))
+        .push(
+            Button::new(Text::new("Cancel").size(12))
+                .on_press(Message::CancelReply)
+                .style(iced::theme::Button::Text)
+                .padding([2, 6])
+        )
+        .into()
+}
@@ -2,13 +2,15 @@ use std::collections::HashMap;
 use crate::client::gui::views::registration::HostType;
 use crate::client::gui::views::logger::LogMessage;
 use crate::client::models::messages::Message;
-use crate::client::services::chat_service::ChatService;
+use crate::client::services::chat_service::{ChatService, HISTORY_PAGE_SIZE};
+use crate::client::services::group_service::{GroupInvite, GroupMemberInfo, GroupService, GroupSummary, PublicGroupsPage};
+use crate::client::services::friend_service::{FriendRequest, FriendService};
 use std::sync::Arc;
 use tokio::sync::Mutex;
 use iced::Command;
 use iced::widget::scrollable;
 
-#[derive(Debug, Clone, PartialEq, Default)]
+#[derive(Debug, Clone, Default)]
 pub enum AppState {
     #[default]
     CheckingSession,
@@ -25,6 +27,18 @@ pub enum AppState {
     MyGroupInvites,
     SendFriendRequest,
     ViewFriends,
+    BlockedUsers,
+    ArchivedChats,
+    SearchGroups,
+    DiscoverGroups,
+    SearchResults,
+    /// Every pinned message of a group, by group id
+    PinnedMessages(String),
+    ChangePassword,
+    DeleteAccount,
+    Sessions,
+    /// Modal confirmation; the boxed messages are dispatched by the two buttons
+    ConfirmDialog { prompt: String, confirm_message: Box<Message>, cancel_message: Box<Message> },
 }
 
 // Helper function to extract username from friend request action messages
@@ -42,6 +56,61 @@ fn extract_username_from_friend_action(message: &str) -> Option<String> {
     }
     None
 }
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatType {
+    Private,
+    Group,
+}
+
+impl ChatType {
+    /// Name used by the server protocol (`/archive_chat <token> <chat_type> <chat_id>`)
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChatType::Private => "private",
+            ChatType::Group => "group",
+        }
+    }
+}
+
+/// A chat hidden from the main lists; `chat_id` is the username for private chats, the group id otherwise
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArchivedChat {
+    pub chat_type: ChatType,
+    pub chat_id: String,
+    pub display_name: String,
+}
+
+/// One row of the "Recent" list in the main view
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversationSummary {
+    /// Username for private chats, group id for groups
+    pub chat_id: String,
+    pub chat_type: ChatType,
+    pub display_name: String,
+    pub last_message: Option<String>,
+    pub last_timestamp: Option<i64>,
+    pub unread: usize,
+}
+
+impl ConversationSummary {
+    /// Key shared with `last_viewed`, unique across private and group chats
+    pub fn key(&self) -> String {
+        format!("{}:{}", self.chat_type.as_str(), self.chat_id)
+    }
+}
+
+/// Invite code dialog of a group chat header, opened by `OpenInviteCodeDialog`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InviteCodeDialog {
+    pub group_id: String,
+    /// Seconds the next code stays valid; `None` for no expiry
+    pub expires_secs: Option<i64>,
+    /// Maximum uses as typed; empty for unlimited
+    pub max_uses: String,
+    /// Last code generated, shown ready to share
+    pub code: Option<String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct ChatMessage {
     pub sender: String,
@@ -51,9 +120,119 @@ pub struct ChatMessage {
     pub sent_at: i64,
     /// True if this is a temporary local message awaiting server confirmation
     pub is_pending: bool,
+    /// Server-side message id, when the source provides one
+    pub message_id: Option<i64>,
+    /// Avatar of the sender, when they set one
+    pub sender_avatar_url: Option<String>,
+    /// Group members (other than the sender) who have seen the message
+    pub seen_count: u32,
+    /// The sender changed the content after sending it
+    pub edited: bool,
+    /// Emoji -> usernames who reacted with it
+    pub reactions: HashMap<String, Vec<String>>,
+    /// Message of the same chat this one replies to
+    pub reply_to_id: Option<i64>,
+    /// Message this one is a forwarded copy of
+    pub forwarded_from_id: Option<i64>,
+}
+
+impl ChatMessage {
+    /// Add or remove `username`'s `emoji` reaction; emoji nobody uses any more disappear.
+    ///
+    /// ```
+    /// use ruggine_modulare::client::models::app_state::ChatMessage;
+    ///
+    /// let mut msg = ChatMessage {
+    ///     sender: "bob".into(), content: "hi".into(), timestamp: 0, formatted_time: String::new(), sent_at: 0,
+    ///     is_pending: false, message_id: Some(1), sender_avatar_url: None, seen_count: 0, edited: false,
+    ///     reactions: Default::default(), reply_to_id: None, forwarded_from_id: None,
+    /// };
+    /// msg.set_reaction("👍", "alice", true);
+    /// msg.set_reaction("👍", "alice", true);
+    /// assert_eq!(msg.reactions["👍"], vec!["alice".to_string()]);
+    /// msg.set_reaction("👍", "alice", false);
+    /// assert!(msg.reactions.is_empty());
+    /// ```
+    pub fn set_reaction(&mut self, emoji: &str, username: &str, on: bool) {
+        let users = self.reactions.entry(emoji.to_string()).or_default();
+        users.retain(|u| u != username);
+        if on {
+            users.push(username.to_string());
+        }
+        if users.is_empty() {
+            self.reactions.remove(emoji);
+        }
+    }
+
+    /// Whether `username` reacted with `emoji`
+    pub fn has_reaction(&self, emoji: &str, username: &str) -> bool {
+        self.reactions.get(emoji).is_some_and(|users| users.iter().any(|u| u == username))
+    }
+}
+
+/// How far (in seconds) the server timestamp of a confirmed message may be from the
+/// local timestamp of the pending copy it replaces
+const PENDING_CONFIRM_WINDOW_SECS: i64 = 30;
+
+/// Merge a freshly received batch into the cached messages of a chat.
+///
+/// Messages are deduplicated by `message_id` when both sides have one, otherwise by
+/// `(sender, content, timestamp)`. Pending local messages are dropped once a confirmed
+/// copy arrives: same sender and content, a timestamp within `PENDING_CONFIRM_WINDOW_SECS`
+/// and not already in the cache, so an older identical message in a full history reload
+/// does not hide a newer one still in flight. Edited copies replace the cached content.
+/// Reactions are taken from the incoming copy when it carries a server id.
+/// The result is sorted by `timestamp`.
+pub fn merge_messages(existing: &[ChatMessage], incoming: &[ChatMessage]) -> Vec<ChatMessage> {
+    let same_message = |a: &ChatMessage, b: &ChatMessage| match (a.message_id, b.message_id) {
+        (Some(x), Some(y)) => x == y,
+        _ => a.sender == b.sender && a.content == b.content && a.timestamp == b.timestamp,
+    };
+    let confirms = |new: &ChatMessage, pending: &ChatMessage| {
+        !new.is_pending
+            && new.sender == pending.sender
+            && new.content == pending.content
+            && (new.timestamp - pending.timestamp).abs() <= PENDING_CONFIRM_WINDOW_SECS
+            && !existing.iter().any(|old| !old.is_pending && same_message(old, new))
+    };
+
+    let mut merged: Vec<ChatMessage> = existing
+        .iter()
+        .filter(|old| !(old.is_pending && incoming.iter().any(|new| confirms(new, old))))
+        .cloned()
+        .collect();
+
+    for msg in incoming {
+        match merged.iter_mut().find(|m| same_message(m, msg)) {
+            Some(existing) => {
+                // Learn the server id of messages that arrived without one, and keep
+                // the freshest receipt count (receipts only grow)
+                existing.message_id = existing.message_id.or(msg.message_id);
+                existing.seen_count = existing.seen_count.max(msg.seen_count);
+                if msg.edited {
+                    existing.content = msg.content.clone();
+                    existing.edited = true;
+                }
+                if msg.message_id.is_some() {
+                    existing.reactions = msg.reactions.clone();
+                }
+            }
+            None => merged.push(msg.clone()),
+        }
+    }
+
+    merged.sort_by_key(|m| m.timestamp);
+    merged
 }
 
+/// Admin announcement received through `SystemBroadcast`
 #[derive(Debug, Clone)]
+pub struct BroadcastBanner {
+    pub message: String,
+    pub expires_at: std::time::Instant,
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct ChatAppState {
     pub app_state: AppState,
     pub username: String,
@@ -68,65 +247,360 @@ pub struct ChatAppState {
     pub logger: Vec<LogMessage>,
     pub users_search_query: String,
     pub users_search_results: Vec<String>,
+    /// Presence of the users listed in the users view, by username
+    pub user_presence: HashMap<String, crate::client::services::users_service::UserPresence>,
     pub current_message_input: String,
     pub private_chats: HashMap<String, Vec<ChatMessage>>,
     pub loading_private_chats: std::collections::HashSet<String>,
     /// Track the latest timestamp loaded via HTTP for each chat to avoid WebSocket duplicates
     pub last_http_timestamp: HashMap<String, i64>,
+    /// Timestamp of the newest message seen per private chat; polling asks only for newer ones
+    pub last_poll_ts: HashMap<String, i64>,
+    /// Group chats whose member sidebar is open
+    pub show_members_sidebar: HashMap<String, bool>,
+    /// Members of each group, loaded when its sidebar is opened
+    pub group_members: HashMap<String, Vec<GroupMemberInfo>>,
+    /// Downloaded avatars by URL
+    pub avatar_cache: HashMap<String, iced::widget::image::Handle>,
+    /// Avatar URLs already requested (including failed ones), so polling does not refetch them
+    pub avatar_requests: std::collections::HashSet<String>,
+    /// Last known avatar URL per username, for messages that arrive without one (WebSocket)
+    pub user_avatars: HashMap<String, String>,
     pub polling_active: bool,
-    /// Track if WebSocket message polling is active
-    pub websocket_polling_active: bool,
     pub group_chats: HashMap<String, Vec<ChatMessage>>,
     pub loading_group_chats: std::collections::HashSet<String>,
     pub group_polling_active: bool,
     pub create_group_name: String,
+    /// Optional description typed in the create group form
+    pub create_group_description: String,
     pub selected_participants: std::collections::HashSet<String>,
-    pub my_groups: Vec<(String, String, usize)>, // (id, name, member_count)
+    pub my_groups: Vec<GroupSummary>,
     pub loading_groups: bool,
-    pub my_group_invites: Vec<(i64, String, String)>, // (invite_id, group_name, invited_by)
+    pub my_group_invites: Vec<GroupInvite>,
     pub loading_invites: bool,
-    pub friends_list: Vec<String>,
-    pub friend_requests: Vec<(String, String)>, // (username, message)
+    pub friends_list: Vec<crate::client::services::friend_service::FriendInfo>,
+    pub friend_requests: Vec<FriendRequest>,
+    pub blocked_users: Vec<String>,
+    pub group_search_query: String,
+    /// Public groups matching `group_search_query`, with their member count
+    pub group_search_results: Vec<(GroupSummary, i64)>,
+    /// Users with a pending friend request sent by us
+    pub pending_sent_requests: std::collections::HashSet<String>,
+    pub archived_chats: Vec<ArchivedChat>,
+    pub recent_conversations: Vec<ConversationSummary>,
+    /// Last time each chat (keyed by `ConversationSummary::key`) was left, used for unread counts
+    pub last_viewed: HashMap<String, i64>,
+    /// Dropdown under the account button of the main actions header
+    pub account_menu_open: bool,
+    pub change_password_old: String,
+    pub change_password_new: String,
+    pub change_password_confirm: String,
+    /// Server error shown inline in the change password form
+    pub change_password_error: Option<String>,
+    /// Username typed to confirm the account deletion, and the password it is checked with
+    pub delete_account_confirm: String,
+    pub delete_account_password: String,
+    /// Server error shown inline in the delete account form
+    pub delete_account_error: Option<String>,
+    /// Our active sessions, shown by the sessions view
+    pub sessions: Vec<crate::client::services::message_parser::SessionInfo>,
+    /// Private chats the user scrolled away from the bottom of; they are not snapped on new messages
+    pub user_scrolled_up: HashMap<String, bool>,
+    /// Private chats that received messages while scrolled up, shown as a "New messages" button
+    pub unseen_new_messages: std::collections::HashSet<String>,
+    /// Private chats with older messages still on the server
+    pub private_has_more: HashMap<String, bool>,
+    /// Private chats with a `LoadPrivateMessagesPage` request in flight
+    pub loading_older_messages: std::collections::HashSet<String>,
+    /// Cursor of the next older page of each group chat that has one
+    pub group_next_before: HashMap<String, i64>,
+    /// Group chats with a `LoadGroupMessagesPage` request in flight
+    pub loading_older_group_messages: std::collections::HashSet<String>,
+    /// Messages removed by `DeleteMessage` and awaiting confirmation, restored if the server refuses
+    pub pending_message_deletions: HashMap<i64, (ChatType, String, ChatMessage)>,
+    /// Own message whose content the input box is editing, set by `StartEditMessage`
+    pub editing_message: Option<i64>,
+    /// Message the next one sent answers, set by `StartReply`
+    pub replying_to: Option<i64>,
+    /// The chat picker of `OpenForwardPicker` is shown, for `forwarding_message`
+    pub forwarding_mode: bool,
+    pub forwarding_message: Option<i64>,
+    /// Latest admin announcement, shown above every view until it expires
+    pub broadcast_banner: Option<BroadcastBanner>,
+    /// Message whose quick reaction picker is open
+    pub reaction_picker: Option<i64>,
+    /// New name being typed in the group chat header, set by `StartRenameGroup`
+    pub group_rename_draft: Option<String>,
+    /// Description of each group chat opened, by group id; empty when it has none
+    pub group_descriptions: HashMap<String, String>,
+    /// Group chats whose description is shown in full rather than cut short
+    pub expanded_group_descriptions: std::collections::HashSet<String>,
+    /// Description being typed in the group chat header, set by `StartEditGroupDescription`
+    pub group_description_draft: Option<String>,
+    /// Status message of each user seen, ours included, by username; empty when unset
+    pub user_statuses: HashMap<String, String>,
+    /// Friends currently online, kept up to date by presence notifications
+    pub online_users: std::collections::HashSet<String>,
+    /// Status being typed in the main actions header, set by `SetStatus`
+    pub status_draft: Option<String>,
+    pub discover_query: String,
+    /// Page of public groups shown by the discover view
+    pub public_groups: PublicGroupsPage,
+    /// Invite code typed in the discover view
+    pub join_code_input: String,
+    pub invite_code_dialog: Option<InviteCodeDialog>,
+    /// Pinned messages of each group chat opened, by group id, most recently pinned first
+    pub pinned_messages: HashMap<String, Vec<crate::client::services::message_parser::PinnedMessage>>,
+    /// Group chats whose pinned banner is expanded
+    pub expanded_pinned_banners: std::collections::HashSet<String>,
+    /// Who is typing where, keyed by (chat, username) with the time of their last indicator.
+    /// The chat is the other user for private chats and the group id for group chats.
+    pub typing_users: HashMap<(String, String), std::time::Instant>,
+    /// A `SendTypingIndicator` is already scheduled
+    pub typing_indicator_pending: bool,
+    /// How far the other user has read each private chat (keyed by their username)
+    pub private_last_read: HashMap<String, i64>,
+    pub message_search_query: String,
+    pub message_search_results: Vec<crate::client::services::message_parser::SearchResult>,
+    /// View the message search was opened from, restored by its back button
+    pub message_search_origin: Option<AppState>,
 }
 
-impl Default for ChatAppState {
-    fn default() -> Self {
-        Self {
-            app_state: AppState::default(),
-            username: String::new(),
-            password: String::new(),
-            selected_host: HostType::default(),
-            manual_host: String::new(),
-            is_login: false,
-            loading: false,
-            error_message: None,
-            session_token: None,
-            show_password: false,
-            logger: Vec::new(),
-            users_search_query: String::new(),
-            users_search_results: Vec::new(),
-            current_message_input: String::new(),
-            private_chats: HashMap::new(),
-            loading_private_chats: std::collections::HashSet::new(),
-            last_http_timestamp: HashMap::new(),
-            polling_active: false,
-            websocket_polling_active: false,
-            group_chats: HashMap::new(),
-            loading_group_chats: std::collections::HashSet::new(),
-            group_polling_active: false,
-            create_group_name: String::new(),
-            selected_participants: std::collections::HashSet::new(),
-            my_groups: Vec::new(),
-            loading_groups: false,
-            my_group_invites: Vec::new(),
-            loading_invites: false,
-            friends_list: Vec::new(),
-            friend_requests: Vec::new(),
-        }
+/// Expiry preselected in the invite code dialog: one week
+pub const INVITE_CODE_DEFAULT_EXPIRY_SECS: i64 = 7 * 24 * 60 * 60;
+
+/// `MarkRead` for `chat_id` up to now
+fn mark_read(chat_type: ChatType, chat_id: String) -> Command<Message> {
+    let timestamp = chrono::Utc::now().timestamp();
+    Command::perform(async move { Message::MarkRead { chat_type, chat_id, timestamp } }, |msg| msg)
+}
+
+/// How long an "is typing" indicator stays up without a fresh one
+pub const TYPING_INDICATOR_TTL: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// How long an admin broadcast stays on screen
+pub const BROADCAST_BANNER_TTL: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Wait before reconnecting a dropped WebSocket
+pub const WEBSOCKET_RECONNECT_DELAY: std::time::Duration = std::time::Duration::from_secs(3);
+
+/// Address of the server selected on the login screen; defaults come from `ClientConfig`
+pub fn resolve_host(state: &ChatAppState) -> String {
+    let cfg = crate::server::config::ClientConfig::from_env();
+    match state.selected_host {
+        HostType::Localhost => format!("{}:{}", cfg.default_host, cfg.default_port),
+        HostType::Remote => format!("{}:{}", cfg.public_host, cfg.default_port),
+        HostType::Manual => state.manual_host.clone(),
     }
 }
 
 impl ChatAppState {
+    /// Number of recent conversations requested from the server
+    const RECENT_CHATS_LIMIT: usize = 20;
+
+    /// Leave the open chat, remembering when it was last seen, and go back to the main view
+    pub fn leave_current_chat(&mut self) {
+        let key = match &self.app_state {
+            AppState::PrivateChat(username) => Some(format!("{}:{}", ChatType::Private.as_str(), username)),
+            AppState::GroupChat(group_id, _) => Some(format!("{}:{}", ChatType::Group.as_str(), group_id)),
+            _ => None,
+        };
+        if let Some(key) = key {
+            self.last_viewed.insert(key, chrono::Utc::now().timestamp());
+        }
+        self.app_state = AppState::MainActions;
+    }
+
+    /// Polling flags after one of the start/stop polling messages; anything else leaves them alone.
+    /// At most one chat polls at a time, and only private chats do: groups are updated over the WebSocket.
+    pub fn update_polling_flags(&mut self, message: &Message) {
+        let private_polling = match message {
+            Message::StartMessagePolling { .. } => true,
+            Message::StopMessagePolling | Message::StartGroupMessagePolling { .. } | Message::StopGroupMessagePolling => false,
+            _ => return,
+        };
+        self.polling_active = private_polling;
+        self.group_polling_active = false;
+    }
+
+    /// Snap the open private chat `chat_id` to its newest message, unless the user is reading
+    /// older history, in which case the "New messages" button is shown instead
+    pub fn scroll_private_chat_to_end(&mut self, chat_id: &str) -> Command<Message> {
+        if !matches!(&self.app_state, AppState::PrivateChat(current) if current == chat_id) {
+            return Command::none();
+        }
+        if self.user_scrolled_up.get(chat_id).copied().unwrap_or(false) {
+            self.unseen_new_messages.insert(chat_id.to_string());
+            return Command::none();
+        }
+        scrollable::snap_to(scrollable::Id::new("messages_scroll"), scrollable::RelativeOffset::END)
+    }
+
+    /// Remember the senders' avatars and start downloading the ones not requested yet
+    pub fn fetch_missing_avatars(&mut self, messages: &[ChatMessage]) -> Command<Message> {
+        let mut commands = Vec::new();
+        for msg in messages {
+            let Some(url) = &msg.sender_avatar_url else { continue };
+            self.user_avatars.insert(msg.sender.clone(), url.clone());
+            if self.avatar_requests.insert(url.clone()) {
+                let url = url.clone();
+                commands.push(Command::perform(
+                    async move {
+                        let handle = match crate::client::services::avatar_service::fetch_avatar(&url).await {
+                            Ok(handle) => Some(handle),
+                            Err(e) => {
+                                println!("[APP] Failed to load avatar {}: {}", url, e);
+                                None
+                            }
+                        };
+                        Message::AvatarLoaded { url, handle }
+                    },
+                    |msg| msg,
+                ));
+            }
+        }
+        Command::batch(commands)
+    }
+
+    /// "alice is typing…" / "alice, bob are typing…" for `chat` (a username or group id)
+    pub fn typing_label(&self, chat: &str) -> Option<String> {
+        let mut names: Vec<&str> = self.typing_users.keys()
+            .filter(|(c, _)| c == chat)
+            .map(|(_, user)| user.as_str())
+            .collect();
+        names.sort_unstable();
+        match names.len() {
+            0 => None,
+            1 => Some(format!("{} is typing…", names[0])),
+            _ => Some(format!("{} are typing…", names.join(", "))),
+        }
+    }
+
+    /// Avatar URL to show for `msg`: its own, or the last one seen for the sender
+    pub fn avatar_url_for<'a>(&'a self, msg: &'a ChatMessage) -> Option<&'a str> {
+        msg.sender_avatar_url.as_deref().or_else(|| self.user_avatars.get(&msg.sender).map(String::as_str))
+    }
+
+    /// Loaded message with id `message_id`, from any chat (ids are unique across chats)
+    pub fn message_by_id(&self, message_id: i64) -> Option<&ChatMessage> {
+        self.private_chats.values()
+            .chain(self.group_chats.values())
+            .flat_map(|messages| messages.iter())
+            .find(|m| m.message_id == Some(message_id))
+    }
+
+    /// Drop every trace of `group_id`, which was deleted or we were removed from, leaving its chat
+    /// if it is open. Returns the group name, or `None` if the group was already forgotten (a deletion
+    /// arrives both as our own `/delete_group` reply and as a WebSocket event).
+    fn forget_group(&mut self, group_id: &str) -> Option<String> {
+        let open_name = match &self.app_state {
+            AppState::GroupChat(open_id, name) if open_id == group_id => Some(name.clone()),
+            _ => None,
+        };
+        let name = self.my_groups.iter().find(|g| g.id == group_id).map(|g| g.name.clone()).or(open_name)?;
+        self.my_groups.retain(|g| g.id != group_id);
+        self.group_search_results.retain(|(g, _)| g.id != group_id);
+        self.recent_conversations.retain(|c| !(c.chat_type == ChatType::Group && c.chat_id == group_id));
+        self.group_chats.remove(group_id);
+        self.group_members.remove(group_id);
+        self.group_descriptions.remove(group_id);
+        self.pinned_messages.remove(group_id);
+        if matches!(&self.app_state, AppState::GroupChat(open_id, _) if open_id == group_id) {
+            self.app_state = AppState::MainActions;
+        }
+        Some(name)
+    }
+
+    /// Show `new_name` for `group_id` everywhere the group is listed, and in its open chat
+    fn apply_group_rename(&mut self, group_id: &str, new_name: &str) {
+        for group in self.my_groups.iter_mut().filter(|g| g.id == group_id) {
+            group.name = new_name.to_string();
+        }
+        for (group, _) in self.group_search_results.iter_mut().filter(|(g, _)| g.id == group_id) {
+            group.name = new_name.to_string();
+        }
+        for conversation in self.recent_conversations.iter_mut().filter(|c| c.chat_type == ChatType::Group && c.chat_id == group_id) {
+            conversation.display_name = new_name.to_string();
+        }
+        if let AppState::GroupChat(open_id, open_name) = &mut self.app_state {
+            if open_id == group_id {
+                *open_name = new_name.to_string();
+                self.group_rename_draft = None;
+            }
+        }
+    }
+
+    /// Merge the server's recent chats with the locally cached conversations
+    /// (union of `private_chats` and `group_chats`), newest first, without archived chats.
+    fn build_recent_conversations(&self, from_server: Vec<ConversationSummary>) -> Vec<ConversationSummary> {
+        let mut conversations = from_server;
+        let local = self.private_chats.iter().map(|(k, v)| (ChatType::Private, k, v))
+            .chain(self.group_chats.iter().map(|(k, v)| (ChatType::Group, k, v)));
+        for (chat_type, chat_id, messages) in local {
+            let last = messages.iter().max_by_key(|m| m.timestamp);
+            match conversations.iter_mut().find(|c| c.chat_type == chat_type && &c.chat_id == chat_id) {
+                Some(existing) => {
+                    if let Some(last) = last {
+                        existing.last_message = Some(last.content.clone());
+                        existing.last_timestamp = existing.last_timestamp.max(Some(last.timestamp));
+                    }
+                }
+                None => {
+                    let display_name = match chat_type {
+                        ChatType::Private => chat_id.clone(),
+                        ChatType::Group => self.my_groups.iter()
+                            .find(|g| &g.id == chat_id)
+                            .map(|g| g.name.clone())
+                            .unwrap_or_else(|| chat_id.clone()),
+                    };
+                    conversations.push(ConversationSummary {
+                        chat_id: chat_id.clone(),
+                        chat_type,
+                        display_name,
+                        last_message: last.map(|m| m.content.clone()),
+                        last_timestamp: last.map(|m| m.timestamp),
+                        unread: 0,
+                    });
+                }
+            }
+        }
+
+        conversations.retain(|c| !self.archived_chats.iter().any(|a| a.chat_type == c.chat_type && a.chat_id == c.chat_id));
+        for conversation in &mut conversations {
+            let seen = self.last_viewed.get(&conversation.key()).copied().unwrap_or(0);
+            let cached = match conversation.chat_type {
+                ChatType::Private => self.private_chats.get(&conversation.chat_id),
+                ChatType::Group => self.group_chats.get(&conversation.chat_id),
+            };
+            conversation.unread = cached
+                .map(|msgs| msgs.iter().filter(|m| m.timestamp > seen && m.sender != self.username).count())
+                .unwrap_or(0);
+        }
+        conversations.sort_by_key(|c| std::cmp::Reverse(c.last_timestamp));
+        conversations
+    }
+
+    /// Send `/archive_chat` or `/unarchive_chat` and report the outcome in the logger
+    fn send_archive_command(&self, command: &'static str, chat_type: ChatType, chat_id: String, chat_service: &Arc<Mutex<ChatService>>) -> Command<Message> {
+        let Some(token) = self.session_token.clone() else { return Command::none() };
+        let host = resolve_host(self);
+        let svc = chat_service.clone();
+        Command::perform(
+            async move {
+                let mut guard = svc.lock().await;
+                match guard.send_command(&host, format!("{} {} {} {}", command, token, chat_type.as_str(), chat_id)).await {
+                    Ok(response) if response.starts_with("OK:") => Message::ArchiveActionResult {
+                        success: true,
+                        message: response.trim_start_matches("OK:").trim().to_string(),
+                    },
+                    Ok(response) => Message::ArchiveActionResult { success: false, message: response },
+                    Err(e) => Message::ArchiveActionResult { success: false, message: format!("Error: {}", e) },
+                }
+            },
+            |msg| msg,
+        )
+    }
+
     pub fn update(&mut self, message: Message, chat_service: &Arc<Mutex<ChatService>>) -> Command<Message> {
         use crate::client::gui::views::logger::{LogMessage, LogLevel};
         use crate::client::utils::session_store;
@@ -160,7 +634,7 @@ impl ChatAppState {
                 self.is_login = !self.is_login;
                 self.error_message = None;
             }
-            Message::AuthResult { success, message, token } => {
+            Message::AuthResult { success, message, token, .. } => {
                 println!("🟢 [DEBUG] AuthResult received - success: {}, message: '{}', token present: {}", success, message, token.is_some());
                 println!("🟢 [DEBUG] Current username before AuthResult: '{}'", self.username);
                 self.loading = false;
@@ -232,22 +706,12 @@ impl ChatAppState {
                     });
 
                     // Initialize WebSocket connection after successful authentication
-                    let ws_svc = chat_service.clone();
-                    let ws_token = self.session_token.clone().unwrap_or_default();
-                    let ws_config = crate::server::config::ClientConfig::from_env();
+                    let token = self.session_token.clone().unwrap_or_default();
+                    let username = self.username.clone();
                     
                     return Command::batch([
                         // Connect to WebSocket for real-time messaging
-                        Command::perform(
-                            async move {
-                                let mut guard = ws_svc.lock().await;
-                                match guard.connect_websocket(&ws_config.websocket_host, ws_config.websocket_port, &ws_token).await {
-                                    Ok(_) => Message::WebSocketConnected,
-                                    Err(e) => Message::WebSocketError { error: format!("WebSocket connection failed: {}", e) }
-                                }
-                            },
-                            |msg| msg,
-                        ),
+                        Command::perform(async move { Message::ConnectWebSocket { token, username } }, |msg| msg),
                         // Auto-clear logger after 2 seconds (same behavior as other views)
                         Command::perform(
                             async move {
@@ -310,17 +774,16 @@ impl ChatAppState {
                     message: "Logout successful".to_string(),
                 });
                 
-                // Reset state including WebSocket polling
+                // Reset state; clearing the session also ends the WebSocket subscription
                 self.session_token = None;
                 self.username.clear();
                 self.password.clear();
-                self.websocket_polling_active = false;  // Stop WebSocket polling
                 self.app_state = AppState::Registration;
-                self.websocket_polling_active = false; // Stop WebSocket polling
                 
                 // Clear all cached private chats to force reload on next login
                 self.private_chats.clear();
                 self.loading_private_chats.clear();
+                self.online_users.clear();
                 println!("[APP] 🧹 Cleared all cached private chats and loading states");
                 
                 // Clear all cached group chats to force reload on next login
@@ -353,272 +816,491 @@ impl ChatAppState {
                 });
             }
             Message::LogError(msg) => {
+                self.loading = false;
                 self.logger.push(LogMessage {
                     level: LogLevel::Error,
                     message: msg,
                 });
             }
-            Message::OpenMainActions => {
-                self.app_state = AppState::MainActions;
-            }
-            Message::OpenPrivateChat(username) => {
-                self.app_state = AppState::PrivateChat(username.clone());
-                self.current_message_input.clear();
-                
-                // If we already have messages cached, don't mark as loading
-                if !self.private_chats.contains_key(&username) {
-                    self.loading_private_chats.insert(username.clone());
-                    
-                    // Load messages once - with WebSocket connected, no need for polling
-                    return Command::perform(
-                        async move { Message::LoadPrivateMessages { with: username } },
-                        |msg| msg,
-                    );
-                }
-                
-                return Command::none();
-            }
-            Message::OpenGroupChat(group_id, group_name) => {
-                self.app_state = AppState::GroupChat(group_id.clone(), group_name.clone());
-                self.current_message_input.clear();
-                // Mark this group chat as loading so the UI shows a loader
-                self.loading_group_chats.insert(group_id.clone());
-
-                // Load initial messages via WebSocket (no polling needed)
-                return Command::perform(
-                    async move { Message::LoadGroupMessages { group_id } },
-                    |msg| msg,
-                );
-            }
-            Message::OpenUsersList { kind } => {
-                self.app_state = AppState::UsersList(kind.clone());
-                self.users_search_query.clear();
-                self.users_search_results.clear();
-                
-                // Auto-load users based on kind
-                let svc = chat_service.clone();
-                let cfg = crate::server::config::ClientConfig::from_env();
-                let host = format!("{}:{}", cfg.default_host, cfg.default_port);
+            Message::ToggleAccountMenu => {
+                self.account_menu_open = !self.account_menu_open;
+            }
+            Message::OpenChangePassword => {
+                self.account_menu_open = false;
+                self.change_password_old.clear();
+                self.change_password_new.clear();
+                self.change_password_confirm.clear();
+                self.change_password_error = None;
+                self.app_state = AppState::ChangePassword;
+            }
+            Message::OpenDeleteAccount => {
+                self.account_menu_open = false;
+                self.delete_account_confirm.clear();
+                self.delete_account_password.clear();
+                self.delete_account_error = None;
+                self.app_state = AppState::DeleteAccount;
+            }
+            Message::OpenSessions => {
+                self.account_menu_open = false;
+                self.app_state = AppState::Sessions;
+                self.loading = true;
                 let token = self.session_token.clone().unwrap_or_default();
-                
+                let svc = chat_service.clone();
+                let host = resolve_host(self);
                 return Command::perform(
                     async move {
-                        let result = if kind == "Online" {
-                            UsersService::list_online(&svc, &host, &token).await
-                        } else {
-                            UsersService::list_all(&svc, &host).await
-                        };
-                        
-                        match result {
-                            Ok(users) => Message::UsersListLoaded { kind, list: users },
-                            Err(_) => Message::UsersListLoaded { kind, list: vec![] },
+                        let mut guard = svc.lock().await;
+                        match guard.list_sessions(&host, &token).await {
+                            Ok(sessions) => Message::SessionsLoaded { sessions },
+                            Err(e) => Message::LogError(format!("Could not load your sessions: {}", e)),
                         }
                     },
                     |msg| msg,
                 );
             }
-            Message::OpenCreateGroup => {
-                self.app_state = AppState::CreateGroup;
-                self.create_group_name.clear();
-                self.selected_participants.clear();
-                self.users_search_query.clear();
-                self.users_search_results.clear();
-                
-                // Auto-load all users for participant selection
+            Message::SessionsLoaded { sessions } => {
+                self.loading = false;
+                self.sessions = sessions;
+            }
+            Message::RevokeSession { id } => {
+                let token = self.session_token.clone().unwrap_or_default();
                 let svc = chat_service.clone();
-                let cfg = crate::server::config::ClientConfig::from_env();
-                let host = format!("{}:{}", cfg.default_host, cfg.default_port);
-                
+                let host = resolve_host(self);
                 return Command::perform(
                     async move {
-                        match UsersService::list_all(&svc, &host).await {
-                            Ok(users) => Message::UsersListLoaded { kind: "CreateGroup".to_string(), list: users },
-                            Err(_) => Message::UsersListLoaded { kind: "CreateGroup".to_string(), list: vec![] },
+                        let mut guard = svc.lock().await;
+                        match guard.revoke_session(&host, &token, &id).await {
+                            Ok(_) => Message::SessionRevoked { id },
+                            Err(e) => Message::LogError(format!("Could not revoke the session: {}", e)),
                         }
                     },
                     |msg| msg,
                 );
             }
-            Message::OpenMyGroups => {
-                self.app_state = AppState::MyGroups;
-                self.loading_groups = true;
-                self.my_groups.clear();
-                
-                // Load user's groups
+            Message::SessionRevoked { id } => {
+                self.sessions.retain(|session| session.id != id);
+                self.logger.push(LogMessage { level: LogLevel::Success, message: "Session revoked".to_string() });
+            }
+            Message::DeleteAccountConfirmChanged(value) => {
+                self.delete_account_confirm = value;
+            }
+            Message::DeleteAccountPasswordChanged(value) => {
+                self.delete_account_password = value;
+            }
+            Message::SubmitDeleteAccount { password } => {
                 if let Some(token) = &self.session_token {
+                    self.loading = true;
+                    self.delete_account_error = None;
                     let svc = chat_service.clone();
-                    let token_clone = token.clone();
-                    let cfg = crate::server::config::ClientConfig::from_env();
-                    let host = format!("{}:{}", cfg.default_host, cfg.default_port);
-                    
+                    let token = token.clone();
+                    let host = resolve_host(self);
                     return Command::perform(
                         async move {
                             let mut guard = svc.lock().await;
-                            match guard.send_command(&host, format!("/my_groups {}", token_clone)).await {
-                                Ok(response) => {
-                                    if response.starts_with("OK: My groups:") {
-                                        let groups_part = response.trim_start_matches("OK: My groups:").trim();
-                                        let groups: Vec<(String, String, usize)> = if groups_part.is_empty() {
-                                            vec![]
-                                        } else {
-                                            groups_part.split(',').filter_map(|s| {
-                                                let s = s.trim();
-                                                if let Some((id, name)) = s.split_once(':') {
-                                                    // For now, set member count to 1 (will be improved with server support)
-                                                    Some((id.to_string(), name.to_string(), 1))
-                                                } else {
-                                                    None
-                                                }
-                                            }).collect()
-                                        };
-                                        Message::MyGroupsLoaded { groups }
-                                    } else {
-                                        Message::MyGroupsLoaded { groups: vec![] }
-                                    }
-                                }
-                                Err(_) => Message::MyGroupsLoaded { groups: vec![] },
+                            match guard.delete_account(&host, &token, &password).await {
+                                Ok(_) => Message::AccountDeleted { success: true, message: "Your account has been deleted".to_string() },
+                                Err(e) => Message::AccountDeleted { success: false, message: e.to_string() },
                             }
                         },
                         |msg| msg,
                     );
                 }
             }
-            Message::OpenInviteToGroup { group_id, group_name } => {
-                self.app_state = AppState::InviteToGroup { group_id: group_id.clone(), group_name };
-                self.users_search_query.clear();
-                self.users_search_results.clear();
-                
-                // Auto-load all users for invitation
-                let svc = chat_service.clone();
-                let cfg = crate::server::config::ClientConfig::from_env();
-                let host = format!("{}:{}", cfg.default_host, cfg.default_port);
-        let _host = format!("{}:{}", cfg.default_host, cfg.default_port);
-        let group_id_for_filter = group_id.clone();
-        let token_clone = self.session_token.clone().unwrap_or_default();
-                
+            Message::AccountDeleted { success, message } => {
+                self.loading = false;
+                if !success {
+                    self.delete_account_error = Some(message);
+                    return Command::none();
+                }
+                let _ = session_store::clear_session_token();
+                // Nothing of the old account survives; only the server choice is kept for the next login
+                *self = ChatAppState {
+                    app_state: AppState::Registration,
+                    selected_host: self.selected_host,
+                    manual_host: std::mem::take(&mut self.manual_host),
+                    ..Default::default()
+                };
+                self.logger.push(LogMessage { level: LogLevel::Info, message });
                 return Command::perform(
                     async move {
-                        // Get all users and group members to filter
-                        let all_users = UsersService::list_all(&svc, &host).await.unwrap_or_default();
-                        
-                        // Get group members to filter them out
-                        let mut guard = svc.lock().await;
-            let group_members_resp = guard.send_command(&host, format!("/group_members {} {}", token_clone, group_id_for_filter)).await.unwrap_or_default();
-                        drop(guard);
-                        
-                        // Parse group members (format "OK: Group members: user1, user2")
-                        let existing_members: Vec<String> = if group_members_resp.starts_with("OK: Group members:") {
-                            group_members_resp.trim_start_matches("OK: Group members:").trim()
-                                .split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect()
-                        } else {
-                            vec![]
-                        };
-                        
-                        println!("[INVITE] Group members response: {}", group_members_resp);
-                        println!("[INVITE] Parsed existing members: {:?}", existing_members);
-                        println!("[INVITE] All users before filter: {:?}", all_users);
-                        
-                        // Filter out existing members and current user
-                        let filtered_users: Vec<String> = all_users.into_iter()
-                            .filter(|user| !existing_members.contains(user))
-                            .collect();
-                        
-                        println!("[INVITE] Filtered users (available to invite): {:?}", filtered_users);
-                        
-                        Message::UsersListLoaded { kind: "Invite".to_string(), list: filtered_users }
+                        tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
+                        Message::ClearLog
                     },
                     |msg| msg,
                 );
             }
-            Message::OpenSendFriendRequest => {
-                self.app_state = AppState::SendFriendRequest;
-                self.users_search_query.clear();
-                self.users_search_results.clear();
-                
-                // Auto-load all users for friend request
-                let svc = chat_service.clone();
-                let cfg = crate::server::config::ClientConfig::from_env();
-                let host = format!("{}:{}", cfg.default_host, cfg.default_port);
-                
-                return Command::perform(
-                    async move {
-                        match UsersService::list_all(&svc, &host).await {
-                            Ok(users) => Message::UsersListLoaded { kind: "FriendRequest".to_string(), list: users },
-                            Err(_) => Message::UsersListLoaded { kind: "FriendRequest".to_string(), list: vec![] },
-                        }
-                    },
-                    |msg| msg,
-                );
+            Message::ChangePasswordOldChanged(value) => {
+                self.change_password_old = value;
             }
-            Message::OpenViewFriends => {
-                self.app_state = AppState::ViewFriends;
-                self.loading = true;
-                
-                // Load user's friends
+            Message::ChangePasswordNewChanged(value) => {
+                self.change_password_new = value;
+            }
+            Message::ChangePasswordConfirmChanged(value) => {
+                self.change_password_confirm = value;
+            }
+            Message::SubmitChangePassword { old, new } => {
                 if let Some(token) = &self.session_token {
+                    self.loading = true;
+                    self.change_password_error = None;
                     let svc = chat_service.clone();
                     let token_clone = token.clone();
-                    let cfg = crate::server::config::ClientConfig::from_env();
-                    let host = format!("{}:{}", cfg.default_host, cfg.default_port);
-                    
+                    let host = resolve_host(self);
                     return Command::perform(
                         async move {
                             let mut guard = svc.lock().await;
-                            match guard.send_command(&host, format!("/list_friends {}", token_clone)).await {
-                                Ok(response) => {
-                                    if response.starts_with("OK: Friends:") {
-                                        let friends_part = response.trim_start_matches("OK: Friends:").trim();
-                                        let friends: Vec<String> = if friends_part.is_empty() {
-                                            vec![]
-                                        } else {
-                                            friends_part.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect()
-                                        };
-                                        Message::FriendsLoaded { friends }
-                                    } else {
-                                        Message::FriendsLoaded { friends: vec![] }
-                                    }
-                                }
-                                Err(_) => Message::FriendsLoaded { friends: vec![] },
+                            match guard.send_command(&host, format!("/change_password {} {} {}", token_clone, old, new)).await {
+                                Ok(response) if response.starts_with("OK:") => Message::ChangePasswordResult {
+                                    success: true,
+                                    message: response.trim_start_matches("OK:").trim().to_string(),
+                                },
+                                Ok(response) => Message::ChangePasswordResult {
+                                    success: false,
+                                    message: response.trim_start_matches("ERR:").trim().to_string(),
+                                },
+                                Err(e) => Message::ChangePasswordResult { success: false, message: format!("Connection error: {}", e) },
                             }
                         },
                         |msg| msg,
                     );
                 }
             }
-            Message::OpenFriendRequests => {
-                self.app_state = AppState::FriendRequests;
-                self.loading = true;
-                
-                // Load user's friend requests
+            Message::ChangePasswordResult { success, message } => {
+                self.loading = false;
+                if success {
+                    self.change_password_old.clear();
+                    self.change_password_new.clear();
+                    self.change_password_confirm.clear();
+                    self.logger.push(LogMessage {
+                        level: LogLevel::Success,
+                        message,
+                    });
+                    return Command::perform(async { Message::OpenMainActions }, |msg| msg);
+                } else {
+                    self.change_password_error = Some(message);
+                }
+            }
+            Message::OpenMainActions => {
+                self.app_state = AppState::MainActions;
+                let mut commands = vec![Command::perform(async { Message::LoadRecentConversations }, |msg| msg)];
+                // Refresh pending friend requests for the notification badge
                 if let Some(token) = &self.session_token {
                     let svc = chat_service.clone();
                     let token_clone = token.clone();
-                    let cfg = crate::server::config::ClientConfig::from_env();
-                    let host = format!("{}:{}", cfg.default_host, cfg.default_port);
-                    
-                    return Command::perform(
+                    let host = resolve_host(self);
+                    commands.push(Command::perform(
                         async move {
-                            let mut guard = svc.lock().await;
-                            match guard.send_command(&host, format!("/received_friend_requests {}", token_clone)).await {
-                                Ok(response) => {
-                                    if response.starts_with("OK: Richieste ricevute:") {
-                                        let requests_part = response.trim_start_matches("OK: Richieste ricevute:").trim();
-                                        let requests: Vec<(String, String)> = if requests_part.is_empty() {
-                                            vec![]
-                                        } else {
-                                            requests_part.split(" | ").filter_map(|s| {
-                                                if let Some((username, message)) = s.trim().split_once(':') {
-                                                    Some((username.trim().to_string(), message.trim().to_string()))
-                                                } else {
-                                                    None
-                                                }
-                                            }).collect()
-                                        };
-                                        Message::FriendRequestsLoaded { requests }
-                                    } else {
-                                        Message::FriendRequestsLoaded { requests: vec![] }
-                                    }
-                                }
+                            match FriendService::received_requests(&svc, &host, &token_clone).await {
+                                Ok(requests) => Message::FriendRequestsLoaded { requests },
+                                Err(_) => Message::FriendRequestsLoaded { requests: vec![] },
+                            }
+                        },
+                        |msg| msg,
+                    ));
+                    // Our own status, shown under the header
+                    let svc = chat_service.clone();
+                    let token_clone = token.clone();
+                    let host = resolve_host(self);
+                    let username = self.username.clone();
+                    commands.push(Command::perform(
+                        async move {
+                            let status = FriendService::get_status(&svc, &host, &token_clone, &username).await.unwrap_or_default();
+                            Message::StatusLoaded { username, status }
+                        },
+                        |msg| msg,
+                    ));
+                }
+                return Command::batch(commands);
+            }
+            Message::LoadRecentConversations => {
+                if let Some(token) = &self.session_token {
+                    let svc = chat_service.clone();
+                    let token_clone = token.clone();
+                    let host = resolve_host(self);
+                    return Command::perform(
+                        async move {
+                            let mut guard = svc.lock().await;
+                            match guard.recent_chats(&host, &token_clone, Self::RECENT_CHATS_LIMIT).await {
+                                Ok(conversations) => Message::RecentConversationsLoaded { conversations },
+                                Err(_) => Message::RecentConversationsLoaded { conversations: vec![] },
+                            }
+                        },
+                        |msg| msg,
+                    );
+                }
+            }
+            Message::RecentConversationsLoaded { conversations } => {
+                self.recent_conversations = self.build_recent_conversations(conversations);
+            }
+            Message::OpenPrivateChat(username) => {
+                self.app_state = AppState::PrivateChat(username.clone());
+                self.current_message_input.clear();
+                self.editing_message = None;
+                self.replying_to = None;
+                self.forwarding_mode = false;
+                // A freshly opened chat starts at the bottom
+                self.user_scrolled_up.remove(&username);
+                self.unseen_new_messages.remove(&username);
+                
+                // Opening the chat reads it; also learn how far the other user has read it
+                let mut commands = vec![
+                    mark_read(ChatType::Private, username.clone()),
+                    Command::perform({
+                        let with = username.clone();
+                        async move { Message::LoadReadReceipts { with } }
+                    }, |msg| msg),
+                ];
+
+                // Fetch the peer's public key up front so the first message is already sealed
+                if let Some(token) = self.session_token.clone() {
+                    let svc = chat_service.clone();
+                    let host = resolve_host(self);
+                    let with = username.clone();
+                    commands.push(Command::perform(
+                        async move {
+                            let mut guard = svc.lock().await;
+                            if guard.e2e_secret.is_some() {
+                                guard.peer_public_key(&host, &token, &with).await;
+                            }
+                            Message::NoOp
+                        },
+                        |msg| msg,
+                    ));
+                }
+
+                // If we already have messages cached, don't mark as loading
+                if !self.private_chats.contains_key(&username) {
+                    self.loading_private_chats.insert(username.clone());
+                    
+                    // Load messages once - with WebSocket connected, no need for polling
+                    commands.push(Command::perform(
+                        async move { Message::LoadPrivateMessages { with: username } },
+                        |msg| msg,
+                    ));
+                }
+                
+                return Command::batch(commands);
+            }
+            Message::OpenGroupChat(group_id, group_name) => {
+                self.app_state = AppState::GroupChat(group_id.clone(), group_name.clone());
+                self.current_message_input.clear();
+                self.editing_message = None;
+                self.replying_to = None;
+                self.forwarding_mode = false;
+                self.group_rename_draft = None;
+                self.group_description_draft = None;
+                self.invite_code_dialog = None;
+                // Mark this group chat as loading so the UI shows a loader
+                self.loading_group_chats.insert(group_id.clone());
+
+                // Load initial messages via WebSocket (no polling needed); members give our role
+                return Command::batch([
+                    mark_read(ChatType::Group, group_id.clone()),
+                    Command::perform({
+                        let group_id = group_id.clone();
+                        async move { Message::LoadGroupMembers { group_id } }
+                    }, |msg| msg),
+                    Command::perform({
+                        let group_id = group_id.clone();
+                        async move { Message::LoadGroupDescription { group_id } }
+                    }, |msg| msg),
+                    Command::perform({
+                        let group_id = group_id.clone();
+                        async move { Message::LoadPinnedMessages { group_id } }
+                    }, |msg| msg),
+                    Command::perform(
+                        async move { Message::LoadGroupMessages { group_id } },
+                        |msg| msg,
+                    ),
+                ]);
+            }
+            Message::OpenUsersList { kind } => {
+                self.app_state = AppState::UsersList(kind.clone());
+                self.users_search_query.clear();
+                self.users_search_results.clear();
+                
+                // Auto-load users based on kind
+                let svc = chat_service.clone();
+                let host = resolve_host(self);
+                let token = self.session_token.clone().unwrap_or_default();
+                
+                let (friends_svc, friends_host, friends_token) = (svc.clone(), host.clone(), token.clone());
+                let (sent_svc, sent_host, sent_token) = (svc.clone(), host.clone(), token.clone());
+                let (presence_svc, presence_host) = (svc.clone(), host.clone());
+
+                return Command::batch([
+                    Command::perform(
+                        async move {
+                            let result = if kind == "Online" {
+                                UsersService::list_online(&svc, &host, &token).await
+                            } else {
+                                UsersService::list_all(&svc, &host).await
+                            };
+
+                            match result {
+                                Ok(users) => Message::UsersListLoaded { kind, list: users },
+                                Err(_) => Message::UsersListLoaded { kind, list: vec![] },
+                            }
+                        },
+                        |msg| msg,
+                    ),
+                    // Friends and pending requests decide which action each row shows
+                    Command::perform(
+                        async move {
+                            match FriendService::list_friends(&friends_svc, &friends_host, &friends_token).await {
+                                Ok(friends) => Message::FriendsLoaded { friends },
+                                Err(_) => Message::FriendsLoaded { friends: vec![] },
+                            }
+                        },
+                        |msg| msg,
+                    ),
+                    Command::perform(
+                        async move {
+                            match FriendService::sent_requests(&sent_svc, &sent_host, &sent_token).await {
+                                Ok(usernames) => Message::SentFriendRequestsLoaded { usernames },
+                                Err(_) => Message::SentFriendRequestsLoaded { usernames: vec![] },
+                            }
+                        },
+                        |msg| msg,
+                    ),
+                    // "Online" or "Last seen ..." under each user
+                    Command::perform(
+                        async move {
+                            let presence = UsersService::list_presence(&presence_svc, &presence_host).await.unwrap_or_default();
+                            Message::UserPresenceLoaded { presence }
+                        },
+                        |msg| msg,
+                    ),
+                ]);
+            }
+            Message::UserPresenceLoaded { presence } => {
+                self.user_presence = presence;
+            }
+            Message::OpenCreateGroup => {
+                self.app_state = AppState::CreateGroup;
+                self.create_group_name.clear();
+                self.create_group_description.clear();
+                self.selected_participants.clear();
+                self.users_search_query.clear();
+                self.users_search_results.clear();
+                
+                // Auto-load all users for participant selection
+                let svc = chat_service.clone();
+                let host = resolve_host(self);
+                
+                return Command::perform(
+                    async move {
+                        match UsersService::list_all(&svc, &host).await {
+                            Ok(users) => Message::UsersListLoaded { kind: "CreateGroup".to_string(), list: users },
+                            Err(_) => Message::UsersListLoaded { kind: "CreateGroup".to_string(), list: vec![] },
+                        }
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::OpenMyGroups => {
+                self.app_state = AppState::MyGroups;
+                self.loading_groups = true;
+                self.my_groups.clear();
+                
+                // Load user's groups
+                if let Some(token) = &self.session_token {
+                    let svc = chat_service.clone();
+                    let token_clone = token.clone();
+                    let host = resolve_host(self);
+                    
+                    return Command::perform(
+                        async move {
+                            match GroupService::my_groups(&svc, &host, &token_clone).await {
+                                Ok(groups) => Message::MyGroupsLoaded { groups },
+                                Err(e) => Message::MyGroupsFailed { message: format!("Error loading groups: {}", e) },
+                            }
+                        },
+                        |msg| msg,
+                    );
+                }
+            }
+            Message::OpenInviteToGroup { group_id, group_name } => {
+                self.app_state = AppState::InviteToGroup { group_id: group_id.clone(), group_name };
+                self.users_search_query.clear();
+                self.users_search_results.clear();
+                
+                // Auto-load all users for invitation
+                let svc = chat_service.clone();
+                let host = resolve_host(self);
+                let group_id_for_filter = group_id.clone();
+                let token_clone = self.session_token.clone().unwrap_or_default();
+
+                return Command::perform(
+                    async move {
+                        // Get all users and group members to filter
+                        let all_users = UsersService::list_all(&svc, &host).await.unwrap_or_default();
+
+                        // Get group members to filter them out (the current user is one of them)
+                        let members = GroupService::get_group_members(&svc, &host, &token_clone, &group_id_for_filter).await.unwrap_or_default();
+                        println!("[INVITE] Group members: {:?}", members);
+
+                        let filtered_users = GroupService::invite_candidates(all_users, &members);
+                        println!("[INVITE] Filtered users (available to invite): {:?}", filtered_users);
+
+                        Message::UsersListLoaded { kind: "Invite".to_string(), list: filtered_users }
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::OpenSendFriendRequest => {
+                self.app_state = AppState::SendFriendRequest;
+                self.users_search_query.clear();
+                self.users_search_results.clear();
+                
+                // Auto-load all users for friend request
+                let svc = chat_service.clone();
+                let host = resolve_host(self);
+                
+                return Command::perform(
+                    async move {
+                        match UsersService::list_all(&svc, &host).await {
+                            Ok(users) => Message::UsersListLoaded { kind: "FriendRequest".to_string(), list: users },
+                            Err(_) => Message::UsersListLoaded { kind: "FriendRequest".to_string(), list: vec![] },
+                        }
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::OpenViewFriends => {
+                self.app_state = AppState::ViewFriends;
+                self.loading = true;
+                
+                // Load user's friends
+                if let Some(token) = &self.session_token {
+                    let svc = chat_service.clone();
+                    let token_clone = token.clone();
+                    let host = resolve_host(self);
+                    
+                    return Command::perform(
+                        async move {
+                            match FriendService::list_friends(&svc, &host, &token_clone).await {
+                                Ok(friends) => Message::FriendsLoaded { friends },
+                                Err(_) => Message::FriendsLoaded { friends: vec![] },
+                            }
+                        },
+                        |msg| msg,
+                    );
+                }
+            }
+            Message::OpenFriendRequests => {
+                self.app_state = AppState::FriendRequests;
+                self.loading = true;
+                
+                // Load user's friend requests
+                if let Some(token) = &self.session_token {
+                    let svc = chat_service.clone();
+                    let token_clone = token.clone();
+                    let host = resolve_host(self);
+                    
+                    return Command::perform(
+                        async move {
+                            match FriendService::received_requests(&svc, &host, &token_clone).await {
+                                Ok(requests) => Message::FriendRequestsLoaded { requests },
                                 Err(_) => Message::FriendRequestsLoaded { requests: vec![] },
                             }
                         },
@@ -630,29 +1312,18 @@ impl ChatAppState {
                 if let Some(token) = &self.session_token {
                     let svc = chat_service.clone();
                     let token_clone = token.clone();
-                    let cfg = crate::server::config::ClientConfig::from_env();
-                    let host = format!("{}:{}", cfg.default_host, cfg.default_port);
+                    let host = resolve_host(self);
                     
                     return Command::perform(
                         async move {
-                            let mut guard = svc.lock().await;
-                            match guard.send_command(&host, format!("/reject_friend_request {} {}", token_clone, username)).await {
-                                Ok(response) => {
-                                    if response.starts_with("OK:") {
-                                        Message::FriendRequestResult { 
-                                            success: true, 
-                                            message: format!("Friend request from {} rejected.", username) 
-                                        }
-                                    } else {
-                                        Message::FriendRequestResult { 
-                                            success: false, 
-                                            message: response 
-                                        }
-                                    }
-                                }
-                                Err(e) => Message::FriendRequestResult { 
-                                    success: false, 
-                                    message: format!("Error rejecting friend request: {}", e) 
+                            match FriendService::reject_request(&svc, &host, &token_clone, &username).await {
+                                Ok(_) => Message::FriendRequestResult {
+                                    success: true,
+                                    message: format!("Friend request from {} rejected.", username)
+                                },
+                                Err(e) => Message::FriendActionFailed {
+                                    level: e.log_level(),
+                                    message: format!("Error rejecting friend request: {}", e)
                                 },
                             }
                         },
@@ -664,30 +1335,111 @@ impl ChatAppState {
                 let token = self.session_token.clone().unwrap_or_default();
                 let svc = chat_service.clone();
                 let username_clone = username.clone();
-                let cfg = crate::server::config::ClientConfig::from_env();
-                let host = format!("{}:{}", cfg.default_host, cfg.default_port);
+                let host = resolve_host(self);
                 return Command::perform(
                     async move {
-                        let mut guard = svc.lock().await;
-                        let cmd = format!("/accept_friend_request {} {}", token, username_clone);
-                        match guard.send_command(&host, cmd).await {
-                            Ok(response) => {
-                                if response.starts_with("OK:") {
-                                    Message::FriendRequestResult { success: true, message: "Friend request accepted!".to_string() }
-                                } else {
-                                    Message::FriendRequestResult { success: false, message: response }
+                        match FriendService::accept_request(&svc, &host, &token, &username_clone).await {
+                            Ok(_) => Message::FriendRequestResult { success: true, message: "Friend request accepted!".to_string() },
+                            Err(e) => Message::FriendActionFailed { level: e.log_level(), message: format!("Error accepting friend request: {}", e) },
+                        }
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::SentFriendRequestsLoaded { usernames } => {
+                self.pending_sent_requests = usernames.into_iter().collect();
+            }
+            Message::SendFriendRequest { to, message } => {
+                let token = self.session_token.clone().unwrap_or_default();
+                let svc = chat_service.clone();
+                let host = resolve_host(self);
+                return Command::perform(
+                    async move {
+                        match FriendService::send_request(&svc, &host, &token, &to, &message).await {
+                            Ok(_) => Message::FriendRequestSent { to },
+                            Err(e) => Message::FriendActionFailed { level: e.log_level(), message: format!("Error sending friend request: {}", e) },
+                        }
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::FriendRequestSent { to } => {
+                self.logger.push(LogMessage {
+                    level: LogLevel::Success,
+                    message: format!("Friend request sent to {}", to),
+                });
+                self.pending_sent_requests.insert(to);
+
+                return Command::perform(
+                    async move {
+                        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+                        Message::ClearLog
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::FriendsLoaded { friends } => {
+                self.loading = false;
+                self.friends_list = friends;
+                self.online_users = self.friends_list.iter().filter(|f| f.is_online).map(|f| f.username.clone()).collect();
+                // Status messages are shown under each friend; later changes, and going
+                // online or offline, arrive over WebSocket
+                if matches!(self.app_state, AppState::ViewFriends) {
+                    let token = self.session_token.clone().unwrap_or_default();
+                    let host = resolve_host(self);
+                    let usernames: Vec<String> = self.friends_list.iter().map(|f| f.username.clone()).collect();
+                    let subscribe = {
+                        let (svc, token, host) = (chat_service.clone(), token.clone(), host.clone());
+                        Command::perform(
+                            async move {
+                                if let Err(e) = FriendService::subscribe_presence(&svc, &host, &token, &usernames).await {
+                                    println!("[APP] Presence subscription failed: {}", e);
                                 }
-                            }
-                            Err(e) => Message::FriendRequestResult { success: false, message: format!("Error: {}", e) }
+                                Message::NoOp
+                            },
+                            |msg| msg,
+                        )
+                    };
+                    let commands = std::iter::once(subscribe).chain(self.friends_list.iter().map(|friend| {
+                        let svc = chat_service.clone();
+                        let (token, host, username) = (token.clone(), host.clone(), friend.username.clone());
+                        Command::perform(
+                            async move {
+                                let status = FriendService::get_status(&svc, &host, &token, &username).await.unwrap_or_default();
+                                Message::StatusLoaded { username, status }
+                            },
+                            |msg| msg,
+                        )
+                    }));
+                    return Command::batch(commands);
+                }
+            }
+            Message::StatusLoaded { username, status } => {
+                self.user_statuses.insert(username, status);
+            }
+            Message::SetStatus(status) => {
+                self.status_draft = Some(status);
+            }
+            Message::CancelStatus => {
+                self.status_draft = None;
+            }
+            Message::SaveStatus { status } => {
+                self.status_draft = None;
+                let token = self.session_token.clone().unwrap_or_default();
+                let username = self.username.clone();
+                let svc = chat_service.clone();
+                let host = resolve_host(self);
+                return Command::perform(
+                    async move {
+                        let status = status.trim().to_string();
+                        match FriendService::set_status(&svc, &host, &token, &status).await {
+                            Ok(_) => Message::StatusLoaded { username, status },
+                            Err(e) => Message::LogError(format!("Could not update your status: {}", e)),
                         }
                     },
                     |msg| msg,
                 );
             }
-            Message::FriendsLoaded { friends } => {
-                self.loading = false;
-                self.friends_list = friends;
-            }
             Message::FriendRequestsLoaded { requests } => {
                 self.loading = false;
                 self.friend_requests = requests;
@@ -711,48 +1463,461 @@ impl ChatAppState {
                     // This ensures the UI updates instantly without waiting for reload
                     // The message will be parsed to extract the username that was processed
                     if let Some(processed_username) = extract_username_from_friend_action(&message) {
-                        self.friend_requests.retain(|(username, _)| username != &processed_username);
+                        self.friend_requests.retain(|request| request.from_username != processed_username);
                     }
                     // Reload friend requests to remove the processed one
-                    let cfg = crate::server::config::ClientConfig::from_env();
-                    let host = format!("{}:{}", cfg.default_host, cfg.default_port);
+                    let host = resolve_host(self);
                     let token = self.session_token.clone().unwrap_or_default();
                     let svc = chat_service.clone();
                      return iced::Command::perform(
                         async move {
-                            let mut guard = svc.lock().await;
-                            let cmd = format!("/received_friend_requests {}", token);
-                            match guard.send_command(&host, cmd).await {
-                                Ok(response) => {
-                                    if response.starts_with("OK:") {
-                                        // Parse the response to extract friend requests
-                                        let after = response.splitn(3, ':').nth(2).unwrap_or("");
-                                        let requests: Vec<(String, String)> = after
-                                            .split('|')
-                                            .filter_map(|item| {
-                                                let item = item.trim();
-                                                if item.is_empty() { return None; }
-                                                if let Some(colon_pos) = item.find(':') {
-                                                    let username = item[..colon_pos].trim().to_string();
-                                                    let message = item[colon_pos + 1..].trim().to_string();
-                                                    Some((username, message))
-                                                } else {
-                                                    None
-                                                }
-                                            })
-                                            .collect();
-                                        Message::FriendRequestsLoaded { requests }
-                                    } else {
-                                        Message::FriendRequestsLoaded { requests: vec![] }
-                                    }
-                                }
-                                Err(_) => Message::FriendRequestsLoaded { requests: vec![] }
+                            match FriendService::received_requests(&svc, &host, &token).await {
+                                Ok(requests) => Message::FriendRequestsLoaded { requests },
+                                Err(_) => Message::FriendRequestsLoaded { requests: vec![] },
                             }
                         },
                         |msg| msg,
                     );
                 
                 
+            }
+            Message::FriendActionFailed { level, message } => {
+                self.logger.push(LogMessage { level, message });
+
+                // Auto-clear logger after 2 seconds
+                return Command::perform(
+                    async move {
+                        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+                        Message::ClearLog
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::OpenMessageSearch => {
+                if !matches!(self.app_state, AppState::SearchResults) {
+                    self.message_search_origin = Some(self.app_state.clone());
+                }
+                self.app_state = AppState::SearchResults;
+                self.message_search_query.clear();
+                self.message_search_results.clear();
+            }
+            Message::MessageSearchQueryChanged(query) => {
+                self.message_search_query = query;
+            }
+            Message::SearchMessages { query } => {
+                let query = query.trim().to_string();
+                if query.is_empty() {
+                    self.message_search_results.clear();
+                    return Command::none();
+                }
+                self.loading = true;
+                let token = self.session_token.clone().unwrap_or_default();
+                let svc = chat_service.clone();
+                let host = resolve_host(self);
+                return Command::perform(
+                    async move {
+                        let mut guard = svc.lock().await;
+                        let response = match guard.send_command(&host, format!("/search_messages {} {}", token, query)).await {
+                            Ok(response) => response,
+                            Err(e) => return Message::LogError(format!("Message search failed: {}", e)),
+                        };
+                        match crate::client::services::message_parser::parse_search_results(&response) {
+                            Ok(results) => Message::SearchResultsLoaded { results },
+                            Err(e) => Message::LogError(format!("Message search failed: {}", e)),
+                        }
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::SearchResultsLoaded { results } => {
+                self.loading = false;
+                self.message_search_results = results;
+            }
+            Message::OpenDiscoverGroups => {
+                self.app_state = AppState::DiscoverGroups;
+                self.discover_query.clear();
+                self.join_code_input.clear();
+                return Command::perform(async { Message::LoadPublicGroups { query: String::new(), page: 1 } }, |msg| msg);
+            }
+            Message::DiscoverQueryChanged(query) => {
+                self.discover_query = query;
+            }
+            Message::LoadPublicGroups { query, page } => {
+                self.loading = true;
+                let token = self.session_token.clone().unwrap_or_default();
+                let svc = chat_service.clone();
+                let host = resolve_host(self);
+                return Command::perform(
+                    async move {
+                        match GroupService::list_public_groups(&svc, &host, &token, query.trim(), page).await {
+                            Ok(page) => Message::PublicGroupsLoaded { page },
+                            Err(e) => Message::LogError(format!("Could not load public groups: {}", e)),
+                        }
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::PublicGroupsLoaded { page } => {
+                self.loading = false;
+                self.public_groups = page;
+            }
+            Message::JoinCodeChanged(code) => {
+                self.join_code_input = code;
+            }
+            Message::JoinByCode { code } => {
+                let code = code.trim().to_string();
+                if code.is_empty() {
+                    return Command::none();
+                }
+                let token = self.session_token.clone().unwrap_or_default();
+                let svc = chat_service.clone();
+                let host = resolve_host(self);
+                return Command::perform(
+                    async move {
+                        match GroupService::join_by_code(&svc, &host, &token, &code).await {
+                            Ok((group_id, group_name)) => Message::JoinedByCode { group_id, group_name },
+                            Err(e) => Message::JoinGroupResult { success: false, message: e.to_string() },
+                        }
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::JoinedByCode { group_id, group_name } => {
+                self.join_code_input.clear();
+                self.logger.push(LogMessage { level: LogLevel::Success, message: format!("Joined {}", group_name) });
+                if !self.my_groups.iter().any(|g| g.id == group_id) {
+                    self.my_groups.push(GroupSummary { id: group_id.clone(), name: group_name.clone() });
+                }
+                return Command::perform(async move { Message::OpenGroupChat(group_id, group_name) }, |msg| msg);
+            }
+            Message::LoadPinnedMessages { group_id } => {
+                let token = self.session_token.clone().unwrap_or_default();
+                let svc = chat_service.clone();
+                let host = resolve_host(self);
+                return Command::perform(
+                    async move {
+                        match GroupService::get_pinned_messages(&svc, &host, &token, &group_id).await {
+                            Ok(pins) => Message::PinnedMessagesLoaded { group_id, pins },
+                            Err(e) => Message::LogError(format!("Could not load pinned messages: {}", e)),
+                        }
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::PinnedMessagesLoaded { group_id, pins } => {
+                self.pinned_messages.insert(group_id, pins);
+            }
+            Message::TogglePinnedBanner { group_id } => {
+                if self.expanded_pinned_banners.contains(&group_id) {
+                    self.expanded_pinned_banners.remove(&group_id);
+                } else {
+                    self.expanded_pinned_banners.insert(group_id);
+                }
+            }
+            Message::OpenPinnedMessages { group_id } => {
+                self.app_state = AppState::PinnedMessages(group_id.clone());
+                return Command::perform(async move { Message::LoadPinnedMessages { group_id } }, |msg| msg);
+            }
+            Message::SetMessagePinned { group_id, message_id, pin } => {
+                let token = self.session_token.clone().unwrap_or_default();
+                let svc = chat_service.clone();
+                let host = resolve_host(self);
+                return Command::perform(
+                    async move {
+                        // Reload right away rather than waiting for the pins_changed event
+                        match GroupService::set_pinned(&svc, &host, &token, &group_id, message_id, pin).await {
+                            Ok(_) => Message::LoadPinnedMessages { group_id },
+                            Err(e) => Message::LogError(format!("Could not {} the message: {}", if pin { "pin" } else { "unpin" }, e)),
+                        }
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::OpenInviteCodeDialog { group_id } => {
+                self.invite_code_dialog = Some(InviteCodeDialog {
+                    group_id,
+                    expires_secs: Some(INVITE_CODE_DEFAULT_EXPIRY_SECS),
+                    max_uses: String::new(),
+                    code: None,
+                });
+            }
+            Message::CloseInviteCodeDialog => {
+                self.invite_code_dialog = None;
+            }
+            Message::InviteCodeExpiryChanged(expires_secs) => {
+                if let Some(dialog) = &mut self.invite_code_dialog {
+                    dialog.expires_secs = expires_secs;
+                }
+            }
+            Message::InviteCodeMaxUsesChanged(max_uses) => {
+                if let Some(dialog) = &mut self.invite_code_dialog {
+                    // Digits only; the field stays empty for unlimited uses
+                    if max_uses.chars().all(|c| c.is_ascii_digit()) {
+                        dialog.max_uses = max_uses;
+                    }
+                }
+            }
+            Message::GenerateInviteCode => {
+                let Some(dialog) = &self.invite_code_dialog else {
+                    return Command::none();
+                };
+                let group_id = dialog.group_id.clone();
+                let expires_secs = dialog.expires_secs;
+                let max_uses = dialog.max_uses.parse::<i64>().ok();
+                let token = self.session_token.clone().unwrap_or_default();
+                let svc = chat_service.clone();
+                let host = resolve_host(self);
+                return Command::perform(
+                    async move {
+                        match GroupService::generate_invite_code(&svc, &host, &token, &group_id, expires_secs, max_uses).await {
+                            Ok(code) => Message::InviteCodeGenerated { group_id, code },
+                            Err(e) => Message::LogError(format!("Could not create an invite code: {}", e)),
+                        }
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::InviteCodeGenerated { group_id, code } => {
+                // Ignore codes arriving after the dialog was closed or moved to another group
+                if let Some(dialog) = self.invite_code_dialog.as_mut().filter(|d| d.group_id == group_id) {
+                    dialog.code = Some(code);
+                }
+            }
+            Message::CopyInviteCode { code } => {
+                self.logger.push(LogMessage { level: LogLevel::Success, message: "Invite code copied".to_string() });
+                return iced::clipboard::write(code);
+            }
+            Message::SearchGroupsQueryChanged(query) => {
+                self.group_search_query = query;
+            }
+            Message::SearchGroups { query } => {
+                let query = query.trim().to_string();
+                if query.is_empty() {
+                    self.group_search_results.clear();
+                    return Command::none();
+                }
+                self.loading = true;
+                let token = self.session_token.clone().unwrap_or_default();
+                let svc = chat_service.clone();
+                let host = resolve_host(self);
+                return Command::perform(
+                    async move {
+                        match GroupService::search_groups(&svc, &host, &token, &query).await {
+                            Ok(groups) => Message::GroupSearchResults { groups },
+                            Err(e) => Message::LogError(format!("Group search failed: {}", e)),
+                        }
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::OpenGroupChatByName { group_name } => {
+                let token = self.session_token.clone().unwrap_or_default();
+                let svc = chat_service.clone();
+                let host = resolve_host(self);
+                return Command::perform(
+                    async move {
+                        let groups = match GroupService::search_groups(&svc, &host, &token, &group_name).await {
+                            Ok(groups) => groups,
+                            Err(e) => return Message::LogError(format!("Group lookup failed: {}", e)),
+                        };
+                        let mut matches: Vec<_> = groups.into_iter().filter(|(g, _)| g.name == group_name).collect();
+                        match matches.len() {
+                            0 => Message::LogError(format!("Group '{}' not found", group_name)),
+                            1 => {
+                                let (group, _) = matches.remove(0);
+                                Message::OpenGroupChat(group.id, group.name)
+                            }
+                            _ => Message::GroupNameAmbiguous { group_name, groups: matches },
+                        }
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::GroupNameAmbiguous { group_name, groups } => {
+                self.app_state = AppState::SearchGroups;
+                self.group_search_query = group_name;
+                self.group_search_results = groups;
+                // Refresh membership so our own groups show "Open" instead of "Join"
+                let token = self.session_token.clone().unwrap_or_default();
+                let svc = chat_service.clone();
+                let host = resolve_host(self);
+                return Command::perform(
+                    async move {
+                        match GroupService::my_groups(&svc, &host, &token).await {
+                            Ok(groups) => Message::MyGroupsLoaded { groups },
+                            Err(e) => Message::MyGroupsFailed { message: format!("Error loading groups: {}", e) },
+                        }
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::GroupSearchResults { groups } => {
+                self.loading = false;
+                self.group_search_results = groups;
+            }
+            Message::JoinGroup { group_id } => {
+                let token = self.session_token.clone().unwrap_or_default();
+                let svc = chat_service.clone();
+                let host = resolve_host(self);
+                return Command::perform(
+                    async move {
+                        match GroupService::join_group(&svc, &host, &token, &group_id).await {
+                            Ok(_) => Message::JoinGroupResult { success: true, message: "Joined group".to_string() },
+                            Err(e) => Message::JoinGroupResult { success: false, message: e.to_string() },
+                        }
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::JoinGroupResult { success, message } => {
+                let level = if success { LogLevel::Success } else { LogLevel::Error };
+                self.logger.push(LogMessage { level, message });
+                if success {
+                    return Command::perform(async { Message::OpenMyGroups }, |msg| msg);
+                }
+                return Command::perform(
+                    async move {
+                        tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+                        Message::ClearLog
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::OpenBlockedUsers => {
+                self.app_state = AppState::BlockedUsers;
+                self.loading = true;
+                return Command::perform(async { Message::ListBlocked }, |msg| msg);
+            }
+            Message::ListBlocked => {
+                if let Some(token) = &self.session_token {
+                    let svc = chat_service.clone();
+                    let token_clone = token.clone();
+                    let host = resolve_host(self);
+
+                    return Command::perform(
+                        async move {
+                            match FriendService::list_blocked(&svc, &host, &token_clone).await {
+                                Ok(users) => Message::BlockedUsersLoaded { users },
+                                Err(_) => Message::BlockedUsersLoaded { users: vec![] },
+                            }
+                        },
+                        |msg| msg,
+                    );
+                }
+            }
+            Message::BlockedUsersLoaded { users } => {
+                self.loading = false;
+                self.blocked_users = users;
+            }
+            Message::BlockUser { username } => {
+                let token = self.session_token.clone().unwrap_or_default();
+                let svc = chat_service.clone();
+                let host = resolve_host(self);
+                return Command::perform(
+                    async move {
+                        match FriendService::block_user(&svc, &host, &token, &username).await {
+                            Ok(_) => Message::BlockActionResult { message: format!("{} has been blocked", username) },
+                            Err(e) => Message::FriendActionFailed { level: e.log_level(), message: format!("Error blocking user: {}", e) },
+                        }
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::UnblockUser { username } => {
+                let token = self.session_token.clone().unwrap_or_default();
+                let svc = chat_service.clone();
+                let host = resolve_host(self);
+                // Rimuovi subito dalla lista, il reload conferma lo stato del server
+                self.blocked_users.retain(|u| u != &username);
+                return Command::perform(
+                    async move {
+                        match FriendService::unblock_user(&svc, &host, &token, &username).await {
+                            Ok(_) => Message::BlockActionResult { message: format!("{} has been unblocked", username) },
+                            Err(e) => Message::FriendActionFailed { level: e.log_level(), message: format!("Error unblocking user: {}", e) },
+                        }
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::BlockActionResult { message } => {
+                self.logger.push(LogMessage { level: LogLevel::Success, message });
+
+                return Command::batch(vec![
+                    Command::perform(async { Message::ListBlocked }, |msg| msg),
+                    Command::perform(
+                        async move {
+                            tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+                            Message::ClearLog
+                        },
+                        |msg| msg,
+                    ),
+                ]);
+            }
+            Message::OpenArchivedChats => {
+                self.app_state = AppState::ArchivedChats;
+                self.loading = true;
+
+                if let Some(token) = &self.session_token {
+                    let svc = chat_service.clone();
+                    let token_clone = token.clone();
+                    let host = resolve_host(self);
+
+                    return Command::perform(
+                        async move {
+                            let mut guard = svc.lock().await;
+                            match guard.list_archived_chats(&host, &token_clone).await {
+                                Ok(chats) => Message::ArchivedChatsLoaded { chats },
+                                Err(_) => Message::ArchivedChatsLoaded { chats: vec![] },
+                            }
+                        },
+                        |msg| msg,
+                    );
+                }
+            }
+            Message::ArchivedChatsLoaded { chats } => {
+                self.loading = false;
+                self.archived_chats = chats;
+            }
+            Message::ArchiveChat { chat_type, chat_id } => {
+                return self.send_archive_command("/archive_chat", chat_type, chat_id, chat_service);
+            }
+            Message::UnarchiveChat { chat_type, chat_id } => {
+                self.archived_chats.retain(|c| !(c.chat_type == chat_type && c.chat_id == chat_id));
+                return self.send_archive_command("/unarchive_chat", chat_type, chat_id, chat_service);
+            }
+            Message::ExportChat { chat_type, chat_id } => {
+                let token = self.session_token.clone().unwrap_or_default();
+                let svc = chat_service.clone();
+                let host = resolve_host(self);
+                return Command::perform(
+                    async move {
+                        let json = match svc.lock().await.export_chat(&host, &token, chat_type.as_str(), &chat_id).await {
+                            Ok(json) => json,
+                            Err(e) => return Message::ExportChatResult { success: false, message: format!("Export failed: {}", e) },
+                        };
+                        let default_name = format!("ruggine-{}-{}.json", chat_type.as_str(), chat_id);
+                        match crate::client::gui::save_dialog::save_json(&default_name, &json).await {
+                            Ok(Some(path)) => Message::ExportChatResult { success: true, message: format!("Chat exported to {}", path.display()) },
+                            Ok(None) => Message::ExportChatResult { success: false, message: "Export cancelled".to_string() },
+                            Err(e) => Message::ExportChatResult { success: false, message: format!("Could not save export: {}", e) },
+                        }
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::ArchiveActionResult { success, message } | Message::ExportChatResult { success, message } => {
+                self.logger.push(LogMessage {
+                    level: if success { LogLevel::Success } else { LogLevel::Error },
+                    message,
+                });
+
+                return Command::perform(
+                    async move {
+                        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+                        Message::ClearLog
+                    },
+                    |msg| msg,
+                );
             }
             Message::InviteToGroupResult { success, message } => {
                 self.logger.push(LogMessage {
@@ -786,7 +1951,11 @@ impl ChatAppState {
                             |msg| msg,
                         ),
                         Command::perform(
-                            async move { Message::OpenMyGroupInvites },
+                            async move {
+                                // Give the server time to drop the handled invite before reloading
+                                tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+                                Message::RefreshGroupInvitesDelayed
+                            },
                             |msg| msg,
                         )
                     ]);
@@ -800,9 +1969,21 @@ impl ChatAppState {
                     );
                 }
             }
+            Message::RefreshGroupInvitesDelayed => {
+                // Only reload if the user is still looking at the invites list
+                if matches!(self.app_state, AppState::MyGroupInvites) {
+                    return Command::perform(
+                        async move { Message::OpenMyGroupInvites },
+                        |msg| msg,
+                    );
+                }
+            }
             Message::CreateGroupInputChanged(name) => {
                 self.create_group_name = name;
             }
+            Message::CreateGroupDescriptionChanged(description) => {
+                self.create_group_description = description;
+            }
             Message::ToggleParticipant(username) => {
                 if self.selected_participants.contains(&username) {
                     self.selected_participants.remove(&username);
@@ -813,40 +1994,43 @@ impl ChatAppState {
             Message::RemoveParticipant(username) => {
                 self.selected_participants.remove(&username);
             }
-            Message::CreateGroupSubmit => {
-                if !self.create_group_name.trim().is_empty() && !self.selected_participants.is_empty() {
-                    if let Some(token) = &self.session_token {
-                        let svc = chat_service.clone();
-                        let token_clone = token.clone();
-                        let name_clone = self.create_group_name.trim().to_string();
-                        let participants = self.selected_participants.clone();
-                        let cfg = crate::server::config::ClientConfig::from_env();
-                        let host = format!("{}:{}", cfg.default_host, cfg.default_port);
-                        
-                        self.loading = true;
-                        
-                        return Command::perform(
-                            async move {
-                                let mut guard = svc.lock().await;
-                                let participants_str = participants.into_iter().collect::<Vec<_>>().join(",");
-                                match guard.send_command(&host, format!("/create_group {} {} {}", token_clone, name_clone, participants_str)).await {
-                                    Ok(response) => {
-                                        // Extract group_id from response: "OK: Group 'name' created with ID: uuid"
-                                        if let Some(id_part) = response.split("ID: ").nth(1) {
-                                            let group_id = id_part.trim().to_string();
-                                            Message::GroupCreated { group_id, group_name: name_clone }
-                                        } else {
-                                            // Fallback: generate a temporary ID (shouldn't happen)
-                                            Message::GroupCreated { group_id: format!("temp_{}", chrono::Utc::now().timestamp()), group_name: name_clone }
-                                        }
-                                        
+            Message::CreateGroupSubmit if !self.create_group_name.trim().is_empty() && !self.selected_participants.is_empty() => {
+                if let Some(token) = &self.session_token {
+                    let svc = chat_service.clone();
+                    let token_clone = token.clone();
+                    let name_clone = self.create_group_name.trim().to_string();
+                    let participants = self.selected_participants.clone();
+                    let description = self.create_group_description.trim().to_string();
+                    let host = resolve_host(self);
+                    
+                    self.loading = true;
+                    
+                    return Command::perform(
+                        async move {
+                            let mut guard = svc.lock().await;
+                            let participants_str = participants.into_iter().collect::<Vec<_>>().join(",");
+                            // The description, if any, takes the rest of the line after the participants
+                            let command = format!("/create_group {} {} {} {}", token_clone, name_clone, participants_str, description);
+                            match guard.send_command(&host, command.trim_end().to_string()).await {
+                                Ok(response) if !response.starts_with("OK:") => {
+                                    Message::LogError(response.trim_start_matches("ERR:").trim().to_string())
+                                }
+                                Ok(response) => {
+                                    // Extract group_id from response: "OK: Group 'name' created with ID: uuid"
+                                    if let Some(id_part) = response.split("ID: ").nth(1) {
+                                        let group_id = id_part.trim().to_string();
+                                        Message::GroupCreated { group_id, group_name: name_clone }
+                                    } else {
+                                        // Fallback: generate a temporary ID (shouldn't happen)
+                                        Message::GroupCreated { group_id: format!("temp_{}", chrono::Utc::now().timestamp()), group_name: name_clone }
                                     }
-                                    Err(e) => Message::LogError(format!("Errore nella creazione del gruppo: {}", e)),
+                                    
                                 }
-                            },
-                            |msg| msg,
-                        );
-                    }
+                                Err(e) => Message::LogError(format!("Errore nella creazione del gruppo: {}", e)),
+                            }
+                        },
+                        |msg| msg,
+                    );
                 }
             }
             Message::GroupCreated { group_id, group_name } => {
@@ -876,24 +2060,29 @@ impl ChatAppState {
                 self.loading_groups = false;
                 self.my_groups = groups;
             }
+            Message::MyGroupsFailed { message } => {
+                self.loading_groups = false;
+                self.logger.push(LogMessage {
+                    level: LogLevel::Error,
+                    message,
+                });
+            }
             Message::InviteUserToGroup { group_id, username } => {
                 if let Some(token) = &self.session_token {
                     let svc = chat_service.clone();
                     let token_clone = token.clone();
                     let group_id_clone = group_id.clone();
                     let username_clone = username.clone();
-                    let cfg = crate::server::config::ClientConfig::from_env();
-                    let host = format!("{}:{}", cfg.default_host, cfg.default_port);
+                    let host = resolve_host(self);
                     
                     return Command::perform(
                         async move {
-                            let mut guard = svc.lock().await;
-                            match guard.send_command(&host, format!("/invite {} {} {}", token_clone, username_clone, group_id_clone)).await {
+                            match GroupService::invite(&svc, &host, &token_clone, &username_clone, &group_id_clone).await {
                                 Ok(response) => {
                                     if response.starts_with("OK:") {
                                         Message::InviteToGroupResult { 
                                             success: true, 
-                                            message: format!("Invite successfully sent toa {}!", username_clone) 
+                                            message: format!("Invite successfully sent to {}!", username_clone) 
                                         }
                                     } else {
                                         Message::InviteToGroupResult { 
@@ -921,38 +2110,13 @@ impl ChatAppState {
                 if let Some(token) = &self.session_token {
                     let svc = chat_service.clone();
                     let token_clone = token.clone();
-                    let cfg = crate::server::config::ClientConfig::from_env();
-                    let host = format!("{}:{}", cfg.default_host, cfg.default_port);
+                    let host = resolve_host(self);
                     
                     return Command::perform(
                         async move {
-                            let mut guard = svc.lock().await;
-                            match guard.send_command(&host, format!("/my_group_invites {}", token_clone)).await {
-                                Ok(response) => {
-                                    if response.starts_with("OK: Group invites:") {
-                                        let invites_part = response.trim_start_matches("OK: Group invites:").trim();
-                                        let invites: Vec<(i64, String, String)> = if invites_part.is_empty() {
-                                            vec![]
-                                        } else {
-                                            invites_part.split(" | ").filter_map(|s| {
-                                                let parts: Vec<&str> = s.trim().split(':').collect();
-                                                if parts.len() == 3 {
-                                                    if let Ok(invite_id) = parts[0].parse::<i64>() {
-                                                        Some((invite_id, parts[1].to_string(), parts[2].to_string()))
-                                                    } else {
-                                                        None
-                                                    }
-                                                } else {
-                                                    None
-                                                }
-                                            }).collect()
-                                        };
-                                        Message::MyGroupInvitesLoaded { invites }
-                                    } else {
-                                        Message::MyGroupInvitesLoaded { invites: vec![] }
-                                    }
-                                }
-                                Err(_) => Message::MyGroupInvitesLoaded { invites: vec![] },
+                            match GroupService::my_group_invites(&svc, &host, &token_clone).await {
+                                Ok(invites) => Message::MyGroupInvitesLoaded { invites },
+                                Err(e) => Message::MyGroupInvitesFailed { message: format!("Error loading group invites: {}", e) },
                             }
                         },
                         |msg| msg,
@@ -963,17 +2127,22 @@ impl ChatAppState {
                 self.loading_invites = false;
                 self.my_group_invites = invites;
             }
+            Message::MyGroupInvitesFailed { message } => {
+                self.loading_invites = false;
+                self.logger.push(LogMessage {
+                    level: LogLevel::Error,
+                    message,
+                });
+            }
             Message::AcceptGroupInvite { invite_id } => {
                 if let Some(token) = &self.session_token {
                     let svc = chat_service.clone();
                     let token_clone = token.clone();
-                    let cfg = crate::server::config::ClientConfig::from_env();
-                    let host = format!("{}:{}", cfg.default_host, cfg.default_port);
+                    let host = resolve_host(self);
                     
                     return Command::perform(
                         async move {
-                            let mut guard = svc.lock().await;
-                            match guard.send_command(&host, format!("/accept_group_invite {} {}", token_clone, invite_id)).await {
+                            match GroupService::accept_group_invite(&svc, &host, &token_clone, invite_id).await {
                                 Ok(response) => {
                                     if response.starts_with("OK:") {
                                         Message::GroupInviteActionResult { 
@@ -1001,13 +2170,11 @@ impl ChatAppState {
                 if let Some(token) = &self.session_token {
                     let svc = chat_service.clone();
                     let token_clone = token.clone();
-                    let cfg = crate::server::config::ClientConfig::from_env();
-                    let host = format!("{}:{}", cfg.default_host, cfg.default_port);
+                    let host = resolve_host(self);
                     
                     return Command::perform(
                         async move {
-                            let mut guard = svc.lock().await;
-                            match guard.send_command(&host, format!("/reject_group_invite {} {}", token_clone, invite_id)).await {
+                            match GroupService::reject_group_invite(&svc, &host, &token_clone, invite_id).await {
                                 Ok(response) => {
                                     if response.starts_with("OK:") {
                                         Message::GroupInviteActionResult { 
@@ -1034,33 +2201,30 @@ impl ChatAppState {
             Message::UsersSearchQueryChanged(query) => {
                 self.users_search_query = query;
             }
-            Message::UsersSearch => {
+            Message::UsersSearch if !self.users_search_query.is_empty() => {
                 // Trigger search based on current query
-                if !self.users_search_query.is_empty() {
-                    let svc = chat_service.clone();
-                    let cfg = crate::server::config::ClientConfig::from_env();
-                    let host = format!("{}:{}", cfg.default_host, cfg.default_port);
-                    let query = self.users_search_query.clone();
-                    // Clone current username so the async block does not borrow &self
-                    let current_username = self.username.clone();
+                let svc = chat_service.clone();
+                let host = resolve_host(self);
+                let query = self.users_search_query.clone();
+                // Clone current username so the async block does not borrow &self
+                let current_username = self.username.clone();
 
-                    return Command::perform(
-                        async move {
-                            // For now, just return all users and filter client-side
-                            match UsersService::list_all(&svc, &host).await {
-                                Ok(users) => {
-                                    let filtered: Vec<String> = users.into_iter()
-                                        .filter(|u| u.to_lowercase().contains(&query.to_lowercase()))
-                                        .filter(|u| u != &current_username) // Remove current user from search results
-                                        .collect();
-                                    Message::UsersListLoaded { kind: "Search".to_string(), list: filtered }
-                                }
-                                Err(_) => Message::UsersListLoaded { kind: "Search".to_string(), list: vec![] },
+                return Command::perform(
+                    async move {
+                        // For now, just return all users and filter client-side
+                        match UsersService::list_all(&svc, &host).await {
+                            Ok(users) => {
+                                let filtered: Vec<String> = users.into_iter()
+                                    .filter(|u| u.to_lowercase().contains(&query.to_lowercase()))
+                                    .filter(|u| u != &current_username) // Remove current user from search results
+                                    .collect();
+                                Message::UsersListLoaded { kind: "Search".to_string(), list: filtered }
                             }
-                        },
-                        |msg| msg,
-                    );
-                }
+                            Err(_) => Message::UsersListLoaded { kind: "Search".to_string(), list: vec![] },
+                        }
+                    },
+                    |msg| msg,
+                );
             }
             Message::UsersListLoaded { kind: _, list } => {
                 // Filter out current user from all user lists
@@ -1099,167 +2263,408 @@ impl ChatAppState {
             Message::MessageInputChanged(input) => {
                 self.current_message_input = input;
             }
-            Message::SendPrivateMessage { to } => {
-                if !self.current_message_input.trim().is_empty() {
-                    if let Some(token) = &self.session_token {
-                        let svc = chat_service.clone();
-                        let token_clone = token.clone();
-                        let to_clone = to.clone();
-                        let message = self.current_message_input.trim().to_string();
-                        let cfg = crate::server::config::ClientConfig::from_env();
-                        let host = format!("{}:{}", cfg.default_host, cfg.default_port);
-                        
-                        // Create a local message to add immediately to the UI
-                        let local_msg = ChatMessage {
-                            sender: self.username.clone(),
-                            content: message.clone(),
-                            timestamp: chrono::Utc::now().timestamp(),
-                            formatted_time: chrono::Utc::now().format("%H:%M").to_string(),
-                            sent_at: chrono::Utc::now().timestamp(),
-                            is_pending: true,  // This is a temporary local message
-                        };
-                        
-                        // Add message to local cache immediately for instant UI feedback
-                        let messages = self.private_chats.entry(to.clone()).or_insert_with(Vec::new);
-                        messages.push(local_msg);
-                        
-                        // Clear input immediately for better UX
-                        // If we don't have the chat history cached yet, mark it as loading
-                        if !self.private_chats.contains_key(&to) {
-                            self.loading_private_chats.insert(to.clone());
+            Message::MarkRead { chat_type, chat_id, timestamp } => {
+                let Some(token) = self.session_token.clone() else {
+                    return Command::none();
+                };
+                let host = resolve_host(self);
+                let svc = chat_service.clone();
+                return Command::perform(
+                    async move {
+                        let mut guard = svc.lock().await;
+                        match guard.send_command(&host, format!("/mark_read {} {} {} {}", token, chat_type.as_str(), chat_id, timestamp)).await {
+                            Ok(response) if !response.starts_with("OK:") => println!("[APP] Could not mark {} as read: {}", chat_id, response),
+                            Err(e) => println!("[APP] Could not mark {} as read: {}", chat_id, e),
+                            Ok(_) => {}
                         }
-
-                        self.current_message_input.clear();
-                        
-                        return Command::batch([
-                            Command::perform(
-                                async move {
-                                    let mut guard = svc.lock().await;
-                                    let _ = guard.send_private_message(&host, &token_clone, &to_clone, &message).await;
-                                    Message::NoOp  // WebSocket will handle server confirmation
-                                },
-                                |msg| msg,
-                            ),
-                            // Auto-scroll to bottom after sending
-                            scrollable::snap_to(
-                                scrollable::Id::new("messages_scroll"),
-                                scrollable::RelativeOffset::END
-                            )
-                        ]);
+                        Message::NoOp
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::LoadReadReceipts { with } => {
+                let Some(token) = self.session_token.clone() else {
+                    return Command::none();
+                };
+                let host = resolve_host(self);
+                let svc = chat_service.clone();
+                return Command::perform(
+                    async move {
+                        let mut guard = svc.lock().await;
+                        let response = guard.send_command(&host, format!("/get_read_receipts {} private {}", token, with)).await.unwrap_or_default();
+                        Message::ReadReceiptsLoaded { with, read_by: crate::client::services::message_parser::parse_read_by(&response) }
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::ReadReceiptsLoaded { with, read_by } => {
+                if let Some(&last_read_at) = read_by.get(&with) {
+                    let entry = self.private_last_read.entry(with).or_default();
+                    *entry = (*entry).max(last_read_at);
+                }
+            }
+            Message::TypingTick => {
+                self.typing_users.retain(|_, since| since.elapsed() < TYPING_INDICATOR_TTL);
+            }
+            Message::SystemBroadcast(message) => {
+                self.broadcast_banner = Some(BroadcastBanner {
+                    message,
+                    expires_at: std::time::Instant::now() + BROADCAST_BANNER_TTL,
+                });
+            }
+            Message::BroadcastTick if self.broadcast_banner.as_ref().is_some_and(|b| b.expires_at <= std::time::Instant::now()) => {
+                self.broadcast_banner = None;
+            }
+            Message::SendPrivateMessage { to } if !self.current_message_input.trim().is_empty() => {
+                if let Some(token) = &self.session_token {
+                    let svc = chat_service.clone();
+                    let token_clone = token.clone();
+                    let to_clone = to.clone();
+                    let message = self.current_message_input.trim().to_string();
+                    let reply_to_id = self.replying_to.take();
+                    let host = resolve_host(self);
+                    
+                    // Create a local message to add immediately to the UI
+                    let local_msg = ChatMessage {
+                        sender: self.username.clone(),
+                        content: message.clone(),
+                        timestamp: chrono::Utc::now().timestamp(),
+                        formatted_time: chrono::Utc::now().format("%H:%M").to_string(),
+                        sent_at: chrono::Utc::now().timestamp(),
+                        is_pending: true,  // This is a temporary local message
+                        message_id: None,
+                        sender_avatar_url: None,
+                        seen_count: 0,
+                        edited: false,
+                        reactions: HashMap::new(),
+                        reply_to_id,
+                        forwarded_from_id: None,
+                    };
+                    
+                    // Add message to local cache immediately for instant UI feedback
+                    let messages = self.private_chats.entry(to.clone()).or_default();
+                    messages.push(local_msg);
+                    
+                    // Clear input immediately for better UX
+                    // If we don't have the chat history cached yet, mark it as loading
+                    if !self.private_chats.contains_key(&to) {
+                        self.loading_private_chats.insert(to.clone());
                     }
+
+                    self.current_message_input.clear();
+                    self.user_scrolled_up.remove(&to);
+                    self.unseen_new_messages.remove(&to);
+                    
+                    return Command::batch([
+                        Command::perform(
+                            async move {
+                                let mut guard = svc.lock().await;
+                                let _ = guard.send_private_message(&host, &token_clone, &to_clone, &message, reply_to_id).await;
+                                Message::NoOp  // WebSocket will handle server confirmation
+                            },
+                            |msg| msg,
+                        ),
+                        // Auto-scroll to bottom after sending
+                        scrollable::snap_to(
+                            scrollable::Id::new("messages_scroll"),
+                            scrollable::RelativeOffset::END
+                        )
+                    ]);
                 }
             }
-            Message::SendGroupMessage { group_id } => {
-                if !self.current_message_input.trim().is_empty() {
-                    if let Some(token) = &self.session_token {
-                        let svc = chat_service.clone();
-                        let token_clone = token.clone();
-                        let group_id_clone = group_id.clone();
-                        let message = self.current_message_input.trim().to_string();
-                        let cfg = crate::server::config::ClientConfig::from_env();
-                        let host = format!("{}:{}", cfg.default_host, cfg.default_port);
-                        
-                        // Create a local message to add immediately to the UI
-                        let local_msg = ChatMessage {
-                            sender: self.username.clone(),
-                            content: message.clone(),
-                            timestamp: chrono::Utc::now().timestamp(),
-                            formatted_time: chrono::Utc::now().format("%H:%M").to_string(),
-                            sent_at: chrono::Utc::now().timestamp(),
-                            is_pending: true,  // This is a temporary local message
-                        };
-                        
-                        // Add message to local cache immediately for instant UI feedback
-                        let messages = self.group_chats.entry(group_id.clone()).or_insert_with(Vec::new);
-                        messages.push(local_msg);
-                        
-                        // Clear input immediately for better UX
-                        // If we don't have the chat history cached yet, mark it as loading
-                        if !self.group_chats.contains_key(&group_id) {
-                            self.loading_group_chats.insert(group_id.clone());
-                        }
+            Message::SendGroupMessage { group_id } if !self.current_message_input.trim().is_empty() => {
+                if let Some(token) = &self.session_token {
+                    let svc = chat_service.clone();
+                    let token_clone = token.clone();
+                    let group_id_clone = group_id.clone();
+                    let message = self.current_message_input.trim().to_string();
+                    let reply_to_id = self.replying_to.take();
+                    let host = resolve_host(self);
+                    
+                    // Create a local message to add immediately to the UI
+                    let local_msg = ChatMessage {
+                        sender: self.username.clone(),
+                        content: message.clone(),
+                        timestamp: chrono::Utc::now().timestamp(),
+                        formatted_time: chrono::Utc::now().format("%H:%M").to_string(),
+                        sent_at: chrono::Utc::now().timestamp(),
+                        is_pending: true,  // This is a temporary local message
+                        message_id: None,
+                        sender_avatar_url: None,
+                        seen_count: 0,
+                        edited: false,
+                        reactions: HashMap::new(),
+                        reply_to_id,
+                        forwarded_from_id: None,
+                    };
+                    
+                    // Add message to local cache immediately for instant UI feedback
+                    let messages = self.group_chats.entry(group_id.clone()).or_default();
+                    messages.push(local_msg);
+                    
+                    // Clear input immediately for better UX
+                    // If we don't have the chat history cached yet, mark it as loading
+                    if !self.group_chats.contains_key(&group_id) {
+                        self.loading_group_chats.insert(group_id.clone());
+                    }
 
-                        self.current_message_input.clear();
-                        
+                    self.current_message_input.clear();
+                    
+                    return Command::batch([
+                        Command::perform(
+                            async move {
+                                let mut guard = svc.lock().await;
+                                let _ = guard.send_group_message(&host, &token_clone, &group_id_clone, &message, reply_to_id).await;
+                                Message::NoOp  // WebSocket will handle server confirmation
+                            },
+                            |msg| msg,
+                        ),
+                        // Auto-scroll to bottom after sending
+                        scrollable::snap_to(
+                            scrollable::Id::new("group_messages_scroll"),
+                            scrollable::RelativeOffset::END
+                        )
+                    ]);
+                }
+            }
+            Message::LoadGroupMessages { group_id } => {
+                if let Some(token) = &self.session_token {
+                    let svc = chat_service.clone();
+                    let token_clone = token.clone();
+                    let group_id_clone = group_id.clone();
+                    let host = resolve_host(self);
+                    
+                    return Command::perform(
+                        async move {
+                            let mut guard = svc.lock().await;
+                            // Older servers cannot page: fall back to the whole conversation
+                            let page = match guard.get_group_messages_page(&host, &token_clone, &group_id_clone, i64::MAX, HISTORY_PAGE_SIZE).await {
+                                Err(e) if !e.to_string().contains("NOT_A_MEMBER") => {
+                                    guard.get_group_messages(&host, &token_clone, &group_id_clone).await.map(|messages| (messages, None))
+                                }
+                                page => page,
+                            };
+                            match page {
+                                Ok((messages, next_before)) => Message::GroupMessagesLoaded { group_id: group_id_clone, messages, next_before },
+                                Err(e) => {
+                                    if e.to_string().contains("NOT_A_MEMBER") {
+                                        Message::NotAMember { group_id: group_id_clone }
+                                    } else {
+                                        Message::GroupMessagesLoaded { group_id: group_id_clone, messages: vec![], next_before: None }
+                                    }
+                                }
+                            }
+                        },
+                        |msg| msg,
+                    );
+                }
+            }
+            Message::GroupMessagesLoaded { group_id, messages, next_before } => {
+                match next_before {
+                    Some(cursor) => self.group_next_before.insert(group_id.clone(), cursor),
+                    None => self.group_next_before.remove(&group_id),
+                };
+                let fetch_avatars = self.fetch_missing_avatars(&messages);
+                let merged = merge_messages(self.group_chats.get(&group_id).map(|v| v.as_slice()).unwrap_or(&[]), &messages);
+                self.group_chats.insert(group_id.clone(), merged);
+                self.loading_group_chats.remove(&group_id);
+                
+                // Auto-scroll to bottom when messages are loaded
+                if let AppState::GroupChat(current_group_id, _) = &self.app_state {
+                    if current_group_id == &group_id {
                         return Command::batch([
-                            Command::perform(
-                                async move {
-                                    let mut guard = svc.lock().await;
-                                    let _ = guard.send_group_message(&host, &token_clone, &group_id_clone, &message).await;
-                                    Message::NoOp  // WebSocket will handle server confirmation
-                                },
-                                |msg| msg,
-                            ),
-                            // Auto-scroll to bottom after sending
+                            fetch_avatars,
                             scrollable::snap_to(
                                 scrollable::Id::new("group_messages_scroll"),
                                 scrollable::RelativeOffset::END
-                            )
+                            ),
                         ]);
                     }
                 }
+                return fetch_avatars;
             }
-            Message::LoadGroupMessages { group_id } => {
+            Message::LoadGroupMessagesPage { group_id, before_ts, limit } => {
+                if !self.loading_older_group_messages.insert(group_id.clone()) {
+                    return Command::none();
+                }
                 if let Some(token) = &self.session_token {
                     let svc = chat_service.clone();
                     let token_clone = token.clone();
-                    let group_id_clone = group_id.clone();
-                    let cfg = crate::server::config::ClientConfig::from_env();
-                    let host = format!("{}:{}", cfg.default_host, cfg.default_port);
-                    
+                    let host = resolve_host(self);
+                    return Command::perform(
+                        async move {
+                            let mut guard = svc.lock().await;
+                            match guard.get_group_messages_page(&host, &token_clone, &group_id, before_ts, limit).await {
+                                Ok((messages, next_before)) => Message::OlderGroupMessagesLoaded { group_id, messages, next_before },
+                                Err(e) if e.to_string().contains("NOT_A_MEMBER") => Message::NotAMember { group_id },
+                                // Keep the same cursor so the next scroll to the top retries
+                                Err(_) => Message::OlderGroupMessagesLoaded { group_id, messages: vec![], next_before: Some(before_ts) },
+                            }
+                        },
+                        |msg| msg,
+                    );
+                }
+            }
+            Message::ToggleMemberSidebar { group_id } => {
+                let open = self.show_members_sidebar.entry(group_id.clone()).or_insert(false);
+                *open = !*open;
+                if *open {
+                    // Refresh on every opening so roles and presence are current
+                    return Command::perform(async move { Message::LoadGroupMembers { group_id } }, |msg| msg);
+                }
+            }
+            Message::LoadGroupMembers { group_id } => {
+                if let Some(token) = &self.session_token {
+                    let svc = chat_service.clone();
+                    let token = token.clone();
+                    let host = resolve_host(self);
                     return Command::perform(
                         async move {
-                            let mut guard = svc.lock().await;
-                            match guard.get_group_messages(&host, &token_clone, &group_id_clone).await {
-                                Ok(messages) => Message::GroupMessagesLoaded { group_id: group_id_clone, messages },
-                                Err(e) => {
-                                    if e.to_string().contains("NOT_A_MEMBER") {
-                                        Message::NotAMember { group_id: group_id_clone }
-                                    } else {
-                                        Message::GroupMessagesLoaded { group_id: group_id_clone, messages: vec![] }
-                                    }
-                                }
-                            }
+                            match GroupService::group_members_status(&svc, &host, &token, &group_id).await {
+                                Ok(members) => Message::GroupMembersLoaded { group_id, members },
+                                Err(e) => Message::LogError(format!("Failed to load group members: {}", e)),
+                            }
+                        },
+                        |msg| msg,
+                    );
+                }
+            }
+            Message::GroupMembersLoaded { group_id, members } => {
+                self.group_members.insert(group_id, members);
+            }
+            Message::StartRenameGroup { current_name } => {
+                self.group_rename_draft = Some(current_name);
+            }
+            Message::RenameGroupDraftChanged(name) => {
+                self.group_rename_draft = Some(name);
+            }
+            Message::CancelRenameGroup => {
+                self.group_rename_draft = None;
+            }
+            Message::RenameGroup { group_id, new_name } => {
+                let token = self.session_token.clone().unwrap_or_default();
+                let svc = chat_service.clone();
+                let host = resolve_host(self);
+                return Command::perform(
+                    async move {
+                        match GroupService::rename_group(&svc, &host, &token, &group_id, &new_name).await {
+                            Ok(_) => Message::GroupRenamed { group_id, new_name },
+                            Err(e) => Message::LogError(format!("Could not rename group: {}", e)),
+                        }
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::GroupRenamed { group_id, new_name } => {
+                self.apply_group_rename(&group_id, &new_name);
+            }
+            Message::LoadGroupDescription { group_id } => {
+                let token = self.session_token.clone().unwrap_or_default();
+                let svc = chat_service.clone();
+                let host = resolve_host(self);
+                return Command::perform(
+                    async move {
+                        match GroupService::get_description(&svc, &host, &token, &group_id).await {
+                            Ok(description) => Message::GroupDescriptionLoaded { group_id, description },
+                            Err(_) => Message::NoOp,
+                        }
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::GroupDescriptionLoaded { group_id, description } => {
+                self.group_descriptions.insert(group_id, description);
+            }
+            Message::ToggleGroupDescription { group_id } => {
+                if self.expanded_group_descriptions.contains(&group_id) {
+                    self.expanded_group_descriptions.remove(&group_id);
+                } else {
+                    self.expanded_group_descriptions.insert(group_id);
+                }
+            }
+            Message::StartEditGroupDescription { current } => {
+                self.group_description_draft = Some(current);
+            }
+            Message::GroupDescriptionDraftChanged(description) => {
+                self.group_description_draft = Some(description);
+            }
+            Message::CancelEditGroupDescription => {
+                self.group_description_draft = None;
+            }
+            Message::SaveGroupDescription { group_id, description } => {
+                self.group_description_draft = None;
+                let token = self.session_token.clone().unwrap_or_default();
+                let svc = chat_service.clone();
+                let host = resolve_host(self);
+                return Command::perform(
+                    async move {
+                        let description = description.trim().to_string();
+                        match GroupService::set_description(&svc, &host, &token, &group_id, &description).await {
+                            Ok(_) => Message::GroupDescriptionLoaded { group_id, description },
+                            Err(e) => Message::LogError(format!("Could not update the group description: {}", e)),
+                        }
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::SetGroupAdmin { group_id, username, admin } => {
+                let token = self.session_token.clone().unwrap_or_default();
+                let svc = chat_service.clone();
+                let host = resolve_host(self);
+                return Command::perform(
+                    async move {
+                        match GroupService::set_group_admin(&svc, &host, &token, &group_id, &username, admin).await {
+                            Ok(message) => Message::GroupRoleChanged { group_id, success: true, message },
+                            Err(e) => Message::GroupRoleChanged { group_id, success: false, message: e.to_string() },
+                        }
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::GroupRoleChanged { group_id, success, message } => {
+                let level = if success { LogLevel::Success } else { LogLevel::Error };
+                self.logger.push(LogMessage { level, message });
+                // Reload the member list either way so the sidebar shows the server's roles
+                return Command::batch([
+                    Command::perform(async move { Message::LoadGroupMembers { group_id } }, |msg| msg),
+                    Command::perform(
+                        async move {
+                            tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+                            Message::ClearLog
                         },
                         |msg| msg,
-                    );
-                }
+                    ),
+                ]);
             }
-            Message::GroupMessagesLoaded { group_id, messages } => {
-                self.group_chats.insert(group_id.clone(), messages);
-                self.loading_group_chats.remove(&group_id);
-                
-                // Auto-scroll to bottom when messages are loaded
-                if let AppState::GroupChat(current_group_id, _) = &self.app_state {
-                    if current_group_id == &group_id {
-                        return scrollable::snap_to(
-                            scrollable::Id::new("group_messages_scroll"),
-                            scrollable::RelativeOffset::END
-                        );
-                    }
-                }
+            Message::AvatarLoaded { url, handle: Some(handle) } => {
+                self.avatar_cache.insert(url, handle);
             }
             Message::LoadPrivateMessages { with } => {
                 if let Some(token) = &self.session_token {
                     let svc = chat_service.clone();
                     let token_clone = token.clone();
                     let with_clone = with.clone();
-                    let cfg = crate::server::config::ClientConfig::from_env();
-                    let host = format!("{}:{}", cfg.default_host, cfg.default_port);
+                    let host = resolve_host(self);
                     
                     return Command::perform(
                         async move {
                             let mut guard = svc.lock().await;
-                            match guard.get_private_messages(&host, &token_clone, &with_clone).await {
-                                Ok(messages) => Message::PrivateMessagesLoaded { with: with_clone, messages },
-                                Err(_) => Message::PrivateMessagesLoaded { with: with_clone, messages: vec![] },
+                            // Older servers cannot page: fall back to the whole conversation
+                            let page = match guard.get_private_messages_page(&host, &token_clone, &with_clone, i64::MAX, HISTORY_PAGE_SIZE).await {
+                                Ok(page) => Ok(page),
+                                Err(_) => guard.get_private_messages(&host, &token_clone, &with_clone).await.map(|messages| (messages, false)),
+                            };
+                            match page {
+                                Ok((messages, has_more)) => Message::PrivateMessagesLoaded { with: with_clone, messages, has_more },
+                                Err(_) => Message::PrivateMessagesLoaded { with: with_clone, messages: vec![], has_more: false },
                             }
                         },
                         |msg| msg,
                     );
                 }
             }
-            Message::PrivateMessagesLoaded { with, messages } => {
+            Message::PrivateMessagesLoaded { with, messages, has_more } => {
+                self.private_has_more.insert(with.clone(), has_more);
                 // Track the latest timestamp from HTTP loaded messages
                 if let Some(latest_msg) = messages.iter().max_by_key(|msg| msg.timestamp) {
                     self.last_http_timestamp.insert(with.clone(), latest_msg.timestamp);
@@ -1269,22 +2674,75 @@ impl ChatAppState {
                     println!("[APP] 📚 HTTP loaded 0 messages for {}", with);
                 }
                 
-                self.private_chats.insert(with.clone(), messages);
+                let fetch_avatars = self.fetch_missing_avatars(&messages);
+                let merged = merge_messages(self.private_chats.get(&with).map(|v| v.as_slice()).unwrap_or(&[]), &messages);
+                self.private_chats.insert(with.clone(), merged);
                 self.loading_private_chats.remove(&with);
                 
                 // Auto-scroll to bottom when messages are loaded (for recipient)
-                if let AppState::PrivateChat(current_chat) = &self.app_state {
-                    if current_chat == &with {
-                        return scrollable::snap_to(
-                            scrollable::Id::new("messages_scroll"),
-                            scrollable::RelativeOffset::END
-                        );
-                    }
+                return Command::batch([fetch_avatars, self.scroll_private_chat_to_end(&with)]);
+            }
+            Message::ConfirmLeaveGroup { group_id, group_name } => {
+                // Cancel goes back to where the request came from
+                let cancel_message = match &self.app_state {
+                    AppState::GroupChat(id, name) => Message::OpenGroupChat(id.clone(), name.clone()),
+                    _ => Message::MyGroups,
+                };
+                self.app_state = AppState::ConfirmDialog {
+                    prompt: format!("Leave the group '{}'? You will need a new invite to join again.", group_name),
+                    confirm_message: Box::new(Message::LeaveGroup { group_id, group_name }),
+                    cancel_message: Box::new(cancel_message),
+                };
+            }
+            Message::ConfirmDeleteGroup { group_id, group_name } => {
+                self.app_state = AppState::ConfirmDialog {
+                    prompt: format!("Delete the group '{}'? All its messages will be lost for every member.", group_name),
+                    confirm_message: Box::new(Message::DeleteGroup { group_id: group_id.clone(), group_name: group_name.clone() }),
+                    cancel_message: Box::new(Message::OpenGroupChat(group_id, group_name)),
+                };
+            }
+            Message::DeleteGroup { group_id, group_name } => {
+                // Back to the chat while the server works; `GroupDeleted` then leaves it
+                self.app_state = AppState::GroupChat(group_id.clone(), group_name);
+                let token = self.session_token.clone().unwrap_or_default();
+                let svc = chat_service.clone();
+                let host = resolve_host(self);
+                return Command::perform(
+                    async move {
+                        match GroupService::delete_group(&svc, &host, &token, &group_id).await {
+                            Ok(_) => Message::GroupDeleted { group_id },
+                            Err(e) => Message::LogError(format!("Could not delete group: {}", e)),
+                        }
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::GroupDeleted { group_id } => {
+                if let Some(name) = self.forget_group(&group_id) {
+                    self.logger.push(LogMessage { level: LogLevel::Info, message: format!("The group '{}' was deleted", name) });
                 }
             }
-             Message::LeaveGroup { group_id: _, group_name } => {
-                let cfg = crate::server::config::ClientConfig::from_env();
-                let host = format!("{}:{}", cfg.default_host, cfg.default_port);
+            Message::KickedFromGroup { group_id } => {
+                if let Some(name) = self.forget_group(&group_id) {
+                    self.logger.push(LogMessage { level: LogLevel::Warning, message: format!("You were removed from the group '{}'", name) });
+                }
+            }
+            Message::KickMember { group_id, username } => {
+                let token = self.session_token.clone().unwrap_or_default();
+                let svc = chat_service.clone();
+                let host = resolve_host(self);
+                return Command::perform(
+                    async move {
+                        match GroupService::kick_member(&svc, &host, &token, &group_id, &username).await {
+                            Ok(message) => Message::GroupRoleChanged { group_id, success: true, message },
+                            Err(e) => Message::GroupRoleChanged { group_id, success: false, message: e.to_string() },
+                        }
+                    },
+                    |msg| msg,
+                );
+            }
+             Message::LeaveGroup { group_id, group_name } => {
+                let host = resolve_host(self);
                 let token = self.session_token.clone().unwrap_or_default();
                 let svc = chat_service.clone();
                 let group_name_clone = group_name.clone();
@@ -1292,28 +2750,29 @@ impl ChatAppState {
                 return Command::perform(
                     async move {
                         let mut guard = svc.lock().await;
-                        let cmd = format!("/leave_group {} {}", token, group_name_clone);
+                        let cmd = format!("/leave_group {} {}", token, group_id);
                         match guard.send_command(&host, cmd).await {
                             Ok(response) => {
                                 if response.starts_with("OK:") {
-                                    Message::LeaveGroupResult { success: true, message: format!("Left group '{}'", group_name_clone) }
+                                    Message::LeaveGroupResult { success: true, message: format!("Left group '{}'", group_name_clone), group_id }
                                 } else {
-                                    Message::LeaveGroupResult { success: false, message: response }
+                                    Message::LeaveGroupResult { success: false, message: response.trim_start_matches("ERR:").trim().to_string(), group_id }
                                 }
                             }
-                            Err(e) => Message::LeaveGroupResult { success: false, message: format!("Error: {}", e) }
+                            Err(e) => Message::LeaveGroupResult { success: false, message: format!("Error: {}", e), group_id }
                         }
                     },
                     |msg| msg,
                 );
             }
-            Message::LeaveGroupResult { success, message } => {
+            Message::LeaveGroupResult { success, message, group_id } => {
                 use crate::client::gui::views::logger::{LogMessage, LogLevel};
                 if success {
                     self.logger.push(LogMessage {
                         level: LogLevel::Success,
                         message: message.clone(),
                     });
+                    self.my_groups.retain(|g| g.id != group_id);
                     
                     // CRITICAL: Stop all polling immediately when leaving group
                     self.polling_active = false;
@@ -1328,36 +2787,13 @@ impl ChatAppState {
                     // Reload groups list to reflect the change and auto-clear logger
                     let svc = chat_service.clone();
                     let token = self.session_token.clone().unwrap_or_default();
-                    let cfg = crate::server::config::ClientConfig::from_env();
-                    let host = format!("{}:{}", cfg.default_host, cfg.default_port);
+                    let host = resolve_host(self);
                     return Command::batch([
                         Command::perform(
                             async move {
-                                let mut guard = svc.lock().await;
-                                let cmd = format!("/my_groups {}", token);
-                                match guard.send_command(&host, cmd).await {
-                                    Ok(response) => {
-                                        // Parse response: "OK: My groups: id1:name1, id2:name2"
-                                        if response.starts_with("OK: My groups:") {
-                                            let after = response.splitn(3, ':').nth(2).unwrap_or("");
-                                            let groups: Vec<(String, String, usize)> = after
-                                                .split(',')
-                                                .map(|s| s.trim())
-                                                .filter(|s| !s.is_empty())
-                                                .map(|s| {
-                                                    if let Some((id, name)) = s.split_once(':') {
-                                                        (id.to_string(), name.to_string(), 0) // member_count not used
-                                                    } else {
-                                                        (s.to_string(), s.to_string(), 0)
-                                                    }
-                                                })
-                                                .collect();
-                                            Message::MyGroupsLoaded { groups }
-                                        } else {
-                                            Message::MyGroupsLoaded { groups: vec![] }
-                                        }
-                                    },
-                                    Err(_) => Message::MyGroupsLoaded { groups: vec![] },
+                                match GroupService::my_groups(&svc, &host, &token).await {
+                                    Ok(groups) => Message::MyGroupsLoaded { groups },
+                                    Err(e) => Message::MyGroupsFailed { message: format!("Error loading groups: {}", e) },
                                 }
                             },
                             |msg| msg
@@ -1375,6 +2811,11 @@ impl ChatAppState {
                         level: LogLevel::Error,
                         message: message.clone(),
                     });
+
+                    // Close the confirmation dialog
+                    if matches!(self.app_state, AppState::ConfirmDialog { .. }) {
+                        self.app_state = AppState::MyGroups;
+                    }
                     
                     // Auto-clear error message after 2 seconds
                     return Command::perform(
@@ -1393,6 +2834,9 @@ impl ChatAppState {
                     message: format!("You are no longer a member of group: {}", group_id),
                 });
                 
+                self.loading_older_group_messages.remove(&group_id);
+                self.group_next_before.remove(&group_id);
+
                 // CRITICAL: Stop all polling immediately
                 self.polling_active = false;
                 self.group_polling_active = false;
@@ -1407,34 +2851,12 @@ impl ChatAppState {
                         // Reload groups list
                         let svc = chat_service.clone();
                         let token = self.session_token.clone().unwrap_or_default();
-                        let cfg = crate::server::config::ClientConfig::from_env();
-                        let host = format!("{}:{}", cfg.default_host, cfg.default_port);
+                        let host = resolve_host(self);
                         return Command::perform(
                             async move {
-                                let mut guard = svc.lock().await;
-                                let cmd = format!("/my_groups {}", token);
-                                match guard.send_command(&host, cmd).await {
-                                    Ok(response) => {
-                                        if response.starts_with("OK: My groups:") {
-                                            let after = response.splitn(3, ':').nth(2).unwrap_or("");
-                                            let groups: Vec<(String, String, usize)> = after
-                                                .split(',')
-                                                .map(|s| s.trim())
-                                                .filter(|s| !s.is_empty())
-                                                .map(|s| {
-                                                    if let Some((id, name)) = s.split_once(':') {
-                                                        (id.to_string(), name.to_string(), 0)
-                                                    } else {
-                                                        (s.to_string(), s.to_string(), 0)
-                                                    }
-                                                })
-                                                .collect();
-                                            Message::MyGroupsLoaded { groups }
-                                        } else {
-                                            Message::MyGroupsLoaded { groups: vec![] }
-                                        }
-                                    },
-                                    Err(_) => Message::MyGroupsLoaded { groups: vec![] },
+                                match GroupService::my_groups(&svc, &host, &token).await {
+                                    Ok(groups) => Message::MyGroupsLoaded { groups },
+                                    Err(e) => Message::MyGroupsFailed { message: format!("Error loading groups: {}", e) },
                                 }
                             },
                             |msg| msg
@@ -1444,8 +2866,7 @@ impl ChatAppState {
             }
             Message::DiscardPrivateMessages { with } => {
                 if let Some(token) = &self.session_token {
-                    let cfg = crate::server::config::ClientConfig::from_env();
-                    let host = format!("{}:{}", cfg.default_host, cfg.default_port);
+                    let host = resolve_host(self);
                     let svc = chat_service.clone();
                     let token_clone = token.clone();
                     let with_clone = with.clone();
@@ -1483,10 +2904,217 @@ impl ChatAppState {
                 }
                 return Command::none();
             }
+            Message::DeleteMessage { message_id } => {
+                let Some(token) = self.session_token.clone() else {
+                    return Command::none();
+                };
+                // Optimistically drop the message from whichever chat holds it
+                let chats = self.private_chats.iter_mut().map(|(k, v)| (ChatType::Private, k, v))
+                    .chain(self.group_chats.iter_mut().map(|(k, v)| (ChatType::Group, k, v)));
+                for (chat_type, chat_id, messages) in chats {
+                    if let Some(pos) = messages.iter().position(|m| m.message_id == Some(message_id)) {
+                        let removed = messages.remove(pos);
+                        self.pending_message_deletions.insert(message_id, (chat_type, chat_id.clone(), removed));
+                        break;
+                    }
+                }
+                let host = resolve_host(self);
+                let svc = chat_service.clone();
+                return Command::perform(
+                    async move {
+                        let mut guard = svc.lock().await;
+                        match guard.send_command(&host, format!("/delete_message {} {}", token, message_id)).await {
+                            Ok(response) if response.starts_with("OK:") => Message::DeleteMessageResult {
+                                message_id,
+                                success: true,
+                                message: response.trim_start_matches("OK:").trim().to_string(),
+                            },
+                            Ok(response) => Message::DeleteMessageResult { message_id, success: false, message: response },
+                            Err(e) => Message::DeleteMessageResult { message_id, success: false, message: format!("Error: {}", e) },
+                        }
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::DeleteMessageResult { message_id, success, message } => {
+                let pending = self.pending_message_deletions.remove(&message_id);
+                if success {
+                    self.logger.push(LogMessage { level: LogLevel::Success, message });
+                } else {
+                    // Put the message back where it was
+                    if let Some((chat_type, chat_id, removed)) = pending {
+                        let chats = match chat_type {
+                            ChatType::Private => &mut self.private_chats,
+                            ChatType::Group => &mut self.group_chats,
+                        };
+                        let restored = merge_messages(chats.get(&chat_id).map(|v| v.as_slice()).unwrap_or(&[]), &[removed]);
+                        chats.insert(chat_id, restored);
+                    }
+                    self.logger.push(LogMessage { level: LogLevel::Error, message: format!("Could not delete message: {}", message) });
+                }
+            }
+            Message::ToggleReactionPicker { message_id } => {
+                self.reaction_picker = (self.reaction_picker != Some(message_id)).then_some(message_id);
+            }
+            Message::ToggleReaction { message_id, emoji } => {
+                self.reaction_picker = None;
+                let Some(token) = self.session_token.clone() else {
+                    return Command::none();
+                };
+                let username = self.username.clone();
+                let Some(msg) = self.private_chats.values_mut()
+                    .chain(self.group_chats.values_mut())
+                    .flat_map(|messages| messages.iter_mut())
+                    .find(|m| m.message_id == Some(message_id))
+                else {
+                    return Command::none();
+                };
+                // Show the change right away; ReactionToggled undoes it if the server refuses
+                let added = !msg.has_reaction(&emoji, &username);
+                msg.set_reaction(&emoji, &username, added);
+                let host = resolve_host(self);
+                let svc = chat_service.clone();
+                return Command::perform(
+                    async move {
+                        let command = if added { "/react" } else { "/unreact" };
+                        let mut guard = svc.lock().await;
+                        let (success, message) = match guard.send_command(&host, format!("{} {} {} {}", command, token, message_id, emoji)).await {
+                            Ok(response) => (response.starts_with("OK:"), response),
+                            Err(e) => (false, format!("Error: {}", e)),
+                        };
+                        Message::ReactionToggled { message_id, emoji, added, success, message }
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::ReactionToggled { message_id, emoji, added, success: false, message } => {
+                let username = self.username.clone();
+                let reverted = self.private_chats.values_mut()
+                    .chain(self.group_chats.values_mut())
+                    .flat_map(|messages| messages.iter_mut())
+                    .find(|m| m.message_id == Some(message_id));
+                if let Some(msg) = reverted {
+                    msg.set_reaction(&emoji, &username, !added);
+                }
+                self.logger.push(LogMessage { level: LogLevel::Error, message: format!("Could not update reaction: {}", message) });
+            }
+            Message::StartEditMessage { message_id, content } => {
+                self.replying_to = None;
+                self.editing_message = Some(message_id);
+                self.current_message_input = content;
+            }
+            Message::CancelEditMessage => {
+                self.editing_message = None;
+                self.current_message_input.clear();
+            }
+            Message::StartReply { message_id } => {
+                // Replying and editing share the input box
+                self.editing_message = None;
+                self.replying_to = Some(message_id);
+            }
+            Message::CancelReply => {
+                self.replying_to = None;
+            }
+            Message::OpenForwardPicker { message_id } => {
+                self.forwarding_mode = true;
+                self.forwarding_message = Some(message_id);
+            }
+            Message::CloseForwardPicker => {
+                self.forwarding_mode = false;
+                self.forwarding_message = None;
+            }
+            Message::ForwardMessage { message_id, target_chat_id } => {
+                self.forwarding_mode = false;
+                self.forwarding_message = None;
+                let Some(token) = self.session_token.clone() else {
+                    return Command::none();
+                };
+                let host = resolve_host(self);
+                let svc = chat_service.clone();
+                return Command::perform(
+                    async move {
+                        let mut guard = svc.lock().await;
+                        match guard.send_command(&host, format!("/forward_message {} {} {}", token, message_id, target_chat_id)).await {
+                            Ok(response) if response.starts_with("OK:") => Message::MessageForwarded { target_chat_id },
+                            Ok(response) => Message::LogError(format!("Could not forward the message: {}", response.trim_start_matches("ERR:").trim())),
+                            Err(e) => Message::LogError(format!("Could not forward the message: {}", e)),
+                        }
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::MessageForwarded { target_chat_id } => {
+                // The copy is only on the server; reload the target chat when it is opened next
+                match target_chat_id.split_once(':') {
+                    Some(("private", username)) => { self.private_chats.remove(username); }
+                    Some(("group", group_id)) => { self.group_chats.remove(group_id); }
+                    _ => {}
+                }
+                self.logger.push(LogMessage { level: LogLevel::Success, message: "Message forwarded".to_string() });
+            }
+            Message::ScrollToMessage { message_id } => {
+                let (scroll_id, messages) = match &self.app_state {
+                    AppState::PrivateChat(with) => ("messages_scroll", self.private_chats.get(with)),
+                    AppState::GroupChat(group_id, _) => ("group_messages_scroll", self.group_chats.get(group_id)),
+                    _ => return Command::none(),
+                };
+                let Some(messages) = messages else {
+                    return Command::none();
+                };
+                let Some(index) = messages.iter().position(|m| m.message_id == Some(message_id)) else {
+                    self.logger.push(LogMessage { level: LogLevel::Info, message: "The original message is not loaded".to_string() });
+                    return Command::none();
+                };
+                // Bubbles vary in height, so the position in the list approximates the offset
+                let y = if messages.len() > 1 { index as f32 / (messages.len() - 1) as f32 } else { 0.0 };
+                return scrollable::snap_to(scrollable::Id::new(scroll_id), scrollable::RelativeOffset { x: 0.0, y });
+            }
+            Message::SubmitEditMessage { message_id } => {
+                let content = self.current_message_input.trim().to_string();
+                let Some(token) = self.session_token.clone() else {
+                    return Command::none();
+                };
+                if content.is_empty() {
+                    return Command::none();
+                }
+                self.editing_message = None;
+                self.current_message_input.clear();
+                let host = resolve_host(self);
+                let svc = chat_service.clone();
+                return Command::perform(
+                    async move {
+                        let mut guard = svc.lock().await;
+                        match guard.send_command(&host, format!("/edit_message {} {} {}", token, message_id, content)).await {
+                            Ok(response) if response.starts_with("OK:") => Message::EditMessageResult {
+                                message_id,
+                                success: true,
+                                message: response.trim_start_matches("OK:").trim().to_string(),
+                                content,
+                            },
+                            Ok(response) => Message::EditMessageResult { message_id, success: false, message: response, content },
+                            Err(e) => Message::EditMessageResult { message_id, success: false, message: format!("Error: {}", e), content },
+                        }
+                    },
+                    |msg| msg,
+                );
+            }
+            Message::EditMessageResult { message_id, success, message, content } => {
+                if success {
+                    let edited = self.private_chats.values_mut()
+                        .chain(self.group_chats.values_mut())
+                        .flat_map(|messages| messages.iter_mut())
+                        .find(|m| m.message_id == Some(message_id));
+                    if let Some(msg) = edited {
+                        msg.content = content;
+                        msg.edited = true;
+                    }
+                } else {
+                    self.logger.push(LogMessage { level: LogLevel::Error, message: format!("Could not edit message: {}", message) });
+                }
+            }
             Message::DiscardGroupMessages { group_id } => {
                 if let Some(token) = &self.session_token {
-                    let cfg = crate::server::config::ClientConfig::from_env();
-                    let host = format!("{}:{}", cfg.default_host, cfg.default_port);
+                    let host = resolve_host(self);
                     let svc = chat_service.clone();
                     let token_clone = token.clone();
                     let group_id_clone = group_id.clone();
@@ -1560,43 +3188,109 @@ impl ChatAppState {
             }
             Message::NewMessagesReceived { with, messages } => {
                 self.loading_private_chats.remove(&with);
-                self.private_chats.insert(with, messages);
+                let previous = self.private_chats.get(&with).map(|v| v.len()).unwrap_or(0);
+                let merged = merge_messages(self.private_chats.get(&with).map(|v| v.as_slice()).unwrap_or(&[]), &messages);
+                let grew = merged.len() > previous;
+                self.private_chats.insert(with.clone(), merged);
+                if grew {
+                    return self.scroll_private_chat_to_end(&with);
+                }
                 return Command::none();
             }
-            Message::NewGroupMessagesReceived { group_id, messages: _ } => {
+            Message::LoadPrivateMessagesPage { with, before_ts } => {
+                // The view asks for a page when the top is reached; that is also "scrolled up"
+                self.user_scrolled_up.insert(with.clone(), true);
+                if !self.loading_older_messages.insert(with.clone()) {
+                    return Command::none();
+                }
+                if let Some(token) = &self.session_token {
+                    let svc = chat_service.clone();
+                    let token_clone = token.clone();
+                    let host = resolve_host(self);
+                    return Command::perform(
+                        async move {
+                            let mut guard = svc.lock().await;
+                            match guard.get_private_messages_page(&host, &token_clone, &with, before_ts, HISTORY_PAGE_SIZE).await {
+                                Ok((messages, has_more)) => Message::OlderMessagesLoaded { with, messages, has_more },
+                                // Keep has_more so the next scroll to the top retries
+                                Err(_) => Message::OlderMessagesLoaded { with, messages: vec![], has_more: true },
+                            }
+                        },
+                        |msg| msg,
+                    );
+                }
+            }
+            Message::OlderMessagesLoaded { with, messages, has_more } => {
+                self.loading_older_messages.remove(&with);
+                self.private_has_more.insert(with.clone(), has_more);
+                let fetch_avatars = self.fetch_missing_avatars(&messages);
+                // Older messages go before the visible ones; merge keeps both, sorted by time
+                let merged = merge_messages(self.private_chats.get(&with).map(|v| v.as_slice()).unwrap_or(&[]), &messages);
+                self.private_chats.insert(with, merged);
+                return fetch_avatars;
+            }
+            Message::MessagesScrolled { chat_id, offset } => {
+                // NaN (content shorter than the viewport) compares false, so it counts as the bottom
+                let scrolled_up = offset.y < 0.95;
+                self.user_scrolled_up.insert(chat_id.clone(), scrolled_up);
+                if !scrolled_up {
+                    self.unseen_new_messages.remove(&chat_id);
+                }
+            }
+            Message::ScrollToLatest { chat_id } => {
+                self.user_scrolled_up.remove(&chat_id);
+                self.unseen_new_messages.remove(&chat_id);
+                return scrollable::snap_to(scrollable::Id::new("messages_scroll"), scrollable::RelativeOffset::END);
+            }
+            Message::NewGroupMessagesReceived { group_id, messages } => {
                 self.loading_group_chats.remove(&group_id);
+                let merged = merge_messages(self.group_chats.get(&group_id).map(|v| v.as_slice()).unwrap_or(&[]), &messages);
+                self.group_chats.insert(group_id, merged);
                 return Command::none();
             }
             Message::StopMessagePolling => {
                 self.polling_active = false;
-                self.app_state = AppState::MainActions;
-                return Command::<Message>::none();
+                self.leave_current_chat();
+                return Command::perform(async { Message::LoadRecentConversations }, |msg| msg);
             }
             Message::StopGroupMessagePolling => {
                 self.group_polling_active = false;
-                self.app_state = AppState::MainActions;
-                return Command::<Message>::none();
+                self.leave_current_chat();
+                return Command::perform(async { Message::LoadRecentConversations }, |msg| msg);
+            }
+            Message::ConnectWebSocket { token, username } => {
+                let svc = chat_service.clone();
+                let cfg = crate::server::config::ClientConfig::from_env();
+                let host = resolve_host(self);
+                return Command::perform(
+                    async move {
+                        let mut guard = svc.lock().await;
+                        guard.set_current_user(username.clone());
+                        println!("[APP] Tentativo connessione WebSocket a {}:{}", cfg.websocket_host, cfg.websocket_port);
+                        match guard.connect_websocket(&cfg.websocket_host, cfg.websocket_port, cfg.enable_tls, &token).await {
+                            Ok(()) => {
+                                println!("[APP] WebSocket connesso, avviando controllo messaggi");
+                                if cfg.enable_encryption {
+                                    if let Err(e) = guard.enable_e2e(&host, &token, &username).await {
+                                        println!("[APP] End-to-end encryption unavailable: {}", e);
+                                    }
+                                }
+                                Message::WebSocketConnected
+                            }
+                            Err(e) => {
+                                println!("[APP] Errore connessione WebSocket: {}", e);
+                                Message::WebSocketError { error: format!("WebSocket connection failed: {}", e) }
+                            }
+                        }
+                    },
+                    |msg| msg,
+                );
             }
             Message::WebSocketConnected => {
                 self.logger.push(LogMessage {
                     level: LogLevel::Success,
                     message: "WebSocket connected - Real-time messaging enabled".to_string(),
                 });
-                
-                // Start WebSocket message polling only if not already active
-                if !self.websocket_polling_active {
-                    self.websocket_polling_active = true;
-                    println!("[APP] 🚀 Starting WebSocket message polling loop");
-                    return Command::perform(
-                        async move {
-                            tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-                            Message::CheckWebSocketMessages
-                        },
-                        |msg| msg,
-                    );
-                } else {
-                    println!("[APP] 🔄 WebSocket polling already active, skipping restart");
-                }
                 return Command::none();
             }
             Message::WebSocketError { error } => {
@@ -1604,10 +3298,26 @@ impl ChatAppState {
                     level: LogLevel::Error,
                     message: format!("WebSocket error: {}", error),
                 });
-                // Stop polling on WebSocket error
-                self.websocket_polling_active = false;
                 return Command::none();
             }
+            Message::WebSocketDisconnected => {
+                // The subscription keeps waiting for the next connection
+                if let Some(token) = self.session_token.clone() {
+                    self.logger.push(LogMessage {
+                        level: LogLevel::Warning,
+                        message: "Real-time connection lost".to_string(),
+                    });
+                    // Reconnect; the server replays the messages missed in the meantime
+                    let username = self.username.clone();
+                    return Command::perform(
+                        async move {
+                            tokio::time::sleep(WEBSOCKET_RECONNECT_DELAY).await;
+                            Message::ConnectWebSocket { token, username }
+                        },
+                        |msg| msg,
+                    );
+                }
+            }
             Message::StartMessagePolling { with } => {
                 // With WebSocket connected, we ONLY load messages once initially
                 // No polling needed - WebSocket will deliver new messages in real-time
@@ -1638,8 +3348,18 @@ impl ChatAppState {
                                 .unwrap_or_else(|| "??:??".to_string()),
                             sent_at: chat_msg.timestamp,
                             is_pending: false,  // This is a confirmed server message
+                            message_id: None,
+                            sender_avatar_url: None,
+                            seen_count: 0,
+                            edited: false,
+                            reactions: HashMap::new(),
+                            reply_to_id: chat_msg.reply_to_id,
+                            forwarded_from_id: None,
                         };
                         
+                        // Whoever sent a message has stopped typing it
+                        self.typing_users.remove(&(chat_msg.group_id.clone().unwrap_or_else(|| chat_msg.from_user.clone()), chat_msg.from_user.clone()));
+
                         // Determine the chat key (who we're chatting with)
                         let chat_key = if chat_msg.chat_type == "private" {
                             if let Some(to_user) = &chat_msg.to_user {
@@ -1669,7 +3389,7 @@ impl ChatAppState {
                         // Add message to the appropriate chat (with deduplication)
                         if chat_msg.chat_type == "private" {
                             let messages = self.private_chats.entry(chat_key.clone())
-                                .or_insert_with(Vec::new);
+                                .or_default();
                             
                             // Check if this WebSocket message is newer than the latest HTTP-loaded message
                             let last_http_ts = self.last_http_timestamp.get(&chat_key).copied().unwrap_or(0);
@@ -1718,7 +3438,7 @@ impl ChatAppState {
                             // Extract just the group_id from "group_groupid" format
                             let group_id = chat_key.strip_prefix("group_").unwrap_or(&chat_key);
                             let messages = self.group_chats.entry(group_id.to_string())
-                                .or_insert_with(Vec::new);
+                                .or_default();
                             
                             // Check if there's a pending message to replace first
                             let replaced_pending = messages.iter_mut().find(|msg| {
@@ -1749,15 +3469,13 @@ impl ChatAppState {
                         
                         // If we're currently viewing this chat, auto-scroll to bottom to trigger UI update
                         if chat_msg.chat_type == "private" {
-                            if let AppState::PrivateChat(current_chat) = &self.app_state {
-                                if current_chat == &chat_key {
-                                    // We're currently viewing this private chat - scroll to bottom
-                                    return scrollable::snap_to(
-                                        scrollable::Id::new("messages_scroll"),
-                                        scrollable::RelativeOffset::END
-                                    );
-                                }
+                            // We may be viewing this private chat - scroll to bottom
+                            let scroll = self.scroll_private_chat_to_end(&chat_key);
+                            // A message arriving in the open chat is read right away
+                            if chat_msg.from_user != self.username && matches!(&self.app_state, AppState::PrivateChat(open) if *open == chat_key) {
+                                return Command::batch([scroll, mark_read(ChatType::Private, chat_key)]);
                             }
+                            return scroll;
                         } else if chat_msg.chat_type == "group" {
                             if let AppState::GroupChat(current_group_id, _) = &self.app_state {
                                 let group_id = chat_key.strip_prefix("group_").unwrap_or(&chat_key);
@@ -1774,6 +3492,66 @@ impl ChatAppState {
                         // Not viewing this chat currently, just add the message silently
                         return Command::none();
                     }
+                    crate::client::services::websocket_client::WebSocketMessage::ReadReceipt { group_id, message_id, user_id } => {
+                        println!("[APP] Message {} in group {} seen by {}", message_id, group_id, user_id);
+                        if let Some(msg) = self.group_chats.get_mut(&group_id).and_then(|msgs| msgs.iter_mut().find(|m| m.message_id == Some(message_id))) {
+                            msg.seen_count += 1;
+                        }
+                    }
+                    crate::client::services::websocket_client::WebSocketMessage::Typing { from_user, group_id, is_typing } => {
+                        let key = (group_id.unwrap_or_else(|| from_user.clone()), from_user);
+                        if is_typing {
+                            self.typing_users.insert(key, std::time::Instant::now());
+                        } else {
+                            self.typing_users.remove(&key);
+                        }
+                    }
+                    crate::client::services::websocket_client::WebSocketMessage::GroupDeleted { group_id } => {
+                        return Command::perform(async move { Message::GroupDeleted { group_id } }, |msg| msg);
+                    }
+                    crate::client::services::websocket_client::WebSocketMessage::KickedFromGroup { group_id } => {
+                        return Command::perform(async move { Message::KickedFromGroup { group_id } }, |msg| msg);
+                    }
+                    crate::client::services::websocket_client::WebSocketMessage::PinsChanged { group_id } => {
+                        // Only groups whose pins we show need refreshing
+                        if self.pinned_messages.contains_key(&group_id) {
+                            return Command::perform(async move { Message::LoadPinnedMessages { group_id } }, |msg| msg);
+                        }
+                    }
+                    crate::client::services::websocket_client::WebSocketMessage::FileReceived { file_id, file_name, mime_type, total_size, from_user, group_id } => {
+                        println!("[APP] File {} ({}, {} bytes) from {} in {:?}", file_id, mime_type, total_size, from_user, group_id);
+                        self.logger.push(LogMessage {
+                            level: LogLevel::Info,
+                            message: format!("📎 {} sent {} ({} bytes)", from_user, file_name, total_size),
+                        });
+                    }
+                    crate::client::services::websocket_client::WebSocketMessage::SystemBroadcast { content } => {
+                        return Command::perform(async move { Message::SystemBroadcast(content) }, |msg| msg);
+                    }
+                    crate::client::services::websocket_client::WebSocketMessage::StatusChanged { username, status } => {
+                        self.user_statuses.insert(username, status);
+                    }
+                    crate::client::services::websocket_client::WebSocketMessage::PresenceUpdate { user, online } => {
+                        if online {
+                            self.online_users.insert(user);
+                        } else {
+                            // Going offline is also the new "last seen"
+                            if let Some(friend) = self.friends_list.iter_mut().find(|f| f.username == user) {
+                                friend.last_seen = Some(chrono::Utc::now().timestamp());
+                            }
+                            self.online_users.remove(&user);
+                        }
+                    }
+                    crate::client::services::websocket_client::WebSocketMessage::GroupRenamed { group_id, new_name } => {
+                        self.apply_group_rename(&group_id, &new_name);
+                    }
+                    crate::client::services::websocket_client::WebSocketMessage::ChatRead { chat_type, chat_id, user, last_read_at } => {
+                        // Group chats show per-message "Seen by" counts instead
+                        if chat_type == "private" && chat_id == user {
+                            let entry = self.private_last_read.entry(chat_id).or_default();
+                            *entry = (*entry).max(last_read_at);
+                        }
+                    }
                     crate::client::services::websocket_client::WebSocketMessage::UserStatusUpdate { user_id, online } => {
                         println!("[APP] User {} is now {}", user_id, if online { "online" } else { "offline" });
                     }
@@ -1787,42 +3565,6 @@ impl ChatAppState {
                 }
                 return Command::none();
             }
-            Message::CheckWebSocketMessages => {
-                // Only continue if polling is still active
-                if !self.websocket_polling_active {
-                    println!("[APP] 🛑 WebSocket polling stopped, ending loop");
-                    return Command::none();
-                }
-                
-                // Check for new WebSocket messages
-                let svc = chat_service.clone();
-                
-                return Command::perform(
-                    async move {
-                        let mut guard = svc.lock().await;
-                        
-                        // Check if WebSocket is connected
-                        if guard.websocket_receiver.is_none() {
-                            // WebSocket not connected, wait and retry
-                            println!("[APP] 🔄 WebSocket receiver not available, retrying in 1 second...");
-                            drop(guard);
-                            tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
-                            return Message::CheckWebSocketMessages;
-                        }
-                        
-                        if let Some(ws_message) = guard.try_receive_websocket_message().await {
-                            drop(guard);
-                            return Message::WebSocketMessageReceived(ws_message);
-                        }
-                        
-                        drop(guard);
-                        // Continue polling after a short delay
-                        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
-                        Message::CheckWebSocketMessages
-                    },
-                    |msg| msg,
-                );
-            }
             // Placeholder implementations for other messages
             _ => {
                 // Handle other messages as needed
@@ -1831,4 +3573,123 @@ impl ChatAppState {
         Command::none()
         
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(id: Option<i64>, sender: &str, content: &str, timestamp: i64) -> ChatMessage {
+        ChatMessage {
+            sender: sender.to_string(),
+            content: content.to_string(),
+            timestamp,
+            formatted_time: String::new(),
+            sent_at: timestamp,
+            is_pending: false,
+            message_id: id,
+            sender_avatar_url: None,
+            seen_count: 0,
+            edited: false,
+            reactions: HashMap::new(),
+            reply_to_id: None,
+            forwarded_from_id: None,
+        }
+    }
+
+    fn ids(messages: &[ChatMessage]) -> Vec<Option<i64>> {
+        messages.iter().map(|m| m.message_id).collect()
+    }
+
+    #[test]
+    fn overlapping_batches_keep_each_message_once() {
+        let existing = vec![msg(Some(1), "alice", "a", 10), msg(Some(2), "bob", "b", 20)];
+        let incoming = vec![msg(Some(2), "bob", "b", 20), msg(Some(3), "alice", "c", 30)];
+        assert_eq!(ids(&merge_messages(&existing, &incoming)), vec![Some(1), Some(2), Some(3)]);
+    }
+
+    #[test]
+    fn duplicates_are_merged_by_id_or_content() {
+        let existing = vec![msg(None, "alice", "hi", 10), msg(Some(2), "bob", "b", 20)];
+        let mut seen = msg(Some(2), "bob", "b", 20);
+        seen.seen_count = 3;
+        let incoming = vec![msg(Some(1), "alice", "hi", 10), seen.clone(), seen];
+        let merged = merge_messages(&existing, &incoming);
+        assert_eq!(ids(&merged), vec![Some(1), Some(2)]);
+        assert_eq!(merged[1].seen_count, 3);
+    }
+
+    #[test]
+    fn out_of_order_batches_are_sorted_by_timestamp() {
+        let existing = vec![msg(Some(5), "alice", "late", 50)];
+        let incoming = vec![msg(Some(3), "bob", "middle", 30), msg(Some(1), "alice", "early", 10)];
+        assert_eq!(ids(&merge_messages(&existing, &incoming)), vec![Some(1), Some(3), Some(5)]);
+    }
+
+    #[test]
+    fn confirmed_copy_replaces_pending_message() {
+        let mut pending = msg(None, "alice", "sending", 10);
+        pending.is_pending = true;
+        let merged = merge_messages(&[pending], &[msg(Some(7), "alice", "sending", 11)]);
+        assert_eq!(merged.len(), 1);
+        assert!(!merged[0].is_pending);
+        assert_eq!(merged[0].message_id, Some(7));
+    }
+
+    #[test]
+    fn older_identical_message_does_not_confirm_pending_one() {
+        let mut pending = msg(None, "alice", "ok", 1000);
+        pending.is_pending = true;
+        let existing = vec![msg(Some(3), "alice", "ok", 100), pending];
+
+        // Full history reload: only the old "ok" is confirmed so far
+        let reload = vec![msg(Some(3), "alice", "ok", 100)];
+        let merged = merge_messages(&existing, &reload);
+        assert_eq!(merged.len(), 2);
+        assert!(merged[1].is_pending);
+
+        // A recent identical message already in the cache does not confirm it either
+        let mut pending = msg(None, "alice", "ok", 1000);
+        pending.is_pending = true;
+        let existing = vec![msg(Some(4), "alice", "ok", 995), pending];
+        let merged = merge_messages(&existing, &[msg(Some(4), "alice", "ok", 995)]);
+        assert!(merged[1].is_pending);
+
+        let merged = merge_messages(&merged, &[msg(Some(4), "alice", "ok", 995), msg(Some(5), "alice", "ok", 1001)]);
+        assert_eq!(ids(&merged), vec![Some(4), Some(5)]);
+        assert!(merged.iter().all(|m| !m.is_pending));
+    }
+
+    #[test]
+    fn polling_flags_follow_start_and_stop_messages() {
+        let mut state = ChatAppState { group_polling_active: true, ..Default::default() };
+        state.update_polling_flags(&Message::StartMessagePolling { with: "bob".to_string() });
+        assert!(state.polling_active && !state.group_polling_active);
+
+        state.update_polling_flags(&Message::StartGroupMessagePolling { group_id: "g1".to_string() });
+        assert!(!state.polling_active && !state.group_polling_active);
+
+        state.update_polling_flags(&Message::StartMessagePolling { with: "bob".to_string() });
+        state.update_polling_flags(&Message::StopMessagePolling);
+        assert!(!state.polling_active && !state.group_polling_active);
+
+        state.update_polling_flags(&Message::StartMessagePolling { with: "bob".to_string() });
+        state.group_polling_active = true;
+        state.update_polling_flags(&Message::StopGroupMessagePolling);
+        assert!(!state.polling_active && !state.group_polling_active);
+
+        // Other messages leave the flags alone
+        state.update_polling_flags(&Message::StartMessagePolling { with: "bob".to_string() });
+        state.update_polling_flags(&Message::NoOp);
+        assert!(state.polling_active);
+    }
+
+    #[test]
+    fn edited_copy_updates_content() {
+        let mut edited = msg(Some(1), "alice", "fixed", 10);
+        edited.edited = true;
+        let merged = merge_messages(&[msg(Some(1), "alice", "typo", 10)], &[edited]);
+        assert_eq!(merged[0].content, "fixed");
+        assert!(merged[0].edited);
+    }
+}
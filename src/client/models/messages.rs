@@ -1,19 +1,33 @@
 use crate::client::gui::views::registration::HostType;
+use crate::client::gui::views::logger::LogLevel;
+use crate::client::services::group_service::GroupSummary;
+use crate::client::services::friend_service::FriendRequest;
+
+/// Previous access to the account, as reported by the server on login.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LastLogin {
+    /// No earlier session on record
+    First,
+    /// Unix timestamp of the previous login
+    At(i64),
+}
 
 #[derive(Debug, Clone)]
 pub enum Message {
     // Placeholder per tutte le azioni dell'app
     NoOp,  // No operation - used when we need to return a message but do nothing
     Logout,
-    None,
     ManualHostChanged(String),
     UsernameChanged(String),
     PasswordChanged(String),
     HostSelected(HostType),
     ToggleLoginRegister,
     SubmitLoginOrRegister,
-    AuthResult { success: bool, message: String, token: Option<String> },
+    /// `last_login` is only set for a successful `/login`
+    AuthResult { success: bool, message: String, token: Option<String>, last_login: Option<LastLogin> },
     SessionMissing,
+    /// The startup session check could not reach the server; logs it, then acts as `SessionMissing`
+    ServerUnreachable,
     ClearLog,
     LogInfo(String),
     LogSuccess(String),
@@ -28,6 +42,7 @@ pub enum Message {
     UsersSearch,
     UsersListLoaded { kind: String, list: Vec<String> },
     UsersListFiltered { list: Vec<String> },
+    UserPresenceLoaded { presence: std::collections::HashMap<String, crate::client::services::users_service::UserPresence> },
     // Test network actions triggered from main_actions (use defaults in the UI)
     SendGroupMessageTest,
     SendPrivateMessageTest,
@@ -52,20 +67,45 @@ pub enum Message {
     JoinGroup { group_id: String },
     // Private chat messages
     MessageInputChanged(String),
+    /// Fired `TYPING_DEBOUNCE` after a keystroke: tell the open chat we are typing
+    SendTypingIndicator,
+    /// Periodic check that expires stale "is typing" indicators
+    TypingTick,
+    /// Announcement from a server administrator, shown as a banner for `BROADCAST_BANNER_TTL`
+    SystemBroadcast(String),
+    /// Periodic check that removes an expired broadcast banner
+    BroadcastTick,
+    /// Tell the server we have read a chat up to `timestamp`
+    MarkRead { chat_type: crate::client::models::app_state::ChatType, chat_id: String, timestamp: i64 },
+    /// Fetch how far the other user has read a private chat
+    LoadReadReceipts { with: String },
+    ReadReceiptsLoaded { with: String, read_by: std::collections::HashMap<String, i64> },
     SendPrivateMessage { to: String },
     LoadPrivateMessages { with: String },
-    PrivateMessagesLoaded { with: String, messages: Vec<crate::client::models::app_state::ChatMessage> },
+    /// Latest page of a private chat; `has_more` tells whether older messages can be paged in
+    PrivateMessagesLoaded { with: String, messages: Vec<crate::client::models::app_state::ChatMessage>, has_more: bool },
+    /// Page in private messages sent before `before_ts` (the oldest one shown)
+    LoadPrivateMessagesPage { with: String, before_ts: i64 },
+    OlderMessagesLoaded { with: String, messages: Vec<crate::client::models::app_state::ChatMessage>, has_more: bool },
     // Real-time message updates
     StartMessagePolling { with: String },
     StopMessagePolling,
     NewMessagesReceived { with: String, messages: Vec<crate::client::models::app_state::ChatMessage> },
+    /// Scroll position of a private chat, used to stop snapping to the bottom while reading history
+    MessagesScrolled { chat_id: String, offset: iced::widget::scrollable::RelativeOffset },
+    /// "New messages" button: jump back to the newest message
+    ScrollToLatest { chat_id: String },
     TriggerImmediateRefresh { with: String },
     // Navigation with polling control
     OpenMainActions,
     // Group chat messages
     SendGroupMessage { group_id: String },
     LoadGroupMessages { group_id: String },
-    GroupMessagesLoaded { group_id: String, messages: Vec<crate::client::models::app_state::ChatMessage> },
+    /// Latest page of a group chat; `next_before` is the cursor of the older page, if any
+    GroupMessagesLoaded { group_id: String, messages: Vec<crate::client::models::app_state::ChatMessage>, next_before: Option<i64> },
+    /// Page in group messages sent before `before_ts`
+    LoadGroupMessagesPage { group_id: String, before_ts: i64, limit: i64 },
+    OlderGroupMessagesLoaded { group_id: String, messages: Vec<crate::client::models::app_state::ChatMessage>, next_before: Option<i64> },
     // Real-time group message updates
     StartGroupMessagePolling { group_id: String },
     StopGroupMessagePolling,
@@ -76,45 +116,191 @@ pub enum Message {
     OpenMyGroups,
     OpenInviteToGroup { group_id: String, group_name: String },
     CreateGroupInputChanged(String),
+    CreateGroupDescriptionChanged(String),
     CreateGroupSubmit,
     GroupCreated { group_id: String, group_name: String },
     // Participant selection for group creation
     ToggleParticipant(String),
     RemoveParticipant(String),
-    MyGroupsLoaded { groups: Vec<(String, String, usize)> }, // (id, name, member_count)
+    MyGroupsLoaded { groups: Vec<GroupSummary> },
+    MyGroupsFailed { message: String },
     InviteUserToGroup { group_id: String, username: String },
     // Group invites management
     OpenMyGroupInvites,
-    MyGroupInvitesLoaded { invites: Vec<(i64, String, String)> }, // (invite_id, group_name, invited_by)
+    MyGroupInvitesLoaded { invites: Vec<crate::client::services::group_service::GroupInvite> },
+    MyGroupInvitesFailed { message: String },
     AcceptGroupInvite { invite_id: i64 },
     RejectGroupInvite { invite_id: i64 },
     GroupInviteActionResult { success: bool, message: String },
+    RefreshGroupInvitesDelayed,
     // Leave group
+    ConfirmLeaveGroup { group_id: String, group_name: String },
     LeaveGroup { group_id: String, group_name: String},
-    LeaveGroupResult { success: bool, message: String },
+    LeaveGroupResult { success: bool, message: String, group_id: String },
+    ConfirmDeleteGroup { group_id: String, group_name: String },
+    DeleteGroup { group_id: String, group_name: String },
+    /// A group was deleted, by us or (through the WebSocket) by its creator
+    GroupDeleted { group_id: String },
     // Error handling for group membership
     NotAMember { group_id: String },
     // Discard messages feature
     DiscardPrivateMessages { with: String },
     DiscardGroupMessages { group_id: String },
+    /// Delete one message for everyone; it disappears locally before the server confirms
+    DeleteMessage { message_id: i64 },
+    DeleteMessageResult { message_id: i64, success: bool, message: String },
+    /// Put an own message into the input box; the next submit replaces its content
+    StartEditMessage { message_id: i64, content: String },
+    CancelEditMessage,
+    SubmitEditMessage { message_id: i64 },
+    EditMessageResult { message_id: i64, success: bool, message: String, content: String },
+    /// Quote a message of the open chat above the input box; the next message sent replies to it
+    StartReply { message_id: i64 },
+    CancelReply,
+    /// Scroll the open chat to a message, e.g. the one a reply quotes
+    ScrollToMessage { message_id: i64 },
+    /// Show the chat picker to forward a message
+    OpenForwardPicker { message_id: i64 },
+    CloseForwardPicker,
+    /// Copy a message into `target_chat_id` (`private:<username>` or `group:<group_id>`)
+    ForwardMessage { message_id: i64, target_chat_id: String },
+    MessageForwarded { target_chat_id: String },
+    /// Open or close the quick reaction picker under a message
+    ToggleReactionPicker { message_id: i64 },
+    /// React with `emoji`, or take the reaction back if the user already reacted with it
+    ToggleReaction { message_id: i64, emoji: String },
+    ReactionToggled { message_id: i64, emoji: String, added: bool, success: bool, message: String },
     // Friend system
     OpenSendFriendRequest,
     OpenViewFriends,
     SendFriendRequestToUser { username: String, message: String },
     FriendRequestResult { success: bool, message: String },
+    FriendActionFailed { level: LogLevel, message: String },
     // Friend request management
     AcceptFriendRequestFromUser { username: String },
     RejectFriendRequestFromUser { username: String },
-    FriendsLoaded { friends: Vec<String> },
-    FriendRequestsLoaded { requests: Vec<(String, String)> },
+    FriendsLoaded { friends: Vec<crate::client::services::friend_service::FriendInfo> },
+    FriendRequestsLoaded { requests: Vec<FriendRequest> },
+    SentFriendRequestsLoaded { usernames: Vec<String> },
+    FriendRequestSent { to: String },
+    // Blocked users
+    SearchGroupsQueryChanged(String),
+    SearchGroups { query: String },
+    GroupSearchResults { groups: Vec<(crate::client::services::group_service::GroupSummary, i64)> },
+    JoinGroupResult { success: bool, message: String },
+    /// Resolve a group by exact name, then open its chat (or list the matches if ambiguous)
+    OpenGroupChatByName { group_name: String },
+    GroupNameAmbiguous { group_name: String, groups: Vec<(crate::client::services::group_service::GroupSummary, i64)> },
+    ExportChat { chat_type: crate::client::models::app_state::ChatType, chat_id: String },
+    ExportChatResult { success: bool, message: String },
+    ToggleMemberSidebar { group_id: String },
+    LoadGroupMembers { group_id: String },
+    GroupMembersLoaded { group_id: String, members: Vec<crate::client::services::group_service::GroupMemberInfo> },
+    // Group rename
+    StartRenameGroup { current_name: String },
+    RenameGroupDraftChanged(String),
+    CancelRenameGroup,
+    RenameGroup { group_id: String, new_name: String },
+    /// A group was renamed, by us or (through the WebSocket) by another admin
+    GroupRenamed { group_id: String, new_name: String },
+    // Group description
+    LoadGroupDescription { group_id: String },
+    GroupDescriptionLoaded { group_id: String, description: String },
+    /// Show the whole description of a group chat, or only its first characters again
+    ToggleGroupDescription { group_id: String },
+    StartEditGroupDescription { current: String },
+    GroupDescriptionDraftChanged(String),
+    CancelEditGroupDescription,
+    SaveGroupDescription { group_id: String, description: String },
+    /// Promote a member to group admin (`admin`) or demote an admin back to member
+    SetGroupAdmin { group_id: String, username: String, admin: bool },
+    GroupRoleChanged { group_id: String, success: bool, message: String },
+    /// Remove a plain member from the group; the result comes back as `GroupRoleChanged`
+    KickMember { group_id: String, username: String },
+    /// An admin removed us from the group (WebSocket)
+    KickedFromGroup { group_id: String },
+    AvatarLoaded { url: String, handle: Option<iced::widget::image::Handle> },
+    // Message search
+    /// Search the messages of every chat, remembering the current view for the back button
+    OpenMessageSearch,
+    MessageSearchQueryChanged(String),
+    SearchMessages { query: String },
+    SearchResultsLoaded { results: Vec<crate::client::services::message_parser::SearchResult> },
+    OpenBlockedUsers,
+    /// (Re)load the blocked users list from the server
+    ListBlocked,
+    BlockedUsersLoaded { users: Vec<String> },
+    BlockUser { username: String },
+    UnblockUser { username: String },
+    BlockActionResult { message: String },
+    // Recent conversations
+    LoadRecentConversations,
+    RecentConversationsLoaded { conversations: Vec<crate::client::models::app_state::ConversationSummary> },
+    // Archived chats
+    OpenArchivedChats,
+    ArchivedChatsLoaded { chats: Vec<crate::client::models::app_state::ArchivedChat> },
+    ArchiveChat { chat_type: crate::client::models::app_state::ChatType, chat_id: String },
+    UnarchiveChat { chat_type: crate::client::models::app_state::ChatType, chat_id: String },
+    ArchiveActionResult { success: bool, message: String },
     InviteToGroupResult{success: bool, message: String},
     DiscardMessagesResult { success: bool, message: String, username: Option<String>, group_id: Option<String> },
     // WebSocket connection messages
+    /// Open the real-time connection for a freshly authenticated session
+    ConnectWebSocket { token: String, username: String },
     WebSocketConnected,
     WebSocketError { error: String },
     // Real-time WebSocket messages
     WebSocketMessageReceived(crate::client::services::websocket_client::WebSocketMessage),
-    CheckWebSocketMessages,
+    WebSocketDisconnected,
     // Logout completion
     LogoutCompleted,
+    // Account menu and password change
+    ToggleAccountMenu,
+    OpenChangePassword,
+    ChangePasswordOldChanged(String),
+    ChangePasswordNewChanged(String),
+    ChangePasswordConfirmChanged(String),
+    SubmitChangePassword { old: String, new: String },
+    ChangePasswordResult { success: bool, message: String },
+    // Account deletion
+    OpenDeleteAccount,
+    DeleteAccountConfirmChanged(String),
+    DeleteAccountPasswordChanged(String),
+    SubmitDeleteAccount { password: String },
+    AccountDeleted { success: bool, message: String },
+    // Active sessions
+    OpenSessions,
+    SessionsLoaded { sessions: Vec<crate::client::services::message_parser::SessionInfo> },
+    RevokeSession { id: String },
+    SessionRevoked { id: String },
+    // Status messages
+    /// Open the status editor in the main actions header, or update the text being typed
+    SetStatus(String),
+    CancelStatus,
+    SaveStatus { status: String },
+    StatusLoaded { username: String, status: String },
+    // Public group discovery
+    OpenDiscoverGroups,
+    DiscoverQueryChanged(String),
+    LoadPublicGroups { query: String, page: i64 },
+    PublicGroupsLoaded { page: crate::client::services::group_service::PublicGroupsPage },
+    JoinCodeChanged(String),
+    JoinByCode { code: String },
+    JoinedByCode { group_id: String, group_name: String },
+    // Group invite codes
+    OpenInviteCodeDialog { group_id: String },
+    CloseInviteCodeDialog,
+    /// Expiry of the next code in seconds; `None` for no expiry
+    InviteCodeExpiryChanged(Option<i64>),
+    InviteCodeMaxUsesChanged(String),
+    GenerateInviteCode,
+    InviteCodeGenerated { group_id: String, code: String },
+    CopyInviteCode { code: String },
+    // Pinned messages
+    LoadPinnedMessages { group_id: String },
+    PinnedMessagesLoaded { group_id: String, pins: Vec<crate::client::services::message_parser::PinnedMessage> },
+    TogglePinnedBanner { group_id: String },
+    OpenPinnedMessages { group_id: String },
+    /// Pin (`pin`) or unpin a message of a group chat
+    SetMessagePinned { group_id: String, message_id: i64, pin: bool },
 }
\ No newline at end of file
@@ -0,0 +1,31 @@
+// Download e decodifica degli avatar degli utenti
+use iced::widget::image::Handle;
+
+/// Side in pixels of the decoded avatar; rendered at 32x32, so this stays sharp on HiDPI screens
+const AVATAR_PIXELS: u32 = 64;
+
+/// Largest avatar download accepted
+const MAX_AVATAR_BYTES: usize = 2 * 1024 * 1024;
+
+/// Download `url` and turn it into a square, circle-masked image handle.
+pub async fn fetch_avatar(url: &str) -> anyhow::Result<Handle> {
+    let resp = reqwest::get(url).await?.error_for_status()?;
+    let bytes = resp.bytes().await?;
+    if bytes.len() > MAX_AVATAR_BYTES {
+        anyhow::bail!("avatar too large ({} bytes)", bytes.len());
+    }
+    let mut img = image::load_from_memory(&bytes)?
+        .resize_to_fill(AVATAR_PIXELS, AVATAR_PIXELS, image::imageops::FilterType::Triangle)
+        .to_rgba8();
+
+    // iced 0.12 cannot clip images, so make the pixels outside the circle transparent
+    let radius = AVATAR_PIXELS as f32 / 2.0;
+    for (x, y, pixel) in img.enumerate_pixels_mut() {
+        let dx = x as f32 + 0.5 - radius;
+        let dy = y as f32 + 0.5 - radius;
+        if dx * dx + dy * dy > radius * radius {
+            pixel[3] = 0;
+        }
+    }
+    Ok(Handle::from_pixels(AVATAR_PIXELS, AVATAR_PIXELS, img.into_raw()))
+}
@@ -3,12 +3,49 @@ use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, BufWriter};
 use tokio::sync::{mpsc, oneshot};
 use tokio::time::{Duration, timeout};
 use crate::client::services::message_parser;
-use crate::client::services::websocket_client::{WebSocketClient, WebSocketMessage};
+use crate::client::services::websocket_client::{LastMessageAt, WebSocketClient, WebSocketMessage};
+use crate::common::crypto::{CryptoManager, E2E_PREFIX};
+use crate::common::protocol::{ServerCapabilities, PROTOCOL_VERSION};
+use std::collections::HashMap;
 
 #[derive(Debug)]
 pub enum CommandType {
     SingleLine(String),
     MultiLine(String),
+    /// Single-line commands written back-to-back; the responses are read in order
+    /// and sent back joined by '\n'
+    Pipeline(Vec<String>),
+}
+
+/// Longest `reset` waits for commands already sent to the background task
+const RESET_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Longest `ensure_connected` waits for the TCP connection to the server
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Private messages requested per `/get_private_messages_page`
+pub const HISTORY_PAGE_SIZE: i64 = 50;
+
+type ServerReader = BufReader<tokio::net::tcp::OwnedReadHalf>;
+type ServerWriter = BufWriter<tokio::net::tcp::OwnedWriteHalf>;
+
+/// Write every command in `cmds` without waiting, then read one response line per command.
+/// Responses read before a failure are kept in `responses`.
+async fn run_pipeline(reader: &mut ServerReader, writer: &mut ServerWriter, cmds: &[String], responses: &mut Vec<String>) -> std::io::Result<()> {
+    for cmd in cmds {
+        writer.write_all(cmd.as_bytes()).await?;
+        writer.write_all(b"\n").await?;
+    }
+    writer.flush().await?;
+    let mut line = String::new();
+    for _ in cmds {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            return Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "server closed connection"));
+        }
+        responses.push(line.trim().to_string());
+    }
+    Ok(())
 }
 
 #[derive(Default)]
@@ -24,6 +61,15 @@ pub struct ChatService {
     pub current_user: Option<String>,
     /// Receiver per messaggi WebSocket
     pub websocket_receiver: Option<mpsc::UnboundedReceiver<WebSocketMessage>>,
+    /// Features declared by the server in its HELLO reply
+    pub capabilities: Option<ServerCapabilities>,
+    /// End-to-end secret key of the current user, set once its public key is registered
+    pub e2e_secret: Option<[u8; 32]>,
+    /// Public keys of peers (`None` when they have not registered one)
+    pub peer_keys: HashMap<String, Option<[u8; 32]>>,
+    /// Last message received over WebSocket, survives `reset` so a reconnection recovers
+    /// what arrived meanwhile; cleared on logout
+    pub last_ws_message_at: LastMessageAt,
 }
 
 impl ChatService {
@@ -34,17 +80,31 @@ impl ChatService {
             websocket: None,
             current_user: None,
             websocket_receiver: None,
+            capabilities: None,
+            e2e_secret: None,
+            peer_keys: HashMap::new(),
+            last_ws_message_at: LastMessageAt::default(),
         }
     }
     
-    /// Reset the service by dropping existing connections and background tasks
+    /// Reset the service by dropping existing connections and background tasks.
+    /// Commands already queued are answered first: closing the channel lets the
+    /// background task finish them and exit, waiting at most `RESET_DRAIN_TIMEOUT`.
     pub async fn reset(&mut self) {
         println!("[CHAT_SERVICE] 🔄 Resetting ChatService - dropping all connections");
-        self.tx = None;
-        self._bg = None;
+        drop(self.tx.take());
+        if let Some(handle) = self._bg.take() {
+            let abort = handle.abort_handle();
+            if timeout(RESET_DRAIN_TIMEOUT, handle).await.is_err() {
+                println!("[CHAT_SERVICE] ⚠️ Pending commands did not complete in time, aborting");
+                abort.abort();
+            }
+        }
         self.websocket = None;
         self.current_user = None;
         self.websocket_receiver = None;
+        self.e2e_secret = None;
+        self.peer_keys.clear();
         println!("[CHAT_SERVICE] ✅ Reset completed");
     }
 
@@ -65,13 +125,43 @@ impl ChatService {
         
         // Then reset local state
         self.reset().await;
+        if let Ok(mut last) = self.last_ws_message_at.lock() {
+            *last = None;
+        }
         println!("[CHAT_SERVICE] 🚪 Logout completed");
         Ok(())
     }
 
-    /// Initialize WebSocket connection
-    pub async fn connect_websocket(&mut self, ws_host: &str, ws_port: u16, session_token: &str) -> anyhow::Result<()> {
-        let ws_url = format!("ws://{}:{}", ws_host, ws_port);
+    /// Delete the account of `session_token` after the server checks `password`; on success the
+    /// session no longer exists, so local state is reset as on logout.
+    pub async fn delete_account(&mut self, host: &str, session_token: &str, password: &str) -> anyhow::Result<String> {
+        let response = self.send_command(host, format!("/delete_account {} {}", session_token, password)).await?;
+        if !response.starts_with("OK:") {
+            return Err(anyhow::anyhow!(response.trim_start_matches("ERR:").trim().to_string()));
+        }
+        self.reset().await;
+        println!("[CHAT_SERVICE] Account deleted");
+        Ok(response)
+    }
+
+    /// Our active sessions, newest first
+    pub async fn list_sessions(&mut self, host: &str, session_token: &str) -> anyhow::Result<Vec<message_parser::SessionInfo>> {
+        let response = self.send_command(host, format!("/list_sessions {}", session_token)).await?;
+        message_parser::parse_sessions(&response).map_err(|e| anyhow::anyhow!(e))
+    }
+
+    /// Sign out our session `session_id` (see `SessionInfo::id`)
+    pub async fn revoke_session(&mut self, host: &str, session_token: &str, session_id: &str) -> anyhow::Result<String> {
+        let response = self.send_command(host, format!("/revoke_session {} {}", session_token, session_id)).await?;
+        if !response.starts_with("OK:") {
+            return Err(anyhow::anyhow!(response.trim_start_matches("ERR:").trim().to_string()));
+        }
+        Ok(response)
+    }
+
+    /// Initialize WebSocket connection, over TLS (wss://) when `tls` is set
+    pub async fn connect_websocket(&mut self, ws_host: &str, ws_port: u16, tls: bool, session_token: &str) -> anyhow::Result<()> {
+        let ws_url = format!("{}://{}:{}", if tls { "wss" } else { "ws" }, ws_host, ws_port);
         println!("[CHAT_SERVICE] 🔌 Starting WebSocket connection to {}", ws_url);
         
         // Reset any existing WebSocket connection
@@ -80,6 +170,7 @@ impl ChatService {
         // Create new WebSocket client
         let mut ws_client = WebSocketClient::new(ws_url.clone());
         ws_client.set_session_token(session_token.to_string());
+        ws_client.set_last_message_at(self.last_ws_message_at.clone());
         
         // Get the receiver before connecting
         self.websocket_receiver = ws_client.take_receiver();
@@ -150,11 +241,26 @@ impl ChatService {
         }
 
         let host = host.to_string();
-        let stream = TcpStream::connect(&host).await?;
+        let stream = timeout(CONNECT_TIMEOUT, TcpStream::connect(&host))
+            .await
+            .map_err(|_| anyhow::anyhow!("connection to {} timed out", host))??;
         let (reader, writer) = stream.into_split();
         let mut reader = BufReader::new(reader);
         let mut writer = BufWriter::new(writer);
 
+        // Protocol negotiation: must happen before any other command
+        writer.write_all(format!("HELLO {}\n", PROTOCOL_VERSION).as_bytes()).await?;
+        writer.flush().await?;
+        let mut hello = String::new();
+        reader.read_line(&mut hello).await?;
+        if hello.starts_with("ERR:") {
+            return Err(anyhow::anyhow!(hello.trim().to_string()));
+        }
+        self.capabilities = ServerCapabilities::parse(&hello);
+        if self.capabilities.is_none() {
+            println!("[CHAT_SERVICE] Server did not answer HELLO as expected: {}", hello.trim());
+        }
+
         let (tx, mut rx) = mpsc::unbounded_channel::<(CommandType, oneshot::Sender<String>)>();
 
         // Spawn background task that processes outgoing requests sequentially.
@@ -173,6 +279,39 @@ impl ChatService {
                 let (cmd, is_multiline) = match cmd_type {
                     CommandType::SingleLine(cmd) => (cmd, false),
                     CommandType::MultiLine(cmd) => (cmd, true),
+                    CommandType::Pipeline(cmds) => {
+                        let mut responses = Vec::with_capacity(cmds.len());
+                        let mut reconnected = false;
+                        loop {
+                            let pending = &cmds[responses.len()..];
+                            match run_pipeline(&mut reader, &mut writer, pending, &mut responses).await {
+                                Ok(()) => break,
+                                Err(e) if !reconnected => {
+                                    // Resend only the commands we have no response for, once
+                                    eprintln!("[CLIENT:SVC] pipeline failed: {}, reconnecting...", e);
+                                    reconnected = true;
+                                    match TcpStream::connect(&host).await {
+                                        Ok(s) => {
+                                            let (r, w) = s.into_split();
+                                            reader = BufReader::new(r);
+                                            writer = BufWriter::new(w);
+                                        }
+                                        Err(e) => {
+                                            eprintln!("[CLIENT:SVC] reconnect failed: {}", e);
+                                            break;
+                                        }
+                                    }
+                                }
+                                Err(e) => {
+                                    eprintln!("[CLIENT:SVC] pipeline failed again: {}", e);
+                                    break;
+                                }
+                            }
+                        }
+                        responses.resize(cmds.len(), "ERR: pipeline interrupted".to_string());
+                        let _ = resp_tx.send(responses.join("\n"));
+                        continue;
+                    }
                 };
 
 
@@ -388,14 +527,36 @@ impl ChatService {
         }
     }
 
+    /// Send several single-line commands back-to-back and return their responses in order.
+    /// Saves one round-trip per command compared to calling `send_command` in a loop.
+    pub async fn send_pipeline(&mut self, host: &str, commands: Vec<String>) -> anyhow::Result<Vec<String>> {
+        if commands.is_empty() {
+            return Ok(vec![]);
+        }
+        if commands.iter().any(|c| c.contains('\n')) {
+            return Err(anyhow::anyhow!("pipelined commands must be single-line"));
+        }
+        self.ensure_connected(host).await?;
+        if let Some(tx) = &self.tx {
+            let (resp_tx, resp_rx) = oneshot::channel();
+            tx.send((CommandType::Pipeline(commands), resp_tx)).map_err(|_| anyhow::anyhow!("send failed: background task ended"))?;
+            let resp = resp_rx.await.map_err(|_| anyhow::anyhow!("response channel closed before response"))?;
+            Ok(resp.split('\n').map(str::to_string).collect())
+        } else {
+            Err(anyhow::anyhow!("not connected"))
+        }
+    }
+
     // Placeholder methods for later
     /// Send a private message using WebSocket if available, fallback to TCP.
-    /// Returns the raw server response.
-    pub async fn send_private_message(&mut self, host: &str, session_token: &str, to: &str, msg: &str) -> anyhow::Result<String> {
+    /// `reply_to_id` is the message it answers, if any. Returns the raw server response.
+    pub async fn send_private_message(&mut self, host: &str, session_token: &str, to: &str, msg: &str, reply_to_id: Option<i64>) -> anyhow::Result<String> {
+        let sealed = self.seal_private_message(host, session_token, to, msg).await;
+        let msg = sealed.as_deref().unwrap_or(msg);
         // Try WebSocket first if connected
         if let Some(ref websocket) = self.websocket {
             if websocket.is_connected() {
-                match websocket.send_private_message(to, msg).await {
+                match websocket.send_private_message(to, msg, reply_to_id).await {
                     Ok(()) => {
                         println!("[CHAT_SERVICE] Message sent via WebSocket to {}", to);
                         return Ok("OK: Message sent via WebSocket".to_string());
@@ -409,7 +570,10 @@ impl ChatService {
         }
         
         // Fallback to TCP
-        let cmd = format!("/send_private_message {} {} {}", session_token, to, msg);
+        let cmd = match reply_to_id {
+            Some(reply_to_id) => format!("/send_reply {} private:{} {} {}", session_token, to, reply_to_id, msg),
+            None => format!("/send_private_message {} {} {}", session_token, to, msg),
+        };
         let resp = self.send_command(host, cmd).await?;
         Ok(resp)
     }
@@ -421,6 +585,77 @@ impl ChatService {
         
         println!("[CHAT_SERVICE] Raw response: {}", resp);
         
+        self.parse_private_messages(&resp, with)
+    }
+
+    /// Retrieve only the private messages with `with` sent at or after `since_ts`.
+    /// Used by the polling loop so idle conversations cost an almost empty response.
+    pub async fn poll_new_messages(&mut self, host: &str, session_token: &str, with: &str, since_ts: i64) -> anyhow::Result<Vec<crate::client::models::app_state::ChatMessage>> {
+        let cmd = format!("/get_private_messages_since {} {} {}", session_token, with, since_ts);
+        let resp = self.send_multiline_command(host, cmd).await?;
+        self.parse_private_messages(&resp, with)
+    }
+
+    /// Up to `limit` private messages with `with` sent before `before_ts`, oldest first,
+    /// and whether older ones remain. Pass `i64::MAX` to get the latest page.
+    pub async fn get_private_messages_page(&mut self, host: &str, session_token: &str, with: &str, before_ts: i64, limit: i64) -> anyhow::Result<(Vec<crate::client::models::app_state::ChatMessage>, bool)> {
+        if self.capabilities.as_ref().is_some_and(|c| !c.supports("message_pages")) {
+            return Err(anyhow::anyhow!("server does not support message pages"));
+        }
+        let cmd = format!("/get_private_messages_page {} {} {} {}", session_token, with, before_ts, limit);
+        let resp = self.send_multiline_command(host, cmd).await?;
+        let has_more = message_parser::page_has_more(&resp);
+        Ok((self.parse_private_messages(&resp, with)?, has_more))
+    }
+
+    /// Publish this device's end-to-end public key for `username`, creating the key
+    /// pair on first login. Private messages are sealed from then on when the peer has a key too.
+    pub async fn enable_e2e(&mut self, host: &str, session_token: &str, username: &str) -> anyhow::Result<()> {
+        if self.capabilities.as_ref().is_some_and(|c| !c.supports("e2e_keys")) {
+            return Err(anyhow::anyhow!("server does not support end-to-end keys"));
+        }
+        let secret = crate::client::utils::e2e_keys::load_or_create_secret(username)?;
+        let public_key = CryptoManager::encode_public_key(&CryptoManager::client_public_key(&secret));
+        let resp = self.send_command(host, format!("/publish_key {} {}", session_token, public_key)).await?;
+        if !resp.starts_with("OK:") {
+            return Err(anyhow::anyhow!(resp));
+        }
+        self.e2e_secret = Some(secret);
+        Ok(())
+    }
+
+    /// Public key published by `username`, looked up once per session
+    /// (when their private chat is first opened, or on the first send).
+    pub async fn peer_public_key(&mut self, host: &str, session_token: &str, username: &str) -> Option<[u8; 32]> {
+        if let Some(key) = self.peer_keys.get(username) {
+            return *key;
+        }
+        let resp = self.send_command(host, format!("/get_public_key {} {}", session_token, username)).await.ok()?;
+        let key = resp.strip_prefix("OK: Public key:").and_then(CryptoManager::parse_public_key);
+        self.peer_keys.insert(username.to_string(), key);
+        key
+    }
+
+    /// Seal `msg` for `to` and for ourselves, or `None` to send it as is
+    /// (end-to-end mode off, or the peer has no public key).
+    async fn seal_private_message(&mut self, host: &str, session_token: &str, to: &str, msg: &str) -> Option<String> {
+        let secret = self.e2e_secret?;
+        let peer_key = self.peer_public_key(host, session_token, to).await?;
+        let own_key = CryptoManager::client_public_key(&secret);
+        CryptoManager::client_encrypt(msg, &[peer_key, own_key]).ok()
+    }
+
+    /// Decrypt content sealed by `seal_private_message`; other content is returned unchanged
+    pub fn open_e2e(&self, content: String) -> String {
+        if !content.starts_with(E2E_PREFIX) {
+            return content;
+        }
+        self.e2e_secret
+            .and_then(|secret| CryptoManager::client_decrypt(&content, &secret).ok())
+            .unwrap_or_else(|| "🔒 Encrypted message".to_string())
+    }
+
+    fn parse_private_messages(&self, resp: &str, with: &str) -> anyhow::Result<Vec<crate::client::models::app_state::ChatMessage>> {
         // For private messages, participants are current user and the other user
         let participants = if let Some(current_user) = &self.current_user {
             vec![current_user.clone(), with.to_string()]
@@ -428,8 +663,14 @@ impl ChatService {
             vec![with.to_string()]
         };
         
-        let msgs = message_parser::parse_private_messages_with_participants(&resp, &participants)
+        let (mut msgs, warnings) = message_parser::parse_private_messages_with_participants(resp, &participants, message_parser::ParseMode::Lenient)
             .map_err(|e| anyhow::anyhow!(e))?;
+        for msg in &mut msgs {
+            msg.content = self.open_e2e(std::mem::take(&mut msg.content));
+        }
+        for warning in &warnings {
+            log::debug!("[CHAT_SERVICE] Skipped malformed line {}: {}", warning.line_number, warning.line);
+        }
         
         println!("[CHAT_SERVICE] Parsed {} messages", msgs.len());
         for (i, msg) in msgs.iter().enumerate() {
@@ -440,12 +681,12 @@ impl ChatService {
     }
 
     /// Send a group message using WebSocket if available, fallback to TCP.
-    /// Returns the raw server response.
-    pub async fn send_group_message(&mut self, host: &str, session_token: &str, group_id: &str, msg: &str) -> anyhow::Result<String> {
+    /// `reply_to_id` is the message it answers, if any. Returns the raw server response.
+    pub async fn send_group_message(&mut self, host: &str, session_token: &str, group_id: &str, msg: &str, reply_to_id: Option<i64>) -> anyhow::Result<String> {
         // Try WebSocket first if connected
         if let Some(ref websocket) = self.websocket {
             if websocket.is_connected() {
-                match websocket.send_group_message(group_id, msg).await {
+                match websocket.send_group_message(group_id, msg, reply_to_id).await {
                     Ok(()) => {
                         println!("[CHAT_SERVICE] Group message sent via WebSocket to group {}", group_id);
                         return Ok("OK: Message sent via WebSocket".to_string());
@@ -459,11 +700,23 @@ impl ChatService {
         }
         
         // Fallback to TCP
-        let cmd = format!("/send_group_message {} {} {}", session_token, group_id, msg);
+        let cmd = match reply_to_id {
+            Some(reply_to_id) => format!("/send_reply {} group:{} {} {}", session_token, group_id, reply_to_id, msg),
+            None => format!("/send_group_message {} {} {}", session_token, group_id, msg),
+        };
         let resp = self.send_command(host, cmd).await?;
         Ok(resp)
     }
 
+    /// Typing indicator for the chat with `target` (a username, or a group id for group chats).
+    /// WebSocket only: without a connection nobody could be shown the indicator anyway.
+    pub async fn send_typing_indicator(&self, chat_type: crate::client::models::app_state::ChatType, target: &str, is_typing: bool) -> anyhow::Result<()> {
+        let Some(websocket) = self.websocket.as_ref().filter(|ws| ws.is_connected()) else {
+            return Err(anyhow::anyhow!("not connected"));
+        };
+        websocket.send_typing(chat_type.as_str(), target, is_typing).await.map_err(|e| anyhow::anyhow!(e.to_string()))
+    }
+
     /// Check for new messages via WebSocket (non-blocking)
     /// Returns messages if available, empty vector otherwise
     pub async fn poll_websocket_messages(&mut self) -> Vec<crate::client::models::app_state::ChatMessage> {
@@ -474,6 +727,11 @@ impl ChatService {
     }
 
     /// Get the current user (needed for WebSocket message processing)
+    /// True if the server declared `capability` in its HELLO reply
+    pub fn supports(&self, capability: &str) -> bool {
+        self.capabilities.as_ref().is_some_and(|c| c.supports(capability))
+    }
+
     pub fn get_current_user(&self) -> Option<&String> {
         self.current_user.as_ref()
     }
@@ -483,24 +741,85 @@ impl ChatService {
         self.current_user = Some(username);
     }
 
+    /// Most recently active chats of the current user, newest first
+    pub async fn recent_chats(&mut self, host: &str, session_token: &str, limit: usize) -> anyhow::Result<Vec<crate::client::models::app_state::ConversationSummary>> {
+        use crate::client::models::app_state::{ChatType, ConversationSummary};
+        let resp = self.send_command(host, format!("/recent_chats {} {}", session_token, limit)).await?;
+        // expected: "OK: Recent chats: <ts>:private:alice, <ts>:group:<id>:<name>"
+        let Some(list) = resp.trim().strip_prefix("OK: Recent chats:") else {
+            return Err(anyhow::anyhow!(resp));
+        };
+        let chats = list
+            .split(", ")
+            .map(|s| s.trim())
+            .filter_map(|entry| {
+                let mut parts = entry.splitn(3, ':');
+                let ts = parts.next()?.parse::<i64>().ok()?;
+                let kind = parts.next()?;
+                let rest = parts.next()?;
+                let (chat_type, chat_id, display_name) = match kind {
+                    "private" => (ChatType::Private, rest, rest),
+                    "group" => {
+                        let (id, name) = rest.split_once(':')?;
+                        (ChatType::Group, id, name)
+                    }
+                    _ => return None,
+                };
+                Some(ConversationSummary {
+                    chat_id: chat_id.to_string(),
+                    chat_type,
+                    display_name: display_name.to_string(),
+                    last_message: None,
+                    last_timestamp: Some(ts),
+                    unread: 0,
+                })
+            })
+            .collect();
+        Ok(chats)
+    }
+
+    /// List the chats archived by the current user
+    pub async fn list_archived_chats(&mut self, host: &str, session_token: &str) -> anyhow::Result<Vec<crate::client::models::app_state::ArchivedChat>> {
+        use crate::client::models::app_state::{ArchivedChat, ChatType};
+        let resp = self.send_command(host, format!("/list_archived {}", session_token)).await?;
+        // expected: "OK: Archived chats: private:alice, group:<id>:<name>"
+        let Some(list) = resp.trim().strip_prefix("OK: Archived chats:") else {
+            return Err(anyhow::anyhow!(resp));
+        };
+        let chats = list
+            .split(", ")
+            .map(|s| s.trim())
+            .filter_map(|entry| {
+                if let Some(username) = entry.strip_prefix("private:") {
+                    Some(ArchivedChat { chat_type: ChatType::Private, chat_id: username.to_string(), display_name: username.to_string() })
+                } else {
+                    let (id, name) = entry.strip_prefix("group:")?.split_once(':')?;
+                    Some(ArchivedChat { chat_type: ChatType::Group, chat_id: id.to_string(), display_name: name.to_string() })
+                }
+            })
+            .collect();
+        Ok(chats)
+    }
+
+    /// Export a chat as a JSON document (`chat_type` is "private" or "group").
+    /// The server splits the document over several lines, which are joined back here.
+    pub async fn export_chat(&mut self, host: &str, session_token: &str, chat_type: &str, chat_id: &str) -> anyhow::Result<String> {
+        let cmd = format!("/export_chat {} {} {}", session_token, chat_type, chat_id);
+        let resp = self.send_multiline_command(host, cmd).await?;
+        let Some(body) = resp.strip_prefix("OK: Export:") else {
+            return Err(anyhow::anyhow!(resp.trim_start_matches("ERR:").trim().to_string()));
+        };
+        Ok(body.split('\n').collect())
+    }
+
     /// Get group members for proper message decryption
     pub async fn get_group_members(&mut self, host: &str, session_token: &str, group_id: &str) -> anyhow::Result<Vec<String>> {
         let cmd = format!("/group_members {} {}", session_token, group_id);
         let resp = self.send_command(host, cmd).await?;
         
-        // Parse response format: "OK: Group members: user1, user2, user3"
-        if resp.starts_with("OK: Group members: ") {
-            let members_str = resp.strip_prefix("OK: Group members: ").unwrap_or("");
-            if members_str.is_empty() {
-                Ok(vec![])
-            } else {
-                let members: Vec<String> = members_str
-                    .split(", ")
-                    .map(|s| s.trim().to_string())
-                    .filter(|s| !s.is_empty())
-                    .collect();
-                Ok(members)
-            }
+        // Parse response format: "OK: Members: user1:owner, user2:member"
+        if let Ok(members) = crate::client::services::group_service::GroupService::parse_group_members(&resp) {
+            Ok(members.into_iter().map(|(username, _)| username).collect())
         } else if resp.starts_with("ERR: Not a group member") {
             // User left the group - return specific error
             Err(anyhow::anyhow!("NOT_A_MEMBER"))
@@ -518,6 +837,42 @@ impl ChatService {
     /// Retrieve group messages and return them parsed as Vec<ChatMessage>.
     pub async fn get_group_messages(&mut self, host: &str, session_token: &str, group_id: &str) -> anyhow::Result<Vec<crate::client::models::app_state::ChatMessage>> {
         // First get the group members for proper decryption
+        let participants = self.group_participants(host, session_token, group_id).await?;
+
+        // Then get the group messages
+        let cmd = format!("/get_group_messages {} {}", session_token, group_id);
+        let resp = self.send_multiline_command(host, cmd).await?;
+        
+        // Check if user is not a member
+        if resp.starts_with("ERR: Not a group member") {
+            return Err(anyhow::anyhow!("NOT_A_MEMBER"));
+        }
+        
+        // Parse messages with proper participants for decryption
+        let msgs = message_parser::parse_group_messages_with_participants(&resp, &participants)
+            .map_err(|e| anyhow::anyhow!(e))?;
+        Ok(msgs)
+    }
+
+    /// Up to `limit` messages of `group_id` sent before `before_ts`, oldest first, and the
+    /// cursor of the next page if older ones remain. Pass `i64::MAX` to get the latest page.
+    pub async fn get_group_messages_page(&mut self, host: &str, session_token: &str, group_id: &str, before_ts: i64, limit: i64) -> anyhow::Result<(Vec<crate::client::models::app_state::ChatMessage>, Option<i64>)> {
+        if self.capabilities.as_ref().is_some_and(|c| !c.supports("message_pages")) {
+            return Err(anyhow::anyhow!("server does not support message pages"));
+        }
+        let participants = self.group_participants(host, session_token, group_id).await?;
+        let cmd = format!("/get_group_messages_page {} {} {} {}", session_token, group_id, before_ts, limit);
+        let resp = self.send_multiline_command(host, cmd).await?;
+        if resp.starts_with("ERR: Not a group member") {
+            return Err(anyhow::anyhow!("NOT_A_MEMBER"));
+        }
+        let msgs = message_parser::parse_group_messages_with_participants(&resp, &participants)
+            .map_err(|e| anyhow::anyhow!(e))?;
+        Ok((msgs, message_parser::page_next_before(&resp)))
+    }
+
+    /// Members of `group_id` used to decrypt its messages; `NOT_A_MEMBER` if we left the group
+    async fn group_participants(&mut self, host: &str, session_token: &str, group_id: &str) -> anyhow::Result<Vec<String>> {
         let participants = match self.get_group_members(host, session_token, group_id).await {
             Ok(members) => {
                 println!("[CHAT_SERVICE] Got {} members for group {}: {:?}", members.len(), group_id, members);
@@ -533,19 +888,6 @@ impl ChatService {
                 vec![]
             }
         };
-
-        // Then get the group messages
-        let cmd = format!("/get_group_messages {} {}", session_token, group_id);
-        let resp = self.send_multiline_command(host, cmd).await?;
-        
-        // Check if user is not a member
-        if resp.starts_with("ERR: Not a group member") {
-            return Err(anyhow::anyhow!("NOT_A_MEMBER"));
-        }
-        
-        // Parse messages with proper participants for decryption
-        let msgs = message_parser::parse_group_messages_with_participants(&resp, &participants)
-            .map_err(|e| anyhow::anyhow!(e))?;
-        Ok(msgs)
+        Ok(participants)
     }
 }
\ No newline at end of file
@@ -0,0 +1,206 @@
+use crate::client::gui::views::logger::LogLevel;
+use crate::client::services::chat_service::ChatService;
+use std::fmt;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// Errors returned by `FriendService`, separating transport failures from
+/// application-level refusals sent back by the server as `ERR: ...`.
+#[derive(Debug)]
+pub enum FriendServiceError {
+    NotFound,
+    AlreadyFriends,
+    SelfRequest,
+    NetworkError(anyhow::Error),
+    ServerError(String),
+}
+
+impl FriendServiceError {
+    /// Map an `ERR: ...` server response to the matching variant.
+    pub fn from_response(resp: &str) -> Self {
+        let reason = resp.trim().trim_start_matches("ERR:").trim();
+        let lower = reason.to_lowercase();
+        if lower.contains("non trovato") || lower.contains("not found") {
+            FriendServiceError::NotFound
+        } else if lower.contains("già amici") || lower.contains("already friends") {
+            FriendServiceError::AlreadyFriends
+        } else if lower.contains("te stesso") || lower.contains("yourself") {
+            FriendServiceError::SelfRequest
+        } else {
+            FriendServiceError::ServerError(reason.to_string())
+        }
+    }
+
+    /// Logger level to use when showing this error in the GUI.
+    pub fn log_level(&self) -> LogLevel {
+        match self {
+            FriendServiceError::NotFound
+            | FriendServiceError::AlreadyFriends
+            | FriendServiceError::SelfRequest => LogLevel::Warning,
+            FriendServiceError::NetworkError(_) | FriendServiceError::ServerError(_) => LogLevel::Error,
+        }
+    }
+}
+
+impl fmt::Display for FriendServiceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FriendServiceError::NotFound => write!(f, "User not found"),
+            FriendServiceError::AlreadyFriends => write!(f, "You are already friends"),
+            FriendServiceError::SelfRequest => write!(f, "You cannot send a friend request to yourself"),
+            FriendServiceError::NetworkError(e) => write!(f, "Network error: {}", e),
+            FriendServiceError::ServerError(msg) => write!(f, "Server error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for FriendServiceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            FriendServiceError::NetworkError(e) => Some(e.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<anyhow::Error> for FriendServiceError {
+    fn from(e: anyhow::Error) -> Self {
+        FriendServiceError::NetworkError(e)
+    }
+}
+
+/// A friend with their presence, as listed by `/friends_status`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FriendInfo {
+    pub username: String,
+    pub is_online: bool,
+    /// Unix timestamp of the last connection or disconnection, if any
+    pub last_seen: Option<i64>,
+}
+
+/// A received friend request, as listed by `/received_friend_requests`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FriendRequest {
+    pub from_username: String,
+    /// Optional note attached by the sender; empty when none was given
+    pub message: String,
+}
+
+#[derive(Debug, Default)]
+pub struct FriendService;
+
+impl FriendService {
+    pub fn new() -> Self { Self {} }
+
+    async fn send(svc: &Arc<Mutex<ChatService>>, host: &str, cmd: String) -> Result<String, FriendServiceError> {
+        let mut guard = svc.lock().await;
+        let resp = guard.send_command(host, cmd).await?;
+        if resp.starts_with("OK:") {
+            Ok(resp)
+        } else {
+            Err(FriendServiceError::from_response(&resp))
+        }
+    }
+
+    /// Send a friend request to `username`. Returns the server confirmation on success.
+    pub async fn send_request(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str, username: &str, message: &str) -> Result<String, FriendServiceError> {
+        let cmd = format!("/send_friend_request {} {} {}", session_token, username, message);
+        Self::send(svc, host, cmd).await
+    }
+
+    /// Accept the pending friend request sent by `username`.
+    pub async fn accept_request(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str, username: &str) -> Result<String, FriendServiceError> {
+        let cmd = format!("/accept_friend_request {} {}", session_token, username);
+        Self::send(svc, host, cmd).await
+    }
+
+    /// Reject the pending friend request sent by `username`.
+    pub async fn reject_request(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str, username: &str) -> Result<String, FriendServiceError> {
+        let cmd = format!("/reject_friend_request {} {}", session_token, username);
+        Self::send(svc, host, cmd).await
+    }
+
+    /// List friends with their online status.
+    pub async fn list_friends(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str) -> Result<Vec<FriendInfo>, FriendServiceError> {
+        let resp = Self::send(svc, host, format!("/friends_status {}", session_token)).await?;
+        // expected: "OK: alice:1:1700000000, bob:0:1699990000, carol:0"
+        let friends_part = resp.trim().trim_start_matches("OK:").trim();
+        Ok(friends_part
+            .split(',')
+            .filter_map(|s| {
+                let mut parts = s.trim().split(':');
+                let username = parts.next().filter(|u| !u.is_empty())?;
+                let is_online = parts.next() == Some("1");
+                let last_seen = parts.next().and_then(|ts| ts.parse().ok());
+                Some(FriendInfo { username: username.to_string(), is_online, last_seen })
+            })
+            .collect())
+    }
+
+    /// Set our status message; an empty `status` clears it.
+    pub async fn set_status(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str, status: &str) -> Result<String, FriendServiceError> {
+        Self::send(svc, host, format!("/set_status {} {}", session_token, status)).await
+    }
+
+    /// Status message of `username`; empty when they have not set one.
+    pub async fn get_status(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str, username: &str) -> Result<String, FriendServiceError> {
+        let resp = Self::send(svc, host, format!("/get_status {} {}", session_token, username)).await?;
+        // expected: "OK: Status: Out for lunch"
+        Ok(resp.trim().trim_start_matches("OK:").trim().trim_start_matches("Status:").trim().to_string())
+    }
+
+    /// Be notified over WebSocket when any of `usernames` goes online or offline;
+    /// replaces the previous subscription.
+    pub async fn subscribe_presence(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str, usernames: &[String]) -> Result<String, FriendServiceError> {
+        Self::send(svc, host, format!("/subscribe_presence {} {}", session_token, usernames.join(","))).await
+    }
+
+    /// List received friend requests with their attached messages.
+    pub async fn received_requests(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str) -> Result<Vec<FriendRequest>, FriendServiceError> {
+        let resp = Self::send(svc, host, format!("/received_friend_requests {}", session_token)).await?;
+        // expected: "OK: Richieste ricevute: alice: ciao | bob: hey"
+        let requests_part = resp.trim().trim_start_matches("OK: Richieste ricevute:").trim();
+        Ok(requests_part
+            .split(" | ")
+            .filter_map(|s| {
+                let (username, message) = s.trim().split_once(':')?;
+                Some(FriendRequest {
+                    from_username: username.trim().to_string(),
+                    message: message.trim().to_string(),
+                })
+            })
+            .collect())
+    }
+
+    /// List the usernames the current user has pending friend requests to.
+    pub async fn sent_requests(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str) -> Result<Vec<String>, FriendServiceError> {
+        let resp = Self::send(svc, host, format!("/sent_friend_requests {}", session_token)).await?;
+        // expected: "OK: Richieste inviate: alice: ciao | bob: hey"
+        let requests_part = resp.trim().trim_start_matches("OK: Richieste inviate:").trim();
+        Ok(requests_part
+            .split(" | ")
+            .filter_map(|s| s.split_once(':').map(|(username, _)| username.trim().to_string()))
+            .filter(|s| !s.is_empty())
+            .collect())
+    }
+
+    /// Block `username`: the server will refuse their future friend requests.
+    pub async fn block_user(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str, username: &str) -> Result<String, FriendServiceError> {
+        let cmd = format!("/block_user {} {}", session_token, username);
+        Self::send(svc, host, cmd).await
+    }
+
+    /// Remove `username` from the blocked list.
+    pub async fn unblock_user(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str, username: &str) -> Result<String, FriendServiceError> {
+        let cmd = format!("/unblock_user {} {}", session_token, username);
+        Self::send(svc, host, cmd).await
+    }
+
+    /// List blocked users. Returns Vec<String> of usernames on success.
+    pub async fn list_blocked(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str) -> Result<Vec<String>, FriendServiceError> {
+        let resp = Self::send(svc, host, format!("/list_blocked {}", session_token)).await?;
+        // expected: "OK: Blocked users: alice, bob"
+        let users_part = resp.trim().trim_start_matches("OK: Blocked users:").trim();
+        Ok(users_part.split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect())
+    }
+}
@@ -0,0 +1,449 @@
+use crate::client::services::chat_service::ChatService;
+use crate::client::services::message_parser::{parse_pinned_messages, PinnedMessage};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+/// A group the current user belongs to, as listed by `/my_groups`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupSummary {
+    pub id: String,
+    pub name: String,
+}
+
+/// A group member as listed by `/group_members_status`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupMemberInfo {
+    pub username: String,
+    /// "owner", "admin" or "member"
+    pub role: String,
+    pub is_online: bool,
+}
+
+/// A pending invite as listed by `/my_group_invites`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupInvite {
+    pub invite_id: i64,
+    pub group_name: String,
+    pub invited_by: String,
+    /// Unix timestamp; `None` for invites that never expire
+    pub expires_at: Option<i64>,
+}
+
+/// A public group as listed by `/list_public_groups`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PublicGroup {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub member_count: i64,
+    /// Whether the current user is already a member
+    pub joined: bool,
+}
+
+/// One page of `/list_public_groups` results; pages are 1-based.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PublicGroupsPage {
+    pub page: i64,
+    pub pages: i64,
+    pub groups: Vec<PublicGroup>,
+}
+
+#[derive(Debug, Default)]
+pub struct GroupService;
+
+impl GroupService {
+    pub fn new() -> Self { Self {} }
+
+    /// List the groups of the logged user. Returns the parsed groups on success.
+    pub async fn my_groups(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str) -> anyhow::Result<Vec<GroupSummary>> {
+        let mut guard = svc.lock().await;
+        let cmd = format!("/my_groups {}", session_token);
+        let resp = guard.send_command(host, cmd).await?;
+        Self::parse_my_groups(&resp)
+    }
+
+    /// Parse a `/my_groups` response into `GroupSummary` values.
+    ///
+    /// Each entry is split on its first `:` only, so group names may contain colons.
+    /// Entries are separated by `,`: an entry without an id (for example the tail of a
+    /// group named `a, b`) fails the whole parse instead of being dropped.
+    ///
+    /// ```
+    /// use ruggine_modulare::client::services::group_service::{GroupService, GroupSummary};
+    ///
+    /// let groups = GroupService::parse_my_groups("OK: My groups: id1:name1, id2:name:2").unwrap();
+    /// assert_eq!(groups, vec![
+    ///     GroupSummary { id: "id1".to_string(), name: "name1".to_string() },
+    ///     GroupSummary { id: "id2".to_string(), name: "name:2".to_string() },
+    /// ]);
+    /// assert!(GroupService::parse_my_groups("OK: My groups:").unwrap().is_empty());
+    /// assert!(GroupService::parse_my_groups("OK: My groups: id1:a, b").is_err());
+    /// assert!(GroupService::parse_my_groups("ERR: Invalid or expired session").is_err());
+    /// ```
+    pub fn parse_my_groups(resp: &str) -> anyhow::Result<Vec<GroupSummary>> {
+        let resp = resp.trim();
+        let Some(groups_part) = resp.strip_prefix("OK: My groups:") else {
+            return Err(anyhow::anyhow!(resp.to_string()));
+        };
+        groups_part
+            .split(',')
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .map(|s| match s.split_once(':') {
+                Some((id, name)) if !id.trim().is_empty() && !name.trim().is_empty() => {
+                    Ok(GroupSummary { id: id.trim().to_string(), name: name.trim().to_string() })
+                }
+                _ => Err(anyhow::anyhow!("Malformed group entry '{}'", s)),
+            })
+            .collect()
+    }
+
+    /// List the pending group invites of the logged user.
+    pub async fn my_group_invites(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str) -> anyhow::Result<Vec<GroupInvite>> {
+        let mut guard = svc.lock().await;
+        let resp = guard.send_command(host, format!("/my_group_invites {}", session_token)).await?;
+        Self::parse_my_group_invites(&resp)
+    }
+
+    /// Parse a `/my_group_invites` response: `id:group_name:invited_by[:expires_at]` entries separated by ` | `.
+    /// Group names may contain `:`, so the id is read from the left and the other fields from the right;
+    /// the server always sends `expires_at`, empty for invites that do not expire.
+    ///
+    /// An entry that does not match the format fails the whole parse, so a server-side
+    /// format change is reported instead of silently hiding invites.
+    ///
+    /// ```
+    /// use ruggine_modulare::client::services::group_service::{GroupInvite, GroupService};
+    ///
+    /// let invites = GroupService::parse_my_group_invites("OK: Group invites: 7:team:alice | 9:club:bob:1700000000").unwrap();
+    /// assert_eq!(invites, vec![
+    ///     GroupInvite { invite_id: 7, group_name: "team".to_string(), invited_by: "alice".to_string(), expires_at: None },
+    ///     GroupInvite { invite_id: 9, group_name: "club".to_string(), invited_by: "bob".to_string(), expires_at: Some(1700000000) },
+    /// ]);
+    ///
+    /// let invites = GroupService::parse_my_group_invites("OK: Group invites: 7:a:b:alice: | 8:x:y:z:bob:1700000000").unwrap();
+    /// assert_eq!((invites[0].group_name.as_str(), invites[0].invited_by.as_str(), invites[0].expires_at), ("a:b", "alice", None));
+    /// assert_eq!((invites[1].group_name.as_str(), invites[1].invited_by.as_str(), invites[1].expires_at), ("x:y:z", "bob", Some(1700000000)));
+    /// assert!(GroupService::parse_my_group_invites("OK: Group invites:").unwrap().is_empty());
+    /// assert!(GroupService::parse_my_group_invites("OK: Group invites: 0b1c-uuid:team:alice").is_err());
+    /// assert!(GroupService::parse_my_group_invites("OK: Group invites: 7:team").is_err());
+    /// assert!(GroupService::parse_my_group_invites("ERR: Invalid or expired session").is_err());
+    /// ```
+    pub fn parse_my_group_invites(resp: &str) -> anyhow::Result<Vec<GroupInvite>> {
+        let resp = resp.trim();
+        let Some(invites_part) = resp.strip_prefix("OK: Group invites:") else {
+            return Err(anyhow::anyhow!(resp.to_string()));
+        };
+        invites_part
+            .split(" | ")
+            .map(|s| s.trim())
+            .filter(|s| !s.is_empty())
+            .map(|s| {
+                let malformed = || anyhow::anyhow!("Malformed group invite '{}'", s);
+                let (id, rest) = s.split_once(':').ok_or_else(malformed)?;
+                let invite_id = id
+                    .parse::<i64>()
+                    .map_err(|_| anyhow::anyhow!("Invalid group invite id '{}'", id))?;
+                // A last field that is empty or a number is expires_at
+                let (rest, expires_at) = match rest.rsplit_once(':') {
+                    Some((head, t)) if t.is_empty() || t.parse::<i64>().is_ok() => (head, t.parse::<i64>().ok()),
+                    _ => (rest, None),
+                };
+                let (group_name, invited_by) = rest.rsplit_once(':').ok_or_else(malformed)?;
+                Ok(GroupInvite { invite_id, group_name: group_name.to_string(), invited_by: invited_by.to_string(), expires_at })
+            })
+            .collect()
+    }
+
+    /// Users that can still be invited: `all_users` minus the members listed in a
+    /// `/group_members` response. An error response filters nobody out.
+    ///
+    /// ```
+    /// use ruggine_modulare::client::services::group_service::GroupService;
+    ///
+    /// let users = vec!["alice".to_string(), "bob".to_string(), "carol".to_string()];
+    /// let members = vec![("alice".to_string(), "owner".to_string()), ("carol".to_string(), "member".to_string())];
+    /// assert_eq!(GroupService::invite_candidates(users.clone(), &members), vec!["bob".to_string()]);
+    /// assert_eq!(GroupService::invite_candidates(users.clone(), &[]), users);
+    /// ```
+    pub fn invite_candidates(all_users: Vec<String>, members: &[(String, String)]) -> Vec<String> {
+        all_users.into_iter().filter(|user| !members.iter().any(|(member, _)| member == user)).collect()
+    }
+
+    /// `(username, role)` of every member of `group_id`, owner and admins first.
+    pub async fn get_group_members(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str, group_id: &str) -> anyhow::Result<Vec<(String, String)>> {
+        let mut guard = svc.lock().await;
+        let resp = guard.send_command(host, format!("/group_members {} {}", session_token, group_id)).await?;
+        Self::parse_group_members(&resp)
+    }
+
+    /// Parse a `/group_members` response ("OK: Members: alice:owner, bob:member").
+    ///
+    /// ```
+    /// use ruggine_modulare::client::services::group_service::GroupService;
+    ///
+    /// let members = GroupService::parse_group_members("OK: Members: alice:owner, bob:member").unwrap();
+    /// assert_eq!(members, vec![
+    ///     ("alice".to_string(), "owner".to_string()),
+    ///     ("bob".to_string(), "member".to_string()),
+    /// ]);
+    /// assert!(GroupService::parse_group_members("OK: Members:").unwrap().is_empty());
+    /// assert!(GroupService::parse_group_members("ERR: Not a group member").is_err());
+    /// ```
+    pub fn parse_group_members(resp: &str) -> anyhow::Result<Vec<(String, String)>> {
+        let resp = resp.trim();
+        let Some(members_part) = resp.strip_prefix("OK: Members:") else {
+            return Err(anyhow::anyhow!(resp.to_string()));
+        };
+        Ok(members_part
+            .split(',')
+            .filter_map(|s| {
+                let (username, role) = s.trim().split_once(':')?;
+                Some((username.to_string(), role.to_string()))
+            })
+            .collect())
+    }
+
+    /// Invite `username` to `group_id`. Returns the raw server response.
+    pub async fn invite(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str, username: &str, group_id: &str) -> anyhow::Result<String> {
+        let mut guard = svc.lock().await;
+        guard.send_command(host, Self::invite_command(session_token, username, group_id)).await
+    }
+
+    /// Build the `/invite` command. The server binds the username before the group id.
+    ///
+    /// ```
+    /// use ruggine_modulare::client::services::group_service::GroupService;
+    ///
+    /// assert_eq!(GroupService::invite_command("tok", "bob", "g1"), "/invite tok bob g1");
+    /// ```
+    pub fn invite_command(session_token: &str, username: &str, group_id: &str) -> String {
+        format!("/invite {} {} {}", session_token, username, group_id)
+    }
+
+    /// Accept a pending group invite. Returns the raw server response.
+    pub async fn accept_group_invite(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str, invite_id: i64) -> anyhow::Result<String> {
+        let mut guard = svc.lock().await;
+        guard.send_command(host, format!("/accept_group_invite {} {}", session_token, invite_id)).await
+    }
+
+    /// Reject a pending group invite. Returns the raw server response.
+    pub async fn reject_group_invite(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str, invite_id: i64) -> anyhow::Result<String> {
+        let mut guard = svc.lock().await;
+        guard.send_command(host, format!("/reject_group_invite {} {}", session_token, invite_id)).await
+    }
+
+    /// Search public groups by name. Returns each match with its member count.
+    pub async fn search_groups(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str, query: &str) -> anyhow::Result<Vec<(GroupSummary, i64)>> {
+        let mut guard = svc.lock().await;
+        let resp = guard.send_command(host, format!("/search_groups {} {}", session_token, query)).await?;
+        let resp = resp.trim();
+        // expected: "OK: Groups: id1:3:name1, id2:1:name2"
+        let Some(groups_part) = resp.strip_prefix("OK: Groups:") else {
+            return Err(anyhow::anyhow!(resp.to_string()));
+        };
+        Ok(groups_part
+            .split(',')
+            .filter_map(|s| {
+                let mut parts = s.trim().splitn(3, ':');
+                let id = parts.next()?.trim();
+                let count = parts.next()?.trim().parse().ok()?;
+                let name = parts.next()?.trim();
+                Some((GroupSummary { id: id.to_string(), name: name.to_string() }, count))
+            })
+            .collect())
+    }
+
+    /// Members of a group with their role and online status.
+    pub async fn group_members_status(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str, group_id: &str) -> anyhow::Result<Vec<GroupMemberInfo>> {
+        let mut guard = svc.lock().await;
+        let resp = guard.send_command(host, format!("/group_members_status {} {}", session_token, group_id)).await?;
+        let resp = resp.trim();
+        // expected: "OK: Members: alice:owner:1, bob:member:0"
+        let Some(members_part) = resp.strip_prefix("OK: Members:") else {
+            return Err(anyhow::anyhow!(resp.to_string()));
+        };
+        Ok(members_part
+            .split(',')
+            .filter_map(|s| {
+                let mut parts = s.trim().splitn(3, ':');
+                let username = parts.next()?.trim();
+                let role = parts.next()?.trim();
+                let is_online = parts.next()?.trim() == "1";
+                Some(GroupMemberInfo { username: username.to_string(), role: role.to_string(), is_online })
+            })
+            .collect())
+    }
+
+    /// Make `username` an admin of the group (`admin`), or a plain member again.
+    /// Only the owner and admins may change roles.
+    pub async fn set_group_admin(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str, group_id: &str, username: &str, admin: bool) -> anyhow::Result<String> {
+        let command = if admin { "/set_group_admin" } else { "/demote_group_admin" };
+        let mut guard = svc.lock().await;
+        let resp = guard.send_command(host, format!("{} {} {} {}", command, session_token, group_id, username)).await?;
+        if resp.starts_with("OK:") {
+            Ok(resp.trim_start_matches("OK:").trim().to_string())
+        } else {
+            Err(anyhow::anyhow!(resp.trim_start_matches("ERR:").trim().to_string()))
+        }
+    }
+
+    /// Rename a group; only its owner and admins may.
+    pub async fn rename_group(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str, group_id: &str, new_name: &str) -> anyhow::Result<String> {
+        let mut guard = svc.lock().await;
+        let resp = guard.send_command(host, format!("/rename_group {} {} {}", session_token, group_id, new_name)).await?;
+        if resp.starts_with("OK:") {
+            Ok(resp)
+        } else {
+            Err(anyhow::anyhow!(resp.trim_start_matches("ERR:").trim().to_string()))
+        }
+    }
+
+    /// Delete a group with all its messages; only its creator may.
+    pub async fn delete_group(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str, group_id: &str) -> anyhow::Result<String> {
+        let mut guard = svc.lock().await;
+        let resp = guard.send_command(host, format!("/delete_group {} {}", session_token, group_id)).await?;
+        if resp.starts_with("OK:") {
+            Ok(resp)
+        } else {
+            Err(anyhow::anyhow!(resp.trim_start_matches("ERR:").trim().to_string()))
+        }
+    }
+
+    /// Remove a plain member from a group; only its owner and admins may.
+    pub async fn kick_member(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str, group_id: &str, username: &str) -> anyhow::Result<String> {
+        let mut guard = svc.lock().await;
+        let resp = guard.send_command(host, format!("/kick_from_group {} {} {}", session_token, group_id, username)).await?;
+        if resp.starts_with("OK:") {
+            Ok(resp)
+        } else {
+            Err(anyhow::anyhow!(resp.trim_start_matches("ERR:").trim().to_string()))
+        }
+    }
+
+    /// Set the description of a group; only its owner and admins may. An empty text clears it.
+    pub async fn set_description(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str, group_id: &str, description: &str) -> anyhow::Result<String> {
+        let mut guard = svc.lock().await;
+        let resp = guard.send_command(host, format!("/set_group_description {} {} {}", session_token, group_id, description)).await?;
+        if resp.starts_with("OK:") {
+            Ok(resp)
+        } else {
+            Err(anyhow::anyhow!(resp.trim_start_matches("ERR:").trim().to_string()))
+        }
+    }
+
+    /// Description of a group, empty if it has none
+    pub async fn get_description(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str, group_id: &str) -> anyhow::Result<String> {
+        let mut guard = svc.lock().await;
+        let resp = guard.send_command(host, format!("/get_group_description {} {}", session_token, group_id)).await?;
+        match resp.strip_prefix("OK: Description:") {
+            Some(description) => Ok(description.trim().to_string()),
+            None => Err(anyhow::anyhow!(resp.trim_start_matches("ERR:").trim().to_string())),
+        }
+    }
+
+    /// Join a public group. Private groups answer with an error asking for an invite.
+    pub async fn join_group(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str, group_id: &str) -> anyhow::Result<String> {
+        let mut guard = svc.lock().await;
+        let resp = guard.send_command(host, format!("/join_group {} {}", session_token, group_id)).await?;
+        if resp.starts_with("OK:") {
+            Ok(resp)
+        } else {
+            Err(anyhow::anyhow!(resp.trim_start_matches("ERR:").trim().to_string()))
+        }
+    }
+
+    /// One page of the public groups whose name or description contains `query`.
+    pub async fn list_public_groups(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str, query: &str, page: i64) -> anyhow::Result<PublicGroupsPage> {
+        let mut guard = svc.lock().await;
+        let resp = guard.send_multiline_command(host, format!("/list_public_groups {} {} {}", session_token, page, query)).await?;
+        Self::parse_public_groups(&resp)
+    }
+
+    /// Parse a `/list_public_groups` response: a page header followed by one
+    /// `id:member_count:joined:name description` line per group.
+    ///
+    /// ```
+    /// use ruggine_modulare::client::services::group_service::{GroupService, PublicGroup};
+    ///
+    /// let page = GroupService::parse_public_groups(
+    ///     "OK: Public groups page 2/3 (45 total):\ng1:12:1:rust Talk about: Rust\ng2:3:0:chess \n",
+    /// ).unwrap();
+    /// assert_eq!((page.page, page.pages), (2, 3));
+    /// assert_eq!(page.groups, vec![
+    ///     PublicGroup { id: "g1".to_string(), name: "rust".to_string(), description: "Talk about: Rust".to_string(), member_count: 12, joined: true },
+    ///     PublicGroup { id: "g2".to_string(), name: "chess".to_string(), description: String::new(), member_count: 3, joined: false },
+    /// ]);
+    /// assert!(GroupService::parse_public_groups("OK: Public groups page 1/1 (0 total):\n").unwrap().groups.is_empty());
+    /// assert!(GroupService::parse_public_groups("ERR: Invalid or expired session").is_err());
+    /// ```
+    pub fn parse_public_groups(resp: &str) -> anyhow::Result<PublicGroupsPage> {
+        let mut lines = resp.lines();
+        let header = lines.next().unwrap_or_default().trim();
+        let Some(counts) = header.strip_prefix("OK: Public groups page ") else {
+            return Err(anyhow::anyhow!(header.to_string()));
+        };
+        let (page, pages) = counts
+            .split_whitespace()
+            .next()
+            .and_then(|p| p.split_once('/'))
+            .and_then(|(page, pages)| Some((page.parse().ok()?, pages.parse().ok()?)))
+            .ok_or_else(|| anyhow::anyhow!("Malformed public groups header '{}'", header))?;
+        let groups = lines
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty())
+            .filter_map(|l| {
+                let mut parts = l.splitn(4, ':');
+                let id = parts.next()?;
+                let member_count = parts.next()?.parse().ok()?;
+                let joined = parts.next()? == "1";
+                let (name, description) = parts.next()?.split_once(' ').unwrap_or((l.rsplit(':').next()?, ""));
+                Some(PublicGroup { id: id.to_string(), name: name.to_string(), description: description.trim().to_string(), member_count, joined })
+            })
+            .collect();
+        Ok(PublicGroupsPage { page, pages, groups })
+    }
+
+    /// Create a shareable invite code for a group; only its owner and admins may.
+    /// `expires_secs` and `max_uses` limit how long and how often the code works.
+    pub async fn generate_invite_code(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str, group_id: &str, expires_secs: Option<i64>, max_uses: Option<i64>) -> anyhow::Result<String> {
+        // The server reads 0 as "no limit"
+        let cmd = format!("/generate_invite_code {} {} {} {}", session_token, group_id, expires_secs.unwrap_or(0), max_uses.unwrap_or(0));
+        let mut guard = svc.lock().await;
+        let resp = guard.send_command(host, cmd).await?;
+        match resp.strip_prefix("OK: Invite code:") {
+            Some(code) => Ok(code.trim().to_string()),
+            None => Err(anyhow::anyhow!(resp.trim_start_matches("ERR:").trim().to_string())),
+        }
+    }
+
+    /// Join the group an invite code belongs to. Returns the group id and name.
+    pub async fn join_by_code(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str, code: &str) -> anyhow::Result<(String, String)> {
+        let mut guard = svc.lock().await;
+        let resp = guard.send_command(host, format!("/join_by_code {} {}", session_token, code)).await?;
+        let Some(joined) = resp.trim().strip_prefix("OK: Joined group ") else {
+            return Err(anyhow::anyhow!(resp.trim_start_matches("ERR:").trim().to_string()));
+        };
+        let (id, name) = joined.split_once(' ').unwrap_or((joined, joined));
+        Ok((id.to_string(), name.to_string()))
+    }
+
+    /// Messages pinned in a group, most recently pinned first.
+    pub async fn get_pinned_messages(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str, group_id: &str) -> anyhow::Result<Vec<PinnedMessage>> {
+        let mut guard = svc.lock().await;
+        let resp = guard.send_multiline_command(host, format!("/get_pinned_messages {} {}", session_token, group_id)).await?;
+        parse_pinned_messages(&resp).map_err(|e| anyhow::anyhow!(e))
+    }
+
+    /// Pin a message to the top of a group chat (`pin`), or unpin it; only the owner and admins may.
+    pub async fn set_pinned(svc: &Arc<Mutex<ChatService>>, host: &str, session_token: &str, group_id: &str, message_id: i64, pin: bool) -> anyhow::Result<String> {
+        let command = if pin { "/pin_message" } else { "/unpin_message" };
+        let mut guard = svc.lock().await;
+        let resp = guard.send_command(host, format!("{} {} {} {}", command, session_token, group_id, message_id)).await?;
+        if resp.starts_with("OK:") {
+            Ok(resp)
+        } else {
+            Err(anyhow::anyhow!(resp.trim_start_matches("ERR:").trim().to_string()))
+        }
+    }
+}
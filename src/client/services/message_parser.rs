@@ -1,7 +1,8 @@
 // Modulo di parsing messaggi lato client
-use crate::client::models::app_state::ChatMessage;
+use crate::client::models::app_state::{ChatMessage, ChatType};
 use crate::common::crypto::CryptoManager;
 use base64::{Engine as _, engine::general_purpose};
+use std::collections::HashMap;
 
 /// Attempt to decrypt a message content if it appears to be encrypted JSON
 fn try_decrypt_content(content: &str, participants: &[String]) -> String {
@@ -9,7 +10,7 @@ fn try_decrypt_content(content: &str, participants: &[String]) -> String {
     if content.contains("ciphertext") && content.contains("nonce") {
         if let Ok(encrypted_data) = serde_json::from_str::<serde_json::Value>(content) {
             if let Some(master_key) = CryptoManager::load_master_key_from_env() {
-                let chat_key = CryptoManager::generate_chat_key(participants, &master_key);
+                let chat_key = CryptoManager::generate_chat_key(participants, &master_key, 0);
                 
                 if let (Some(ciphertext), Some(nonce)) = (
                     encrypted_data.get("ciphertext").and_then(|v| v.as_str()),
@@ -20,7 +21,7 @@ fn try_decrypt_content(content: &str, participants: &[String]) -> String {
                         general_purpose::STANDARD.decode(ciphertext),
                         general_purpose::STANDARD.decode(nonce)
                     ) {
-                        if let Ok(decrypted) = CryptoManager::decrypt_message(&cipher_bytes, &nonce_bytes, &chat_key) {
+                        if let Ok(decrypted) = CryptoManager::decrypt_stored_message(&cipher_bytes, &nonce_bytes, &chat_key) {
                             return decrypted;
                         }
                     }
@@ -50,67 +51,372 @@ pub fn parse_messages(resp: &str) -> Result<Vec<String>, &'static str> {
 	}
 }
 
+/// Whether a `/get_private_messages_page` response announces older messages
+/// in its `OK: Messages: has_more=<bool>` header.
+///
+/// ```
+/// use ruggine_modulare::client::services::message_parser::page_has_more;
+///
+/// assert!(page_has_more("OK: Messages: has_more=true next_before=1700000000\n[1700000000] bob: hi"));
+/// assert!(!page_has_more("OK: Messages: has_more=false\n"));
+/// assert!(!page_has_more("OK: Messages:\n[1700000000] bob: has_more=true"));
+/// ```
+pub fn page_has_more(resp: &str) -> bool {
+    resp.trim_start()
+        .lines()
+        .next()
+        .and_then(|header| header.strip_prefix("OK: Messages:"))
+        .is_some_and(|rest| rest.split_whitespace().any(|field| field == "has_more=true"))
+}
+
+/// `username -> last_read_at` from a `/get_read_receipts` response
+/// ("OK: read_by: alice:1700000000, bob:1700000100").
+///
+/// ```
+/// use ruggine_modulare::client::services::message_parser::parse_read_by;
+///
+/// let read_by = parse_read_by("OK: read_by: alice:1700000000, bob:1700000100");
+/// assert_eq!(read_by.get("bob"), Some(&1700000100));
+/// assert!(parse_read_by("OK: read_by: ").is_empty());
+/// assert!(parse_read_by("ERR: User not found").is_empty());
+/// ```
+pub fn parse_read_by(resp: &str) -> HashMap<String, i64> {
+    let Some(list) = resp.trim().strip_prefix("OK: read_by:") else {
+        return HashMap::new();
+    };
+    list.split(", ")
+        .filter_map(|entry| {
+            let (user, ts) = entry.trim().rsplit_once(':')?;
+            Some((user.to_string(), ts.parse().ok()?))
+        })
+        .collect()
+}
+
+/// One active session of ours, as listed by `/list_sessions`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SessionInfo {
+    /// Last 8 characters of the session token, used by `/revoke_session`
+    pub id: String,
+    /// Device label given at login; empty when none was given
+    pub label: String,
+    pub created_at: i64,
+    pub expires_at: i64,
+}
+
+/// Sessions of a `/list_sessions` response ("OK: Sessions: id:label:created_at:expires_at, ...").
+///
+/// ```
+/// use ruggine_modulare::client::services::message_parser::parse_sessions;
+///
+/// let sessions = parse_sessions("OK: Sessions: 3fa2c1d9:laptop-linux:1700000000:1702592000, 77b0e4aa::1690000000:1692592000").unwrap();
+/// assert_eq!(sessions.len(), 2);
+/// assert_eq!((sessions[0].id.as_str(), sessions[0].label.as_str()), ("3fa2c1d9", "laptop-linux"));
+/// assert_eq!((sessions[1].label.as_str(), sessions[1].expires_at), ("", 1692592000));
+/// assert!(parse_sessions("OK: Sessions: ").unwrap().is_empty());
+/// assert!(parse_sessions("ERR: Invalid or expired session").is_err());
+/// ```
+pub fn parse_sessions(resp: &str) -> Result<Vec<SessionInfo>, String> {
+    let resp = resp.trim();
+    let Some(body) = resp.strip_prefix("OK: Sessions:") else {
+        return Err(resp.trim_start_matches("ERR:").trim().to_string());
+    };
+    Ok(body.split(',').filter_map(|entry| {
+        let mut fields = entry.trim().split(':');
+        let id = fields.next().filter(|id| !id.is_empty())?;
+        let label = fields.next()?;
+        let created_at = fields.next()?.parse().ok()?;
+        let expires_at = fields.next()?.parse().ok()?;
+        Some(SessionInfo { id: id.to_string(), label: label.to_string(), created_at, expires_at })
+    }).collect())
+}
+
+/// One `/search_messages` hit; `chat_id` is the other user's name for private chats, the group id otherwise
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    pub message_id: i64,
+    pub chat_type: ChatType,
+    pub chat_id: String,
+    pub sender: String,
+    pub content: String,
+    pub sent_at: i64,
+}
+
+/// Hits of a `/search_messages` response, whose lines look like
+/// `[ts|id chat=private:bob] alice: content`.
+///
+/// ```
+/// use ruggine_modulare::client::models::app_state::ChatType;
+/// use ruggine_modulare::client::services::message_parser::parse_search_results;
+///
+/// let results = parse_search_results("OK: Search results:\n[1700000000|7 chat=group:g1 edited=1700000100] bob: see you: at noon").unwrap();
+/// assert_eq!(results.len(), 1);
+/// assert_eq!((results[0].chat_type, results[0].chat_id.as_str()), (ChatType::Group, "g1"));
+/// assert_eq!((results[0].sender.as_str(), results[0].content.as_str()), ("bob", "see you: at noon"));
+/// assert!(parse_search_results("OK: Search results:\n").unwrap().is_empty());
+/// assert!(parse_search_results("ERR: Search unavailable in encrypted mode").is_err());
+/// ```
+pub fn parse_search_results(resp: &str) -> Result<Vec<SearchResult>, String> {
+    let resp = resp.trim();
+    let Some(body) = resp.strip_prefix("OK: Search results:") else {
+        return Err(resp.trim_start_matches("ERR:").trim().to_string());
+    };
+    Ok(body.lines().filter_map(|line| {
+        let (header, rest) = line.trim().strip_prefix('[')?.split_once("] ")?;
+        let mut fields = header.split_whitespace();
+        let (sent_at, message_id) = fields.next()?.split_once('|')?;
+        let (chat_type, chat_id) = fields.find_map(|f| f.strip_prefix("chat="))?.split_once(':')?;
+        let chat_type = match chat_type {
+            "private" => ChatType::Private,
+            "group" => ChatType::Group,
+            _ => return None,
+        };
+        let (sender, content) = rest.split_once(": ")?;
+        Some(SearchResult {
+            message_id: message_id.parse().ok()?,
+            chat_type,
+            chat_id: chat_id.to_string(),
+            sender: sender.to_string(),
+            content: content.to_string(),
+            sent_at: sent_at.parse().ok()?,
+        })
+    }).collect())
+}
+
+/// Cursor of the next page announced by a `/get_*_messages_page` response
+/// (`next_before=<ts>` in its header), if older messages remain.
+///
+/// ```
+/// use ruggine_modulare::client::services::message_parser::page_next_before;
+///
+/// assert_eq!(page_next_before("OK: Messages: has_more=true next_before=1700000000\n"), Some(1700000000));
+/// assert_eq!(page_next_before("OK: Messages: has_more=false next_before=1700000000\n"), None);
+/// ```
+pub fn page_next_before(resp: &str) -> Option<i64> {
+    if !page_has_more(resp) {
+        return None;
+    }
+    resp.trim_start()
+        .lines()
+        .next()?
+        .split_whitespace()
+        .find_map(|field| field.strip_prefix("next_before="))?
+        .parse()
+        .ok()
+}
+
+/// How to treat lines that do not match the `[timestamp|id] sender: content` pattern
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParseMode {
+    /// Fail on the first malformed line
+    Strict,
+    /// Skip malformed lines and report them as `ParseWarning`s
+    #[default]
+    Lenient,
+}
+
+/// A line skipped while parsing in lenient mode
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseWarning {
+    pub line_number: usize,
+    pub line: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    UnexpectedFormat,
+    MalformedLine(String),
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::UnexpectedFormat => write!(f, "unexpected response format"),
+            ParseError::MalformedLine(line) => write!(f, "malformed message line: {}", line),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Fields of one `[header] sender: content` message line
+struct MessageLine {
+    timestamp: i64,
+    message_id: Option<i64>,
+    reply_to_id: Option<i64>,
+    forwarded_from_id: Option<i64>,
+    avatar_url: Option<String>,
+    seen_count: u32,
+    edited: bool,
+    reactions: HashMap<String, Vec<String>>,
+    sender: String,
+    content: String,
+}
+
+/// Split a `[timestamp] sender: content`, `[timestamp|id] sender: content` or, for replies,
+/// `[timestamp|id|reply_to_id] sender: content` line; the header may also carry ` avatar=<url>`,
+/// ` seen=<n>`, ` edited=<ts>`, ` forwarded=<id>` and ` reactions=<base64 JSON map of emoji -> usernames>`
+/// after the timestamp.
+/// Only the first `]` and the first `:` after it are delimiters, so content may contain both.
+fn parse_message_line(line: &str) -> Option<MessageLine> {
+    let rest = line.strip_prefix('[')?;
+    let bracket_end = rest.find(']')?;
+    let mut header = rest[..bracket_end].split_whitespace();
+    let stamp = header.next()?;
+    let mut stamp = stamp.split('|');
+    let timestamp = stamp.next()?.parse::<i64>().ok()?;
+    let message_id = match stamp.next() {
+        Some(id) => Some(id.parse::<i64>().ok()?),
+        None => None,
+    };
+    let reply_to_id = match stamp.next() {
+        Some(id) => Some(id.parse::<i64>().ok()?),
+        None => None,
+    };
+    let mut avatar_url = None;
+    let mut seen_count = 0;
+    let mut edited = false;
+    let mut forwarded_from_id = None;
+    let mut reactions = HashMap::new();
+    for field in header {
+        if let Some(url) = field.strip_prefix("avatar=") {
+            avatar_url = Some(url.to_string());
+        } else if let Some(n) = field.strip_prefix("seen=") {
+            seen_count = n.parse().unwrap_or(0);
+        } else if field.starts_with("edited=") {
+            edited = true;
+        } else if let Some(id) = field.strip_prefix("forwarded=") {
+            forwarded_from_id = id.parse().ok();
+        } else if let Some(encoded) = field.strip_prefix("reactions=") {
+            reactions = general_purpose::STANDARD.decode(encoded).ok()
+                .and_then(|json| serde_json::from_slice(&json).ok())
+                .unwrap_or_default();
+        }
+    }
+    let (sender, content) = rest[bracket_end + 1..].split_once(':')?;
+    let sender = sender.trim();
+    if sender.is_empty() {
+        return None;
+    }
+    Some(MessageLine { timestamp, message_id, reply_to_id, forwarded_from_id, avatar_url, seen_count, edited, reactions, sender: sender.to_string(), content: content.trim().to_string() })
+}
+
 /// Parse private messages from server response into ChatMessage structs with decryption
-pub fn parse_private_messages_with_participants(resp: &str, participants: &[String]) -> Result<Vec<ChatMessage>, &'static str> {
+///
+/// ```
+/// use ruggine_modulare::client::services::message_parser::{
+///     parse_private_messages_with_participants, ParseError, ParseMode, ParseWarning,
+/// };
+///
+/// // Only the first `]` and the first `:` after it are delimiters
+/// let resp = "OK: Messages:\n[1700000000|7] bob: see [1]: it's done]: really";
+/// let (messages, warnings) = parse_private_messages_with_participants(resp, &[], ParseMode::Strict).unwrap();
+/// assert_eq!((messages[0].sender.as_str(), messages[0].content.as_str()), ("bob", "see [1]: it's done]: really"));
+/// assert!(warnings.is_empty());
+///
+/// // The continuation of a multi-line message has no header of its own
+/// let resp = "OK: Messages:\n[1700000000|7] bob: first line\nsecond line\n[1700000001|8] alice: ok";
+/// let (messages, warnings) = parse_private_messages_with_participants(resp, &[], ParseMode::Lenient).unwrap();
+/// assert_eq!(messages.len(), 2);
+/// assert_eq!(messages[0].content, "first line");
+/// assert_eq!(warnings, vec![ParseWarning { line_number: 2, line: "second line".to_string() }]);
+/// assert_eq!(
+///     parse_private_messages_with_participants(resp, &[], ParseMode::Strict).unwrap_err(),
+///     ParseError::MalformedLine("second line".to_string())
+/// );
+///
+/// // An empty sender is malformed too, and does not drop the lines after it
+/// let resp = "OK: Messages:\n[1700000000|7] : nobody\n[1700000001|8] alice: ok";
+/// let (messages, warnings) = parse_private_messages_with_participants(resp, &[], ParseMode::Lenient).unwrap();
+/// assert_eq!((messages.len(), messages[0].sender.as_str()), (1, "alice"));
+/// assert_eq!(warnings[0].line, "[1700000000|7] : nobody");
+/// assert_eq!(
+///     parse_private_messages_with_participants(resp, &[], ParseMode::Strict).unwrap_err(),
+///     ParseError::MalformedLine("[1700000000|7] : nobody".to_string())
+/// );
+///
+/// assert_eq!(
+///     parse_private_messages_with_participants("ERR: Not found", &[], ParseMode::Lenient).unwrap_err(),
+///     ParseError::UnexpectedFormat
+/// );
+/// ```
+pub fn parse_private_messages_with_participants(resp: &str, participants: &[String], mode: ParseMode) -> Result<(Vec<ChatMessage>, Vec<ParseWarning>), ParseError> {
     let trimmed = resp.trim();
     if !trimmed.starts_with("OK: Messages:") {
-        return Err("unexpected response format");
+        return Err(ParseError::UnexpectedFormat);
     }
     
     let mut parts = trimmed.splitn(2, '\n');
     parts.next(); // skip the OK header
     
+    let mut messages = Vec::new();
+    let mut warnings = Vec::new();
+    
     if let Some(body) = parts.next() {
-        let mut messages = Vec::new();
-        
-        for line in body.lines() {
+        for (idx, line) in body.lines().enumerate() {
             let line = line.trim();
             if line.is_empty() {
                 continue;
             }
             
-            // Expected format: [timestamp] sender: message
-            if let Some(bracket_end) = line.find(']') {
-                if line.starts_with('[') {
-                    let timestamp_str = &line[1..bracket_end];
-                    let rest = &line[bracket_end + 1..].trim();
+            match parse_message_line(line) {
+                Some(parsed) => {
+                    // Try to decrypt the content if it's encrypted
+                    let decrypted_content = try_decrypt_content(&parsed.content, participants);
                     
-                    if let Some(colon_pos) = rest.find(':') {
-                        let sender = rest[..colon_pos].trim().to_string();
-                        let raw_content = rest[colon_pos + 1..].trim().to_string();
-                        
-                        if let Ok(timestamp) = timestamp_str.parse::<i64>() {
-                            let formatted_time = format_timestamp(timestamp);
-                            
-                            // Try to decrypt the content if it's encrypted
-                            let decrypted_content = try_decrypt_content(&raw_content, participants);
-                            
-                            messages.push(ChatMessage {
-                                sender,
-                                content: decrypted_content,
-                                timestamp,
-                                formatted_time,
-                                sent_at: timestamp,
-                                is_pending: false,  // HTTP messages are confirmed by server
-                            });
-                        }
-                    }
+                    messages.push(ChatMessage {
+                        sender: parsed.sender,
+                        content: decrypted_content,
+                        timestamp: parsed.timestamp,
+                        formatted_time: format_timestamp(parsed.timestamp),
+                        sent_at: parsed.timestamp,
+                        is_pending: false,  // HTTP messages are confirmed by server
+                        message_id: parsed.message_id,
+                        sender_avatar_url: parsed.avatar_url,
+                        seen_count: parsed.seen_count,
+                        edited: parsed.edited,
+                        reactions: parsed.reactions,
+                        reply_to_id: parsed.reply_to_id,
+                        forwarded_from_id: parsed.forwarded_from_id,
+                    });
                 }
+                None => match mode {
+                    ParseMode::Strict => return Err(ParseError::MalformedLine(line.to_string())),
+                    ParseMode::Lenient => warnings.push(ParseWarning { line_number: idx + 1, line: line.to_string() }),
+                },
             }
         }
         
         // Sort by timestamp to ensure chronological order
         messages.sort_by_key(|m| m.timestamp);
-        Ok(messages)
-    } else {
-        Ok(vec![])
     }
+    Ok((messages, warnings))
 }
 
 /// Parse private messages from server response into ChatMessage structs (legacy version)
-pub fn parse_private_messages(resp: &str) -> Result<Vec<ChatMessage>, &'static str> {
+///
+/// ```
+/// use ruggine_modulare::client::services::message_parser::parse_private_messages;
+///
+/// let messages = parse_private_messages("OK: Messages:\n[1700000000|7 edited=1700000060] bob: hi: there\n[1700000001|8] alice: ok").unwrap();
+/// assert_eq!((messages[0].message_id, messages[0].edited), (Some(7), true));
+/// assert_eq!(messages[0].content, "hi: there");
+/// assert!(!messages[1].edited);
+///
+/// // "reactions=" carries base64 of {"👍":["alice"]}
+/// let messages = parse_private_messages("OK: Messages:\n[1700000000|7 reactions=eyLwn5GNIjpbImFsaWNlIl19] bob: hi").unwrap();
+/// assert_eq!(messages[0].reactions["👍"], vec!["alice".to_string()]);
+///
+/// // A reply names the message it answers after its own id
+/// let messages = parse_private_messages("OK: Messages:\n[1700000000|7] bob: hi\n[1700000005|8|7] alice: hello").unwrap();
+/// assert_eq!((messages[0].reply_to_id, messages[1].reply_to_id), (None, Some(7)));
+/// assert_eq!(messages[1].message_id, Some(8));
+///
+/// let messages = parse_private_messages("OK: Messages:\n[1700000000|9 forwarded=4] bob: look").unwrap();
+/// assert_eq!(messages[0].forwarded_from_id, Some(4));
+/// ```
+pub fn parse_private_messages(resp: &str) -> Result<Vec<ChatMessage>, ParseError> {
     // Use empty participants list for backward compatibility
-    parse_private_messages_with_participants(resp, &[])
+    parse_private_messages_with_participants(resp, &[], ParseMode::Lenient).map(|(messages, _)| messages)
 }
 
 pub fn format_timestamp(timestamp: i64) -> String {
@@ -123,6 +429,35 @@ pub fn format_timestamp(timestamp: i64) -> String {
     local_dt.format("%H:%M").to_string()
 }
 
+/// How long ago `timestamp` was: "just now", "5 min ago", "3 hours ago", or the local
+/// date once it is a day old.
+///
+/// ```
+/// use ruggine_modulare::client::services::message_parser::relative_time;
+///
+/// let now = chrono::Utc::now().timestamp();
+/// assert_eq!(relative_time(now), "just now");
+/// assert_eq!(relative_time(now - 5 * 60), "5 min ago");
+/// assert_eq!(relative_time(now - 3600), "1 hour ago");
+/// assert_eq!(relative_time(now - 3 * 3600), "3 hours ago");
+/// assert!(relative_time(1_700_000_000).ends_with("2023"));
+/// ```
+pub fn relative_time(timestamp: i64) -> String {
+    use chrono::{DateTime, Utc, Local, TimeZone};
+
+    match (Utc::now().timestamp() - timestamp).max(0) {
+        s if s < 60 => "just now".to_string(),
+        s if s < 3600 => format!("{} min ago", s / 60),
+        s if s < 7200 => "1 hour ago".to_string(),
+        s if s < 86_400 => format!("{} hours ago", s / 3600),
+        _ => {
+            let dt = Utc.timestamp_opt(timestamp, 0).single().unwrap_or_else(Utc::now);
+            let local_dt: DateTime<Local> = dt.with_timezone(&Local);
+            local_dt.format("%d %b %Y").to_string()
+        }
+    }
+}
+
 /// Parse group messages from server response into ChatMessage structs with decryption
 pub fn parse_group_messages_with_participants(resp: &str, participants: &[String]) -> Result<Vec<ChatMessage>, &'static str> {
     let trimmed = resp.trim();
@@ -143,32 +478,25 @@ pub fn parse_group_messages_with_participants(resp: &str, participants: &[String
             }
             
             // Expected format: [timestamp] sender_name: message
-            if let Some(bracket_end) = line.find(']') {
-                if line.starts_with('[') {
-                    let timestamp_str = &line[1..bracket_end];
-                    let rest = &line[bracket_end + 1..].trim();
-                    
-                    if let Some(colon_pos) = rest.find(':') {
-                        let sender_name = rest[..colon_pos].trim().to_string();
-                        let raw_content = rest[colon_pos + 1..].trim().to_string();
-                        
-                        if let Ok(timestamp) = timestamp_str.parse::<i64>() {
-                            let formatted_time = format_timestamp(timestamp);
-                            
-                            // Try to decrypt the content if it's encrypted
-                            let decrypted_content = try_decrypt_content(&raw_content, participants);
-                            
-                            messages.push(ChatMessage {
-                                sender: sender_name, // Now shows actual username
-                                content: decrypted_content,
-                                timestamp,
-                                formatted_time,
-                                sent_at: timestamp,
-                                is_pending: false,  // HTTP messages are confirmed by server
-                            });
-                        }
-                    }
-                }
+            if let Some(parsed) = parse_message_line(line) {
+                // Try to decrypt the content if it's encrypted
+                let decrypted_content = try_decrypt_content(&parsed.content, participants);
+                
+                messages.push(ChatMessage {
+                    sender: parsed.sender, // Now shows actual username
+                    content: decrypted_content,
+                    timestamp: parsed.timestamp,
+                    formatted_time: format_timestamp(parsed.timestamp),
+                    sent_at: parsed.timestamp,
+                    is_pending: false,  // HTTP messages are confirmed by server
+                    message_id: parsed.message_id,
+                    sender_avatar_url: parsed.avatar_url,
+                    seen_count: parsed.seen_count,
+                    edited: parsed.edited,
+                    reactions: parsed.reactions,
+                    reply_to_id: parsed.reply_to_id,
+                    forwarded_from_id: parsed.forwarded_from_id,
+                });
             }
         }
         
@@ -185,3 +513,59 @@ pub fn parse_group_messages(resp: &str) -> Result<Vec<ChatMessage>, &'static str
     // Use empty participants list for backward compatibility
     parse_group_messages_with_participants(resp, &[])
 }
+
+/// A message pinned in a group, with who pinned it and when
+#[derive(Debug, Clone)]
+pub struct PinnedMessage {
+    pub message: ChatMessage,
+    pub pinned_by: String,
+    pub pinned_at: i64,
+}
+
+/// Pins of a `/get_pinned_messages` response, whose lines look like
+/// `[ts|id pinned_by=alice pinned_at=ts] sender: content`.
+///
+/// ```
+/// use ruggine_modulare::client::services::message_parser::parse_pinned_messages;
+///
+/// let pins = parse_pinned_messages("OK: Pinned messages:\n[1700000000|7 pinned_by=alice pinned_at=1700000500] bob: meet at: noon").unwrap();
+/// assert_eq!(pins.len(), 1);
+/// assert_eq!((pins[0].pinned_by.as_str(), pins[0].pinned_at), ("alice", 1700000500));
+/// assert_eq!((pins[0].message.message_id, pins[0].message.sender.as_str()), (Some(7), "bob"));
+/// assert_eq!(pins[0].message.content, "meet at: noon");
+/// assert!(parse_pinned_messages("OK: Pinned messages:\n").unwrap().is_empty());
+/// assert!(parse_pinned_messages("ERR: Not a group member").is_err());
+/// ```
+pub fn parse_pinned_messages(resp: &str) -> Result<Vec<PinnedMessage>, String> {
+    let resp = resp.trim();
+    let Some(body) = resp.strip_prefix("OK: Pinned messages:") else {
+        return Err(resp.trim_start_matches("ERR:").trim().to_string());
+    };
+    Ok(body.lines().filter_map(|line| {
+        let line = line.trim();
+        let header = line.strip_prefix('[')?.split_once(']')?.0;
+        let field = |name: &str| header.split_whitespace().find_map(|f| f.strip_prefix(name)).map(str::to_string);
+        let pinned_by = field("pinned_by=")?;
+        let pinned_at = field("pinned_at=")?.parse().ok()?;
+        let parsed = parse_message_line(line)?;
+        Some(PinnedMessage {
+            message: ChatMessage {
+                sender: parsed.sender,
+                content: parsed.content,
+                timestamp: parsed.timestamp,
+                formatted_time: format_timestamp(parsed.timestamp),
+                sent_at: parsed.timestamp,
+                is_pending: false,
+                message_id: parsed.message_id,
+                sender_avatar_url: parsed.avatar_url,
+                seen_count: parsed.seen_count,
+                edited: parsed.edited,
+                reactions: parsed.reactions,
+                reply_to_id: parsed.reply_to_id,
+                forwarded_from_id: parsed.forwarded_from_id,
+            },
+            pinned_by,
+            pinned_at,
+        })
+    }).collect())
+}
@@ -2,5 +2,8 @@ pub mod connection;
 pub mod chat_service;
 pub mod message_parser;
 pub mod users_service;
+pub mod group_service;
+pub mod friend_service;
 pub mod websocket_service;
 pub mod websocket_client;
+pub mod avatar_service;
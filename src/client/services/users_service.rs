@@ -1,7 +1,54 @@
 use crate::client::services::chat_service::ChatService;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
+/// Split a comma separated user list, dropping per-entry flags such as `archived=1`.
+fn parse_user_entries(list: &str) -> Vec<String> {
+    list.trim()
+        .split(',')
+        .filter_map(|s| s.split_whitespace().next())
+        .map(|s| s.to_string())
+        .collect()
+}
+
+/// Presence of a user as flagged in `/all_users` entries (`online=1` or `last_seen=<ts>`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UserPresence {
+    pub is_online: bool,
+    /// Unix timestamp of the last connection or disconnection, if any
+    pub last_seen: Option<i64>,
+}
+
+/// Presence of every user in a comma separated `/all_users` list, by username.
+///
+/// ```
+/// use ruggine_modulare::client::services::users_service::parse_presence;
+///
+/// let presence = parse_presence("alice online=1, bob archived=1 last_seen=1700000000, carol");
+/// assert!(presence["alice"].is_online);
+/// assert_eq!(presence["bob"].last_seen, Some(1700000000));
+/// assert!(!presence["carol"].is_online && presence["carol"].last_seen.is_none());
+/// ```
+pub fn parse_presence(list: &str) -> HashMap<String, UserPresence> {
+    list.trim()
+        .split(',')
+        .filter_map(|entry| {
+            let mut fields = entry.split_whitespace();
+            let username = fields.next()?;
+            let mut presence = UserPresence::default();
+            for flag in fields {
+                match flag.split_once('=') {
+                    Some(("online", value)) => presence.is_online = value == "1",
+                    Some(("last_seen", value)) => presence.last_seen = value.parse().ok(),
+                    _ => {}
+                }
+            }
+            Some((username.to_string(), presence))
+        })
+        .collect()
+}
+
 #[derive(Debug, Default)]
 pub struct UsersService;
 
@@ -23,7 +70,7 @@ impl UsersService {
         } else {
             resp.split_once(':').map(|x| x.1).unwrap_or("")
         };
-        let list = after.trim().split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect();
+        let list = parse_user_entries(after);
         Ok(list)
     }
 
@@ -40,7 +87,19 @@ impl UsersService {
         } else {
             resp.split_once(':').map(|x| x.1).unwrap_or("")
         };
-        let list = after.trim().split(',').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect();
+        let list = parse_user_entries(after);
         Ok(list)
     }
+
+    /// Presence of all users, by username.
+    pub async fn list_presence(svc: &Arc<Mutex<ChatService>>, host: &str) -> anyhow::Result<HashMap<String, UserPresence>> {
+        let mut guard = svc.lock().await;
+        let resp = guard.send_command(host, "/all_users".to_string()).await?;
+        if !resp.starts_with("OK:") {
+            return Err(anyhow::anyhow!(resp));
+        }
+        // expected: "OK: All users: alice online=1, bob last_seen=1700000000"
+        let after = resp.splitn(3, ':').nth(2).unwrap_or("");
+        Ok(parse_presence(after))
+    }
 }
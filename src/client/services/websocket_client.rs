@@ -28,6 +28,9 @@ pub struct IncomingChatMessage {
     pub group_id: Option<String>, // per messaggi di gruppo  
     pub content: String,
     pub timestamp: i64,
+    /// Message this one answers, for replies
+    #[serde(default)]
+    pub reply_to_id: Option<i64>,
 }
 
 // Messaggio da inviare tramite WebSocket
@@ -39,12 +42,41 @@ pub struct OutgoingChatMessage {
     pub group_id: Option<String>, // per messaggi di gruppo
     pub content: String,
     pub session_token: String,
+    /// Only meaningful for `message_type: "typing"`
+    #[serde(default)]
+    pub is_typing: bool,
+    /// Message this one answers, for replies
+    #[serde(default)]
+    pub reply_to_id: Option<i64>,
 }
 
 #[derive(Debug, Clone)]
 pub enum WebSocketMessage {
     NewMessage(IncomingChatMessage),
     UserStatusUpdate { user_id: String, online: bool },
+    /// `user_id` has seen group message `message_id`
+    ReadReceipt { group_id: String, message_id: i64, user_id: String },
+    /// `from_user` started or stopped typing to us (`group_id` is `None`) or in a group
+    Typing { from_user: String, group_id: Option<String>, is_typing: bool },
+    /// `user` has read the chat up to `last_read_at`; `chat_id` is their username for
+    /// private chats (`chat_type` "private") and the group id otherwise
+    ChatRead { chat_type: String, chat_id: String, user: String, last_read_at: i64 },
+    /// A group we belong to was renamed
+    GroupRenamed { group_id: String, new_name: String },
+    /// A group we belonged to was deleted by its creator
+    GroupDeleted { group_id: String },
+    /// An admin removed us from a group
+    KickedFromGroup { group_id: String },
+    /// A message of a group we belong to was pinned or unpinned
+    PinsChanged { group_id: String },
+    /// A server administrator sent an announcement to every connected user
+    SystemBroadcast { content: String },
+    /// `from_user` finished sending us a file, privately (`group_id` is `None`) or in a group
+    FileReceived { file_id: String, file_name: String, mime_type: String, total_size: u64, from_user: String, group_id: Option<String> },
+    /// A friend changed their status message (empty when cleared)
+    StatusChanged { username: String, status: String },
+    /// A user we subscribed to with `/subscribe_presence` went online or offline
+    PresenceUpdate { user: String, online: bool },
     Error(String),
 }
 
@@ -73,14 +105,20 @@ impl std::fmt::Display for WebSocketError {
 
 impl std::error::Error for WebSocketError {}
 
+type Subscribers = std::sync::Arc<std::sync::Mutex<Vec<mpsc::UnboundedSender<WebSocketMessage>>>>;
+/// Timestamp of the last chat message received, shared across reconnections
+pub type LastMessageAt = std::sync::Arc<std::sync::Mutex<Option<i64>>>;
+
 pub struct WebSocketClient {
     url: String,
     session_token: Option<String>,
     connection_retry_attempts: u32,
     max_retry_attempts: u32,
     retry_delay: tokio::time::Duration,
-    /// Channel per inviare messaggi ricevuti all'applicazione
-    pub message_sender: Option<mpsc::UnboundedSender<WebSocketMessage>>,
+    /// Channels fed by the receive loop, one per `subscribe` call
+    subscribers: Subscribers,
+    /// Sent as `?since=` when connecting, so the server replays what was missed meanwhile
+    last_ws_message_at: LastMessageAt,
     /// Receiver per l'applicazione per ricevere i messaggi
     pub message_receiver: Option<mpsc::UnboundedReceiver<WebSocketMessage>>,
     /// Sender per inviare messaggi al WebSocket
@@ -96,7 +134,8 @@ impl WebSocketClient {
             connection_retry_attempts: 0,
             max_retry_attempts: 5,
             retry_delay: tokio::time::Duration::from_secs(2),
-            message_sender: Some(tx),
+            subscribers: std::sync::Arc::new(std::sync::Mutex::new(vec![tx])),
+            last_ws_message_at: LastMessageAt::default(),
             message_receiver: Some(rx),
             outgoing_sender: None,
         }
@@ -107,10 +146,26 @@ impl WebSocketClient {
         self.message_receiver.take()
     }
 
+    /// New receiver of every message arriving from now on. The channel closes when
+    /// the connection ends, so a `None` from `recv` means the client is disconnected.
+    pub fn subscribe(&self) -> mpsc::UnboundedReceiver<WebSocketMessage> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.push(tx);
+        }
+        rx
+    }
+
     pub fn set_session_token(&mut self, token: String) {
         self.session_token = Some(token);
     }
 
+    /// Track the last received message in `last_ws_message_at`, kept by the caller
+    /// across clients to recover the messages missed while reconnecting
+    pub fn set_last_message_at(&mut self, last_ws_message_at: LastMessageAt) {
+        self.last_ws_message_at = last_ws_message_at;
+    }
+
     pub async fn connect_with_auth(&mut self) -> Result<(), WebSocketError> {
         for attempt in 1..=self.max_retry_attempts {
             match self.try_connect().await {
@@ -143,9 +198,14 @@ impl WebSocketClient {
     }
 
     async fn try_connect(&self) -> Result<mpsc::UnboundedSender<OutgoingChatMessage>, WebSocketError> {
-        // Connect to WebSocket
-        println!("[WS:CLIENT] Connecting to {}", self.url);
-        let (ws_stream, _) = connect_async(&self.url)
+        // Connect to WebSocket, asking for the messages missed since the last one received
+        let since = self.last_ws_message_at.lock().ok().and_then(|last| *last);
+        let url = match since {
+            Some(since) => format!("{}?since={}", self.url, since),
+            None => self.url.clone(),
+        };
+        println!("[WS:CLIENT] Connecting to {}", url);
+        let (ws_stream, _) = connect_async(&url)
             .await
             .map_err(|e| {
                 println!("[WS:CLIENT] Connection failed: {}", e);
@@ -207,38 +267,46 @@ impl WebSocketClient {
             
             // Crea channel per messaggi in uscita
             let (outgoing_tx, mut outgoing_rx) = mpsc::unbounded_channel::<OutgoingChatMessage>();
+            // Payloads of server pings, answered by the outgoing handler
+            let (pong_tx, mut pong_rx) = mpsc::unbounded_channel::<Vec<u8>>();
             
-            // Avvia il loop di gestione messaggi in background
-            if let Some(sender) = &self.message_sender {
-                let sender_clone = sender.clone();
-                
-                // Spawn task per gestire messaggi in arrivo
-                tokio::spawn(async move {
-                    Self::handle_incoming_messages(ws_receiver, sender_clone).await;
-                });
-                
-                // Spawn task per gestire messaggi in uscita
-                tokio::spawn(async move {
-                    println!("[WS:CLIENT] Starting outgoing message handler");
-                    while let Some(outgoing_msg) = outgoing_rx.recv().await {
-                        println!("[WS:CLIENT] Received outgoing message: {:?}", outgoing_msg.message_type);
-                        match serde_json::to_string(&outgoing_msg) {
-                            Ok(json) => {
-                                println!("[WS:CLIENT] Sending JSON: {}", json);
-                                if let Err(e) = ws_sender.send(Message::Text(json)).await {
-                                    println!("[WS:CLIENT] Failed to send message: {}", e);
-                                    break;
-                                }
-                                println!("[WS:CLIENT] Message sent successfully");
+            // Spawn task per gestire messaggi in arrivo
+            tokio::spawn(Self::start_message_loop(ws_receiver, self.subscribers.clone(), self.last_ws_message_at.clone(), pong_tx));
+
+            // Spawn task per gestire messaggi in uscita
+            tokio::spawn(async move {
+                println!("[WS:CLIENT] Starting outgoing message handler");
+                loop {
+                    let outgoing_msg = tokio::select! {
+                        Some(payload) = pong_rx.recv() => {
+                            if let Err(e) = ws_sender.send(Message::Pong(payload)).await {
+                                println!("[WS:CLIENT] Failed to send pong: {}", e);
+                                break;
                             }
-                            Err(e) => {
-                                println!("[WS:CLIENT] Failed to serialize outgoing message: {}", e);
+                            continue;
+                        }
+                        outgoing_msg = outgoing_rx.recv() => match outgoing_msg {
+                            Some(outgoing_msg) => outgoing_msg,
+                            None => break,
+                        },
+                    };
+                    println!("[WS:CLIENT] Received outgoing message: {:?}", outgoing_msg.message_type);
+                    match serde_json::to_string(&outgoing_msg) {
+                        Ok(json) => {
+                            println!("[WS:CLIENT] Sending JSON: {}", json);
+                            if let Err(e) = ws_sender.send(Message::Text(json)).await {
+                                println!("[WS:CLIENT] Failed to send message: {}", e);
+                                break;
                             }
+                            println!("[WS:CLIENT] Message sent successfully");
+                        }
+                        Err(e) => {
+                            println!("[WS:CLIENT] Failed to serialize outgoing message: {}", e);
                         }
                     }
-                    println!("[WS:CLIENT] Outgoing message handler ended");
-                });
-            }
+                }
+                println!("[WS:CLIENT] Outgoing message handler ended");
+            });
             
             Ok(outgoing_tx)
         } else {
@@ -253,11 +321,24 @@ impl WebSocketClient {
 
 
 
-    /// Gestisce i messaggi in arrivo dal WebSocket in background
-    async fn handle_incoming_messages(
+    /// Receive loop of an authenticated connection: every text frame is parsed and
+    /// forwarded to all subscribers, pings are handed to `pong_tx` to be answered.
+    /// `last_ws_message_at` follows the timestamp of the chat messages received.
+    /// When the connection ends the subscriber channels are closed, so receivers
+    /// observe the disconnection.
+    async fn start_message_loop(
         mut ws_receiver: futures_util::stream::SplitStream<tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>>,
-        sender: mpsc::UnboundedSender<WebSocketMessage>
+        subscribers: Subscribers,
+        last_ws_message_at: LastMessageAt,
+        pong_tx: mpsc::UnboundedSender<Vec<u8>>,
     ) {
+        // Deliver to every live subscriber; false once all of them are gone
+        let send = |ws_msg: WebSocketMessage| -> bool {
+            let Ok(mut subscribers) = subscribers.lock() else { return false };
+            subscribers.retain(|tx| tx.send(ws_msg.clone()).is_ok());
+            !subscribers.is_empty()
+        };
+
         println!("[WS:CLIENT] Starting incoming message handler");
         while let Some(message) = ws_receiver.next().await {
             match message {
@@ -265,33 +346,45 @@ impl WebSocketClient {
                     println!("[WS:CLIENT] Received message: {}", text);
                     match Self::parse_websocket_message(&text) {
                         Ok(ws_msg) => {
-                            if let Err(_) = sender.send(ws_msg) {
+                            if let WebSocketMessage::NewMessage(chat_msg) = &ws_msg {
+                                if let Ok(mut last) = last_ws_message_at.lock() {
+                                    *last = Some(last.map_or(chat_msg.timestamp, |ts| ts.max(chat_msg.timestamp)));
+                                }
+                            }
+                            if !send(ws_msg) {
                                 println!("[WS:CLIENT] Failed to send message to application - receiver dropped");
                                 break;
                             }
                         }
                         Err(e) => {
                             println!("[WS:CLIENT] Failed to parse message: {} - Raw: {}", e, text);
-                            let _ = sender.send(WebSocketMessage::Error(format!("Parse error: {}", e)));
+                            send(WebSocketMessage::Error(format!("Parse error: {}", e)));
                         }
                     }
                 }
                 Ok(Message::Close(_)) => {
                     println!("[WS:CLIENT] WebSocket connection closed by server");
-                    let _ = sender.send(WebSocketMessage::Error("Connection closed".to_string()));
+                    send(WebSocketMessage::Error("Connection closed".to_string()));
                     break;
                 }
+                Ok(Message::Ping(payload)) => {
+                    // Heartbeat del server: rispondi col pong
+                    let _ = pong_tx.send(payload);
+                }
                 Ok(_) => {
-                    // Ignora altri tipi di messaggio (binary, ping, pong)
+                    // Ignora altri tipi di messaggio (binary, pong)
                 }
                 Err(e) => {
                     println!("[WS:CLIENT] WebSocket error: {}", e);
-                    let _ = sender.send(WebSocketMessage::Error(format!("WebSocket error: {}", e)));
+                    send(WebSocketMessage::Error(format!("WebSocket error: {}", e)));
                     break;
                 }
             }
         }
         println!("[WS:CLIENT] Message handling loop ended");
+        if let Ok(mut subscribers) = subscribers.lock() {
+            subscribers.clear();
+        }
     }
 
     /// Parsa un messaggio JSON dal WebSocket
@@ -320,6 +413,88 @@ impl WebSocketClient {
                     .ok_or("Missing online field in user_status message")?;
                 Ok(WebSocketMessage::UserStatusUpdate { user_id, online })
             }
+            "read_receipt" => {
+                let field = |name: &str| generic.get(name).and_then(|v| v.as_str()).map(str::to_string);
+                let group_id = field("group_id").ok_or("Missing group_id in read_receipt message")?;
+                let message_id = field("message_id")
+                    .and_then(|id| id.parse::<i64>().ok())
+                    .ok_or("Missing message_id in read_receipt message")?;
+                let user_id = field("user_id").ok_or("Missing user_id in read_receipt message")?;
+                Ok(WebSocketMessage::ReadReceipt { group_id, message_id, user_id })
+            }
+            "typing" => {
+                let from_user = generic.get("from_user")
+                    .and_then(|v| v.as_str())
+                    .ok_or("Missing from_user in typing message")?
+                    .to_string();
+                let group_id = generic.get("group_id").and_then(|v| v.as_str()).map(str::to_string);
+                let is_typing = generic.get("is_typing").and_then(|v| v.as_bool()).unwrap_or(false);
+                Ok(WebSocketMessage::Typing { from_user, group_id, is_typing })
+            }
+            "chat_read" => {
+                let field = |name: &str| generic.get(name).and_then(|v| v.as_str()).map(str::to_string);
+                let chat_type = field("chat_type").ok_or("Missing chat_type in chat_read message")?;
+                let chat_id = field("chat_id").ok_or("Missing chat_id in chat_read message")?;
+                let user = field("user").ok_or("Missing user in chat_read message")?;
+                let last_read_at = generic.get("last_read_at")
+                    .and_then(|v| v.as_i64())
+                    .ok_or("Missing last_read_at in chat_read message")?;
+                Ok(WebSocketMessage::ChatRead { chat_type, chat_id, user, last_read_at })
+            }
+            "file_transfer_complete" => {
+                let field = |name: &str| generic.get(name).and_then(|v| v.as_str()).map(str::to_string);
+                let file_id = field("file_id").ok_or("Missing file_id in file_transfer_complete message")?;
+                let file_name = field("file_name").ok_or("Missing file_name in file_transfer_complete message")?;
+                let mime_type = field("mime_type").unwrap_or_default();
+                let total_size = generic.get("total_size").and_then(|v| v.as_u64()).unwrap_or(0);
+                let from_user = field("from_user").ok_or("Missing from_user in file_transfer_complete message")?;
+                Ok(WebSocketMessage::FileReceived { file_id, file_name, mime_type, total_size, from_user, group_id: field("group_id") })
+            }
+            "system" => {
+                let field = |name: &str| generic.get(name).and_then(|v| v.as_str()).map(str::to_string);
+                match field("type").as_deref() {
+                    Some("group_renamed") => {
+                        let group_id = field("group_id").ok_or("Missing group_id in group_renamed event")?;
+                        let new_name = field("new_name").ok_or("Missing new_name in group_renamed event")?;
+                        Ok(WebSocketMessage::GroupRenamed { group_id, new_name })
+                    }
+                    Some("group_deleted") => {
+                        let group_id = field("group_id").ok_or("Missing group_id in group_deleted event")?;
+                        Ok(WebSocketMessage::GroupDeleted { group_id })
+                    }
+                    Some("kicked") => {
+                        let group_id = field("group_id").ok_or("Missing group_id in kicked event")?;
+                        Ok(WebSocketMessage::KickedFromGroup { group_id })
+                    }
+                    Some("pins_changed") => {
+                        let group_id = field("group_id").ok_or("Missing group_id in pins_changed event")?;
+                        Ok(WebSocketMessage::PinsChanged { group_id })
+                    }
+                    // The server refused or dropped our connection
+                    Some("error") => Ok(WebSocketMessage::Error(field("message").unwrap_or_default())),
+                    Some("broadcast") => {
+                        let content = field("content").ok_or("Missing content in broadcast event")?;
+                        Ok(WebSocketMessage::SystemBroadcast { content })
+                    }
+                    other => Err(format!("Unknown system event: {:?}", other)),
+                }
+            }
+            "notification" => {
+                let field = |name: &str| generic.get(name).and_then(|v| v.as_str()).map(str::to_string);
+                match field("type").as_deref() {
+                    Some("status_changed") => {
+                        let username = field("username").ok_or("Missing username in status_changed event")?;
+                        let status = field("status").unwrap_or_default();
+                        Ok(WebSocketMessage::StatusChanged { username, status })
+                    }
+                    Some("presence") => {
+                        let user = field("user").ok_or("Missing user in presence event")?;
+                        let online = generic.get("online").and_then(|v| v.as_bool()).ok_or("Missing online in presence event")?;
+                        Ok(WebSocketMessage::PresenceUpdate { user, online })
+                    }
+                    other => Err(format!("Unknown notification: {:?}", other)),
+                }
+            }
             _ => {
                 Err(format!("Unknown message type: {}", message_type))
             }
@@ -335,8 +510,8 @@ impl WebSocketClient {
         self.connection_retry_attempts
     }
 
-    /// Invia un messaggio privato tramite WebSocket
-    pub async fn send_private_message(&self, to_user: &str, content: &str) -> Result<(), WebSocketError> {
+    /// Invia un messaggio privato tramite WebSocket; `reply_to_id` is the message it answers
+    pub async fn send_private_message(&self, to_user: &str, content: &str, reply_to_id: Option<i64>) -> Result<(), WebSocketError> {
         println!("[WS:CLIENT] send_private_message called for user: {}, content: {}", to_user, content);
         
         let session_token = self.session_token.as_ref()
@@ -349,6 +524,8 @@ impl WebSocketClient {
             group_id: None,
             content: content.to_string(),
             session_token: session_token.clone(),
+            is_typing: false,
+            reply_to_id,
         };
 
         if let Some(sender) = &self.outgoing_sender {
@@ -369,8 +546,8 @@ impl WebSocketClient {
         }
     }
 
-    /// Invia un messaggio di gruppo tramite WebSocket
-    pub async fn send_group_message(&self, group_id: &str, content: &str) -> Result<(), WebSocketError> {
+    /// Invia un messaggio di gruppo tramite WebSocket; `reply_to_id` is the message it answers
+    pub async fn send_group_message(&self, group_id: &str, content: &str, reply_to_id: Option<i64>) -> Result<(), WebSocketError> {
         let session_token = self.session_token.as_ref()
             .ok_or_else(|| WebSocketError::MessageSendFailed("No session token available".to_string()))?;
 
@@ -381,6 +558,35 @@ impl WebSocketClient {
             group_id: Some(group_id.to_string()),
             content: content.to_string(),
             session_token: session_token.clone(),
+            is_typing: false,
+            reply_to_id,
+        };
+
+        if let Some(sender) = &self.outgoing_sender {
+            sender.send(message)
+                .map_err(|_| WebSocketError::MessageSendFailed("Failed to queue message for sending".to_string()))?;
+            Ok(())
+        } else {
+            Err(WebSocketError::MessageSendFailed("WebSocket not connected".to_string()))
+        }
+    }
+
+    /// Tell the other participants of a chat that we are (or no longer are) typing.
+    /// `target` is the other user for private chats and the group id for group chats.
+    pub async fn send_typing(&self, chat_type: &str, target: &str, is_typing: bool) -> Result<(), WebSocketError> {
+        let session_token = self.session_token.as_ref()
+            .ok_or_else(|| WebSocketError::MessageSendFailed("No session token available".to_string()))?;
+
+        let private = chat_type == "private";
+        let message = OutgoingChatMessage {
+            message_type: "typing".to_string(),
+            chat_type: chat_type.to_string(),
+            to_user: private.then(|| target.to_string()),
+            group_id: (!private).then(|| target.to_string()),
+            content: String::new(),
+            session_token: session_token.clone(),
+            is_typing,
+            reply_to_id: None,
         };
 
         if let Some(sender) = &self.outgoing_sender {
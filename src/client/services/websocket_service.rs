@@ -9,36 +9,49 @@ pub use crate::server::websocket::{WebSocketMessage, MessageType};
 
 #[derive(Debug, Clone)]
 pub struct WebSocketService {
+    /// Outgoing messages, set once connected
     sender: Arc<Mutex<Option<mpsc::UnboundedSender<WebSocketMessage>>>>,
+    /// Feeds `receiver` from the receive task
+    incoming: mpsc::UnboundedSender<WebSocketMessage>,
     receiver: Arc<Mutex<Option<mpsc::UnboundedReceiver<WebSocketMessage>>>>,
     is_connected: Arc<Mutex<bool>>,
+    /// Timestamp of the last message received, sent as `?since=` when reconnecting
+    last_ws_message_at: Arc<Mutex<Option<i64>>>,
 }
 
 impl WebSocketService {
     pub fn new() -> Self {
         let (tx, rx) = mpsc::unbounded_channel();
         Self {
-            sender: Arc::new(Mutex::new(Some(tx))),
+            sender: Arc::new(Mutex::new(None)),
+            incoming: tx,
             receiver: Arc::new(Mutex::new(Some(rx))),
             is_connected: Arc::new(Mutex::new(false)),
+            last_ws_message_at: Arc::new(Mutex::new(None)),
         }
     }
 
+    /// Connect to `ws_url`. After a connection drop the server is asked for the messages
+    /// missed since the last one received.
     pub async fn connect(&self, ws_url: &str, _user_id: String) -> anyhow::Result<()> {
-        let url = Url::parse(ws_url)?;
+        let mut url = Url::parse(ws_url)?;
+        if let Some(since) = *self.last_ws_message_at.lock().await {
+            url.query_pairs_mut().append_pair("since", &since.to_string());
+        }
         let (ws_stream, _) = connect_async(url).await?;
         let (mut ws_sender, mut ws_receiver) = ws_stream.split();
 
         *self.is_connected.lock().await = true;
 
         let sender_clone = self.sender.clone();
-        let _receiver_clone = self.receiver.clone();
+        let incoming = self.incoming.clone();
         let is_connected_clone = self.is_connected.clone();
+        let last_ws_message_at = self.last_ws_message_at.clone();
 
         // Channel per comunicazione interna
         let (internal_tx, mut internal_rx) = mpsc::unbounded_channel::<WebSocketMessage>();
         
-        // Sostituisci il sender con quello interno
+        // Installa il sender verso il task di invio
         {
             let mut sender_guard = sender_clone.lock().await;
             *sender_guard = Some(internal_tx);
@@ -63,11 +76,15 @@ impl WebSocketService {
             while let Some(message) = ws_receiver.next().await {
                 match message {
                     Ok(Message::Text(text)) => {
-                        if let Ok(ws_message) = serde_json::from_str::<WebSocketMessage>(&text) {
-                            // Invece di usare il receiver come sender, salviamo il messaggio in un buffer
-                            // Per ora, stampiamo il messaggio ricevuto
-                            println!("[WS:CLIENT] Received message: {:?}", ws_message);
-                            
+                        match serde_json::from_str::<WebSocketMessage>(&text) {
+                            // Queued until drained by `receive_messages` / `receive_message`
+                            Ok(ws_message) => {
+                                *last_ws_message_at.lock().await = Some(ws_message.timestamp);
+                                if incoming.send(ws_message).is_err() {
+                                    break;
+                                }
+                            }
+                            Err(e) => println!("[WS:CLIENT] Ignoring unparsable message: {}", e),
                         }
                     }
                     Ok(Message::Close(_)) => {
@@ -106,6 +123,7 @@ impl WebSocketService {
             target: to.to_string(),
             content: content.to_string(),
             timestamp: chrono::Utc::now().timestamp(),
+            recipient_user_id: None,
         };
 
         self.send_message(message).await
@@ -122,6 +140,7 @@ impl WebSocketService {
             target: group_id.to_string(),
             content: content.to_string(),
             timestamp: chrono::Utc::now().timestamp(),
+            recipient_user_id: None,
         };
 
         self.send_message(message).await
@@ -0,0 +1,68 @@
+use keyring::Entry;
+use std::path::PathBuf;
+use crate::common::crypto::CryptoManager;
+
+const SERVICE: &str = "ruggine_app";
+
+/// Keyring account holding the end-to-end secret of `username`
+fn keyring_user(username: &str) -> String {
+    format!("ruggine_e2e_{}", username)
+}
+
+/// Fallback key file: `<config dir>/ruggine/e2e_<username>`, next to the session fallback
+fn key_file(username: &str) -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("ruggine").join(format!("e2e_{}", username)))
+}
+
+fn fallback_allowed() -> bool {
+    std::env::var("KEYRING_FALLBACK").unwrap_or_default() == "true"
+}
+
+fn decode_secret(encoded: &str) -> Option<[u8; 32]> {
+    // Secrets use the same base64 encoding as public keys
+    CryptoManager::parse_public_key(encoded)
+}
+
+fn load_secret(username: &str) -> Option<[u8; 32]> {
+    if let Ok(encoded) = Entry::new(SERVICE, &keyring_user(username)).get_password() {
+        return decode_secret(&encoded);
+    }
+    if fallback_allowed() {
+        let encoded = std::fs::read_to_string(key_file(username)?).ok()?;
+        return decode_secret(&encoded);
+    }
+    None
+}
+
+fn store_secret(username: &str, secret: &[u8; 32]) -> anyhow::Result<()> {
+    let encoded = CryptoManager::encode_public_key(secret);
+    if Entry::new(SERVICE, &keyring_user(username)).set_password(&encoded).is_ok() {
+        return Ok(());
+    }
+    if !fallback_allowed() {
+        return Err(anyhow::anyhow!("keyring unavailable and file fallback disabled"));
+    }
+    let path = key_file(username).ok_or_else(|| anyhow::anyhow!("no config directory for the key file"))?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(&path, encoded)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    println!("[E2E_KEYS] Keyring unavailable, persisted key to fallback file");
+    Ok(())
+}
+
+/// The end-to-end secret key of `username` on this device, created on first use.
+/// It never leaves the client: only its public half is sent to the server.
+pub fn load_or_create_secret(username: &str) -> anyhow::Result<[u8; 32]> {
+    if let Some(secret) = load_secret(username) {
+        return Ok(secret);
+    }
+    let (secret, _) = CryptoManager::generate_client_keypair();
+    store_secret(username, &secret)?;
+    Ok(secret)
+}
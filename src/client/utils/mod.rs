@@ -1,2 +1,3 @@
 pub mod constants;
+pub mod e2e_keys;
 pub mod session_store;
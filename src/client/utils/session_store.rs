@@ -1,8 +1,40 @@
 use keyring::Entry;
+use std::path::PathBuf;
 
 const SERVICE: &str = "ruggine_app";
 const USER: &str = "ruggine_session";
 
+/// Location of the fallback token file used by releases that stored it in the working directory
+fn legacy_session_file() -> PathBuf {
+    std::path::Path::new("data").join("session_token.txt")
+}
+
+/// Fallback token file: `<config dir>/ruggine/session`, i.e. `~/.config/ruggine/session` on Linux,
+/// `~/Library/Application Support/ruggine/session` on macOS and `%APPDATA%\ruggine\session` on Windows
+fn session_file() -> PathBuf {
+    match dirs::config_dir() {
+        Some(dir) => dir.join("ruggine").join("session"),
+        None => {
+            log::warn!("[SESSION_STORE] No config directory for this platform, using the current directory");
+            legacy_session_file()
+        }
+    }
+}
+
+/// Write the token readable only by the current user
+fn write_private(path: &std::path::Path, token: &str) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, token)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(())
+}
+
 pub fn save_session_token(token: &str) -> anyhow::Result<()> {
     let entry = Entry::new(SERVICE, USER);
     match entry.set_password(token) {
@@ -14,11 +46,7 @@ pub fn save_session_token(token: &str) -> anyhow::Result<()> {
             // Keyring failed. Optionally fall back to a local file when explicitly allowed
             let allow_fallback = std::env::var("KEYRING_FALLBACK").unwrap_or_default() == "true";
             if allow_fallback {
-                let path = std::path::Path::new("data").join("session_token.txt");
-                if let Some(parent) = path.parent() {
-                    let _ = std::fs::create_dir_all(parent);
-                }
-                std::fs::write(&path, token)?;
+                write_private(&session_file(), token)?;
                 // warn in logs but do not print token
                 println!("[SESSION_STORE] Keyring unavailable, persisted token to fallback file");
                 Ok(())
@@ -40,8 +68,8 @@ pub fn load_session_token() -> Option<String> {
             // Only attempt file fallback when explicitly enabled via env var
             let allow_fallback = std::env::var("KEYRING_FALLBACK").unwrap_or_default() == "true";
             if allow_fallback {
-                let path = std::path::Path::new("data").join("session_token.txt");
-                if path.exists() {
+                // The legacy location is still read so existing sessions survive the upgrade
+                for path in [session_file(), legacy_session_file()] {
                     if let Ok(s) = std::fs::read_to_string(&path) {
                         let t = s.trim().to_string();
                         if !t.is_empty() {
@@ -61,9 +89,10 @@ pub fn clear_session_token() -> anyhow::Result<()> {
     // remove fallback file only if fallback is enabled
     let allow_fallback = std::env::var("KEYRING_FALLBACK").unwrap_or_default() == "true";
     if allow_fallback {
-        let path = std::path::Path::new("data").join("session_token.txt");
-        if path.exists() {
-            let _ = std::fs::remove_file(&path);
+        for path in [session_file(), legacy_session_file()] {
+            if path.exists() {
+                let _ = std::fs::remove_file(&path);
+            }
         }
     }
     Ok(())
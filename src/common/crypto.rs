@@ -2,9 +2,11 @@
 use argon2::{Argon2, password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString}};
 use rand::{RngCore, rngs::OsRng};
 use serde::{Serialize, Deserialize};
-use ring::aead::{self, AES_256_GCM, LessSafeKey, UnboundKey, Nonce, NONCE_LEN};
+use ring::aead::{self, AES_256_GCM, CHACHA20_POLY1305, LessSafeKey, UnboundKey, Nonce, NONCE_LEN};
 use ring::error::Unspecified;
 use std::env;
+use base64::{Engine as _, engine::general_purpose};
+use x25519_dalek::{EphemeralSecret, PublicKey, StaticSecret};
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct EncryptedMessage {
@@ -16,6 +18,58 @@ pub struct EncryptedMessage {
     pub sent_at: chrono::DateTime<chrono::Utc>,
 }
 
+/// AEAD cipher used for stored messages. Both take a 256-bit key and a 96-bit nonce.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CipherSuite {
+    #[default]
+    Aes256Gcm,
+    ChaCha20Poly1305,
+}
+
+impl CipherSuite {
+    /// One-byte tag prepended to stored ciphertext
+    pub fn tag(self) -> u8 {
+        match self {
+            CipherSuite::Aes256Gcm => 0x01,
+            CipherSuite::ChaCha20Poly1305 => 0x02,
+        }
+    }
+
+    pub fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0x01 => Some(CipherSuite::Aes256Gcm),
+            0x02 => Some(CipherSuite::ChaCha20Poly1305),
+            _ => None,
+        }
+    }
+
+    fn algorithm(self) -> &'static aead::Algorithm {
+        match self {
+            CipherSuite::Aes256Gcm => &AES_256_GCM,
+            CipherSuite::ChaCha20Poly1305 => &CHACHA20_POLY1305,
+        }
+    }
+}
+
+impl std::str::FromStr for CipherSuite {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().replace(['-', '_'], "").as_str() {
+            "aes256gcm" => Ok(CipherSuite::Aes256Gcm),
+            "chacha20poly1305" => Ok(CipherSuite::ChaCha20Poly1305),
+            other => Err(format!("Unknown cipher suite: {}", other)),
+        }
+    }
+}
+
+/// Prefix of message content sealed on the client by `CryptoManager::client_encrypt`.
+/// The server stores and routes such messages without being able to read them.
+pub const E2E_PREFIX: &str = "e2e:";
+
+/// HKDF context binding derived keys to this protocol version
+const E2E_INFO: &[u8] = b"ruggine-e2e-v1";
+
 pub struct CryptoManager;
 
 impl CryptoManager {
@@ -81,9 +135,74 @@ impl CryptoManager {
         String::from_utf8(plaintext.to_vec()).map_err(|_| Unspecified)
     }
 
-    /// Generates a chat-specific key based on participant IDs
-    pub fn generate_chat_key(participants: &[String], master_key: &[u8; 32]) -> [u8; 32] {
-        use ring::digest;
+    /// Encrypts a message with the given suite. The returned ciphertext starts with the suite tag.
+    pub fn encrypt_message_with(plaintext: &str, key: &[u8; 32], suite: CipherSuite) -> Result<(Vec<u8>, Vec<u8>), Unspecified> {
+        let mut nonce_bytes = [0u8; NONCE_LEN];
+        OsRng.fill_bytes(&mut nonce_bytes);
+        let ciphertext = Self::encrypt_message_with_nonce(plaintext, key, suite, nonce_bytes)?;
+        Ok((ciphertext, nonce_bytes.to_vec()))
+    }
+
+    /// Like `encrypt_message_with`, with a nonce chosen by the caller (see `counter_nonce`).
+    /// The caller must never use the same nonce twice with the same key.
+    pub fn encrypt_message_with_nonce(plaintext: &str, key: &[u8; 32], suite: CipherSuite, nonce: [u8; NONCE_LEN]) -> Result<Vec<u8>, Unspecified> {
+        let unbound_key = UnboundKey::new(suite.algorithm(), key)?;
+        let key = LessSafeKey::new(unbound_key);
+
+        let mut ciphertext = plaintext.as_bytes().to_vec();
+        key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), aead::Aad::empty(), &mut ciphertext)?;
+        ciphertext.insert(0, suite.tag());
+
+        Ok(ciphertext)
+    }
+
+    /// Deterministic nonce for the `counter`-th message of `chat_id`: the first 4 bytes of
+    /// SHA-256(chat_id) followed by the big-endian counter. Unlike random nonces it cannot
+    /// collide within a chat, however many messages it holds.
+    ///
+    /// ```
+    /// use ruggine_modulare::common::crypto::CryptoManager;
+    /// let first = CryptoManager::counter_nonce("group:42", 1);
+    /// assert_eq!(first[4..], 1u64.to_be_bytes());
+    /// assert_eq!(first[..4], CryptoManager::counter_nonce("group:42", 2)[..4]);
+    /// assert_ne!(first, CryptoManager::counter_nonce("group:43", 1));
+    /// ```
+    pub fn counter_nonce(chat_id: &str, counter: u64) -> [u8; NONCE_LEN] {
+        let digest = ring::digest::digest(&ring::digest::SHA256, chat_id.as_bytes());
+        let mut nonce = [0u8; NONCE_LEN];
+        nonce[..4].copy_from_slice(&digest.as_ref()[..4]);
+        nonce[4..].copy_from_slice(&counter.to_be_bytes());
+        nonce
+    }
+
+    /// Decrypts a tagged ciphertext produced by `encrypt_message_with`.
+    pub fn decrypt_tagged_message(ciphertext: &[u8], nonce: &[u8], key: &[u8; 32]) -> Result<String, Unspecified> {
+        let (&tag, body) = ciphertext.split_first().ok_or(Unspecified)?;
+        let suite = CipherSuite::from_tag(tag).ok_or(Unspecified)?;
+        let unbound_key = UnboundKey::new(suite.algorithm(), key)?;
+        let key = LessSafeKey::new(unbound_key);
+
+        let nonce_array: [u8; NONCE_LEN] = nonce.try_into().map_err(|_| Unspecified)?;
+        let nonce = Nonce::assume_unique_for_key(nonce_array);
+
+        let mut body = body.to_vec();
+        let plaintext = key.open_in_place(nonce, aead::Aad::empty(), &mut body)?;
+
+        String::from_utf8(plaintext.to_vec()).map_err(|_| Unspecified)
+    }
+
+    /// Decrypts a stored message, accepting both tagged ciphertext and legacy
+    /// untagged AES-256-GCM ciphertext.
+    pub fn decrypt_stored_message(ciphertext: &[u8], nonce: &[u8], key: &[u8; 32]) -> Result<String, Unspecified> {
+        Self::decrypt_tagged_message(ciphertext, nonce, key)
+            .or_else(|_| Self::decrypt_message(ciphertext, nonce, key))
+    }
+
+    /// Generates a chat-specific key based on participant IDs and the chat's key
+    /// rotation. Rotation 0 is the key used before chats were rotated; later ones
+    /// are derived from it with HKDF, the rotation index being part of the info.
+    pub fn generate_chat_key(participants: &[String], master_key: &[u8; 32], rotation_index: u32) -> [u8; 32] {
+        use ring::{digest, hkdf};
         
         // Sort participants to ensure consistent key generation
         let mut sorted_participants = participants.to_vec();
@@ -99,10 +218,46 @@ impl CryptoManager {
         let digest = digest::digest(&digest::SHA256, &input);
         let mut chat_key = [0u8; 32];
         chat_key.copy_from_slice(digest.as_ref());
-        
+        if rotation_index == 0 {
+            return chat_key;
+        }
+
+        let rotation = rotation_index.to_be_bytes();
+        let info: [&[u8]; 2] = [b"ruggine chat key rotation", &rotation];
+        let prk = hkdf::Salt::new(hkdf::HKDF_SHA256, master_key).extract(&chat_key);
+        prk.expand(&info, hkdf::HKDF_SHA256)
+            .and_then(|okm| okm.fill(&mut chat_key))
+            .expect("32 bytes is a valid HKDF-SHA256 output length");
         chat_key
     }
 
+    /// Key of a group chat at membership `key_version`. Version 0 is the plain
+    /// `generate_chat_key`; each membership change moves the group to a new version,
+    /// derived with HKDF so a key never comes back even when the members do.
+    ///
+    /// ```
+    /// use ruggine_modulare::common::crypto::CryptoManager;
+    /// let members = vec!["alice".to_string(), "bob".to_string()];
+    /// let master = [7u8; 32];
+    /// assert_eq!(CryptoManager::generate_group_key(&members, &master, 0, 0), CryptoManager::generate_chat_key(&members, &master, 0));
+    /// assert_ne!(CryptoManager::generate_group_key(&members, &master, 0, 1), CryptoManager::generate_group_key(&members, &master, 0, 2));
+    /// ```
+    pub fn generate_group_key(members: &[String], master_key: &[u8; 32], rotation_index: u32, key_version: u32) -> [u8; 32] {
+        use ring::hkdf;
+
+        let mut key = Self::generate_chat_key(members, master_key, rotation_index);
+        if key_version == 0 {
+            return key;
+        }
+        let version = key_version.to_be_bytes();
+        let info: [&[u8]; 2] = [b"ruggine group key version", &version];
+        let prk = hkdf::Salt::new(hkdf::HKDF_SHA256, master_key).extract(&key);
+        prk.expand(&info, hkdf::HKDF_SHA256)
+            .and_then(|okm| okm.fill(&mut key))
+            .expect("32 bytes is a valid HKDF-SHA256 output length");
+        key
+    }
+
     pub fn generate_nonce(length: usize) -> Vec<u8> {
         let mut nonce = vec![0u8; length];
         OsRng.fill_bytes(&mut nonce);
@@ -169,6 +324,115 @@ impl CryptoManager {
 
         None
     }
+
+    /// Generates an X25519 key pair for end-to-end encryption, as (secret, public)
+    pub fn generate_client_keypair() -> ([u8; 32], [u8; 32]) {
+        let (public, secret) = Self::generate_dh_keypair();
+        (secret.to_bytes(), public.to_bytes())
+    }
+
+    /// Generates a long-lived X25519 key pair, published with `/publish_key`
+    pub fn generate_dh_keypair() -> (PublicKey, StaticSecret) {
+        let secret = StaticSecret::random_from_rng(OsRng);
+        (PublicKey::from(&secret), secret)
+    }
+
+    /// X25519 shared secret between our key and a peer's published key.
+    /// Both sides get the same 32 bytes; feed them through a KDF before using them as a key.
+    ///
+    /// ```
+    /// use ruggine_modulare::common::crypto::CryptoManager;
+    /// let (alice_pub, alice) = CryptoManager::generate_dh_keypair();
+    /// let (bob_pub, bob) = CryptoManager::generate_dh_keypair();
+    /// assert_eq!(
+    ///     CryptoManager::compute_shared_secret(&alice, &bob_pub),
+    ///     CryptoManager::compute_shared_secret(&bob, &alice_pub),
+    /// );
+    /// ```
+    pub fn compute_shared_secret(my_priv: &StaticSecret, their_pub: &PublicKey) -> [u8; 32] {
+        my_priv.diffie_hellman(their_pub).to_bytes()
+    }
+
+    /// Public half of a client secret created by `generate_client_keypair`
+    pub fn client_public_key(secret: &[u8; 32]) -> [u8; 32] {
+        PublicKey::from(&StaticSecret::from(*secret)).to_bytes()
+    }
+
+    pub fn encode_public_key(public_key: &[u8; 32]) -> String {
+        general_purpose::STANDARD.encode(public_key)
+    }
+
+    /// Parse a base64 X25519 public key as exchanged with `/publish_key`
+    pub fn parse_public_key(encoded: &str) -> Option<[u8; 32]> {
+        general_purpose::STANDARD.decode(encoded.trim()).ok()?.try_into().ok()
+    }
+
+    /// Seals `plaintext` for every key in `recipients` (usually the peer and the sender,
+    /// so both can read their history). Each copy uses a fresh ephemeral X25519 key,
+    /// HKDF-SHA256 and ChaCha20-Poly1305, and is encoded as
+    /// base64(ephemeral public key || nonce || ciphertext); copies are joined by `.`.
+    pub fn client_encrypt(plaintext: &str, recipients: &[[u8; 32]]) -> Result<String, Unspecified> {
+        if recipients.is_empty() {
+            return Err(Unspecified);
+        }
+        let mut copies = Vec::with_capacity(recipients.len());
+        for recipient in recipients {
+            let ephemeral = EphemeralSecret::random_from_rng(OsRng);
+            let ephemeral_public = PublicKey::from(&ephemeral).to_bytes();
+            let shared = ephemeral.diffie_hellman(&PublicKey::from(*recipient));
+            if !shared.was_contributory() {
+                return Err(Unspecified);
+            }
+            let key = Self::e2e_key(shared.as_bytes(), &ephemeral_public, recipient)?;
+
+            let mut nonce_bytes = [0u8; NONCE_LEN];
+            OsRng.fill_bytes(&mut nonce_bytes);
+            let mut ciphertext = plaintext.as_bytes().to_vec();
+            key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce_bytes), aead::Aad::empty(), &mut ciphertext)?;
+
+            let mut copy = ephemeral_public.to_vec();
+            copy.extend_from_slice(&nonce_bytes);
+            copy.extend_from_slice(&ciphertext);
+            copies.push(general_purpose::STANDARD.encode(copy));
+        }
+        Ok(format!("{}{}", E2E_PREFIX, copies.join(".")))
+    }
+
+    /// Opens a message sealed by `client_encrypt` with the reader's secret key,
+    /// trying each copy until one authenticates.
+    pub fn client_decrypt(sealed: &str, secret: &[u8; 32]) -> Result<String, Unspecified> {
+        let copies = sealed.trim().strip_prefix(E2E_PREFIX).ok_or(Unspecified)?;
+        let secret = StaticSecret::from(*secret);
+        let own_public = PublicKey::from(&secret).to_bytes();
+        for copy in copies.split('.') {
+            let Ok(bytes) = general_purpose::STANDARD.decode(copy) else { continue };
+            if bytes.len() < 32 + NONCE_LEN {
+                continue;
+            }
+            let (ephemeral_public, rest) = bytes.split_at(32);
+            let (nonce, ciphertext) = rest.split_at(NONCE_LEN);
+            let ephemeral_public: [u8; 32] = ephemeral_public.try_into().map_err(|_| Unspecified)?;
+            let shared = Self::compute_shared_secret(&secret, &PublicKey::from(ephemeral_public));
+            let key = Self::e2e_key(&shared, &ephemeral_public, &own_public)?;
+
+            let nonce: [u8; NONCE_LEN] = nonce.try_into().map_err(|_| Unspecified)?;
+            let mut body = ciphertext.to_vec();
+            if let Ok(plaintext) = key.open_in_place(Nonce::assume_unique_for_key(nonce), aead::Aad::empty(), &mut body) {
+                return String::from_utf8(plaintext.to_vec()).map_err(|_| Unspecified);
+            }
+        }
+        Err(Unspecified)
+    }
+
+    /// ChaCha20-Poly1305 key for one sealed copy, bound to both public keys involved
+    fn e2e_key(shared: &[u8; 32], ephemeral_public: &[u8; 32], recipient_public: &[u8; 32]) -> Result<LessSafeKey, Unspecified> {
+        use ring::hkdf;
+        let mut salt = ephemeral_public.to_vec();
+        salt.extend_from_slice(recipient_public);
+        let prk = hkdf::Salt::new(hkdf::HKDF_SHA256, &salt).extract(shared);
+        let okm = prk.expand(&[E2E_INFO], &CHACHA20_POLY1305)?;
+        Ok(LessSafeKey::new(UnboundKey::from(okm)))
+    }
 }
 
 // Add more cryptographic utilities as needed for features (e.g., key exchange, signatures)
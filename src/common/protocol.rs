@@ -0,0 +1,85 @@
+// Negoziazione della versione del protocollo (HELLO) condivisa da client e server
+use std::collections::HashSet;
+
+/// Version advertised in `HELLO`; the major component must match between client and server
+pub const PROTOCOL_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Features the server advertises in its `HELLO` reply
+pub const SERVER_CAPABILITIES: &[&str] = &["encryption", "websocket", "friends", "groups", "blocking", "archive", "recent_chats", "e2e_keys", "message_pages"];
+
+/// Shortest password accepted when registering or changing password, in characters
+pub const MIN_PASSWORD_CHARS: usize = 8;
+
+/// Why `password` is too weak for a new account or password, or `None` if it is acceptable.
+/// Commands split on whitespace, so passwords cannot contain it; besides the minimum length
+/// they need two kinds of characters among lowercase, uppercase, digits and symbols.
+///
+/// ```
+/// use ruggine_modulare::common::protocol::password_error;
+///
+/// assert_eq!(password_error("hunter42"), None);
+/// assert_eq!(password_error("Password"), None);
+/// assert!(password_error("short1").is_some());
+/// assert!(password_error("lettersonly").is_some());
+/// assert!(password_error("has space1").is_some());
+/// ```
+pub fn password_error(password: &str) -> Option<&'static str> {
+    if password.chars().any(char::is_whitespace) {
+        return Some("Password cannot contain spaces");
+    }
+    if password.chars().count() < MIN_PASSWORD_CHARS {
+        return Some("Password must be at least 8 characters");
+    }
+    let classes = [
+        password.chars().any(|c| c.is_lowercase()),
+        password.chars().any(|c| c.is_uppercase()),
+        password.chars().any(|c| c.is_ascii_digit()),
+        password.chars().any(|c| !c.is_alphanumeric()),
+    ]
+    .iter()
+    .filter(|&&present| present)
+    .count();
+    (classes < 2).then_some("Password must mix two of lowercase, uppercase, digits and symbols")
+}
+
+fn major(version: &str) -> Option<u64> {
+    version.trim().split('.').next()?.parse().ok()
+}
+
+/// Versions are compatible when their major components are equal
+pub fn is_compatible(client_version: &str, server_version: &str) -> bool {
+    matches!((major(client_version), major(server_version)), (Some(c), Some(s)) if c == s)
+}
+
+/// Server reply to `HELLO <client_version>`
+pub fn hello_response(client_version: &str) -> String {
+    if is_compatible(client_version, PROTOCOL_VERSION) {
+        format!("HELLO {} capabilities={}", PROTOCOL_VERSION, SERVER_CAPABILITIES.join(","))
+    } else {
+        "ERR: Incompatible version, please upgrade".to_string()
+    }
+}
+
+/// What the connected server declared in its `HELLO` reply
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ServerCapabilities {
+    pub version: String,
+    pub capabilities: HashSet<String>,
+}
+
+impl ServerCapabilities {
+    /// Parse `HELLO <server_version> capabilities=<comma_list>`
+    pub fn parse(resp: &str) -> Option<Self> {
+        let mut parts = resp.trim().strip_prefix("HELLO ")?.split_whitespace();
+        let version = parts.next()?.to_string();
+        let capabilities = parts
+            .find_map(|p| p.strip_prefix("capabilities="))
+            .map(|list| list.split(',').filter(|c| !c.is_empty()).map(str::to_string).collect())
+            .unwrap_or_default();
+        Some(Self { version, capabilities })
+    }
+
+    pub fn supports(&self, capability: &str) -> bool {
+        self.capabilities.contains(capability)
+    }
+}
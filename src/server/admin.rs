@@ -0,0 +1,109 @@
+// Funzioni di amministrazione del server (solo per gli admin, vedi auth::is_admin)
+use crate::server::database::Database;
+use std::sync::Arc;
+use sqlx::Row;
+
+/// Largest page accepted by `/admin_list_users`
+pub const MAX_PAGE_SIZE: i64 = 100;
+
+/// One page (1-based) of registered users ordered by username:
+/// "OK: Users page <page>/<pages> (<total> total):\n<username> id=<id> online=<0|1> last_seen=<ts|-> banned=<0|1> registered_at=<ts|->"
+pub async fn list_users(db: Arc<Database>, page: i64, page_size: i64) -> String {
+    if page < 1 || !(1..=MAX_PAGE_SIZE).contains(&page_size) {
+        return format!("ERR: Page must be >= 1 and page size between 1 and {}", MAX_PAGE_SIZE);
+    }
+    let total: i64 = match sqlx::query("SELECT COUNT(*) AS n FROM users").fetch_one(&db.pool).await {
+        Ok(row) => row.get("n"),
+        Err(e) => return format!("ERR: DB error: {}", e),
+    };
+    let rows = sqlx::query("SELECT id, username, is_online, last_seen, is_banned, registered_at FROM users ORDER BY username LIMIT ? OFFSET ?")
+        .bind(page_size)
+        .bind((page - 1) * page_size)
+        .fetch_all(&db.pool)
+        .await;
+    match rows {
+        Ok(rows) => {
+            let users: Vec<String> = rows.iter().map(|r| {
+                format!("{} id={} online={} last_seen={} banned={} registered_at={}",
+                    r.get::<String, _>("username"),
+                    r.get::<String, _>("id"),
+                    r.get::<i64, _>("is_online"),
+                    r.get::<Option<i64>, _>("last_seen").map(|ts| ts.to_string()).unwrap_or_else(|| "-".to_string()),
+                    r.get::<i64, _>("is_banned"),
+                    r.get::<Option<i64>, _>("registered_at").map(|ts| ts.to_string()).unwrap_or_else(|| "-".to_string()),
+                )
+            }).collect();
+            let pages = ((total + page_size - 1) / page_size).max(1);
+            format!("OK: Users page {}/{} ({} total):\n{}", page, pages, total, users.join("\n"))
+        }
+        Err(e) => format!("ERR: DB error: {}", e),
+    }
+}
+
+/// Row counts of the main tables and connection pool usage, followed by the Redis status reported by the caller:
+/// "OK: DB stats: users=<n> online=<n> groups=<n> messages=<n> sessions=<n> ws_pending=<n> pool_size=<n> pool_idle=<n> pool_in_use=<n> pool_max=<n> redis=<status>"
+pub async fn db_stats(db: Arc<Database>, redis_status: &str) -> String {
+    let queries = [
+        ("users", "SELECT COUNT(*) AS n FROM users"),
+        ("online", "SELECT COUNT(*) AS n FROM users WHERE is_online = 1"),
+        ("groups", "SELECT COUNT(*) AS n FROM groups"),
+        ("messages", "SELECT COUNT(*) AS n FROM encrypted_messages"),
+        ("sessions", "SELECT COUNT(*) AS n FROM sessions"),
+        ("ws_pending", "SELECT COUNT(*) AS n FROM ws_pending_messages"),
+    ];
+    let mut stats = Vec::with_capacity(queries.len());
+    for (label, query) in queries {
+        match sqlx::query(query).fetch_one(&db.pool).await {
+            Ok(row) => stats.push(format!("{}={}", label, row.get::<i64, _>("n"))),
+            Err(e) => return format!("ERR: DB error: {}", e),
+        }
+    }
+    let pool = db.pool_stats();
+    format!(
+        "OK: DB stats: {} pool_size={} pool_idle={} pool_in_use={} pool_max={} redis={}",
+        stats.join(" "), pool.size, pool.idle, pool.in_use, pool.max, redis_status
+    )
+}
+
+/// Ban or unban `username`. Banning also ends the user's sessions, so they are logged out.
+pub async fn set_banned(db: Arc<Database>, username: &str, banned: bool) -> String {
+    let user_id = match sqlx::query("SELECT id FROM users WHERE username = ?")
+        .bind(username)
+        .fetch_optional(&db.pool)
+        .await
+    {
+        Ok(Some(row)) => row.get::<String, _>("id"),
+        Ok(None) => return "ERR: User not found".to_string(),
+        Err(e) => return format!("ERR: DB error: {}", e),
+    };
+    if let Err(e) = sqlx::query("UPDATE users SET is_banned = ? WHERE id = ?")
+        .bind(banned as i64)
+        .bind(&user_id)
+        .execute(&db.pool)
+        .await
+    {
+        return format!("ERR: DB error: {}", e);
+    }
+    if banned {
+        let _ = sqlx::query("DELETE FROM sessions WHERE user_id = ?").bind(&user_id).execute(&db.pool).await;
+        db.auth_cache.invalidate_user(&user_id);
+        let _ = sqlx::query("UPDATE users SET is_online = 0 WHERE id = ?").bind(&user_id).execute(&db.pool).await;
+        log::warn!("[ADMIN] User {} ({}) banned", username, user_id);
+        format!("OK: User {} banned", username)
+    } else {
+        log::warn!("[ADMIN] User {} ({}) unbanned", username, user_id);
+        format!("OK: User {} unbanned", username)
+    }
+}
+
+/// Keep a copy of an admin broadcast in `encrypted_messages` under the `system:broadcast` chat.
+/// Broadcasts are meant for everyone, so they are stored in plain text.
+pub async fn store_broadcast(db: Arc<Database>, message: &str) -> Result<(), String> {
+    sqlx::query("INSERT INTO encrypted_messages (chat_id, sender_id, message, sent_at) VALUES ('system:broadcast', 'system', ?, ?)")
+        .bind(message)
+        .bind(chrono::Utc::now().timestamp())
+        .execute(&db.pool)
+        .await
+        .map(|_| ())
+        .map_err(|e| format!("ERR: DB error: {}", e))
+}
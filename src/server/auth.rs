@@ -1,7 +1,9 @@
 use crate::server::database::Database;
 use crate::server::config::ServerConfig;
+use crate::server::users;
 use std::sync::Arc;
 use sqlx::Row;
+use crate::common::protocol::password_error;
 use argon2::{Argon2, password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString}};
 use rand::RngCore;
 
@@ -17,7 +19,8 @@ pub async fn logout(db: Arc<Database>, session_token: &str) -> String {
     match row {
         Ok(Some(row)) => {
             let user_id: String = row.get("user_id");
-            // Invalidate all sessions for this user (logout from all devices) to enforce single-session semantics
+            // Invalidate all sessions for this user (logout from all devices)
+            db.auth_cache.invalidate_user(&user_id);
             match sqlx::query("DELETE FROM sessions WHERE user_id = ?")
                 .bind(&user_id)
                 .execute(&db.pool)
@@ -28,7 +31,8 @@ pub async fn logout(db: Arc<Database>, session_token: &str) -> String {
             }
 
             // Force user offline
-            match sqlx::query("UPDATE users SET is_online = 0 WHERE id = ?")
+            match sqlx::query("UPDATE users SET is_online = 0, last_seen = ? WHERE id = ?")
+                .bind(chrono::Utc::now().timestamp())
                 .bind(&user_id)
                 .execute(&db.pool)
                 .await
@@ -107,16 +111,20 @@ fn generate_session_token() -> String {
 
 pub async fn register(db: Arc<Database>, username: &str, password: &str, config: &ServerConfig) -> String {
     println!("[AUTH] Register attempt: {}", username);
+    if let Some(e) = password_error(password) {
+        return format!("ERR: {}", e);
+    }
     let user_id = uuid::Uuid::new_v4().to_string();
     let created_at = chrono::Utc::now().timestamp();
     let password_hash = hash_password(password, config.argon2_salt_length);
     let tx = db.pool.begin().await;
     match tx {
         Ok(mut tx) => {
-            let res = sqlx::query("INSERT INTO users (id, username, created_at, is_online) VALUES (?, ?, ?, 0)")
+            let res = sqlx::query("INSERT INTO users (id, username, created_at, registered_at, is_online) VALUES (?, ?, ?, ?, 0)")
                 .bind(&user_id)
                 .bind(username)
                 .bind(created_at)
+                .bind(created_at)
                 .execute(&mut *tx)
                 .await;
             if let Err(e) = res {
@@ -169,9 +177,195 @@ pub async fn register(db: Arc<Database>, username: &str, password: &str, config:
     }
 }
 
-pub async fn login(db: Arc<Database>, username: &str, password: &str, config: &ServerConfig) -> String {
+/// Replace the password of `user_id` after checking `old_password`, then revoke every session
+/// except `session_token`, the one the change came from.
+pub async fn change_password(db: Arc<Database>, user_id: &str, session_token: &str, old_password: &str, new_password: &str, config: &ServerConfig) -> String {
+    println!("[AUTH] Change password for user {}", user_id);
+    let password_hash: String = match sqlx::query("SELECT password_hash FROM auth WHERE user_id = ?")
+        .bind(user_id)
+        .fetch_optional(&db.pool)
+        .await
+    {
+        Ok(Some(row)) => row.get("password_hash"),
+        Ok(None) => return "ERR: User not found".to_string(),
+        Err(e) => return format!("ERR: DB error: {}", e),
+    };
+    if !verify_password(&password_hash, old_password) {
+        return "ERR: Current password is incorrect".to_string();
+    }
+    if old_password == new_password {
+        return "ERR: New password must differ from the current one".to_string();
+    }
+    if let Some(e) = password_error(new_password) {
+        return format!("ERR: {}", e);
+    }
+    let new_hash = hash_password(new_password, config.argon2_salt_length);
+    let mut tx = match db.pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return format!("ERR: DB error: {}", e),
+    };
+    if let Err(e) = sqlx::query("UPDATE auth SET password_hash = ? WHERE user_id = ?")
+        .bind(&new_hash)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+    {
+        return format!("ERR: DB error: {}", e);
+    }
+    let revoked = match sqlx::query("DELETE FROM sessions WHERE user_id = ? AND session_token != ?")
+        .bind(user_id)
+        .bind(session_token)
+        .execute(&mut *tx)
+        .await
+    {
+        Ok(res) => res.rows_affected(),
+        Err(e) => return format!("ERR: DB error: {}", e),
+    };
+    if let Err(e) = tx.commit().await {
+        return format!("ERR: DB error: {}", e);
+    }
+    // Cached validations of the revoked tokens must not outlive their rows
+    db.auth_cache.invalidate_user(user_id);
+    println!("[AUTH] Password changed for {}, {} other session(s) revoked", user_id, revoked);
+    "OK: Password changed. All other sessions have been revoked.".to_string()
+}
+
+/// `sender_id` of the messages written by accounts that deleted themselves
+pub const DELETED_USER_ID: &str = "deleted_user";
+
+/// Delete the account of `user_id` after checking `password`. Messages it sent stay in the
+/// chats of the other participants, attributed to `DELETED_USER_ID`, and a `deleted_users`
+/// row records the deletion; everything else about the user goes, in one transaction.
+pub async fn delete_account(db: Arc<Database>, user_id: &str, password: &str) -> String {
+    let password_hash: String = match sqlx::query("SELECT password_hash FROM auth WHERE user_id = ?")
+        .bind(user_id)
+        .fetch_optional(&db.pool)
+        .await
+    {
+        Ok(Some(row)) => row.get("password_hash"),
+        Ok(None) => return "ERR: User not found".to_string(),
+        Err(e) => return format!("ERR: DB error: {}", e),
+    };
+    if !verify_password(&password_hash, password) {
+        return "ERR: Password is incorrect".to_string();
+    }
+    // Groups would be left without an owner
+    let owned = sqlx::query(
+        "SELECT 1 FROM group_members gm WHERE gm.user_id = ?1 AND gm.role = 'owner' \
+         AND EXISTS (SELECT 1 FROM group_members o WHERE o.group_id = gm.group_id AND o.user_id != ?1)")
+        .bind(user_id)
+        .fetch_optional(&db.pool)
+        .await;
+    match owned {
+        Ok(Some(_)) => return "ERR: Delete the groups you own before deleting your account".to_string(),
+        Ok(None) => {}
+        Err(e) => return format!("ERR: DB error: {}", e),
+    }
+
+    let mut tx = match db.pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return format!("ERR: DB error: {}", e),
+    };
+    if let Err(e) = sqlx::query("UPDATE encrypted_messages SET sender_id = ? WHERE sender_id = ?")
+        .bind(DELETED_USER_ID)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+    {
+        return format!("ERR: DB error: {}", e);
+    }
+    if let Err(e) = users::remove_user_rows(&mut tx, user_id).await {
+        println!("[AUTH] Error deleting account {}: {}", user_id, e);
+        return format!("ERR: DB error: {}", e);
+    }
+    if let Err(e) = sqlx::query("INSERT OR REPLACE INTO deleted_users (original_id, deleted_at) VALUES (?, ?)")
+        .bind(user_id)
+        .bind(chrono::Utc::now().timestamp())
+        .execute(&mut *tx)
+        .await
+    {
+        return format!("ERR: DB error: {}", e);
+    }
+    if let Err(e) = tx.commit().await {
+        return format!("ERR: DB error: {}", e);
+    }
+    db.auth_cache.invalidate_user(user_id);
+    println!("[AUTH] Account {} deleted by its owner", user_id);
+    "OK: Account deleted".to_string()
+}
+
+/// Active sessions of `user_id`, newest first:
+/// "OK: Sessions: <id>:<label>:<created_at>:<expires_at>, ..." where the label is empty when unset
+pub async fn list_sessions(db: Arc<Database>, user_id: &str) -> String {
+    let rows = sqlx::query("SELECT session_token, device_label, created_at, expires_at FROM sessions WHERE user_id = ? AND expires_at > ? ORDER BY created_at DESC")
+        .bind(user_id)
+        .bind(chrono::Utc::now().timestamp())
+        .fetch_all(&db.pool)
+        .await;
+    match rows {
+        Ok(rows) => {
+            let sessions: Vec<String> = rows.iter().map(|r| {
+                format!("{}:{}:{}:{}",
+                    session_id(&r.get::<String, _>("session_token")),
+                    r.get::<Option<String>, _>("device_label").unwrap_or_default(),
+                    r.get::<i64, _>("created_at"),
+                    r.get::<i64, _>("expires_at"))
+            }).collect();
+            format!("OK: Sessions: {}", sessions.join(", "))
+        }
+        Err(e) => format!("ERR: DB error: {}", e),
+    }
+}
+
+/// Sign out the session of `user_id` whose id (see `list_sessions`) is `target_id`
+pub async fn revoke_session(db: Arc<Database>, user_id: &str, target_id: &str) -> String {
+    let res = sqlx::query("DELETE FROM sessions WHERE user_id = ? AND substr(session_token, -8) = ?")
+        .bind(user_id)
+        .bind(target_id)
+        .execute(&db.pool)
+        .await;
+    match res {
+        Ok(r) if r.rows_affected() > 0 => {
+            db.auth_cache.invalidate_user(user_id);
+            "OK: Session revoked".to_string()
+        }
+        Ok(_) => "ERR: Session not found".to_string(),
+        Err(e) => format!("ERR: DB error: {}", e),
+    }
+}
+
+/// Longest device label accepted by `/login`
+const MAX_DEVICE_LABEL_CHARS: usize = 32;
+
+/// Whether `label` can name a session: short, and made of letters, digits, `-`, `_` and `.`
+/// so it fits the `/list_sessions` fields.
+///
+/// ```
+/// use ruggine_modulare::server::auth::is_valid_device_label;
+///
+/// assert!(is_valid_device_label("laptop-linux"));
+/// assert!(!is_valid_device_label(""));
+/// assert!(!is_valid_device_label("my:phone"));
+/// assert!(!is_valid_device_label(&"x".repeat(33)));
+/// ```
+pub fn is_valid_device_label(label: &str) -> bool {
+    !label.is_empty()
+        && label.chars().count() <= MAX_DEVICE_LABEL_CHARS
+        && label.chars().all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+/// Public id of a session in `/list_sessions` and `/revoke_session`: the last 8 characters
+/// of its token, so other sessions can be told apart without revealing their tokens
+fn session_id(session_token: &str) -> &str {
+    &session_token[session_token.len().saturating_sub(8)..]
+}
+
+pub async fn login(db: Arc<Database>, username: &str, password: &str, device_label: Option<&str>, config: &ServerConfig) -> String {
+    if device_label.is_some_and(|label| !is_valid_device_label(label)) {
+        return "ERR: Invalid device label".to_string();
+    }
     println!("[AUTH] Login attempt: {}", username);
-    let row = sqlx::query("SELECT users.id, password_hash FROM users JOIN auth ON users.id = auth.user_id WHERE username = ?")
+    let row = sqlx::query("SELECT users.id, password_hash, is_banned FROM users JOIN auth ON users.id = auth.user_id WHERE username = ?")
         .bind(username)
         .fetch_optional(&db.pool)
         .await;
@@ -180,17 +374,38 @@ pub async fn login(db: Arc<Database>, username: &str, password: &str, config: &S
             let user_id: String = row.get("id");
             let password_hash: String = row.get("password_hash");
             if verify_password(&password_hash, password) {
-                // Begin transaction to ensure atomic single-session semantics
+                if row.get::<i64, _>("is_banned") != 0 {
+                    println!("[AUTH] Login refused for {}: account suspended", username);
+                    return "ERR: Account suspended".to_string();
+                }
+                // Begin transaction so the session and the login event are recorded together
                 match db.pool.begin().await {
                     Ok(mut tx) => {
-                        // Remove any existing sessions for this user
-                        match sqlx::query("DELETE FROM sessions WHERE user_id = ?")
+                        // Remember when the account was last accessed before its sessions are replaced:
+                        // logout deletes session rows, so earlier logins come from the event log
+                        let last_login: Option<i64> = sqlx::query(
+                            "SELECT MAX(created_at) AS last_login FROM (
+                                SELECT created_at FROM sessions WHERE user_id = ?1
+                                UNION ALL
+                                SELECT created_at FROM session_events WHERE user_id = ?1 AND event_type = 'login_success'
+                            )",
+                        )
+                        .bind(&user_id)
+                        .fetch_one(&mut *tx)
+                        .await
+                        .ok()
+                        .and_then(|r| r.try_get::<Option<i64>, _>("last_login").ok().flatten());
+
+                        // Other devices stay signed in (see /list_sessions); only expired sessions go
+                        let now = chrono::Utc::now().timestamp();
+                        match sqlx::query("DELETE FROM sessions WHERE user_id = ? AND expires_at <= ?")
                             .bind(&user_id)
+                            .bind(now)
                             .execute(&mut *tx)
                             .await
                         {
-                            Ok(r) => println!("[AUTH] Deleted {} old sessions for user {} during login", r.rows_affected(), user_id),
-                            Err(e) => println!("[AUTH] Failed deleting old sessions for {}: {}", user_id, e),
+                            Ok(r) => println!("[AUTH] Deleted {} expired sessions for user {} during login", r.rows_affected(), user_id),
+                            Err(e) => println!("[AUTH] Failed deleting expired sessions for {}: {}", user_id, e),
                         }
 
                         // Set user online
@@ -205,13 +420,13 @@ pub async fn login(db: Arc<Database>, username: &str, password: &str, config: &S
 
                         // Create new session token
                         let session_token = generate_session_token();
-                        let now = chrono::Utc::now().timestamp();
                         let expires = now + 60*60*24*config.session_expiry_days as i64;
-                        match sqlx::query("INSERT INTO sessions (user_id, session_token, created_at, expires_at) VALUES (?, ?, ?, ?)")
+                        match sqlx::query("INSERT INTO sessions (user_id, session_token, created_at, expires_at, device_label) VALUES (?, ?, ?, ?, ?)")
                             .bind(&user_id)
                             .bind(&session_token)
                             .bind(now)
                             .bind(expires)
+                            .bind(device_label)
                             .execute(&mut *tx)
                             .await
                         {
@@ -234,7 +449,10 @@ pub async fn login(db: Arc<Database>, username: &str, password: &str, config: &S
                         }
 
                         println!("[AUTH] Login success for {} (id={})", username, user_id);
-                        format!("OK: Logged in as {} SESSION: {}", username, session_token)
+                        match last_login {
+                            Some(ts) => format!("OK: Logged in as {} SESSION: {} LAST_LOGIN: {}", username, session_token, ts),
+                            None => format!("OK: Logged in as {} SESSION: {}", username, session_token),
+                        }
                     }
                     Err(e) => {
                         println!("[AUTH] Failed to start transaction for login {}: {}", username, e);
@@ -259,7 +477,10 @@ pub async fn login(db: Arc<Database>, username: &str, password: &str, config: &S
 
 pub async fn validate_session(db: Arc<Database>, session_token: &str) -> Option<String> {
     let now = chrono::Utc::now().timestamp();
-    let row = sqlx::query("SELECT user_id FROM sessions WHERE session_token = ? AND expires_at > ?")
+    if let Some(user_id) = db.auth_cache.get(session_token, now) {
+        return Some(user_id);
+    }
+    let row = sqlx::query("SELECT user_id, expires_at FROM sessions WHERE session_token = ? AND expires_at > ?")
         .bind(session_token)
         .bind(now)
         .fetch_optional(&db.pool)
@@ -269,6 +490,7 @@ pub async fn validate_session(db: Arc<Database>, session_token: &str) -> Option<
     if let Some(row) = row {
         let user_id: String = row.get("user_id");
         println!("[AUTH] validate_session: token {} is valid for user {}", session_token, user_id);
+        db.auth_cache.insert(session_token, &user_id, row.get::<i64, _>("expires_at"));
         
         // Set user online when session is validated (for auto-login scenarios)
         let _ = sqlx::query("UPDATE users SET is_online = 1 WHERE id = ?")
@@ -284,9 +506,31 @@ pub async fn validate_session(db: Arc<Database>, session_token: &str) -> Option<
     }
 }
 
-/// Rimuove le sessioni scadute dal DB. Idempotente e sicuro da eseguire periodicamente.
-pub async fn cleanup_expired_sessions(db: Arc<Database>) {
+/// Periodic cleanup of expired or stale rows. Tables that are not present in the
+/// current schema are skipped. Returns the users it marked offline.
+pub async fn run_maintenance(db: Arc<Database>) -> Vec<String> {
     let now = chrono::Utc::now().timestamp();
+    let day_ago = now - 24 * 60 * 60;
+    let week_ago = now - 7 * 24 * 60 * 60;
+
+    // Users still flagged online whose last session expires were never seen disconnecting;
+    // record the expiry as their last sighting
+    let went_offline: Vec<String> = match sqlx::query("UPDATE users SET is_online = 0, last_seen = (SELECT MAX(expires_at) FROM sessions WHERE user_id = users.id) WHERE is_online = 1 AND id IN (SELECT user_id FROM sessions WHERE expires_at <= ?1) AND id NOT IN (SELECT user_id FROM sessions WHERE expires_at > ?1) RETURNING id")
+        .bind(now)
+        .fetch_all(&db.pool)
+        .await
+    {
+        Ok(rows) => {
+            println!("[AUTH] Marked {} users with expired sessions offline", rows.len());
+            rows.iter().map(|r| r.get::<String, _>("id")).collect()
+        }
+        Err(e) => {
+            println!("[AUTH] Failed to mark users with expired sessions offline: {}", e);
+            vec![]
+        }
+    };
+
+    // Sessioni scadute
     match sqlx::query("DELETE FROM sessions WHERE expires_at <= ?")
         .bind(now)
         .execute(&db.pool)
@@ -295,4 +539,76 @@ pub async fn cleanup_expired_sessions(db: Arc<Database>) {
         Ok(res) => println!("[AUTH] Cleaned up {} expired sessions", res.rows_affected()),
         Err(e) => println!("[AUTH] Failed to cleanup sessions: {}", e),
     }
+
+    // Inviti di gruppo scaduti
+    match sqlx::query("DELETE FROM group_invites WHERE status = 'pending' AND expires_at IS NOT NULL AND expires_at <= ?")
+        .bind(now)
+        .execute(&db.pool)
+        .await
+    {
+        Ok(res) => println!("[AUTH] Cleaned up {} expired group invites", res.rows_affected()),
+        Err(e) => println!("[AUTH] Failed to cleanup group invites: {}", e),
+    }
+
+    let tasks: [(&str, &str, Option<i64>); 4] = [
+        ("login_failures", "DELETE FROM login_failures WHERE created_at <= ?", Some(day_ago)),
+        ("idempotency_log", "DELETE FROM idempotency_log WHERE created_at <= ?", Some(day_ago)),
+        ("scheduled_messages", "DELETE FROM scheduled_messages WHERE triggered = 1", None),
+        ("notifications", "DELETE FROM notifications WHERE is_read = 1 AND created_at <= ?", Some(week_ago)),
+    ];
+
+    for (table, sql, cutoff) in tasks {
+        let exists = sqlx::query("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?")
+            .bind(table)
+            .fetch_optional(&db.pool)
+            .await;
+        if !matches!(exists, Ok(Some(_))) {
+            continue;
+        }
+        let mut query = sqlx::query(sql);
+        if let Some(cutoff) = cutoff {
+            query = query.bind(cutoff);
+        }
+        match query.execute(&db.pool).await {
+            Ok(res) => println!("[AUTH] Cleaned up {} rows from {}", res.rows_affected(), table),
+            Err(e) => println!("[AUTH] Failed to cleanup {}: {}", table, e),
+        }
+    }
+    went_offline
+}
+
+/// True if the user is listed in ADMIN_USERS
+pub async fn is_admin(db: Arc<Database>, config: &ServerConfig, user_id: &str) -> bool {
+    match sqlx::query("SELECT username FROM users WHERE id = ?")
+        .bind(user_id)
+        .fetch_optional(&db.pool)
+        .await
+    {
+        Ok(Some(row)) => config.admin_users.contains(&row.get::<String, _>("username")),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::server::test_support::{group_id, register, server, PASSWORD};
+
+    #[tokio::test]
+    async fn deleted_account_leaves_group_history_readable() {
+        let server = server().await;
+        let alice = register(&server, "alice").await;
+        register(&server, "bobby").await;
+        let carol = register(&server, "carol").await;
+        server.handle_command("/create_group", &[&alice, "friends", "bobby,carol"]).await;
+        let group = group_id(&server, "friends").await;
+        server.handle_command("/send_group_message", &[&alice, &group, "hello before delete"]).await;
+        server.handle_command("/send_group_message", &[&carol, &group, "bye"]).await;
+
+        let resp = server.handle_command("/delete_account", &[&carol, PASSWORD]).await;
+        assert!(resp.starts_with("OK:"), "{}", resp);
+
+        let history = server.handle_command("/get_group_messages", &[&alice, &group]).await;
+        assert!(history.contains("alice: hello before delete"), "{}", history);
+        assert!(history.contains("deleted_user: bye"), "{}", history);
+    }
 }
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Default number of session tokens kept by `AuthCache`
+pub const DEFAULT_SESSION_CACHE_SIZE: usize = 1000;
+
+struct CachedSession {
+    user_id: String,
+    expires_at: i64,
+    last_used: u64,
+}
+
+#[derive(Default)]
+struct Inner {
+    sessions: HashMap<String, CachedSession>,
+    tick: u64,
+}
+
+/// Session token -> (user_id, expires_at), so `auth::validate_session` can skip the
+/// sessions table for tokens it has already seen. The least recently used token is
+/// evicted once `capacity` is reached; a capacity of 0 disables the cache.
+///
+/// ```
+/// use ruggine_modulare::server::auth_cache::AuthCache;
+///
+/// let cache = AuthCache::new(2);
+/// cache.insert("t1", "alice", 100);
+/// cache.insert("t2", "bob", 100);
+/// assert_eq!(cache.get("t1", 50), Some("alice".to_string()));
+/// cache.insert("t3", "carol", 100); // evicts t2, the least recently used
+/// assert_eq!(cache.get("t2", 50), None);
+/// assert_eq!(cache.get("t1", 100), None); // expired
+/// cache.invalidate_user("carol");
+/// assert_eq!(cache.get("t3", 50), None);
+/// ```
+#[derive(Clone)]
+pub struct AuthCache {
+    inner: Arc<Mutex<Inner>>,
+    capacity: usize,
+}
+
+impl AuthCache {
+    pub fn new(capacity: usize) -> Self {
+        Self { inner: Arc::new(Mutex::new(Inner::default())), capacity }
+    }
+
+    /// User of `token` if it is cached and still valid at `now`; expired entries are dropped
+    pub fn get(&self, token: &str, now: i64) -> Option<String> {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.tick += 1;
+        let tick = inner.tick;
+        match inner.sessions.get_mut(token) {
+            Some(entry) if entry.expires_at > now => {
+                entry.last_used = tick;
+                Some(entry.user_id.clone())
+            }
+            Some(_) => {
+                inner.sessions.remove(token);
+                None
+            }
+            None => None,
+        }
+    }
+
+    pub fn insert(&self, token: &str, user_id: &str, expires_at: i64) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.tick += 1;
+        let last_used = inner.tick;
+        if !inner.sessions.contains_key(token) && inner.sessions.len() >= self.capacity {
+            let oldest = inner.sessions.iter().min_by_key(|(_, e)| e.last_used).map(|(t, _)| t.clone());
+            if let Some(oldest) = oldest {
+                inner.sessions.remove(&oldest);
+            }
+        }
+        inner.sessions.insert(token.to_string(), CachedSession { user_id: user_id.to_string(), expires_at, last_used });
+    }
+
+    /// Drop every cached token of `user_id`, e.g. after their sessions are deleted
+    pub fn invalidate_user(&self, user_id: &str) {
+        let mut inner = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        inner.sessions.retain(|_, e| e.user_id != user_id);
+    }
+}
+
+impl Default for AuthCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_SESSION_CACHE_SIZE)
+    }
+}
+
+impl std::fmt::Debug for AuthCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AuthCache").field("capacity", &self.capacity).finish_non_exhaustive()
+    }
+}
@@ -1,26 +1,179 @@
 use std::env;
-use crate::common::crypto::CryptoManager;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use anyhow::Context;
+use serde::{Deserialize, Deserializer};
+use crate::common::crypto::{CipherSuite, CryptoManager};
+use crate::server::logging;
 
-#[derive(Debug, Clone)]
+/// Command line options of the server binary
+#[derive(Debug, Default)]
+pub struct Args {
+    pub log_level: Option<String>,
+    pub log_file: Option<PathBuf>,
+    /// TOML file layered between the built-in defaults and the environment
+    pub config: Option<PathBuf>,
+    /// Print a commented config template and exit
+    pub print_config: bool,
+}
+
+impl Args {
+    pub fn parse() -> anyhow::Result<Self> {
+        let usage = "usage: ruggine-server [--log-level <error|warn|info|debug|trace>] [--log-file <path>] [--config <path>] [--print-config]";
+        let mut args = Self::default();
+        let mut it = env::args().skip(1);
+        while let Some(arg) = it.next() {
+            match arg.as_str() {
+                "--log-level" => {
+                    let level = it.next().ok_or_else(|| anyhow::anyhow!(usage))?.to_lowercase();
+                    if !logging::LOG_LEVELS.contains(&level.as_str()) {
+                        return Err(anyhow::anyhow!("invalid log level '{}'\n{}", level, usage));
+                    }
+                    args.log_level = Some(level);
+                }
+                "--log-file" => args.log_file = Some(it.next().ok_or_else(|| anyhow::anyhow!(usage))?.into()),
+                "--config" => args.config = Some(it.next().ok_or_else(|| anyhow::anyhow!(usage))?.into()),
+                "--print-config" => args.print_config = true,
+                other => return Err(anyhow::anyhow!("unknown argument '{}'\n{}", other, usage)),
+            }
+        }
+        Ok(args)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct ServerConfig {
     pub host: String,
     pub port: u16,
+    /// Port of the WebSocket server, served over TLS (wss://) when the TCP server uses TLS
+    pub ws_port: u16,
     pub database_url: String,
     pub max_clients: usize,
     pub enable_encryption: bool,
     pub log_level: String,
     pub session_expiry_days: u32,
+    /// Session tokens kept in memory by `auth::validate_session` (0 disables the cache)
+    pub session_cache_size: usize,
     pub argon2_salt_length: u32,
     pub max_message_length: usize,
+    /// Only read from ENCRYPTION_MASTER_KEY so the secret never ends up in a config file
+    #[serde(skip)]
     pub encryption_master_key: [u8; 32], // Master key for message encryption
+    #[serde(deserialize_with = "deserialize_cipher_suite")]
+    pub cipher_suite: CipherSuite,
+    pub max_connection_attempts_per_minute: usize,
+    pub blacklist_duration_secs: u64,
+    pub enable_audit_log: bool,
+    pub admin_users: Vec<String>,
+    pub invite_expiry_hours: i64,
+    /// Members a group may hold, owner included
+    pub max_group_size: usize,
+    /// Groups a user may belong to, whether created or joined
+    pub max_groups_per_user: usize,
+    /// Attempts to open and migrate the database while another process holds its lock
+    pub db_connect_retries: u32,
+    pub db_connect_retry_delay_ms: u64,
+    /// Bounds of the SQLite connection pool
+    pub db_pool_max_connections: u32,
+    pub db_pool_min_connections: u32,
+    /// Coordinate WebSocket delivery across server instances through Redis
+    pub enable_redis: bool,
+    pub redis_url: String,
+    /// Seconds between WebSocket pings, and how long a pong may take before the connection is dropped
+    pub heartbeat_interval_secs: u64,
+    pub heartbeat_timeout_secs: u64,
+    /// WebSocket messages kept for an offline user; the oldest are dropped beyond this
+    pub max_pending_messages_per_user: usize,
+    /// Directory of files sent over WebSocket: chunks in `<file_id>/`, finished files in `complete/`
+    pub file_storage_dir: String,
+    /// Largest file, in bytes, and most chunks one WebSocket upload may have
+    pub max_file_size_bytes: u64,
+    pub max_file_chunks: u32,
+    /// WebSocket connections one user may keep open at once (e.g. one per device)
+    pub max_ws_connections_per_user: usize,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_string(),
+            port: 5000,
+            ws_port: 5001,
+            database_url: "sqlite:data/ruggine_modulare.db".to_string(),
+            max_clients: 100,
+            enable_encryption: true,
+            log_level: "info".to_string(),
+            session_expiry_days: 7,
+            session_cache_size: crate::server::auth_cache::DEFAULT_SESSION_CACHE_SIZE,
+            argon2_salt_length: 16,
+            max_message_length: 2048,
+            encryption_master_key: [0; 32],
+            cipher_suite: CipherSuite::default(),
+            max_connection_attempts_per_minute: 30,
+            blacklist_duration_secs: 300,
+            enable_audit_log: false,
+            admin_users: Vec::new(),
+            invite_expiry_hours: 72,
+            max_group_size: 500,
+            max_groups_per_user: 50,
+            db_connect_retries: 5,
+            db_connect_retry_delay_ms: 500,
+            db_pool_max_connections: crate::server::database::DEFAULT_POOL_MAX_CONNECTIONS,
+            db_pool_min_connections: crate::server::database::DEFAULT_POOL_MIN_CONNECTIONS,
+            enable_redis: true,
+            redis_url: "redis://localhost:6379".to_string(),
+            heartbeat_interval_secs: 30,
+            heartbeat_timeout_secs: 10,
+            max_pending_messages_per_user: 200,
+            file_storage_dir: "data/files".to_string(),
+            max_file_size_bytes: 100 * 1024 * 1024,
+            max_file_chunks: 4096,
+            max_ws_connections_per_user: 5,
+        }
+    }
+}
+
+fn deserialize_cipher_suite<'de, D: Deserializer<'de>>(deserializer: D) -> Result<CipherSuite, D::Error> {
+    String::deserialize(deserializer)?.parse().map_err(serde::de::Error::custom)
+}
+
+/// Parsed value of an environment variable; unset or unparsable values yield `None`
+fn env_parse<T: FromStr>(key: &str) -> Option<T> {
+    env::var(key).ok().and_then(|v| v.parse().ok())
+}
+
+fn env_bool(key: &str) -> Option<bool> {
+    env::var(key).ok().map(|v| v == "true" || v == "1")
 }
 
 impl ServerConfig {
+    /// Defaults overridden by environment variables (and `.env`)
     pub fn from_env() -> Self {
+        Self::default().with_env()
+    }
+
+    /// Resolves the configuration with precedence env > `--config` file > defaults
+    pub fn load(args: &Args) -> anyhow::Result<Self> {
+        let base = match &args.config {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+        Ok(base.with_env())
+    }
+
+    /// Reads a TOML config file; keys it leaves out keep their defaults
+    pub fn from_file(path: &Path) -> anyhow::Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("cannot read config file {}", path.display()))?;
+        toml::from_str(&text).with_context(|| format!("invalid config file {}", path.display()))
+    }
+
+    fn with_env(mut self) -> Self {
         dotenvy::dotenv().ok();
-        
+
         // Load master key from environment if present, otherwise generate and log suggestion
-        let encryption_master_key = if let Some(k) = CryptoManager::load_master_key_from_env() {
+        self.encryption_master_key = if let Some(k) = CryptoManager::load_master_key_from_env() {
             println!("[CRYPTO] Loaded ENCRYPTION_MASTER_KEY from .env");
             k
         } else {
@@ -34,19 +187,130 @@ impl ServerConfig {
             println!("[CRYPTO] Generated master key: {}", key_hex);
             key
         };
-        
-        Self {
-            host: env::var("SERVER_HOST").unwrap_or_else(|_| "127.0.0.1".to_string()),
-            port: env::var("SERVER_PORT").ok().and_then(|p| p.parse().ok()).unwrap_or(5000),
-            database_url: env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite:data/ruggine_modulare.db".to_string()),
-            max_clients: env::var("MAX_CLIENTS").ok().and_then(|v| v.parse().ok()).unwrap_or(100),
-            enable_encryption: env::var("ENABLE_ENCRYPTION").map(|v| v == "true" || v == "1").unwrap_or(true),
-            log_level: env::var("LOG_LEVEL").unwrap_or_else(|_| "info".to_string()),
-            session_expiry_days: env::var("SESSION_EXPIRY_DAYS").ok().and_then(|v| v.parse().ok()).unwrap_or(7),
-            argon2_salt_length: env::var("ARGON2_SALT_LENGTH").ok().and_then(|v| v.parse().ok()).unwrap_or(16),
-            max_message_length: env::var("MAX_MESSAGE_LENGTH").ok().and_then(|v| v.parse().ok()).unwrap_or(2048),
-            encryption_master_key,
+
+        if let Ok(v) = env::var("SERVER_HOST") { self.host = v; }
+        if let Some(v) = env_parse("SERVER_PORT") { self.port = v; }
+        if let Some(v) = env_parse("WS_PORT") { self.ws_port = v; }
+        if let Ok(v) = env::var("DATABASE_URL") { self.database_url = v; }
+        if let Some(v) = env_parse("MAX_CLIENTS") { self.max_clients = v; }
+        if let Some(v) = env_bool("ENABLE_ENCRYPTION") { self.enable_encryption = v; }
+        if let Ok(v) = env::var("LOG_LEVEL") { self.log_level = v; }
+        if let Some(v) = env_parse("SESSION_EXPIRY_DAYS") { self.session_expiry_days = v; }
+        if let Some(v) = env_parse("SESSION_CACHE_SIZE") { self.session_cache_size = v; }
+        if let Some(v) = env_parse("ARGON2_SALT_LENGTH") { self.argon2_salt_length = v; }
+        if let Some(v) = env_parse("MAX_MESSAGE_LENGTH") { self.max_message_length = v; }
+        if let Ok(v) = env::var("CIPHER_SUITE") {
+            match v.parse() {
+                Ok(suite) => self.cipher_suite = suite,
+                Err(e) => log::warn!("[CONFIG] Ignoring CIPHER_SUITE: {}; using {:?}", e, self.cipher_suite),
+            }
         }
+        if let Some(v) = env_parse("MAX_CONNECTION_ATTEMPTS_PER_MINUTE") { self.max_connection_attempts_per_minute = v; }
+        if let Some(v) = env_parse("BLACKLIST_DURATION_SECS") { self.blacklist_duration_secs = v; }
+        if let Some(v) = env_bool("AUDIT_LOG") { self.enable_audit_log = v; }
+        if let Ok(v) = env::var("ADMIN_USERS") {
+            self.admin_users = v.split(',').map(|u| u.trim().to_string()).filter(|u| !u.is_empty()).collect();
+        }
+        if let Some(v) = env_parse("INVITE_EXPIRY_HOURS") { self.invite_expiry_hours = v; }
+        if let Some(v) = env_parse("MAX_GROUP_SIZE") { self.max_group_size = v; }
+        if let Some(v) = env_parse("MAX_GROUPS_PER_USER") { self.max_groups_per_user = v; }
+        if let Some(v) = env_parse("DB_CONNECT_RETRIES") { self.db_connect_retries = v; }
+        if let Some(v) = env_parse("DB_CONNECT_RETRY_DELAY_MS") { self.db_connect_retry_delay_ms = v; }
+        if let Some(v) = env_parse("DB_POOL_MAX") { self.db_pool_max_connections = v; }
+        if let Some(v) = env_parse("DB_POOL_MIN") { self.db_pool_min_connections = v; }
+        if let Some(v) = env_bool("ENABLE_REDIS") { self.enable_redis = v; }
+        if let Ok(v) = env::var("REDIS_URL") { self.redis_url = v; }
+        if let Some(v) = env_parse("HEARTBEAT_INTERVAL_SECS") { self.heartbeat_interval_secs = v; }
+        if let Some(v) = env_parse("HEARTBEAT_TIMEOUT_SECS") { self.heartbeat_timeout_secs = v; }
+        if let Some(v) = env_parse("MAX_PENDING_MESSAGES_PER_USER") { self.max_pending_messages_per_user = v; }
+        if let Ok(v) = env::var("FILE_STORAGE_DIR") { self.file_storage_dir = v; }
+        if let Some(v) = env_parse("MAX_FILE_SIZE_BYTES") { self.max_file_size_bytes = v; }
+        if let Some(v) = env_parse("MAX_FILE_CHUNKS") { self.max_file_chunks = v; }
+        if let Some(v) = env_parse("MAX_WS_CONNECTIONS_PER_USER") { self.max_ws_connections_per_user = v; }
+        self
+    }
+
+    /// Commented TOML file holding the default value of every setting, printed by `--print-config`
+    ///
+    /// ```
+    /// use ruggine_modulare::server::config::ServerConfig;
+    ///
+    /// let parsed: ServerConfig = toml::from_str(&ServerConfig::template()).unwrap();
+    /// assert_eq!(parsed.port, ServerConfig::default().port);
+    /// assert_eq!(parsed.redis_url, ServerConfig::default().redis_url);
+    /// ```
+    pub fn template() -> String {
+        let d = Self::default();
+        let cipher_suite = match d.cipher_suite {
+            CipherSuite::Aes256Gcm => "aes256gcm",
+            CipherSuite::ChaCha20Poly1305 => "chacha20poly1305",
+        };
+        format!(
+            r#"# ruggine-server configuration, load it with `ruggine-server --config <path>`
+# Environment variables (and .env) take precedence over the values below.
+# ENCRYPTION_MASTER_KEY and TLS_CERT_PATH/TLS_KEY_PATH are only read from the environment.
+
+# Address and port of the TCP command server (SERVER_HOST, SERVER_PORT)
+host = {:?}
+port = {}
+# WebSocket port on the same host, wss:// when TLS is configured (WS_PORT)
+ws_port = {}
+# SQLite database (DATABASE_URL)
+database_url = {:?}
+# Attempts (and delay between them) to open/migrate the database while it is locked
+db_connect_retries = {}
+db_connect_retry_delay_ms = {}
+# Bounds of the connection pool (DB_POOL_MAX, DB_POOL_MIN)
+db_pool_max_connections = {}
+db_pool_min_connections = {}
+max_clients = {}
+# TLS for client connections (ENABLE_ENCRYPTION)
+enable_encryption = {}
+log_level = {:?}
+session_expiry_days = {}
+# Session tokens cached in memory, 0 disables the cache (SESSION_CACHE_SIZE)
+session_cache_size = {}
+argon2_salt_length = {}
+max_message_length = {}
+# Cipher for stored messages: "aes256gcm" or "chacha20poly1305" (CIPHER_SUITE)
+cipher_suite = {:?}
+max_connection_attempts_per_minute = {}
+blacklist_duration_secs = {}
+# Log every command to the audit log (AUDIT_LOG)
+enable_audit_log = {}
+# Usernames allowed to run /admin_* commands (ADMIN_USERS, comma-separated)
+admin_users = {:?}
+invite_expiry_hours = {}
+# Members a group may hold, owner included
+max_group_size = {}
+# Groups a user may belong to, whether created or joined
+max_groups_per_user = {}
+# Coordinate WebSocket delivery across instances; without Redis delivery is local only
+enable_redis = {}
+redis_url = {:?}
+# WebSocket keep-alive: ping every interval, drop the connection without a pong within the timeout
+# (HEARTBEAT_INTERVAL_SECS, HEARTBEAT_TIMEOUT_SECS)
+heartbeat_interval_secs = {}
+heartbeat_timeout_secs = {}
+# WebSocket messages queued for an offline user, oldest dropped first (MAX_PENDING_MESSAGES_PER_USER)
+max_pending_messages_per_user = {}
+# Files sent over WebSocket (FILE_STORAGE_DIR)
+file_storage_dir = {:?}
+# Largest file and most chunks per upload; bigger uploads are abandoned (MAX_FILE_SIZE_BYTES, MAX_FILE_CHUNKS)
+max_file_size_bytes = {}
+max_file_chunks = {}
+# WebSocket connections a user may keep open at once (MAX_WS_CONNECTIONS_PER_USER)
+max_ws_connections_per_user = {}
+"#,
+            d.host, d.port, d.ws_port, d.database_url, d.db_connect_retries, d.db_connect_retry_delay_ms,
+            d.db_pool_max_connections, d.db_pool_min_connections,
+            d.max_clients, d.enable_encryption, d.log_level, d.session_expiry_days, d.session_cache_size,
+            d.argon2_salt_length, d.max_message_length, cipher_suite, d.max_connection_attempts_per_minute,
+            d.blacklist_duration_secs, d.enable_audit_log, d.admin_users, d.invite_expiry_hours,
+            d.max_group_size, d.max_groups_per_user, d.enable_redis, d.redis_url,
+            d.heartbeat_interval_secs, d.heartbeat_timeout_secs, d.max_pending_messages_per_user,
+            d.file_storage_dir, d.max_file_size_bytes, d.max_file_chunks, d.max_ws_connections_per_user,
+        )
     }
 }
 
@@ -57,17 +321,25 @@ pub struct ClientConfig {
     pub public_host: String,
     pub websocket_host: String,
     pub websocket_port: u16,
+    /// Connect to the WebSocket server with wss:// instead of ws://
+    pub enable_tls: bool,
+    /// Seal private messages end-to-end when both parties registered a public key
+    pub enable_encryption: bool,
 }
 
 impl ClientConfig {
     pub fn from_env() -> Self {
         dotenvy::dotenv().ok();
+        let default_host = env::var("CLIENT_DEFAULT_HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
         Self {
-            default_host: env::var("CLIENT_DEFAULT_HOST").unwrap_or_else(|_| "127.0.0.1".to_string()),
             default_port: env::var("CLIENT_DEFAULT_PORT").ok().and_then(|p| p.parse().ok()).unwrap_or(5000),
             public_host: env::var("CLIENT_PUBLIC_HOST").unwrap_or_else(|_| "remote.example.com".to_string()),
-            websocket_host: env::var("WEBSOCKET_HOST").unwrap_or_else(|_| "127.0.0.1".to_string()),
+            // The WebSocket endpoint lives next to the TCP command port unless configured elsewhere
+            websocket_host: env::var("WEBSOCKET_HOST").unwrap_or_else(|_| default_host.clone()),
+            default_host,
             websocket_port: env::var("WEBSOCKET_PORT").ok().and_then(|p| p.parse().ok()).unwrap_or(5001),
+            enable_tls: env::var("CLIENT_ENABLE_TLS").map(|v| v == "true" || v == "1").unwrap_or(false),
+            enable_encryption: env::var("CLIENT_ENABLE_ENCRYPTION").map(|v| v == "true" || v == "1").unwrap_or(false),
         }
     }
 }
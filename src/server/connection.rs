@@ -1,7 +1,11 @@
-use crate::server::{database::Database, auth, users, groups, messages, presence::PresenceRegistry, websocket::ChatWebSocketManager};
+use crate::server::{database::Database, auth, admin, users, groups, messages, reactions, presence::PresenceRegistry, websocket::{ChatWebSocketManager, MessageType, WebSocketMessage}};
 use sqlx::Row;
 use crate::server::config::ServerConfig;
 use std::sync::Arc;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
 use tokio::net::{TcpListener, TcpStream};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader, BufWriter};
 use std::fs::File;
@@ -12,16 +16,20 @@ use tokio_rustls::TlsAcceptor;
 use rustls::{ServerConfig as RustlsConfig};
 use rustls_pemfile::{certs, rsa_private_keys, pkcs8_private_keys};
 
+/// Maximum number of results returned by /search_groups
+const SEARCH_GROUPS_LIMIT: i64 = 20;
+
 pub struct Server {
     pub db: Arc<Database>,
     pub config: ServerConfig,
     pub presence: PresenceRegistry,
     pub ws_manager: Option<Arc<ChatWebSocketManager>>,
+    pub peer: Option<std::net::SocketAddr>,
 }
 
 impl Server {
-    /// Configure TLS acceptor from environment variables
-    fn setup_tls_acceptor(&self) -> anyhow::Result<Option<TlsAcceptor>> {
+    /// Configure TLS acceptor from environment variables; `None` when encryption is disabled
+    pub fn setup_tls_acceptor(&self) -> anyhow::Result<Option<TlsAcceptor>> {
         if !self.config.enable_encryption {
             println!("[TLS] TLS disabled in configuration");
             return Ok(None);
@@ -40,7 +48,7 @@ impl Server {
         let mut cert_reader = StdBufReader::new(cert_file);
         let cert_chain = certs(&mut cert_reader)?
             .into_iter()
-            .map(|v| rustls::Certificate(v))
+            .map(rustls::Certificate)
             .collect::<Vec<_>>();
 
         if cert_chain.is_empty() {
@@ -97,25 +105,64 @@ impl Server {
             }
         };
 
+        // Rate limiting per IP: tentativi di connessione nell'ultimo minuto e IP temporaneamente bloccati
+        let connection_attempts: Arc<Mutex<HashMap<IpAddr, VecDeque<Instant>>>> = Arc::new(Mutex::new(HashMap::new()));
+        let blacklist: Arc<Mutex<HashSet<IpAddr>>> = Arc::new(Mutex::new(HashSet::new()));
+
         loop {
             let (stream, peer) = listener.accept().await?;
+            let ip = peer.ip();
+            if blacklist.lock().await.contains(&ip) {
+                println!("[SERVER] Rejected connection from blacklisted {}", peer);
+                drop(stream);
+                continue;
+            }
+            {
+                let mut attempts = connection_attempts.lock().await;
+                let now = Instant::now();
+                // Pota i tentativi oltre il minuto per tutti gli IP e rimuove quelli rimasti vuoti,
+                // così la mappa contiene solo i peer attivi nell'ultimo minuto
+                attempts.retain(|_, deque| {
+                    while deque.front().is_some_and(|t| now.duration_since(*t) > Duration::from_secs(60)) {
+                        deque.pop_front();
+                    }
+                    !deque.is_empty()
+                });
+                let deque = attempts.entry(ip).or_default();
+                deque.push_back(now);
+                if deque.len() > self.config.max_connection_attempts_per_minute {
+                    attempts.remove(&ip);
+                    blacklist.lock().await.insert(ip);
+                    log::warn!("[SERVER] Blacklisting {} for {}s: too many connection attempts", ip, self.config.blacklist_duration_secs);
+                    let blacklist = blacklist.clone();
+                    let duration = Duration::from_secs(self.config.blacklist_duration_secs);
+                    tokio::spawn(async move {
+                        tokio::time::sleep(duration).await;
+                        blacklist.lock().await.remove(&ip);
+                        log::warn!("[SERVER] Removed {} from blacklist", ip);
+                    });
+                    drop(stream);
+                    continue;
+                }
+            }
             println!("[SERVER] New connection from {}", peer);
             let db = self.db.clone();
             let config = self.config.clone();
             let acceptor = tls_acceptor.clone();
             let presence = self.presence.clone();
+            let ws_manager = self.ws_manager.clone();
             tokio::spawn(async move {
                 // If TLS is configured, try to accept TLS, otherwise use plain TCP
                 if let Some(acceptor) = acceptor {
                     match acceptor.accept(stream).await {
                         Ok(tls_stream) => {
-                                    if let Err(e) = handle_tls_client(db, config, tls_stream, peer, presence.clone()).await {
+                                    if let Err(e) = handle_tls_client(db, config, tls_stream, peer, presence.clone(), ws_manager).await {
                                         println!("[SERVER] Client error (tls {}) : {}", peer, e);
                                     }
                         }
                         Err(e) => println!("[SERVER] TLS accept failed: {}", e),
                     }
-                } else if let Err(e) = handle_client(db, config, stream, peer, presence.clone()).await {
+                } else if let Err(e) = handle_client(db, config, stream, peer, presence.clone(), ws_manager).await {
                     println!("[SERVER] Client error ({}): {}", peer, e);
                 }
             });
@@ -124,6 +171,71 @@ impl Server {
 
     pub async fn handle_command(&self, cmd: &str, args: &[&str]) -> String {
         println!("[SERVER] Received command: {} {:?}", cmd, args);
+        let response = self.dispatch_command(cmd, args).await;
+        if self.config.enable_audit_log {
+            self.audit_command(cmd, args, &response).await;
+        }
+        response
+    }
+
+    /// Store the command in command_log, without passwords or session tokens
+    async fn audit_command(&self, cmd: &str, args: &[&str], response: &str) {
+        let user_id = match args.first() {
+            Some(token) => sqlx::query("SELECT user_id FROM sessions WHERE session_token = ?")
+                .bind(*token)
+                .fetch_optional(&self.db.pool)
+                .await
+                .ok()
+                .flatten()
+                .map(|row| row.get::<String, _>("user_id")),
+            None => None,
+        };
+        let args_redacted: Vec<&str> = args.iter().enumerate().map(|(i, arg)| {
+            match cmd {
+                "/login" | "/register" if i == 1 => "[REDACTED]",
+                "/change_password" | "/delete_account" if i >= 1 => "[REDACTED]",
+                _ if i == 0 && user_id.is_some() => "[TOKEN]",
+                _ => arg,
+            }
+        }).collect();
+        let result_prefix: String = response.chars().take(50).collect();
+        let res = sqlx::query("INSERT INTO command_log (user_id, command, args_redacted, result_prefix, executed_at, ip) VALUES (?, ?, ?, ?, ?, ?)")
+            .bind(&user_id)
+            .bind(cmd)
+            .bind(args_redacted.join(" "))
+            .bind(&result_prefix)
+            .bind(chrono::Utc::now().timestamp())
+            .bind(self.peer.map(|p| p.ip().to_string()))
+            .execute(&self.db.pool)
+            .await;
+        if let Err(e) = res {
+            println!("[AUDIT] Failed to store command {}: {}", cmd, e);
+        }
+    }
+
+    /// Close the WebSocket of `username`; their TCP session, if any, stays open
+    async fn admin_disconnect_ws(&self, admin_id: &str, username: &str) -> String {
+        let Some(ws_manager) = &self.ws_manager else {
+            return "ERR: WebSocket server not running".to_string();
+        };
+        let user_id = match sqlx::query("SELECT id FROM users WHERE username = ?")
+            .bind(username)
+            .fetch_optional(&self.db.pool)
+            .await
+        {
+            Ok(Some(row)) => row.get::<String, _>("id"),
+            _ => return "ERR: User not found".to_string(),
+        };
+        match ws_manager.disconnect_user(&user_id).await {
+            Ok(()) => {
+                log::warn!("[ADMIN] Forced WebSocket disconnect of user {} by admin {}", user_id, admin_id);
+                format!("OK: WebSocket of {} closed", username)
+            }
+            Err(_) => "ERR: User has no active WebSocket connection".to_string(),
+        }
+    }
+
+    async fn dispatch_command(&self, cmd: &str, args: &[&str]) -> String {
         match cmd {
             // FRIENDSHIP SYSTEM
             "/send_friend_request" if args.len() >= 2 => {
@@ -154,6 +266,49 @@ impl Server {
                     "ERR: Invalid or expired session".to_string()
                 }
             }
+            "/block_user" if args.len() == 2 => {
+                let session_token = args[0];
+                let username = args[1];
+                if let Some(uid) = auth::validate_session(self.db.clone(), session_token).await {
+                    users::block_user(self.db.clone(), &uid, username).await
+                } else {
+                    "ERR: Invalid or expired session".to_string()
+                }
+            }
+            "/unblock_user" if args.len() == 2 => {
+                let session_token = args[0];
+                let username = args[1];
+                if let Some(uid) = auth::validate_session(self.db.clone(), session_token).await {
+                    users::unblock_user(self.db.clone(), &uid, username).await
+                } else {
+                    "ERR: Invalid or expired session".to_string()
+                }
+            }
+            // /register_public_key is the name older clients still send
+            "/publish_key" | "/register_public_key" if args.len() == 2 => {
+                if let Some(uid) = auth::validate_session(self.db.clone(), args[0]).await {
+                    users::register_public_key(self.db.clone(), &uid, args[1]).await
+                } else {
+                    "ERR: Invalid or expired session".to_string()
+                }
+            }
+            "/get_public_key" if args.len() == 2 => {
+                if auth::validate_session(self.db.clone(), args[0]).await.is_some() {
+                    users::get_public_key(self.db.clone(), args[1]).await
+                } else {
+                    "ERR: Invalid or expired session".to_string()
+                }
+            }
+            // Public keys are public: no session needed to look one up
+            "/get_public_key" if args.len() == 1 => {
+                users::get_public_key(self.db.clone(), args[0]).await
+            }
+            "HELLO" if args.len() == 1 => {
+                crate::common::protocol::hello_response(args[0])
+            }
+            "/list_blocked" if args.len() == 1 => {
+                users::list_blocked(self.db.clone(), args[0]).await
+            }
             "/list_friends" if args.len() == 1 => {
                 let session_token = args[0];
                 if let Some(uid) = auth::validate_session(self.db.clone(), session_token).await {
@@ -162,6 +317,72 @@ impl Server {
                     "ERR: Invalid or expired session".to_string()
                 }
             }
+            "/get_profile" if args.len() == 1 || args.len() == 2 => {
+                let session_token = args[0];
+                if let Some(uid) = auth::validate_session(self.db.clone(), session_token).await {
+                    users::get_profile(self.db.clone(), &uid, args.get(1).copied()).await
+                } else {
+                    "ERR: Invalid or expired session".to_string()
+                }
+            }
+            "/set_avatar_url" if args.len() == 2 => {
+                let session_token = args[0];
+                if let Some(uid) = auth::validate_session(self.db.clone(), session_token).await {
+                    let url = if args[1] == "none" { None } else { Some(args[1]) };
+                    users::set_avatar_url(self.db.clone(), &uid, url).await
+                } else {
+                    "ERR: Invalid or expired session".to_string()
+                }
+            }
+            "/get_last_seen" if args.len() == 2 => {
+                if auth::validate_session(self.db.clone(), args[0]).await.is_some() {
+                    users::get_last_seen(self.db.clone(), args[1]).await
+                } else {
+                    "ERR: Invalid or expired session".to_string()
+                }
+            }
+            "/set_status" if !args.is_empty() => {
+                if let Some(uid) = auth::validate_session(self.db.clone(), args[0]).await {
+                    let (reply, events) = users::set_status(self.db.clone(), &uid, &args[1..].join(" ")).await;
+                    if let Some(ws_manager) = &self.ws_manager {
+                        ws_manager.publish_notifications(events).await;
+                    }
+                    reply
+                } else {
+                    "ERR: Invalid or expired session".to_string()
+                }
+            }
+            "/subscribe_presence" if args.len() == 2 => {
+                let Some(uid) = auth::validate_session(self.db.clone(), args[0]).await else {
+                    return "ERR: Invalid or expired session".to_string();
+                };
+                let Some(ws_manager) = &self.ws_manager else {
+                    return "ERR: Real-time notifications unavailable".to_string();
+                };
+                match users::presence_targets(&self.db, args[1]).await {
+                    Ok(user_ids) => {
+                        let count = user_ids.len();
+                        ws_manager.subscribe_presence(&uid, user_ids).await;
+                        format!("OK: Subscribed to presence of {} users", count)
+                    }
+                    Err(e) => e,
+                }
+            }
+            "/get_status" if args.len() == 2 => {
+                if auth::validate_session(self.db.clone(), args[0]).await.is_some() {
+                    users::get_status(self.db.clone(), args[1]).await
+                } else {
+                    "ERR: Invalid or expired session".to_string()
+                }
+            }
+            "/friends_status" if args.len() == 1 => {
+                let session_token = args[0];
+                if let Some(uid) = auth::validate_session(self.db.clone(), session_token).await {
+                    users::friends_status(self.db.clone(), &uid).await
+                } else {
+                    "ERR: Invalid or expired session".to_string()
+                }
+            }
             "/received_friend_requests" if args.len() == 1 => {
                 let session_token = args[0];
                 if let Some(uid) = auth::validate_session(self.db.clone(), session_token).await {
@@ -194,7 +415,7 @@ impl Server {
                     
                     // Disconnect WebSocket connections for this user BEFORE logout
                     if let Some(ws_manager) = &self.ws_manager {
-                        ws_manager.disconnect_user(&uid).await;
+                        let _ = ws_manager.disconnect_user(&uid).await;
                     }
                     
                     let res = auth::logout(self.db.clone(), token).await;
@@ -216,6 +437,10 @@ impl Server {
                     println!("[AUTH][DB CHECK] after logout: sessions_count={} users.is_online={} for user {}", sess_cnt, is_online, uid);
                     let kicked = self.presence.kick_all(&uid).await;
                     println!("[AUTH] Logout triggered kick for user {} (kicked={})", uid, kicked);
+                    if let Some(ws_manager) = &self.ws_manager {
+                        ws_manager.unsubscribe_presence(&uid).await;
+                        ws_manager.notify_presence(&self.db, &uid, false).await;
+                    }
                     res
                 } else {
                     // session not valid/expired, still call logout for consistent response
@@ -247,8 +472,45 @@ impl Server {
             "/register" if args.len() == 2 => {
                 auth::register(self.db.clone(), args[0], args[1], &self.config).await
             }
-            "/login" if args.len() == 2 => {
-                auth::login(self.db.clone(), args[0], args[1], &self.config).await
+            "/login" if args.len() == 2 || args.len() == 3 => {
+                auth::login(self.db.clone(), args[0], args[1], args.get(2).copied(), &self.config).await
+            }
+            "/list_sessions" if args.len() == 1 => {
+                if let Some(uid) = auth::validate_session(self.db.clone(), args[0]).await {
+                    auth::list_sessions(self.db.clone(), &uid).await
+                } else {
+                    "ERR: Invalid or expired session".to_string()
+                }
+            }
+            "/revoke_session" if args.len() == 2 => {
+                if let Some(uid) = auth::validate_session(self.db.clone(), args[0]).await {
+                    auth::revoke_session(self.db.clone(), &uid, args[1]).await
+                } else {
+                    "ERR: Invalid or expired session".to_string()
+                }
+            }
+            "/change_password" if args.len() == 3 => {
+                let session_token = args[0];
+                if let Some(uid) = auth::validate_session(self.db.clone(), session_token).await {
+                    auth::change_password(self.db.clone(), &uid, session_token, args[1], args[2], &self.config).await
+                } else {
+                    "ERR: Invalid or expired session".to_string()
+                }
+            }
+            "/delete_account" if args.len() == 2 => {
+                if let Some(uid) = auth::validate_session(self.db.clone(), args[0]).await {
+                    let res = auth::delete_account(self.db.clone(), &uid, args[1]).await;
+                    if res.starts_with("OK:") {
+                        // The account is gone: close whatever is still connected as it
+                        if let Some(ws_manager) = &self.ws_manager {
+                            let _ = ws_manager.disconnect_user(&uid).await;
+                        }
+                        self.presence.kick_all(&uid).await;
+                    }
+                    res
+                } else {
+                    "ERR: Invalid or expired session".to_string()
+                }
             }
             "/online_users" if args.len() == 1 => {
                 let session_token = args[0];
@@ -256,14 +518,16 @@ impl Server {
             }
             "/all_users" => {
                 let exclude = None;
-                users::list_all(self.db.clone(), exclude).await
+                users::list_all(self.db.clone(), exclude, args.first().copied()).await
             }
             "/create_group" if args.len() >= 2 => {
                 let session_token = args[0];
                 let group_name = args[1];
-                let participants = if args.len() > 2 { Some(args[2]) } else { None };
+                let participants: Vec<&str> = args.get(2).map(|p| p.split(',').collect()).unwrap_or_default();
+                // Everything after the participants is the optional description
+                let description = args.get(3..).map(|words| words.join(" ")).unwrap_or_default();
                 if let Some(uid) = auth::validate_session(self.db.clone(), session_token).await {
-                    groups::create_group_with_participants(self.db.clone(), &uid, group_name, participants).await
+                    groups::create_group_with_members(self.db.clone(), &uid, group_name, &participants, &description, &self.config).await
                 } else {
                     "ERR: Invalid or expired session".to_string()
                 }
@@ -281,7 +545,7 @@ impl Server {
                 let username = args[1];
                 let group_id = args[2];
                 if let Some(uid) = auth::validate_session(self.db.clone(), session_token).await {
-                    groups::invite_user_to_group(self.db.clone(), &uid, username, group_id).await
+                    groups::invite_user_to_group(self.db.clone(), &uid, username, group_id, &self.config).await
                 } else {
                     "ERR: Invalid or expired session".to_string()
                 }
@@ -290,7 +554,7 @@ impl Server {
                 let session_token = args[0];
                 let invite_id = args[1];
                 if let Some(uid) = auth::validate_session(self.db.clone(), session_token).await {
-                    groups::accept_invite(self.db.clone(), &uid, invite_id).await
+                    groups::accept_group_invite(self.db.clone(), &uid, invite_id, &self.config).await
                 } else {
                     "ERR: Invalid or expired session".to_string()
                 }
@@ -299,7 +563,7 @@ impl Server {
                 let session_token = args[0];
                 let invite_id = args[1];
                 if let Some(uid) = auth::validate_session(self.db.clone(), session_token).await {
-                    groups::reject_invite(self.db.clone(), &uid, invite_id).await
+                    groups::reject_group_invite(self.db.clone(), &uid, invite_id).await
                 } else {
                     "ERR: Invalid or expired session".to_string()
                 }
@@ -307,7 +571,7 @@ impl Server {
             "/my_group_invites" if args.len() == 1 => {
                 let session_token = args[0];
                 if let Some(uid) = auth::validate_session(self.db.clone(), session_token).await {
-                    groups::my_invites(self.db.clone(), &uid).await
+                    groups::my_group_invites(self.db.clone(), &uid).await
                 } else {
                     "ERR: Invalid or expired session".to_string()
                 }
@@ -315,8 +579,16 @@ impl Server {
             "/group_members" if args.len() == 2 => {
                 let session_token = args[0];
                 let group_id = args[1];
-                if let Some(_uid) = auth::validate_session(self.db.clone(), session_token).await {
-                    groups::get_group_members(self.db.clone(), group_id).await
+                if let Some(uid) = auth::validate_session(self.db.clone(), session_token).await {
+                    groups::list_members(self.db.clone(), &uid, group_id).await
+                } else {
+                    "ERR: Invalid or expired session".to_string()
+                }
+            }
+            "/group_members_status" if args.len() == 2 => {
+                let session_token = args[0];
+                if let Some(uid) = auth::validate_session(self.db.clone(), session_token).await {
+                    groups::group_members_status(self.db.clone(), &uid, args[1]).await
                 } else {
                     "ERR: Invalid or expired session".to_string()
                 }
@@ -324,7 +596,135 @@ impl Server {
             "/join_group" if args.len() == 2 => {
                 let session_token = args[0];
                 if let Some(uid) = auth::validate_session(self.db.clone(), session_token).await {
-                    groups::join_group(self.db.clone(), &uid, args[1]).await
+                    groups::join_group(self.db.clone(), &uid, args[1], &self.config).await
+                } else {
+                    "ERR: Invalid or expired session".to_string()
+                }
+            }
+            "/list_public_groups" if !args.is_empty() => {
+                // A leading number picks the page; everything else is the search text
+                let (page, query) = match args.get(1).and_then(|p| p.parse::<i64>().ok()) {
+                    Some(page) => (page, args[2..].join(" ")),
+                    None => (1, args[1..].join(" ")),
+                };
+                if let Some(uid) = auth::validate_session(self.db.clone(), args[0]).await {
+                    groups::list_public_groups(self.db.clone(), &uid, &query, page).await
+                } else {
+                    "ERR: Invalid or expired session".to_string()
+                }
+            }
+            "/generate_invite_code" if (2..=4).contains(&args.len()) => {
+                let expires_secs = args.get(2).map(|a| a.parse::<i64>());
+                let max_uses = args.get(3).map(|a| a.parse::<i64>());
+                if expires_secs.as_ref().is_some_and(|r| r.is_err()) || max_uses.as_ref().is_some_and(|r| r.is_err()) {
+                    return "ERR: Usage: /generate_invite_code <group_id> [expires_secs] [max_uses]".to_string();
+                }
+                if let Some(uid) = auth::validate_session(self.db.clone(), args[0]).await {
+                    groups::generate_invite_code(self.db.clone(), &uid, args[1], expires_secs.and_then(Result::ok), max_uses.and_then(Result::ok)).await
+                } else {
+                    "ERR: Invalid or expired session".to_string()
+                }
+            }
+            "/join_by_code" if args.len() == 2 => {
+                if let Some(uid) = auth::validate_session(self.db.clone(), args[0]).await {
+                    groups::join_by_code(self.db.clone(), &uid, args[1], &self.config).await
+                } else {
+                    "ERR: Invalid or expired session".to_string()
+                }
+            }
+            "/search_groups" if args.len() >= 2 => {
+                groups::search_groups(self.db.clone(), args[0], &args[1..].join(" "), SEARCH_GROUPS_LIMIT).await
+            }
+            "/set_group_public" if args.len() == 3 => {
+                let session_token = args[0];
+                if let Some(uid) = auth::validate_session(self.db.clone(), session_token).await {
+                    match args[2] {
+                        "on" => groups::set_group_public(self.db.clone(), &uid, args[1], true).await,
+                        "off" => groups::set_group_public(self.db.clone(), &uid, args[1], false).await,
+                        _ => "ERR: Usage: /set_group_public <group_id> <on|off>".to_string(),
+                    }
+                } else {
+                    "ERR: Invalid or expired session".to_string()
+                }
+            }
+            "/rename_group" if args.len() == 3 => {
+                if let Some(uid) = auth::validate_session(self.db.clone(), args[0]).await {
+                    let (result, events) = groups::rename_group(self.db.clone(), &uid, args[1], args[2]).await;
+                    if let Some(ws_manager) = &self.ws_manager {
+                        ws_manager.publish_notifications(events).await;
+                    }
+                    result
+                } else {
+                    "ERR: Invalid or expired session".to_string()
+                }
+            }
+            "/delete_group" if args.len() == 2 => {
+                if let Some(uid) = auth::validate_session(self.db.clone(), args[0]).await {
+                    let (result, events) = groups::delete_group(self.db.clone(), &uid, args[1]).await;
+                    if let Some(ws_manager) = &self.ws_manager {
+                        ws_manager.publish_notifications(events).await;
+                    }
+                    result
+                } else {
+                    "ERR: Invalid or expired session".to_string()
+                }
+            }
+            "/kick_from_group" if args.len() == 3 => {
+                if let Some(uid) = auth::validate_session(self.db.clone(), args[0]).await {
+                    let (result, events) = groups::kick_member(self.db.clone(), &uid, args[1], args[2]).await;
+                    if let Some(ws_manager) = &self.ws_manager {
+                        ws_manager.publish_notifications(events).await;
+                    }
+                    result
+                } else {
+                    "ERR: Invalid or expired session".to_string()
+                }
+            }
+            "/set_group_description" if args.len() >= 2 => {
+                if let Some(uid) = auth::validate_session(self.db.clone(), args[0]).await {
+                    groups::set_group_description(self.db.clone(), &uid, args[1], &args[2..].join(" ")).await
+                } else {
+                    "ERR: Invalid or expired session".to_string()
+                }
+            }
+            "/pin_message" | "/unpin_message" if args.len() == 3 => {
+                let Ok(message_id) = args[2].parse::<i64>() else {
+                    return "ERR: Invalid message id".to_string();
+                };
+                if let Some(uid) = auth::validate_session(self.db.clone(), args[0]).await {
+                    let (result, events) = if cmd == "/pin_message" {
+                        groups::pin_message(self.db.clone(), &uid, args[1], message_id).await
+                    } else {
+                        groups::unpin_message(self.db.clone(), &uid, args[1], message_id).await
+                    };
+                    if let Some(ws_manager) = &self.ws_manager {
+                        ws_manager.publish_notifications(events).await;
+                    }
+                    result
+                } else {
+                    "ERR: Invalid or expired session".to_string()
+                }
+            }
+            "/get_pinned_messages" if args.len() == 2 => {
+                messages::get_pinned_messages(self.db.clone(), args[0], args[1], &self.config).await
+            }
+            "/get_group_description" if args.len() == 2 => {
+                if let Some(uid) = auth::validate_session(self.db.clone(), args[0]).await {
+                    groups::get_group_description(self.db.clone(), &uid, args[1]).await
+                } else {
+                    "ERR: Invalid or expired session".to_string()
+                }
+            }
+            "/set_group_admin" if args.len() == 3 => {
+                if let Some(uid) = auth::validate_session(self.db.clone(), args[0]).await {
+                    groups::set_group_admin(self.db.clone(), &uid, args[1], args[2]).await
+                } else {
+                    "ERR: Invalid or expired session".to_string()
+                }
+            }
+            "/demote_group_admin" if args.len() == 3 => {
+                if let Some(uid) = auth::validate_session(self.db.clone(), args[0]).await {
+                    groups::demote_group_admin(self.db.clone(), &uid, args[1], args[2]).await
                 } else {
                     "ERR: Invalid or expired session".to_string()
                 }
@@ -342,24 +742,117 @@ impl Server {
                 let session_token = args[0];
                 let group_name = args[1];
                 let message = &args[2..].join(" ");
-                messages::send_group_message(self.db.clone(), session_token, group_name, message, &self.config).await
+                let result = messages::send_group_message(self.db.clone(), session_token, group_name, message, messages::MessageLinks::default(), &self.config).await;
+                if result.starts_with("OK:") {
+                    if let (Some(ws_manager), Some(uid)) = (&self.ws_manager, auth::validate_session(self.db.clone(), session_token).await) {
+                        ws_manager.publish_notifications(messages::mention_notifications(&self.db, group_name, &uid, message).await).await;
+                    }
+                }
+                result
             }
             "/send_private_message"  if args.len() >= 3 => {
                 let session_token = args[0];
                 let to_username = args[1];
                 let message = &args[2..].join(" ");
-                messages::send_private_message(self.db.clone(), session_token, to_username, message, &self.config).await
+                messages::send_private_message(self.db.clone(), session_token, to_username, message, messages::MessageLinks::default(), &self.config).await
+            }
+            // <chat_id> is private:<username> or group:<group_id>, as in search results
+            "/send_reply" if args.len() >= 4 => {
+                let session_token = args[0];
+                let Ok(reply_to_id) = args[2].parse::<i64>() else {
+                    return "ERR: Invalid message id".to_string();
+                };
+                let message = &args[3..].join(" ");
+                match args[1].split_once(':') {
+                    Some(("private", to_username)) => {
+                        messages::send_private_message(self.db.clone(), session_token, to_username, message, messages::MessageLinks::reply_to(Some(reply_to_id)), &self.config).await
+                    }
+                    Some(("group", group_id)) => {
+                        let result = messages::send_group_message(self.db.clone(), session_token, group_id, message, messages::MessageLinks::reply_to(Some(reply_to_id)), &self.config).await;
+                        if result.starts_with("OK:") {
+                            if let (Some(ws_manager), Some(uid)) = (&self.ws_manager, auth::validate_session(self.db.clone(), session_token).await) {
+                                ws_manager.publish_notifications(messages::mention_notifications(&self.db, group_id, &uid, message).await).await;
+                            }
+                        }
+                        result
+                    }
+                    _ => "ERR: Unknown chat (use private:<username> or group:<group_id>)".to_string(),
+                }
+            }
+            "/forward_message" if args.len() == 3 => {
+                match args[1].parse::<i64>() {
+                    Ok(message_id) => messages::forward_message(self.db.clone(), args[0], message_id, args[2], &self.config).await,
+                    Err(_) => "ERR: Invalid message id".to_string(),
+                }
             }
             "/get_group_messages" if args.len() == 2 => {
                 let session_token = args[0];
                 let group_name = args[1];
-                messages::get_group_messages(self.db.clone(), session_token, group_name, &self.config).await
+                let (result, newly_seen) = messages::get_group_messages(self.db.clone(), session_token, group_name, &self.config).await;
+                if !newly_seen.is_empty() {
+                    if let (Some(ws_manager), Some(uid)) = (&self.ws_manager, auth::validate_session(self.db.clone(), session_token).await) {
+                        ws_manager.publish_notifications(messages::read_receipt_notifications(&self.db, group_name, &uid, &newly_seen).await).await;
+                    }
+                }
+                result
+            }
+            "/get_group_messages_page" if args.len() == 4 => {
+                let session_token = args[0];
+                let group_id = args[1];
+                let (before_ts, limit) = match (args[2].parse::<i64>(), args[3].parse::<i64>()) {
+                    (Ok(before_ts), Ok(limit)) => (before_ts, limit),
+                    (Err(_), _) => return "ERR: Invalid timestamp".to_string(),
+                    (_, Err(_)) => return "ERR: Invalid limit".to_string(),
+                };
+                let (result, newly_seen) = messages::get_group_messages_page(self.db.clone(), session_token, group_id, before_ts, limit, &self.config).await;
+                if !newly_seen.is_empty() {
+                    if let (Some(ws_manager), Some(uid)) = (&self.ws_manager, auth::validate_session(self.db.clone(), session_token).await) {
+                        ws_manager.publish_notifications(messages::read_receipt_notifications(&self.db, group_id, &uid, &newly_seen).await).await;
+                    }
+                }
+                result
+            }
+            "/get_receipts" if args.len() == 2 => {
+                let session_token = args[0];
+                if let Some(uid) = auth::validate_session(self.db.clone(), session_token).await {
+                    match args[1].parse::<i64>() {
+                        Ok(message_id) => messages::get_receipts(self.db.clone(), &uid, message_id).await,
+                        Err(_) => "ERR: Invalid message id".to_string(),
+                    }
+                } else {
+                    "ERR: Invalid or expired session".to_string()
+                }
             }
             "/get_private_messages" if args.len() == 2 => {
                 let session_token = args[0];
                 let other_username = args[1];
                 messages::get_private_messages(self.db.clone(), session_token, other_username, &self.config).await
             }
+            "/get_private_messages_since" if args.len() == 3 => {
+                let session_token = args[0];
+                let other_username = args[1];
+                match args[2].parse::<i64>() {
+                    Ok(since) => messages::get_private_messages_since(self.db.clone(), session_token, other_username, since, &self.config).await,
+                    Err(_) => "ERR: Invalid timestamp".to_string(),
+                }
+            }
+            "/get_private_messages_page" if args.len() == 4 => {
+                let session_token = args[0];
+                let other_username = args[1];
+                match (args[2].parse::<i64>(), args[3].parse::<i64>()) {
+                    (Ok(before_ts), Ok(limit)) => messages::get_private_messages_page(self.db.clone(), session_token, other_username, before_ts, limit, &self.config).await,
+                    (Err(_), _) => "ERR: Invalid timestamp".to_string(),
+                    (_, Err(_)) => "ERR: Invalid limit".to_string(),
+                }
+            }
+            "/export_chat" if args.len() == 3 => {
+                let session_token = args[0];
+                match args[1] {
+                    "private" => messages::export_private_chat(self.db.clone(), session_token, args[2], &self.config).await,
+                    "group" => messages::export_group_chat(self.db.clone(), session_token, args[2], &self.config).await,
+                    _ => "ERR: Unknown chat type (use private or group)".to_string(),
+                }
+            }
             "/delete_group_messages" if args.len() == 2 => {
                 let session_token = args[0];
                 let group_id = args[1];
@@ -370,12 +863,233 @@ impl Server {
                 let other_username = args[1];
                 messages::delete_private_messages(self.db.clone(), session_token, other_username).await
             }
+            "/delete_message" if args.len() == 2 => {
+                let session_token = args[0];
+                match args[1].parse::<i64>() {
+                    Ok(message_id) => messages::delete_single_message(self.db.clone(), session_token, message_id).await,
+                    Err(_) => "ERR: Invalid message id".to_string(),
+                }
+            }
+            "/react" if args.len() == 3 => {
+                match args[1].parse::<i64>() {
+                    Ok(message_id) => reactions::react(self.db.clone(), args[0], message_id, args[2]).await,
+                    Err(_) => "ERR: Invalid message id".to_string(),
+                }
+            }
+            "/unreact" if args.len() == 3 => {
+                match args[1].parse::<i64>() {
+                    Ok(message_id) => reactions::unreact(self.db.clone(), args[0], message_id, args[2]).await,
+                    Err(_) => "ERR: Invalid message id".to_string(),
+                }
+            }
+            "/get_reactions" if args.len() == 2 => {
+                match args[1].parse::<i64>() {
+                    Ok(message_id) => reactions::get_reactions(self.db.clone(), args[0], message_id).await,
+                    Err(_) => "ERR: Invalid message id".to_string(),
+                }
+            }
+            "/edit_message" if args.len() >= 3 => {
+                let session_token = args[0];
+                let new_content = &args[2..].join(" ");
+                match args[1].parse::<i64>() {
+                    Ok(message_id) => messages::edit_message(self.db.clone(), session_token, message_id, new_content, &self.config).await,
+                    Err(_) => "ERR: Invalid message id".to_string(),
+                }
+            }
+            "/mark_read" if args.len() == 4 => {
+                let Ok(timestamp) = args[3].parse::<i64>() else {
+                    return "ERR: Invalid timestamp".to_string();
+                };
+                let (result, events) = messages::mark_read(self.db.clone(), args[0], args[1], args[2], timestamp).await;
+                if let Some(ws_manager) = &self.ws_manager {
+                    ws_manager.publish_notifications(events).await;
+                }
+                result
+            }
+            "/get_read_receipts" if args.len() == 3 => {
+                messages::get_read_receipts(self.db.clone(), args[0], args[1], args[2]).await
+            }
+            "/search_messages" if args.len() >= 2 => {
+                // A trailing number is the result limit, the rest is the query
+                let (query_words, limit) = match args[args.len() - 1].parse::<i64>() {
+                    Ok(limit) if args.len() >= 3 => (&args[1..args.len() - 1], limit),
+                    _ => (&args[1..], messages::DEFAULT_SEARCH_RESULTS),
+                };
+                messages::search_messages(self.db.clone(), args[0], &query_words.join(" "), limit, &self.config).await
+            }
+            "/archive_chat" if args.len() == 3 => {
+                messages::archive_chat(self.db.clone(), args[0], args[1], args[2]).await
+            }
+            "/unarchive_chat" if args.len() == 3 => {
+                messages::unarchive_chat(self.db.clone(), args[0], args[1], args[2]).await
+            }
+            "/recent_chats" if args.len() == 2 => {
+                let limit: i64 = match args[1].parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => return "ERR: Invalid limit".to_string(),
+                };
+                messages::recent_chats(self.db.clone(), args[0], limit).await
+            }
+            "/list_archived" if args.len() == 1 => {
+                messages::list_archived_chats(self.db.clone(), args[0]).await
+            }
+            "/admin_delete_user" if args.len() == 2 => {
+                let session_token = args[0];
+                match auth::validate_session(self.db.clone(), session_token).await {
+                    Some(uid) if auth::is_admin(self.db.clone(), &self.config, &uid).await => {
+                        users::delete_user(self.db.clone(), args[1]).await
+                    }
+                    Some(_) => "ERR: Admin privileges required".to_string(),
+                    None => "ERR: Invalid or expired session".to_string(),
+                }
+            }
+            "/admin_disconnect_ws" if args.len() == 2 => {
+                let session_token = args[0];
+                match auth::validate_session(self.db.clone(), session_token).await {
+                    Some(admin_id) if auth::is_admin(self.db.clone(), &self.config, &admin_id).await => {
+                        self.admin_disconnect_ws(&admin_id, args[1]).await
+                    }
+                    Some(_) => "ERR: Admin privileges required".to_string(),
+                    None => "ERR: Invalid or expired session".to_string(),
+                }
+            }
+            "/admin_list_users" if args.len() == 3 => {
+                let session_token = args[0];
+                let (page, page_size) = match (args[1].parse::<i64>(), args[2].parse::<i64>()) {
+                    (Ok(page), Ok(page_size)) => (page, page_size),
+                    _ => return "ERR: Invalid page or page size".to_string(),
+                };
+                match auth::validate_session(self.db.clone(), session_token).await {
+                    Some(uid) if auth::is_admin(self.db.clone(), &self.config, &uid).await => {
+                        admin::list_users(self.db.clone(), page, page_size).await
+                    }
+                    Some(_) => "ERR: Admin privileges required".to_string(),
+                    None => "ERR: Invalid or expired session".to_string(),
+                }
+            }
+            "/admin_ban_user" | "/admin_unban_user" if args.len() == 2 => {
+                let session_token = args[0];
+                match auth::validate_session(self.db.clone(), session_token).await {
+                    Some(uid) if auth::is_admin(self.db.clone(), &self.config, &uid).await => {
+                        let banned = cmd == "/admin_ban_user";
+                        let result = admin::set_banned(self.db.clone(), args[1], banned).await;
+                        if banned && result.starts_with("OK:") {
+                            // Drop the live WebSocket too, if any
+                            let _ = self.admin_disconnect_ws(&uid, args[1]).await;
+                        }
+                        result
+                    }
+                    Some(_) => "ERR: Admin privileges required".to_string(),
+                    None => "ERR: Invalid or expired session".to_string(),
+                }
+            }
+            "/admin_db_stats" if args.len() == 1 => {
+                let session_token = args[0];
+                match auth::validate_session(self.db.clone(), session_token).await {
+                    Some(uid) if auth::is_admin(self.db.clone(), &self.config, &uid).await => {
+                        let redis_status = match &self.ws_manager {
+                            Some(ws_manager) if ws_manager.redis_enabled() => {
+                                if ws_manager.redis_health().await { "ok" } else { "down" }
+                            }
+                            _ => "disabled",
+                        };
+                        admin::db_stats(self.db.clone(), redis_status).await
+                    }
+                    Some(_) => "ERR: Admin privileges required".to_string(),
+                    None => "ERR: Invalid or expired session".to_string(),
+                }
+            }
+            "/broadcast" if args.len() >= 2 => {
+                // Same ADMIN_USERS check as the /admin_* commands
+                let admin_id = match auth::validate_session(self.db.clone(), args[0]).await {
+                    Some(uid) if auth::is_admin(self.db.clone(), &self.config, &uid).await => uid,
+                    Some(_) => return "ERR: Unauthorized".to_string(),
+                    None => return "ERR: Invalid or expired session".to_string(),
+                };
+                let message = args[1..].join(" ");
+                if message.len() > self.config.max_message_length {
+                    return format!("ERR: Message too long (max {} chars)", self.config.max_message_length);
+                }
+                if let Err(e) = admin::store_broadcast(self.db.clone(), &message).await {
+                    return e;
+                }
+                if let Some(ws_manager) = &self.ws_manager {
+                    let event = WebSocketMessage {
+                        id: uuid::Uuid::new_v4().to_string(),
+                        message_type: MessageType::System,
+                        sender: "system".to_string(),
+                        target: "broadcast".to_string(),
+                        content: serde_json::json!({ "type": "broadcast", "content": message }).to_string(),
+                        timestamp: chrono::Utc::now().timestamp(),
+                        recipient_user_id: None,
+                    };
+                    if let Err(e) = ws_manager.broadcast_message(event).await {
+                        println!("[SERVER] Failed to deliver broadcast: {}", e);
+                    }
+                }
+                log::warn!("[ADMIN] Broadcast sent by admin {}: {}", admin_id, message);
+                "OK: Broadcast sent".to_string()
+            }
+            "/admin_get_audit_log" if args.len() == 2 => {
+                let session_token = args[0];
+                let limit: i64 = match args[1].parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => return "ERR: Invalid limit".to_string(),
+                };
+                match auth::validate_session(self.db.clone(), session_token).await {
+                    Some(uid) if auth::is_admin(self.db.clone(), &self.config, &uid).await => {
+                        get_audit_log(self.db.clone(), limit).await
+                    }
+                    Some(_) => "ERR: Admin privileges required".to_string(),
+                    None => "ERR: Invalid or expired session".to_string(),
+                }
+            }
             _ => "ERR: Unknown or invalid command".to_string(),
         }
     }
 }
 
-async fn handle_client(db: Arc<Database>, config: ServerConfig, stream: TcpStream, peer: std::net::SocketAddr, presence: PresenceRegistry) -> anyhow::Result<()> {
+/// Most recent command_log entries, newest first
+async fn get_audit_log(db: Arc<Database>, limit: i64) -> String {
+    let rows = sqlx::query("SELECT user_id, command, args_redacted, result_prefix, executed_at, ip FROM command_log ORDER BY id DESC LIMIT ?")
+        .bind(limit)
+        .fetch_all(&db.pool)
+        .await;
+    match rows {
+        Ok(rows) => {
+            let entries: Vec<String> = rows.iter().map(|r| {
+                format!("[{}] {} {} {} {} -> {}",
+                    r.get::<i64, _>("executed_at"),
+                    r.get::<Option<String>, _>("ip").unwrap_or_else(|| "-".to_string()),
+                    r.get::<Option<String>, _>("user_id").unwrap_or_else(|| "-".to_string()),
+                    r.get::<String, _>("command"),
+                    r.get::<String, _>("args_redacted"),
+                    r.get::<String, _>("result_prefix").replace('\n', " "),
+                )
+            }).collect();
+            format!("OK: Audit log:\n{}", entries.join("\n"))
+        }
+        Err(e) => format!("ERR: DB error: {}", e),
+    }
+}
+
+/// Split a raw protocol line into the command and its whitespace-separated arguments.
+/// Blank lines yield `None`.
+///
+/// ```
+/// use ruggine_modulare::server::connection::parse_command_line;
+///
+/// assert_eq!(parse_command_line("/invite tok bob g1\r\n"), Some(("/invite", vec!["tok", "bob", "g1"])));
+/// assert_eq!(parse_command_line("/help"), Some(("/help", vec![])));
+/// assert_eq!(parse_command_line(" \t\u{3000}\n"), None);
+/// ```
+pub fn parse_command_line(line: &str) -> Option<(&str, Vec<&str>)> {
+    let mut parts = line.split_whitespace();
+    let cmd = parts.next()?;
+    Some((cmd, parts.collect()))
+}
+
+async fn handle_client(db: Arc<Database>, config: ServerConfig, stream: TcpStream, peer: std::net::SocketAddr, presence: PresenceRegistry, ws_manager: Option<Arc<ChatWebSocketManager>>) -> anyhow::Result<()> {
     let (reader, writer) = stream.into_split();
     let mut reader = BufReader::new(reader);
     let mut writer = BufWriter::new(writer);
@@ -411,15 +1125,11 @@ async fn handle_client(db: Arc<Database>, config: ServerConfig, stream: TcpStrea
                 break;
             }
         }
-    let trimmed = line.trim();
     // Raw incoming line logger for diagnostics
-    println!("[CONN:RAW] [{}] Raw line received: '{}'", peer, trimmed);
-        if trimmed.is_empty() { continue; }
-        let mut parts = trimmed.split_whitespace();
-        let cmd = parts.next().unwrap_or("");
-        let args: Vec<&str> = parts.collect();
+    println!("[CONN:RAW] [{}] Raw line received: '{}'", peer, line.trim());
+        let Some((cmd, args)) = parse_command_line(&line) else { continue; };
         println!("[CONN] [{}] Cmd='{}' Args={:?}", peer, cmd, args);
-        let server = Server { db: db.clone(), config: config.clone(), presence: presence.clone(), ws_manager: None };
+        let server = Server { db: db.clone(), config: config.clone(), presence: presence.clone(), ws_manager: ws_manager.clone(), peer: Some(peer) };
         let response = server.handle_command(cmd, &args).await;
         println!("[CONN] [{}] Response: {}", peer, response);
         // If the client just validated an existing session, register presence so
@@ -432,12 +1142,11 @@ async fn handle_client(db: Arc<Database>, config: ServerConfig, stream: TcpStrea
                 // Do not kick existing sessions on validate; just register this connection
                 let rx = presence.register(&uid).await;
                 println!("[CONN] [{}] Registered presence receiver for user {} (via validate_session)", peer, uid);
-                // set is_online = 1 when a connection registers
-                let _ = sqlx::query("UPDATE users SET is_online = 1 WHERE id = ?")
-                    .bind(&uid)
-                    .execute(&db.pool)
-                    .await;
-                println!("[DB] Set is_online=1 for user {} due to validate_session", uid);
+                // set is_online = 1 when a connection registers (validate_session)
+                users::mark_online(&db, &uid).await;
+                if let Some(ws_manager) = &ws_manager {
+                    ws_manager.notify_presence(&db, &uid, true).await;
+                }
                 kick_rx = Some(rx);
                 registered_user = Some(uid.clone());
                 registered_token = Some(token.to_string());
@@ -448,7 +1157,7 @@ async fn handle_client(db: Arc<Database>, config: ServerConfig, stream: TcpStrea
         if response.contains("SESSION:") {
             if let Some(line) = response.lines().find(|l| l.contains("SESSION:")) {
                 if let Some(tok) = line.split("SESSION:").nth(1) {
-                    let token = tok.trim();
+                    let token = tok.split_whitespace().next().unwrap_or("");
                     println!("[CONN] [{}] Detected SESSION token: {}", peer, token);
                     if let Some(uid) = auth::validate_session(db.clone(), token).await {
                         println!("[CONN] [{}] Token maps to user_id={}", peer, uid);
@@ -469,12 +1178,11 @@ async fn handle_client(db: Arc<Database>, config: ServerConfig, stream: TcpStrea
                         }
                         let rx = presence.register(&uid).await;
                         println!("[CONN] [{}] Registered presence receiver for user {}", peer, uid);
-                        // set is_online = 1 when a connection registers
-                        let _ = sqlx::query("UPDATE users SET is_online = 1 WHERE id = ?")
-                            .bind(&uid)
-                            .execute(&db.pool)
-                            .await;
-                        println!("[DB] Set is_online=1 for user {} due to active connection", uid);
+                        // set is_online = 1 when a connection registers (active connection)
+                        users::mark_online(&db, &uid).await;
+                        if let Some(ws_manager) = &ws_manager {
+                            ws_manager.notify_presence(&db, &uid, true).await;
+                        }
                         kick_rx = Some(rx);
                         registered_user = Some(uid.clone());
                         registered_token = Some(token.to_string());
@@ -485,6 +1193,11 @@ async fn handle_client(db: Arc<Database>, config: ServerConfig, stream: TcpStrea
         writer.write_all(response.as_bytes()).await?;
         writer.write_all(b"\n").await?;
         writer.flush().await?;
+        // Client con versione incompatibile: chiudi dopo aver inviato l'errore
+        if cmd == "HELLO" && response.starts_with("ERR:") {
+            println!("[CONN] [{}] Incompatible client version {:?}, closing", peer, args.first());
+            break;
+        }
     }
     if let Some(uid) = registered_user {
         println!("[CONN] [{}] Connection for user {} ending; cleaning up", peer, uid);
@@ -492,11 +1205,11 @@ async fn handle_client(db: Arc<Database>, config: ServerConfig, stream: TcpStrea
         // If no more active connections, set is_online = 0 (preserve session row for auto-login)
         let remaining = presence.count(&uid).await;
         if remaining == 0 {
-            let _ = sqlx::query("UPDATE users SET is_online = 0 WHERE id = ?")
-                .bind(&uid)
-                .execute(&db.pool)
-                .await;
-            println!("[DB] Set is_online=0 for user {} because no active connections remain", uid);
+            println!("[CONN] [{}] No active connections remain for user {}", peer, uid);
+            users::mark_offline(&db, &uid).await;
+            if let Some(ws_manager) = &ws_manager {
+                ws_manager.notify_presence(&db, &uid, false).await;
+            }
         } else {
             println!("[CONN] [{}] {} active connections remain for user {}, leaving is_online=1", peer, remaining, uid);
         }
@@ -518,7 +1231,7 @@ async fn handle_client(db: Arc<Database>, config: ServerConfig, stream: TcpStrea
 }
 
 // TLS stream handling: keep the same protocol logic but using the TLS stream types
-async fn handle_tls_client<S>(db: Arc<Database>, config: ServerConfig, stream: S, peer: std::net::SocketAddr, presence: PresenceRegistry) -> anyhow::Result<()>
+async fn handle_tls_client<S>(db: Arc<Database>, config: ServerConfig, stream: S, peer: std::net::SocketAddr, presence: PresenceRegistry, ws_manager: Option<Arc<ChatWebSocketManager>>) -> anyhow::Result<()>
 where
     S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin + Send + 'static,
 {
@@ -557,14 +1270,10 @@ where
                 break;
             }
         }
-    let trimmed = line.trim();
     // Raw incoming line logger for diagnostics (TLS)
-    println!("[CONN:RAW] [{}] TLS Raw line received: '{}'", peer, trimmed);
-    if trimmed.is_empty() { continue; }
-        let mut parts = trimmed.split_whitespace();
-        let cmd = parts.next().unwrap_or("");
-        let args: Vec<&str> = parts.collect();
-        let server = Server { db: db.clone(), config: config.clone(), presence: presence.clone(), ws_manager: None };
+    println!("[CONN:RAW] [{}] TLS Raw line received: '{}'", peer, line.trim());
+        let Some((cmd, args)) = parse_command_line(&line) else { continue; };
+        let server = Server { db: db.clone(), config: config.clone(), presence: presence.clone(), ws_manager: ws_manager.clone(), peer: Some(peer) };
         let response = server.handle_command(cmd, &args).await;
         // If the client just validated an existing session, register presence so
         // we treat this TLS connection as an active one (preserve session row for auto-login
@@ -575,11 +1284,10 @@ where
             if let Some(uid) = auth::validate_session(db.clone(), token).await {
                 let rx = presence.register(&uid).await;
                 println!("[CONN] [{}] TLS Registered presence receiver for user {} (via validate_session)", peer, uid);
-                let _ = sqlx::query("UPDATE users SET is_online = 1 WHERE id = ?")
-                    .bind(&uid)
-                    .execute(&db.pool)
-                    .await;
-                println!("[DB] TLS Set is_online=1 for user {} due to validate_session", uid);
+                users::mark_online(&db, &uid).await;
+                if let Some(ws_manager) = &ws_manager {
+                    ws_manager.notify_presence(&db, &uid, true).await;
+                }
                 kick_rx = Some(rx);
                 registered_user = Some(uid.clone());
                 registered_token = Some(token.to_string());
@@ -590,7 +1298,7 @@ where
         if response.contains("SESSION:") {
             if let Some(line) = response.lines().find(|l| l.contains("SESSION:")) {
                 if let Some(tok) = line.split("SESSION:").nth(1) {
-                    let token = tok.trim();
+                    let token = tok.split_whitespace().next().unwrap_or("");
                     if let Some(uid) = auth::validate_session(db.clone(), token).await {
                         let kicked = presence.kick_all(&uid).await;
                         if kicked > 0 {
@@ -604,6 +1312,10 @@ where
                                 .await;
                         }
                         let rx = presence.register(&uid).await;
+                        users::mark_online(&db, &uid).await;
+                        if let Some(ws_manager) = &ws_manager {
+                            ws_manager.notify_presence(&db, &uid, true).await;
+                        }
                         kick_rx = Some(rx);
                         registered_user = Some(uid.clone());
                         registered_token = Some(token.to_string());
@@ -614,6 +1326,11 @@ where
         writer.write_all(response.as_bytes()).await?;
         writer.write_all(b"\n").await?;
         writer.flush().await?;
+        // Client con versione incompatibile: chiudi dopo aver inviato l'errore
+        if cmd == "HELLO" && response.starts_with("ERR:") {
+            println!("[CONN] [{}] Incompatible client version {:?}, closing", peer, args.first());
+            break;
+        }
     }
     if let Some(uid) = registered_user {
         println!("[CONN] [{}] TLS connection for user {} ending; cleaning up", peer, uid);
@@ -621,11 +1338,11 @@ where
         // If no more active connections, set is_online = 0 (preserve session row for auto-login)
         let remaining = presence.count(&uid).await;
         if remaining == 0 {
-            let _ = sqlx::query("UPDATE users SET is_online = 0 WHERE id = ?")
-                .bind(&uid)
-                .execute(&db.pool)
-                .await;
-            println!("[DB] TLS Set is_online=0 for user {} because no active connections remain", uid);
+            println!("[CONN] [{}] TLS no active connections remain for user {}", peer, uid);
+            users::mark_offline(&db, &uid).await;
+            if let Some(ws_manager) = &ws_manager {
+                ws_manager.notify_presence(&db, &uid, false).await;
+            }
         } else {
             println!("[CONN] [{}] TLS {} active connections remain for user {}, leaving is_online=1", peer, remaining, uid);
         }
@@ -1,26 +1,97 @@
 use sqlx::{SqlitePool, sqlite::SqlitePoolOptions};
+use crate::server::auth_cache::AuthCache;
+use std::future::Future;
+use std::time::Duration;
+
+/// Attempts made by `Database::connect` when the file is locked by another process
+pub const DEFAULT_CONNECT_RETRIES: u32 = 5;
+pub const DEFAULT_CONNECT_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// Pool bounds used by `Database::connect`; the server reads DB_POOL_MAX / DB_POOL_MIN
+pub const DEFAULT_POOL_MAX_CONNECTIONS: u32 = 10;
+pub const DEFAULT_POOL_MIN_CONNECTIONS: u32 = 1;
+
+/// SQLITE_BUSY / SQLITE_LOCKED: another connection (often a second server instance
+/// starting on the same file) holds the lock
+fn is_locked(e: &sqlx::Error) -> bool {
+    match e.as_database_error() {
+        Some(db_err) => matches!(db_err.code().as_deref(), Some("5") | Some("6")) || db_err.message().contains("locked"),
+        None => false,
+    }
+}
+
+/// Run `op` until it succeeds, fails with something other than a lock, or `retries` attempts are used up
+async fn retry_while_locked<T, F, Fut>(what: &str, retries: u32, delay: Duration, mut op: F) -> Result<T, sqlx::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, sqlx::Error>>,
+{
+    let retries = retries.max(1);
+    let mut attempt = 1;
+    loop {
+        match op().await {
+            Err(e) if is_locked(&e) && attempt < retries => {
+                println!("⏳ {} attempt {}/{} failed, database is locked; retrying in {:?}", what, attempt, retries, delay);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Snapshot of the connection pool. sqlx does not expose how many tasks wait in `acquire`;
+/// a pool with `in_use` equal to its maximum is saturated and further queries queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolStats {
+    /// Open connections, idle or not
+    pub size: u32,
+    pub idle: u32,
+    pub in_use: u32,
+    pub max: u32,
+}
 
 #[derive(Debug, Clone)]
 pub struct Database {
     pub pool: SqlitePool,
+    /// Validated session tokens, see `auth::validate_session`
+    pub auth_cache: AuthCache,
 }
 
 impl Database {
     pub async fn connect(database_url: &str) -> Result<Self, sqlx::Error> {
+        Self::connect_with_retry(
+            database_url,
+            DEFAULT_CONNECT_RETRIES,
+            DEFAULT_CONNECT_RETRY_DELAY,
+            DEFAULT_POOL_MAX_CONNECTIONS,
+            DEFAULT_POOL_MIN_CONNECTIONS,
+        )
+        .await
+    }
+
+    /// Like `connect`, retrying up to `retries` times, `delay` apart, while the database is locked,
+    /// with a pool of `min_connections..=max_connections` connections
+    pub async fn connect_with_retry(
+        database_url: &str,
+        retries: u32,
+        delay: Duration,
+        max_connections: u32,
+        min_connections: u32,
+    ) -> Result<Self, sqlx::Error> {
         println!("🔗 Attempting to connect to database: {}", database_url);
         
         // Extract file path from database URL to create directory if needed
-        let file_path = if database_url.starts_with("sqlite://") {
+        let file_path = if let Some(path_part) = database_url.strip_prefix("sqlite://") {
             // Remove "sqlite://" prefix and any query parameters
-            let path_part = &database_url[9..];
             if let Some(query_pos) = path_part.find('?') {
                 &path_part[..query_pos]
             } else {
                 path_part
             }
-        } else if database_url.starts_with("sqlite:") {
+        } else if let Some(path_part) = database_url.strip_prefix("sqlite:") {
             // Remove "sqlite:" prefix
-            &database_url[7..]
+            path_part
         } else {
             database_url
         };
@@ -50,18 +121,79 @@ impl Database {
             println!("📄 Database file does not exist, SQLite will create it");
         }
         
-        println!("🔗 Creating SQLite connection pool...");
-        let pool = SqlitePoolOptions::new()
-            .max_connections(5)
-            .connect(database_url)
-            .await
-            .map_err(|e| {
-                println!("❌ SQLite connection failed: {}", e);
-                e
-            })?;
+        let max_connections = max_connections.max(1);
+        let min_connections = min_connections.min(max_connections);
+        println!("🔗 Creating SQLite connection pool ({}..={} connections)...", min_connections, max_connections);
+        let pool = retry_while_locked("Connection", retries, delay, || {
+            SqlitePoolOptions::new()
+                .max_connections(max_connections)
+                .min_connections(min_connections)
+                .connect(database_url)
+        })
+        .await
+        .map_err(|e| {
+            println!("❌ SQLite connection failed: {}", e);
+            e
+        })?;
         
         println!("✅ Database connection successful!");
-        Ok(Self { pool })
+        Ok(Self { pool, auth_cache: AuthCache::default() })
+    }
+
+    pub fn pool_stats(&self) -> PoolStats {
+        let size = self.pool.size();
+        let idle = self.pool.num_idle() as u32;
+        PoolStats {
+            size,
+            idle,
+            in_use: size.saturating_sub(idle),
+            max: self.pool.options().get_max_connections(),
+        }
+    }
+
+    /// Replace the session cache with one holding at most `size` tokens (0 disables it)
+    pub fn with_session_cache_size(mut self, size: usize) -> Self {
+        self.auth_cache = AuthCache::new(size);
+        self
+    }
+
+    /// Run `migrate`, starting over while another process holds the lock.
+    /// Every step is idempotent, so a partially applied attempt is safe to repeat.
+    pub async fn migrate_with_retry(&self, retries: u32, delay: Duration) -> Result<(), sqlx::Error> {
+        retry_while_locked("Migration", retries, delay, || self.migrate()).await
+    }
+
+    /// Keep `messages_fts` in sync with `encrypted_messages` when `enabled`, i.e. when messages
+    /// are stored in clear; otherwise drop the triggers and empty the index so no plaintext
+    /// lingers after encryption is turned on.
+    pub async fn sync_message_search(&self, enabled: bool) -> Result<(), sqlx::Error> {
+        if !enabled {
+            for trigger in ["messages_fts_insert", "messages_fts_update", "messages_fts_delete"] {
+                sqlx::query(&format!("DROP TRIGGER IF EXISTS {}", trigger)).execute(&self.pool).await?;
+            }
+            sqlx::query("DELETE FROM messages_fts").execute(&self.pool).await?;
+            return Ok(());
+        }
+        sqlx::query(r#"
+            CREATE TRIGGER IF NOT EXISTS messages_fts_insert AFTER INSERT ON encrypted_messages BEGIN
+                INSERT INTO messages_fts (content, message_id) VALUES (new.message, new.id);
+            END;
+        "#).execute(&self.pool).await?;
+        sqlx::query(r#"
+            CREATE TRIGGER IF NOT EXISTS messages_fts_update AFTER UPDATE OF message ON encrypted_messages BEGIN
+                UPDATE messages_fts SET content = new.message WHERE message_id = old.id;
+            END;
+        "#).execute(&self.pool).await?;
+        sqlx::query(r#"
+            CREATE TRIGGER IF NOT EXISTS messages_fts_delete AFTER DELETE ON encrypted_messages BEGIN
+                DELETE FROM messages_fts WHERE message_id = old.id;
+            END;
+        "#).execute(&self.pool).await?;
+        // Index messages stored before the triggers existed
+        sqlx::query("INSERT INTO messages_fts (content, message_id) SELECT message, id FROM encrypted_messages WHERE id NOT IN (SELECT message_id FROM messages_fts)")
+            .execute(&self.pool)
+            .await?;
+        Ok(())
     }
 
     pub async fn migrate(&self) -> Result<(), sqlx::Error> {
@@ -71,9 +203,25 @@ impl Database {
                 id TEXT PRIMARY KEY,
                 username TEXT UNIQUE NOT NULL,
                 created_at INTEGER NOT NULL,
-                is_online INTEGER NOT NULL DEFAULT 0
+                is_online INTEGER NOT NULL DEFAULT 0,
+                last_seen INTEGER,
+                avatar_url TEXT,
+                is_banned INTEGER NOT NULL DEFAULT 0,
+                status_message TEXT DEFAULT '',
+                registered_at INTEGER
             );
         "#).execute(&self.pool).await?;
+        // Databases created before presence tracking lack the column; ignore "duplicate column" errors
+        let _ = sqlx::query("ALTER TABLE users ADD COLUMN last_seen INTEGER").execute(&self.pool).await;
+        // Databases created before avatars lack the column; ignore "duplicate column" errors
+        let _ = sqlx::query("ALTER TABLE users ADD COLUMN avatar_url TEXT").execute(&self.pool).await;
+        // Databases created before account bans lack the column; ignore "duplicate column" errors
+        let _ = sqlx::query("ALTER TABLE users ADD COLUMN is_banned INTEGER NOT NULL DEFAULT 0").execute(&self.pool).await;
+        // Same for databases created before status messages
+        let _ = sqlx::query("ALTER TABLE users ADD COLUMN status_message TEXT DEFAULT ''").execute(&self.pool).await;
+        // Registration time shown to admins; accounts from before the column registered when they were created
+        let _ = sqlx::query("ALTER TABLE users ADD COLUMN registered_at INTEGER").execute(&self.pool).await;
+        sqlx::query("UPDATE users SET registered_at = created_at WHERE registered_at IS NULL").execute(&self.pool).await?;
 
         // User encryption keys
         sqlx::query(r#"
@@ -102,6 +250,16 @@ impl Database {
             );
         "#).execute(&self.pool).await?;
 
+        // Archived chats (per-user, non influisce sul recupero dei messaggi)
+        sqlx::query(r#"
+            CREATE TABLE IF NOT EXISTS archived_chats (
+                user_id TEXT NOT NULL,
+                chat_id TEXT NOT NULL,
+                archived_at INTEGER NOT NULL,
+                PRIMARY KEY (user_id, chat_id)
+            );
+        "#).execute(&self.pool).await?;
+
         // Encrypted messages
         sqlx::query(r#"
             CREATE TABLE IF NOT EXISTS encrypted_messages (
@@ -109,10 +267,110 @@ impl Database {
                 chat_id TEXT NOT NULL,
                 sender_id TEXT NOT NULL,
                 message TEXT NOT NULL,
-                sent_at INTEGER NOT NULL
+                sent_at INTEGER NOT NULL,
+                edited_at INTEGER,
+                edited_count INTEGER NOT NULL DEFAULT 0,
+                reply_to_id INTEGER,
+                forwarded_from_id INTEGER,
+                key_rotation INTEGER
+            );
+        "#).execute(&self.pool).await?;
+        // Databases created before message editing lack the columns; ignore "duplicate column" errors
+        let _ = sqlx::query("ALTER TABLE encrypted_messages ADD COLUMN edited_at INTEGER").execute(&self.pool).await;
+        let _ = sqlx::query("ALTER TABLE encrypted_messages ADD COLUMN edited_count INTEGER NOT NULL DEFAULT 0").execute(&self.pool).await;
+        // Same for replies and forwards, added later still
+        let _ = sqlx::query("ALTER TABLE encrypted_messages ADD COLUMN reply_to_id INTEGER").execute(&self.pool).await;
+        let _ = sqlx::query("ALTER TABLE encrypted_messages ADD COLUMN forwarded_from_id INTEGER").execute(&self.pool).await;
+        // Rotation index of the chat key a message was encrypted with; NULL for older messages (index 0)
+        let _ = sqlx::query("ALTER TABLE encrypted_messages ADD COLUMN key_rotation INTEGER").execute(&self.pool).await;
+
+        // One-time data migrations already applied, by name
+        sqlx::query(r#"
+            CREATE TABLE IF NOT EXISTS migrations (
+                name TEXT PRIMARY KEY,
+                applied_at INTEGER NOT NULL
+            );
+        "#).execute(&self.pool).await?;
+
+        // Chat key rotations: the highest index of a chat is the one new messages use
+        sqlx::query(r#"
+            CREATE TABLE IF NOT EXISTS chat_key_rotations (
+                chat_id TEXT NOT NULL,
+                rotation_index INTEGER NOT NULL,
+                rotated_at INTEGER NOT NULL,
+                PRIMARY KEY (chat_id, rotation_index)
+            );
+        "#).execute(&self.pool).await?;
+
+        // Last nonce counter used to encrypt a message of each chat. Never reset or deleted:
+        // the chat key could come back (same participants) and a nonce must not repeat under it
+        sqlx::query(r#"
+            CREATE TABLE IF NOT EXISTS nonce_counters (
+                chat_id TEXT PRIMARY KEY,
+                counter INTEGER NOT NULL DEFAULT 0
+            );
+        "#).execute(&self.pool).await?;
+
+        // Full-text index of message contents, filled by `sync_message_search`
+        sqlx::query(r#"
+            CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(
+                content,
+                message_id UNINDEXED,
+                tokenize='porter ascii'
+            );
+        "#).execute(&self.pool).await?;
+
+        // Read receipts of group messages
+        sqlx::query(r#"
+            CREATE TABLE IF NOT EXISTS message_receipts (
+                message_id INTEGER NOT NULL,
+                user_id TEXT NOT NULL,
+                seen_at INTEGER NOT NULL,
+                PRIMARY KEY (message_id, user_id)
+            );
+        "#).execute(&self.pool).await?;
+
+        // How far each participant has read each chat
+        sqlx::query(r#"
+            CREATE TABLE IF NOT EXISTS read_receipts (
+                chat_id TEXT NOT NULL,
+                user_id TEXT NOT NULL,
+                last_read_at INTEGER NOT NULL,
+                PRIMARY KEY (chat_id, user_id)
+            );
+        "#).execute(&self.pool).await?;
+
+        // Emoji reactions to messages
+        sqlx::query(r#"
+            CREATE TABLE IF NOT EXISTS message_reactions (
+                message_id INTEGER NOT NULL,
+                user_id TEXT NOT NULL,
+                emoji TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                PRIMARY KEY (message_id, user_id, emoji)
+            );
+        "#).execute(&self.pool).await?;
+
+        // Public keys registered by clients for end-to-end encrypted messages
+        sqlx::query(r#"
+            CREATE TABLE IF NOT EXISTS user_keys (
+                user_id TEXT PRIMARY KEY,
+                public_key TEXT NOT NULL,
+                updated_at INTEGER NOT NULL
             );
         "#).execute(&self.pool).await?;
 
+        // X25519 keys published with /publish_key; supersedes user_keys, whose rows are carried over
+        sqlx::query(r#"
+            CREATE TABLE IF NOT EXISTS user_public_keys (
+                user_id TEXT PRIMARY KEY,
+                dh_public_key TEXT NOT NULL,
+                updated_at INTEGER NOT NULL
+            );
+        "#).execute(&self.pool).await?;
+        sqlx::query("INSERT OR IGNORE INTO user_public_keys (user_id, dh_public_key, updated_at) SELECT user_id, public_key, updated_at FROM user_keys")
+            .execute(&self.pool).await?;
+
         // Friend requests
         sqlx::query(r#"
             CREATE TABLE IF NOT EXISTS friend_requests (
@@ -135,15 +393,34 @@ impl Database {
             );
         "#).execute(&self.pool).await?;
 
+        // Blocked users (blocker_id non riceve richieste da blocked_id)
+        sqlx::query(r#"
+            CREATE TABLE IF NOT EXISTS blocked_users (
+                blocker_id TEXT NOT NULL,
+                blocked_id TEXT NOT NULL,
+                created_at INTEGER NOT NULL,
+                PRIMARY KEY (blocker_id, blocked_id)
+            );
+        "#).execute(&self.pool).await?;
+
         // Groups
         sqlx::query(r#"
             CREATE TABLE IF NOT EXISTS groups (
                 id TEXT PRIMARY KEY,
                 name TEXT NOT NULL,
                 created_by TEXT NOT NULL,
-                created_at INTEGER NOT NULL
+                created_at INTEGER NOT NULL,
+                is_public INTEGER NOT NULL DEFAULT 0,
+                description TEXT DEFAULT '',
+                key_version INTEGER NOT NULL DEFAULT 0
             );
         "#).execute(&self.pool).await?;
+        // Databases created before public groups lack the column; ignore "duplicate column" errors
+        let _ = sqlx::query("ALTER TABLE groups ADD COLUMN is_public INTEGER NOT NULL DEFAULT 0").execute(&self.pool).await;
+        // Same for databases created before group descriptions
+        let _ = sqlx::query("ALTER TABLE groups ADD COLUMN description TEXT DEFAULT ''").execute(&self.pool).await;
+        // Bumped on every membership change, see `groups::bump_key_version`
+        let _ = sqlx::query("ALTER TABLE groups ADD COLUMN key_version INTEGER NOT NULL DEFAULT 0").execute(&self.pool).await;
 
         // Group members
         sqlx::query(r#"
@@ -151,9 +428,12 @@ impl Database {
                 group_id TEXT NOT NULL,
                 user_id TEXT NOT NULL,
                 joined_at INTEGER NOT NULL,
+                role TEXT NOT NULL DEFAULT 'member',
                 PRIMARY KEY (group_id, user_id)
             );
         "#).execute(&self.pool).await?;
+        // Databases created before member roles lack the column; ignore "duplicate column" errors
+        let _ = sqlx::query("ALTER TABLE group_members ADD COLUMN role TEXT NOT NULL DEFAULT 'member'").execute(&self.pool).await;
 
         // Group invites
         sqlx::query(r#"
@@ -163,9 +443,51 @@ impl Database {
                 invited_user_id TEXT NOT NULL,
                 invited_by TEXT NOT NULL,
                 created_at INTEGER NOT NULL,
-                status TEXT NOT NULL
+                status TEXT NOT NULL,
+                expires_at INTEGER
             );
         "#).execute(&self.pool).await?;
+        // Databases created before invite expiry lack the column; ignore "duplicate column" errors
+        let _ = sqlx::query("ALTER TABLE group_invites ADD COLUMN expires_at INTEGER").execute(&self.pool).await;
+
+        // Shareable invite codes; expires_at and max_uses are NULL when unlimited
+        sqlx::query(r#"
+            CREATE TABLE IF NOT EXISTS group_invite_codes (
+                code TEXT PRIMARY KEY,
+                group_id TEXT NOT NULL,
+                created_by TEXT NOT NULL,
+                expires_at INTEGER,
+                max_uses INTEGER,
+                use_count INTEGER NOT NULL DEFAULT 0
+            );
+        "#).execute(&self.pool).await?;
+
+        // Messages pinned to the top of a group chat by its admins
+        sqlx::query(r#"
+            CREATE TABLE IF NOT EXISTS pinned_messages (
+                group_id TEXT NOT NULL,
+                message_id INTEGER NOT NULL,
+                pinned_by TEXT NOT NULL,
+                pinned_at INTEGER NOT NULL,
+                PRIMARY KEY (group_id, message_id)
+            );
+        "#).execute(&self.pool).await?;
+
+        // Group events (kicked, left); actor_id acted on target_id
+        sqlx::query(r#"
+            CREATE TABLE IF NOT EXISTS group_events (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                group_id TEXT NOT NULL,
+                event_type TEXT NOT NULL,
+                actor_id TEXT NOT NULL,
+                target_id TEXT,
+                created_at INTEGER NOT NULL
+            );
+        "#).execute(&self.pool).await?;
+        // Key version each membership change moved the group to, see `groups::bump_key_version`;
+        // NULL for founding members and for rows from before it was recorded
+        let _ = sqlx::query("ALTER TABLE group_members ADD COLUMN key_version INTEGER").execute(&self.pool).await;
+        let _ = sqlx::query("ALTER TABLE group_events ADD COLUMN key_version INTEGER").execute(&self.pool).await;
 
         // Auth
         sqlx::query(r#"
@@ -181,7 +503,18 @@ impl Database {
                 user_id TEXT NOT NULL,
                 session_token TEXT PRIMARY KEY,
                 created_at INTEGER NOT NULL,
-                expires_at INTEGER NOT NULL
+                expires_at INTEGER NOT NULL,
+                device_label TEXT
+            );
+        "#).execute(&self.pool).await?;
+        // Databases created before session listing lack the column; ignore "duplicate column" errors
+        let _ = sqlx::query("ALTER TABLE sessions ADD COLUMN device_label TEXT").execute(&self.pool).await;
+
+        // Deleted accounts; their messages keep sender_id = 'deleted_user'
+        sqlx::query(r#"
+            CREATE TABLE IF NOT EXISTS deleted_users (
+                original_id TEXT PRIMARY KEY,
+                deleted_at INTEGER NOT NULL
             );
         "#).execute(&self.pool).await?;
 
@@ -195,6 +528,29 @@ impl Database {
             );
         "#).execute(&self.pool).await?;
 
+        // Command audit log (enabled with AUDIT_LOG)
+        sqlx::query(r#"
+            CREATE TABLE IF NOT EXISTS command_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                user_id TEXT,
+                command TEXT NOT NULL,
+                args_redacted TEXT NOT NULL,
+                result_prefix TEXT NOT NULL,
+                executed_at INTEGER NOT NULL,
+                ip TEXT
+            );
+        "#).execute(&self.pool).await?;
+
+        // WebSocket messages for users who were offline, replayed when they reconnect
+        sqlx::query(r#"
+            CREATE TABLE IF NOT EXISTS ws_pending_messages (
+                id INTEGER PRIMARY KEY,
+                user_id TEXT NOT NULL,
+                payload TEXT NOT NULL,
+                created_at INTEGER NOT NULL
+            );
+        "#).execute(&self.pool).await?;
+
         Ok(())
     }
 }
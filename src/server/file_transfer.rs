@@ -0,0 +1,249 @@
+// Trasferimento file a blocchi tramite frame binari del WebSocket
+use crate::server::config::ServerConfig;
+use crate::server::database::Database;
+use crate::server::websocket::{MessageType, OutgoingChatMessage, WebSocketMessage};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use sqlx::Row;
+use tokio::io::AsyncWriteExt;
+use uuid::Uuid;
+
+/// Bytes in front of the payload of every binary frame
+pub const CHUNK_HEADER_LEN: usize = 76;
+
+/// Header of a binary frame: 36-byte UUID file id, big-endian chunk index and chunk
+/// count (4 bytes each), SHA-256 of the payload (32 bytes)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkHeader {
+    pub file_id: String,
+    pub chunk_index: u32,
+    pub total_chunks: u32,
+    pub sha256: [u8; 32],
+}
+
+impl ChunkHeader {
+    /// Split a binary frame into its header and payload. The file id must be a hyphenated
+    /// UUID, so it is safe to use as a directory name.
+    ///
+    /// ```
+    /// use ruggine_modulare::server::file_transfer::ChunkHeader;
+    ///
+    /// let mut frame = b"6f1c2a4e-8d3b-4c5a-9e7f-0a1b2c3d4e5f".to_vec();
+    /// frame.extend_from_slice(&1u32.to_be_bytes());
+    /// frame.extend_from_slice(&3u32.to_be_bytes());
+    /// frame.extend_from_slice(&[0; 32]);
+    /// frame.extend_from_slice(b"data");
+    /// let (header, payload) = ChunkHeader::parse(&frame).unwrap();
+    /// assert_eq!((header.chunk_index, header.total_chunks), (1, 3));
+    /// assert_eq!(payload, b"data");
+    ///
+    /// assert!(ChunkHeader::parse(&frame[..40]).is_err());
+    /// frame[..2].copy_from_slice(b"..");
+    /// assert!(ChunkHeader::parse(&frame).is_err());
+    /// ```
+    pub fn parse(frame: &[u8]) -> Result<(ChunkHeader, &[u8]), String> {
+        if frame.len() < CHUNK_HEADER_LEN {
+            return Err(format!("Frame shorter than the {}-byte chunk header", CHUNK_HEADER_LEN));
+        }
+        let file_id = std::str::from_utf8(&frame[..36])
+            .ok()
+            .and_then(|id| Uuid::parse_str(id).ok())
+            .ok_or("Invalid file id")?;
+        let chunk_index = u32::from_be_bytes([frame[36], frame[37], frame[38], frame[39]]);
+        let total_chunks = u32::from_be_bytes([frame[40], frame[41], frame[42], frame[43]]);
+        if chunk_index >= total_chunks {
+            return Err(format!("Chunk {} out of range ({} chunks)", chunk_index, total_chunks));
+        }
+        let mut sha256 = [0u8; 32];
+        sha256.copy_from_slice(&frame[44..CHUNK_HEADER_LEN]);
+        let header = ChunkHeader { file_id: file_id.to_string(), chunk_index, total_chunks, sha256 };
+        Ok((header, &frame[CHUNK_HEADER_LEN..]))
+    }
+
+    /// Whether `payload` matches the checksum of the header
+    pub fn verify(&self, payload: &[u8]) -> bool {
+        ring::digest::digest(&ring::digest::SHA256, payload).as_ref() == self.sha256
+    }
+}
+
+/// File announced with a `file_transfer` message, whose chunks are being received
+pub struct Upload {
+    pub file_name: String,
+    pub mime_type: String,
+    /// Username of the uploader
+    pub sender_name: String,
+    /// "private:<username>" or "group:<group_id>"
+    pub target: String,
+    /// Users notified once the file is complete, uploader excluded
+    pub recipients: Vec<String>,
+    total_chunks: Option<u32>,
+    /// Size of each chunk received so far, by index
+    received: HashMap<u32, u64>,
+    /// Limits from `ServerConfig::max_file_chunks` and `max_file_size_bytes`
+    max_chunks: u32,
+    max_bytes: u64,
+}
+
+impl Upload {
+    /// Check chunk `header` with a payload of `len` bytes before it is stored. Errors when
+    /// the chunk count differs from the one of earlier chunks or the file would exceed
+    /// the configured chunk count or size; the upload should then be abandoned.
+    pub fn admit(&self, header: &ChunkHeader, len: usize) -> Result<(), String> {
+        if let Some(total) = self.total_chunks.filter(|&total| total != header.total_chunks) {
+            return Err(format!("Chunk count changed from {} to {}", total, header.total_chunks));
+        }
+        if header.total_chunks > self.max_chunks {
+            return Err(format!("{} chunks exceed the limit of {}", header.total_chunks, self.max_chunks));
+        }
+        let replaced = self.received.get(&header.chunk_index).copied().unwrap_or(0);
+        let size = self.received.values().sum::<u64>() - replaced + len as u64;
+        if size > self.max_bytes {
+            return Err(format!("File exceeds the limit of {} bytes", self.max_bytes));
+        }
+        Ok(())
+    }
+
+    /// Record stored chunk `header` of `len` bytes, admitted by `admit`; true once every
+    /// chunk of the file has arrived
+    pub fn record(&mut self, header: &ChunkHeader, len: usize) -> bool {
+        self.total_chunks = Some(header.total_chunks);
+        self.received.insert(header.chunk_index, len as u64);
+        self.received.len() as u32 == header.total_chunks
+    }
+}
+
+/// Start an upload from a `file_transfer` message: `file_id` (a UUID), the file name in
+/// `content`, `mime_type` and the chat, as for `send_message`. Returns the file id and
+/// its upload; group uploads require the sender to be a member. The id is reserved under
+/// `storage_dir` for this upload: ids already used by another upload or a stored file are refused.
+pub async fn announce(db: &Arc<Database>, sender_id: &str, msg: &OutgoingChatMessage, storage_dir: &Path, config: &ServerConfig) -> Result<(String, Upload), String> {
+    let file_id = msg.file_id.as_deref()
+        .and_then(|id| Uuid::parse_str(id).ok())
+        .ok_or("Invalid file id")?
+        .to_string();
+    let file_name = msg.content.trim();
+    if file_name.is_empty() {
+        return Err("Missing file name".to_string());
+    }
+    let sender_name = sqlx::query("SELECT username FROM users WHERE id = ?")
+        .bind(sender_id)
+        .fetch_optional(&db.pool)
+        .await
+        .map_err(|e| e.to_string())?
+        .map(|r| r.get::<String, _>("username"))
+        .ok_or("Unknown sender")?;
+    let (target, recipients) = match (msg.chat_type.as_str(), &msg.to_user, &msg.group_id) {
+        ("private", Some(to_user), _) => {
+            let recipient = sqlx::query("SELECT id FROM users WHERE username = ?")
+                .bind(to_user)
+                .fetch_optional(&db.pool)
+                .await
+                .map_err(|e| e.to_string())?
+                .map(|r| r.get::<String, _>("id"))
+                .ok_or("User not found")?;
+            (format!("private:{}", to_user), vec![recipient])
+        }
+        ("group", _, Some(group_id)) => {
+            let members: Vec<String> = sqlx::query("SELECT user_id FROM group_members WHERE group_id = ?")
+                .bind(group_id)
+                .fetch_all(&db.pool)
+                .await
+                .map_err(|e| e.to_string())?
+                .iter()
+                .map(|r| r.get("user_id"))
+                .collect();
+            if !members.iter().any(|m| m == sender_id) {
+                return Err("Not a member of this group".to_string());
+            }
+            (format!("group:{}", group_id), members)
+        }
+        _ => return Err("Missing file recipient".to_string()),
+    };
+    reserve(storage_dir, &file_id).await?;
+    let upload = Upload {
+        file_name: file_name.to_string(),
+        mime_type: msg.mime_type.clone().unwrap_or_else(|| "application/octet-stream".to_string()),
+        sender_name,
+        target,
+        recipients: recipients.into_iter().filter(|r| r != sender_id).collect(),
+        total_chunks: None,
+        received: HashMap::new(),
+        max_chunks: config.max_file_chunks,
+        max_bytes: config.max_file_size_bytes,
+    };
+    Ok((file_id, upload))
+}
+
+/// Claim `file_id` by creating its chunk directory, which fails if another upload already
+/// did; ids of stored files are refused too, so nobody can overwrite someone else's file
+async fn reserve(storage_dir: &Path, file_id: &str) -> Result<(), String> {
+    if tokio::fs::try_exists(stored_file_path(storage_dir, file_id)).await.unwrap_or(true) {
+        return Err("File id already in use".to_string());
+    }
+    tokio::fs::create_dir_all(storage_dir).await.map_err(|e| e.to_string())?;
+    match tokio::fs::create_dir(storage_dir.join(file_id)).await {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => Err("File id already in use".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// Drop the chunks of an abandoned upload, releasing its file id
+pub async fn discard(storage_dir: &Path, file_id: &str) {
+    let _ = tokio::fs::remove_dir_all(storage_dir.join(file_id)).await;
+}
+
+/// Write a verified chunk to `<storage_dir>/<file_id>/<chunk_index>`; the directory is
+/// created when the upload is announced
+pub async fn store_chunk(storage_dir: &Path, header: &ChunkHeader, payload: &[u8]) -> std::io::Result<()> {
+    tokio::fs::write(storage_dir.join(&header.file_id).join(header.chunk_index.to_string()), payload).await
+}
+
+/// Where a completed file is kept
+pub fn stored_file_path(storage_dir: &Path, file_id: &str) -> PathBuf {
+    storage_dir.join("complete").join(file_id)
+}
+
+/// Join the chunks of `file_id` in order, move the result to `stored_file_path` and drop
+/// the chunks. Returns the size of the file; an existing stored file is never replaced.
+pub async fn assemble(storage_dir: &Path, file_id: &str, total_chunks: u32) -> std::io::Result<u64> {
+    let chunk_dir = storage_dir.join(file_id);
+    let assembled = chunk_dir.join("assembled");
+    let mut out = tokio::fs::File::create(&assembled).await?;
+    let mut total_size = 0u64;
+    for index in 0..total_chunks {
+        let chunk = tokio::fs::read(chunk_dir.join(index.to_string())).await?;
+        out.write_all(&chunk).await?;
+        total_size += chunk.len() as u64;
+    }
+    out.flush().await?;
+    drop(out);
+
+    let destination = stored_file_path(storage_dir, file_id);
+    if let Some(parent) = destination.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    // hard_link fails if the destination exists, unlike rename
+    tokio::fs::hard_link(&assembled, &destination).await?;
+    tokio::fs::remove_dir_all(&chunk_dir).await?;
+    Ok(total_size)
+}
+
+/// `FileTransferComplete` event of `upload` for `recipient`
+pub fn complete_event(file_id: &str, upload: &Upload, total_size: u64, recipient: &str) -> WebSocketMessage {
+    WebSocketMessage {
+        id: Uuid::new_v4().to_string(),
+        message_type: MessageType::FileTransferComplete {
+            file_id: file_id.to_string(),
+            file_name: upload.file_name.clone(),
+            mime_type: upload.mime_type.clone(),
+            total_size,
+        },
+        sender: upload.sender_name.clone(),
+        target: upload.target.clone(),
+        content: String::new(),
+        timestamp: chrono::Utc::now().timestamp(),
+        recipient_user_id: Some(recipient.to_string()),
+    }
+}
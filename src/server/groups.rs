@@ -1,115 +1,196 @@
 use crate::server::database::Database;
+use crate::server::config::ServerConfig;
+use crate::server::websocket::{MessageType, WebSocketMessage};
 use std::sync::Arc;
 use sqlx::Row;
+use rand::Rng;
 
-pub async fn create_group(db: Arc<Database>, user_id: &str, group_name: &str) -> String {
+const GROUP_FULL: &str = "ERR: Group is at maximum capacity";
+const TOO_MANY_GROUPS: &str = "ERR: You have reached the maximum number of groups";
+
+/// Length bounds of a group name set by `rename_group`, in characters
+const MIN_GROUP_NAME_CHARS: usize = 3;
+const MAX_GROUP_NAME_CHARS: usize = 64;
+
+/// Longest group description, in characters
+const MAX_GROUP_DESCRIPTION_CHARS: usize = 500;
+const DESCRIPTION_TOO_LONG: &str = "ERR: Group description must be at most 500 characters";
+
+/// Public groups per `/list_public_groups` page
+const PUBLIC_GROUPS_PAGE_SIZE: i64 = 20;
+
+/// Length of the codes made by `generate_invite_code`
+const INVITE_CODE_CHARS: usize = 8;
+
+/// Number of groups `user_id` belongs to
+async fn groups_of_user(db: &Database, user_id: &str) -> Result<usize, sqlx::Error> {
+    let row = sqlx::query("SELECT COUNT(*) AS c FROM group_members WHERE user_id = ?")
+        .bind(user_id)
+        .fetch_one(&db.pool)
+        .await?;
+    Ok(row.get::<i64,_>("c") as usize)
+}
+
+/// Why `user_id` may not become a member of `group_id` under the configured limits, if anything.
+/// Existing members always pass, so joining twice stays a no-op.
+async fn membership_limit_error(db: &Database, user_id: &str, group_id: &str, config: &ServerConfig) -> Option<String> {
+    let row = sqlx::query("SELECT COUNT(*) AS members, COALESCE(SUM(user_id = ?), 0) AS already FROM group_members WHERE group_id = ?")
+        .bind(user_id)
+        .bind(group_id)
+        .fetch_one(&db.pool)
+        .await;
+    let (members, already) = match row {
+        Ok(r) => (r.get::<i64,_>("members") as usize, r.get::<i64,_>("already") > 0),
+        Err(e) => return Some(format!("ERR: DB error: {}", e)),
+    };
+    if already {
+        return None;
+    }
+    if members >= config.max_group_size {
+        return Some(GROUP_FULL.to_string());
+    }
+    match groups_of_user(db, user_id).await {
+        Ok(n) if n >= config.max_groups_per_user => Some(TOO_MANY_GROUPS.to_string()),
+        Ok(_) => None,
+        Err(e) => Some(format!("ERR: DB error: {}", e)),
+    }
+}
+
+/// Create a group owned by `user_id`. The group row and the owner membership are
+/// written in one transaction, so a failure never leaves a group without members.
+pub async fn create_group(db: Arc<Database>, user_id: &str, group_name: &str, config: &ServerConfig) -> String {
     println!("[GROUPS] Create group '{}' by user {}", group_name, user_id);
+    match groups_of_user(&db, user_id).await {
+        Ok(n) if n >= config.max_groups_per_user => return TOO_MANY_GROUPS.to_string(),
+        Ok(_) => {}
+        Err(e) => return format!("ERR: DB error: {}", e),
+    }
     let group_id = uuid::Uuid::new_v4().to_string();
     let created_at = chrono::Utc::now().timestamp();
-    let tx = db.pool.begin().await;
-    match tx {
-        Ok(mut tx) => {
-            let res = sqlx::query("INSERT INTO groups (id, name, created_by, created_at) VALUES (?, ?, ?, ?)")
-                .bind(&group_id)
-                .bind(group_name)
-                .bind(user_id)
-                .bind(created_at)
-                .execute(&mut *tx)
-                .await;
-            if let Err(e) = res {
-                println!("[GROUPS] Error creating group: {}", e);
-                return format!("ERR: Could not create group: {}", e);
-            }
-            let res2 = sqlx::query("INSERT INTO group_members (group_id, user_id, joined_at) VALUES (?, ?, ?)")
-                .bind(&group_id)
-                .bind(user_id)
-                .bind(created_at)
-                .execute(&mut *tx)
-                .await;
-            if let Err(e) = res2 {
-                println!("[GROUPS] Error adding creator as member: {}", e);
-                return format!("ERR: Could not add creator as member: {}", e);
-            }
-            tx.commit().await.ok();
-            println!("[GROUPS] Group '{}' created with id {}", group_name, group_id);
-            format!("OK: Group '{}' created", group_name)
-        }
+    let mut tx = match db.pool.begin().await {
+        Ok(tx) => tx,
         Err(e) => {
             println!("[GROUPS] Error starting transaction: {}", e);
-            format!("ERR: Could not create group: {}", e)
+            return format!("ERR: Could not create group: {}", e);
         }
+    };
+
+    // Returning before `commit` drops the transaction, which rolls back the group row
+    if let Err(e) = sqlx::query("INSERT INTO groups (id, name, created_by, created_at) VALUES (?, ?, ?, ?)")
+        .bind(&group_id)
+        .bind(group_name)
+        .bind(user_id)
+        .bind(created_at)
+        .execute(&mut *tx)
+        .await
+    {
+        println!("[GROUPS] Error creating group: {}", e);
+        return format!("ERR: Could not create group: {}", e);
+    }
+
+    if let Err(e) = sqlx::query("INSERT INTO group_members (group_id, user_id, joined_at, role) VALUES (?, ?, ?, 'owner')")
+        .bind(&group_id)
+        .bind(user_id)
+        .bind(created_at)
+        .execute(&mut *tx)
+        .await
+    {
+        println!("[GROUPS] Error adding creator as member: {}", e);
+        return format!("ERR: Could not add creator as member: {}", e);
+    }
+
+    if let Err(e) = tx.commit().await {
+        println!("[GROUPS] Error committing group creation: {}", e);
+        return format!("ERR: Could not create group: {}", e);
     }
+    println!("[GROUPS] Group '{}' created with id {}", group_name, group_id);
+    format!("OK: Group '{}' created", group_name)
 }
 
-pub async fn create_group_with_participants(db: Arc<Database>, user_id: &str, group_name: &str, participants: Option<&str>) -> String {
-    println!("[GROUPS] Create group '{}' by user {} with participants: {:?}", group_name, user_id, participants);
+/// Create a group with its members in one transaction: the creator joins as `owner`,
+/// every participant as `member`. Unknown usernames abort the whole creation.
+pub async fn create_group_with_members(db: Arc<Database>, user_id: &str, group_name: &str, participant_usernames: &[&str], description: &str, config: &ServerConfig) -> String {
+    println!("[GROUPS] Create group '{}' by user {} with members: {:?}", group_name, user_id, participant_usernames);
+    let description = description.trim();
+    if description.chars().count() > MAX_GROUP_DESCRIPTION_CHARS {
+        return DESCRIPTION_TOO_LONG.to_string();
+    }
+    match groups_of_user(&db, user_id).await {
+        Ok(n) if n >= config.max_groups_per_user => return TOO_MANY_GROUPS.to_string(),
+        Ok(_) => {}
+        Err(e) => return format!("ERR: DB error: {}", e),
+    }
+    // The creator takes one of the seats
+    if participant_usernames.iter().filter(|u| !u.trim().is_empty()).count() + 1 > config.max_group_size {
+        return GROUP_FULL.to_string();
+    }
     let group_id = uuid::Uuid::new_v4().to_string();
     let created_at = chrono::Utc::now().timestamp();
-    let tx = db.pool.begin().await;
-    match tx {
-        Ok(mut tx) => {
-            // Create group
-            let res = sqlx::query("INSERT INTO groups (id, name, created_by, created_at) VALUES (?, ?, ?, ?)")
-                .bind(&group_id)
-                .bind(group_name)
-                .bind(user_id)
-                .bind(created_at)
-                .execute(&mut *tx)
-                .await;
-            if let Err(e) = res {
-                println!("[GROUPS] Error creating group: {}", e);
-                return format!("ERR: Could not create group: {}", e);
-            }
-            
-            // Add creator as member
-            let res2 = sqlx::query("INSERT INTO group_members (group_id, user_id, joined_at) VALUES (?, ?, ?)")
-                .bind(&group_id)
-                .bind(user_id)
-                .bind(created_at)
-                .execute(&mut *tx)
-                .await;
-            if let Err(e) = res2 {
-                println!("[GROUPS] Error adding creator as member: {}", e);
-                return format!("ERR: Could not add creator as member: {}", e);
-            }
-            
-            // Send invites to participants if provided (don't add them directly)
-            if let Some(participants_str) = participants {
-                let participant_usernames: Vec<&str> = participants_str.split(',').collect();
-                for username in participant_usernames {
-                    let username = username.trim();
-                    if !username.is_empty() && username != user_id {
-                        // Get user_id from username
-                        if let Ok(Some(row)) = sqlx::query("SELECT id FROM users WHERE username = ?")
-                            .bind(username)
-                            .fetch_optional(&mut *tx)
-                            .await
-                        {
-                            let participant_id: String = row.get("id");
-                            // Create invite instead of adding directly to group
-                            let _ = sqlx::query("INSERT INTO group_invites (group_id, invited_user_id, invited_by, created_at, status) VALUES (?, ?, ?, ?, 'pending')")
-                                .bind(&group_id)
-                                .bind(&participant_id)
-                                .bind(user_id)
-                                .bind(created_at)
-                                .execute(&mut *tx)
-                                .await;
-                            println!("[GROUPS] Sent invite to participant {} for group {}", username, group_id);
-                        }
-                    }
-                }
-            }
-            
-            tx.commit().await.ok();
-            println!("[GROUPS] Group '{}' created with id {}", group_name, group_id);
-            format!("OK: Group '{}' created with ID: {}", group_name, group_id)
-        }
+    let mut tx = match db.pool.begin().await {
+        Ok(tx) => tx,
         Err(e) => {
             println!("[GROUPS] Error starting transaction: {}", e);
-            format!("ERR: Could not create group: {}", e)
+            return format!("ERR: Could not create group: {}", e);
         }
+    };
+
+    if let Err(e) = sqlx::query("INSERT INTO groups (id, name, created_by, created_at, description) VALUES (?, ?, ?, ?, ?)")
+        .bind(&group_id)
+        .bind(group_name)
+        .bind(user_id)
+        .bind(created_at)
+        .bind(description)
+        .execute(&mut *tx)
+        .await
+    {
+        println!("[GROUPS] Error creating group: {}", e);
+        return format!("ERR: Could not create group: {}", e);
     }
+
+    if let Err(e) = sqlx::query("INSERT INTO group_members (group_id, user_id, joined_at, role) VALUES (?, ?, ?, 'owner')")
+        .bind(&group_id)
+        .bind(user_id)
+        .bind(created_at)
+        .execute(&mut *tx)
+        .await
+    {
+        println!("[GROUPS] Error adding creator as owner: {}", e);
+        return format!("ERR: Could not add creator as member: {}", e);
+    }
+
+    for username in participant_usernames.iter().map(|u| u.trim()).filter(|u| !u.is_empty()) {
+        let participant_id = match sqlx::query("SELECT id FROM users WHERE username = ?")
+            .bind(username)
+            .fetch_optional(&mut *tx)
+            .await
+        {
+            Ok(Some(row)) => row.get::<String,_>("id"),
+            Ok(None) => return format!("ERR: User {} not found", username),
+            Err(e) => return format!("ERR: Database error: {}", e),
+        };
+        if participant_id == user_id {
+            continue;
+        }
+        if let Err(e) = sqlx::query("INSERT OR IGNORE INTO group_members (group_id, user_id, joined_at, role) VALUES (?, ?, ?, 'member')")
+            .bind(&group_id)
+            .bind(&participant_id)
+            .bind(created_at)
+            .execute(&mut *tx)
+            .await
+        {
+            println!("[GROUPS] Error adding member {}: {}", username, e);
+            return format!("ERR: Could not add member {}: {}", username, e);
+        }
+    }
+
+    if let Err(e) = tx.commit().await {
+        println!("[GROUPS] Error committing group creation: {}", e);
+        return format!("ERR: Could not create group: {}", e);
+    }
+    println!("[GROUPS] Group '{}' created with id {}", group_name, group_id);
+    format!("OK: Group '{}' created with ID: {}", group_name, group_id)
 }
+
 pub async fn my_groups(db: Arc<Database>, user_id: &str) -> String {
     println!("[GROUPS] List groups for user {}", user_id);
     let rows = sqlx::query("SELECT g.id, g.name FROM groups g JOIN group_members m ON g.id = m.group_id WHERE m.user_id = ?")
@@ -128,7 +209,7 @@ pub async fn my_groups(db: Arc<Database>, user_id: &str) -> String {
     }
 }
 
-pub async fn invite_user_to_group(db: Arc<Database>, from_user_id: &str, to_username: &str, group_id: &str) -> String {
+pub async fn invite_user_to_group(db: Arc<Database>, from_user_id: &str, to_username: &str, group_id: &str, config: &ServerConfig) -> String {
     println!("[GROUPS] Invite {} to group '{}' by {}", to_username, group_id, from_user_id);
     
     // Verify group exists
@@ -177,9 +258,11 @@ pub async fn invite_user_to_group(db: Arc<Database>, from_user_id: &str, to_user
     }
     
     // Check if there's already a pending invite
-    let existing_invite = sqlx::query("SELECT 1 FROM group_invites WHERE group_id = ? AND invited_user_id = ? AND status = 'pending'")
+    let now = chrono::Utc::now().timestamp();
+    let existing_invite = sqlx::query("SELECT 1 FROM group_invites WHERE group_id = ? AND invited_user_id = ? AND status = 'pending' AND (expires_at IS NULL OR expires_at > ?)")
         .bind(group_id)
         .bind(&to_user_id)
+        .bind(now)
         .fetch_optional(&db.pool)
         .await
         .ok()
@@ -190,12 +273,14 @@ pub async fn invite_user_to_group(db: Arc<Database>, from_user_id: &str, to_user
     }
     
     // Create group invite
-    let created_at = chrono::Utc::now().timestamp();
-    let res = sqlx::query("INSERT INTO group_invites (group_id, invited_user_id, invited_by, created_at, status) VALUES (?, ?, ?, ?, 'pending')")
+    let created_at = now;
+    let expires_at = created_at + config.invite_expiry_hours * 3600;
+    let res = sqlx::query("INSERT INTO group_invites (group_id, invited_user_id, invited_by, created_at, status, expires_at) VALUES (?, ?, ?, ?, 'pending', ?)")
         .bind(group_id)
         .bind(&to_user_id)
         .bind(from_user_id)
         .bind(created_at)
+        .bind(expires_at)
         .execute(&db.pool)
         .await;
     match res {
@@ -210,16 +295,32 @@ pub async fn invite_user_to_group(db: Arc<Database>, from_user_id: &str, to_user
     }
 }
 
-pub async fn get_group_members(db: Arc<Database>, group_id: &str) -> String {
+/// Members with their role, owner first, then admins, then plain members, each by username:
+/// "OK: Members: alice:owner, carol:admin, bob:member". Only members of the group may list them.
+pub async fn list_members(db: Arc<Database>, user_id: &str, group_id: &str) -> String {
     println!("[GROUPS] Get members for group {}", group_id);
-    let rows = sqlx::query("SELECT u.username FROM group_members gm JOIN users u ON gm.user_id = u.id WHERE gm.group_id = ?")
+    let is_member = sqlx::query("SELECT 1 FROM group_members WHERE group_id = ? AND user_id = ?")
+        .bind(group_id)
+        .bind(user_id)
+        .fetch_optional(&db.pool)
+        .await;
+    match is_member {
+        Ok(Some(_)) => {}
+        Ok(None) => return "ERR: Not a group member".to_string(),
+        Err(e) => return format!("ERR: DB error: {}", e),
+    }
+    let rows = sqlx::query(
+        "SELECT u.username, gm.role FROM group_members gm JOIN users u ON gm.user_id = u.id WHERE gm.group_id = ? \
+         ORDER BY CASE gm.role WHEN 'owner' THEN 0 WHEN 'admin' THEN 1 ELSE 2 END, u.username")
         .bind(group_id)
         .fetch_all(&db.pool)
         .await;
     match rows {
         Ok(rows) => {
-            let members: Vec<String> = rows.iter().map(|r| r.get::<String,_>("username")).collect();
-            format!("OK: Group members: {}", members.join(", "))
+            let members: Vec<String> = rows.iter()
+                .map(|r| format!("{}:{}", r.get::<String,_>("username"), r.get::<String,_>("role")))
+                .collect();
+            format!("OK: Members: {}", members.join(", "))
         }
         Err(e) => {
             println!("[GROUPS] Error getting group members: {}", e);
@@ -228,19 +329,52 @@ pub async fn get_group_members(db: Arc<Database>, group_id: &str) -> String {
     }
 }
 
-pub async fn my_invites(db: Arc<Database>, user_id: &str) -> String {
+/// Members with their role and presence: "OK: Members: alice:owner:1, bob:member:0".
+/// Only members of the group may list them.
+pub async fn group_members_status(db: Arc<Database>, user_id: &str, group_id: &str) -> String {
+    let is_member = sqlx::query("SELECT 1 FROM group_members WHERE group_id = ? AND user_id = ?")
+        .bind(group_id)
+        .bind(user_id)
+        .fetch_optional(&db.pool)
+        .await
+        .ok()
+        .flatten()
+        .is_some();
+    if !is_member {
+        return "ERR: Not a group member".to_string();
+    }
+    let rows = sqlx::query("SELECT u.username, gm.role, u.is_online FROM group_members gm JOIN users u ON gm.user_id = u.id WHERE gm.group_id = ? ORDER BY u.username")
+        .bind(group_id)
+        .fetch_all(&db.pool)
+        .await;
+    match rows {
+        Ok(rows) => {
+            let members: Vec<String> = rows.iter().map(|r| {
+                format!("{}:{}:{}", r.get::<String,_>("username"), r.get::<String,_>("role"), r.get::<i64,_>("is_online"))
+            }).collect();
+            format!("OK: Members: {}", members.join(", "))
+        }
+        Err(e) => format!("ERR: DB error: {}", e),
+    }
+}
+
+pub async fn my_group_invites(db: Arc<Database>, user_id: &str) -> String {
     println!("[GROUPS] List invites for user {}", user_id);
-    let rows = sqlx::query("SELECT gi.id, g.name as group_name, u.username as invited_by FROM group_invites gi JOIN groups g ON gi.group_id = g.id JOIN users u ON gi.invited_by = u.id WHERE gi.invited_user_id = ? AND gi.status = 'pending'")
+    let now = chrono::Utc::now().timestamp();
+    let rows = sqlx::query("SELECT gi.id, g.name as group_name, u.username as invited_by, gi.expires_at FROM group_invites gi JOIN groups g ON gi.group_id = g.id JOIN users u ON gi.invited_by = u.id WHERE gi.invited_user_id = ? AND gi.status = 'pending' AND (gi.expires_at IS NULL OR gi.expires_at > ?)")
         .bind(user_id)
+        .bind(now)
         .fetch_all(&db.pool)
         .await;
     match rows {
         Ok(rows) => {
+            // Format: id:group_name:invited_by:expires_at (expires_at empty for invites without expiry)
             let invites: Vec<String> = rows.iter().map(|r| {
-                format!("{}:{}:{}", 
+                format!("{}:{}:{}:{}", 
                     r.get::<i64,_>("id"), 
                     r.get::<String,_>("group_name"), 
-                    r.get::<String,_>("invited_by")
+                    r.get::<String,_>("invited_by"),
+                    r.get::<Option<i64>,_>("expires_at").map(|t| t.to_string()).unwrap_or_default()
                 )
             }).collect();
             // Remove duplicates by converting to HashSet and back
@@ -255,18 +389,46 @@ pub async fn my_invites(db: Arc<Database>, user_id: &str) -> String {
     }
 }
 
-pub async fn accept_invite(db: Arc<Database>, user_id: &str, invite_id: &str) -> String {
+pub async fn accept_group_invite(db: Arc<Database>, user_id: &str, invite_id: &str, config: &ServerConfig) -> String {
     println!("[GROUPS] Accept invite {} by user {}", invite_id, user_id);
-    // Trova invito
-    let row = sqlx::query("SELECT group_id FROM group_invites WHERE id = ? AND invited_user_id = ? AND status = 'pending'")
+    // Trova invito; only the invited user may accept it
+    let row = sqlx::query("SELECT group_id, expires_at, status FROM group_invites WHERE id = ? AND invited_user_id = ?")
         .bind(invite_id)
         .bind(user_id)
         .fetch_optional(&db.pool)
         .await;
-    let group_id = match row {
-        Ok(Some(row)) => row.get::<String,_>("group_id"),
+    let (group_id, expires_at, status) = match row {
+        Ok(Some(row)) => (row.get::<String,_>("group_id"), row.get::<Option<i64>,_>("expires_at"), row.get::<String,_>("status")),
         _ => return "ERR: Invite not found or already handled".to_string(),
     };
+    // Accepting again, or after joining another way, is not an error
+    let already_member = sqlx::query("SELECT 1 FROM group_members WHERE group_id = ? AND user_id = ?")
+        .bind(&group_id)
+        .bind(user_id)
+        .fetch_optional(&db.pool)
+        .await
+        .ok()
+        .flatten()
+        .is_some();
+    if already_member {
+        if status == "pending" {
+            let _ = sqlx::query("UPDATE group_invites SET status = 'accepted' WHERE id = ?")
+                .bind(invite_id)
+                .execute(&db.pool)
+                .await;
+        }
+        return "OK: Already a member".to_string();
+    }
+    if status != "pending" {
+        return "ERR: Invite not found or already handled".to_string();
+    }
+    if expires_at.is_some_and(|t| t <= chrono::Utc::now().timestamp()) {
+        return "ERR: Invite has expired".to_string();
+    }
+    // The invite stays pending when a limit is hit, so it can be accepted once there is room
+    if let Some(err) = membership_limit_error(&db, user_id, &group_id, config).await {
+        return err;
+    }
     // Aggiorna invito
     let res = sqlx::query("UPDATE group_invites SET status = 'accepted' WHERE id = ?")
         .bind(invite_id)
@@ -276,15 +438,27 @@ pub async fn accept_invite(db: Arc<Database>, user_id: &str, invite_id: &str) ->
         return "ERR: Could not update invite".to_string();
     }
     // Aggiungi a group_members
+    let mut tx = match db.pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return format!("ERR: DB error: {}", e),
+    };
     let joined_at = chrono::Utc::now().timestamp();
-    let res2 = sqlx::query("INSERT OR IGNORE INTO group_members (group_id, user_id, joined_at) VALUES (?, ?, ?)")
+    let res2 = sqlx::query("INSERT OR IGNORE INTO group_members (group_id, user_id, role, joined_at) VALUES (?, ?, 'member', ?)")
         .bind(&group_id)
         .bind(user_id)
         .bind(joined_at)
-        .execute(&db.pool)
+        .execute(&mut *tx)
         .await;
-    match res2 {
-        Ok(_) => {
+    let res2 = match res2 {
+        Ok(r) if r.rows_affected() > 0 => record_join(&mut tx, &group_id, user_id).await,
+        Ok(_) => Ok(()),
+        Err(e) => Err(e),
+    };
+    match res2.and(Ok(tx)) {
+        Ok(tx) => {
+            if let Err(e) = tx.commit().await {
+                return format!("ERR: DB error: {}", e);
+            }
             println!("[GROUPS] User {} joined group {} via invite", user_id, group_id);
             "OK: Invite accepted".to_string()
         }
@@ -295,8 +469,20 @@ pub async fn accept_invite(db: Arc<Database>, user_id: &str, invite_id: &str) ->
     }
 }
 
-pub async fn reject_invite(db: Arc<Database>, user_id: &str, invite_id: &str) -> String {
+pub async fn reject_group_invite(db: Arc<Database>, user_id: &str, invite_id: &str) -> String {
     println!("[GROUPS] Reject invite {} by user {}", invite_id, user_id);
+    let expired = sqlx::query("SELECT 1 FROM group_invites WHERE id = ? AND invited_user_id = ? AND status = 'pending' AND expires_at <= ?")
+        .bind(invite_id)
+        .bind(user_id)
+        .bind(chrono::Utc::now().timestamp())
+        .fetch_optional(&db.pool)
+        .await
+        .ok()
+        .flatten()
+        .is_some();
+    if expired {
+        return "ERR: Invite has expired".to_string();
+    }
     let res = sqlx::query("UPDATE group_invites SET status = 'rejected' WHERE id = ? AND invited_user_id = ? AND status = 'pending'")
         .bind(invite_id)
         .bind(user_id)
@@ -314,27 +500,46 @@ pub async fn reject_invite(db: Arc<Database>, user_id: &str, invite_id: &str) ->
     }
 }
 
-pub async fn join_group(db: Arc<Database>, user_id: &str, group_name: &str) -> String {
-    println!("[GROUPS] User {} joins group '{}'", user_id, group_name);
-    // Trova group_id
-    let group_row = sqlx::query("SELECT id FROM groups WHERE name = ?")
-        .bind(group_name)
+pub async fn join_group(db: Arc<Database>, user_id: &str, group_ident: &str, config: &ServerConfig) -> String {
+    println!("[GROUPS] User {} joins group '{}'", user_id, group_ident);
+    // Trova il gruppo per id, poi per nome
+    let group_row = sqlx::query("SELECT id, is_public FROM groups WHERE id = ?1 UNION ALL SELECT id, is_public FROM groups WHERE name = ?1 LIMIT 1")
+        .bind(group_ident)
         .fetch_optional(&db.pool)
         .await;
-    let group_id = match group_row {
-        Ok(Some(row)) => row.get::<String,_>("id"),
+    let (group_id, is_public) = match group_row {
+        Ok(Some(row)) => (row.get::<String,_>("id"), row.get::<i64,_>("is_public") != 0),
         _ => return "ERR: Group not found".to_string(),
     };
+    // I gruppi privati si raggiungono solo tramite invito
+    if !is_public {
+        return "ERR: This group requires an invite".to_string();
+    }
+    if let Some(err) = membership_limit_error(&db, user_id, &group_id, config).await {
+        return err;
+    }
     // Aggiungi a group_members
+    let mut tx = match db.pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return format!("ERR: DB error: {}", e),
+    };
     let joined_at = chrono::Utc::now().timestamp();
     let res = sqlx::query("INSERT OR IGNORE INTO group_members (group_id, user_id, joined_at) VALUES (?, ?, ?)")
         .bind(&group_id)
         .bind(user_id)
         .bind(joined_at)
-        .execute(&db.pool)
+        .execute(&mut *tx)
         .await;
-    match res {
-        Ok(_) => {
+    let res = match res {
+        Ok(r) if r.rows_affected() > 0 => record_join(&mut tx, &group_id, user_id).await,
+        Ok(_) => Ok(()),
+        Err(e) => Err(e),
+    };
+    match res.and(Ok(tx)) {
+        Ok(tx) => {
+            if let Err(e) = tx.commit().await {
+                return format!("ERR: DB error: {}", e);
+            }
             println!("[GROUPS] User {} joined group {}", user_id, group_id);
             "OK: Joined group".to_string()
         }
@@ -345,6 +550,583 @@ pub async fn join_group(db: Arc<Database>, user_id: &str, group_name: &str) -> S
     }
 }
 
+/// One page (1-based) of public groups whose name or description contains `query`, biggest first:
+/// "OK: Public groups page <page>/<pages> (<total> total):\n<id>:<member_count>:<joined 0|1>:<name> <description>"
+pub async fn list_public_groups(db: Arc<Database>, user_id: &str, query: &str, page: i64) -> String {
+    if page < 1 {
+        return "ERR: Page must be >= 1".to_string();
+    }
+    let pattern = format!("%{}%", query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+    let filter = r"g.is_public = 1 AND (g.name LIKE ?1 ESCAPE '\' OR g.description LIKE ?1 ESCAPE '\')";
+    let total: i64 = match sqlx::query(&format!("SELECT COUNT(*) AS n FROM groups g WHERE {}", filter))
+        .bind(&pattern)
+        .fetch_one(&db.pool)
+        .await
+    {
+        Ok(row) => row.get("n"),
+        Err(e) => return format!("ERR: DB error: {}", e),
+    };
+    let rows = sqlx::query(&format!(r#"
+        SELECT g.id, g.name, COALESCE(g.description, '') AS description,
+               (SELECT COUNT(*) FROM group_members m WHERE m.group_id = g.id) AS member_count,
+               EXISTS(SELECT 1 FROM group_members m WHERE m.group_id = g.id AND m.user_id = ?2) AS joined
+        FROM groups g
+        WHERE {}
+        ORDER BY member_count DESC, g.name
+        LIMIT ?3 OFFSET ?4
+    "#, filter))
+        .bind(&pattern)
+        .bind(user_id)
+        .bind(PUBLIC_GROUPS_PAGE_SIZE)
+        .bind((page - 1) * PUBLIC_GROUPS_PAGE_SIZE)
+        .fetch_all(&db.pool)
+        .await;
+    match rows {
+        Ok(rows) => {
+            let groups: Vec<String> = rows.iter().map(|r| {
+                format!("{}:{}:{}:{} {}",
+                    r.get::<String,_>("id"),
+                    r.get::<i64,_>("member_count"),
+                    r.get::<i64,_>("joined"),
+                    r.get::<String,_>("name"),
+                    r.get::<String,_>("description"))
+            }).collect();
+            let pages = ((total + PUBLIC_GROUPS_PAGE_SIZE - 1) / PUBLIC_GROUPS_PAGE_SIZE).max(1);
+            format!("OK: Public groups page {}/{} ({} total):\n{}", page, pages, total, groups.join("\n"))
+        }
+        Err(e) => format!("ERR: DB error: {}", e),
+    }
+}
+
+/// Create a shareable code for joining `group_id` (admins only), valid for `expires_secs`
+/// seconds and `max_uses` joins when given and non-zero: "OK: Invite code: <code>"
+pub async fn generate_invite_code(db: Arc<Database>, user_id: &str, group_id: &str, expires_secs: Option<i64>, max_uses: Option<i64>) -> String {
+    if expires_secs.is_some_and(|secs| secs < 0) || max_uses.is_some_and(|uses| uses < 0) {
+        return "ERR: Expiry and maximum uses cannot be negative".to_string();
+    }
+    // 0 means "no limit", so a use cap can be given without an expiry
+    let expires_secs = expires_secs.filter(|&secs| secs > 0);
+    let max_uses = max_uses.filter(|&uses| uses > 0);
+    if let Err(e) = require_admin(&db, group_id, user_id).await {
+        return e;
+    }
+    let expires_at = expires_secs.map(|secs| chrono::Utc::now().timestamp() + secs);
+    // A collision with an existing code is astronomically unlikely, but costs nothing to retry
+    for _ in 0..3 {
+        let code: String = rand::rngs::OsRng
+            .sample_iter(&rand::distributions::Alphanumeric)
+            .take(INVITE_CODE_CHARS)
+            .map(char::from)
+            .collect();
+        let res = sqlx::query("INSERT OR IGNORE INTO group_invite_codes (code, group_id, created_by, expires_at, max_uses) VALUES (?, ?, ?, ?, ?)")
+            .bind(&code)
+            .bind(group_id)
+            .bind(user_id)
+            .bind(expires_at)
+            .bind(max_uses)
+            .execute(&db.pool)
+            .await;
+        match res {
+            Ok(r) if r.rows_affected() > 0 => return format!("OK: Invite code: {}", code),
+            Ok(_) => continue,
+            Err(e) => return format!("ERR: DB error: {}", e),
+        }
+    }
+    "ERR: Could not generate an invite code".to_string()
+}
+
+/// Join the group of invite `code` if it has not expired or run out of uses: "OK: Joined group <id> <name>"
+pub async fn join_by_code(db: Arc<Database>, user_id: &str, code: &str, config: &ServerConfig) -> String {
+    let row = sqlx::query("SELECT c.group_id, c.expires_at, c.max_uses, c.use_count, g.name FROM group_invite_codes c JOIN groups g ON g.id = c.group_id WHERE c.code = ?")
+        .bind(code)
+        .fetch_optional(&db.pool)
+        .await;
+    let (group_id, group_name) = match row {
+        Ok(Some(r)) => {
+            if r.get::<Option<i64>,_>("expires_at").is_some_and(|ts| ts <= chrono::Utc::now().timestamp()) {
+                return "ERR: Invite code has expired".to_string();
+            }
+            if r.get::<Option<i64>,_>("max_uses").is_some_and(|max| r.get::<i64,_>("use_count") >= max) {
+                return "ERR: Invite code has been used up".to_string();
+            }
+            (r.get::<String,_>("group_id"), r.get::<String,_>("name"))
+        }
+        Ok(None) => return "ERR: Invalid invite code".to_string(),
+        Err(e) => return format!("ERR: DB error: {}", e),
+    };
+    let already = sqlx::query("SELECT 1 FROM group_members WHERE group_id = ? AND user_id = ?")
+        .bind(&group_id)
+        .bind(user_id)
+        .fetch_optional(&db.pool)
+        .await
+        .ok()
+        .flatten()
+        .is_some();
+    if already {
+        return "ERR: Already a member of this group".to_string();
+    }
+    if let Some(err) = membership_limit_error(&db, user_id, &group_id, config).await {
+        return err;
+    }
+
+    let mut tx = match db.pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return format!("ERR: DB error: {}", e),
+    };
+    // Re-check the limits while counting the use, so concurrent joins cannot overrun max_uses
+    let counted = sqlx::query("UPDATE group_invite_codes SET use_count = use_count + 1 WHERE code = ? AND (max_uses IS NULL OR use_count < max_uses) AND (expires_at IS NULL OR expires_at > ?)")
+        .bind(code)
+        .bind(chrono::Utc::now().timestamp())
+        .execute(&mut *tx)
+        .await;
+    match counted {
+        Ok(r) if r.rows_affected() > 0 => {}
+        Ok(_) => return "ERR: Invite code has been used up".to_string(),
+        Err(e) => return format!("ERR: DB error: {}", e),
+    }
+    if let Err(e) = sqlx::query("INSERT INTO group_members (group_id, user_id, joined_at) VALUES (?, ?, ?)")
+        .bind(&group_id)
+        .bind(user_id)
+        .bind(chrono::Utc::now().timestamp())
+        .execute(&mut *tx)
+        .await
+    {
+        return format!("ERR: Could not join group: {}", e);
+    }
+    if let Err(e) = record_join(&mut tx, &group_id, user_id).await {
+        return format!("ERR: DB error: {}", e);
+    }
+    if let Err(e) = tx.commit().await {
+        return format!("ERR: DB error: {}", e);
+    }
+    println!("[GROUPS] User {} joined group {} with an invite code", user_id, group_id);
+    format!("OK: Joined group {} {}", group_id, group_name)
+}
+
+/// Make a group discoverable through `/search_groups` (and joinable without an invite), or hide it again
+pub async fn set_group_public(db: Arc<Database>, user_id: &str, group_id: &str, public: bool) -> String {
+    let role = sqlx::query("SELECT role FROM group_members WHERE group_id = ? AND user_id = ?")
+        .bind(group_id)
+        .bind(user_id)
+        .fetch_optional(&db.pool)
+        .await;
+    match role {
+        Ok(Some(row)) if row.get::<String,_>("role") == "owner" => {}
+        Ok(Some(_)) => return "ERR: Only the group owner can change visibility".to_string(),
+        Ok(None) => return "ERR: Not a group member".to_string(),
+        Err(e) => return format!("ERR: DB error: {}", e),
+    }
+    match sqlx::query("UPDATE groups SET is_public = ? WHERE id = ?")
+        .bind(public as i64)
+        .bind(group_id)
+        .execute(&db.pool)
+        .await
+    {
+        Ok(_) => {
+            println!("[GROUPS] Group {} is_public={} (by {})", group_id, public, user_id);
+            format!("OK: Group is now {}", if public { "public" } else { "private" })
+        }
+        Err(e) => format!("ERR: DB error: {}", e),
+    }
+}
+
+/// Ok if `user_id` administers `group_id`; the owner counts as an admin
+pub async fn require_admin(db: &Arc<Database>, group_id: &str, user_id: &str) -> Result<(), String> {
+    let role = sqlx::query("SELECT role FROM group_members WHERE group_id = ? AND user_id = ?")
+        .bind(group_id)
+        .bind(user_id)
+        .fetch_optional(&db.pool)
+        .await;
+    match role {
+        Ok(Some(row)) if matches!(row.get::<String,_>("role").as_str(), "owner" | "admin") => Ok(()),
+        Ok(_) => Err("ERR: Not a group admin".to_string()),
+        Err(e) => Err(format!("ERR: DB error: {}", e)),
+    }
+}
+
+/// Id and role of `username` in `group_id`
+async fn member_role(db: &Arc<Database>, group_id: &str, username: &str) -> Result<(String, String), String> {
+    let row = sqlx::query("SELECT gm.user_id, gm.role FROM group_members gm JOIN users u ON u.id = gm.user_id WHERE gm.group_id = ? AND u.username = ?")
+        .bind(group_id)
+        .bind(username)
+        .fetch_optional(&db.pool)
+        .await;
+    match row {
+        Ok(Some(r)) => Ok((r.get("user_id"), r.get("role"))),
+        Ok(None) => Err("ERR: User is not a group member".to_string()),
+        Err(e) => Err(format!("ERR: DB error: {}", e)),
+    }
+}
+
+async fn set_member_role(db: &Arc<Database>, group_id: &str, member_id: &str, role: &str) -> Result<(), String> {
+    sqlx::query("UPDATE group_members SET role = ? WHERE group_id = ? AND user_id = ?")
+        .bind(role)
+        .bind(group_id)
+        .bind(member_id)
+        .execute(&db.pool)
+        .await
+        .map(|_| ())
+        .map_err(|e| format!("ERR: DB error: {}", e))
+}
+
+/// Promote member `username` to admin of `group_id`
+pub async fn set_group_admin(db: Arc<Database>, user_id: &str, group_id: &str, username: &str) -> String {
+    if let Err(e) = require_admin(&db, group_id, user_id).await {
+        return e;
+    }
+    let (member_id, role) = match member_role(&db, group_id, username).await {
+        Ok(member) => member,
+        Err(e) => return e,
+    };
+    if role != "member" {
+        return format!("ERR: {} is already an admin", username);
+    }
+    match set_member_role(&db, group_id, &member_id, "admin").await {
+        Ok(()) => {
+            println!("[GROUPS] {} promoted to admin of {} by {}", username, group_id, user_id);
+            format!("OK: {} is now an admin", username)
+        }
+        Err(e) => e,
+    }
+}
+
+/// Turn admin `username` of `group_id` back into a plain member; the owner cannot be demoted
+pub async fn demote_group_admin(db: Arc<Database>, user_id: &str, group_id: &str, username: &str) -> String {
+    if let Err(e) = require_admin(&db, group_id, user_id).await {
+        return e;
+    }
+    let (member_id, role) = match member_role(&db, group_id, username).await {
+        Ok(member) => member,
+        Err(e) => return e,
+    };
+    match role.as_str() {
+        "admin" => {}
+        "owner" => return "ERR: The group owner cannot be demoted".to_string(),
+        _ => return format!("ERR: {} is not an admin", username),
+    }
+    match set_member_role(&db, group_id, &member_id, "member").await {
+        Ok(()) => {
+            println!("[GROUPS] {} demoted in {} by {}", username, group_id, user_id);
+            format!("OK: {} is no longer an admin", username)
+        }
+        Err(e) => e,
+    }
+}
+
+/// Rename `group_id` (admins only) and build a `group_renamed` system event for every member,
+/// so their clients update the group name without reloading their groups.
+pub async fn rename_group(db: Arc<Database>, user_id: &str, group_id: &str, new_name: &str) -> (String, Vec<WebSocketMessage>) {
+    if let Err(e) = require_admin(&db, group_id, user_id).await {
+        return (e, vec![]);
+    }
+    let new_name = new_name.trim();
+    if !(MIN_GROUP_NAME_CHARS..=MAX_GROUP_NAME_CHARS).contains(&new_name.chars().count()) {
+        return (format!("ERR: Group name must be {}-{} characters", MIN_GROUP_NAME_CHARS, MAX_GROUP_NAME_CHARS), vec![]);
+    }
+    if let Err(e) = sqlx::query("UPDATE groups SET name = ? WHERE id = ?")
+        .bind(new_name)
+        .bind(group_id)
+        .execute(&db.pool)
+        .await
+    {
+        return (format!("ERR: DB error: {}", e), vec![]);
+    }
+    println!("[GROUPS] Group {} renamed to '{}' by {}", group_id, new_name, user_id);
+
+    let content = serde_json::json!({ "type": "group_renamed", "group_id": group_id, "new_name": new_name });
+    let events = system_events(user_id, group_id, member_ids(&db, group_id).await, &content);
+    (format!("OK: Group renamed to {}", new_name), events)
+}
+
+/// Delete `group_id` with its messages, members and invites in one transaction (creator only),
+/// and build a `group_deleted` system event for every former member.
+pub async fn delete_group(db: Arc<Database>, user_id: &str, group_id: &str) -> (String, Vec<WebSocketMessage>) {
+    let created_by = sqlx::query("SELECT created_by FROM groups WHERE id = ?")
+        .bind(group_id)
+        .fetch_optional(&db.pool)
+        .await;
+    match created_by {
+        Ok(Some(row)) if row.get::<String,_>("created_by") == user_id => {}
+        Ok(Some(_)) => return ("ERR: Only the group creator can delete the group".to_string(), vec![]),
+        Ok(None) => return ("ERR: Group not found".to_string(), vec![]),
+        Err(e) => return (format!("ERR: DB error: {}", e), vec![]),
+    }
+    let members = member_ids(&db, group_id).await;
+
+    let mut tx = match db.pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return (format!("ERR: DB error: {}", e), vec![]),
+    };
+    // Rows keyed by message go before the messages they point to
+    let statements = [
+        "DELETE FROM message_reactions WHERE message_id IN (SELECT id FROM encrypted_messages WHERE chat_id = 'group:' || ?1)",
+        "DELETE FROM pinned_messages WHERE group_id = ?1",
+        "DELETE FROM message_receipts WHERE message_id IN (SELECT id FROM encrypted_messages WHERE chat_id = 'group:' || ?1)",
+        "DELETE FROM encrypted_messages WHERE chat_id = 'group:' || ?1",
+        "DELETE FROM read_receipts WHERE chat_id = 'group:' || ?1",
+        "DELETE FROM archived_chats WHERE chat_id = 'group:' || ?1",
+        "DELETE FROM deleted_chats WHERE chat_id = 'group:' || ?1",
+        "DELETE FROM group_encryption_keys WHERE group_id = ?1",
+        "DELETE FROM group_members WHERE group_id = ?1",
+        "DELETE FROM group_invites WHERE group_id = ?1",
+        "DELETE FROM group_invite_codes WHERE group_id = ?1",
+        "DELETE FROM group_events WHERE group_id = ?1",
+        "DELETE FROM groups WHERE id = ?1",
+    ];
+    for sql in statements {
+        if let Err(e) = sqlx::query(sql).bind(group_id).execute(&mut *tx).await {
+            println!("[GROUPS] Error deleting group {}: {}", group_id, e);
+            return (format!("ERR: DB error: {}", e), vec![]);
+        }
+    }
+    if let Err(e) = tx.commit().await {
+        return (format!("ERR: DB error: {}", e), vec![]);
+    }
+    println!("[GROUPS] Group {} deleted by {}", group_id, user_id);
+
+    let content = serde_json::json!({ "type": "group_deleted", "group_id": group_id });
+    ("OK: Group deleted".to_string(), system_events(user_id, group_id, members, &content))
+}
+
+/// Remove plain member `username` from `group_id` (admins only), record it in `group_events`
+/// and build a `kicked` system event for the removed user.
+pub async fn kick_member(db: Arc<Database>, user_id: &str, group_id: &str, username: &str) -> (String, Vec<WebSocketMessage>) {
+    if let Err(e) = require_admin(&db, group_id, user_id).await {
+        return (e, vec![]);
+    }
+    let (member_id, role) = match member_role(&db, group_id, username).await {
+        Ok(member) => member,
+        Err(e) => return (e, vec![]),
+    };
+    if role != "member" {
+        return ("ERR: Cannot kick a group admin".to_string(), vec![]);
+    }
+
+    let mut tx = match db.pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return (format!("ERR: DB error: {}", e), vec![]),
+    };
+    if let Err(e) = sqlx::query("DELETE FROM group_members WHERE group_id = ? AND user_id = ?")
+        .bind(group_id)
+        .bind(&member_id)
+        .execute(&mut *tx)
+        .await
+    {
+        return (format!("ERR: DB error: {}", e), vec![]);
+    }
+    if let Err(e) = record_removal(&mut tx, group_id, "kicked", user_id, &member_id).await {
+        return (format!("ERR: DB error: {}", e), vec![]);
+    }
+    if let Err(e) = tx.commit().await {
+        return (format!("ERR: DB error: {}", e), vec![]);
+    }
+    println!("[GROUPS] {} kicked from {} by {}", username, group_id, user_id);
+
+    let content = serde_json::json!({ "type": "kicked", "group_id": group_id });
+    (format!("OK: {} was removed from the group", username), system_events(user_id, group_id, vec![member_id], &content))
+}
+
+/// Move `group_id` to its next key version after a membership change, so members who
+/// left cannot read new messages and new members get a fresh key. Returns the new version.
+async fn bump_key_version(tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>, group_id: &str) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar("UPDATE groups SET key_version = key_version + 1 WHERE id = ? RETURNING key_version")
+        .bind(group_id)
+        .fetch_one(&mut **tx)
+        .await
+}
+
+/// Move `group_id` to its next key version for the membership of `user_id`, just inserted,
+/// and stamp the membership with it so older messages are known to predate it
+async fn record_join(tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>, group_id: &str, user_id: &str) -> Result<(), sqlx::Error> {
+    let key_version = bump_key_version(tx, group_id).await?;
+    sqlx::query("UPDATE group_members SET key_version = ? WHERE group_id = ? AND user_id = ?")
+        .bind(key_version)
+        .bind(group_id)
+        .bind(user_id)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+/// Record in `group_events` that `user_id` left `group_id`, which also moves it to the next key version
+pub(crate) async fn record_departure(tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>, group_id: &str, user_id: &str) -> Result<(), sqlx::Error> {
+    record_removal(tx, group_id, "left", user_id, user_id).await
+}
+
+/// Move `group_id` to its next key version after `target_id`, already removed from it, was
+/// `event_type` (kicked or left) by `actor_id`, and record that in `group_events` with the version
+async fn record_removal(tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>, group_id: &str, event_type: &str, actor_id: &str, target_id: &str) -> Result<(), sqlx::Error> {
+    let key_version = bump_key_version(tx, group_id).await?;
+    sqlx::query("INSERT INTO group_events (group_id, event_type, actor_id, target_id, created_at, key_version) VALUES (?, ?, ?, ?, ?, ?)")
+        .bind(group_id)
+        .bind(event_type)
+        .bind(actor_id)
+        .bind(target_id)
+        .bind(chrono::Utc::now().timestamp())
+        .bind(key_version)
+        .execute(&mut **tx)
+        .await?;
+    Ok(())
+}
+
+/// Ids of the members of `group_id`
+async fn member_ids(db: &Arc<Database>, group_id: &str) -> Vec<String> {
+    sqlx::query("SELECT user_id FROM group_members WHERE group_id = ?")
+        .bind(group_id)
+        .fetch_all(&db.pool)
+        .await
+        .unwrap_or_default()
+        .iter()
+        .map(|r| r.get("user_id"))
+        .collect()
+}
+
+/// One `MessageType::System` event about `group_id` with `content` for each of `recipients`
+fn system_events(sender_id: &str, group_id: &str, recipients: Vec<String>, content: &serde_json::Value) -> Vec<WebSocketMessage> {
+    let content = content.to_string();
+    let now = chrono::Utc::now().timestamp();
+    recipients.into_iter()
+        .map(|recipient| WebSocketMessage {
+            id: uuid::Uuid::new_v4().to_string(),
+            message_type: MessageType::System,
+            sender: sender_id.to_string(),
+            target: group_id.to_string(),
+            content: content.clone(),
+            timestamp: now,
+            recipient_user_id: Some(recipient),
+        })
+        .collect()
+}
+
+/// Set the description of `group_id` (admins only); an empty text clears it
+pub async fn set_group_description(db: Arc<Database>, user_id: &str, group_id: &str, description: &str) -> String {
+    if let Err(e) = require_admin(&db, group_id, user_id).await {
+        return e;
+    }
+    let description = description.trim();
+    if description.chars().count() > MAX_GROUP_DESCRIPTION_CHARS {
+        return DESCRIPTION_TOO_LONG.to_string();
+    }
+    match sqlx::query("UPDATE groups SET description = ? WHERE id = ?")
+        .bind(description)
+        .bind(group_id)
+        .execute(&db.pool)
+        .await
+    {
+        Ok(_) => {
+            println!("[GROUPS] Description of {} updated by {}", group_id, user_id);
+            "OK: Group description updated".to_string()
+        }
+        Err(e) => format!("ERR: DB error: {}", e),
+    }
+}
+
+/// Pin message `message_id` of `group_id` (admins only) and build a `pins_changed`
+/// system event for every member, so their pinned banners refresh.
+pub async fn pin_message(db: Arc<Database>, user_id: &str, group_id: &str, message_id: i64) -> (String, Vec<WebSocketMessage>) {
+    if let Err(e) = require_admin(&db, group_id, user_id).await {
+        return (e, vec![]);
+    }
+    let in_group = sqlx::query("SELECT 1 FROM encrypted_messages WHERE id = ? AND chat_id = 'group:' || ?")
+        .bind(message_id)
+        .bind(group_id)
+        .fetch_optional(&db.pool)
+        .await;
+    match in_group {
+        Ok(Some(_)) => {}
+        Ok(None) => return ("ERR: Message not found".to_string(), vec![]),
+        Err(e) => return (format!("ERR: DB error: {}", e), vec![]),
+    }
+    let res = sqlx::query("INSERT OR IGNORE INTO pinned_messages (group_id, message_id, pinned_by, pinned_at) VALUES (?, ?, ?, ?)")
+        .bind(group_id)
+        .bind(message_id)
+        .bind(user_id)
+        .bind(chrono::Utc::now().timestamp())
+        .execute(&db.pool)
+        .await;
+    match res {
+        Ok(r) if r.rows_affected() == 0 => ("ERR: Message already pinned".to_string(), vec![]),
+        Ok(_) => {
+            println!("[GROUPS] Message {} pinned in {} by {}", message_id, group_id, user_id);
+            let content = serde_json::json!({ "type": "pins_changed", "group_id": group_id });
+            ("OK: Message pinned".to_string(), system_events(user_id, group_id, member_ids(&db, group_id).await, &content))
+        }
+        Err(e) => (format!("ERR: DB error: {}", e), vec![]),
+    }
+}
+
+/// Unpin message `message_id` of `group_id` (admins only); members get a `pins_changed` event
+pub async fn unpin_message(db: Arc<Database>, user_id: &str, group_id: &str, message_id: i64) -> (String, Vec<WebSocketMessage>) {
+    if let Err(e) = require_admin(&db, group_id, user_id).await {
+        return (e, vec![]);
+    }
+    let res = sqlx::query("DELETE FROM pinned_messages WHERE group_id = ? AND message_id = ?")
+        .bind(group_id)
+        .bind(message_id)
+        .execute(&db.pool)
+        .await;
+    match res {
+        Ok(r) if r.rows_affected() == 0 => ("ERR: Message is not pinned".to_string(), vec![]),
+        Ok(_) => {
+            println!("[GROUPS] Message {} unpinned in {} by {}", message_id, group_id, user_id);
+            let content = serde_json::json!({ "type": "pins_changed", "group_id": group_id });
+            ("OK: Message unpinned".to_string(), system_events(user_id, group_id, member_ids(&db, group_id).await, &content))
+        }
+        Err(e) => (format!("ERR: DB error: {}", e), vec![]),
+    }
+}
+
+/// Description of `group_id` as "OK: Description: <text>"; readable by members and,
+/// for public groups, by anyone deciding whether to join.
+pub async fn get_group_description(db: Arc<Database>, user_id: &str, group_id: &str) -> String {
+    let row = sqlx::query(
+        "SELECT g.description FROM groups g WHERE g.id = ? \
+         AND (g.is_public = 1 OR EXISTS (SELECT 1 FROM group_members gm WHERE gm.group_id = g.id AND gm.user_id = ?))")
+        .bind(group_id)
+        .bind(user_id)
+        .fetch_optional(&db.pool)
+        .await;
+    match row {
+        Ok(Some(r)) => format!("OK: Description: {}", r.get::<Option<String>,_>("description").unwrap_or_default()),
+        Ok(None) => "ERR: Group not found".to_string(),
+        Err(e) => format!("ERR: DB error: {}", e),
+    }
+}
+
+/// Search public groups, and the caller's own groups, by name.
+/// Response: "OK: Groups: <id>:<member_count>:<name>, ..."
+pub async fn search_groups(db: Arc<Database>, session_token: &str, query: &str, limit: i64) -> String {
+    let user_id = match crate::server::auth::validate_session(db.clone(), session_token).await {
+        Some(uid) => uid,
+        None => return "ERR: Invalid or expired session".to_string(),
+    };
+    // Escape dei caratteri jolly di LIKE presenti nella query dell'utente
+    let escaped = query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    let rows = sqlx::query(r#"
+        SELECT g.id, g.name, (SELECT COUNT(*) FROM group_members m WHERE m.group_id = g.id) AS member_count
+        FROM groups g
+        WHERE (g.is_public = 1 OR g.id IN (SELECT group_id FROM group_members WHERE user_id = ?))
+          AND g.name LIKE ? ESCAPE '\'
+        ORDER BY member_count DESC, g.name
+        LIMIT ?
+    "#)
+        .bind(&user_id)
+        .bind(format!("%{}%", escaped))
+        .bind(limit)
+        .fetch_all(&db.pool)
+        .await;
+    match rows {
+        Ok(rows) => {
+            let groups: Vec<String> = rows
+                .iter()
+                .map(|r| format!("{}:{}:{}", r.get::<String,_>("id"), r.get::<i64,_>("member_count"), r.get::<String,_>("name")))
+                .collect();
+            format!("OK: Groups: {}", groups.join(", "))
+        }
+        Err(e) => format!("ERR: DB error: {}", e),
+    }
+}
+
 pub async fn leave_group(db: Arc<Database>, user_id: &str, group_ident: &str) -> String {
     println!("[GROUPS] User {} leaves group '{}'", user_id, group_ident);
     // Try to resolve the provided identifier as a group id first, then fall back to name
@@ -387,14 +1169,43 @@ pub async fn leave_group(db: Arc<Database>, user_id: &str, group_ident: &str) ->
             }
         }
     };
+    // L'ultimo owner non può lasciare un gruppo che ha ancora altri membri
+    let owners_and_members = sqlx::query(
+        "SELECT (SELECT role FROM group_members WHERE group_id = ?1 AND user_id = ?2) AS my_role, \
+                (SELECT COUNT(*) FROM group_members WHERE group_id = ?1 AND role = 'owner') AS owners, \
+                (SELECT COUNT(*) FROM group_members WHERE group_id = ?1) AS members")
+        .bind(&group_id)
+        .bind(user_id)
+        .fetch_one(&db.pool)
+        .await;
+    if let Ok(row) = owners_and_members {
+        let my_role: Option<String> = row.get("my_role");
+        let owners: i64 = row.get("owners");
+        let members: i64 = row.get("members");
+        if my_role.as_deref() == Some("owner") && owners == 1 && members > 1 {
+            return "ERR: Transfer ownership before leaving".to_string();
+        }
+    }
     // Rimuovi da group_members
+    let mut tx = match db.pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return format!("ERR: DB error: {}", e),
+    };
     let res = sqlx::query("DELETE FROM group_members WHERE group_id = ? AND user_id = ?")
         .bind(&group_id)
         .bind(user_id)
-        .execute(&db.pool)
+        .execute(&mut *tx)
         .await;
-    match res {
-        Ok(_) => {
+    let res = match res {
+        Ok(r) if r.rows_affected() > 0 => record_departure(&mut tx, &group_id, user_id).await,
+        Ok(_) => Ok(()),
+        Err(e) => Err(e),
+    };
+    match res.and(Ok(tx)) {
+        Ok(tx) => {
+            if let Err(e) = tx.commit().await {
+                return format!("ERR: DB error: {}", e);
+            }
             println!("[GROUPS] User {} left group {}", user_id, group_id);
             "OK: Left group".to_string()
         }
@@ -404,3 +1215,53 @@ pub async fn leave_group(db: Arc<Database>, user_id: &str, group_ident: &str) ->
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::services::group_service::GroupService;
+    use crate::server::connection::parse_command_line;
+    use crate::server::test_support::{group_id, register, server};
+
+    #[tokio::test]
+    async fn create_group_rolls_back_when_owner_membership_fails() {
+        let db = Arc::new(Database::connect("sqlite::memory:").await.unwrap());
+        db.migrate().await.unwrap();
+        sqlx::query("CREATE TRIGGER fail_owner_insert BEFORE INSERT ON group_members BEGIN SELECT RAISE(ABORT, 'owner insert failed'); END")
+            .execute(&db.pool)
+            .await
+            .unwrap();
+
+        let resp = create_group(db.clone(), "owner-id", "rollback", &ServerConfig::default()).await;
+        assert!(resp.starts_with("ERR: Could not add creator as member"), "{}", resp);
+
+        let row = sqlx::query("SELECT COUNT(*) AS c FROM groups").fetch_one(&db.pool).await.unwrap();
+        assert_eq!(row.get::<i64, _>("c"), 0);
+    }
+
+    #[tokio::test]
+    async fn invite_flow_from_client_commands_to_membership() {
+        let server = server().await;
+        let alice = register(&server, "alice").await;
+        let bobby = register(&server, "bobby").await;
+        server.handle_command("/create_group", &[&alice, "team"]).await;
+        let group = group_id(&server, "team").await;
+
+        // Sent exactly as the client builds it, so a swapped argument order fails here
+        let line = GroupService::invite_command(&alice, "bobby", &group);
+        let (cmd, args) = parse_command_line(&line).unwrap();
+        let resp = server.handle_command(cmd, &args).await;
+        assert!(resp.starts_with("OK:"), "{}", resp);
+
+        let invites = GroupService::parse_my_group_invites(&server.handle_command("/my_group_invites", &[&bobby]).await).unwrap();
+        assert_eq!(invites.len(), 1);
+        assert_eq!((invites[0].group_name.as_str(), invites[0].invited_by.as_str()), ("team", "alice"));
+
+        let resp = server.handle_command("/accept_group_invite", &[&bobby, &invites[0].invite_id.to_string()]).await;
+        assert!(resp.starts_with("OK:"), "{}", resp);
+        let groups = GroupService::parse_my_groups(&server.handle_command("/my_groups", &[&bobby]).await).unwrap();
+        assert_eq!(groups.iter().map(|g| g.id.as_str()).collect::<Vec<_>>(), vec![group.as_str()]);
+        let invites = GroupService::parse_my_group_invites(&server.handle_command("/my_group_invites", &[&bobby]).await).unwrap();
+        assert!(invites.is_empty());
+    }
+}
@@ -0,0 +1,82 @@
+// Inizializzazione del logging del server: livello da CLI/env e copia opzionale su file
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Accepted values for `--log-level`
+pub const LOG_LEVELS: &[&str] = &["error", "warn", "info", "debug", "trace"];
+
+/// Log files above this size are rotated
+pub const MAX_LOG_FILE_BYTES: u64 = 50 * 1024 * 1024;
+
+/// Log file shared between the logger and the rotation task
+#[derive(Clone)]
+pub struct LogFile {
+    path: PathBuf,
+    writer: Arc<Mutex<BufWriter<File>>>,
+}
+
+impl LogFile {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Ok(Self { path: path.to_path_buf(), writer: Arc::new(Mutex::new(BufWriter::new(Self::open_append(path)?))) })
+    }
+
+    fn open_append(path: &Path) -> io::Result<File> {
+        OpenOptions::new().create(true).append(true).open(path)
+    }
+
+    /// Rename the file to `<path>.<timestamp>` and start a new one once it exceeds `max_bytes`
+    pub fn rotate_if_needed(&self, max_bytes: u64) -> io::Result<bool> {
+        let mut writer = self.writer.lock().unwrap_or_else(|e| e.into_inner());
+        writer.flush()?;
+        if std::fs::metadata(&self.path)?.len() <= max_bytes {
+            return Ok(false);
+        }
+        let rotated = PathBuf::from(format!("{}.{}", self.path.display(), chrono::Local::now().format("%Y%m%d%H%M%S")));
+        std::fs::rename(&self.path, &rotated)?;
+        *writer = BufWriter::new(Self::open_append(&self.path)?);
+        Ok(true)
+    }
+}
+
+/// Writes every log record to stdout and to the log file
+struct TeeWriter {
+    file: LogFile,
+}
+
+impl Write for TeeWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        io::stdout().write_all(buf)?;
+        let mut writer = self.file.writer.lock().unwrap_or_else(|e| e.into_inner());
+        writer.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::stdout().flush()?;
+        self.file.writer.lock().unwrap_or_else(|e| e.into_inner()).flush()
+    }
+}
+
+/// Initialize `env_logger`. `level` (from `--log-level`) overrides `RUST_LOG`/`LOG_LEVEL`;
+/// with `log_file` the output is duplicated to that file, which is returned for rotation.
+pub fn init(level: Option<&str>, log_file: Option<&Path>) -> io::Result<Option<LogFile>> {
+    let level = level
+        .map(str::to_string)
+        .or_else(|| std::env::var("LOG_LEVEL").ok())
+        .unwrap_or_else(|| "info".to_string());
+    std::env::set_var("RUST_LOG", &level); //setto env var per usare log::info
+
+    let mut builder = env_logger::Builder::from_default_env();
+    let file = match log_file {
+        Some(path) => {
+            let file = LogFile::open(path)?;
+            builder.target(env_logger::Target::Pipe(Box::new(TeeWriter { file: file.clone() })));
+            Some(file)
+        }
+        None => None,
+    };
+    builder.init();
+    Ok(file)
+}
@@ -1,20 +1,42 @@
 // src/server/main.rs
 // Entry point per il server ruggine_modulare
 use ruggine_modulare::server::{config::ServerConfig, database::Database, connection::Server};
-use ruggine_modulare::server::websocket::ChatWebSocketManager;
+use ruggine_modulare::server::websocket::{self, ChatWebSocketManager};
 use ruggine_modulare::utils::performance;
 use std::sync::Arc;
 use tokio::net::TcpListener;
-use log::{info, error};
+use tokio_rustls::TlsAcceptor;
+use log::{info, warn, error};
+
+use ruggine_modulare::server::config::Args;
+use ruggine_modulare::server::logging;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     // Configura logging
-    let log_level = std::env::var("LOG_LEVEL").unwrap_or_else(|_| "info".to_string());
-    std::env::set_var("RUST_LOG", &log_level); //setto env var per usare log::info
-    env_logger::init();
+    let args = Args::parse()?;
+    if args.print_config {
+        print!("{}", ServerConfig::template());
+        return Ok(());
+    }
+    let log_file = logging::init(args.log_level.as_deref(), args.log_file.as_deref())?;
+
+    // Weekly rotation of the log file
+    if let Some(log_file) = log_file {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(7 * 24 * 3600));
+            loop {
+                interval.tick().await;
+                match log_file.rotate_if_needed(logging::MAX_LOG_FILE_BYTES) {
+                    Ok(true) => info!("📄 Log file rotated"),
+                    Ok(false) => {}
+                    Err(e) => error!("Log rotation failed: {}", e),
+                }
+            }
+        });
+    }
 
-    let config = ServerConfig::from_env();
+    let config = ServerConfig::load(&args)?;
 
     // TLS hint for the operator
     if config.enable_encryption {
@@ -24,29 +46,60 @@ async fn main() -> anyhow::Result<()> {
     }
 
     // Initialize database and server
-    let database = Arc::new(Database::connect(&config.database_url).await?);
+    let db_retry_delay = std::time::Duration::from_millis(config.db_connect_retry_delay_ms);
+    let database = Arc::new(
+        Database::connect_with_retry(
+            &config.database_url,
+            config.db_connect_retries,
+            db_retry_delay,
+            config.db_pool_max_connections,
+            config.db_pool_min_connections,
+        )
+            .await?
+            .with_session_cache_size(config.session_cache_size),
+    );
     
     // Run database migrations to create tables if they don't exist
     info!("🗄️ Running database migrations...");
-    database.migrate().await.map_err(|e| {
+    database.migrate_with_retry(config.db_connect_retries, db_retry_delay).await.map_err(|e| {
         error!("Database migration failed: {}", e);
         e
     })?;
     info!("✅ Database migrations completed successfully");
+
+    // Message search indexes clear text only, so it is off while messages are encrypted
+    if let Err(e) = database.sync_message_search(!config.enable_encryption).await {
+        warn!("Message search index unavailable: {}", e);
+    }
+
+    // Re-encrypt messages stored before the cipher tag was introduced
+    ruggine_modulare::server::messages::migrate_legacy_ciphertexts(database.clone(), &config).await;
     
-    // Initialize WebSocket manager with Redis
-    let redis_url = std::env::var("REDIS_URL").unwrap_or_else(|_| "redis://localhost:6379".to_string());
-    let ws_manager = Arc::new(ChatWebSocketManager::new(&redis_url).await?);
+    // Initialize WebSocket manager; without Redis messages only reach clients of this instance
+    let ws_manager = if config.enable_redis {
+        match ChatWebSocketManager::new(&config.redis_url).await {
+            Some(ws_manager) => ws_manager,
+            None => {
+                warn!("Redis unavailable at {}; WebSocket delivery is limited to this instance", config.redis_url);
+                ChatWebSocketManager::local_only()
+            }
+        }
+    } else {
+        warn!("Redis disabled (ENABLE_REDIS=false); WebSocket delivery is limited to this instance");
+        ChatWebSocketManager::local_only()
+    };
+    let ws_manager = Arc::new(ws_manager.with_pending_queue(database.clone(), config.max_pending_messages_per_user));
     
     // Start Redis subscriber for cross-instance messaging
     ws_manager.start_redis_subscriber().await?;
     
     let presence = ruggine_modulare::server::presence::PresenceRegistry::new();
-    let mut server = Server { 
+    let server = Server { 
         db: database.clone(), 
         config: config.clone(), 
         presence,
         ws_manager: Some(ws_manager.clone()),
+        peer: None,
     };
 
     // Start performance logger in background
@@ -58,19 +111,60 @@ async fn main() -> anyhow::Result<()> {
         performance::start_performance_logger(perf_db, &perf_log_path).await;
     });
 
-    // Start WebSocket server on a different port
-    let ws_port = config.port + 1; // WebSocket su porta +1 rispetto al server principale
+    // Periodic database maintenance (expired sessions and stale rows)
+    let maintenance_db = database.clone();
+    let maintenance_ws_manager = ws_manager.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(3600));
+        loop {
+            interval.tick().await;
+            let went_offline = ruggine_modulare::server::auth::run_maintenance(maintenance_db.clone()).await;
+            for user_id in went_offline {
+                maintenance_ws_manager.notify_presence(&maintenance_db, &user_id, false).await;
+            }
+        }
+    });
+
+    // Daily chat key rotation: chats active in the last day encrypt new messages with a fresh key
+    if config.enable_encryption {
+        let rotation_db = database.clone();
+        tokio::spawn(async move {
+            const DAY: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+            let mut interval = tokio::time::interval(DAY);
+            // The first tick completes immediately; restarts should not rotate
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                let active_since = chrono::Utc::now().timestamp() - DAY.as_secs() as i64;
+                match ruggine_modulare::server::messages::rotate_chat_keys(&rotation_db, active_since).await {
+                    Ok(rotated) => info!("Rotated the keys of {} chats", rotated),
+                    Err(e) => error!("Chat key rotation failed: {}", e),
+                }
+            }
+        });
+    }
+
+    // Start WebSocket server on its own port, with the TCP server's certificate when TLS is set up
+    let ws_port = config.ws_port;
+    let ws_tls_acceptor = match server.setup_tls_acceptor() {
+        Ok(acceptor) => acceptor,
+        Err(e) => {
+            warn!("WebSocket TLS unavailable ({}); serving plain ws://", e);
+            None
+        }
+    };
+    let ws_scheme = if ws_tls_acceptor.is_some() { "wss" } else { "ws" };
     let ws_host = config.host.clone();
     let ws_manager_clone = ws_manager.clone();
     let database_clone = database.clone();
     let config_clone = config.clone();
     tokio::spawn(async move {
-        if let Err(e) = start_websocket_server(&format!("{}:{}", ws_host, ws_port), ws_manager_clone, database_clone, config_clone).await {
+        if let Err(e) = start_websocket_server(&format!("{}:{}", ws_host, ws_port), ws_manager_clone, database_clone, config_clone, ws_tls_acceptor).await {
             error!("WebSocket server error: {}", e);
         }
     });
 
-    info!("WebSocket server started on {}:{}", config.host, ws_port);
+    info!("WebSocket server started on {}://{}:{}", ws_scheme, config.host, ws_port);
 
     server.run(&format!("{}:{}", config.host, config.port)).await?;
     Ok(())
@@ -80,7 +174,8 @@ async fn start_websocket_server(
     addr: &str, 
     ws_manager: Arc<ChatWebSocketManager>,
     database: Arc<Database>,
-    config: ServerConfig
+    config: ServerConfig,
+    tls_acceptor: Option<TlsAcceptor>,
 ) -> anyhow::Result<()> {
     let listener = TcpListener::bind(addr).await?;
     info!("WebSocket server listening on {}", addr);
@@ -90,12 +185,24 @@ async fn start_websocket_server(
         let ws_manager = ws_manager.clone();
         let database = database.clone();
         let config = config.clone();
+        let tls_acceptor = tls_acceptor.clone();
         
         tokio::spawn(async move {
-            match tokio_tungstenite::accept_async(stream).await {
-                Ok(ws_stream) => {
+            if let Some(acceptor) = tls_acceptor {
+                match acceptor.accept(stream).await {
+                    Ok(tls_stream) => {
+                        if let Err(e) = ws_manager.handle_authenticated_connection_tls(tls_stream, database, config).await {
+                            error!("Error handling WebSocket connection: {}", e);
+                        }
+                    }
+                    Err(e) => error!("WebSocket TLS handshake failed: {}", e),
+                }
+                return;
+            }
+            match websocket::accept_with_since(stream).await {
+                Ok((ws_stream, since)) => {
                     // Usa l'autenticazione corretta invece di user_id fittizio
-                    if let Err(e) = ws_manager.handle_authenticated_connection(ws_stream, database, config).await {
+                    if let Err(e) = ws_manager.handle_authenticated_connection(ws_stream, since, database, config).await {
                         error!("Error handling WebSocket connection: {}", e);
                     }
                 }
@@ -6,9 +6,13 @@ use serde_json;
 
 use crate::server::config::ServerConfig;
 use crate::common::crypto::CryptoManager;
+use crate::server::websocket::{MessageType, OutgoingChatMessage, WebSocketMessage};
+use crate::server::reactions::{self, Reactions};
 
-/// Encrypts a message for storage in the database
-fn encrypt_message_for_storage(message: &str, chat_participants: &[String], config: &ServerConfig) -> Result<String, String> {
+/// Encrypts a message of `chat_id` for storage in the database with key rotation `rotation_index` of the chat
+/// and, for groups, the membership `key_version` (0 for private chats).
+/// The nonce comes from the chat's counter in `nonce_counters`, so it is never reused.
+async fn encrypt_message_for_storage(db: &Database, chat_id: &str, message: &str, chat_participants: &[String], rotation_index: u32, key_version: u32, config: &ServerConfig) -> Result<String, String> {
     if !config.enable_encryption {
         return Ok(message.to_string());
     }
@@ -16,15 +20,17 @@ fn encrypt_message_for_storage(message: &str, chat_participants: &[String], conf
     println!("[CRYPTO] Encrypting message for participants: {:?}", chat_participants);
     
     // Generate chat-specific key from participants and master key
-    let chat_key = CryptoManager::generate_chat_key(chat_participants, &config.encryption_master_key);
+    let chat_key = CryptoManager::generate_group_key(chat_participants, &config.encryption_master_key, rotation_index, key_version);
+    let counter = next_nonce_counter(db, chat_id).await.map_err(|e| format!("Nonce counter unavailable: {}", e))?;
     
     // Encrypt the message
-    match CryptoManager::encrypt_message(message, &chat_key) {
-        Ok((ciphertext, nonce)) => {
-            // Store as base64 encoded JSON containing ciphertext and nonce
+    match CryptoManager::encrypt_message_with_nonce(message, &chat_key, config.cipher_suite, CryptoManager::counter_nonce(chat_id, counter)) {
+        Ok(ciphertext) => {
+            // Store as base64 encoded JSON containing ciphertext and nonce counter
             let encrypted_data = serde_json::json!({
                 "ciphertext": general_purpose::STANDARD.encode(&ciphertext),
-                "nonce": general_purpose::STANDARD.encode(&nonce)
+                "nonce_counter": counter,
+                "key_version": key_version
             });
             println!("[CRYPTO] Successfully encrypted message");
             Ok(encrypted_data.to_string())
@@ -33,8 +39,28 @@ fn encrypt_message_for_storage(message: &str, chat_participants: &[String], conf
     }
 }
 
-/// Decrypts a message from the database
-fn decrypt_message_from_storage(encrypted_data: &str, chat_participants: &[String], config: &ServerConfig) -> Result<String, String> {
+/// Group key version a stored message was encrypted with; older messages have none and used version 0
+fn stored_key_version(data: &serde_json::Value) -> u32 {
+    data["key_version"].as_u64().unwrap_or(0) as u32
+}
+
+/// `stored_key_version` of a stored message; 0 for plain text
+fn message_key_version(stored: &str) -> u32 {
+    serde_json::from_str(stored).map(|data| stored_key_version(&data)).unwrap_or(0)
+}
+
+/// Bump the nonce counter of `chat_id` and return the new value (1 for the first message)
+async fn next_nonce_counter(db: &Database, chat_id: &str) -> Result<u64, sqlx::Error> {
+    let counter: i64 = sqlx::query_scalar("INSERT INTO nonce_counters (chat_id, counter) VALUES (?, 1)
+                                           ON CONFLICT(chat_id) DO UPDATE SET counter = counter + 1 RETURNING counter")
+        .bind(chat_id)
+        .fetch_one(&db.pool)
+        .await?;
+    Ok(counter as u64)
+}
+
+/// Decrypts a message of `chat_id` from the database, stored with key rotation `rotation_index` (see `key_rotation_of`)
+fn decrypt_message_from_storage(chat_id: &str, encrypted_data: &str, chat_participants: &[String], rotation_index: u32, config: &ServerConfig) -> Result<String, String> {
     if !config.enable_encryption {
         return Ok(encrypted_data.to_string());
     }
@@ -45,13 +71,17 @@ fn decrypt_message_from_storage(encrypted_data: &str, chat_participants: &[Strin
         // This is an encrypted message
         println!("[CRYPTO] Decrypting message for participants: {:?}", chat_participants);
         let ciphertext = general_purpose::STANDARD.decode(data["ciphertext"].as_str().ok_or("Missing ciphertext")?).map_err(|_| "Invalid ciphertext base64")?;
-        let nonce = general_purpose::STANDARD.decode(data["nonce"].as_str().ok_or("Missing nonce")?).map_err(|_| "Invalid nonce base64")?;
+        // Messages from before nonce counters carry their random nonce instead
+        let nonce = match data["nonce_counter"].as_u64() {
+            Some(counter) => CryptoManager::counter_nonce(chat_id, counter).to_vec(),
+            None => general_purpose::STANDARD.decode(data["nonce"].as_str().ok_or("Missing nonce")?).map_err(|_| "Invalid nonce base64")?,
+        };
         
-        // Generate chat-specific key from participants and master key
-        let chat_key = CryptoManager::generate_chat_key(chat_participants, &config.encryption_master_key);
+        // Generate chat-specific key from participants and master key, at the group key version the message was sent with
+        let chat_key = CryptoManager::generate_group_key(chat_participants, &config.encryption_master_key, rotation_index, stored_key_version(&data));
         
         // Decrypt the message
-        match CryptoManager::decrypt_message(&ciphertext, &nonce, &chat_key) {
+        match CryptoManager::decrypt_stored_message(&ciphertext, &nonce, &chat_key) {
             Ok(decrypted) => {
                 println!("[CRYPTO] Successfully decrypted message");
                 Ok(decrypted)
@@ -68,7 +98,60 @@ fn decrypt_message_from_storage(encrypted_data: &str, chat_participants: &[Strin
     }
 }
 
-pub async fn send_group_message(db: Arc<Database>, session_token: &str, group_name: &str, message: &str, config: &ServerConfig) -> String {
+/// Key rotation a stored message was encrypted with; messages from before chat keys were
+/// rotated have none and used rotation 0
+fn key_rotation_of(row: &sqlx::sqlite::SqliteRow) -> u32 {
+    row.get::<Option<i64>, _>("key_rotation").unwrap_or(0) as u32
+}
+
+/// Latest key rotation of `chat_id`, used for its new messages; 0 if it was never rotated
+async fn current_key_rotation(db: &Database, chat_id: &str) -> Result<u32, sqlx::Error> {
+    let rotation: Option<i64> = sqlx::query_scalar("SELECT MAX(rotation_index) FROM chat_key_rotations WHERE chat_id = ?")
+        .bind(chat_id)
+        .fetch_one(&db.pool)
+        .await?;
+    Ok(rotation.unwrap_or(0) as u32)
+}
+
+/// Move every chat with messages since `active_since` to its next key rotation; returns how
+/// many were rotated. Messages keep the rotation they were encrypted with.
+pub async fn rotate_chat_keys(db: &Database, active_since: i64) -> Result<u64, sqlx::Error> {
+    let res = sqlx::query(r#"
+        INSERT INTO chat_key_rotations (chat_id, rotation_index, rotated_at)
+        SELECT m.chat_id, COALESCE((SELECT MAX(r.rotation_index) FROM chat_key_rotations r WHERE r.chat_id = m.chat_id), 0) + 1, ?
+        FROM encrypted_messages m
+        WHERE m.sent_at >= ? AND (m.chat_id LIKE 'private:%' OR m.chat_id LIKE 'group:%')
+        GROUP BY m.chat_id
+    "#)
+        .bind(chrono::Utc::now().timestamp())
+        .bind(active_since)
+        .execute(&db.pool)
+        .await?;
+    Ok(res.rows_affected())
+}
+
+/// Earlier messages a new message refers to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MessageLinks {
+    /// Message of the same chat it answers
+    pub reply_to_id: Option<i64>,
+    /// Message, possibly of another chat, it is a copy of
+    pub forwarded_from_id: Option<i64>,
+}
+
+impl MessageLinks {
+    pub fn reply_to(reply_to_id: Option<i64>) -> Self {
+        MessageLinks { reply_to_id, forwarded_from_id: None }
+    }
+
+    /// Links stored in a row of `encrypted_messages`
+    fn from_row(r: &sqlx::sqlite::SqliteRow) -> Self {
+        MessageLinks { reply_to_id: r.get("reply_to_id"), forwarded_from_id: r.get("forwarded_from_id") }
+    }
+}
+
+/// Store a message for a group, linked to the earlier messages in `links`.
+pub async fn send_group_message(db: Arc<Database>, session_token: &str, group_name: &str, message: &str, links: MessageLinks, config: &ServerConfig) -> String {
     if message.len() > config.max_message_length {
         return format!("ERR: Message too long (max {} chars)", config.max_message_length);
     }
@@ -110,19 +193,38 @@ pub async fn send_group_message(db: Arc<Database>, session_token: &str, group_na
         }
     };
     
+    let chat_id = format!("group:{}", group_id);
+    let key_rotation = match current_key_rotation(&db, &chat_id).await {
+        Ok(rotation) => rotation,
+        Err(e) => return format!("ERR: {}", e),
+    };
+    let key_version = match sqlx::query_scalar::<_, i64>("SELECT key_version FROM groups WHERE id = ?")
+        .bind(&group_id)
+        .fetch_one(&db.pool)
+        .await
+    {
+        Ok(version) => version as u32,
+        Err(e) => return format!("ERR: {}", e),
+    };
+
     // Encrypt the message before storing
-    let encrypted_message = match encrypt_message_for_storage(message, &group_members, config) {
+    let encrypted_message = match encrypt_message_for_storage(&db, &chat_id, message, &group_members, key_rotation, key_version, config).await {
         Ok(encrypted) => encrypted,
         Err(e) => return format!("ERR: Encryption failed: {}", e),
     };
     
     let sent_at = chrono::Utc::now().timestamp();
-    let chat_id = format!("group:{}", group_id);
-    let res = sqlx::query("INSERT INTO encrypted_messages (chat_id, sender_id, message, sent_at) VALUES (?, ?, ?, ?)")
+    if let Err(e) = check_reply_target(&db, &chat_id, links.reply_to_id).await {
+        return e;
+    }
+    let res = sqlx::query("INSERT INTO encrypted_messages (chat_id, sender_id, message, sent_at, reply_to_id, forwarded_from_id, key_rotation) VALUES (?, ?, ?, ?, ?, ?, ?)")
         .bind(&chat_id)
         .bind(&user_id)
         .bind(&encrypted_message)
         .bind(sent_at)
+        .bind(links.reply_to_id)
+        .bind(links.forwarded_from_id)
+        .bind(key_rotation)
         .execute(&db.pool)
         .await;
     match res {
@@ -137,7 +239,162 @@ pub async fn send_group_message(db: Arc<Database>, session_token: &str, group_na
     }
 }
 
-pub async fn send_private_message(db: Arc<Database>, session_token: &str, to_username: &str, message: &str, config: &ServerConfig) -> String {
+/// Build one notification per group member mentioned as `@username` in `message`,
+/// addressed to that member only. The sender is never notified.
+pub async fn mention_notifications(db: &Arc<Database>, group_id: &str, sender_id: &str, message: &str) -> Vec<WebSocketMessage> {
+    let mentioned: std::collections::HashSet<&str> = message
+        .split_whitespace()
+        .filter_map(|word| word.strip_prefix('@'))
+        .map(|name| name.trim_end_matches(|c: char| !c.is_alphanumeric() && c != '_'))
+        .filter(|name| !name.is_empty())
+        .collect();
+    if mentioned.is_empty() {
+        return vec![];
+    }
+    let rows = sqlx::query("SELECT u.id, u.username FROM group_members m JOIN users u ON u.id = m.user_id WHERE m.group_id = ?")
+        .bind(group_id)
+        .fetch_all(&db.pool)
+        .await
+        .unwrap_or_default();
+    let sender_name = rows
+        .iter()
+        .find(|r| r.get::<String,_>("id") == sender_id)
+        .map(|r| r.get::<String,_>("username"))
+        .unwrap_or_else(|| sender_id.to_string());
+    rows.iter()
+        .filter(|r| r.get::<String,_>("id") != sender_id && mentioned.contains(r.get::<String,_>("username").as_str()))
+        .map(|r| WebSocketMessage {
+            id: uuid::Uuid::new_v4().to_string(),
+            message_type: MessageType::Notification,
+            sender: sender_name.clone(),
+            target: group_id.to_string(),
+            content: format!("{} mentioned you", sender_name),
+            timestamp: chrono::Utc::now().timestamp(),
+            recipient_user_id: Some(r.get("id")),
+        })
+        .collect()
+}
+
+/// One `ReadReceipt` per message in `message_ids` for every other member of the group,
+/// so each member's client can bump the "Seen by" counter of those messages.
+pub async fn read_receipt_notifications(db: &Arc<Database>, group_id: &str, reader_id: &str, message_ids: &[i64]) -> Vec<WebSocketMessage> {
+    let members = sqlx::query("SELECT user_id FROM group_members WHERE group_id = ? AND user_id != ?")
+        .bind(group_id)
+        .bind(reader_id)
+        .fetch_all(&db.pool)
+        .await
+        .unwrap_or_default();
+    let now = chrono::Utc::now().timestamp();
+    members.iter()
+        .flat_map(|m| message_ids.iter().map(move |id| (m.get::<String, _>("user_id"), *id)))
+        .map(|(member, message_id)| WebSocketMessage {
+            id: uuid::Uuid::new_v4().to_string(),
+            message_type: MessageType::ReadReceipt { message_id: message_id.to_string(), user_id: reader_id.to_string() },
+            sender: reader_id.to_string(),
+            target: group_id.to_string(),
+            content: String::new(),
+            timestamp: now,
+            recipient_user_id: Some(member),
+        })
+        .collect()
+}
+
+/// One `TypingIndicator` for each other participant of the chat `typing` was sent for:
+/// the recipient of a private chat, or the members of a group the typing user belongs to.
+pub async fn typing_notifications(db: &Arc<Database>, sender_id: &str, typing: &OutgoingChatMessage) -> Vec<WebSocketMessage> {
+    let Some(sender_name) = sqlx::query("SELECT username FROM users WHERE id = ?")
+        .bind(sender_id)
+        .fetch_optional(&db.pool)
+        .await
+        .ok()
+        .flatten()
+        .map(|r| r.get::<String, _>("username"))
+    else {
+        return vec![];
+    };
+    let (target, recipients) = match (typing.chat_type.as_str(), &typing.to_user, &typing.group_id) {
+        ("private", Some(to_user), _) => {
+            let recipient = sqlx::query("SELECT id FROM users WHERE username = ?")
+                .bind(to_user)
+                .fetch_optional(&db.pool)
+                .await
+                .ok()
+                .flatten()
+                .map(|r| r.get::<String, _>("id"));
+            (format!("private:{}", to_user), recipient.into_iter().collect::<Vec<_>>())
+        }
+        ("group", _, Some(group_id)) => {
+            let members: Vec<String> = sqlx::query("SELECT user_id FROM group_members WHERE group_id = ?")
+                .bind(group_id)
+                .fetch_all(&db.pool)
+                .await
+                .unwrap_or_default()
+                .iter()
+                .map(|r| r.get("user_id"))
+                .collect();
+            if !members.iter().any(|m| m == sender_id) {
+                return vec![];
+            }
+            (format!("group:{}", group_id), members)
+        }
+        _ => return vec![],
+    };
+    let now = chrono::Utc::now().timestamp();
+    recipients.into_iter()
+        .filter(|r| r != sender_id)
+        .map(|recipient| WebSocketMessage {
+            id: uuid::Uuid::new_v4().to_string(),
+            message_type: MessageType::TypingIndicator { sender: sender_name.clone(), target: target.clone(), is_typing: typing.is_typing },
+            sender: sender_id.to_string(),
+            target: target.clone(),
+            content: String::new(),
+            timestamp: now,
+            recipient_user_id: Some(recipient),
+        })
+        .collect()
+}
+
+/// Who has seen message `message_id`: "OK: seen_by: alice:1710000000, bob:1710000001".
+/// Only members of the message's group may ask.
+pub async fn get_receipts(db: Arc<Database>, user_id: &str, message_id: i64) -> String {
+    let chat_id = match sqlx::query("SELECT chat_id FROM encrypted_messages WHERE id = ?")
+        .bind(message_id)
+        .fetch_optional(&db.pool)
+        .await
+    {
+        Ok(Some(row)) => row.get::<String, _>("chat_id"),
+        Ok(None) => return "ERR: Message not found".to_string(),
+        Err(e) => return format!("ERR: DB error: {}", e),
+    };
+    let Some(group_id) = chat_id.strip_prefix("group:") else {
+        return "ERR: Receipts are only available for group messages".to_string();
+    };
+    let is_member = sqlx::query("SELECT 1 FROM group_members WHERE group_id = ? AND user_id = ?")
+        .bind(group_id)
+        .bind(user_id)
+        .fetch_optional(&db.pool)
+        .await
+        .ok()
+        .flatten()
+        .is_some();
+    if !is_member {
+        return "ERR: Not a group member".to_string();
+    }
+    let rows = sqlx::query("SELECT u.username, r.seen_at FROM message_receipts r JOIN users u ON u.id = r.user_id WHERE r.message_id = ? ORDER BY r.seen_at")
+        .bind(message_id)
+        .fetch_all(&db.pool)
+        .await;
+    match rows {
+        Ok(rows) => {
+            let seen: Vec<String> = rows.iter().map(|r| format!("{}:{}", r.get::<String, _>("username"), r.get::<i64, _>("seen_at"))).collect();
+            format!("OK: seen_by: {}", seen.join(", "))
+        }
+        Err(e) => format!("ERR: DB error: {}", e),
+    }
+}
+
+/// Store a private message for `to_username`, linked to the earlier messages in `links`.
+pub async fn send_private_message(db: Arc<Database>, session_token: &str, to_username: &str, message: &str, links: MessageLinks, config: &ServerConfig) -> String {
     if message.len() > config.max_message_length {
         return format!("ERR: Message too long (max {} chars)", config.max_message_length);
     }
@@ -153,22 +410,36 @@ pub async fn send_private_message(db: Arc<Database>, session_token: &str, to_use
         Ok(Some(row)) => row.get::<String,_>("id"),
         _ => return "ERR: User not found".to_string(),
     };
+    if crate::server::users::blocked_between(&db, &user_id, &to_id).await {
+        return "ERR: User has blocked you or you have blocked this user".to_string();
+    }
     let mut ids = vec![user_id.clone(), to_id.clone()];
     ids.sort();
     let chat_id = format!("private:{}-{}", ids[0], ids[1]);
+    if let Err(e) = check_reply_target(&db, &chat_id, links.reply_to_id).await {
+        return e;
+    }
     
+    let key_rotation = match current_key_rotation(&db, &chat_id).await {
+        Ok(rotation) => rotation,
+        Err(e) => return format!("ERR: {}", e),
+    };
+
     // Encrypt the message before storing
-    let encrypted_message = match encrypt_message_for_storage(message, &ids, config) {
+    let encrypted_message = match encrypt_message_for_storage(&db, &chat_id, message, &ids, key_rotation, 0, config).await {
         Ok(encrypted) => encrypted,
         Err(e) => return format!("ERR: Encryption failed: {}", e),
     };
     
     let sent_at = chrono::Utc::now().timestamp();
-    let res = sqlx::query("INSERT INTO encrypted_messages (chat_id, sender_id, message, sent_at) VALUES (?, ?, ?, ?)")
+    let res = sqlx::query("INSERT INTO encrypted_messages (chat_id, sender_id, message, sent_at, reply_to_id, forwarded_from_id, key_rotation) VALUES (?, ?, ?, ?, ?, ?, ?)")
         .bind(&chat_id)
         .bind(&user_id)
         .bind(&encrypted_message)
         .bind(sent_at)
+        .bind(links.reply_to_id)
+        .bind(links.forwarded_from_id)
+        .bind(key_rotation)
         .execute(&db.pool)
         .await;
     match res {
@@ -183,10 +454,65 @@ pub async fn send_private_message(db: Arc<Database>, session_token: &str, to_use
     }
 }
 
-pub async fn get_group_messages(db: Arc<Database>, session_token: &str, group_name: &str, config: &ServerConfig) -> String {
+/// A reply may only answer a message of its own chat
+async fn check_reply_target(db: &Arc<Database>, chat_id: &str, reply_to_id: Option<i64>) -> Result<(), String> {
+    let Some(reply_to_id) = reply_to_id else {
+        return Ok(());
+    };
+    match sqlx::query("SELECT 1 FROM encrypted_messages WHERE id = ? AND chat_id = ?")
+        .bind(reply_to_id)
+        .bind(chat_id)
+        .fetch_optional(&db.pool)
+        .await
+    {
+        Ok(Some(_)) => Ok(()),
+        Ok(None) => Err("ERR: Original message not found".to_string()),
+        Err(e) => Err(format!("ERR: DB error: {}", e)),
+    }
+}
+
+/// `[ts]`, `[ts|id]` or, for replies, `[ts|id|reply_to_id]`, followed by ` avatar=<url>` when
+/// the sender has an avatar, ` seen=<n>` when the message has read receipts, ` edited=<ts>` once
+/// it was edited, ` forwarded=<id>` for copies of message `id` and ` reactions=<base64 JSON>`
+/// when someone reacted to it
+fn message_header(ts: i64, message_id: Option<i64>, links: MessageLinks, avatar_url: Option<String>, seen_count: Option<i64>, edited_at: Option<i64>, reactions: Option<&Reactions>) -> String {
+    let mut header = match (message_id, links.reply_to_id) {
+        (Some(id), Some(reply_to)) => format!("[{}|{}|{}", ts, id, reply_to),
+        (Some(id), None) => format!("[{}|{}", ts, id),
+        (None, _) => format!("[{}", ts),
+    };
+    if let Some(url) = avatar_url {
+        header.push_str(&format!(" avatar={}", url));
+    }
+    if let Some(n) = seen_count {
+        header.push_str(&format!(" seen={}", n));
+    }
+    if let Some(ts) = edited_at {
+        header.push_str(&format!(" edited={}", ts));
+    }
+    if let Some(id) = links.forwarded_from_id {
+        header.push_str(&format!(" forwarded={}", id));
+    }
+    if let Some(reactions) = reactions {
+        header.push_str(&reactions::header_field(reactions));
+    }
+    header.push(']');
+    header
+}
+
+/// Session user and chat id of a group the user belongs to
+struct GroupChat {
+    user_id: String,
+    group_id: String,
+    chat_id: String,
+    /// Messages up to this time were deleted by the session user
+    deleted_at: Option<i64>,
+}
+
+async fn open_group_chat(db: &Arc<Database>, session_token: &str, group_name: &str) -> Result<GroupChat, String> {
     let user_id = match auth::validate_session(db.clone(), session_token).await {
         Some(uid) => uid,
-        None => return "ERR: Invalid session".to_string(),
+        None => return Err("ERR: Invalid session".to_string()),
     };
     // group_name is actually group_id in this context
     let group_row = sqlx::query("SELECT id FROM groups WHERE id = ?")
@@ -195,7 +521,7 @@ pub async fn get_group_messages(db: Arc<Database>, session_token: &str, group_na
         .await;
     let group_id = match group_row {
         Ok(Some(row)) => row.get::<String,_>("id"),
-        _ => return "ERR: Group not found".to_string(),
+        _ => return Err("ERR: Group not found".to_string()),
     };
     let is_member = sqlx::query("SELECT 1 FROM group_members WHERE group_id = ? AND user_id = ?")
         .bind(&group_id)
@@ -206,7 +532,7 @@ pub async fn get_group_messages(db: Arc<Database>, session_token: &str, group_na
         .flatten()
         .is_some();
     if !is_member {
-        return "ERR: Not a group member".to_string();
+        return Err("ERR: Not a group member".to_string());
     }
     let chat_id = format!("group:{}", group_id);
     
@@ -219,163 +545,229 @@ pub async fn get_group_messages(db: Arc<Database>, session_token: &str, group_na
         .ok()
         .flatten()
         .map(|row| row.get::<i64, _>("deleted_at"));
+
+    Ok(GroupChat { user_id, group_id, chat_id, deleted_at })
+}
+
+/// Response lines for `rows` of `encrypted_messages` (joined with the sender's username and avatar),
+/// recording read receipts for them. Returns the lines and the ids the caller saw for the first time.
+async fn group_message_lines(db: &Arc<Database>, chat: &GroupChat, rows: &[sqlx::sqlite::SqliteRow], config: &ServerConfig) -> (Vec<String>, Vec<i64>) {
+    // Older messages were encrypted for whoever was in the group back then
+    let roster = GroupRoster::load(db, &chat.group_id).await;
+
+    // Read receipts: record what the caller sees now, then count all of them per message
+    let now = chrono::Utc::now().timestamp();
+    let mut newly_seen = Vec::new();
+    for r in rows.iter() {
+        let ts: i64 = r.get("sent_at");
+        if r.get::<String, _>("sender_id") == chat.user_id || chat.deleted_at.is_some_and(|d| ts <= d) {
+            continue;
+        }
+        let message_id: i64 = r.get("id");
+        let inserted = sqlx::query("INSERT OR IGNORE INTO message_receipts (message_id, user_id, seen_at) VALUES (?, ?, ?)")
+            .bind(message_id)
+            .bind(&chat.user_id)
+            .bind(now)
+            .execute(&db.pool)
+            .await;
+        if inserted.is_ok_and(|res| res.rows_affected() > 0) {
+            newly_seen.push(message_id);
+        }
+    }
+    // Only the time span of `rows` needs counting
+    let first_ts = rows.iter().map(|r| r.get::<i64, _>("sent_at")).min().unwrap_or(0);
+    let last_ts = rows.iter().map(|r| r.get::<i64, _>("sent_at")).max().unwrap_or(0);
+    let seen_counts: std::collections::HashMap<i64, i64> = sqlx::query("SELECT r.message_id, COUNT(*) AS n FROM message_receipts r JOIN encrypted_messages m ON m.id = r.message_id WHERE m.chat_id = ? AND m.sent_at BETWEEN ? AND ? GROUP BY r.message_id")
+        .bind(&chat.chat_id)
+        .bind(first_ts)
+        .bind(last_ts)
+        .fetch_all(&db.pool)
+        .await
+        .unwrap_or_default()
+        .iter()
+        .map(|r| (r.get("message_id"), r.get("n")))
+        .collect();
+    let reactions = reactions::reactions_in_span(db, &chat.chat_id, first_ts, last_ts).await;
+
+    let mut msgs: Vec<String> = Vec::with_capacity(rows.len());
+    for r in rows.iter() {
+        let sender_id: String = r.get("sender_id");
+        // Per i gruppi, converti sender_id in username (fallback to ID if username not found)
+        let sender_name = r.get::<Option<String>, _>("username").unwrap_or_else(|| sender_id.clone());
+        let msg: String = r.get("message");
+        let ts: i64 = r.get("sent_at");
+        
+        // Filter out messages before deletion timestamp if user deleted this chat
+        if let Some(deleted_timestamp) = chat.deleted_at {
+            if ts <= deleted_timestamp {
+                continue; // Skip this message
+            }
+        }
+        
+        let clear = decrypt_group_message(&chat.chat_id, &msg, &roster.candidates(ts, message_key_version(&msg), &sender_id), key_rotation_of(r), config);
+        
+        let message_id: i64 = r.get("id");
+        let header = message_header(ts, Some(message_id), MessageLinks::from_row(r), r.get("avatar_url"), seen_counts.get(&message_id).copied(), r.get("edited_at"), reactions.get(&message_id));
+        msgs.push(format!("{} {}: {}", header, sender_name, clear));
+    }
+    (msgs, newly_seen)
+}
+
+/// Group messages visible to the caller, plus the ids of the messages the caller
+/// has just seen for the first time (a `message_receipts` row was inserted for them).
+pub async fn get_group_messages(db: Arc<Database>, session_token: &str, group_name: &str, config: &ServerConfig) -> (String, Vec<i64>) {
+    let chat = match open_group_chat(&db, session_token, group_name).await {
+        Ok(chat) => chat,
+        Err(e) => return (e, vec![]),
+    };
     
-    let rows = sqlx::query("SELECT sender_id, message, sent_at FROM encrypted_messages WHERE chat_id = ? ORDER BY sent_at ASC")
-        .bind(&chat_id)
+    let rows = sqlx::query("SELECT m.id, m.sender_id, m.message, m.key_rotation, m.sent_at, m.edited_at, m.reply_to_id, m.forwarded_from_id, u.username, u.avatar_url FROM encrypted_messages m LEFT JOIN users u ON u.id = m.sender_id WHERE m.chat_id = ? ORDER BY m.sent_at ASC")
+        .bind(&chat.chat_id)
         .fetch_all(&db.pool)
         .await;
     match rows {
         Ok(rows) => {
-            // Get current group members for the latest key
-            let current_members_rows = sqlx::query("SELECT user_id FROM group_members WHERE group_id = ?")
-                .bind(&group_id)
-                .fetch_all(&db.pool)
-                .await;
-            let current_members: Vec<String> = match current_members_rows {
-                Ok(rows) => rows.iter().map(|r| r.get::<String, _>("user_id")).collect::<Vec<String>>(),
-                Err(_) => vec![],
-            };
-
-            // Get all historical member combinations for decryption fallback
-            let all_members_rows = sqlx::query("SELECT DISTINCT user_id FROM group_members WHERE group_id = ?")
-                .bind(&group_id)
-                .fetch_all(&db.pool)
-                .await;
-            let all_historical_members: Vec<String> = match all_members_rows {
-                Ok(rows) => rows.iter().map(|r| r.get::<String, _>("user_id")).collect::<Vec<String>>(),
-                Err(_) => vec![],
-            };
-
-            let mut msgs: Vec<String> = Vec::with_capacity(rows.len());
-            for r in rows.iter() {
-                let sender_id: String = r.get("sender_id");
-                // Per i gruppi, converti sender_id in username
-                let sender_name = if let Ok(Some(user_row)) = sqlx::query("SELECT username FROM users WHERE id = ?")
-                    .bind(&sender_id)
-                    .fetch_optional(&db.pool)
-                    .await
-                {
-                    user_row.get::<String, _>("username")
-                } else {
-                    sender_id.clone() // fallback to ID if username not found
-                };
-                let msg: String = r.get("message");
-                let ts: i64 = r.get("sent_at");
-                
-                // Filter out messages before deletion timestamp if user deleted this chat
-                if let Some(deleted_timestamp) = deleted_at {
-                    if ts <= deleted_timestamp {
-                        continue; // Skip this message
-                    }
-                }
-                
-                // Try multiple decryption strategies for historical messages
-                let clear = decrypt_group_message_with_fallback(&msg, &current_members, &all_historical_members, &sender_id, config);
-                
-                msgs.push(format!("[{}] {}: {}", ts, sender_name, clear));
-            }
-            format!("OK: Messages:\n{}", msgs.join("\n"))
+            let (msgs, newly_seen) = group_message_lines(&db, &chat, &rows, config).await;
+            (format!("OK: Messages:\n{}", msgs.join("\n")), newly_seen)
         }
         Err(e) => {
             println!("[MSG] Error getting group messages: {}", e);
-            format!("ERR: {}", e)
+            (format!("ERR: {}", e), vec![])
         }
     }
 }
 
-/// Try multiple decryption strategies for group messages
-fn decrypt_group_message_with_fallback(
-    encrypted_data: &str,
-    current_members: &[String],
-    all_historical_members: &[String],
-    sender_id: &str,
-    config: &ServerConfig
-) -> String {
-    println!("[DECRYPT] Attempting to decrypt group message");
-    println!("[DECRYPT] Current members: {:?}", current_members);
-    println!("[DECRYPT] All historical members: {:?}", all_historical_members);
-    println!("[DECRYPT] Sender ID: {}", sender_id);
-    
-    // Strategy 1: Try with current members
-    println!("[DECRYPT] Strategy 1: Trying with current members");
-    if let Ok(decrypted) = decrypt_message_from_storage(encrypted_data, current_members, config) {
-        println!("[DECRYPT] SUCCESS with current members");
-        return decrypted;
+/// Messages pinned in a group, most recently pinned first, as "OK: Pinned messages:" followed by
+/// `[ts|id pinned_by=<username> pinned_at=<ts>] sender: content` lines
+pub async fn get_pinned_messages(db: Arc<Database>, session_token: &str, group_id: &str, config: &ServerConfig) -> String {
+    let chat = match open_group_chat(&db, session_token, group_id).await {
+        Ok(chat) => chat,
+        Err(e) => return e,
+    };
+    let rows = sqlx::query(r#"
+        SELECT m.id, m.sender_id, m.message, m.key_rotation, m.sent_at, m.edited_at, p.pinned_at,
+               COALESCE(u.username, m.sender_id) AS sender, COALESCE(pu.username, p.pinned_by) AS pinned_by
+        FROM pinned_messages p
+        JOIN encrypted_messages m ON m.id = p.message_id
+        LEFT JOIN users u ON u.id = m.sender_id
+        LEFT JOIN users pu ON pu.id = p.pinned_by
+        WHERE p.group_id = ?
+        ORDER BY p.pinned_at DESC
+    "#)
+        .bind(&chat.group_id)
+        .fetch_all(&db.pool)
+        .await;
+    let rows = match rows {
+        Ok(rows) => rows,
+        Err(e) => return format!("ERR: DB error: {}", e),
+    };
+    let roster = GroupRoster::load(&db, &chat.group_id).await;
+    let lines: Vec<String> = rows.iter().map(|r| {
+        let stored: String = r.get("message");
+        let candidates = roster.candidates(r.get("sent_at"), message_key_version(&stored), &r.get::<String, _>("sender_id"));
+        let clear = decrypt_group_message(&chat.chat_id, &stored, &candidates, key_rotation_of(r), config);
+        let header = message_header(r.get("sent_at"), Some(r.get("id")), MessageLinks::default(), None, None, r.get("edited_at"), None);
+        // Pin metadata goes inside the header, right before its closing bracket
+        format!("{} pinned_by={} pinned_at={}] {}: {}",
+            header.trim_end_matches(']'),
+            r.get::<String, _>("pinned_by"),
+            r.get::<i64, _>("pinned_at"),
+            r.get::<String, _>("sender"),
+            clear)
+    }).collect();
+    format!("OK: Pinned messages:\n{}", lines.join("\n"))
+}
+
+/// Group counterpart of `get_private_messages_page`: up to `limit` messages sent before `before_ts`,
+/// oldest first, under "OK: Messages: has_more=<true|false> next_before=<ts>", where `next_before`
+/// is the cursor of the following page. Also returns the ids seen for the first time.
+pub async fn get_group_messages_page(db: Arc<Database>, session_token: &str, group_name: &str, before_ts: i64, limit: i64, config: &ServerConfig) -> (String, Vec<i64>) {
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return (format!("ERR: Page size must be between 1 and {}", MAX_PAGE_SIZE), vec![]);
     }
-    
-    // Strategy 2: Try with all possible historical member combinations
-    // Start with smaller combinations and work up
-    println!("[DECRYPT] Strategy 2: Trying historical member combinations");
-    for size in 2..=all_historical_members.len() {
-        let combinations = generate_member_combinations(all_historical_members, size);
-        println!("[DECRYPT] Trying {} combinations of size {}", combinations.len(), size);
-        for combo in combinations {
-            println!("[DECRYPT] Trying combination: {:?}", combo);
-            if let Ok(decrypted) = decrypt_message_from_storage(encrypted_data, &combo, config) {
-                println!("[DECRYPT] SUCCESS with combination: {:?}", combo);
-                return decrypted;
-            }
+    let chat = match open_group_chat(&db, session_token, group_name).await {
+        Ok(chat) => chat,
+        Err(e) => return (e, vec![]),
+    };
+    let not_before = chat.deleted_at.unwrap_or(i64::MIN);
+    let (rows, has_more) = match page_rows(&db, &chat.chat_id, before_ts, not_before, limit).await {
+        Ok(page) => page,
+        Err(e) => {
+            println!("[MSG] Error getting group messages page: {}", e);
+            return (format!("ERR: {}", e), vec![]);
         }
-    }
-    
-    // Strategy 3: Try with just sender (for very old messages)
-    println!("[DECRYPT] Strategy 3: Trying with sender only");
-    if let Ok(decrypted) = decrypt_message_from_storage(encrypted_data, &[sender_id.to_string()], config) {
-        println!("[DECRYPT] SUCCESS with sender only");
-        return decrypted;
-    }
-    
-    // Strategy 4: If it's not encrypted JSON, return as plain text (legacy)
-    if !encrypted_data.starts_with('{') {
-        println!("[DECRYPT] Strategy 4: Returning as plain text (legacy)");
-        return encrypted_data.to_string();
-    }
-    
-    // Last resort: show decryption failed
-    println!("[DECRYPT] ALL STRATEGIES FAILED");
-    "[DECRYPTION FAILED]".to_string()
+    };
+    let next_before = rows.first().map(|r| r.get::<i64, _>("sent_at")).unwrap_or(before_ts);
+    let (msgs, newly_seen) = group_message_lines(&db, &chat, &rows, config).await;
+    (format!("OK: Messages: has_more={} next_before={}\n{}", has_more, next_before, msgs.join("\n")), newly_seen)
 }
 
-/// Generate all possible combinations of members of a given size
-fn generate_member_combinations(members: &[String], size: usize) -> Vec<Vec<String>> {
-    if size == 0 || size > members.len() {
-        return vec![];
-    }
-    
-    if size == 1 {
-        return members.iter().map(|m| vec![m.clone()]).collect();
-    }
-    
-    let mut combinations = Vec::new();
-    generate_combinations_recursive(members, size, 0, &mut Vec::new(), &mut combinations);
-    combinations
-}
-
-fn generate_combinations_recursive(
-    members: &[String],
-    size: usize,
-    start: usize,
-    current: &mut Vec<String>,
-    result: &mut Vec<Vec<String>>
-) {
-    if current.len() == size {
-        let mut sorted_combo = current.clone();
-        sorted_combo.sort(); // Important: sort for consistent key generation
-        result.push(sorted_combo);
-        return;
-    }
-    
-    for i in start..members.len() {
-        current.push(members[i].clone());
-        generate_combinations_recursive(members, size, i + 1, current, result);
-        current.pop();
+/// Rows of `chat_id` sent in `(not_before, before_ts)`, oldest first, for one page of at least
+/// `limit` messages, and whether older ones remain. sent_at has a one-second resolution, so a page
+/// never ends in the middle of a second: the next one (sent_at < oldest returned) would skip the rest of it.
+async fn page_rows(db: &Arc<Database>, chat_id: &str, before_ts: i64, not_before: i64, limit: i64) -> Result<(Vec<sqlx::sqlite::SqliteRow>, bool), sqlx::Error> {
+    const COLUMNS: &str = "SELECT m.id, m.sender_id, m.message, m.key_rotation, m.sent_at, m.edited_at, m.reply_to_id, m.forwarded_from_id, u.username, u.avatar_url FROM encrypted_messages m LEFT JOIN users u ON u.id = m.sender_id";
+    // One extra row tells whether an older page exists
+    let mut rows = sqlx::query(&format!("{} WHERE m.chat_id = ? AND m.sent_at < ? AND m.sent_at > ? ORDER BY m.sent_at DESC, m.id DESC LIMIT ?", COLUMNS))
+        .bind(chat_id)
+        .bind(before_ts)
+        .bind(not_before)
+        .bind(limit + 1)
+        .fetch_all(&db.pool)
+        .await?;
+    let mut has_more = rows.len() as i64 > limit;
+    rows.truncate(limit as usize);
+    if let Some(boundary) = rows.last().map(|r| r.get::<i64, _>("sent_at")).filter(|_| has_more) {
+        rows.retain(|r| r.get::<i64, _>("sent_at") != boundary);
+        let same_second = sqlx::query(&format!("{} WHERE m.chat_id = ? AND m.sent_at = ? ORDER BY m.id DESC", COLUMNS))
+            .bind(chat_id)
+            .bind(boundary)
+            .fetch_all(&db.pool)
+            .await?;
+        rows.extend(same_second);
+        // The extra row may have belonged to that second too
+        has_more = sqlx::query("SELECT 1 FROM encrypted_messages WHERE chat_id = ? AND sent_at < ? AND sent_at > ? LIMIT 1")
+            .bind(chat_id)
+            .bind(boundary)
+            .bind(not_before)
+            .fetch_optional(&db.pool)
+            .await?
+            .is_some();
     }
+    rows.reverse();
+    Ok((rows, has_more))
+}
+
+/// Plain text of a group message, trying the key of each participant list in `candidates`
+/// (see `GroupRoster::candidates`)
+fn decrypt_group_message(chat_id: &str, encrypted_data: &str, candidates: &[Vec<String>], rotation_index: u32, config: &ServerConfig) -> String {
+    candidates.iter()
+        .find_map(|participants| decrypt_message_from_storage(chat_id, encrypted_data, participants, rotation_index, config).ok())
+        .unwrap_or_else(|| {
+            println!("[DECRYPT] No participant list decrypts this message of {}", chat_id);
+            "[DECRYPTION FAILED]".to_string()
+        })
 }
 
 pub async fn get_private_messages(db: Arc<Database>, session_token: &str, other_username: &str, config: &ServerConfig) -> String {
+    get_private_messages_since(db, session_token, other_username, i64::MIN, config).await
+}
+
+/// Session user and chat id of the private conversation with another user
+struct PrivateChat {
+    user_id: String,
+    my_username: String,
+    /// Both user ids, sorted as in `chat_id`
+    ids: Vec<String>,
+    chat_id: String,
+    /// Messages up to this time were deleted by the session user
+    deleted_at: Option<i64>,
+}
+
+async fn open_private_chat(db: &Arc<Database>, session_token: &str, other_username: &str) -> Result<PrivateChat, String> {
     let user_id = match auth::validate_session(db.clone(), session_token).await {
         Some(uid) => uid,
-        None => return "ERR: Invalid session".to_string(),
+        None => return Err("ERR: Invalid session".to_string()),
     };
     
     // Ottieni anche il nostro username per i messaggi
@@ -394,9 +786,9 @@ pub async fn get_private_messages(db: Arc<Database>, session_token: &str, other_
         .await;
     let to_id = match to_row {
         Ok(Some(row)) => row.get::<String,_>("id"),
-        _ => return "ERR: User not found".to_string(),
+        _ => return Err("ERR: User not found".to_string()),
     };
-    let mut ids = vec![user_id.clone(), to_id.clone()];
+    let mut ids = vec![user_id.clone(), to_id];
     ids.sort();
     let chat_id = format!("private:{}-{}", ids[0], ids[1]);
     
@@ -409,43 +801,65 @@ pub async fn get_private_messages(db: Arc<Database>, session_token: &str, other_
         .ok()
         .flatten()
         .map(|row| row.get::<i64, _>("deleted_at"));
+
+    Ok(PrivateChat { user_id, my_username, ids, chat_id, deleted_at })
+}
+
+/// One line of a private messages response for a row of `encrypted_messages` (with its id) joined with the sender's avatar
+fn private_message_line(r: &sqlx::sqlite::SqliteRow, chat: &PrivateChat, other_username: &str, reactions: &std::collections::HashMap<i64, Reactions>, config: &ServerConfig) -> String {
+    let sender: String = r.get("sender_id");
+    // Converti sender_id in username
+    let sender_name = if sender == chat.user_id {
+        chat.my_username.as_str()
+    } else {
+        other_username
+    };
+    let msg: String = r.get("message");
+    let ts: i64 = r.get("sent_at");
+    // For private chats the participants are the two user ids we already computed in `ids`
+    let clear = match decrypt_message_from_storage(&chat.chat_id, &msg, &chat.ids, key_rotation_of(r), config) {
+        Ok(s) => s,
+        Err(_) => "[DECRYPTION FAILED]".to_string(),
+    };
+    let message_id: i64 = r.get("id");
+    format!("{} {}: {}", message_header(ts, Some(message_id), MessageLinks::from_row(r), r.get("avatar_url"), None, r.get("edited_at"), reactions.get(&message_id)), sender_name, clear)
+}
+
+/// Reactions to the private messages in `rows`, which are ordered by `sent_at`
+async fn private_reactions(db: &Arc<Database>, chat: &PrivateChat, rows: &[sqlx::sqlite::SqliteRow]) -> std::collections::HashMap<i64, Reactions> {
+    match (rows.first(), rows.last()) {
+        (Some(first), Some(last)) => reactions::reactions_in_span(db, &chat.chat_id, first.get("sent_at"), last.get("sent_at")).await,
+        _ => std::collections::HashMap::new(),
+    }
+}
+
+/// Like `get_private_messages`, but only messages with `sent_at >= since`.
+/// The bound is inclusive so a message stored later in the same second is not missed;
+/// clients already deduplicate the repeated ones.
+pub async fn get_private_messages_since(db: Arc<Database>, session_token: &str, other_username: &str, since: i64, config: &ServerConfig) -> String {
+    let chat = match open_private_chat(&db, session_token, other_username).await {
+        Ok(chat) => chat,
+        Err(e) => return e,
+    };
     
     // Force WAL checkpoint to ensure we see the latest messages from WebSocket connections
     let _ = sqlx::query("PRAGMA wal_checkpoint;")
         .execute(&db.pool)
         .await;
     
-    let rows = sqlx::query("SELECT sender_id, message, sent_at FROM encrypted_messages WHERE chat_id = ? ORDER BY sent_at ASC")
-        .bind(&chat_id)
+    let rows = sqlx::query("SELECT m.id, m.sender_id, m.message, m.key_rotation, m.sent_at, m.edited_at, m.reply_to_id, m.forwarded_from_id, u.avatar_url FROM encrypted_messages m LEFT JOIN users u ON u.id = m.sender_id WHERE m.chat_id = ? AND m.sent_at >= ? ORDER BY m.sent_at ASC")
+        .bind(&chat.chat_id)
+        .bind(since)
         .fetch_all(&db.pool)
         .await;
     match rows {
         Ok(rows) => {
-            let msgs: Vec<String> = rows.iter().filter_map(|r| {
-                let sender: String = r.get("sender_id");
-                // Converti sender_id in username
-                let sender_name = if sender == user_id {
-                    my_username.clone()
-                } else {
-                    other_username.to_string()
-                };
-                let msg: String = r.get("message");
-                let ts: i64 = r.get("sent_at");
-                
+            let reactions = private_reactions(&db, &chat, &rows).await;
+            let msgs: Vec<String> = rows.iter()
                 // Filter out messages before deletion timestamp if user deleted this chat
-                if let Some(deleted_timestamp) = deleted_at {
-                    if ts <= deleted_timestamp {
-                        return None; // Skip this message
-                    }
-                }
-                
-                // For private chats the participants are the two user ids we already computed in `ids`
-                let clear = match decrypt_message_from_storage(&msg, &ids, config) {
-                    Ok(s) => s,
-                    Err(_) => "[DECRYPTION FAILED]".to_string(),
-                };
-                format!("[{}] {}: {}", ts, sender_name, clear).into()
-            }).collect();
+                .filter(|r| chat.deleted_at.is_none_or(|deleted| r.get::<i64, _>("sent_at") > deleted))
+                .map(|r| private_message_line(r, &chat, other_username, &reactions, config))
+                .collect();
             format!("OK: Messages:\n{}", msgs.join("\n"))
         }
         Err(e) => {
@@ -455,6 +869,39 @@ pub async fn get_private_messages(db: Arc<Database>, session_token: &str, other_
     }
 }
 
+/// Largest `limit` accepted by `get_private_messages_page`
+pub const MAX_PAGE_SIZE: i64 = 200;
+
+/// Up to `limit` private messages sent before `before_ts`, oldest first, under the header
+/// "OK: Messages: has_more=<true|false> next_before=<ts>". The next page is requested with
+/// `next_before`, the timestamp of the oldest message returned, so clients page backwards
+/// through the history. A page holds more than `limit` messages when several were sent in
+/// the second it starts at.
+pub async fn get_private_messages_page(db: Arc<Database>, session_token: &str, other_username: &str, before_ts: i64, limit: i64, config: &ServerConfig) -> String {
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return format!("ERR: Page size must be between 1 and {}", MAX_PAGE_SIZE);
+    }
+    let chat = match open_private_chat(&db, session_token, other_username).await {
+        Ok(chat) => chat,
+        Err(e) => return e,
+    };
+
+    match page_rows(&db, &chat.chat_id, before_ts, chat.deleted_at.unwrap_or(i64::MIN), limit).await {
+        Ok((rows, has_more)) => {
+            let next_before = rows.first().map(|r| r.get::<i64, _>("sent_at")).unwrap_or(before_ts);
+            let reactions = private_reactions(&db, &chat, &rows).await;
+            let msgs: Vec<String> = rows.iter()
+                .map(|r| private_message_line(r, &chat, other_username, &reactions, config))
+                .collect();
+            format!("OK: Messages: has_more={} next_before={}\n{}", has_more, next_before, msgs.join("\n"))
+        }
+        Err(e) => {
+            println!("[MSG] Error getting private messages page: {}", e);
+            format!("ERR: {}", e)
+        }
+    }
+}
+
 pub async fn delete_group_messages(db: Arc<Database>, session_token: &str, group_id: &str) -> String {
     let user_id = match auth::validate_session(db.clone(), session_token).await {
         Some(uid) => uid,
@@ -521,3 +968,909 @@ pub async fn delete_private_messages(db: Arc<Database>, session_token: &str, oth
         }
     }
 }
+
+/// Delete the `encrypted_messages` row `message_id` for everyone. Allowed to its sender
+/// and, in groups, to the owner and admins.
+pub async fn delete_single_message(db: Arc<Database>, session_token: &str, message_id: i64) -> String {
+    let user_id = match auth::validate_session(db.clone(), session_token).await {
+        Some(uid) => uid,
+        None => return "ERR: Invalid session".to_string(),
+    };
+    let row = match sqlx::query("SELECT chat_id, sender_id FROM encrypted_messages WHERE id = ?")
+        .bind(message_id)
+        .fetch_optional(&db.pool)
+        .await
+    {
+        Ok(Some(row)) => row,
+        Ok(None) => return "ERR: Message not found".to_string(),
+        Err(e) => return format!("ERR: DB error: {}", e),
+    };
+    let chat_id: String = row.get("chat_id");
+    let sender_id: String = row.get("sender_id");
+
+    if sender_id != user_id {
+        let Some(group_id) = chat_id.strip_prefix("group:") else {
+            return "ERR: You can only delete your own messages".to_string();
+        };
+        let role = sqlx::query("SELECT role FROM group_members WHERE group_id = ? AND user_id = ?")
+            .bind(group_id)
+            .bind(&user_id)
+            .fetch_optional(&db.pool)
+            .await;
+        match role {
+            Ok(Some(row)) if matches!(row.get::<String, _>("role").as_str(), "owner" | "admin") => {}
+            Ok(_) => return "ERR: Only the sender or a group admin can delete this message".to_string(),
+            Err(e) => return format!("ERR: DB error: {}", e),
+        }
+    }
+
+    let mut tx = match db.pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return format!("ERR: DB error: {}", e),
+    };
+    for sql in [
+        "DELETE FROM message_receipts WHERE message_id = ?",
+        "DELETE FROM message_reactions WHERE message_id = ?",
+        "DELETE FROM pinned_messages WHERE message_id = ?",
+        "DELETE FROM encrypted_messages WHERE id = ?",
+    ] {
+        if let Err(e) = sqlx::query(sql).bind(message_id).execute(&mut *tx).await {
+            return format!("ERR: DB error: {}", e);
+        }
+    }
+    if let Err(e) = tx.commit().await {
+        return format!("ERR: DB error: {}", e);
+    }
+    println!("[MSG] User {} deleted message {} in {}", user_id, message_id, chat_id);
+    "OK: Message deleted".to_string()
+}
+
+/// Replace the content of the caller's own message `message_id`, re-encrypted with the key
+/// of the participants it was stored under, and stamp it as edited.
+pub async fn edit_message(db: Arc<Database>, session_token: &str, message_id: i64, new_content: &str, config: &ServerConfig) -> String {
+    if new_content.len() > config.max_message_length {
+        return format!("ERR: Message too long (max {} chars)", config.max_message_length);
+    }
+    let user_id = match auth::validate_session(db.clone(), session_token).await {
+        Some(uid) => uid,
+        None => return "ERR: Invalid session".to_string(),
+    };
+    let row = match sqlx::query("SELECT chat_id, sender_id, message, key_rotation, sent_at FROM encrypted_messages WHERE id = ?")
+        .bind(message_id)
+        .fetch_optional(&db.pool)
+        .await
+    {
+        Ok(Some(row)) => row,
+        Ok(None) => return "ERR: Message not found".to_string(),
+        Err(e) => return format!("ERR: DB error: {}", e),
+    };
+    let chat_id: String = row.get("chat_id");
+    let sender_id: String = row.get("sender_id");
+    if sender_id != user_id {
+        return "ERR: You can only edit your own messages".to_string();
+    }
+
+    // Group keys follow the membership, so keep whichever participants the old content decrypts with
+    // The edited content keeps the key rotation and group key version of the original
+    let stored: String = row.get("message");
+    let key_rotation = key_rotation_of(&row);
+    let key_version = message_key_version(&stored);
+    let candidates = candidate_participants(&db, &chat_id, &sender_id, row.get("sent_at"), key_version).await;
+    let participants = candidates.iter()
+        .find(|p| decrypt_message_from_storage(&chat_id, &stored, p, key_rotation, config).is_ok())
+        .or(candidates.first())
+        .cloned()
+        .unwrap_or_default();
+    let encrypted_message = match encrypt_message_for_storage(&db, &chat_id, new_content, &participants, key_rotation, key_version, config).await {
+        Ok(encrypted) => encrypted,
+        Err(e) => return format!("ERR: Encryption failed: {}", e),
+    };
+
+    let res = sqlx::query("UPDATE encrypted_messages SET message = ?, edited_at = ?, edited_count = edited_count + 1 WHERE id = ? AND sender_id = ?")
+        .bind(&encrypted_message)
+        .bind(chrono::Utc::now().timestamp())
+        .bind(message_id)
+        .bind(&user_id)
+        .execute(&db.pool)
+        .await;
+    match res {
+        Ok(r) if r.rows_affected() > 0 => {
+            println!("[MSG] User {} edited message {} in {}", user_id, message_id, chat_id);
+            "OK: Message edited".to_string()
+        }
+        Ok(_) => "ERR: Message not found".to_string(),
+        Err(e) => format!("ERR: DB error: {}", e),
+    }
+}
+
+/// Copy message `message_id`, which the caller can read, into `target_chat_id` (`private:<username>`
+/// or `group:<group_id>`) as a new message of theirs, re-encrypted with the target chat's key.
+pub async fn forward_message(db: Arc<Database>, session_token: &str, message_id: i64, target_chat_id: &str, config: &ServerConfig) -> String {
+    if let Err(e) = reactions::open_message(&db, session_token, message_id).await {
+        return e;
+    }
+    let row = match sqlx::query("SELECT chat_id, sender_id, message, key_rotation, sent_at FROM encrypted_messages WHERE id = ?")
+        .bind(message_id)
+        .fetch_optional(&db.pool)
+        .await
+    {
+        Ok(Some(row)) => row,
+        Ok(None) => return "ERR: Message not found".to_string(),
+        Err(e) => return format!("ERR: DB error: {}", e),
+    };
+    let chat_id: String = row.get("chat_id");
+    let sender_id: String = row.get("sender_id");
+    let stored: String = row.get("message");
+    let candidates = candidate_participants(&db, &chat_id, &sender_id, row.get("sent_at"), message_key_version(&stored)).await;
+    let Some(clear) = candidates.iter().find_map(|p| decrypt_message_from_storage(&chat_id, &stored, p, key_rotation_of(&row), config).ok()) else {
+        return "ERR: Could not decrypt the original message".to_string();
+    };
+
+    let links = MessageLinks { reply_to_id: None, forwarded_from_id: Some(message_id) };
+    let result = match target_chat_id.split_once(':') {
+        Some(("private", username)) => send_private_message(db, session_token, username, &clear, links, config).await,
+        Some(("group", group_id)) => send_group_message(db, session_token, group_id, &clear, links, config).await,
+        _ => return "ERR: Unknown chat (use private:<username> or group:<group_id>)".to_string(),
+    };
+    if result.starts_with("OK:") {
+        println!("[MSG] Message {} forwarded to {}", message_id, target_chat_id);
+        "OK: Message forwarded".to_string()
+    } else {
+        result
+    }
+}
+
+/// Candidate participant lists whose chat key may have encrypted a message of `chat_id`
+/// sent at `sent_at` with group key version `key_version`
+async fn candidate_participants(db: &Database, chat_id: &str, sender_id: &str, sent_at: i64, key_version: u32) -> Vec<Vec<String>> {
+    let mut candidates = Vec::new();
+    if let Some(pair) = chat_id.strip_prefix("private:") {
+        // User ids may contain '-', so try every split point of "<id1>-<id2>"
+        for (pos, _) in pair.match_indices('-') {
+            let (a, b) = (&pair[..pos], &pair[pos + 1..]);
+            if !a.is_empty() && !b.is_empty() {
+                candidates.push(vec![a.to_string(), b.to_string()]);
+            }
+        }
+    } else if let Some(group_id) = chat_id.strip_prefix("group:") {
+        return GroupRoster::load(db, group_id).await.candidates(sent_at, key_version, sender_id);
+    }
+    candidates.push(vec![sender_id.to_string()]);
+    candidates
+}
+
+/// Membership history of a group, enough to tell who was in it when a message was sent
+/// and so which participant list its key was derived from
+struct GroupRoster {
+    /// Current members
+    members: Vec<MembershipChange>,
+    /// Members kicked or gone
+    departures: Vec<MembershipChange>,
+}
+
+/// When `user_id` joined or left a group: at time `at`, moving it to `key_version`
+/// (unknown for founding members and for changes from before versions were recorded)
+struct MembershipChange {
+    user_id: String,
+    at: i64,
+    key_version: Option<i64>,
+}
+
+impl GroupRoster {
+    async fn load(db: &Database, group_id: &str) -> Self {
+        let changes = |rows: Vec<(String, i64, Option<i64>)>| -> Vec<MembershipChange> {
+            rows.into_iter().map(|(user_id, at, key_version)| MembershipChange { user_id, at, key_version }).collect()
+        };
+        let members = sqlx::query_as("SELECT user_id, joined_at, key_version FROM group_members WHERE group_id = ?")
+            .bind(group_id)
+            .fetch_all(&db.pool)
+            .await
+            .unwrap_or_default();
+        let departures = sqlx::query_as("SELECT target_id, created_at, key_version FROM group_events WHERE group_id = ? AND event_type IN ('kicked', 'left') AND target_id IS NOT NULL")
+            .bind(group_id)
+            .fetch_all(&db.pool)
+            .await
+            .unwrap_or_default();
+        Self { members: changes(members), departures: changes(departures) }
+    }
+
+    /// Members when a message with group key version `key_version` was sent at `sent_at`:
+    /// those who had joined by then, plus those kicked or gone since. Every membership change
+    /// moves the group to a new key version, so the version orders the message against the
+    /// changes that recorded theirs. The others fall back to time, whose one-second resolution
+    /// leaves changes in the second of `sent_at` ambiguous: `same_second_counts` includes them.
+    fn members_at(&self, sent_at: i64, key_version: u32, same_second_counts: bool) -> Vec<String> {
+        let key_version = key_version as i64;
+        let joined = self.members.iter().filter(|m| match m.key_version {
+            Some(v) => v <= key_version,
+            None => m.at < sent_at || (same_second_counts && m.at == sent_at),
+        });
+        let departed = self.departures.iter().filter(|d| match d.key_version {
+            Some(v) => v > key_version,
+            None => d.at > sent_at || (same_second_counts && d.at == sent_at),
+        });
+        let mut ids: Vec<String> = joined.chain(departed).map(|c| c.user_id.clone()).collect();
+        ids.sort();
+        ids.dedup();
+        ids
+    }
+
+    /// Participant lists to try for a message of `sender_id` with group key version `key_version`
+    /// sent at `sent_at`, most likely first: the current members, the members at send time, and
+    /// the sender alone (very old messages)
+    fn candidates(&self, sent_at: i64, key_version: u32, sender_id: &str) -> Vec<Vec<String>> {
+        let mut current: Vec<String> = self.members.iter().map(|m| m.user_id.clone()).collect();
+        current.sort();
+        let mut candidates = vec![current];
+        for participants in [self.members_at(sent_at, key_version, true), self.members_at(sent_at, key_version, false), vec![sender_id.to_string()]] {
+            if !candidates.contains(&participants) {
+                candidates.push(participants);
+            }
+        }
+        candidates
+    }
+}
+
+/// Re-encrypts messages stored before ciphertexts carried a cipher tag, using the
+/// configured cipher suite. Tagged and plain text messages are left untouched.
+/// Runs once: every message stored since is tagged, so completion is recorded in `migrations`.
+pub async fn migrate_legacy_ciphertexts(db: Arc<Database>, config: &ServerConfig) {
+    if !config.enable_encryption {
+        return;
+    }
+    match sqlx::query("SELECT 1 FROM migrations WHERE name = ?").bind(LEGACY_CIPHERTEXT_MIGRATION).fetch_optional(&db.pool).await {
+        Ok(None) => {}
+        Ok(Some(_)) => return,
+        Err(e) => {
+            println!("[CRYPTO] Failed to check the cipher migration: {}", e);
+            return;
+        }
+    }
+    // Counter-based nonces (`nonce_counter`) came after the tag, so only random-nonce rows can be legacy
+    let rows = match sqlx::query(r#"SELECT id, chat_id, sender_id, message, key_rotation, sent_at FROM encrypted_messages WHERE message LIKE '%"nonce":%'"#)
+        .fetch_all(&db.pool)
+        .await
+    {
+        Ok(rows) => rows,
+        Err(e) => {
+            println!("[CRYPTO] Failed to load messages for cipher migration: {}", e);
+            return;
+        }
+    };
+
+    let mut migrated = 0;
+    for r in rows.iter() {
+        let id: i64 = r.get("id");
+        let chat_id: String = r.get("chat_id");
+        let sender_id: String = r.get("sender_id");
+        let msg: String = r.get("message");
+        let key_rotation = key_rotation_of(r);
+
+        let Ok(data) = serde_json::from_str::<serde_json::Value>(&msg) else { continue };
+        let (Some(ciphertext), Some(nonce)) = (
+            data["ciphertext"].as_str().and_then(|c| general_purpose::STANDARD.decode(c).ok()),
+            data["nonce"].as_str().and_then(|n| general_purpose::STANDARD.decode(n).ok()),
+        ) else { continue };
+
+        for participants in candidate_participants(&db, &chat_id, &sender_id, r.get("sent_at"), stored_key_version(&data)).await {
+            let chat_key = CryptoManager::generate_chat_key(&participants, &config.encryption_master_key, key_rotation);
+            if CryptoManager::decrypt_tagged_message(&ciphertext, &nonce, &chat_key).is_ok() {
+                break; // already migrated
+            }
+            let Ok(clear) = CryptoManager::decrypt_message(&ciphertext, &nonce, &chat_key) else { continue };
+            match encrypt_message_for_storage(&db, &chat_id, &clear, &participants, key_rotation, stored_key_version(&data), config).await {
+                Ok(encrypted) => {
+                    if sqlx::query("UPDATE encrypted_messages SET message = ? WHERE id = ?")
+                        .bind(&encrypted)
+                        .bind(id)
+                        .execute(&db.pool)
+                        .await
+                        .is_ok()
+                    {
+                        migrated += 1;
+                    }
+                }
+                Err(e) => println!("[CRYPTO] Failed to re-encrypt message {}: {}", id, e),
+            }
+            break;
+        }
+    }
+    if migrated > 0 {
+        println!("[CRYPTO] Re-encrypted {} legacy messages with {:?}", migrated, config.cipher_suite);
+    }
+    if let Err(e) = sqlx::query("INSERT OR IGNORE INTO migrations (name, applied_at) VALUES (?, ?)")
+        .bind(LEGACY_CIPHERTEXT_MIGRATION)
+        .bind(chrono::Utc::now().timestamp())
+        .execute(&db.pool)
+        .await
+    {
+        println!("[CRYPTO] Failed to record the cipher migration: {}", e);
+    }
+}
+
+/// Name of `migrate_legacy_ciphertexts` in the `migrations` table
+const LEGACY_CIPHERTEXT_MIGRATION: &str = "legacy_ciphertexts";
+
+/// Resolve `<chat_type> <chat_id>` as sent by clients into the stored `chat_id`.
+/// Private chats are addressed by the other user's username, groups by group id.
+async fn resolve_chat_id(db: &Arc<Database>, user_id: &str, chat_type: &str, target: &str) -> Result<String, String> {
+    match chat_type {
+        "private" => {
+            let row = sqlx::query("SELECT id FROM users WHERE username = ?")
+                .bind(target)
+                .fetch_optional(&db.pool)
+                .await
+                .map_err(|e| format!("ERR: {}", e))?;
+            let Some(row) = row else { return Err("ERR: User not found".to_string()) };
+            let mut ids = [user_id.to_string(), row.get::<String,_>("id")];
+            ids.sort();
+            Ok(format!("private:{}-{}", ids[0], ids[1]))
+        }
+        "group" => {
+            let member = sqlx::query("SELECT 1 FROM group_members WHERE group_id = ? AND user_id = ?")
+                .bind(target)
+                .bind(user_id)
+                .fetch_optional(&db.pool)
+                .await
+                .map_err(|e| format!("ERR: {}", e))?;
+            if member.is_none() {
+                return Err("ERR: Not a group member".to_string());
+            }
+            Ok(format!("group:{}", target))
+        }
+        _ => Err("ERR: Unknown chat type (use private or group)".to_string()),
+    }
+}
+
+/// Record that the caller has read `<chat_type> <target>` up to `timestamp` (never moving
+/// backwards nor past now), and build a `ChatRead` event for every other participant.
+pub async fn mark_read(db: Arc<Database>, session_token: &str, chat_type: &str, target: &str, timestamp: i64) -> (String, Vec<WebSocketMessage>) {
+    let user_id = match auth::validate_session(db.clone(), session_token).await {
+        Some(uid) => uid,
+        None => return ("ERR: Invalid session".to_string(), vec![]),
+    };
+    let chat_id = match resolve_chat_id(&db, &user_id, chat_type, target).await {
+        Ok(id) => id,
+        Err(e) => return (e, vec![]),
+    };
+    let now = chrono::Utc::now().timestamp();
+    let res = sqlx::query("INSERT INTO read_receipts (chat_id, user_id, last_read_at) VALUES (?, ?, ?) ON CONFLICT(chat_id, user_id) DO UPDATE SET last_read_at = MAX(last_read_at, excluded.last_read_at) RETURNING last_read_at")
+        .bind(&chat_id)
+        .bind(&user_id)
+        .bind(timestamp.min(now))
+        .fetch_one(&db.pool)
+        .await;
+    let last_read_at: i64 = match res {
+        Ok(row) => row.get("last_read_at"),
+        Err(e) => return (format!("ERR: {}", e), vec![]),
+    };
+
+    let others: Vec<String> = match chat_id.strip_prefix("group:") {
+        Some(group_id) => sqlx::query("SELECT user_id FROM group_members WHERE group_id = ? AND user_id != ?")
+            .bind(group_id)
+            .bind(&user_id)
+            .fetch_all(&db.pool)
+            .await
+            .unwrap_or_default()
+            .iter()
+            .map(|r| r.get("user_id"))
+            .collect(),
+        None => sqlx::query("SELECT id FROM users WHERE username = ?")
+            .bind(target)
+            .fetch_optional(&db.pool)
+            .await
+            .ok()
+            .flatten()
+            .map(|r| r.get::<String, _>("id"))
+            .into_iter()
+            .collect(),
+    };
+    let username = sqlx::query("SELECT username FROM users WHERE id = ?")
+        .bind(&user_id)
+        .fetch_optional(&db.pool)
+        .await
+        .ok()
+        .flatten()
+        .map(|r| r.get::<String, _>("username"))
+        .unwrap_or_else(|| user_id.clone());
+    let events = others.into_iter()
+        .map(|recipient| WebSocketMessage {
+            id: uuid::Uuid::new_v4().to_string(),
+            message_type: MessageType::ChatRead { chat_id: chat_id.clone(), user_id: user_id.clone(), last_read_at },
+            sender: username.clone(),
+            target: chat_id.clone(),
+            content: String::new(),
+            timestamp: now,
+            recipient_user_id: Some(recipient),
+        })
+        .collect();
+    (format!("OK: Read up to {}", last_read_at), events)
+}
+
+/// How far each participant has read `<chat_type> <target>`:
+/// "OK: read_by: alice:1710000000, bob:1710000001"
+pub async fn get_read_receipts(db: Arc<Database>, session_token: &str, chat_type: &str, target: &str) -> String {
+    let user_id = match auth::validate_session(db.clone(), session_token).await {
+        Some(uid) => uid,
+        None => return "ERR: Invalid session".to_string(),
+    };
+    let chat_id = match resolve_chat_id(&db, &user_id, chat_type, target).await {
+        Ok(id) => id,
+        Err(e) => return e,
+    };
+    let rows = sqlx::query("SELECT u.username, r.last_read_at FROM read_receipts r JOIN users u ON u.id = r.user_id WHERE r.chat_id = ? ORDER BY u.username")
+        .bind(&chat_id)
+        .fetch_all(&db.pool)
+        .await;
+    match rows {
+        Ok(rows) => {
+            let read: Vec<String> = rows.iter().map(|r| format!("{}:{}", r.get::<String, _>("username"), r.get::<i64, _>("last_read_at"))).collect();
+            format!("OK: read_by: {}", read.join(", "))
+        }
+        Err(e) => format!("ERR: {}", e),
+    }
+}
+
+pub async fn archive_chat(db: Arc<Database>, session_token: &str, chat_type: &str, target: &str) -> String {
+    let user_id = match auth::validate_session(db.clone(), session_token).await {
+        Some(uid) => uid,
+        None => return "ERR: Invalid session".to_string(),
+    };
+    let chat_id = match resolve_chat_id(&db, &user_id, chat_type, target).await {
+        Ok(id) => id,
+        Err(e) => return e,
+    };
+    let now = chrono::Utc::now().timestamp();
+    let res = sqlx::query("INSERT OR REPLACE INTO archived_chats (user_id, chat_id, archived_at) VALUES (?, ?, ?)")
+        .bind(&user_id)
+        .bind(&chat_id)
+        .bind(now)
+        .execute(&db.pool)
+        .await;
+    match res {
+        Ok(_) => {
+            println!("[MSG] User {} archived {}", user_id, chat_id);
+            "OK: Chat archived".to_string()
+        }
+        Err(e) => format!("ERR: {}", e),
+    }
+}
+
+pub async fn unarchive_chat(db: Arc<Database>, session_token: &str, chat_type: &str, target: &str) -> String {
+    let user_id = match auth::validate_session(db.clone(), session_token).await {
+        Some(uid) => uid,
+        None => return "ERR: Invalid session".to_string(),
+    };
+    let chat_id = match resolve_chat_id(&db, &user_id, chat_type, target).await {
+        Ok(id) => id,
+        Err(e) => return e,
+    };
+    let res = sqlx::query("DELETE FROM archived_chats WHERE user_id = ? AND chat_id = ?")
+        .bind(&user_id)
+        .bind(&chat_id)
+        .execute(&db.pool)
+        .await;
+    match res {
+        Ok(r) if r.rows_affected() > 0 => "OK: Chat unarchived".to_string(),
+        Ok(_) => "ERR: Chat is not archived".to_string(),
+        Err(e) => format!("ERR: {}", e),
+    }
+}
+
+/// Usernames of the private chats `user_id` has archived
+pub async fn archived_private_usernames(db: &Arc<Database>, user_id: &str) -> std::collections::HashSet<String> {
+    let rows = sqlx::query("SELECT chat_id FROM archived_chats WHERE user_id = ? AND chat_id LIKE 'private:%'")
+        .bind(user_id)
+        .fetch_all(&db.pool)
+        .await
+        .unwrap_or_default();
+    let mut usernames = std::collections::HashSet::new();
+    for row in rows {
+        let chat_id: String = row.get("chat_id");
+        let Some(other_id) = other_private_participant(&chat_id, user_id) else { continue };
+        if let Ok(Some(r)) = sqlx::query("SELECT username FROM users WHERE id = ?")
+            .bind(other_id)
+            .fetch_optional(&db.pool)
+            .await
+        {
+            usernames.insert(r.get::<String,_>("username"));
+        }
+    }
+    usernames
+}
+
+/// Given `private:<a>-<b>` and one of the two ids, return the other one.
+/// User ids are UUIDs and contain `-` themselves, so strip the known id instead of splitting.
+fn other_private_participant<'a>(chat_id: &'a str, user_id: &str) -> Option<&'a str> {
+    let pair = chat_id.strip_prefix("private:")?;
+    pair.strip_prefix(user_id)
+        .and_then(|rest| rest.strip_prefix('-'))
+        .or_else(|| pair.strip_suffix(user_id).and_then(|rest| rest.strip_suffix('-')))
+}
+
+/// List archived chats as `private:<username>` or `group:<group_id>:<group_name>` entries
+pub async fn list_archived_chats(db: Arc<Database>, session_token: &str) -> String {
+    let user_id = match auth::validate_session(db.clone(), session_token).await {
+        Some(uid) => uid,
+        None => return "ERR: Invalid session".to_string(),
+    };
+    let mut entries: Vec<String> = archived_private_usernames(&db, &user_id)
+        .await
+        .into_iter()
+        .map(|u| format!("private:{}", u))
+        .collect();
+    entries.sort();
+    let groups = sqlx::query("SELECT g.id, g.name FROM archived_chats a JOIN groups g ON a.chat_id = 'group:' || g.id WHERE a.user_id = ? ORDER BY g.name")
+        .bind(&user_id)
+        .fetch_all(&db.pool)
+        .await;
+    match groups {
+        Ok(rows) => {
+            for r in rows {
+                entries.push(format!("group:{}:{}", r.get::<String,_>("id"), r.get::<String,_>("name")));
+            }
+            format!("OK: Archived chats: {}", entries.join(", "))
+        }
+        Err(e) => format!("ERR: {}", e),
+    }
+}
+
+/// Most recently active chats of the user, newest first, as `<ts>:private:<username>`
+/// or `<ts>:group:<group_id>:<group_name>` entries. Archived chats and messages
+/// discarded by the user are ignored.
+pub async fn recent_chats(db: Arc<Database>, session_token: &str, limit: i64) -> String {
+    let user_id = match auth::validate_session(db.clone(), session_token).await {
+        Some(uid) => uid,
+        None => return "ERR: Invalid session".to_string(),
+    };
+    let rows = sqlx::query(r#"
+        SELECT m.chat_id, MAX(m.sent_at) AS last_ts
+        FROM encrypted_messages m
+        WHERE ((m.chat_id LIKE 'private:%' AND instr(m.chat_id, ?) > 0)
+               OR m.chat_id IN (SELECT 'group:' || group_id FROM group_members WHERE user_id = ?))
+          AND m.chat_id NOT IN (SELECT chat_id FROM archived_chats WHERE user_id = ?)
+          AND m.sent_at > COALESCE((SELECT deleted_at FROM deleted_chats d WHERE d.user_id = ? AND d.chat_id = m.chat_id), 0)
+        GROUP BY m.chat_id
+        ORDER BY last_ts DESC
+        LIMIT ?
+    "#)
+        .bind(&user_id)
+        .bind(&user_id)
+        .bind(&user_id)
+        .bind(&user_id)
+        .bind(limit)
+        .fetch_all(&db.pool)
+        .await;
+    let rows = match rows {
+        Ok(rows) => rows,
+        Err(e) => return format!("ERR: {}", e),
+    };
+
+    let mut entries = Vec::new();
+    for row in rows {
+        let chat_id: String = row.get("chat_id");
+        let last_ts: i64 = row.get("last_ts");
+        if let Some(group_id) = chat_id.strip_prefix("group:") {
+            let name = sqlx::query("SELECT name FROM groups WHERE id = ?")
+                .bind(group_id)
+                .fetch_optional(&db.pool)
+                .await
+                .ok()
+                .flatten()
+                .map(|r| r.get::<String,_>("name"))
+                .unwrap_or_else(|| group_id.to_string());
+            entries.push(format!("{}:group:{}:{}", last_ts, group_id, name));
+        } else if let Some(other_id) = other_private_participant(&chat_id, &user_id) {
+            if let Ok(Some(r)) = sqlx::query("SELECT username FROM users WHERE id = ?")
+                .bind(other_id)
+                .fetch_optional(&db.pool)
+                .await
+            {
+                entries.push(format!("{}:private:{}", last_ts, r.get::<String,_>("username")));
+            }
+        }
+    }
+    format!("OK: Recent chats: {}", entries.join(", "))
+}
+
+/// Largest burst of messages replayed to a reconnecting WebSocket client
+pub const MAX_MISSED_MESSAGES: i64 = 500;
+
+/// Private and group messages `user_id` received since `since`, oldest first, in the
+/// `new_message` format of the WebSocket live delivery. Sent to a client that reconnects
+/// with `?since=<ts>` so it catches up on what arrived while it was disconnected.
+/// sent_at has a one-second resolution, so the second of `since` is replayed whole.
+pub async fn missed_messages(db: &Arc<Database>, user_id: &str, since: i64, config: &ServerConfig) -> Result<Vec<serde_json::Value>, sqlx::Error> {
+    let rows = sqlx::query(r#"
+        SELECT m.chat_id, m.sender_id, COALESCE(u.username, m.sender_id) AS sender, m.message, m.key_rotation, m.sent_at, m.reply_to_id
+        FROM encrypted_messages m LEFT JOIN users u ON u.id = m.sender_id
+        WHERE m.sent_at >= ?
+          AND ((m.chat_id LIKE 'private:%' AND instr(m.chat_id, ?) > 0)
+               OR m.chat_id IN (SELECT 'group:' || group_id FROM group_members WHERE user_id = ?))
+          AND m.sent_at > COALESCE((SELECT deleted_at FROM deleted_chats d WHERE d.user_id = ? AND d.chat_id = m.chat_id), 0)
+        ORDER BY m.sent_at ASC, m.id ASC
+        LIMIT ?
+    "#)
+        .bind(since)
+        .bind(user_id)
+        .bind(user_id)
+        .bind(user_id)
+        .bind(MAX_MISSED_MESSAGES)
+        .fetch_all(&db.pool)
+        .await?;
+
+    let mut usernames: std::collections::HashMap<String, String> = std::collections::HashMap::new();
+    let mut rosters: std::collections::HashMap<String, GroupRoster> = std::collections::HashMap::new();
+    let mut events = Vec::with_capacity(rows.len());
+    for row in rows {
+        let chat_id: String = row.get("chat_id");
+        let sender_id: String = row.get("sender_id");
+        let msg: String = row.get("message");
+        let key_rotation = key_rotation_of(&row);
+        let mut event = serde_json::json!({
+            "message_type": "new_message",
+            "from_user": row.get::<String, _>("sender"),
+            "reply_to_id": row.get::<Option<i64>, _>("reply_to_id"),
+            "timestamp": row.get::<i64, _>("sent_at"),
+        });
+        if let Some(group_id) = chat_id.strip_prefix("group:") {
+            if !rosters.contains_key(group_id) {
+                rosters.insert(group_id.to_string(), GroupRoster::load(db, group_id).await);
+            }
+            let candidates = rosters[group_id].candidates(row.get("sent_at"), message_key_version(&msg), &sender_id);
+            event["chat_type"] = "group".into();
+            event["group_id"] = group_id.into();
+            event["content"] = decrypt_group_message(&chat_id, &msg, &candidates, key_rotation, config).into();
+        } else if let Some(other_id) = other_private_participant(&chat_id, user_id) {
+            // The recipient is whoever of the two did not send it
+            let recipient_id = if sender_id == user_id { other_id } else { user_id };
+            if !usernames.contains_key(recipient_id) {
+                let Some(r) = sqlx::query("SELECT username FROM users WHERE id = ?")
+                    .bind(recipient_id)
+                    .fetch_optional(&db.pool)
+                    .await?
+                else { continue };
+                usernames.insert(recipient_id.to_string(), r.get("username"));
+            }
+            let mut participants = vec![user_id.to_string(), other_id.to_string()];
+            participants.sort();
+            event["chat_type"] = "private".into();
+            event["to_user"] = usernames[recipient_id].clone().into();
+            event["content"] = decrypt_message_from_storage(&chat_id, &msg, &participants, key_rotation, config)
+                .unwrap_or_else(|_| "[DECRYPTION FAILED]".to_string())
+                .into();
+        } else {
+            continue;
+        }
+        events.push(event);
+    }
+    Ok(events)
+}
+
+// EXPORT
+/// Maximum size of each line of an `/export_chat` response
+const EXPORT_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Serialize the exported messages as a JSON array and split it into lines of at most
+/// `EXPORT_CHUNK_BYTES`. serde_json escapes control characters, so the document itself
+/// never contains a newline and clients rebuild it by concatenating the lines.
+fn export_response(entries: Vec<serde_json::Value>) -> String {
+    let json = serde_json::Value::Array(entries).to_string();
+    let mut out = String::from("OK: Export:");
+    let mut rest = json.as_str();
+    while !rest.is_empty() {
+        let mut end = rest.len().min(EXPORT_CHUNK_BYTES);
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        out.push('\n');
+        out.push_str(&rest[..end]);
+        rest = &rest[end..];
+    }
+    out
+}
+
+/// Rows of `chat_id` visible to `user_id` (after any chat deletion), oldest first:
+/// (id, sender_id, sender username, stored message, key rotation, sent_at)
+async fn export_rows(db: &Arc<Database>, user_id: &str, chat_id: &str) -> Result<Vec<(i64, String, String, String, u32, i64)>, sqlx::Error> {
+    let rows = sqlx::query(r#"
+        SELECT m.id, m.sender_id, COALESCE(u.username, m.sender_id) AS sender, m.message, m.key_rotation, m.sent_at
+        FROM encrypted_messages m LEFT JOIN users u ON u.id = m.sender_id
+        WHERE m.chat_id = ?
+          AND m.sent_at > COALESCE((SELECT deleted_at FROM deleted_chats d WHERE d.user_id = ? AND d.chat_id = m.chat_id), 0)
+        ORDER BY m.sent_at ASC, m.id ASC
+    "#)
+        .bind(chat_id)
+        .bind(user_id)
+        .fetch_all(&db.pool)
+        .await?;
+    Ok(rows
+        .iter()
+        .map(|r| (r.get("id"), r.get("sender_id"), r.get("sender"), r.get("message"), key_rotation_of(r), r.get("sent_at")))
+        .collect())
+}
+
+/// Export a private chat as JSON: `[{"id", "sender", "content", "timestamp"}, ...]` with decrypted content
+pub async fn export_private_chat(db: Arc<Database>, session_token: &str, other_username: &str, config: &ServerConfig) -> String {
+    let user_id = match auth::validate_session(db.clone(), session_token).await {
+        Some(uid) => uid,
+        None => return "ERR: Invalid or expired session".to_string(),
+    };
+    let other_id = match sqlx::query("SELECT id FROM users WHERE username = ?")
+        .bind(other_username)
+        .fetch_optional(&db.pool)
+        .await
+    {
+        Ok(Some(row)) => row.get::<String,_>("id"),
+        _ => return "ERR: User not found".to_string(),
+    };
+    let mut participants = vec![user_id.clone(), other_id];
+    participants.sort();
+    let chat_id = format!("private:{}-{}", participants[0], participants[1]);
+    match export_rows(&db, &user_id, &chat_id).await {
+        Ok(rows) => {
+            let entries = rows
+                .into_iter()
+                .map(|(id, _, sender, msg, key_rotation, ts)| {
+                    let content = decrypt_message_from_storage(&chat_id, &msg, &participants, key_rotation, config).unwrap_or_else(|_| "[DECRYPTION FAILED]".to_string());
+                    serde_json::json!({ "id": id, "sender": sender, "content": content, "timestamp": ts })
+                })
+                .collect();
+            println!("[MSG] Exported private chat {} for {}", chat_id, user_id);
+            export_response(entries)
+        }
+        Err(e) => format!("ERR: {}", e),
+    }
+}
+
+/// Export a group chat as JSON, same format as `export_private_chat`
+pub async fn export_group_chat(db: Arc<Database>, session_token: &str, group_id: &str, config: &ServerConfig) -> String {
+    let user_id = match auth::validate_session(db.clone(), session_token).await {
+        Some(uid) => uid,
+        None => return "ERR: Invalid or expired session".to_string(),
+    };
+    let chat_id = match resolve_chat_id(&db, &user_id, "group", group_id).await {
+        Ok(chat_id) => chat_id,
+        Err(e) => return e,
+    };
+    let roster = GroupRoster::load(&db, group_id).await;
+    match export_rows(&db, &user_id, &chat_id).await {
+        Ok(rows) => {
+            let entries = rows
+                .into_iter()
+                .map(|(id, sender_id, sender, msg, key_rotation, ts)| {
+                    let content = decrypt_group_message(&chat_id, &msg, &roster.candidates(ts, message_key_version(&msg), &sender_id), key_rotation, config);
+                    serde_json::json!({ "id": id, "sender": sender, "content": content, "timestamp": ts })
+                })
+                .collect();
+            println!("[MSG] Exported group chat {} for {}", chat_id, user_id);
+            export_response(entries)
+        }
+        Err(e) => format!("ERR: {}", e),
+    }
+}
+
+/// Default and largest number of `/search_messages` results
+pub const DEFAULT_SEARCH_RESULTS: i64 = 20;
+pub const MAX_SEARCH_RESULTS: i64 = 100;
+
+/// FTS5 query matching every word of `input`; each word is quoted so operators and
+/// punctuation typed by the user are searched literally instead of failing to parse.
+///
+/// ```
+/// use ruggine_modulare::server::messages::fts_query;
+///
+/// assert_eq!(fts_query("hello world"), "\"hello\" \"world\"");
+/// assert_eq!(fts_query("say \"hi\" OR"), "\"say\" \"\"\"hi\"\"\" \"OR\"");
+/// assert_eq!(fts_query("   "), "");
+/// ```
+pub fn fts_query(input: &str) -> String {
+    input.split_whitespace()
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Up to `limit` messages matching `query` in the caller's chats, newest first, under
+/// "OK: Search results:". Each line carries its chat as `chat=private:<username>` or
+/// `chat=group:<group_id>` in the header. Only available while messages are stored in clear.
+pub async fn search_messages(db: Arc<Database>, session_token: &str, query: &str, limit: i64, config: &ServerConfig) -> String {
+    if config.enable_encryption {
+        return "ERR: Search unavailable in encrypted mode".to_string();
+    }
+    let user_id = match auth::validate_session(db.clone(), session_token).await {
+        Some(uid) => uid,
+        None => return "ERR: Invalid session".to_string(),
+    };
+    let fts = fts_query(query);
+    if fts.is_empty() {
+        return "ERR: Empty search query".to_string();
+    }
+    // Only chats the caller takes part in, without the history they deleted
+    let rows = sqlx::query(r#"
+        SELECT m.id, m.sender_id, m.sent_at, m.chat_id, m.message, m.key_rotation, m.edited_at, COALESCE(u.username, m.sender_id) AS sender
+        FROM encrypted_messages m
+        JOIN messages_fts fts ON m.id = fts.message_id
+        LEFT JOIN users u ON u.id = m.sender_id
+        WHERE fts.content MATCH ?
+          AND (m.chat_id LIKE ? OR m.chat_id LIKE ?
+               OR m.chat_id IN (SELECT 'group:' || group_id FROM group_members WHERE user_id = ?))
+          AND m.sent_at > COALESCE((SELECT deleted_at FROM deleted_chats d WHERE d.user_id = ? AND d.chat_id = m.chat_id), 0)
+        ORDER BY m.sent_at DESC
+        LIMIT ?
+    "#)
+        .bind(&fts)
+        .bind(format!("private:{}-%", user_id))
+        .bind(format!("private:%-{}", user_id))
+        .bind(&user_id)
+        .bind(&user_id)
+        .bind(limit.clamp(1, MAX_SEARCH_RESULTS))
+        .fetch_all(&db.pool)
+        .await;
+    let rows = match rows {
+        Ok(rows) => rows,
+        Err(e) => return format!("ERR: DB error: {}", e),
+    };
+
+    let mut usernames: std::collections::HashMap<String, String> = std::collections::HashMap::new();
+    let mut lines = Vec::with_capacity(rows.len());
+    for r in rows.iter() {
+        let chat_id: String = r.get("chat_id");
+        let chat = match chat_id.strip_prefix("private:") {
+            Some(pair) => {
+                let other_id = pair.strip_prefix(&format!("{}-", user_id))
+                    .or_else(|| pair.strip_suffix(&format!("-{}", user_id)))
+                    .unwrap_or(pair)
+                    .to_string();
+                if !usernames.contains_key(&other_id) {
+                    let name = sqlx::query("SELECT username FROM users WHERE id = ?")
+                        .bind(&other_id)
+                        .fetch_optional(&db.pool)
+                        .await
+                        .ok()
+                        .flatten()
+                        .map(|row| row.get::<String, _>("username"))
+                        .unwrap_or_else(|| "Unknown".to_string());
+                    usernames.insert(other_id.clone(), name);
+                }
+                format!("private:{}", usernames[&other_id])
+            }
+            None => chat_id.clone(),
+        };
+        let message: String = r.get("message");
+        let sender_id: String = r.get("sender_id");
+        let clear = decrypt_message_from_storage(&chat_id, &message, &[sender_id], key_rotation_of(r), config).unwrap_or_else(|_| "[DECRYPTION FAILED]".to_string());
+        let header = message_header(r.get("sent_at"), Some(r.get("id")), MessageLinks::default(), None, None, r.get("edited_at"), None);
+        // The chat goes inside the header, right before its closing bracket
+        lines.push(format!("{} chat={}] {}: {}", header.trim_end_matches(']'), chat, r.get::<String, _>("sender"), clear));
+    }
+    format!("OK: Search results:\n{}", lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::server::test_support::{group_id, register, server};
+
+    #[tokio::test]
+    async fn history_stays_readable_after_members_leave_or_are_kicked() {
+        let server = server().await;
+        let alice = register(&server, "alice").await;
+        let bobby = register(&server, "bobby").await;
+        let carol = register(&server, "carol").await;
+        let resp = server.handle_command("/create_group", &[&alice, "friends", "bobby,carol"]).await;
+        assert!(resp.starts_with("OK:"), "{}", resp);
+        let group = group_id(&server, "friends").await;
+
+        server.handle_command("/send_group_message", &[&alice, &group, "hello before leave"]).await;
+        assert!(server.handle_command("/leave_group", &[&carol, &group]).await.starts_with("OK:"));
+        server.handle_command("/send_group_message", &[&bobby, &group, "hello before kick"]).await;
+        assert!(server.handle_command("/kick_from_group", &[&alice, &group, "bobby"]).await.starts_with("OK:"));
+        server.handle_command("/send_group_message", &[&alice, &group, "alone now"]).await;
+
+        let history = server.handle_command("/get_group_messages", &[&alice, &group]).await;
+        assert!(!history.contains("[DECRYPTION FAILED]"), "{}", history);
+        for line in ["alice: hello before leave", "bobby: hello before kick", "alice: alone now"] {
+            assert!(history.contains(line), "missing {:?} in {}", line, history);
+        }
+    }
+}
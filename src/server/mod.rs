@@ -2,10 +2,17 @@ pub mod database;
 pub mod connection;
 pub mod chat_manager;
 pub mod config;
+pub mod logging;
 pub mod auth;
+pub mod auth_cache;
 pub mod users;
+pub mod admin;
 pub mod groups;
 pub mod messages;
+pub mod reactions;
+pub mod file_transfer;
 pub mod presence;
 pub mod websocket;
 pub mod redis_cache;
+#[cfg(test)]
+mod test_support;
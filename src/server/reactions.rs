@@ -0,0 +1,155 @@
+// Reazioni emoji ai messaggi
+use crate::server::{database::Database, auth};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use sqlx::Row;
+use base64::{Engine as _, engine::general_purpose};
+
+/// Longest emoji accepted, in bytes; enough for skin tones and ZWJ sequences
+const MAX_EMOJI_BYTES: usize = 32;
+
+/// Emoji -> usernames who reacted with it, in reaction order
+pub type Reactions = BTreeMap<String, Vec<String>>;
+
+/// Whether `emoji` can be stored as a reaction: short, without whitespace and not plain text.
+///
+/// ```
+/// use ruggine_modulare::server::reactions::is_valid_emoji;
+///
+/// assert!(is_valid_emoji("👍"));
+/// assert!(is_valid_emoji("👍🏽"));
+/// assert!(!is_valid_emoji("ok"));
+/// assert!(!is_valid_emoji(""));
+/// ```
+pub fn is_valid_emoji(emoji: &str) -> bool {
+    !emoji.is_empty() && emoji.len() <= MAX_EMOJI_BYTES && !emoji.is_ascii() && !emoji.chars().any(char::is_whitespace)
+}
+
+/// `reactions=<base64 JSON>` field of a message header; base64 keeps usernames
+/// and emoji from clashing with the header's spaces and closing bracket.
+///
+/// ```
+/// use ruggine_modulare::server::reactions::{header_field, Reactions};
+///
+/// let mut reactions = Reactions::new();
+/// reactions.insert("👍".to_string(), vec!["alice".to_string()]);
+/// let field = header_field(&reactions);
+/// assert!(field.starts_with(" reactions="));
+/// assert!(!field[1..].contains(' ') && !field.contains(']'));
+/// ```
+pub fn header_field(reactions: &Reactions) -> String {
+    let json = serde_json::to_string(reactions).unwrap_or_default();
+    format!(" reactions={}", general_purpose::STANDARD.encode(json))
+}
+
+/// Session user and chat of message `message_id`, if the user takes part in that chat
+pub(crate) async fn open_message(db: &Arc<Database>, session_token: &str, message_id: i64) -> Result<String, String> {
+    let user_id = match auth::validate_session(db.clone(), session_token).await {
+        Some(uid) => uid,
+        None => return Err("ERR: Invalid session".to_string()),
+    };
+    let chat_id = match sqlx::query("SELECT chat_id FROM encrypted_messages WHERE id = ?")
+        .bind(message_id)
+        .fetch_optional(&db.pool)
+        .await
+    {
+        Ok(Some(row)) => row.get::<String, _>("chat_id"),
+        Ok(None) => return Err("ERR: Message not found".to_string()),
+        Err(e) => return Err(format!("ERR: DB error: {}", e)),
+    };
+    let participant = if let Some(pair) = chat_id.strip_prefix("private:") {
+        pair.starts_with(&format!("{}-", user_id)) || pair.ends_with(&format!("-{}", user_id))
+    } else if let Some(group_id) = chat_id.strip_prefix("group:") {
+        sqlx::query("SELECT 1 FROM group_members WHERE group_id = ? AND user_id = ?")
+            .bind(group_id)
+            .bind(&user_id)
+            .fetch_optional(&db.pool)
+            .await
+            .ok()
+            .flatten()
+            .is_some()
+    } else {
+        false
+    };
+    if !participant {
+        return Err("ERR: Message not found".to_string());
+    }
+    Ok(user_id)
+}
+
+pub async fn react(db: Arc<Database>, session_token: &str, message_id: i64, emoji: &str) -> String {
+    if !is_valid_emoji(emoji) {
+        return "ERR: Invalid emoji".to_string();
+    }
+    let user_id = match open_message(&db, session_token, message_id).await {
+        Ok(uid) => uid,
+        Err(e) => return e,
+    };
+    let res = sqlx::query("INSERT OR IGNORE INTO message_reactions (message_id, user_id, emoji, created_at) VALUES (?, ?, ?, ?)")
+        .bind(message_id)
+        .bind(&user_id)
+        .bind(emoji)
+        .bind(chrono::Utc::now().timestamp())
+        .execute(&db.pool)
+        .await;
+    match res {
+        Ok(_) => "OK: Reaction added".to_string(),
+        Err(e) => format!("ERR: DB error: {}", e),
+    }
+}
+
+pub async fn unreact(db: Arc<Database>, session_token: &str, message_id: i64, emoji: &str) -> String {
+    let user_id = match open_message(&db, session_token, message_id).await {
+        Ok(uid) => uid,
+        Err(e) => return e,
+    };
+    let res = sqlx::query("DELETE FROM message_reactions WHERE message_id = ? AND user_id = ? AND emoji = ?")
+        .bind(message_id)
+        .bind(&user_id)
+        .bind(emoji)
+        .execute(&db.pool)
+        .await;
+    match res {
+        Ok(_) => "OK: Reaction removed".to_string(),
+        Err(e) => format!("ERR: DB error: {}", e),
+    }
+}
+
+/// Reactions to `message_id` as "OK: Reactions: <JSON map of emoji -> [username, ...]>"
+pub async fn get_reactions(db: Arc<Database>, session_token: &str, message_id: i64) -> String {
+    if let Err(e) = open_message(&db, session_token, message_id).await {
+        return e;
+    }
+    let rows = sqlx::query("SELECT r.emoji, u.username FROM message_reactions r JOIN users u ON u.id = r.user_id WHERE r.message_id = ? ORDER BY r.created_at")
+        .bind(message_id)
+        .fetch_all(&db.pool)
+        .await;
+    match rows {
+        Ok(rows) => {
+            let mut reactions = Reactions::new();
+            for r in rows.iter() {
+                reactions.entry(r.get("emoji")).or_default().push(r.get("username"));
+            }
+            format!("OK: Reactions: {}", serde_json::to_string(&reactions).unwrap_or_default())
+        }
+        Err(e) => format!("ERR: DB error: {}", e),
+    }
+}
+
+/// Reactions to the messages of `chat_id` sent between `first_ts` and `last_ts`, by message id
+pub async fn reactions_in_span(db: &Arc<Database>, chat_id: &str, first_ts: i64, last_ts: i64) -> HashMap<i64, Reactions> {
+    let rows = sqlx::query("SELECT r.message_id, r.emoji, u.username FROM message_reactions r JOIN encrypted_messages m ON m.id = r.message_id JOIN users u ON u.id = r.user_id WHERE m.chat_id = ? AND m.sent_at BETWEEN ? AND ? ORDER BY r.created_at")
+        .bind(chat_id)
+        .bind(first_ts)
+        .bind(last_ts)
+        .fetch_all(&db.pool)
+        .await
+        .unwrap_or_default();
+    let mut by_message: HashMap<i64, Reactions> = HashMap::new();
+    for r in rows.iter() {
+        by_message.entry(r.get("message_id")).or_default()
+            .entry(r.get("emoji")).or_default()
+            .push(r.get("username"));
+    }
+    by_message
+}
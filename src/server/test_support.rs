@@ -0,0 +1,33 @@
+//! Helpers for tests that drive `Server::handle_command` against an in-memory database
+
+use crate::server::{config::ServerConfig, connection::Server, database::Database, presence::PresenceRegistry};
+use std::sync::Arc;
+
+/// Password accepted by `/register` for every test user
+pub const PASSWORD: &str = "passw0rd";
+
+/// A server with default configuration over a fresh, migrated `sqlite::memory:` database
+pub async fn server() -> Server {
+    let db = Arc::new(Database::connect("sqlite::memory:").await.unwrap());
+    db.migrate().await.unwrap();
+    Server { db, config: ServerConfig::default(), presence: PresenceRegistry::new(), ws_manager: None, peer: None }
+}
+
+/// Register `username` and return its session token
+pub async fn register(server: &Server, username: &str) -> String {
+    let resp = server.handle_command("/register", &[username, PASSWORD]).await;
+    resp.split("SESSION: ")
+        .nth(1)
+        .unwrap_or_else(|| panic!("registration of {} failed: {}", username, resp))
+        .trim()
+        .to_string()
+}
+
+/// Id of the group called `name`
+pub async fn group_id(server: &Server, name: &str) -> String {
+    sqlx::query_scalar("SELECT id FROM groups WHERE name = ?")
+        .bind(name)
+        .fetch_one(&server.db.pool)
+        .await
+        .unwrap()
+}
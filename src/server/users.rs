@@ -11,6 +11,16 @@ pub async fn send_friend_request(db: Arc<Database>, from_user_id: &str, to_usern
         Ok(None) => return "ERR: Destinatario non trovato".to_string(),
         Err(e) => return format!("ERR: DB error: {}", e),
     };
+    if to_user_id == from_user_id {
+        return "ERR: Non puoi inviare una richiesta a te stesso".to_string();
+    }
+    // Il destinatario ha bloccato il mittente: non riveliamo il motivo
+    if is_blocked(db.clone(), &to_user_id, from_user_id).await {
+        return "ERR: Cannot send friend request to this user".to_string();
+    }
+    if is_blocked(db.clone(), from_user_id, &to_user_id).await {
+        return "ERR: Unblock this user before sending a friend request".to_string();
+    }
     
     // Controlla se sono già amici
     let friendship_check = sqlx::query("SELECT 1 FROM friendships WHERE (user1_id = ? AND user2_id = ?) OR (user1_id = ? AND user2_id = ?)")
@@ -122,6 +132,187 @@ pub async fn list_friends(db: Arc<Database>, user_id: &str) -> String {
     }
 }
 
+/// Friends with their presence: "OK: alice:1:<last_seen>, bob:0:<last_seen>".
+/// The last_seen field is omitted for users that never connected.
+pub async fn friends_status(db: Arc<Database>, user_id: &str) -> String {
+    let rows = sqlx::query("SELECT u.username, u.is_online, u.last_seen FROM friendships f JOIN users u ON (u.id = f.user1_id OR u.id = f.user2_id) WHERE (f.user1_id = ? OR f.user2_id = ?) AND u.id != ? ORDER BY u.username")
+        .bind(user_id)
+        .bind(user_id)
+        .bind(user_id)
+        .fetch_all(&db.pool)
+        .await;
+    match rows {
+        Ok(rows) => {
+            let friends: Vec<String> = rows.iter().map(|r| {
+                let username: String = r.get("username");
+                let online = r.get::<Option<i64>,_>("is_online").unwrap_or(0) != 0;
+                match r.get::<Option<i64>,_>("last_seen") {
+                    Some(ts) => format!("{}:{}:{}", username, online as u8, ts),
+                    None => format!("{}:{}", username, online as u8),
+                }
+            }).collect();
+            format!("OK: {}", friends.join(", "))
+        }
+        Err(e) => format!("ERR: DB error: {}", e),
+    }
+}
+
+/// When `username` was last connected: "OK: Online", "OK: Last seen: <unix ts>",
+/// or "OK: Last seen: never" for users that never connected.
+pub async fn get_last_seen(db: Arc<Database>, username: &str) -> String {
+    let row = sqlx::query("SELECT is_online, last_seen FROM users WHERE username = ?")
+        .bind(username)
+        .fetch_optional(&db.pool)
+        .await;
+    match row {
+        Ok(Some(r)) if r.get::<Option<i64>,_>("is_online").unwrap_or(0) != 0 => "OK: Online".to_string(),
+        Ok(Some(r)) => match r.get::<Option<i64>,_>("last_seen") {
+            Some(ts) => format!("OK: Last seen: {}", ts),
+            None => "OK: Last seen: never".to_string(),
+        },
+        Ok(None) => "ERR: User not found".to_string(),
+        Err(e) => format!("ERR: DB error: {}", e),
+    }
+}
+
+/// Profile of `username`, or of the caller when `None`: "OK: Profile: <username> avatar_url=<url>".
+/// The avatar_url field is omitted when the user has not set one.
+pub async fn get_profile(db: Arc<Database>, user_id: &str, username: Option<&str>) -> String {
+    let row = match username {
+        Some(username) => sqlx::query("SELECT username, avatar_url FROM users WHERE username = ?").bind(username),
+        None => sqlx::query("SELECT username, avatar_url FROM users WHERE id = ?").bind(user_id),
+    }
+        .fetch_optional(&db.pool)
+        .await;
+    match row {
+        Ok(Some(r)) => match r.get::<Option<String>,_>("avatar_url") {
+            Some(url) => format!("OK: Profile: {} avatar_url={}", r.get::<String,_>("username"), url),
+            None => format!("OK: Profile: {}", r.get::<String,_>("username")),
+        },
+        Ok(None) => "ERR: User not found".to_string(),
+        Err(e) => format!("ERR: DB error: {}", e),
+    }
+}
+
+/// Set or clear (`url = None`) the caller's avatar. Only http(s) URLs are accepted,
+/// and they may not contain whitespace or `]` since they travel inside message headers.
+pub async fn set_avatar_url(db: Arc<Database>, user_id: &str, url: Option<&str>) -> String {
+    if let Some(url) = url {
+        if !(url.starts_with("http://") || url.starts_with("https://")) || url.contains(']') || url.chars().any(char::is_whitespace) {
+            return "ERR: Invalid avatar URL".to_string();
+        }
+    }
+    let res = sqlx::query("UPDATE users SET avatar_url = ? WHERE id = ?")
+        .bind(url)
+        .bind(user_id)
+        .execute(&db.pool)
+        .await;
+    match res {
+        Ok(_) if url.is_some() => "OK: Avatar updated".to_string(),
+        Ok(_) => "OK: Avatar removed".to_string(),
+        Err(e) => format!("ERR: DB error: {}", e),
+    }
+}
+
+/// Longest status message accepted, in characters
+pub const MAX_STATUS_CHARS: usize = 100;
+
+/// Set the caller's status message (an empty text clears it) and notify their friends,
+/// so online contacts see it without polling.
+pub async fn set_status(db: Arc<Database>, user_id: &str, status: &str) -> (String, Vec<WebSocketMessage>) {
+    let status = status.trim();
+    if status.chars().count() > MAX_STATUS_CHARS {
+        return (format!("ERR: Status must be at most {} characters", MAX_STATUS_CHARS), vec![]);
+    }
+    if let Err(e) = sqlx::query("UPDATE users SET status_message = ? WHERE id = ?")
+        .bind(status)
+        .bind(user_id)
+        .execute(&db.pool)
+        .await
+    {
+        return (format!("ERR: DB error: {}", e), vec![]);
+    }
+    let rows = sqlx::query("SELECT CASE WHEN user1_id = ? THEN user2_id ELSE user1_id END AS friend_id FROM friendships WHERE user1_id = ? OR user2_id = ?")
+        .bind(user_id)
+        .bind(user_id)
+        .bind(user_id)
+        .fetch_all(&db.pool)
+        .await
+        .unwrap_or_default();
+    let username = sqlx::query("SELECT username FROM users WHERE id = ?")
+        .bind(user_id)
+        .fetch_optional(&db.pool)
+        .await
+        .ok()
+        .flatten()
+        .map(|r| r.get::<String,_>("username"))
+        .unwrap_or_else(|| user_id.to_string());
+    let content = serde_json::json!({
+        "type": "status_changed",
+        "user_id": user_id,
+        "username": username,
+        "status": status,
+    }).to_string();
+    let now = Utc::now().timestamp();
+    let events = rows.iter()
+        .map(|r| WebSocketMessage {
+            id: uuid::Uuid::new_v4().to_string(),
+            message_type: MessageType::Notification,
+            sender: username.clone(),
+            target: user_id.to_string(),
+            content: content.clone(),
+            timestamp: now,
+            recipient_user_id: Some(r.get("friend_id")),
+        })
+        .collect();
+    let reply = if status.is_empty() { "OK: Status cleared" } else { "OK: Status updated" };
+    (reply.to_string(), events)
+}
+
+/// Status message of `username` as "OK: Status: <text>"; the text is empty when unset
+pub async fn get_status(db: Arc<Database>, username: &str) -> String {
+    let row = sqlx::query("SELECT status_message FROM users WHERE username = ?")
+        .bind(username)
+        .fetch_optional(&db.pool)
+        .await;
+    match row {
+        Ok(Some(r)) => format!("OK: Status: {}", r.get::<Option<String>,_>("status_message").unwrap_or_default()),
+        Ok(None) => "ERR: User not found".to_string(),
+        Err(e) => format!("ERR: DB error: {}", e),
+    }
+}
+
+/// Store the caller's X25519 public key (base64, 32 bytes) for end-to-end encryption.
+/// The server only hands it out; private keys never leave the client.
+pub async fn register_public_key(db: Arc<Database>, user_id: &str, public_key: &str) -> String {
+    if crate::common::crypto::CryptoManager::parse_public_key(public_key).is_none() {
+        return "ERR: Invalid public key".to_string();
+    }
+    let res = sqlx::query("INSERT INTO user_public_keys (user_id, dh_public_key, updated_at) VALUES (?, ?, ?)
+                           ON CONFLICT(user_id) DO UPDATE SET dh_public_key = excluded.dh_public_key, updated_at = excluded.updated_at")
+        .bind(user_id)
+        .bind(public_key)
+        .bind(chrono::Utc::now().timestamp())
+        .execute(&db.pool)
+        .await;
+    match res {
+        Ok(_) => "OK: Public key registered".to_string(),
+        Err(e) => format!("ERR: DB error: {}", e),
+    }
+}
+
+pub async fn get_public_key(db: Arc<Database>, username: &str) -> String {
+    let row = sqlx::query("SELECT k.dh_public_key FROM user_public_keys k JOIN users u ON u.id = k.user_id WHERE u.username = ?")
+        .bind(username)
+        .fetch_optional(&db.pool)
+        .await;
+    match row {
+        Ok(Some(r)) => format!("OK: Public key: {}", r.get::<String,_>("dh_public_key")),
+        Ok(None) => format!("ERR: No public key for {}", username),
+        Err(e) => format!("ERR: DB error: {}", e),
+    }
+}
+
 pub async fn received_friend_requests(db: Arc<Database>, user_id: &str) -> String {
     let rows = sqlx::query("SELECT u.username, fr.message FROM friend_requests fr JOIN users u ON fr.from_user_id = u.id WHERE fr.to_user_id = ? AND fr.status = 'pending'")
         .bind(user_id)
@@ -150,11 +341,190 @@ pub async fn sent_friend_requests(db: Arc<Database>, user_id: &str) -> String {
     }
 }
 
+// BLOCKING
+/// True if `blocker_id` has blocked `blocked_id`
+pub async fn is_blocked(db: Arc<Database>, blocker_id: &str, blocked_id: &str) -> bool {
+    sqlx::query("SELECT 1 FROM blocked_users WHERE blocker_id = ? AND blocked_id = ?")
+        .bind(blocker_id)
+        .bind(blocked_id)
+        .fetch_optional(&db.pool)
+        .await
+        .ok()
+        .flatten()
+        .is_some()
+}
+
+/// True if either user has blocked the other
+pub async fn blocked_between(db: &Arc<Database>, user_a: &str, user_b: &str) -> bool {
+    sqlx::query("SELECT 1 FROM blocked_users WHERE (blocker_id = ? AND blocked_id = ?) OR (blocker_id = ? AND blocked_id = ?)")
+        .bind(user_a)
+        .bind(user_b)
+        .bind(user_b)
+        .bind(user_a)
+        .fetch_optional(&db.pool)
+        .await
+        .ok()
+        .flatten()
+        .is_some()
+}
+
+/// Usernames `blocker_id` has blocked, hidden from their user lists
+async fn blocked_usernames(db: &Arc<Database>, blocker_id: &str) -> std::collections::HashSet<String> {
+    sqlx::query("SELECT u.username FROM blocked_users b JOIN users u ON u.id = b.blocked_id WHERE b.blocker_id = ?")
+        .bind(blocker_id)
+        .fetch_all(&db.pool)
+        .await
+        .unwrap_or_default()
+        .iter()
+        .map(|r| r.get::<String,_>("username"))
+        .collect()
+}
+
+pub async fn block_user(db: Arc<Database>, blocker_id: &str, username: &str) -> String {
+    let row = sqlx::query("SELECT id FROM users WHERE username = ?")
+        .bind(username)
+        .fetch_optional(&db.pool)
+        .await;
+    let blocked_id = match row {
+        Ok(Some(r)) => r.get::<String,_>("id"),
+        Ok(None) => return "ERR: Utente non trovato".to_string(),
+        Err(e) => return format!("ERR: DB error: {}", e),
+    };
+    if blocked_id == blocker_id {
+        return "ERR: Non puoi bloccare te stesso".to_string();
+    }
+    let now = Utc::now().timestamp();
+    let res = sqlx::query("INSERT OR IGNORE INTO blocked_users (blocker_id, blocked_id, created_at) VALUES (?, ?, ?)")
+        .bind(blocker_id)
+        .bind(&blocked_id)
+        .bind(now)
+        .execute(&db.pool)
+        .await;
+    if let Err(e) = res {
+        return format!("ERR: DB error: {}", e);
+    }
+    // Scarta le richieste pendenti dell'utente bloccato
+    let _ = sqlx::query("UPDATE friend_requests SET status = 'rejected' WHERE from_user_id = ? AND to_user_id = ? AND status = 'pending'")
+        .bind(&blocked_id)
+        .bind(blocker_id)
+        .execute(&db.pool)
+        .await;
+    format!("OK: {} bloccato", username)
+}
+
+pub async fn unblock_user(db: Arc<Database>, blocker_id: &str, username: &str) -> String {
+    let res = sqlx::query("DELETE FROM blocked_users WHERE blocker_id = ? AND blocked_id = (SELECT id FROM users WHERE username = ?)")
+        .bind(blocker_id)
+        .bind(username)
+        .execute(&db.pool)
+        .await;
+    match res {
+        Ok(r) if r.rows_affected() > 0 => format!("OK: {} sbloccato", username),
+        Ok(_) => "ERR: Utente non bloccato".to_string(),
+        Err(e) => format!("ERR: DB error: {}", e),
+    }
+}
+
+pub async fn list_blocked(db: Arc<Database>, session_token: &str) -> String {
+    let user_id = match crate::server::auth::validate_session(db.clone(), session_token).await {
+        Some(uid) => uid,
+        None => return "ERR: Invalid or expired session".to_string(),
+    };
+    let rows = sqlx::query("SELECT u.username FROM blocked_users b JOIN users u ON u.id = b.blocked_id WHERE b.blocker_id = ? ORDER BY u.username")
+        .bind(&user_id)
+        .fetch_all(&db.pool)
+        .await;
+    match rows {
+        Ok(rows) => {
+            let users: Vec<String> = rows.iter().map(|r| r.get::<String,_>("username")).collect();
+            format!("OK: Blocked users: {}", users.join(", "))
+        }
+        Err(e) => format!("ERR: DB error: {}", e),
+    }
+}
+
+// ACCOUNT REMOVAL
+/// Remove a user and every row that references them, in one transaction
+pub async fn delete_user(db: Arc<Database>, username: &str) -> String {
+    let user_id = match sqlx::query("SELECT id FROM users WHERE username = ?")
+        .bind(username)
+        .fetch_optional(&db.pool)
+        .await
+    {
+        Ok(Some(r)) => r.get::<String,_>("id"),
+        Ok(None) => return "ERR: Utente non trovato".to_string(),
+        Err(e) => return format!("ERR: DB error: {}", e),
+    };
+    let mut tx = match db.pool.begin().await {
+        Ok(tx) => tx,
+        Err(e) => return format!("ERR: DB error: {}", e),
+    };
+    let deleted = match sqlx::query("DELETE FROM encrypted_messages WHERE chat_id LIKE 'private:%' AND instr(chat_id, ?) > 0")
+        .bind(&user_id)
+        .execute(&mut *tx)
+        .await
+    {
+        Ok(_) => remove_user_rows(&mut tx, &user_id).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = deleted {
+        println!("[USERS] Error deleting user {}: {}", username, e);
+        return format!("ERR: DB error: {}", e);
+    }
+    if let Err(e) = tx.commit().await {
+        return format!("ERR: DB error: {}", e);
+    }
+    db.auth_cache.invalidate_user(&user_id);
+    println!("[USERS] Deleted user {} ({})", username, user_id);
+    format!("OK: Utente {} eliminato", username)
+}
+
+/// Delete every row about `user_id` except the messages they sent, which each caller handles:
+/// `/delete_account` keeps them for the other participants, `/admin_delete_user` drops private ones.
+/// The user leaves each group through a `left` event, so group history stays readable.
+pub(crate) async fn remove_user_rows(tx: &mut sqlx::Transaction<'_, sqlx::Sqlite>, user_id: &str) -> Result<(), sqlx::Error> {
+    let group_ids: Vec<String> = sqlx::query_scalar("SELECT group_id FROM group_members WHERE user_id = ?")
+        .bind(user_id)
+        .fetch_all(&mut **tx)
+        .await?;
+    for group_id in group_ids {
+        groups::record_departure(tx, &group_id, user_id).await?;
+    }
+    let statements = [
+        "DELETE FROM group_members WHERE user_id = ?1",
+        "DELETE FROM group_invites WHERE invited_user_id = ?1 OR invited_by = ?1",
+        "DELETE FROM friend_requests WHERE from_user_id = ?1 OR to_user_id = ?1",
+        "DELETE FROM friendships WHERE user1_id = ?1 OR user2_id = ?1",
+        "DELETE FROM blocked_users WHERE blocker_id = ?1 OR blocked_id = ?1",
+        "DELETE FROM message_reactions WHERE user_id = ?1",
+        "DELETE FROM message_receipts WHERE user_id = ?1",
+        "DELETE FROM read_receipts WHERE user_id = ?1",
+        "DELETE FROM archived_chats WHERE user_id = ?1",
+        "DELETE FROM deleted_chats WHERE user_id = ?1",
+        "DELETE FROM user_encryption_keys WHERE user_id = ?1",
+        "DELETE FROM user_keys WHERE user_id = ?1",
+        "DELETE FROM user_public_keys WHERE user_id = ?1",
+        "DELETE FROM session_events WHERE user_id = ?1",
+        "DELETE FROM sessions WHERE user_id = ?1",
+        "DELETE FROM auth WHERE user_id = ?1",
+        "DELETE FROM users WHERE id = ?1",
+    ];
+    for sql in statements {
+        sqlx::query(sql).bind(user_id).execute(&mut **tx).await?;
+    }
+    Ok(())
+}
+
 // HELP
 pub async fn help() -> String {
     let help = "Comandi disponibili:\n\
+    HELLO <client_version>\n\
     /register <username> <password>\n\
-    /login <username> <password>\n\
+    /login <username> <password> [device_label]\n\
+    /change_password <old_password> <new_password>\n\
+    /list_sessions\n\
+    /revoke_session <session_id>\n\
+    /delete_account <password>\n\
     /logout\n\
     /users\n\
     /all_users\n\
@@ -162,16 +532,148 @@ pub async fn help() -> String {
     /accept_friend_request <username>\n\
     /reject_friend_request <username>\n\
     /list_friends\n\
+    /friends_status\n\
+    /get_profile [username]\n\
+    /get_last_seen <username>\n\
+    /set_avatar_url <url|none>\n\
+    /set_status <text>\n\
+    /get_status <username>\n\
+    /subscribe_presence <user1,user2,...>\n\
+    /publish_key <base64_x25519_key>\n\
+    /get_public_key <username>\n\
     /received_friend_requests\n\
     /sent_friend_requests\n\
+    /block_user <username>\n\
+    /unblock_user <username>\n\
+    /list_blocked\n\
+    /archive_chat <private|group> <username|group_id>\n\
+    /unarchive_chat <private|group> <username|group_id>\n\
+    /list_archived\n\
+    /recent_chats <limit>\n\
+    /export_chat <private|group> <username|group_id>\n\
+    /get_receipts <message_id>\n\
+    /mark_read <private|group> <username|group_id> <timestamp>\n\
+    /get_read_receipts <private|group> <username|group_id>\n\
+    /search_messages <query> [limit]\n\
+    /send_reply <private:username|group:group_id> <reply_to_message_id> <content>\n\
+    /forward_message <message_id> <private:username|group:group_id>\n\
+    /edit_message <message_id> <new_content>\n\
+    /delete_message <message_id>\n\
+    /react <message_id> <emoji>\n\
+    /unreact <message_id> <emoji>\n\
+    /get_reactions <message_id>\n\
+    /search_groups <query>\n\
+    /list_public_groups [page] [query]\n\
+    /generate_invite_code <group_id> [expires_secs] [max_uses]\n\
+    /join_by_code <code>\n\
+    /group_members <group_id>\n\
+    /group_members_status <group_id>\n\
+    /set_group_public <group_id> <on|off>\n\
+    /rename_group <group_id> <new_name>\n\
+    /set_group_description <group_id> <text>\n\
+    /get_group_description <group_id>\n\
+    /pin_message <group_id> <message_id>\n\
+    /unpin_message <group_id> <message_id>\n\
+    /get_pinned_messages <group_id>\n\
+    /delete_group <group_id>\n\
+    /kick_from_group <group_id> <username>\n\
+    /set_group_admin <group_id> <username>\n\
+    /demote_group_admin <group_id> <username>\n\
     /help\n\
     /quit\n";
     help.to_string()
 }
 use crate::server::database::Database;
+use crate::server::groups;
+use crate::server::websocket::{WebSocketMessage, MessageType};
 use std::sync::Arc;
 use sqlx::Row;
 
+// PRESENCE
+/// Mark the user online and refresh `last_seen`
+pub async fn mark_online(db: &Arc<Database>, user_id: &str) {
+    let now = Utc::now().timestamp();
+    match sqlx::query("UPDATE users SET is_online = 1, last_seen = ? WHERE id = ?")
+        .bind(now)
+        .bind(user_id)
+        .execute(&db.pool)
+        .await
+    {
+        Ok(_) => println!("[DB] Set is_online=1 for user {}", user_id),
+        Err(e) => println!("[DB] Failed to set is_online=1 for {}: {}", user_id, e),
+    }
+}
+
+/// Mark the user offline and record `last_seen`
+pub async fn mark_offline(db: &Arc<Database>, user_id: &str) {
+    let now = Utc::now().timestamp();
+    match sqlx::query("UPDATE users SET is_online = 0, last_seen = ? WHERE id = ?")
+        .bind(now)
+        .bind(user_id)
+        .execute(&db.pool)
+        .await
+    {
+        Ok(_) => println!("[DB] Set is_online=0 for user {}", user_id),
+        Err(e) => println!("[DB] Failed to set is_online=0 for {}: {}", user_id, e),
+    }
+}
+
+/// Largest number of users a `/subscribe_presence` call can watch
+pub const MAX_PRESENCE_SUBSCRIPTIONS: usize = 500;
+
+/// Ids of the comma-separated `usernames` for `/subscribe_presence`; errors on unknown names
+pub async fn presence_targets(db: &Arc<Database>, usernames: &str) -> Result<std::collections::HashSet<String>, String> {
+    let usernames: std::collections::HashSet<&str> = usernames.split(',').map(str::trim).filter(|u| !u.is_empty()).collect();
+    if usernames.len() > MAX_PRESENCE_SUBSCRIPTIONS {
+        return Err(format!("ERR: At most {} users per presence subscription", MAX_PRESENCE_SUBSCRIPTIONS));
+    }
+    let mut ids = std::collections::HashSet::new();
+    for username in usernames {
+        match sqlx::query("SELECT id FROM users WHERE username = ?")
+            .bind(username)
+            .fetch_optional(&db.pool)
+            .await
+        {
+            Ok(Some(r)) => { ids.insert(r.get::<String,_>("id")); }
+            Ok(None) => return Err(format!("ERR: User {} not found", username)),
+            Err(e) => return Err(format!("ERR: DB error: {}", e)),
+        }
+    }
+    Ok(ids)
+}
+
+/// Notifications telling `subscribers` that `user_id` went online or offline
+pub async fn presence_notifications(db: &Arc<Database>, user_id: &str, online: bool, subscribers: Vec<String>) -> Vec<WebSocketMessage> {
+    if subscribers.is_empty() {
+        return vec![];
+    }
+    let username = sqlx::query("SELECT username FROM users WHERE id = ?")
+        .bind(user_id)
+        .fetch_optional(&db.pool)
+        .await
+        .ok()
+        .flatten()
+        .map(|r| r.get::<String,_>("username"))
+        .unwrap_or_else(|| user_id.to_string());
+    let content = serde_json::json!({
+        "type": "presence",
+        "user": username,
+        "online": online,
+    }).to_string();
+    let now = Utc::now().timestamp();
+    subscribers.into_iter()
+        .map(|subscriber| WebSocketMessage {
+            id: uuid::Uuid::new_v4().to_string(),
+            message_type: MessageType::Notification,
+            sender: username.clone(),
+            target: user_id.to_string(),
+            content: content.clone(),
+            timestamp: now,
+            recipient_user_id: Some(subscriber),
+        })
+        .collect()
+}
+
 pub async fn list_online(db: Arc<Database>) -> String {
     println!("[USERS] Listing online users");
     let rows = sqlx::query("SELECT username FROM users WHERE is_online = 1")
@@ -214,10 +716,16 @@ pub async fn list_online_excluding_self(db: Arc<Database>, session_token: &str)
         .bind(&current_user_id)
         .fetch_all(&db.pool)
         .await;
+    let archived = crate::server::messages::archived_private_usernames(&db, &current_user_id).await;
+    let blocked = blocked_usernames(&db, &current_user_id).await;
     
     match rows {
         Ok(rows) => {
-            let users: Vec<String> = rows.iter().map(|r| r.get::<String,_>("username")).collect();
+            let users: Vec<String> = rows.iter()
+                .map(|r| r.get::<String,_>("username"))
+                .filter(|u| !blocked.contains(u))
+                .map(|u| with_archived_flag(u, &archived))
+                .collect();
             println!("[USERS] Found {} online users excluding {}", users.len(), current_username);
             format!("OK: Online users: {}", users.join(", "))
         }
@@ -228,17 +736,52 @@ pub async fn list_online_excluding_self(db: Arc<Database>, session_token: &str)
     }
 }
 
-pub async fn list_all(db: Arc<Database>, exclude_username: Option<&str>) -> String {
+/// Append ` archived=1` to usernames whose private chat the viewer has archived
+fn with_archived_flag(username: String, archived: &std::collections::HashSet<String>) -> String {
+    if archived.contains(&username) {
+        format!("{} archived=1", username)
+    } else {
+        username
+    }
+}
+
+/// Append ` online=1` or ` last_seen=<ts>` so lists can show presence next to each user
+fn with_presence_flag(entry: String, online: bool, last_seen: Option<i64>) -> String {
+    match (online, last_seen) {
+        (true, _) => format!("{} online=1", entry),
+        (false, Some(ts)) => format!("{} last_seen={}", entry, ts),
+        (false, None) => entry,
+    }
+}
+
+pub async fn list_all(db: Arc<Database>, exclude_username: Option<&str>, session_token: Option<&str>) -> String {
     println!("[USERS] Listing all users");
-    let rows = sqlx::query("SELECT username FROM users")
+    let rows = sqlx::query("SELECT username, is_online, last_seen FROM users")
         .fetch_all(&db.pool)
         .await;
+    // Archived flags and block filtering are only available when the caller identifies itself
+    let (archived, blocked) = match session_token {
+        Some(token) => match crate::server::auth::validate_session(db.clone(), token).await {
+            Some(uid) => (
+                crate::server::messages::archived_private_usernames(&db, &uid).await,
+                blocked_usernames(&db, &uid).await,
+            ),
+            None => Default::default(),
+        },
+        None => Default::default(),
+    };
     match rows {
         Ok(rows) => {
-            let mut users: Vec<String> = rows.iter().map(|r| r.get::<String,_>("username")).collect();
-            if let Some(exclude) = exclude_username {
-                users.retain(|u| u != exclude);
-            }
+            let users: Vec<String> = rows.iter()
+                .filter(|r| {
+                    let username = r.get::<String,_>("username");
+                    exclude_username != Some(username.as_str()) && !blocked.contains(&username)
+                })
+                .map(|r| {
+                    let entry = with_archived_flag(r.get("username"), &archived);
+                    with_presence_flag(entry, r.get::<Option<i64>,_>("is_online").unwrap_or(0) != 0, r.get("last_seen"))
+                })
+                .collect();
             format!("OK: All users: {}", users.join(", "))
         }
         Err(e) => {
@@ -247,3 +790,31 @@ pub async fn list_all(db: Arc<Database>, exclude_username: Option<&str>) -> Stri
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::server::test_support::{group_id, register, server};
+
+    #[tokio::test]
+    async fn deleted_user_leaves_group_history_readable() {
+        let server = server().await;
+        let alice = register(&server, "alice").await;
+        register(&server, "bobby").await;
+        register(&server, "carol").await;
+        server.handle_command("/create_group", &[&alice, "friends", "bobby,carol"]).await;
+        let group = group_id(&server, "friends").await;
+        server.handle_command("/send_group_message", &[&alice, &group, "hello before delete"]).await;
+
+        let resp = delete_user(server.db.clone(), "carol").await;
+        assert!(resp.starts_with("OK:"), "{}", resp);
+
+        let history = server.handle_command("/get_group_messages", &[&alice, &group]).await;
+        assert!(history.contains("alice: hello before delete"), "{}", history);
+        let left: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM group_events WHERE event_type = 'left'")
+            .fetch_one(&server.db.pool)
+            .await
+            .unwrap();
+        assert_eq!(left, 1);
+    }
+}
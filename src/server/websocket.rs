@@ -1,13 +1,16 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncWrite};
 use tokio::sync::{Mutex, broadcast};
 use tokio_tungstenite::{WebSocketStream, tungstenite::Message};
+use tokio_tungstenite::tungstenite::handshake::server::{Callback, ErrorResponse, Request, Response};
 use futures_util::{SinkExt, StreamExt};
 use serde::{Serialize, Deserialize};
 use uuid::Uuid;
 use redis::aio::ConnectionManager;
 use crate::server::database::Database;
-use crate::server::messages;
+use crate::server::{file_transfer, messages};
 use sqlx::Row;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,6 +21,18 @@ pub struct OutgoingChatMessage {
     pub group_id: Option<String>, // per messaggi di gruppo
     pub content: String,
     pub session_token: String,
+    /// Only meaningful for `message_type: "typing"`; `false` when the user stopped typing
+    #[serde(default)]
+    pub is_typing: bool,
+    /// Message this one answers, for replies
+    #[serde(default)]
+    pub reply_to_id: Option<i64>,
+    /// Only for `message_type: "file_transfer"`: UUID of the file whose chunks follow as
+    /// binary frames (`content` holds the file name)
+    #[serde(default)]
+    pub file_id: Option<String>,
+    #[serde(default)]
+    pub mime_type: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +43,328 @@ pub struct WebSocketMessage {
     pub target: String, // user_id or group_id
     pub content: String,
     pub timestamp: i64,
+    /// Only this user receives the message; `None` means broadcast (used by notifications)
+    #[serde(default)]
+    pub recipient_user_id: Option<String>,
+}
+
+impl WebSocketMessage {
+    /// Redis channel the message is published on
+    pub fn redis_channel(&self) -> String {
+        match (&self.message_type, &self.recipient_user_id) {
+            (MessageType::PrivateMessage, _) => format!("private:{}", self.target),
+            (MessageType::GroupMessage, _) => format!("group:{}", self.target),
+            (_, Some(user_id)) => format!("notifications:{}", user_id),
+            (MessageType::Notification, None) => "notifications".to_string(),
+            _ => "system".to_string(),
+        }
+    }
+}
+
+impl WebSocketMessage {
+    /// JSON delivered to GUI clients for this message
+    pub fn client_payload(&self) -> String {
+        match &self.message_type {
+            MessageType::ReadReceipt { message_id, user_id } => serde_json::json!({
+                "message_type": "read_receipt",
+                "group_id": self.target,
+                "message_id": message_id,
+                "user_id": user_id,
+            }).to_string(),
+            MessageType::TypingIndicator { sender, target, is_typing } => {
+                let (chat_type, chat) = target.split_once(':').unwrap_or(("private", target));
+                serde_json::json!({
+                    "message_type": "typing",
+                    "chat_type": chat_type,
+                    "from_user": sender,
+                    "group_id": (chat_type == "group").then_some(chat),
+                    "is_typing": is_typing,
+                }).to_string()
+            }
+            MessageType::ChatRead { chat_id, last_read_at, .. } => {
+                // Private chats are known to the recipient by the reader's username
+                let (chat_type, chat) = match chat_id.strip_prefix("group:") {
+                    Some(group_id) => ("group", group_id),
+                    None => ("private", self.sender.as_str()),
+                };
+                serde_json::json!({
+                    "message_type": "chat_read",
+                    "chat_type": chat_type,
+                    "chat_id": chat,
+                    "user": self.sender,
+                    "last_read_at": last_read_at,
+                }).to_string()
+            }
+            MessageType::FileChunk { file_id, chunk_index, total_chunks } => serde_json::json!({
+                "message_type": "file_chunk",
+                "file_id": file_id,
+                "chunk_index": chunk_index,
+                "total_chunks": total_chunks,
+            }).to_string(),
+            MessageType::FileTransferComplete { file_id, file_name, mime_type, total_size } => {
+                let (chat_type, chat) = self.target.split_once(':').unwrap_or(("private", &self.target));
+                serde_json::json!({
+                    "message_type": "file_transfer_complete",
+                    "file_id": file_id,
+                    "file_name": file_name,
+                    "mime_type": mime_type,
+                    "total_size": total_size,
+                    "from_user": self.sender,
+                    "chat_type": chat_type,
+                    "group_id": (chat_type == "group").then_some(chat),
+                }).to_string()
+            }
+            // System events carry a JSON object ({"type": ..., ...}) in `content`
+            MessageType::System => {
+                let mut event = serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(&self.content).unwrap_or_default();
+                event.insert("message_type".to_string(), "system".into());
+                serde_json::Value::Object(event).to_string()
+            }
+            // Structured notifications (e.g. status changes) are flattened the same way;
+            // plain-text ones such as mentions keep the full message
+            MessageType::Notification if self.content.starts_with('{') => {
+                let mut event = serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(&self.content).unwrap_or_default();
+                event.insert("message_type".to_string(), "notification".into());
+                serde_json::Value::Object(event).to_string()
+            }
+            _ => serde_json::to_string(self).unwrap_or_default(),
+        }
+    }
+}
+
+/// Publish `message` on its Redis channel so every server instance can deliver it
+pub async fn publish_to_redis(redis_manager: &Arc<Mutex<ConnectionManager>>, message: &WebSocketMessage) {
+    let serialized = serde_json::to_string(message).unwrap_or_default();
+    let mut redis_conn = redis_manager.lock().await;
+    let res: Result<(), _> = redis::cmd("PUBLISH")
+        .arg(message.redis_channel())
+        .arg(&serialized)
+        .query_async(&mut *redis_conn)
+        .await;
+    if let Err(e) = res {
+        println!("[WS:REDIS] Failed to publish on {}: {}", message.redis_channel(), e);
+    }
+}
+
+/// Publish `message` through Redis when available; otherwise deliver it to the
+/// connections of this instance only
+async fn publish(redis_manager: Option<&Arc<Mutex<ConnectionManager>>>, connections: &Connections, user_connections: &UserConnections, message: &WebSocketMessage) {
+    match redis_manager {
+        Some(redis_manager) => publish_to_redis(redis_manager, message).await,
+        None => deliver_local(connections, user_connections, message).await,
+    }
+}
+
+/// Store one binary frame of an upload announced on this connection and acknowledge it
+/// to the uploader; after the last chunk the file is assembled and its recipients notified
+#[allow(clippy::too_many_arguments)]
+async fn receive_file_chunk(
+    frame: &[u8],
+    uploads: &mut HashMap<String, file_transfer::Upload>,
+    storage_dir: &std::path::Path,
+    user_id: &str,
+    sender: &tokio::sync::mpsc::UnboundedSender<Message>,
+    redis_manager: Option<&Arc<Mutex<ConnectionManager>>>,
+    connections: &Connections,
+    user_connections: &UserConnections,
+) {
+    let (header, payload) = match file_transfer::ChunkHeader::parse(frame) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            println!("[WS:FILE] Rejected chunk from user {}: {}", user_id, e);
+            return;
+        }
+    };
+    let Some(upload) = uploads.get_mut(&header.file_id) else {
+        println!("[WS:FILE] Chunk for unknown upload {} from user {}", header.file_id, user_id);
+        return;
+    };
+    if !header.verify(payload) {
+        println!("[WS:FILE] Checksum mismatch on chunk {} of {}", header.chunk_index, header.file_id);
+        return;
+    }
+    if let Err(e) = upload.admit(&header, payload.len()) {
+        println!("[WS:FILE] Upload {} from user {} abandoned: {}", header.file_id, user_id, e);
+        uploads.remove(&header.file_id);
+        file_transfer::discard(storage_dir, &header.file_id).await;
+        return;
+    }
+    if let Err(e) = file_transfer::store_chunk(storage_dir, &header, payload).await {
+        println!("[WS:FILE] Failed to store chunk {} of {}: {}", header.chunk_index, header.file_id, e);
+        return;
+    }
+    let complete = upload.record(&header, payload.len());
+    let ack = WebSocketMessage {
+        id: Uuid::new_v4().to_string(),
+        message_type: MessageType::FileChunk {
+            file_id: header.file_id.clone(),
+            chunk_index: header.chunk_index,
+            total_chunks: header.total_chunks,
+        },
+        sender: user_id.to_string(),
+        target: header.file_id.clone(),
+        content: String::new(),
+        timestamp: chrono::Utc::now().timestamp(),
+        recipient_user_id: Some(user_id.to_string()),
+    };
+    let _ = sender.send(Message::Text(ack.client_payload()));
+    if !complete {
+        return;
+    }
+
+    let Some(upload) = uploads.remove(&header.file_id) else { return };
+    match file_transfer::assemble(storage_dir, &header.file_id, header.total_chunks).await {
+        Ok(total_size) => {
+            println!("[WS:FILE] Upload {} complete ({} bytes)", header.file_id, total_size);
+            for recipient in &upload.recipients {
+                let event = file_transfer::complete_event(&header.file_id, &upload, total_size, recipient);
+                publish(redis_manager, connections, user_connections, &event).await;
+            }
+            let event = file_transfer::complete_event(&header.file_id, &upload, total_size, user_id);
+            let _ = sender.send(Message::Text(event.client_payload()));
+        }
+        Err(e) => println!("[WS:FILE] Failed to assemble {}: {}", header.file_id, e),
+    }
+}
+
+/// Chat message already sent to a reconnecting client, as (timestamp, content);
+/// `deliver_missed` skips it so the client does not get it twice
+type DeliveredMessage = (i64, String);
+
+/// Send the queued messages of `user_id` in arrival order through `sender` and remove them.
+/// Returns the chat messages among them (see `DeliveredMessage`).
+async fn deliver_pending(db: &Database, user_id: &str, sender: &tokio::sync::mpsc::UnboundedSender<Message>) -> Result<HashSet<DeliveredMessage>, sqlx::Error> {
+    let mut tx = db.pool.begin().await?;
+    let rows = sqlx::query("SELECT id, payload FROM ws_pending_messages WHERE user_id = ? ORDER BY created_at, id")
+        .bind(user_id)
+        .fetch_all(&mut *tx)
+        .await?;
+    let Some(last_id) = rows.last().map(|row| row.get::<i64, _>("id")) else {
+        return Ok(HashSet::new());
+    };
+    sqlx::query("DELETE FROM ws_pending_messages WHERE user_id = ? AND id <= ?")
+        .bind(user_id)
+        .bind(last_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    let mut delivered = HashSet::new();
+    for row in &rows {
+        let payload: String = row.get("payload");
+        if let Ok(message) = serde_json::from_str::<WebSocketMessage>(&payload) {
+            if matches!(message.message_type, MessageType::PrivateMessage | MessageType::GroupMessage) {
+                delivered.insert((message.timestamp, message.content));
+            }
+        }
+        let _ = sender.send(Message::Text(payload));
+    }
+    println!("[WS:QUEUE] Delivered {} queued messages to user {}", rows.len(), user_id);
+    Ok(delivered)
+}
+
+/// `since` parameter of the upgrade URL query, sent by clients that reconnect
+///
+/// ```
+/// use ruggine_modulare::server::websocket::since_from_query;
+///
+/// assert_eq!(since_from_query(Some("since=1700000000")), Some(1700000000));
+/// assert_eq!(since_from_query(Some("v=2&since=42")), Some(42));
+/// assert_eq!(since_from_query(Some("since=yesterday")), None);
+/// assert_eq!(since_from_query(None), None);
+/// ```
+pub fn since_from_query(query: Option<&str>) -> Option<i64> {
+    query?
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("since="))
+        .and_then(|value| value.parse().ok())
+}
+
+/// Complete the WebSocket handshake on `stream`, also returning the `since` timestamp of
+/// the upgrade URL (see `since_from_query`)
+pub async fn accept_with_since<S>(stream: S) -> Result<(WebSocketStream<S>, Option<i64>), tokio_tungstenite::tungstenite::Error>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut since = None;
+    let ws_stream = tokio_tungstenite::accept_hdr_async(stream, SinceCallback(&mut since)).await?;
+    Ok((ws_stream, since))
+}
+
+/// Handshake callback of `accept_with_since`: records `since` and accepts every upgrade
+struct SinceCallback<'a>(&'a mut Option<i64>);
+
+impl Callback for SinceCallback<'_> {
+    fn on_request(self, request: &Request, response: Response) -> Result<Response, ErrorResponse> {
+        *self.0 = since_from_query(request.uri().query());
+        Ok(response)
+    }
+}
+
+/// Send the messages `user_id` missed after `since` through `sender`, before live delivery starts,
+/// except those `deliver_pending` already sent from the offline queue
+async fn deliver_missed(db: &Arc<Database>, user_id: &str, since: i64, already_delivered: &HashSet<DeliveredMessage>, config: &crate::server::config::ServerConfig, sender: &tokio::sync::mpsc::UnboundedSender<Message>) -> Result<(), sqlx::Error> {
+    let events = messages::missed_messages(db, user_id, since, config).await?;
+    let mut sent = 0;
+    for event in &events {
+        let key = (event["timestamp"].as_i64().unwrap_or_default(), event["content"].as_str().unwrap_or_default().to_string());
+        if already_delivered.contains(&key) {
+            continue;
+        }
+        let _ = sender.send(Message::Text(event.to_string()));
+        sent += 1;
+    }
+    log::info!("[WS:RECOVER] Sent {} messages missed since {} to user {}", sent, since, user_id);
+    Ok(())
+}
+
+/// Send `message` to the matching WebSocket connections of this instance
+async fn deliver_local(connections: &Connections, user_connections: &UserConnections, ws_message: &WebSocketMessage) {
+    match ws_message.message_type {
+        MessageType::PrivateMessage => {
+            // Send to specific user
+            let user_connections_guard = user_connections.lock().await;
+            let connections_guard = connections.lock().await;
+
+            let json_msg = serde_json::to_string(ws_message).unwrap_or_default();
+            for connection in connections_of(&connections_guard, &user_connections_guard, &ws_message.target) {
+                let _ = connection.sender.send(tokio_tungstenite::tungstenite::Message::Text(json_msg.clone()));
+                println!("[WS:DELIVER] Delivered private message to user {}", ws_message.target);
+            }
+        }
+        MessageType::GroupMessage => {
+            // Broadcast to all connected users (would filter by group in production)
+            let connections_guard = connections.lock().await;
+            let json_msg = serde_json::to_string(ws_message).unwrap_or_default();
+
+            for connection in connections_guard.values() {
+                if connection.user_id != ws_message.sender {
+                    let _ = connection.sender.send(tokio_tungstenite::tungstenite::Message::Text(json_msg.clone()));
+                }
+            }
+            println!("[WS:DELIVER] Broadcasted group message from {}", ws_message.sender);
+        }
+        MessageType::Notification | MessageType::System | MessageType::ReadReceipt { .. } | MessageType::TypingIndicator { .. } | MessageType::ChatRead { .. } | MessageType::FileTransferComplete { .. } if ws_message.recipient_user_id.is_some() => {
+            // Notifica personale: solo al destinatario
+            let recipient = ws_message.recipient_user_id.as_deref().unwrap_or_default();
+            let user_connections_guard = user_connections.lock().await;
+            let connections_guard = connections.lock().await;
+            for connection in connections_of(&connections_guard, &user_connections_guard, recipient) {
+                let _ = connection.sender.send(tokio_tungstenite::tungstenite::Message::Text(ws_message.client_payload()));
+                println!("[WS:DELIVER] Delivered notification to user {}", recipient);
+            }
+        }
+        MessageType::Notification | MessageType::System => {
+            // Broadcast to all connected users
+            let connections_guard = connections.lock().await;
+            let json_msg = ws_message.client_payload();
+
+            for connection in connections_guard.values() {
+                let _ = connection.sender.send(tokio_tungstenite::tungstenite::Message::Text(json_msg.clone()));
+            }
+            println!("[WS:DELIVER] Broadcasted {} message", if matches!(ws_message.message_type, MessageType::Notification) { "notification" } else { "system" });
+        }
+        _ => {}
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -52,6 +389,30 @@ pub enum MessageType {
     UserLeft,
     Notification,
     System,
+    /// `user_id` has seen group message `message_id`; `target` is the group
+    ReadReceipt { message_id: String, user_id: String },
+    /// The user named `sender` is typing in `target`: "group:<group_id>", or "private:<username>"
+    /// with the recipient's username
+    TypingIndicator { sender: String, target: String, is_typing: bool },
+    /// `user_id` has read `chat_id` (as stored in `encrypted_messages`) up to `last_read_at`;
+    /// `sender` is their username
+    ChatRead { chat_id: String, user_id: String, last_read_at: i64 },
+    /// Chunk `chunk_index` of `file_id` was stored; sent back to the uploader
+    FileChunk { file_id: String, chunk_index: u32, total_chunks: u32 },
+    /// Every chunk of `file_id` arrived; `sender` is the uploader's username and `target`
+    /// the chat, as for `TypingIndicator`
+    FileTransferComplete { file_id: String, file_name: String, mime_type: String, total_size: u64 },
+}
+
+impl MessageType {
+    /// Whether an offline user should still get this message when they reconnect.
+    /// Typing indicators, presence, receipts and chunk acks are stale by then.
+    pub fn is_durable(&self) -> bool {
+        matches!(
+            self,
+            MessageType::PrivateMessage | MessageType::GroupMessage | MessageType::System | MessageType::FileTransferComplete { .. }
+        )
+    }
 }
 
 pub type ClientId = String;
@@ -63,30 +424,100 @@ pub struct WebSocketConnection {
     pub sender: tokio::sync::mpsc::UnboundedSender<Message>,
 }
 
+type Connections = Arc<Mutex<HashMap<ClientId, WebSocketConnection>>>;
+type UserConnections = Arc<Mutex<HashMap<UserId, Vec<ClientId>>>>;
+type PresenceSubscriptions = Arc<Mutex<HashMap<UserId, HashSet<UserId>>>>;
+
+/// Live connections of `user_id`, one per device
+fn connections_of<'a>(
+    connections: &'a HashMap<ClientId, WebSocketConnection>,
+    user_connections: &'a HashMap<UserId, Vec<ClientId>>,
+    user_id: &str,
+) -> impl Iterator<Item = &'a WebSocketConnection> + 'a {
+    user_connections.get(user_id).into_iter().flatten().filter_map(|cid| connections.get(cid))
+}
+
+/// Longest `redis_health` waits for Redis to answer PING
+const REDIS_PING_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
 pub struct ChatWebSocketManager {
     // Mappa client_id -> connection info
-    connections: Arc<Mutex<HashMap<ClientId, WebSocketConnection>>>,
-    // Mappa user_id -> client_id (per trovare rapidamente la connessione di un utente)
-    user_connections: Arc<Mutex<HashMap<UserId, ClientId>>>,
+    connections: Connections,
+    // Mappa user_id -> client_id delle sue connessioni (una per dispositivo)
+    user_connections: UserConnections,
+    // Mappa user_id -> utenti di cui riceve i cambi di stato online/offline (`/subscribe_presence`)
+    presence_subscriptions: PresenceSubscriptions,
     // Broadcaster per messaggi globali
     message_broadcaster: broadcast::Sender<WebSocketMessage>,
-    // Redis connection per pub/sub tra istanze server
-    redis_manager: Arc<Mutex<ConnectionManager>>,
+    // Redis connection per pub/sub tra istanze server; `None` = solo consegna locale
+    redis_manager: Option<Arc<Mutex<ConnectionManager>>>,
+    // Coda su database per gli utenti offline; `None` = i messaggi per loro vanno persi
+    pending_queue: Option<PendingQueue>,
+}
+
+/// Where `send_to_user` keeps messages for users without a connection
+struct PendingQueue {
+    db: Arc<Database>,
+    max_per_user: usize,
 }
 
 impl ChatWebSocketManager {
-    pub async fn new(redis_url: &str) -> anyhow::Result<Self> {
-        let client = redis::Client::open(redis_url)?;
-        let redis_manager = ConnectionManager::new(client).await?;
-        
+    /// Connect to Redis for cross-instance delivery. `None` when Redis is unavailable;
+    /// use `local_only` to keep serving WebSockets on this instance.
+    pub async fn new(redis_url: &str) -> Option<Self> {
+        let client = match redis::Client::open(redis_url) {
+            Ok(client) => client,
+            Err(e) => {
+                println!("[WS:REDIS] Invalid Redis URL {}: {}", redis_url, e);
+                return None;
+            }
+        };
+        match ConnectionManager::new(client).await {
+            Ok(redis_manager) => Some(Self::with_redis(Some(Arc::new(Mutex::new(redis_manager))))),
+            Err(e) => {
+                println!("[WS:REDIS] Failed to connect to Redis at {}: {}", redis_url, e);
+                None
+            }
+        }
+    }
+
+    /// Manager without Redis: messages only reach clients connected to this instance
+    pub fn local_only() -> Self {
+        Self::with_redis(None)
+    }
+
+    fn with_redis(redis_manager: Option<Arc<Mutex<ConnectionManager>>>) -> Self {
         let (message_broadcaster, _) = broadcast::channel(1000);
-        
-        Ok(Self {
+
+        Self {
             connections: Arc::new(Mutex::new(HashMap::new())),
             user_connections: Arc::new(Mutex::new(HashMap::new())),
+            presence_subscriptions: Arc::new(Mutex::new(HashMap::new())),
             message_broadcaster,
-            redis_manager: Arc::new(Mutex::new(redis_manager)),
-        })
+            redis_manager,
+            pending_queue: None,
+        }
+    }
+
+    /// Keep up to `max_per_user` messages in `ws_pending_messages` for users who are offline
+    /// when `send_to_user` is called; they are delivered on their next connection
+    pub fn with_pending_queue(mut self, db: Arc<Database>, max_per_user: usize) -> Self {
+        self.pending_queue = Some(PendingQueue { db, max_per_user });
+        self
+    }
+
+    pub fn redis_enabled(&self) -> bool {
+        self.redis_manager.is_some()
+    }
+
+    /// Whether Redis answers PING within `REDIS_PING_TIMEOUT`; always `false` without Redis
+    pub async fn redis_health(&self) -> bool {
+        let Some(redis_manager) = &self.redis_manager else {
+            return false;
+        };
+        let mut redis_conn = redis_manager.lock().await;
+        let ping = redis::cmd("PING");
+        matches!(tokio::time::timeout(REDIS_PING_TIMEOUT, ping.query_async::<_, String>(&mut *redis_conn)).await, Ok(Ok(_)))
     }
 
     /// Validate session token and return user_id if valid
@@ -118,12 +549,30 @@ impl ChatWebSocketManager {
         }
     }
 
-    pub async fn handle_authenticated_connection(
+    /// Accept a WebSocket over an established TLS connection (wss://), then authenticate it
+    /// like `handle_authenticated_connection`
+    pub async fn handle_authenticated_connection_tls(
         &self,
-        ws_stream: WebSocketStream<tokio::net::TcpStream>,
+        tls_stream: tokio_rustls::server::TlsStream<tokio::net::TcpStream>,
         db: Arc<Database>,
         config: crate::server::config::ServerConfig,
     ) -> anyhow::Result<()> {
+        let (ws_stream, since) = accept_with_since(tls_stream).await?;
+        self.handle_authenticated_connection(ws_stream, since, db, config).await
+    }
+
+    /// Authenticate a WebSocket with its first message and serve it. `since` is the
+    /// timestamp of the last message a reconnecting client received, if any.
+    pub async fn handle_authenticated_connection<S>(
+        &self,
+        ws_stream: WebSocketStream<S>,
+        since: Option<i64>,
+        db: Arc<Database>,
+        config: crate::server::config::ServerConfig,
+    ) -> anyhow::Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
         let (mut ws_sender, mut ws_receiver) = ws_stream.split();
         
         // Wait for authentication message
@@ -213,7 +662,7 @@ impl ChatWebSocketManager {
             let rebuilt_stream = ws_sender.reunite(ws_receiver)
                 .map_err(|e| anyhow::anyhow!("Failed to reunite WebSocket stream: {}", e))?;
             
-            return self.add_connection(rebuilt_stream, user_id, auth_message.session_token, db, config).await;
+            return self.add_connection(rebuilt_stream, user_id, auth_message.session_token, since, db, config).await;
         } else {
             // Authentication failed
             let error_response = AuthResponse {
@@ -230,48 +679,87 @@ impl ChatWebSocketManager {
         }
     }
 
-    pub async fn add_connection(
+    pub async fn add_connection<S>(
         &self,
-        ws_stream: WebSocketStream<tokio::net::TcpStream>,
+        ws_stream: WebSocketStream<S>,
         user_id: UserId,
         session_token: String,
+        since: Option<i64>,
         db: Arc<Database>,
         config: crate::server::config::ServerConfig,
-    ) -> anyhow::Result<()> {
+    ) -> anyhow::Result<()>
+    where
+        S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+    {
         let client_id = Uuid::new_v4().to_string();
         let (mut ws_sender, mut ws_receiver) = ws_stream.split();
         let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let heartbeat_tx = tx.clone();
+        let receive_tx = tx.clone();
 
-        // Aggiungi connessione alle mappe
-        {
+        // Aggiungi connessione alle mappe, se l'utente non ha già troppe connessioni aperte
+        let open_connections = {
             let mut connections = self.connections.lock().await;
             let mut user_connections = self.user_connections.lock().await;
-            
-            connections.insert(client_id.clone(), WebSocketConnection {
-                client_id: client_id.clone(),
-                user_id: user_id.clone(),
-                sender: tx,
-            });
-            
-            user_connections.insert(user_id.clone(), client_id.clone());
+            let user_client_ids = user_connections.entry(user_id.clone()).or_default();
+            let open_connections = user_client_ids.len();
+            if open_connections < config.max_ws_connections_per_user {
+                user_client_ids.push(client_id.clone());
+                connections.insert(client_id.clone(), WebSocketConnection {
+                    client_id: client_id.clone(),
+                    user_id: user_id.clone(),
+                    sender: tx,
+                });
+            } else if user_client_ids.is_empty() {
+                user_connections.remove(&user_id);
+            }
+            open_connections
+        };
+        if open_connections >= config.max_ws_connections_per_user {
+            println!("[WS:LIMIT] User {} already has {} WebSocket connections, rejecting a new one", user_id, open_connections);
+            let rejection = WebSocketMessage {
+                id: Uuid::new_v4().to_string(),
+                message_type: MessageType::System,
+                sender: "system".to_string(),
+                target: user_id.clone(),
+                content: serde_json::json!({ "type": "error", "message": "Max connections reached" }).to_string(),
+                timestamp: chrono::Utc::now().timestamp(),
+                recipient_user_id: Some(user_id.clone()),
+            };
+            let _ = ws_sender.send(Message::Text(rejection.client_payload())).await;
+            let _ = ws_sender.close().await;
+            return Ok(());
+        }
+
+        // Consegna i messaggi arrivati mentre l'utente era offline
+        let delivered = match deliver_pending(&db, &user_id, &heartbeat_tx).await {
+            Ok(delivered) => delivered,
+            Err(e) => {
+                println!("[WS:ERROR] Error delivering queued messages to user {}: {}", user_id, e);
+                HashSet::new()
+            }
+        };
+
+        // Client in riconnessione: recupera i messaggi persi prima della consegna live
+        if let Some(since) = since {
+            if let Err(e) = deliver_missed(&db, &user_id, since, &delivered, &config, &heartbeat_tx).await {
+                log::error!("[WS:RECOVER] Error recovering missed messages for user {}: {}", user_id, e);
+            }
         }
 
         // Set user online when WebSocket connects
-        let _ = sqlx::query("UPDATE users SET is_online = 1 WHERE id = ?")
-            .bind(&user_id)
-            .execute(&db.pool)
-            .await;
-        println!("[WS:ONLINE] Set is_online=1 for user {} due to WebSocket connection", user_id);
+        println!("[WS:ONLINE] User {} connected via WebSocket", user_id);
+        crate::server::users::mark_online(&db, &user_id).await;
+        self.notify_presence(&db, &user_id, true).await;
 
         let connections_clone = self.connections.clone();
         let user_connections_clone = self.user_connections.clone();
-        let client_id_clone = client_id.clone();
         let user_id_clone = user_id.clone();
         let message_broadcaster = self.message_broadcaster.clone();
         let redis_manager = self.redis_manager.clone();
 
         // Task per inviare messaggi al client
-        let send_task = tokio::spawn(async move {
+        let mut send_task = tokio::spawn(async move {
             while let Some(message) = rx.recv().await {
                 if ws_sender.send(message).await.is_err() {
                     break;
@@ -279,11 +767,39 @@ impl ChatWebSocketManager {
             }
         });
 
+        // Task di heartbeat: ping periodico, la connessione cade se il pong non arriva in tempo
+        let last_pong = Arc::new(Mutex::new(Instant::now()));
+        let heartbeat_last_pong = last_pong.clone();
+        let heartbeat_user_id = user_id.clone();
+        let heartbeat_interval = Duration::from_secs(config.heartbeat_interval_secs.max(1));
+        let heartbeat_timeout = Duration::from_secs(config.heartbeat_timeout_secs);
+        let mut heartbeat_task = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(heartbeat_interval);
+            // The first tick completes immediately
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let ping_sent_at = Instant::now();
+                if heartbeat_tx.send(Message::Ping(Vec::new())).is_err() {
+                    break;
+                }
+                tokio::time::sleep(heartbeat_timeout).await;
+                if *heartbeat_last_pong.lock().await < ping_sent_at {
+                    println!("[WS:HEARTBEAT] No pong from user {} within {:?}, dropping connection", heartbeat_user_id, heartbeat_timeout);
+                    break;
+                }
+            }
+            // Returning drops `heartbeat_tx`; add_connection then removes the connection
+        });
+
         // Task per ricevere messaggi dal client
         let db_clone = db.clone();
         let config_clone = config.clone();
         let session_token_clone = session_token.clone();
-        let receive_task = tokio::spawn(async move {
+        let storage_dir = std::path::PathBuf::from(&config.file_storage_dir);
+        let mut receive_task = tokio::spawn(async move {
+            // Upload annunciati su questa connessione, per file_id
+            let mut uploads: HashMap<String, file_transfer::Upload> = HashMap::new();
             while let Some(message) = ws_receiver.next().await {
                 match message {
                     Ok(Message::Text(text)) => {
@@ -303,6 +819,7 @@ impl ChatWebSocketManager {
                                                 &session_token_clone,
                                                 to_user,
                                                 &outgoing_msg.content,
+                                                messages::MessageLinks::reply_to(outgoing_msg.reply_to_id),
                                                 &config_clone
                                             ).await;
                                             println!("[WS:DB] Private message save result: {}", result);
@@ -341,6 +858,7 @@ impl ChatWebSocketManager {
                                                     "from_user": username,
                                                     "to_user": to_user,
                                                     "content": outgoing_msg.content,
+                                                    "reply_to_id": outgoing_msg.reply_to_id,
                                                     "timestamp": chrono::Utc::now().timestamp()
                                                 });
                                                 
@@ -371,25 +889,22 @@ impl ChatWebSocketManager {
                                                 let user_connections_guard = user_connections_clone.lock().await;
                                                 let connections_guard = connections_clone.lock().await;
                                                 
-                                                if let Some(client_id) = user_connections_guard.get(&target_user_id) {
-                                                    if let Some(connection) = connections_guard.get(client_id) {
-                                                        let json_msg = serde_json::to_string(&incoming_msg).unwrap_or_default();
-                                                        let _ = connection.sender.send(tokio_tungstenite::tungstenite::Message::Text(json_msg));
-                                                        println!("[WS:BROADCAST] ✅ Delivered message to user {} (user_id: {})", to_user, target_user_id);
-                                                    } else {
-                                                        println!("[WS:BROADCAST] ❌ Client ID found but connection not found for user {}", to_user);
-                                                    }
+                                                let json_msg = serde_json::to_string(&incoming_msg).unwrap_or_default();
+                                                let mut delivered = false;
+                                                for connection in connections_of(&connections_guard, &user_connections_guard, &target_user_id) {
+                                                    let _ = connection.sender.send(tokio_tungstenite::tungstenite::Message::Text(json_msg.clone()));
+                                                    delivered = true;
+                                                }
+                                                if delivered {
+                                                    println!("[WS:BROADCAST] ✅ Delivered message to user {} (user_id: {})", to_user, target_user_id);
                                                 } else {
                                                     println!("[WS:BROADCAST] ❌ User {} (user_id: {}) not connected via WebSocket", to_user, target_user_id);
                                                 }
                                                 
-                                                // Also send to sender (echo back for confirmation)
-                                                if let Some(sender_client_id) = user_connections_guard.get(&user_id_clone) {
-                                                    if let Some(sender_connection) = connections_guard.get(sender_client_id) {
-                                                        let json_msg = serde_json::to_string(&incoming_msg).unwrap_or_default();
-                                                        let _ = sender_connection.sender.send(tokio_tungstenite::tungstenite::Message::Text(json_msg));
-                                                        println!("[WS:BROADCAST] Echoed message back to sender");
-                                                    }
+                                                // Also send to sender (echo back for confirmation), on every device
+                                                for sender_connection in connections_of(&connections_guard, &user_connections_guard, &user_id_clone) {
+                                                    let _ = sender_connection.sender.send(tokio_tungstenite::tungstenite::Message::Text(json_msg.clone()));
+                                                    println!("[WS:BROADCAST] Echoed message back to sender");
                                                 }
                                             }
                                         }
@@ -402,6 +917,7 @@ impl ChatWebSocketManager {
                                                 &session_token_clone,
                                                 group_id,
                                                 &outgoing_msg.content,
+                                                messages::MessageLinks::reply_to(outgoing_msg.reply_to_id),
                                                 &config_clone
                                             ).await;
                                             println!("[WS:DB] Group message save result: {}", result);
@@ -432,6 +948,7 @@ impl ChatWebSocketManager {
                                                     "from_user": username,
                                                     "group_id": group_id,
                                                     "content": outgoing_msg.content,
+                                                    "reply_to_id": outgoing_msg.reply_to_id,
                                                     "timestamp": chrono::Utc::now().timestamp()
                                                 });
                                                 
@@ -461,21 +978,27 @@ impl ChatWebSocketManager {
                                                 
                                                 let mut delivered_count = 0;
                                                 for member_user_id in &group_members {
-                                                    if let Some(client_id) = user_connections_guard.get(member_user_id) {
-                                                        if let Some(connection) = connections_guard.get(client_id) {
-                                                            let _ = connection.sender.send(tokio_tungstenite::tungstenite::Message::Text(json_msg.clone()));
-                                                            delivered_count += 1;
-                                                            println!("[WS:BROADCAST] ✅ Delivered group message to user_id: {}", member_user_id);
-                                                        } else {
-                                                            println!("[WS:BROADCAST] ❌ Client ID found but connection not found for user_id {}", member_user_id);
-                                                        }
-                                                    } else {
+                                                    let mut member_connections = connections_of(&connections_guard, &user_connections_guard, member_user_id).peekable();
+                                                    if member_connections.peek().is_none() {
                                                         println!("[WS:BROADCAST] ⚠️ Group member {} not connected via WebSocket", member_user_id);
+                                                        continue;
+                                                    }
+                                                    for connection in member_connections {
+                                                        let _ = connection.sender.send(tokio_tungstenite::tungstenite::Message::Text(json_msg.clone()));
                                                     }
+                                                    delivered_count += 1;
+                                                    println!("[WS:BROADCAST] ✅ Delivered group message to user_id: {}", member_user_id);
                                                 }
                                                 
                                                 println!("[WS:BROADCAST] ✅ Delivered group message to {}/{} members in group {}", 
                                                     delivered_count, group_members.len(), group_id);
+                                                drop(connections_guard);
+                                                drop(user_connections_guard);
+
+                                                // Notifiche @menzione solo ai diretti interessati
+                                                for notification in messages::mention_notifications(&db_clone, group_id, &user_id_clone, &outgoing_msg.content).await {
+                                                    publish(redis_manager.as_ref(), &connections_clone, &user_connections_clone, &notification).await;
+                                                }
                                             }
                                         }
                                     }
@@ -483,6 +1006,20 @@ impl ChatWebSocketManager {
                                         println!("[WS:DB] Unknown chat_type: {}", outgoing_msg.chat_type);
                                     }
                                 }
+                            } else if outgoing_msg.message_type == "typing" {
+                                // Nothing is stored: just tell the other participants
+                                for indicator in messages::typing_notifications(&db_clone, &user_id_clone, &outgoing_msg).await {
+                                    publish(redis_manager.as_ref(), &connections_clone, &user_connections_clone, &indicator).await;
+                                }
+                            } else if outgoing_msg.message_type == "file_transfer" {
+                                // I blocchi del file seguono come frame binari
+                                match file_transfer::announce(&db_clone, &user_id_clone, &outgoing_msg, &storage_dir, &config_clone).await {
+                                    Ok((file_id, upload)) => {
+                                        println!("[WS:FILE] User {} started upload {} ({})", user_id_clone, file_id, upload.file_name);
+                                        uploads.insert(file_id, upload);
+                                    }
+                                    Err(e) => println!("[WS:FILE] Upload from user {} rejected: {}", user_id_clone, e),
+                                }
                             }
                         }
                         // Fallback: try to parse as WebSocketMessage (old format)
@@ -498,6 +1035,7 @@ impl ChatWebSocketManager {
                                         &session_token_clone,
                                         &ws_message.target,
                                         &ws_message.content,
+                                        messages::MessageLinks::default(),
                                         &config_clone
                                     ).await;
                                     println!("[WS:DB] Private message save result: {}", result);
@@ -515,94 +1053,139 @@ impl ChatWebSocketManager {
                             let _ = message_broadcaster.send(ws_message.clone());
                             
                             // Pubblica su Redis per altre istanze server
-                            let mut redis_conn = redis_manager.lock().await;
-                            let channel = match ws_message.message_type {
-                                MessageType::PrivateMessage => format!("private:{}", ws_message.target),
-                                MessageType::GroupMessage => format!("group:{}", ws_message.target),
-                                _ => "system".to_string(),
-                            };
-                                
-                            let serialized = serde_json::to_string(&ws_message).unwrap_or_default();
-                            let _: Result<(), _> = redis::cmd("PUBLISH")
-                                .arg(&channel)
-                                .arg(&serialized)
-                                .query_async(&mut *redis_conn)
-                                .await;
+                            publish(redis_manager.as_ref(), &connections_clone, &user_connections_clone, &ws_message).await;
                         } else {
                             println!("[WS:RECV] Failed to parse JSON message: {}", text);
                         }
                     }
+                    Ok(Message::Binary(frame)) => {
+                        receive_file_chunk(
+                            &frame,
+                            &mut uploads,
+                            &storage_dir,
+                            &user_id_clone,
+                            &receive_tx,
+                            redis_manager.as_ref(),
+                            &connections_clone,
+                            &user_connections_clone,
+                        ).await;
+                    }
+                    Ok(Message::Pong(_)) => {
+                        *last_pong.lock().await = Instant::now();
+                    }
                     Ok(Message::Close(_)) => break,
                     Err(_) => break,
                     _ => {}
                 }
             }
-
-            // Cleanup quando la connessione si chiude
-            {
-                let mut connections = connections_clone.lock().await;
-                let mut user_connections = user_connections_clone.lock().await;
-                
-                connections.remove(&client_id_clone);
-                user_connections.remove(&user_id_clone);
-                
-                // Set user offline when WebSocket disconnects (only if no other WebSocket connections)
-                if !user_connections.values().any(|cid| {
-                    connections.get(cid).map_or(false, |conn| conn.user_id == user_id_clone)
-                }) {
-                    let _ = sqlx::query("UPDATE users SET is_online = 0 WHERE id = ?")
-                        .bind(&user_id_clone)
-                        .execute(&db_clone.pool)
-                        .await;
-                    println!("[WS:OFFLINE] Set is_online=0 for user {} due to WebSocket disconnection", user_id_clone);
-                } else {
-                    println!("[WS:ONLINE] User {} still has other WebSocket connections, keeping online", user_id_clone);
-                }
-            }
         });
 
-        // Aspetta che uno dei task finisca (disconnessione)
+        // Aspetta che uno dei task finisca (disconnessione o heartbeat scaduto) e ferma gli altri
         tokio::select! {
-            _ = send_task => {},
-            _ = receive_task => {},
+            _ = &mut send_task => {},
+            _ = &mut receive_task => {},
+            _ = &mut heartbeat_task => {},
+        }
+        send_task.abort();
+        receive_task.abort();
+        heartbeat_task.abort();
+
+        // Cleanup quando la connessione si chiude
+        let went_offline = {
+            let mut connections = self.connections.lock().await;
+            let mut user_connections = self.user_connections.lock().await;
+            
+            connections.remove(&client_id);
+            let last_connection = match user_connections.get_mut(&user_id) {
+                Some(client_ids) => {
+                    client_ids.retain(|cid| cid != &client_id);
+                    client_ids.is_empty()
+                }
+                None => true,
+            };
+            
+            // Set user offline when WebSocket disconnects (only if no other WebSocket connections)
+            if last_connection {
+                user_connections.remove(&user_id);
+                println!("[WS:OFFLINE] User {} disconnected from WebSocket", user_id);
+                crate::server::users::mark_offline(&db, &user_id).await;
+            } else {
+                println!("[WS:ONLINE] User {} still has other WebSocket connections, keeping online", user_id);
+            }
+            last_connection
+        };
+        // Outside the block: notifying takes the connection locks again
+        if went_offline {
+            self.notify_presence(&db, &user_id, false).await;
         }
 
         Ok(())
     }
 
+    /// Send `message` to `user_id`, or queue it for their next connection when they are offline
+    /// and the message is durable (see `MessageType::is_durable`); transient ones are dropped
     pub async fn send_to_user(&self, user_id: &str, message: WebSocketMessage) -> anyhow::Result<()> {
-        let connections = self.connections.lock().await;
-        let user_connections = self.user_connections.lock().await;
-        
-        if let Some(client_id) = user_connections.get(user_id) {
-            if let Some(connection) = connections.get(client_id) {
-                let json_message = serde_json::to_string(&message)?;
-                let _ = connection.sender.send(Message::Text(json_message));
+        let json_message = serde_json::to_string(&message)?;
+        {
+            let connections = self.connections.lock().await;
+            let user_connections = self.user_connections.lock().await;
+
+            let mut delivered = false;
+            for connection in connections_of(&connections, &user_connections, user_id) {
+                let _ = connection.sender.send(Message::Text(json_message.clone()));
+                delivered = true;
+            }
+            if delivered {
+                return Ok(());
             }
         }
+
+        if let Some(queue) = self.pending_queue.as_ref().filter(|_| message.message_type.is_durable()) {
+            sqlx::query("INSERT INTO ws_pending_messages (user_id, payload, created_at) VALUES (?, ?, ?)")
+                .bind(user_id)
+                .bind(&json_message)
+                .bind(chrono::Utc::now().timestamp())
+                .execute(&queue.db.pool)
+                .await?;
+            // Oltre il limite si scartano i più vecchi
+            sqlx::query("DELETE FROM ws_pending_messages WHERE user_id = ? AND id NOT IN (SELECT id FROM ws_pending_messages WHERE user_id = ? ORDER BY created_at DESC, id DESC LIMIT ?)")
+                .bind(user_id)
+                .bind(user_id)
+                .bind(queue.max_per_user as i64)
+                .execute(&queue.db.pool)
+                .await?;
+            println!("[WS:QUEUE] User {} offline, message queued", user_id);
+        }
         
         Ok(())
     }
 
-    pub async fn send_to_group(&self, _group_id: &str, message: WebSocketMessage, exclude_user: Option<&str>) -> anyhow::Result<()> {
-        // In una implementazione completa, dovresti avere una mappa group_id -> Vec<user_id>
-        // Per ora inviamo a tutti gli utenti connessi (da migliorare)
+    /// Send `message` to the connected members of `group_id`, except `exclude_user`.
+    /// Members are read from `group_members` on every send, so joins, leaves and kicks
+    /// take effect immediately, without waiting for a reconnect.
+    pub async fn send_to_group(&self, db: &Database, group_id: &str, message: WebSocketMessage, exclude_user: Option<&str>) -> anyhow::Result<()> {
+        let members: Vec<UserId> = sqlx::query_scalar("SELECT user_id FROM group_members WHERE group_id = ?")
+            .bind(group_id)
+            .fetch_all(&db.pool)
+            .await?;
         let connections = self.connections.lock().await;
+        let user_connections = self.user_connections.lock().await;
         let json_message = serde_json::to_string(&message)?;
         
-        for connection in connections.values() {
-            if let Some(exclude) = exclude_user {
-                if connection.user_id == exclude {
-                    continue;
-                }
+        for member in &members {
+            if exclude_user == Some(member.as_str()) {
+                continue;
+            }
+            for connection in connections_of(&connections, &user_connections, member) {
+                let _ = connection.sender.send(Message::Text(json_message.clone()));
             }
-            let _ = connection.sender.send(Message::Text(json_message.clone()));
         }
         
         Ok(())
     }
 
     pub async fn broadcast_message(&self, message: WebSocketMessage) -> anyhow::Result<()> {
+        publish(self.redis_manager.as_ref(), &self.connections, &self.user_connections, &message).await;
         let _ = self.message_broadcaster.send(message);
         Ok(())
     }
@@ -611,27 +1194,69 @@ impl ChatWebSocketManager {
         self.message_broadcaster.subscribe()
     }
 
-    /// Disconnette e rimuove tutte le connessioni WebSocket per un utente specifico
-    pub async fn disconnect_user(&self, user_id: &str) {
+    /// Publish notifications (read receipts, typing indicators); each one reaches only its `recipient_user_id`, or everyone if unset
+    pub async fn publish_notifications(&self, notifications: Vec<WebSocketMessage>) {
+        for notification in &notifications {
+            publish(self.redis_manager.as_ref(), &self.connections, &self.user_connections, notification).await;
+        }
+    }
+
+    /// Replace the users whose online/offline changes `subscriber` is notified of
+    pub async fn subscribe_presence(&self, subscriber: &str, user_ids: HashSet<UserId>) {
+        let mut subscriptions = self.presence_subscriptions.lock().await;
+        if user_ids.is_empty() {
+            subscriptions.remove(subscriber);
+        } else {
+            subscriptions.insert(subscriber.to_string(), user_ids);
+        }
+    }
+
+    /// Drop the presence subscription of `subscriber`, e.g. on logout
+    pub async fn unsubscribe_presence(&self, subscriber: &str) {
+        self.presence_subscriptions.lock().await.remove(subscriber);
+    }
+
+    /// Tell the presence subscribers of `user_id` that its `is_online` flag changed
+    pub async fn notify_presence(&self, db: &Arc<Database>, user_id: &str, online: bool) {
+        let subscribers: Vec<UserId> = self.presence_subscriptions.lock().await
+            .iter()
+            .filter(|(_, watched)| watched.contains(user_id))
+            .map(|(subscriber, _)| subscriber.clone())
+            .collect();
+        let events = crate::server::users::presence_notifications(db, user_id, online, subscribers).await;
+        self.publish_notifications(events).await;
+    }
+
+    /// Disconnette e rimuove tutte le connessioni WebSocket per un utente specifico.
+    /// Errors when the user has no active WebSocket connection.
+    pub async fn disconnect_user(&self, user_id: &str) -> anyhow::Result<()> {
         println!("[WS:CLEANUP] Disconnecting all WebSocket connections for user: {}", user_id);
         
         let mut connections = self.connections.lock().await;
         let mut user_connections = self.user_connections.lock().await;
         
-        // Trova il client_id per questo user_id
-        if let Some(client_id) = user_connections.remove(user_id) {
-            // Chiudi la connessione inviando un messaggio di chiusura
-            if let Some(connection) = connections.remove(&client_id) {
-                // Invia messaggio di chiusura (questo farà terminare il task del WebSocket)
-                let _ = connection.sender.send(tokio_tungstenite::tungstenite::Message::Close(None));
-                println!("[WS:CLEANUP] Sent close message to WebSocket connection for user: {}", user_id);
+        // Trova i client_id per questo user_id
+        if let Some(client_ids) = user_connections.remove(user_id) {
+            // Chiudi ogni connessione inviando un messaggio di chiusura
+            for client_id in client_ids {
+                if let Some(connection) = connections.remove(&client_id) {
+                    // Invia messaggio di chiusura (questo farà terminare il task del WebSocket)
+                    let _ = connection.sender.send(tokio_tungstenite::tungstenite::Message::Close(None));
+                    println!("[WS:CLEANUP] Sent close message to WebSocket connection for user: {}", user_id);
+                }
             }
+            Ok(())
         } else {
             println!("[WS:CLEANUP] No active WebSocket connection found for user: {}", user_id);
+            Err(anyhow::anyhow!("no active WebSocket connection for user {}", user_id))
         }
     }
 
     pub async fn start_redis_subscriber(&self) -> anyhow::Result<()> {
+        if self.redis_manager.is_none() {
+            println!("[WS:REDIS] Redis disabled, pub/sub subscriber not started");
+            return Ok(());
+        }
         let _redis_manager = self.redis_manager.clone();
         let message_broadcaster = self.message_broadcaster.clone();
         let connections = self.connections.clone();
@@ -655,8 +1280,9 @@ impl ChatWebSocketManager {
                                 let _ = pubsub.subscribe("group:*").await;
                                 let _ = pubsub.subscribe("system").await;
                                 let _ = pubsub.subscribe("notifications").await;
+                                let _ = pubsub.psubscribe("notifications:*").await;
                                 
-                                println!("[WS:REDIS] Subscribed to channels: private:*, group:*, system, notifications");
+                                println!("[WS:REDIS] Subscribed to channels: private:*, group:*, system, notifications, notifications:*");
                                 
                                 // Listen for messages
                                 let mut stream = pubsub.on_message();
@@ -672,45 +1298,7 @@ impl ChatWebSocketManager {
                                             println!("[WS:REDIS] Received message on channel '{}': {}", channel, payload);
                                             
                                             if let Ok(ws_message) = serde_json::from_str::<WebSocketMessage>(&payload) {
-                                                // Route message based on type
-                                                match ws_message.message_type {
-                                                    MessageType::PrivateMessage => {
-                                                        // Send to specific user
-                                                        let user_connections_guard = user_connections.lock().await;
-                                                        let connections_guard = connections.lock().await;
-                                                        
-                                                        if let Some(client_id) = user_connections_guard.get(&ws_message.target) {
-                                                            if let Some(connection) = connections_guard.get(client_id) {
-                                                                let json_msg = serde_json::to_string(&ws_message).unwrap_or_default();
-                                                                let _ = connection.sender.send(tokio_tungstenite::tungstenite::Message::Text(json_msg));
-                                                                println!("[WS:REDIS] Delivered private message to user {}", ws_message.target);
-                                                            }
-                                                        }
-                                                    }
-                                                    MessageType::GroupMessage => {
-                                                        // Broadcast to all connected users (would filter by group in production)
-                                                        let connections_guard = connections.lock().await;
-                                                        let json_msg = serde_json::to_string(&ws_message).unwrap_or_default();
-                                                        
-                                                        for connection in connections_guard.values() {
-                                                            if connection.user_id != ws_message.sender {
-                                                                let _ = connection.sender.send(tokio_tungstenite::tungstenite::Message::Text(json_msg.clone()));
-                                                            }
-                                                        }
-                                                        println!("[WS:REDIS] Broadcasted group message from {}", ws_message.sender);
-                                                    }
-                                                    MessageType::Notification | MessageType::System => {
-                                                        // Broadcast to all connected users
-                                                        let connections_guard = connections.lock().await;
-                                                        let json_msg = serde_json::to_string(&ws_message).unwrap_or_default();
-                                                        
-                                                        for connection in connections_guard.values() {
-                                                            let _ = connection.sender.send(tokio_tungstenite::tungstenite::Message::Text(json_msg.clone()));
-                                                        }
-                                                        println!("[WS:REDIS] Broadcasted {} message", if matches!(ws_message.message_type, MessageType::Notification) { "notification" } else { "system" });
-                                                    }
-                                                    _ => {}
-                                                }
+                                                deliver_local(&connections, &user_connections, &ws_message).await;
                                                 
                                                 // Also broadcast locally
                                                 let _ = message_broadcaster.send(ws_message);
@@ -69,9 +69,11 @@ pub async fn start_performance_logger(db: Arc<Database>, log_path: &str) {
             }
         };
 
+        let pool = db.pool_stats();
+
         // Log to console
-        info!("📊 Performance - Active Users: {}, Groups: {}, Messages: {}, CPU: {:.1}%", 
-            active_users, groups, total_messages, cpu_usage);
+        info!("📊 Performance - Active Users: {}, Groups: {}, Messages: {}, CPU: {:.1}%, DB pool: {} open / {} idle / {} in use (max {})", 
+            active_users, groups, total_messages, cpu_usage, pool.size, pool.idle, pool.in_use, pool.max);
 
         // Write to file
         if let Err(e) = writeln!(file, "{}, {}, {}, {}, {:.1}%", timestamp, active_users, groups, total_messages, cpu_usage) {